@@ -120,19 +120,38 @@ impl<'a, D: MerkleDB> PersistentMerkleTree<'a, D> {
     }
 
     pub fn add_commitment_hash(&mut self, hash: BLSScalar) -> Result<u64> {
-        let mut cache = Cache::new();
-        // 1. generate keys of ancestors for update in tree
-        let path = get_path_from_uid(self.entry_count);
-        let keys = generate_path_keys(path);
+        let uids = self.add_commitment_hashes(&[hash]).c(d!())?;
+        Ok(uids[0])
+    }
 
-        let (leaf, ancestors) = keys.as_slice().split_last().unwrap();
+    // Adds the commitment hashes `hashes` as new leaves and returns their uids.
+    // The ancestors of the new leaves are updated level by level, each of them once, and the
+    // hashes of a level are computed at once with RescueInstance::rescue_hash_chunks, on multiple
+    // threads with the `parallel` feature of crypto. It is much faster than adding the leaves
+    // one by one when building or rebuilding a tree.
+    pub fn add_commitment_hashes(&mut self, hashes: &[BLSScalar]) -> Result<Vec<u64>> {
+        let mut cache = Cache::new();
+        let first_uid = self.entry_count;
+
+        // 1. save leaf nodes
+        let mut level_keys: Vec<String> = vec![];
+        for (uid, hash) in (first_uid..).zip(hashes.iter()) {
+            let keys = generate_path_keys(get_path_from_uid(uid));
+            let leaf = keys.last().unwrap();
+            cache.set(leaf, hash.zei_to_bytes());
+            level_keys.push(leaf.clone());
+        }
 
-        // 2. Hash ABAR and save leaf node
-        let uid = self.entry_count;
-        cache.set(leaf, hash.zei_to_bytes());
+        // 2. update hash of all ancestors of the new leaves, from the bottom level
+        let hasher = RescueInstance::new();
+        for _ in 0..TREE_DEPTH {
+            // leaves are consecutive, so are their ancestors on each level
+            let mut parent_keys: Vec<String> = level_keys
+                .iter()
+                .map(|key| key[..key.len() - 1].to_owned())
+                .collect();
+            parent_keys.dedup();
 
-        // 3. update hash of all ancestors of the new leaf
-        for node_key in ancestors.iter().rev() {
             let parse_hash = |key: &str| -> Result<BLSScalar> {
                 if let Some(b) = cache.get(key) {
                     return BLSScalar::zei_from_bytes(b.as_slice());
@@ -142,27 +161,27 @@ impl<'a, D: MerkleDB> PersistentMerkleTree<'a, D> {
                     None => Ok(BLSScalar::zero()),
                 }
             };
-            let left_child_hash = parse_hash(format!("{}{}", node_key, "l").as_str())?;
-            let middle_child_hash = parse_hash(format!("{}{}", node_key, "m").as_str())?;
-            let right_child_hash = parse_hash(format!("{}{}", node_key, "r").as_str())?;
-
-            let hasher = RescueInstance::new();
-            let hash = hasher.rescue_hash(&[
-                left_child_hash,
-                middle_child_hash,
-                right_child_hash,
-                BLSScalar::zero(),
-            ])[0];
-            cache.set(node_key, BLSScalar::zei_to_bytes(&hash));
+            let mut children_hashes = Vec::with_capacity(3 * parent_keys.len());
+            for node_key in parent_keys.iter() {
+                for child in ["l", "m", "r"] {
+                    children_hashes
+                        .push(parse_hash(format!("{}{}", node_key, child).as_str())?);
+                }
+            }
+            let parent_hashes = hasher.rescue_hash_chunks(&children_hashes);
+            for (node_key, hash) in parent_keys.iter().zip(parent_hashes.iter()) {
+                cache.set(node_key, BLSScalar::zei_to_bytes(hash));
+            }
+            level_keys = parent_keys;
         }
 
-        self.entry_count += 1;
+        self.entry_count += hashes.len() as u64;
         cache.set(ENTRY_COUNT_KEY, self.entry_count.to_be_bytes().to_vec());
 
         for (k, v) in cache.iter() {
             self.store.set(k.as_bytes(), v.to_vec())?;
         }
-        Ok(uid)
+        Ok((first_uid..self.entry_count).collect())
     }
 
     pub fn generate_proof(&self, id: u64) -> Result<Proof> {
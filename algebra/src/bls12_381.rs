@@ -52,6 +52,8 @@ impl FromStr for BLSScalar {
     }
 }
 
+// The conversion is exact: the order of the Jubjub scalar field is smaller than the
+// order of the BLS12-381 scalar field
 impl From<&JubjubScalar> for BLSScalar {
     fn from(scalar: &JubjubScalar) -> Self {
         let bytes = scalar.to_bytes();
@@ -69,6 +71,23 @@ impl BLSScalar {
     pub fn get_scalar(&self) -> Fr {
         self.0
     }
+
+    /// Converts the scalar into a Jubjub scalar with the same integer representative.
+    /// Returns an error if the scalar is not smaller than the order of the Jubjub scalar field.
+    pub fn to_jubjub_scalar(&self) -> Result<JubjubScalar> {
+        let bytes = self.to_bytes();
+        let scalar = JubjubScalar::from_bytes(&bytes).c(d!())?;
+        if scalar.to_bytes() != bytes {
+            return Err(eg!(AlgebraError::ParameterError));
+        }
+        Ok(scalar)
+    }
+
+    /// Converts the scalar into a Jubjub scalar, reducing its integer representative modulo
+    /// the order of the Jubjub scalar field
+    pub fn to_jubjub_scalar_mod_order(&self) -> JubjubScalar {
+        JubjubScalar::from_bytes(&self.to_bytes()).unwrap() // safe unwrap
+    }
 }
 
 impl One for BLSScalar {
@@ -160,6 +179,21 @@ impl ZeiScalar for BLSScalar {
         Self(Fr::from(value))
     }
 
+    #[inline]
+    fn from_u128(value: u128) -> Self {
+        Self(Fr::from(value))
+    }
+
+    #[inline]
+    fn double(&self) -> Self {
+        Self(self.0.double())
+    }
+
+    #[inline]
+    fn square(&self) -> Self {
+        Self(self.0.square())
+    }
+
     #[inline]
     fn from_hash<D>(hash: D) -> Self
     where
@@ -283,6 +317,20 @@ impl Group for BLSG1 {
     }
 }
 
+impl BLSG1 {
+    /// Affine coordinates (x, y) as little-endian bytes, None for the point at infinity
+    pub fn to_affine_le_bytes(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        let affine = self.0.into_affine();
+        if affine.infinity {
+            return None;
+        }
+        Some((
+            affine.x.into_repr().to_bytes_le(),
+            affine.y.into_repr().to_bytes_le(),
+        ))
+    }
+}
+
 impl GroupArithmetic for BLSG1 {
     type S = BLSScalar;
 
@@ -356,6 +404,27 @@ impl Group for BLSG2 {
     }
 }
 
+impl BLSG2 {
+    /// Affine coordinates ([x.c0, x.c1], [y.c0, y.c1]) as little-endian bytes, None for the
+    /// point at infinity
+    pub fn to_affine_le_bytes(&self) -> Option<([Vec<u8>; 2], [Vec<u8>; 2])> {
+        let affine = self.0.into_affine();
+        if affine.infinity {
+            return None;
+        }
+        Some((
+            [
+                affine.x.c0.into_repr().to_bytes_le(),
+                affine.x.c1.into_repr().to_bytes_le(),
+            ],
+            [
+                affine.y.c0.into_repr().to_bytes_le(),
+                affine.y.c1.into_repr().to_bytes_le(),
+            ],
+        ))
+    }
+}
+
 impl GroupArithmetic for BLSG2 {
     type S = BLSScalar;
 
@@ -392,6 +461,17 @@ impl Pairing for Bls12381 {
     fn pairing(a: &Self::G1, b: &Self::G2) -> Self::Gt {
         BLSGt(Bls12381pairing::pairing(a.0, b.0))
     }
+
+    fn product_of_pairings(pairs: &[(Self::G1, Self::G2)]) -> Self::Gt {
+        let prepared_pairs: Vec<(
+            <Bls12381pairing as PairingEngine>::G1Prepared,
+            <Bls12381pairing as PairingEngine>::G2Prepared,
+        )> = pairs
+            .iter()
+            .map(|(a, b)| (G1Affine::from(a.0).into(), G2Affine::from(b.0).into()))
+            .collect();
+        BLSGt(Bls12381pairing::product_of_pairings(&prepared_pairs))
+    }
 }
 
 impl GroupArithmetic for BLSGt {
@@ -499,9 +579,14 @@ mod bls12_381_groups_test {
     use crate::{
         bls12_381::{BLSGt, BLSScalar, Bls12381, BLSG1, BLSG2},
         groups::{
-            group_tests::{test_scalar_operations, test_scalar_serialization},
-            Group, GroupArithmetic, Pairing, Scalar,
+            batch_from_compressed_bytes,
+            group_tests::{
+                test_scalar_operations, test_scalar_serialization, test_to_wnaf,
+                test_wnaf_mul,
+            },
+            Group, GroupArithmetic, One, Pairing, Scalar, ScalarArithmetic, Zero,
         },
+        jubjub::JubjubScalar,
     };
     use ark_bls12_381::{G1Affine, G2Affine};
     use ark_ec::ProjectiveCurve;
@@ -521,6 +606,39 @@ mod bls12_381_groups_test {
         test_scalar_serialization::<super::BLSScalar>();
     }
 
+    #[test]
+    fn jubjub_scalar_conversion() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let jubjub_scalar = JubjubScalar::random(&mut rng);
+        let bls_scalar = BLSScalar::from(&jubjub_scalar);
+        assert_eq!(bls_scalar.to_bytes(), jubjub_scalar.to_bytes());
+        assert_eq!(bls_scalar.to_jubjub_scalar().unwrap(), jubjub_scalar);
+        assert_eq!(bls_scalar.to_jubjub_scalar_mod_order(), jubjub_scalar);
+
+        // the largest Jubjub scalar converts, the order of the Jubjub scalar field does not
+        let max_jubjub_scalar = JubjubScalar::from_u32(1).neg();
+        let max_bls_scalar = BLSScalar::from(&max_jubjub_scalar);
+        assert_eq!(
+            max_bls_scalar.to_jubjub_scalar().unwrap(),
+            max_jubjub_scalar
+        );
+        let jubjub_order = max_bls_scalar.add(&BLSScalar::one());
+        assert!(jubjub_order.to_jubjub_scalar().is_err());
+        assert_eq!(
+            jubjub_order.to_jubjub_scalar_mod_order(),
+            JubjubScalar::zero()
+        );
+        let minus_one = BLSScalar::one().neg();
+        assert!(minus_one.to_jubjub_scalar().is_err());
+    }
+
+    #[test]
+    fn scalar_to_wnaf() {
+        test_to_wnaf::<BLSScalar>();
+        test_wnaf_mul::<BLSG1>();
+        test_wnaf_mul::<BLSG2>();
+    }
+
     #[test]
     fn scalar_from_to_bytes() {
         let small_value = BLSScalar::from_u32(165747);
@@ -580,6 +698,24 @@ mod bls12_381_groups_test {
         assert_eq!(gt_mapped_element, gt_base_computed.mul(&s2).mul(&s1));
     }
 
+    #[test]
+    fn product_of_pairings() {
+        let mut rng = ChaCha20Rng::from_entropy();
+        let pairs: Vec<(BLSG1, BLSG2)> = (0..3)
+            .map(|_| {
+                (
+                    BLSG1::get_base().mul(&BLSScalar::random(&mut rng)),
+                    BLSG2::get_base().mul(&BLSScalar::random(&mut rng)),
+                )
+            })
+            .collect();
+        let expected = pairs.iter().fold(BLSGt::get_identity(), |acc, (a, b)| {
+            acc.add(&Bls12381::pairing(a, b))
+        });
+        assert_eq!(Bls12381::product_of_pairings(&pairs), expected);
+        assert_eq!(Bls12381::product_of_pairings(&[]), BLSGt::get_identity());
+    }
+
     #[test]
     fn curve_points_respresentation_of_g1() {
         let mut rng = ChaCha20Rng::from_entropy();
@@ -659,4 +795,24 @@ mod bls12_381_groups_test {
         let gt_recovered = BLSGt::from_compressed_bytes(&gt_bytes).unwrap();
         assert_eq!(gt, gt_recovered);
     }
+
+    #[test]
+    fn batch_point_decompression() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let points: Vec<BLSG2> =
+            (0..10).map(|_| BLSG2::get_random_base(&mut rng)).collect();
+        let mut bytes: Vec<Vec<u8>> =
+            points.iter().map(|p| p.to_compressed_bytes()).collect();
+        let recovered = batch_from_compressed_bytes::<BLSG2, _>(&bytes).unwrap();
+        assert_eq!(recovered, points);
+        assert!(batch_from_compressed_bytes::<BLSG2, Vec<u8>>(&[])
+            .unwrap()
+            .is_empty());
+
+        // a single invalid encoding makes the batch fail
+        bytes[7][1] ^= 1;
+        assert!(batch_from_compressed_bytes::<BLSG2, _>(&bytes).is_err());
+        bytes[7] = vec![0u8; 10];
+        assert!(batch_from_compressed_bytes::<BLSG2, _>(&bytes).is_err());
+    }
 }
@@ -0,0 +1,806 @@
+// The BN254 curve, also known as alt_bn128, whose pairing is available on Ethereum through
+// the precompiles of EIP-196 and EIP-197. Its scalar field has a multiplicative subgroup of
+// order 2^28, hence it supports the FFTs of PLONK, and it is much cheaper to verify proofs
+// over BN254 on EVM chains than over BLS12-381, at the cost of a lower security level.
+
+use crate::{
+    errors::AlgebraError,
+    groups::{
+        Group, GroupArithmetic, One, Pairing, Scalar as ZeiScalar, ScalarArithmetic,
+        Zero,
+    },
+};
+use ark_bn254::{
+    fr::FrParameters, Bn254 as Bn254pairing, Fq, Fq12Parameters, Fq2, Fr, G1Affine,
+    G1Projective, G2Affine, G2Projective,
+};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{
+    BigInteger, BigInteger256, FftField, FftParameters, Field, Fp12, PrimeField,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    rand::{CryptoRng, RngCore},
+    result::Result as StdResult,
+    str::FromStr,
+    One as ArkOne, UniformRand, Zero as ArkZero,
+};
+use digest::{generic_array::typenum::U64, Digest};
+use rand_chacha::ChaCha20Rng;
+use ruc::*;
+use utils::{derive_prng_from_hash, u8_le_slice_to_u64};
+use wasm_bindgen::prelude::*;
+
+pub const BN254_SCALAR_LEN: usize = 32;
+
+// Element of the base field from its canonical little-endian encoding
+fn fq_from_le_bytes(bytes: &[u8]) -> Result<Fq> {
+    if bytes.len() != 32 {
+        return Err(eg!(AlgebraError::DeserializationError));
+    }
+    let mut limbs = [0u64; 4];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        *limb = u8_le_slice_to_u64(&bytes[8 * i..8 * (i + 1)]);
+    }
+    Fq::from_repr(BigInteger256::new(limbs))
+        .ok_or(eg!(AlgebraError::DeserializationError))
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct BN254Scalar(Fr);
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BN254G1(pub(crate) G1Projective);
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BN254G2(pub(crate) G2Projective);
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BN254Gt(pub(crate) Fp12<Fq12Parameters>);
+
+impl FromStr for BN254Scalar {
+    type Err = AlgebraError;
+
+    fn from_str(string: &str) -> StdResult<Self, AlgebraError> {
+        let res = Fr::from_str(string);
+
+        if res.is_ok() {
+            Ok(Self(res.unwrap()))
+        } else {
+            Err(AlgebraError::DeserializationError)
+        }
+    }
+}
+
+impl BN254Scalar {
+    #[inline]
+    pub fn new(elem: Fr) -> Self {
+        Self(elem)
+    }
+
+    #[inline]
+    pub fn get_scalar(&self) -> Fr {
+        self.0
+    }
+}
+
+impl One for BN254Scalar {
+    #[inline]
+    fn one() -> Self {
+        BN254Scalar(Fr::one())
+    }
+}
+
+impl Zero for BN254Scalar {
+    #[inline]
+    fn zero() -> Self {
+        Self(Fr::zero())
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+impl ScalarArithmetic for BN254Scalar {
+    #[inline]
+    fn add(&self, b: &Self) -> Self {
+        Self(self.0.add(&b.0))
+    }
+
+    #[inline]
+    fn add_assign(&mut self, b: &Self) {
+        (self.0).add_assign(&b.0);
+    }
+
+    #[inline]
+    fn mul(&self, b: &Self) -> Self {
+        Self(self.0.mul(&b.0))
+    }
+
+    #[inline]
+    fn mul_assign(&mut self, b: &Self) {
+        (self.0).mul_assign(&b.0);
+    }
+
+    #[inline]
+    fn sub(&self, b: &Self) -> Self {
+        Self(self.0.sub(&b.0))
+    }
+
+    #[inline]
+    fn sub_assign(&mut self, b: &Self) {
+        (self.0).sub_assign(&b.0);
+    }
+
+    #[inline]
+    fn inv(&self) -> Result<Self> {
+        let a = self.0.inverse();
+        if bool::from(a.is_none()) {
+            return Err(eg!(AlgebraError::GroupInversionError));
+        }
+        Ok(Self(a.unwrap()))
+    }
+
+    #[inline]
+    fn neg(&self) -> Self {
+        Self(self.0.neg())
+    }
+
+    #[inline]
+    fn pow(&self, exponent: &[u64]) -> Self {
+        let len = exponent.len();
+        let mut array = [0u64; 4];
+        array[..len].copy_from_slice(exponent);
+        Self(self.0.pow(&array))
+    }
+}
+
+impl ZeiScalar for BN254Scalar {
+    #[inline]
+    fn random<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
+        Self(Fr::rand(rng))
+    }
+
+    #[inline]
+    fn from_u32(value: u32) -> Self {
+        Self::from_u64(value as u64)
+    }
+
+    #[inline]
+    fn from_u64(value: u64) -> Self {
+        Self(Fr::from(value))
+    }
+
+    #[inline]
+    fn from_u128(value: u128) -> Self {
+        Self(Fr::from(value))
+    }
+
+    #[inline]
+    fn double(&self) -> Self {
+        Self(self.0.double())
+    }
+
+    #[inline]
+    fn square(&self) -> Self {
+        Self(self.0.square())
+    }
+
+    #[inline]
+    fn from_hash<D>(hash: D) -> Self
+    where
+        D: Digest<OutputSize = U64> + Default,
+    {
+        let mut prng = derive_prng_from_hash::<D, ChaCha20Rng>(hash);
+        Self::random(&mut prng)
+    }
+
+    #[inline]
+    fn multiplicative_generator() -> Self {
+        Self(Fr::multiplicative_generator())
+    }
+
+    #[inline]
+    fn get_field_size_lsf_bytes() -> Vec<u8> {
+        [
+            0x01, 0x00, 0x00, 0xf0, 0x93, 0xf5, 0xe1, 0x43, 0x91, 0x70, 0xb9, 0x79,
+            0x48, 0xe8, 0x33, 0x28, 0x5d, 0x58, 0x81, 0x81, 0xb6, 0x45, 0x50, 0xb8,
+            0x29, 0xa0, 0x31, 0xe1, 0x72, 0x4e, 0x64, 0x30,
+        ]
+        .to_vec()
+    }
+
+    #[inline]
+    fn get_little_endian_u64(&self) -> Vec<u64> {
+        let a = self.0.into_repr().to_bytes_le();
+        let a1 = u8_le_slice_to_u64(&a[0..8]);
+        let a2 = u8_le_slice_to_u64(&a[8..16]);
+        let a3 = u8_le_slice_to_u64(&a[16..24]);
+        let a4 = u8_le_slice_to_u64(&a[24..]);
+        vec![a1, a2, a3, a4]
+    }
+
+    #[inline]
+    fn bytes_len() -> usize {
+        BN254_SCALAR_LEN
+    }
+
+    #[inline]
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.into_repr().to_bytes_le()
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() > Self::bytes_len() {
+            return Err(eg!(AlgebraError::DeserializationError));
+        }
+        let mut array = vec![0u8; Self::bytes_len()];
+        array[0..bytes.len()].copy_from_slice(bytes);
+        Self::from_le_bytes(&array).c(d!())
+    }
+
+    #[inline]
+    fn from_le_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self(Fr::from_le_bytes_mod_order(bytes)))
+    }
+}
+
+impl Group for BN254G1 {
+    const COMPRESSED_LEN: usize = 32;
+
+    #[inline]
+    fn get_identity() -> Self {
+        Self(G1Projective::zero())
+    }
+
+    #[inline]
+    fn get_base() -> Self {
+        Self(G1Projective::prime_subgroup_generator())
+    }
+
+    #[inline]
+    fn get_random_base<R: CryptoRng + RngCore>(prng: &mut R) -> Self {
+        Self::get_base().mul(&BN254Scalar::random(prng))
+    }
+
+    #[inline]
+    fn to_compressed_bytes(&self) -> Vec<u8> {
+        let affine = G1Affine::from(self.0);
+        let mut buf = Vec::new();
+        affine.serialize(&mut buf).unwrap();
+
+        buf
+    }
+
+    #[inline]
+    fn from_compressed_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ark_std::io::BufReader::new(bytes);
+
+        let affine = G1Affine::deserialize(&mut reader);
+
+        if affine.is_ok() {
+            Ok(Self(G1Projective::from(affine.unwrap()))) // safe unwrap
+        } else {
+            Err(eg!(AlgebraError::DeserializationError))
+        }
+    }
+
+    #[inline]
+    fn from_hash<D>(hash: D) -> Self
+    where
+        D: Digest<OutputSize = U64> + Default,
+    {
+        let mut prng = derive_prng_from_hash::<D, ChaCha20Rng>(hash);
+        Self(G1Projective::rand(&mut prng))
+    }
+
+    #[inline]
+    fn vartime_multi_exp(scalars: &[&Self::S], points: &[&Self]) -> Self {
+        let scalars_raw = scalars
+            .iter()
+            .map(|r| r.0.into_repr())
+            .collect::<Vec<<FrParameters as FftParameters>::BigInt>>();
+        let points_raw = G1Projective::batch_normalization_into_affine(
+            &points.iter().map(|r| r.0).collect::<Vec<G1Projective>>(),
+        );
+
+        Self(ark_ec::msm::VariableBase::msm(&points_raw, &scalars_raw))
+    }
+}
+
+impl BN254G1 {
+    /// Point of affine coordinates (x, y), given as little-endian bytes. Returns an error if
+    /// a coordinate is not lower than the order of the base field or if the point is not on
+    /// the curve.
+    pub fn from_affine_le_bytes(x: &[u8], y: &[u8]) -> Result<Self> {
+        let point = G1Affine::new(
+            fq_from_le_bytes(x).c(d!())?,
+            fq_from_le_bytes(y).c(d!())?,
+            false,
+        );
+        if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(eg!(AlgebraError::DeserializationError));
+        }
+        Ok(Self(point.into_projective()))
+    }
+
+    /// Affine coordinates (x, y) as little-endian bytes, None for the point at infinity
+    pub fn to_affine_le_bytes(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        let affine = self.0.into_affine();
+        if affine.infinity {
+            return None;
+        }
+        Some((
+            affine.x.into_repr().to_bytes_le(),
+            affine.y.into_repr().to_bytes_le(),
+        ))
+    }
+}
+
+impl GroupArithmetic for BN254G1 {
+    type S = BN254Scalar;
+
+    #[inline]
+    fn add(&self, other: &Self) -> Self {
+        Self(self.0.add(&other.0))
+    }
+
+    #[inline]
+    fn double(&self) -> Self {
+        Self(self.0.double())
+    }
+
+    #[inline]
+    fn mul(&self, other: &BN254Scalar) -> Self {
+        Self(self.0.mul(&other.0.into_repr()))
+    }
+
+    #[inline]
+    fn sub(&self, other: &Self) -> Self {
+        Self(self.0.sub(&other.0))
+    }
+}
+
+impl Group for BN254G2 {
+    const COMPRESSED_LEN: usize = 64;
+
+    #[inline]
+    fn get_identity() -> Self {
+        Self(G2Projective::zero())
+    }
+
+    #[inline]
+    fn get_base() -> Self {
+        Self(G2Projective::prime_subgroup_generator())
+    }
+
+    #[inline]
+    fn get_random_base<R: CryptoRng + RngCore>(prng: &mut R) -> Self {
+        Self::get_base().mul(&BN254Scalar::random(prng))
+    }
+
+    #[inline]
+    fn to_compressed_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.0.serialize(&mut buf).unwrap();
+
+        buf
+    }
+
+    #[inline]
+    fn from_compressed_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ark_std::io::BufReader::new(bytes);
+
+        let affine = G2Affine::deserialize(&mut reader);
+
+        if affine.is_ok() {
+            Ok(Self(affine.unwrap().into_projective()))
+        } else {
+            Err(eg!(AlgebraError::DeserializationError))
+        }
+    }
+
+    #[inline]
+    fn from_hash<D>(hash: D) -> Self
+    where
+        D: Digest<OutputSize = U64> + Default,
+    {
+        let mut prng = derive_prng_from_hash::<D, ChaCha20Rng>(hash);
+        Self(G2Projective::rand(&mut prng))
+    }
+}
+
+impl BN254G2 {
+    /// Point of affine coordinates ([x.c0, x.c1], [y.c0, y.c1]), given as little-endian bytes.
+    /// Returns an error if a coordinate is not lower than the order of the base field or if
+    /// the point is not in the prime order subgroup.
+    pub fn from_affine_le_bytes(x: [&[u8]; 2], y: [&[u8]; 2]) -> Result<Self> {
+        let x = Fq2::new(
+            fq_from_le_bytes(x[0]).c(d!())?,
+            fq_from_le_bytes(x[1]).c(d!())?,
+        );
+        let y = Fq2::new(
+            fq_from_le_bytes(y[0]).c(d!())?,
+            fq_from_le_bytes(y[1]).c(d!())?,
+        );
+        let point = G2Affine::new(x, y, false);
+        if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(eg!(AlgebraError::DeserializationError));
+        }
+        Ok(Self(point.into_projective()))
+    }
+
+    /// Affine coordinates ([x.c0, x.c1], [y.c0, y.c1]) as little-endian bytes, None for the
+    /// point at infinity
+    pub fn to_affine_le_bytes(&self) -> Option<([Vec<u8>; 2], [Vec<u8>; 2])> {
+        let affine = self.0.into_affine();
+        if affine.infinity {
+            return None;
+        }
+        Some((
+            [
+                affine.x.c0.into_repr().to_bytes_le(),
+                affine.x.c1.into_repr().to_bytes_le(),
+            ],
+            [
+                affine.y.c0.into_repr().to_bytes_le(),
+                affine.y.c1.into_repr().to_bytes_le(),
+            ],
+        ))
+    }
+}
+
+impl GroupArithmetic for BN254G2 {
+    type S = BN254Scalar;
+
+    #[inline]
+    fn add(&self, other: &Self) -> Self {
+        Self(self.0.add(&other.0))
+    }
+
+    #[inline]
+    fn mul(&self, other: &BN254Scalar) -> Self {
+        Self(self.0.mul(&other.0.into_repr()))
+    }
+
+    #[inline]
+    fn sub(&self, other: &Self) -> Self {
+        Self(self.0.sub(&other.0))
+    }
+
+    #[inline]
+    fn double(&self) -> Self {
+        Self(self.0.double())
+    }
+}
+
+pub struct Bn254;
+
+impl Pairing for Bn254 {
+    type ScalarField = BN254Scalar;
+    type G1 = BN254G1;
+    type G2 = BN254G2;
+    type Gt = BN254Gt;
+
+    #[inline]
+    fn pairing(a: &Self::G1, b: &Self::G2) -> Self::Gt {
+        BN254Gt(Bn254pairing::pairing(a.0, b.0))
+    }
+
+    fn product_of_pairings(pairs: &[(Self::G1, Self::G2)]) -> Self::Gt {
+        let prepared_pairs: Vec<(
+            <Bn254pairing as PairingEngine>::G1Prepared,
+            <Bn254pairing as PairingEngine>::G2Prepared,
+        )> = pairs
+            .iter()
+            .map(|(a, b)| (G1Affine::from(a.0).into(), G2Affine::from(b.0).into()))
+            .collect();
+        BN254Gt(Bn254pairing::product_of_pairings(&prepared_pairs))
+    }
+}
+
+impl GroupArithmetic for BN254Gt {
+    type S = BN254Scalar;
+
+    #[inline]
+    fn add(&self, other: &Self) -> Self {
+        let r = self.0.mul(other.0);
+        Self(r)
+    }
+
+    #[inline]
+    fn mul(&self, scalar: &BN254Scalar) -> Self {
+        let mut acc = Self::get_identity();
+
+        // This is a simple double-and-add implementation of group element
+        // multiplication, moving from most significant to least
+        // significant bit of the scalar.
+        //
+        // We skip the leading bit because it's always unset for Fq
+        // elements.
+        for bit in scalar
+            .0
+            .into_repr()
+            .to_bytes_le()
+            .iter()
+            .rev()
+            .flat_map(|byte| (0..8).rev().map(move |i| ((byte >> i) & 1u8) == 1u8))
+            .skip(1)
+        {
+            acc = acc.double();
+            if bit {
+                acc = acc.add(self)
+            }
+        }
+
+        acc
+    }
+
+    #[inline]
+    fn double(&self) -> Self {
+        Self(self.0.mul(&self.0))
+    }
+
+    #[inline]
+    fn sub(&self, other: &Self) -> Self {
+        let mut other_inverse = other.0.clone();
+        other_inverse.conjugate();
+
+        Self(self.0.mul(&other_inverse))
+    }
+}
+
+impl Group for BN254Gt {
+    const COMPRESSED_LEN: usize = 384;
+
+    #[inline]
+    fn get_identity() -> Self {
+        Self(Fp12::<Fq12Parameters>::one())
+    }
+
+    #[inline]
+    fn get_base() -> Self {
+        Bn254::pairing(&BN254G1::get_base(), &BN254G2::get_base())
+    }
+
+    #[inline]
+    fn get_random_base<R: CryptoRng + RngCore>(prng: &mut R) -> Self {
+        Self::get_base().mul(&BN254Scalar::random(prng))
+    }
+
+    #[inline]
+    fn to_compressed_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.0.serialize(&mut buf).unwrap();
+
+        buf
+    }
+
+    #[inline]
+    fn from_compressed_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ark_std::io::BufReader::new(bytes);
+
+        let res = Fp12::<Fq12Parameters>::deserialize(&mut reader);
+
+        if res.is_ok() {
+            Ok(Self(res.unwrap()))
+        } else {
+            Err(eg!(AlgebraError::DeserializationError))
+        }
+    }
+
+    #[inline]
+    fn from_hash<D>(hash: D) -> Self
+    where
+        D: Digest<OutputSize = U64> + Default,
+    {
+        let mut prng = derive_prng_from_hash::<D, ChaCha20Rng>(hash);
+        Self(Fp12::<Fq12Parameters>::rand(&mut prng))
+    }
+}
+
+#[cfg(test)]
+mod bn254_groups_test {
+    use crate::{
+        bn254::{BN254Gt, BN254Scalar, Bn254, BN254G1, BN254G2},
+        groups::{
+            batch_from_compressed_bytes,
+            group_tests::{
+                test_scalar_operations, test_scalar_serialization, test_to_wnaf,
+                test_wnaf_mul,
+            },
+            Group, GroupArithmetic, One, Pairing, Scalar, ScalarArithmetic, Zero,
+        },
+    };
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_scalar_ops() {
+        test_scalar_operations::<super::BN254Scalar>();
+    }
+
+    #[test]
+    fn scalar_deser() {
+        test_scalar_serialization::<super::BN254Scalar>();
+    }
+
+    #[test]
+    fn scalar_to_wnaf() {
+        test_to_wnaf::<BN254Scalar>();
+        test_wnaf_mul::<BN254G1>();
+        test_wnaf_mul::<BN254G2>();
+    }
+
+    #[test]
+    fn scalar_field_size() {
+        // the order of the field is zero in the field
+        let order = BN254Scalar::from_le_bytes(&BN254Scalar::get_field_size_lsf_bytes());
+        assert!(order.unwrap().is_zero());
+        let minus_one = BN254Scalar::one().neg();
+        let mut expected_bytes = BN254Scalar::get_field_size_lsf_bytes();
+        expected_bytes[0] -= 1;
+        assert_eq!(minus_one.to_bytes(), expected_bytes);
+        // 2^28 divides the order of the multiplicative group
+        assert_eq!(expected_bytes[..3], [0u8; 3]);
+        assert_eq!(expected_bytes[3] & 0x0f, 0);
+    }
+
+    #[test]
+    fn scalar_from_to_bytes() {
+        let small_value = BN254Scalar::from_u32(165747);
+        let small_value_bytes = small_value.to_bytes();
+        let expected_small_value_bytes: [u8; 32] = [
+            115, 135, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        assert_eq!(small_value_bytes, expected_small_value_bytes);
+
+        let small_value_from_bytes =
+            BN254Scalar::from_bytes(&small_value_bytes).unwrap();
+        assert_eq!(small_value_from_bytes, small_value);
+    }
+
+    #[test]
+    fn hard_coded_group_elements() {
+        let base_gt = BN254Gt::get_base();
+        let expected_base = Bn254::pairing(&BN254G1::get_base(), &BN254G2::get_base());
+        assert_eq!(base_gt, expected_base);
+
+        // the generator of G1 is (1, 2), as for the precompiles of EIP-196
+        let (x, y) = BN254G1::get_base().to_affine_le_bytes().unwrap();
+        let mut one = vec![0u8; 32];
+        one[0] = 1;
+        let mut two = vec![0u8; 32];
+        two[0] = 2;
+        assert_eq!(x, one);
+        assert_eq!(y, two);
+        assert!(BN254G1::get_identity().to_affine_le_bytes().is_none());
+    }
+
+    #[test]
+    fn affine_coordinates() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let g1 = BN254G1::get_random_base(&mut rng);
+        let (x, y) = g1.to_affine_le_bytes().unwrap();
+        assert_eq!(BN254G1::from_affine_le_bytes(&x, &y).unwrap(), g1);
+        // -P has the same x coordinate, flipping a bit of y leaves the curve
+        let minus_g1 = BN254G1::get_identity().sub(&g1);
+        let (_, minus_y) = minus_g1.to_affine_le_bytes().unwrap();
+        assert_eq!(
+            BN254G1::from_affine_le_bytes(&x, &minus_y).unwrap(),
+            minus_g1
+        );
+        let mut bad_y = y.clone();
+        bad_y[0] ^= 1;
+        assert!(BN254G1::from_affine_le_bytes(&x, &bad_y).is_err());
+        assert!(BN254G1::from_affine_le_bytes(&x, &y[..31]).is_err());
+        // a coordinate not lower than the order of the base field
+        assert!(BN254G1::from_affine_le_bytes(&[0xff; 32], &y).is_err());
+
+        let g2 = BN254G2::get_random_base(&mut rng);
+        let (x, y) = g2.to_affine_le_bytes().unwrap();
+        assert_eq!(
+            BN254G2::from_affine_le_bytes([&x[0], &x[1]], [&y[0], &y[1]]).unwrap(),
+            g2
+        );
+        assert!(BN254G2::from_affine_le_bytes([&x[1], &x[0]], [&y[0], &y[1]]).is_err());
+    }
+
+    #[test]
+    fn bilinear_properties() {
+        let identity_g1 = BN254G1::get_identity();
+        let identity_g2 = BN254G2::get_identity();
+        let identity_gt_computed = Bn254::pairing(&identity_g1, &identity_g2);
+        let identity_gt = BN254Gt::get_identity();
+        assert_eq!(identity_gt, identity_gt_computed);
+
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+
+        let s1 = BN254Scalar::random(&mut rng);
+        let s2 = BN254Scalar::random(&mut rng);
+
+        let base_g1 = BN254G1::get_base();
+        let base_g2 = BN254G2::get_base();
+
+        let s1_base_g1 = base_g1.mul(&s1);
+        let s2_base_g2 = base_g2.mul(&s2);
+
+        let gt_mapped_element = Bn254::pairing(&s1_base_g1, &s2_base_g2);
+
+        let gt_base_computed = Bn254::pairing(&base_g1, &base_g2);
+        let base_gt = BN254Gt::get_base();
+        assert_eq!(base_gt, gt_base_computed);
+
+        assert_eq!(
+            gt_mapped_element,
+            Bn254::pairing(&base_g1, &s2_base_g2).mul(&s1)
+        );
+        assert_eq!(
+            gt_mapped_element,
+            Bn254::pairing(&s1_base_g1, &base_g2).mul(&s2)
+        );
+
+        assert_eq!(gt_mapped_element, gt_base_computed.mul(&s1).mul(&s2));
+        assert_eq!(gt_mapped_element, gt_base_computed.mul(&s2).mul(&s1));
+    }
+
+    #[test]
+    fn product_of_pairings() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let pairs: Vec<(BN254G1, BN254G2)> = (0..3)
+            .map(|_| {
+                (
+                    BN254G1::get_base().mul(&BN254Scalar::random(&mut rng)),
+                    BN254G2::get_base().mul(&BN254Scalar::random(&mut rng)),
+                )
+            })
+            .collect();
+        let expected = pairs.iter().fold(BN254Gt::get_identity(), |acc, (a, b)| {
+            acc.add(&Bn254::pairing(a, b))
+        });
+        assert_eq!(Bn254::product_of_pairings(&pairs), expected);
+        assert_eq!(Bn254::product_of_pairings(&[]), BN254Gt::get_identity());
+    }
+
+    #[test]
+    fn test_serialization_of_points() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+
+        let g1 = BN254G1::get_random_base(&mut rng);
+        let g1_bytes = g1.to_compressed_bytes();
+        assert_eq!(g1_bytes.len(), BN254G1::COMPRESSED_LEN);
+        let g1_recovered = BN254G1::from_compressed_bytes(&g1_bytes).unwrap();
+        assert_eq!(g1, g1_recovered);
+
+        let g2 = BN254G2::get_random_base(&mut rng);
+        let g2_bytes = g2.to_compressed_bytes();
+        assert_eq!(g2_bytes.len(), BN254G2::COMPRESSED_LEN);
+        let g2_recovered = BN254G2::from_compressed_bytes(&g2_bytes).unwrap();
+        assert_eq!(g2, g2_recovered);
+
+        let gt = BN254Gt::get_random_base(&mut rng);
+        let gt_bytes = gt.to_compressed_bytes();
+        assert_eq!(gt_bytes.len(), BN254Gt::COMPRESSED_LEN);
+        let gt_recovered = BN254Gt::from_compressed_bytes(&gt_bytes).unwrap();
+        assert_eq!(gt, gt_recovered);
+    }
+
+    #[test]
+    fn batch_point_decompression() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let points: Vec<BN254G2> = (0..10)
+            .map(|_| BN254G2::get_random_base(&mut rng))
+            .collect();
+        let mut bytes: Vec<Vec<u8>> =
+            points.iter().map(|p| p.to_compressed_bytes()).collect();
+        let recovered = batch_from_compressed_bytes::<BN254G2, _>(&bytes).unwrap();
+        assert_eq!(recovered, points);
+
+        // a single invalid encoding makes the batch fail
+        bytes[7] = vec![0u8; 10];
+        assert!(batch_from_compressed_bytes::<BN254G2, _>(&bytes).is_err());
+    }
+}
@@ -1,10 +1,13 @@
+use crate::errors::AlgebraError;
 use ark_std::{
     borrow::Borrow,
     fmt::Debug,
     rand::{CryptoRng, RngCore},
 };
 use digest::{generic_array::typenum::U64, Digest};
-use ruc::err::*;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use ruc::*;
 use serde::{Deserialize, Serialize};
 use utils::shift_u8_vec;
 
@@ -57,11 +60,44 @@ pub trait ScalarArithmetic: Clone + One + Zero + Sized {
 }
 
 pub trait Scalar:
-    Copy + Debug + PartialEq + Eq + ScalarArithmetic + Serialize + for<'de> Deserialize<'de>
+    Copy
+    + Debug
+    + PartialEq
+    + Eq
+    + ScalarArithmetic
+    + Serialize
+    + for<'de> Deserialize<'de>
+    + Send
+    + Sync
 {
     fn random<R: CryptoRng + RngCore>(rng: &mut R) -> Self;
     fn from_u32(value: u32) -> Self;
     fn from_u64(value: u64) -> Self;
+    fn from_u128(value: u128) -> Self {
+        // 2^64 = (2^64 - 1) + 1
+        let two_pow_64 = Self::from_u64(u64::MAX).add(&Self::one());
+        Self::from_u64((value >> 64) as u64)
+            .mul(&two_pow_64)
+            .add(&Self::from_u64(value as u64))
+    }
+    /// negative values are mapped to the additive inverse of their absolute value
+    fn from_i64(value: i64) -> Self {
+        let abs = Self::from_u64(value.unsigned_abs());
+        if value < 0 {
+            abs.neg()
+        } else {
+            abs
+        }
+    }
+    fn double(&self) -> Self {
+        self.add(self)
+    }
+    fn square(&self) -> Self {
+        self.mul(self)
+    }
+    fn pow_u64(&self, exponent: u64) -> Self {
+        self.pow(&[exponent])
+    }
     fn from_hash<D>(hash: D) -> Self
     where
         D: Digest<OutputSize = U64> + Default;
@@ -88,6 +124,8 @@ pub trait Group:
     + PartialEq
     + Eq
     + Clone
+    + Send
+    + Sync
     + GroupArithmetic
     + Serialize
     + for<'de> Deserialize<'de>
@@ -129,6 +167,13 @@ pub trait Group:
         Self::naive_multi_exp(scalars, points)
     }
 
+    /// Variable time multiplication by `scalar`, with the width-`WNAF_WIDTH` NAF of `scalar`.
+    /// Leaks `scalar` through timing: only for public scalars, e.g. in verification.
+    #[inline]
+    fn vartime_mul(&self, scalar: &Self::S) -> Self {
+        wnaf_mul(self, scalar, WNAF_WIDTH)
+    }
+
     #[inline]
     fn vartime_multi_exp(scalars: &[&Self::S], points: &[&Self]) -> Self {
         if scalars.is_empty() {
@@ -145,6 +190,34 @@ pub trait Pairing {
     type G2: Group<S = Self::ScalarField>;
     type Gt: Group<S = Self::ScalarField>;
     fn pairing(a: &Self::G1, b: &Self::G2) -> Self::Gt;
+
+    /// Computes the product of the pairings e(a_i, b_i) of the pairs (a_i, b_i) in `pairs`.
+    /// Implementations may share the final exponentiation among all pairings.
+    fn product_of_pairings(pairs: &[(Self::G1, Self::G2)]) -> Self::Gt {
+        pairs.iter().fold(Self::Gt::get_identity(), |acc, (a, b)| {
+            acc.add(&Self::pairing(a, b))
+        })
+    }
+}
+
+/// Decompresses the elements encoded in `points`, as in Group::from_compressed_bytes, and
+/// fails if any of them is not a valid encoding. For elliptic curve groups this includes
+/// checking the points are in the prime order subgroup, which is expensive for BLS12-381 G2,
+/// hence the elements are decompressed on multiple threads with the `parallel` feature.
+pub fn batch_from_compressed_bytes<G: Group, B: AsRef<[u8]> + Sync>(
+    points: &[B],
+) -> Result<Vec<G>> {
+    #[cfg(feature = "parallel")]
+    let iter = points.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let iter = points.iter();
+    let decompressed: Vec<Option<G>> = iter
+        .map(|bytes| G::from_compressed_bytes(bytes.as_ref()).ok())
+        .collect();
+    decompressed
+        .into_iter()
+        .map(|point| point.c(d!(AlgebraError::DeserializationError)))
+        .collect()
 }
 
 pub fn scalar_to_radix_2_power_w<S: Scalar>(scalar: &S, w: usize) -> Vec<i8> {
@@ -196,9 +269,86 @@ pub fn scalar_to_radix_2_power_w<S: Scalar>(scalar: &S, w: usize) -> Vec<i8> {
     digits
 }
 
+/// Window width of the wNAF used by Group::vartime_mul
+pub const WNAF_WIDTH: usize = 5;
+
+/// Computes the width-`w` non-adjacent form of `scalar`, for 2 <= w <= 8: the digits d_i,
+/// least significant first, such that scalar = \sum d_i 2^i, where every non-zero digit is odd
+/// with |d_i| < 2^{w-1}, and at most one of any w consecutive digits is non-zero.
+/// Returns an empty vector for zero.
+pub fn scalar_to_wnaf<S: Scalar>(scalar: &S, w: usize) -> Vec<i8> {
+    debug_assert!((2..=8).contains(&w));
+    let mut limbs = scalar.get_little_endian_u64();
+    // room for the carries of negative digits
+    limbs.push(0);
+    let radix: u64 = 1 << w;
+    let window_mask: u64 = radix - 1;
+
+    let mut digits = vec![];
+    while limbs.iter().any(|limb| *limb != 0) {
+        if limbs[0] & 1 == 1 {
+            let window = limbs[0] & window_mask;
+            if window < radix / 2 {
+                // subtract the digit, this clears the window
+                limbs[0] -= window;
+                digits.push(window as i8);
+            } else {
+                // add the absolute value of the digit, this clears the window with a carry
+                let abs_digit = radix - window;
+                let mut carry = abs_digit;
+                for limb in limbs.iter_mut() {
+                    let (sum, overflow) = limb.overflowing_add(carry);
+                    *limb = sum;
+                    if !overflow {
+                        break;
+                    }
+                    carry = 1;
+                }
+                digits.push(-(abs_digit as i16) as i8);
+            }
+        } else {
+            digits.push(0);
+        }
+        // shift the limbs right by one bit
+        let mut carried_bit = 0u64;
+        for limb in limbs.iter_mut().rev() {
+            let low_bit = *limb & 1;
+            *limb = (*limb >> 1) | (carried_bit << 63);
+            carried_bit = low_bit;
+        }
+    }
+    digits
+}
+
+/// Computes `scalar` * `point` in variable time from the width-`w` NAF of `scalar`,
+/// for 2 <= w <= 8. Uses 2^{w-2} precomputed multiples of `point`.
+pub fn wnaf_mul<G: Group>(point: &G, scalar: &G::S, w: usize) -> G {
+    let digits = scalar_to_wnaf(scalar, w);
+    // odd multiples point, 3 * point, ..., (2^{w-1} - 1) * point
+    let double_point = point.double();
+    let mut odd_multiples = vec![point.clone()];
+    for i in 1..1 << (w - 2) {
+        let next = odd_multiples[i - 1].add(&double_point);
+        odd_multiples.push(next);
+    }
+    let mut result = G::get_identity();
+    for digit in digits.iter().rev() {
+        result = result.double();
+        match digit.signum() {
+            1 => result = result.add(&odd_multiples[(*digit as usize) / 2]),
+            -1 => result = result.sub(&odd_multiples[((-*digit) as usize) / 2]),
+            _ => {}
+        }
+    }
+    result
+}
+
 #[cfg(test)]
 pub(crate) mod group_tests {
-    use crate::groups::{scalar_to_radix_2_power_w, Scalar};
+    use crate::groups::{
+        scalar_to_radix_2_power_w, scalar_to_wnaf, wnaf_mul, Group, Scalar,
+    };
+    use ark_std::rand::SeedableRng;
 
     pub(crate) fn test_scalar_operations<S: Scalar>() {
         let a = S::from_u32(40);
@@ -263,6 +413,23 @@ pub(crate) mod group_tests {
         let c = a.pow(&b[..]);
         let d = S::from_u64(3486784401);
         assert_eq!(c, d);
+        assert_eq!(a.pow_u64(20), d);
+        assert_eq!(a.pow_u64(0), S::from_u32(1));
+
+        let a = S::from_u32(12);
+        assert_eq!(a.double(), S::from_u32(24));
+        assert_eq!(a.square(), S::from_u32(144));
+
+        assert_eq!(S::from_u128(0x100000000), S::from_u64(0x100000000));
+        let two_pow_64 = S::from_u128(1u128 << 64);
+        assert_eq!(two_pow_64, S::from_u64(1 << 32).square());
+        let big = S::from_u128(u128::MAX);
+        assert_eq!(big.add(&S::from_u32(1)), two_pow_64.square());
+
+        assert_eq!(S::from_i64(40), S::from_u32(40));
+        assert_eq!(S::from_i64(-40), S::from_u32(40).neg());
+        assert_eq!(S::from_i64(0), S::from_u32(0));
+        assert_eq!(S::from_i64(i64::MIN), S::from_u64(1 << 63).neg());
     }
 
     pub(crate) fn test_scalar_serialization<S: Scalar>() {
@@ -291,4 +458,57 @@ pub(crate) mod group_tests {
         let expected = [-24, 16];
         assert_eq!(expected.as_ref(), r.as_slice());
     }
+
+    pub(crate) fn test_to_wnaf<S: Scalar>() {
+        let int = S::from_u32(41);
+        let r = scalar_to_wnaf(&int, 3);
+        let expected = [1i8, 0, 0, -3, 0, 0, 1]; // 41 = 1 - 3 * 8 + 64
+        assert_eq!(r.as_slice(), expected.as_ref());
+
+        let int = S::from_u32(7);
+        let r = scalar_to_wnaf(&int, 2);
+        let expected = [-1i8, 0, 0, 1]; // 7 = -1 + 8
+        assert_eq!(r.as_slice(), expected.as_ref());
+
+        assert!(scalar_to_wnaf(&S::from_u32(0), 4).is_empty());
+
+        // random scalars: check the digits and recompose
+        let mut prng = rand_chacha::ChaCha20Rng::from_seed([0u8; 32]);
+        for w in 2..9 {
+            let scalar = S::random(&mut prng);
+            let digits = scalar_to_wnaf(&scalar, w);
+            let mut recomposed = S::from_u32(0);
+            let two = S::from_u32(2);
+            for (i, digit) in digits.iter().enumerate().rev() {
+                assert!(
+                    *digit == 0
+                        || (digit % 2 != 0 && (*digit as i16).abs() < 1 << (w - 1))
+                );
+                if *digit != 0 {
+                    assert!(digits[i + 1..].iter().take(w - 1).all(|d| *d == 0));
+                }
+                let digit_scalar = S::from_u32((*digit as i16).abs() as u32);
+                recomposed = recomposed.mul(&two);
+                if *digit > 0 {
+                    recomposed = recomposed.add(&digit_scalar);
+                } else {
+                    recomposed = recomposed.sub(&digit_scalar);
+                }
+            }
+            assert_eq!(recomposed, scalar);
+        }
+    }
+
+    pub(crate) fn test_wnaf_mul<G: Group>() {
+        let mut prng = rand_chacha::ChaCha20Rng::from_seed([0u8; 32]);
+        let point = G::get_random_base(&mut prng);
+        for w in 2..9 {
+            let scalar = G::S::random(&mut prng);
+            assert_eq!(wnaf_mul(&point, &scalar, w), point.mul(&scalar));
+        }
+        let scalar = G::S::random(&mut prng);
+        assert_eq!(point.vartime_mul(&scalar), point.mul(&scalar));
+        assert_eq!(point.vartime_mul(&G::S::from_u32(0)), G::get_identity());
+        assert_eq!(point.vartime_mul(&G::S::from_u32(1)), point);
+    }
 }
@@ -3,11 +3,12 @@ use crate::{
     errors::AlgebraError,
     groups::{Group, GroupArithmetic, One, Scalar, ScalarArithmetic, Zero},
 };
-use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ec::{models::TEModelParameters, AffineCurve, ProjectiveCurve};
 use ark_ed_on_bls12_381::{
-    EdwardsAffine as AffinePoint, EdwardsProjective as ExtendedPoint, Fr,
+    EdwardsAffine as AffinePoint, EdwardsParameters, EdwardsProjective as ExtendedPoint,
+    Fq, Fr,
 };
-use ark_ff::{BigInteger, Field, PrimeField};
+use ark_ff::{BigInteger, Field, PrimeField, SquareRootField};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{
     cmp::Ordering,
@@ -137,6 +138,21 @@ impl Scalar for JubjubScalar {
         Self(Fr::from(value))
     }
 
+    #[inline]
+    fn from_u128(value: u128) -> Self {
+        Self(Fr::from(value))
+    }
+
+    #[inline]
+    fn double(&self) -> Self {
+        Self(self.0.double())
+    }
+
+    #[inline]
+    fn square(&self) -> Self {
+        Self(self.0.square())
+    }
+
     #[inline]
     fn from_hash<D>(hash: D) -> Self
     where
@@ -295,13 +311,64 @@ impl JubjubPoint {
         let affine_point = AffinePoint::from(self.0);
         BLSScalar::new(affine_point.y)
     }
+
+    /// Encode the point as in the Zcash Sapling specification (repr_J): the little-endian
+    /// y-coordinate, with the parity of the x-coordinate in the most significant bit.
+    pub fn to_sapling_bytes(&self) -> [u8; 32] {
+        let affine_point = AffinePoint::from(self.0);
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&affine_point.y.into_repr().to_bytes_le());
+        if affine_point.x.into_repr().is_odd() {
+            bytes[31] |= 0x80;
+        }
+        bytes
+    }
+
+    /// Decode a point encoded as in the Zcash Sapling specification (abst_J).
+    /// The point is not checked to be in the prime order subgroup.
+    pub fn from_sapling_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != Self::COMPRESSED_LEN {
+            return Err(eg!(AlgebraError::DeserializationError));
+        }
+        let mut y_bytes = [0u8; 32];
+        y_bytes.copy_from_slice(bytes);
+        let x_is_odd = y_bytes[31] >> 7 == 1;
+        y_bytes[31] &= 0x7f;
+        let y = Fq::from_le_bytes_mod_order(&y_bytes);
+        // non canonical encoding of y
+        if y.into_repr().to_bytes_le() != y_bytes {
+            return Err(eg!(AlgebraError::DecompressElementError));
+        }
+        // a*x^2 + y^2 = 1 + d*x^2*y^2, hence x^2 = (y^2 - 1) / (d*y^2 - a)
+        let y2 = y.square();
+        let denominator = EdwardsParameters::COEFF_D * y2 - EdwardsParameters::COEFF_A;
+        let denominator_inv = denominator.inverse();
+        if denominator_inv.is_none() {
+            return Err(eg!(AlgebraError::DecompressElementError));
+        }
+        let x = ((y2 - Fq::one()) * denominator_inv.unwrap()).sqrt(); // safe unwrap
+        if x.is_none() {
+            return Err(eg!(AlgebraError::DecompressElementError));
+        }
+        let mut x = x.unwrap(); // safe unwrap
+        if x.is_zero() && x_is_odd {
+            return Err(eg!(AlgebraError::DecompressElementError));
+        }
+        if x.into_repr().is_odd() != x_is_odd {
+            x = -x;
+        }
+        Ok(Self(ExtendedPoint::from(AffinePoint::new(x, y))))
+    }
 }
 
 #[cfg(test)]
 mod jubjub_groups_test {
     use crate::{
         groups::{
-            group_tests::{test_scalar_operations, test_scalar_serialization},
+            group_tests::{
+                test_scalar_operations, test_scalar_serialization, test_to_wnaf,
+                test_wnaf_mul,
+            },
             Group, GroupArithmetic, Scalar, ScalarArithmetic,
         },
         jubjub::{JubjubPoint, JubjubScalar},
@@ -319,6 +386,12 @@ mod jubjub_groups_test {
         test_scalar_serialization::<JubjubScalar>();
     }
 
+    #[test]
+    fn scalar_to_wnaf() {
+        test_to_wnaf::<JubjubScalar>();
+        test_wnaf_mul::<JubjubPoint>();
+    }
+
     #[test]
     fn scalar_from_to_bytes() {
         let small_value = JubjubScalar::from_u32(165747);
@@ -334,6 +407,34 @@ mod jubjub_groups_test {
         assert_eq!(small_value_from_bytes, small_value);
     }
 
+    #[test]
+    fn sapling_point_encoding() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        for _ in 0..10 {
+            let point = JubjubPoint::get_random_base(&mut rng);
+            let bytes = point.to_sapling_bytes();
+            assert_eq!(JubjubPoint::from_sapling_bytes(&bytes).unwrap(), point);
+            let neg_bytes = JubjubPoint::get_identity().sub(&point).to_sapling_bytes();
+            assert_eq!(bytes[..31], neg_bytes[..31]);
+            assert_ne!(bytes[31], neg_bytes[31]);
+        }
+        let identity = JubjubPoint::get_identity();
+        let mut bytes = [0u8; 32];
+        bytes[0] = 1;
+        assert_eq!(identity.to_sapling_bytes(), bytes);
+        assert_eq!(JubjubPoint::from_sapling_bytes(&bytes).unwrap(), identity);
+        // x = 0 with odd sign
+        bytes[31] = 0x80;
+        assert!(JubjubPoint::from_sapling_bytes(&bytes).is_err());
+        // y = q is not canonical
+        let q: [u8; 32] = [
+            1, 0, 0, 0, 255, 255, 255, 255, 254, 91, 254, 255, 2, 164, 189, 83, 5, 216,
+            161, 9, 8, 216, 57, 51, 72, 125, 157, 41, 83, 167, 237, 115,
+        ];
+        assert!(JubjubPoint::from_sapling_bytes(&q).is_err());
+        assert!(JubjubPoint::from_sapling_bytes(&q[..31]).is_err());
+    }
+
     #[test]
     fn schnorr_identification_protocol() {
         let mut rng = ChaCha20Rng::from_entropy();
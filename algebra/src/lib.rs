@@ -19,9 +19,11 @@
 extern crate utils;
 
 pub mod bls12_381;
+pub mod bn254;
 pub mod errors;
 pub mod groups;
 pub mod jubjub;
 pub mod multi_exp;
+pub mod pairing_batch;
 pub mod ristretto;
 pub mod serialization;
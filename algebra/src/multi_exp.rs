@@ -76,6 +76,7 @@ pub fn pippenger<G: Group>(scalars: &[&G::S], elems: &[&G]) -> Result<G> {
 #[cfg(test)]
 mod tests {
     use crate::bls12_381::{BLSGt, BLSG1, BLSG2};
+    use crate::bn254::{BN254Gt, BN254G1, BN254G2};
     use crate::groups::{Group, Scalar};
     use crate::ristretto::RistrettoPoint;
 
@@ -95,6 +96,18 @@ mod tests {
     fn test_multiexp_blsgt() {
         run_multiexp_test::<BLSGt>();
     }
+    #[test]
+    fn test_multiexp_bn254g1() {
+        run_multiexp_test::<BN254G1>();
+    }
+    #[test]
+    fn test_multiexp_bn254g2() {
+        run_multiexp_test::<BN254G2>();
+    }
+    #[test]
+    fn test_multiexp_bn254gt() {
+        run_multiexp_test::<BN254Gt>();
+    }
 
     fn run_multiexp_test<G: Group>() {
         let g = G::vartime_multi_exp(&[], &[]);
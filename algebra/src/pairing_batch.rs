@@ -0,0 +1,167 @@
+use crate::errors::AlgebraError;
+use crate::groups::Pairing;
+use ark_std::rand::{CryptoRng, RngCore};
+use ruc::*;
+
+/// Pairing equation prod_i e(lhs_i.0, lhs_i.1) == prod_j e(rhs_j.0, rhs_j.1), as checked
+/// at the end of the verification of a pairing based proof (e.g. a KZG opening or a credential)
+pub struct PairingCheck<P: Pairing> {
+    pub lhs: Vec<(P::G1, P::G2)>,
+    pub rhs: Vec<(P::G1, P::G2)>,
+}
+
+impl<P: Pairing> PairingCheck<P> {
+    pub fn new(lhs: Vec<(P::G1, P::G2)>, rhs: Vec<(P::G1, P::G2)>) -> PairingCheck<P> {
+        PairingCheck { lhs, rhs }
+    }
+
+    // pairs whose product of pairings is the identity iff the check holds, with the G1
+    // elements scaled by `scalar`
+    fn scaled_pairs(&self, scalar: &P::ScalarField) -> Vec<(P::G1, P::G2)> {
+        let minus_scalar = scalar.neg();
+        self.lhs
+            .iter()
+            .map(|(a, b)| (a.mul(scalar), b.clone()))
+            .chain(
+                self.rhs
+                    .iter()
+                    .map(|(a, b)| (a.mul(&minus_scalar), b.clone())),
+            )
+            .collect()
+    }
+
+    /// Checks the equation with a single final exponentiation
+    pub fn verify(&self) -> Result<()> {
+        let pairs = self.scaled_pairs(&P::ScalarField::from_u32(1));
+        if P::product_of_pairings(&pairs) == P::Gt::get_identity() {
+            Ok(())
+        } else {
+            Err(eg!(AlgebraError::ArgumentVerificationError))
+        }
+    }
+}
+
+/// Batch of pairing checks of proofs verified together (e.g. the proofs of a block), possibly
+/// of different types. The checks are combined with random scalars r_k into the single
+/// equation prod_k (prod_i e(lhs_ki) / prod_j e(rhs_kj))^{r_k} == 1, computed as one
+/// multi-pairing with one final exponentiation. A false check passes the combined check
+/// with probability 1/p at most.
+pub struct PairingCheckBatch<P: Pairing> {
+    checks: Vec<PairingCheck<P>>,
+}
+
+impl<P: Pairing> Default for PairingCheckBatch<P> {
+    fn default() -> Self {
+        PairingCheckBatch { checks: vec![] }
+    }
+}
+
+impl<P: Pairing> PairingCheckBatch<P> {
+    pub fn new() -> PairingCheckBatch<P> {
+        Self::default()
+    }
+
+    /// Adds `check` to the batch, returns its index in the batch
+    pub fn add_check(&mut self, check: PairingCheck<P>) -> usize {
+        self.checks.push(check);
+        self.checks.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.checks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    /// Verifies all the checks of the batch at once
+    pub fn verify<R: CryptoRng + RngCore>(&self, prng: &mut R) -> Result<()> {
+        let mut pairs = vec![];
+        for (k, check) in self.checks.iter().enumerate() {
+            // the first check needs no randomization
+            let scalar = if k == 0 {
+                P::ScalarField::from_u32(1)
+            } else {
+                P::ScalarField::random(prng)
+            };
+            pairs.append(&mut check.scaled_pairs(&scalar));
+        }
+        if P::product_of_pairings(&pairs) == P::Gt::get_identity() {
+            Ok(())
+        } else {
+            Err(eg!(AlgebraError::ArgumentVerificationError))
+        }
+    }
+
+    /// Returns the indices of the checks of the batch that fail, which is empty iff all
+    /// checks hold. Verifies the batch at once first, and falls back to verifying the checks
+    /// one by one only if the batch fails.
+    pub fn failed_checks<R: CryptoRng + RngCore>(&self, prng: &mut R) -> Vec<usize> {
+        if self.verify(prng).is_ok() {
+            return vec![];
+        }
+        self.checks
+            .iter()
+            .enumerate()
+            .filter(|(_, check)| check.verify().is_err())
+            .map(|(k, _)| k)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bls12_381::{BLSScalar, Bls12381, BLSG1, BLSG2};
+    use crate::groups::{Group, GroupArithmetic, Scalar, ScalarArithmetic};
+    use crate::pairing_batch::{PairingCheck, PairingCheckBatch};
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use ruc::*;
+
+    // e(s * g1, t * g2) == e((s * t) * g1, g2)
+    fn check(s: &BLSScalar, t: &BLSScalar, valid: bool) -> PairingCheck<Bls12381> {
+        let mut st = s.mul(t);
+        if !valid {
+            st = st.add(&BLSScalar::from_u32(1));
+        }
+        PairingCheck::new(
+            vec![(BLSG1::get_base().mul(s), BLSG2::get_base().mul(t))],
+            vec![(BLSG1::get_base().mul(&st), BLSG2::get_base())],
+        )
+    }
+
+    #[test]
+    fn test_pairing_check_batch() {
+        let mut prng = ChaCha20Rng::from_seed([0u8; 32]);
+        let mut batch = PairingCheckBatch::<Bls12381>::new();
+        assert!(batch.is_empty());
+        pnk!(batch.verify(&mut prng));
+        for _ in 0..4 {
+            let s = BLSScalar::random(&mut prng);
+            let t = BLSScalar::random(&mut prng);
+            pnk!(check(&s, &t, true).verify());
+            batch.add_check(check(&s, &t, true));
+        }
+        // a check with two pairings on each side
+        let s = BLSScalar::random(&mut prng);
+        let mut two_sided = check(&s, &s, true);
+        two_sided
+            .lhs
+            .push((BLSG1::get_base(), BLSG2::get_base().mul(&s)));
+        two_sided
+            .rhs
+            .push((BLSG1::get_base().mul(&s), BLSG2::get_base()));
+        assert_eq!(batch.add_check(two_sided), 4);
+        assert_eq!(batch.len(), 5);
+        pnk!(batch.verify(&mut prng));
+        assert!(batch.failed_checks(&mut prng).is_empty());
+
+        let s = BLSScalar::random(&mut prng);
+        let bad_check = check(&s, &s, false);
+        assert!(bad_check.verify().is_err());
+        let bad_index = batch.add_check(bad_check);
+        assert!(batch.verify(&mut prng).is_err());
+        assert_eq!(batch.failed_checks(&mut prng), vec![bad_index]);
+    }
+}
@@ -310,4 +310,9 @@ mod ristretto_group_test {
     fn scalar_to_radix() {
         crate::groups::group_tests::test_to_radix::<super::RistrettoScalar>();
     }
+    #[test]
+    fn scalar_to_wnaf() {
+        crate::groups::group_tests::test_to_wnaf::<super::RistrettoScalar>();
+        crate::groups::group_tests::test_wnaf_mul::<super::RistrettoPoint>();
+    }
 }
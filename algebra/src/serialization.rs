@@ -1,5 +1,6 @@
 use crate::{
     bls12_381::{BLSGt, BLSScalar, BLSG1, BLSG2},
+    bn254::{BN254Gt, BN254Scalar, BN254G1, BN254G2},
     groups::{Group, Scalar},
     jubjub::{JubjubPoint, JubjubScalar},
     ristretto::{
@@ -29,6 +30,7 @@ macro_rules! to_from_bytes_scalar {
 to_from_bytes_scalar!(RistrettoScalar);
 to_from_bytes_scalar!(JubjubScalar);
 to_from_bytes_scalar!(BLSScalar);
+to_from_bytes_scalar!(BN254Scalar);
 
 impl ZeiFromToBytes for CompressedRistretto {
     #[inline]
@@ -61,6 +63,7 @@ serialize_deserialize!(CompressedEdwardsY);
 serialize_deserialize!(RistrettoScalar);
 serialize_deserialize!(JubjubScalar);
 serialize_deserialize!(BLSScalar);
+serialize_deserialize!(BN254Scalar);
 
 macro_rules! to_from_bytes_group {
     ($g:ident) => {
@@ -81,9 +84,61 @@ to_from_bytes_group!(JubjubPoint);
 to_from_bytes_group!(BLSG1);
 to_from_bytes_group!(BLSG2);
 to_from_bytes_group!(BLSGt);
+to_from_bytes_group!(BN254G1);
+to_from_bytes_group!(BN254G2);
+to_from_bytes_group!(BN254Gt);
 
 serialize_deserialize!(RistrettoPoint);
 serialize_deserialize!(JubjubPoint);
 serialize_deserialize!(BLSG1);
 serialize_deserialize!(BLSG2);
 serialize_deserialize!(BLSGt);
+serialize_deserialize!(BN254G1);
+serialize_deserialize!(BN254G2);
+serialize_deserialize!(BN254Gt);
+
+/// Serde helpers for vectors of group elements, for fields annotated with
+/// `#[serde(with = "algebra::serialization::batch_points")]`. The encoding is the one of
+/// `Vec<G>`, but the elements are decompressed and validated in a batch, see
+/// groups::batch_from_compressed_bytes.
+pub mod batch_points {
+    use crate::groups::{batch_from_compressed_bytes, Group};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use utils::serialization::zei_obj_serde::BytesVisitor;
+
+    // compressed group element, as encoded by the serialize_deserialize macro
+    struct CompressedPoint(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for CompressedPoint {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let bytes = if deserializer.is_human_readable() {
+                deserializer.deserialize_str(BytesVisitor)?
+            } else {
+                deserializer.deserialize_bytes(BytesVisitor)?
+            };
+            Ok(CompressedPoint(bytes))
+        }
+    }
+
+    pub fn serialize<S, G>(points: &[G], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        G: Group,
+    {
+        serializer.collect_seq(points)
+    }
+
+    pub fn deserialize<'de, D, G>(deserializer: D) -> Result<Vec<G>, D::Error>
+    where
+        D: Deserializer<'de>,
+        G: Group,
+    {
+        let compressed: Vec<CompressedPoint> = Vec::deserialize(deserializer)?;
+        let bytes: Vec<&[u8]> =
+            compressed.iter().map(|point| point.0.as_slice()).collect();
+        batch_from_compressed_bytes(&bytes).map_err(serde::de::Error::custom)
+    }
+}
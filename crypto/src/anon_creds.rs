@@ -76,21 +76,38 @@ in the credentials by
         (e(sigma1',P) = e(r * u * G1, c * (X2 + \sum attr_i * Y2_j + t * G2 + sk * Z2) )
                       = e(G1,G2) * r * u * c( x + \sum attr_i * y_i + t + sk * z), and
          e(sigma2', c * G2) = e(G1,G2) * r * c * u * (x + \sum attr_i * y_i + t + sk * x)
+
+  + Blind issuance: the issuer signs attributes without learning the hidden ones, as in
+  section 6.1 of Pointcheval and Sanders. The issuer publishes G1 and {Y1_i = y_i * G1}.
+    a) Request: the user samples a scalar t, computes C = t * G1 + \sum Y1_i * attr_i
+       (where i ranges for the hidden attributes), and a NI proof of knowledge of t,
+       {attr_i} and sk such that C = t * G1 + \sum Y1_i * attr_i and user_pk = sk * Z1
+    b) Blind signature: the issuer verifies the proof, samples a scalar u, and computes
+       sigma1 = u * G1, sigma2 = u * ((x + \sum attr_j * y_j) * G1 + C + user_pk)
+       (where j ranges for the attributes known to the issuer)
+    c) Unblinding: sigma2' = sigma2 - t * sigma1 = u * (x + \sum attr_i * y_i + sk * z) * G1,
+       so that (sigma1, sigma2') is the signature of ac_sign, which the user verifies
 */
 
-use crate::sigma::{SigmaTranscript, SigmaTranscriptPairing};
+use crate::sigma::{
+    sigma_prove, sigma_verify, SigmaProof, SigmaTranscript, SigmaTranscriptPairing,
+};
 use algebra::groups::{Group, GroupArithmetic, Pairing, Scalar, ScalarArithmetic};
+use algebra::pairing_batch::PairingCheck;
 use itertools::Itertools;
 use merlin::Transcript;
 use rand_core::{CryptoRng, RngCore};
 use ruc::*;
 use utils::errors::ZeiError;
+use utils::serialization::Validate;
 
 pub(crate) const AC_REVEAL_PROOF_DOMAIN: &[u8] = b"AC Reveal PoK";
 pub(crate) const AC_REVEAL_PROOF_NEW_TRANSCRIPT_INSTANCE: &[u8] =
     b"AC Reveal PoK Instance";
 pub(crate) const AC_COMMIT_NEW_TRANSCRIPT_INSTANCE: &[u8] = b"AC Commit SoK Instance";
 pub(crate) const SOK_LABEL: &[u8] = b"Signature Message";
+const AC_BLIND_ISSUANCE_NEW_TRANSCRIPT_INSTANCE: &[u8] =
+    b"AC Blind Issuance PoK Instance";
 
 /// I contain Credentials' Issuer Public key fields
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -179,6 +196,24 @@ pub struct ACRevealSig<G1, G2, S> {
     pub pok: ACRevealProof<G2, S>,
 }
 
+impl<G1: Group> Validate for ACCommitment<G1> {
+    /// Checks that sigma1 is not the identity, a commitment (0, 0) would pass the pairing check
+    /// for any set of attributes
+    fn validate(&self) -> Result<()> {
+        if self.0.sigma1 == G1::get_identity() {
+            return Err(eg!(ZeiError::IdentityRevealVerifyError));
+        }
+        Ok(())
+    }
+}
+
+impl<G1: Group, G2, S> Validate for ACRevealSig<G1, G2, S> {
+    /// Checks the credential commitment
+    fn validate(&self) -> Result<()> {
+        self.sig_commitment.validate().c(d!())
+    }
+}
+
 /// Proof that revealed attributes verify a credential commitment signature
 pub type ACRevealProof<G2, S> = ACPoK<G2, S>;
 
@@ -206,6 +241,29 @@ pub struct ACKey<S> {
     pub t: S,
 }
 
+/// I contain the elements of G1 that an issuer publishes for the blind issuance of credentials
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACBlindIssuanceKey<G1> {
+    pub gen1: G1,     //gen1 in CredIssuerSecretKey
+    pub yy1: Vec<G1>, //gen1^{y_i}, y_i in CredIssuerSecretKey
+}
+
+/// I'm a request for the blind signature of a set of attributes, some of which are hidden
+/// to the issuer
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACBlindIssueRequest<G1, S> {
+    pub commitment: G1, // t*G1 + sum_{a_i in hidden attrs} a_i*Y1_i
+    pub pok: SigmaProof<S, G1>,
+}
+
+/// Secret blinding scalar t of a blind issuance request. Used to unblind the signature.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACBlindingKey<S>(pub(crate) S);
+
+/// I'm a signature produced by the issuer for a blind issuance request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACBlindSignature<G1>(pub(crate) ACSignature<G1>);
+
 #[allow(type_alias_bounds)]
 pub type ACCommitOutput<P: Pairing> = (
     ACCommitment<P::G1>,
@@ -284,6 +342,238 @@ pub fn ac_sign<R: CryptoRng + RngCore, P: Pairing>(
     })
 }
 
+/// I compute the elements that the issuer publishes for the blind issuance of credentials
+pub fn ac_blind_issuance_key<P: Pairing>(
+    issuer_sk: &ACIssuerSecretKey<P::G1, P::ScalarField>,
+) -> ACBlindIssuanceKey<P::G1> {
+    ACBlindIssuanceKey {
+        gen1: issuer_sk.gen1.clone(),
+        yy1: issuer_sk
+            .y
+            .iter()
+            .map(|yi| issuer_sk.gen1.mul(yi))
+            .collect(),
+    }
+}
+
+// Statement of the proof of a blind issuance request, for the sigma protocol of sigma.rs:
+// public elements [identity, G1, {Y1_i}, Z1, C, user_pk], the secrets being t, the hidden
+// attributes, and sk
+#[allow(clippy::type_complexity)]
+fn ac_blind_issue_statement<'a, P: Pairing>(
+    transcript: &mut Transcript,
+    issuer_pk: &'a ACIssuerPublicKey<P::G1, P::G2>,
+    blind_key: &'a ACBlindIssuanceKey<P::G1>,
+    user_pk: &'a ACUserPublicKey<P::G1>,
+    commitment: &'a P::G1,
+    identity: &'a P::G1,
+    attrs: &[Attribute<&P::ScalarField>],
+) -> Result<(Vec<&'a P::G1>, Vec<Vec<usize>>, Vec<usize>)> {
+    let n = issuer_pk.num_attrs();
+    if attrs.len() != n || blind_key.yy1.len() != n {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let mut elems = vec![identity, &blind_key.gen1];
+    for y in blind_key.yy1.iter() {
+        elems.push(y);
+    }
+    elems.push(&issuer_pk.zz1);
+    elems.push(commitment);
+    elems.push(&user_pk.0);
+
+    let mut commitment_row = vec![1];
+    for (i, attr) in attrs.iter().enumerate() {
+        match attr {
+            Attribute::Revealed(attr) => {
+                transcript.append_field_element(b"revealed attribute", *attr);
+            }
+            Attribute::Hidden(_) => {
+                transcript.append_message(b"hidden attribute", b"");
+                commitment_row.push(2 + i);
+            }
+        }
+    }
+    commitment_row.push(0);
+    let mut user_pk_row = vec![0; commitment_row.len()];
+    user_pk_row[commitment_row.len() - 1] = 2 + n;
+    Ok((elems, vec![commitment_row, user_pk_row], vec![3 + n, 4 + n]))
+}
+
+/// I compute a request for the blind signature of a set of attributes by the issuer of
+/// `blind_key`: the attributes at the positions where the bitmap is true are known to the
+/// issuer, the other ones are hidden. The blinding key is used to unblind the signature with
+/// ac_unblind_credential.
+#[allow(clippy::type_complexity)]
+pub fn ac_blind_issue_request<R: CryptoRng + RngCore, P: Pairing>(
+    prng: &mut R,
+    issuer_pk: &ACIssuerPublicKey<P::G1, P::G2>,
+    blind_key: &ACBlindIssuanceKey<P::G1>,
+    user_sk: &ACUserSecretKey<P::ScalarField>,
+    attrs: &[P::ScalarField],
+    reveal_bitmap: &[bool],
+) -> Result<(
+    ACBlindIssueRequest<P::G1, P::ScalarField>,
+    ACBlindingKey<P::ScalarField>,
+)> {
+    if attrs.len() != reveal_bitmap.len() || blind_key.yy1.len() != attrs.len() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    // the published elements must be the ones of the issuer public key, otherwise the
+    // commitment may not hide the attributes
+    if blind_key.gen1 == P::G1::get_identity() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    for (yy1i, yy2i) in blind_key.yy1.iter().zip(issuer_pk.yy2.iter()) {
+        PairingCheck::<P>::new(
+            vec![(yy1i.clone(), issuer_pk.gen2.clone())],
+            vec![(blind_key.gen1.clone(), yy2i.clone())],
+        )
+        .verify()
+        .c(d!(ZeiError::ParameterError))?;
+    }
+
+    let t = P::ScalarField::random(prng);
+    let mut commitment = blind_key.gen1.mul(&t);
+    let mut secrets = vec![&t];
+    let mut attributes = vec![];
+    for ((attr, b), yy1i) in attrs.iter().zip(reveal_bitmap).zip(blind_key.yy1.iter()) {
+        if *b {
+            attributes.push(Attribute::Revealed(attr));
+        } else {
+            commitment = commitment.add(&yy1i.mul(attr));
+            secrets.push(attr);
+            attributes.push(Attribute::Hidden(Some(attr)));
+        }
+    }
+    secrets.push(&user_sk.0);
+    let user_pk = ACUserPublicKey(issuer_pk.zz1.mul(&user_sk.0));
+    let identity = P::G1::get_identity();
+
+    let mut transcript = Transcript::new(AC_BLIND_ISSUANCE_NEW_TRANSCRIPT_INSTANCE);
+    let (elems, lhs_matrix, _) = ac_blind_issue_statement::<P>(
+        &mut transcript,
+        issuer_pk,
+        blind_key,
+        &user_pk,
+        &commitment,
+        &identity,
+        attributes.as_slice(),
+    )
+    .c(d!())?;
+    let pok = sigma_prove(
+        &mut transcript,
+        prng,
+        elems.as_slice(),
+        lhs_matrix.as_slice(),
+        secrets.as_slice(),
+    );
+
+    Ok((ACBlindIssueRequest { commitment, pok }, ACBlindingKey(t)))
+}
+
+/// I verify a blind issuance request of `user_pk` and sign its attributes: the Revealed(attr)
+/// attributes are known to the issuer and the Hidden(None) ones are committed in the request.
+pub fn ac_issuer_blind_sign<R: CryptoRng + RngCore, P: Pairing>(
+    prng: &mut R,
+    issuer_sk: &ACIssuerSecretKey<P::G1, P::ScalarField>,
+    issuer_pk: &ACIssuerPublicKey<P::G1, P::G2>,
+    user_pk: &ACUserPublicKey<P::G1>,
+    attrs: &[Attribute<P::ScalarField>],
+    request: &ACBlindIssueRequest<P::G1, P::ScalarField>,
+) -> Result<ACBlindSignature<P::G1>> {
+    if attrs.len() != issuer_sk.y.len() {
+        return Err(eg!(ZeiError::AnonymousCredentialSignError));
+    }
+    let blind_key = ac_blind_issuance_key::<P>(issuer_sk);
+    let identity = P::G1::get_identity();
+    let attributes = attrs
+        .iter()
+        .map(|attr| match attr {
+            Attribute::Revealed(attr) => Attribute::Revealed(attr),
+            Attribute::Hidden(_) => Attribute::Hidden(None),
+        })
+        .collect_vec();
+    let mut transcript = Transcript::new(AC_BLIND_ISSUANCE_NEW_TRANSCRIPT_INSTANCE);
+    let (elems, lhs_matrix, rhs_vec) = ac_blind_issue_statement::<P>(
+        &mut transcript,
+        issuer_pk,
+        &blind_key,
+        user_pk,
+        &request.commitment,
+        &identity,
+        attributes.as_slice(),
+    )
+    .c(d!())?;
+    if request.pok.commitments.len() != rhs_vec.len()
+        || request.pok.responses.len() != lhs_matrix[0].len()
+    {
+        return Err(eg!(ZeiError::ZKProofVerificationError));
+    }
+    sigma_verify(
+        &mut transcript,
+        prng,
+        elems.as_slice(),
+        lhs_matrix.as_slice(),
+        rhs_vec.as_slice(),
+        &request.pok,
+    )
+    .c(d!())?;
+
+    let u = P::ScalarField::random(prng);
+    let mut exponent = issuer_sk.x;
+    for (attr, yi) in attrs.iter().zip(issuer_sk.y.iter()) {
+        if let Attribute::Revealed(attr) = attr {
+            exponent = exponent.add(&attr.mul(yi));
+        }
+    }
+    let cc = issuer_sk
+        .gen1
+        .mul(&exponent)
+        .add(&request.commitment)
+        .add(&user_pk.0);
+    Ok(ACBlindSignature(ACSignature {
+        sigma1: issuer_sk.gen1.mul(&u),
+        sigma2: cc.mul(&u),
+    }))
+}
+
+/// I unblind the signature of a blind issuance request for the attributes `attrs`, check it,
+/// and return the credential. Returns Err(ZeiError::SignatureError) if the signature is not
+/// valid for the attributes.
+pub fn ac_unblind_credential<P: Pairing>(
+    issuer_pk: &ACIssuerPublicKey<P::G1, P::G2>,
+    user_sk: &ACUserSecretKey<P::ScalarField>,
+    blind_sig: &ACBlindSignature<P::G1>,
+    blinding_key: &ACBlindingKey<P::ScalarField>,
+    attrs: Vec<P::ScalarField>,
+) -> Result<Credential<P::G1, P::G2, P::ScalarField>> {
+    if attrs.len() != issuer_pk.num_attrs() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let sigma1 = blind_sig.0.sigma1.clone();
+    let sigma2 = blind_sig.0.sigma2.sub(&sigma1.mul(&blinding_key.0));
+    if sigma1 == P::G1::get_identity() {
+        return Err(eg!(ZeiError::SignatureError));
+    }
+    // e(sigma1, X2 + sk * Z2 + \sum attr_i * Y2_i) = e(sigma2, G2)
+    let mut p = issuer_pk.xx2.add(&issuer_pk.zz2.mul(&user_sk.0));
+    for (attr, yy2i) in attrs.iter().zip(issuer_pk.yy2.iter()) {
+        p = p.add(&yy2i.mul(attr));
+    }
+    PairingCheck::<P>::new(
+        vec![(sigma1.clone(), p)],
+        vec![(sigma2.clone(), issuer_pk.gen2.clone())],
+    )
+    .verify()
+    .c(d!(ZeiError::SignatureError))?;
+
+    Ok(Credential {
+        signature: ACSignature { sigma1, sigma2 },
+        attributes: attrs,
+        issuer_pub_key: issuer_pk.clone(),
+    })
+}
+
 /// Sample an  AC commitment key
 pub fn ac_commitment_key_gen<R: CryptoRng + RngCore, P: Pairing>(
     prng: &mut R,
@@ -366,6 +656,7 @@ pub fn ac_verify_commitment<P: Pairing>(
     sok: &ACPoK<P::G2, P::ScalarField>,
     msg: &[u8],
 ) -> Result<()> {
+    sig_commitment.validate().c(d!())?;
     let mut transcript = Transcript::new(AC_COMMIT_NEW_TRANSCRIPT_INSTANCE);
     ac_init_transcript::<P>(&mut transcript, issuer_pub_key, sig_commitment); // public parameters
     transcript.append_message(SOK_LABEL, msg); // SoK proof on message msg
@@ -408,7 +699,29 @@ pub(crate) fn ac_do_challenge_check_commitment<P: Pairing>(
     attributes: &[Attribute<P::ScalarField>],
     challenge: &P::ScalarField,
 ) -> Result<()> {
+    ac_challenge_pairing_check::<P>(
+        issuer_pub_key,
+        sig_commitment,
+        pok,
+        attributes,
+        challenge,
+    )
+    .c(d!())?
+    .verify()
+    .c(d!(ZeiError::IdentityRevealVerifyError))
+}
+
+fn ac_challenge_pairing_check<P: Pairing>(
+    issuer_pub_key: &ACIssuerPublicKey<P::G1, P::G2>,
+    sig_commitment: &ACCommitment<P::G1>,
+    pok: &ACPoK<P::G2, P::ScalarField>,
+    attributes: &[Attribute<P::ScalarField>],
+    challenge: &P::ScalarField,
+) -> Result<PairingCheck<P>> {
     // p = X_2*c - proof_commitment + &G2 * r_t + Z2 * r_sk + \sum r_attr_i * Y2_i;
+    if attributes.len() != issuer_pub_key.yy2.len() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
 
     let minus_one: P::ScalarField = P::ScalarField::from_u32(1).neg();
     let mut scalars = vec![
@@ -438,6 +751,10 @@ pub(crate) fn ac_do_challenge_check_commitment<P: Pairing>(
             }
         }
     }
+    // one response for each hidden attribute
+    if resp_attr_iter.next().is_some() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
     let mut elems = vec![
         &issuer_pub_key.gen2,
         &issuer_pub_key.xx2,
@@ -449,7 +766,12 @@ pub(crate) fn ac_do_challenge_check_commitment<P: Pairing>(
         elems.push(y);
     }
     let p = P::G2::vartime_multi_exp(scalars.as_slice(), elems.as_slice());
-    ac_verify_final_check::<P>(sig_commitment, challenge, &issuer_pub_key.gen2, &p)
+    Ok(ac_final_pairing_check::<P>(
+        sig_commitment,
+        challenge,
+        &issuer_pub_key.gen2,
+        &p,
+    ))
 }
 /// Produce a AttrsRevealProof, attributes that are not Revealed(attr) and secret parameters
 /// are proved in ZeroKnowledge.
@@ -565,6 +887,7 @@ pub fn ac_verify<P: Pairing>(
     sig_commitment: &ACCommitment<P::G1>,
     reveal_proof: &ACRevealProof<P::G2, P::ScalarField>,
 ) -> Result<()> {
+    sig_commitment.validate().c(d!())?;
     let mut transcript = Transcript::new(AC_REVEAL_PROOF_NEW_TRANSCRIPT_INSTANCE);
     ac_init_transcript::<P>(&mut transcript, issuer_pub_key, sig_commitment);
     pok_verify::<P>(
@@ -576,6 +899,29 @@ pub fn ac_verify<P: Pairing>(
     )
 }
 
+/// Same as ac_verify, but returns the final pairing check, e(sigma1, p) == e(sigma2, c*g2),
+/// instead of checking it, so that it can be batched with the pairing checks of other proofs.
+/// Returns an error if the proof is malformed.
+pub fn ac_verify_pairing_check<P: Pairing>(
+    issuer_pub_key: &ACIssuerPublicKey<P::G1, P::G2>,
+    attrs: &[Attribute<P::ScalarField>],
+    sig_commitment: &ACCommitment<P::G1>,
+    reveal_proof: &ACRevealProof<P::G2, P::ScalarField>,
+) -> Result<PairingCheck<P>> {
+    sig_commitment.validate().c(d!())?;
+    let mut transcript = Transcript::new(AC_REVEAL_PROOF_NEW_TRANSCRIPT_INSTANCE);
+    ac_init_transcript::<P>(&mut transcript, issuer_pub_key, sig_commitment);
+    transcript.append_proof_commitment(&reveal_proof.commitment);
+    let challenge = transcript.get_challenge::<P::ScalarField>();
+    ac_challenge_pairing_check::<P>(
+        issuer_pub_key,
+        sig_commitment,
+        reveal_proof,
+        attrs,
+        &challenge,
+    )
+}
+
 pub(super) fn ac_init_transcript<P: Pairing>(
     transcript: &mut Transcript,
     issuer_pk: &ACIssuerPublicKey<P::G1, P::G2>,
@@ -618,6 +964,9 @@ fn prove_pok<R: CryptoRng + RngCore, P: Pairing>(
     t: &P::ScalarField,
     attrs: &[Attribute<&P::ScalarField>],
 ) -> Result<ACPoK<P::G2, P::ScalarField>> {
+    if attrs.len() != issuer_pk.yy2.len() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
     let beta1 = P::ScalarField::random(prng);
     let beta2 = P::ScalarField::random(prng);
     let mut gamma = vec![];
@@ -641,13 +990,13 @@ fn prove_pok<R: CryptoRng + RngCore, P: Pairing>(
     let response_t = challenge.mul(t).add(&beta1); // challente*t + beta1
     let response_sk = challenge.mul(&user_sk.0).add(&beta2);
     let mut response_attrs = vec![];
-    let mut gamma_iter = gamma.iter();
-    for attr_enum in attrs {
-        if let Attribute::Hidden(Some(attr)) = attr_enum {
-            let gamma = gamma_iter.next().unwrap(); // safe unwrap()
-            let resp_attr_i = challenge.mul(attr).add(gamma);
-            response_attrs.push(resp_attr_i);
-        }
+    let hidden_attrs = attrs.iter().filter_map(|attr| match attr {
+        Attribute::Hidden(Some(attr)) => Some(attr),
+        _ => None,
+    });
+    for (attr, gamma_i) in hidden_attrs.zip(gamma.iter()) {
+        let resp_attr_i = challenge.mul(attr).add(gamma_i);
+        response_attrs.push(resp_attr_i);
     }
     Ok(ACPoK {
         commitment,
@@ -658,26 +1007,22 @@ fn prove_pok<R: CryptoRng + RngCore, P: Pairing>(
 }
 
 #[allow(non_snake_case)]
-fn ac_verify_final_check<P: Pairing>(
+fn ac_final_pairing_check<P: Pairing>(
     sig_commitment: &ACCommitment<P::G1>,
     challenge: &P::ScalarField,
     G2: &P::G2,
     p: &P::G2,
-) -> Result<()> {
-    let lhs = P::pairing(&sig_commitment.0.sigma1, p);
-    let rhs = P::pairing(&sig_commitment.0.sigma2.mul(challenge), G2);
-
-    if lhs == rhs {
-        Ok(())
-    } else {
-        Err(eg!(ZeiError::IdentityRevealVerifyError))
-    }
+) -> PairingCheck<P> {
+    PairingCheck::new(
+        vec![(sig_commitment.0.sigma1.clone(), p.clone())],
+        vec![(sig_commitment.0.sigma2.vartime_mul(challenge), G2.clone())],
+    )
 }
 
 #[cfg(test)]
 pub(crate) mod credentials_tests {
     use super::*;
-    use algebra::bls12_381::Bls12381;
+    use algebra::bls12_381::{BLSScalar, Bls12381};
     use rand_chacha::ChaChaRng;
     use rand_core::SeedableRng;
     use rmp_serde::Deserializer;
@@ -786,7 +1131,26 @@ pub(crate) mod credentials_tests {
                 &reveal_sig.pok
             )
             .is_ok()
+        );
+        let pairing_check = pnk!(ac_verify_pairing_check::<P>(
+            &issuer_pk,
+            revealed_attributes.as_slice(),
+            &reveal_sig.sig_commitment,
+            &reveal_sig.pok
+        ));
+        pnk!(pairing_check.verify());
+        pnk!(reveal_sig.validate());
+
+        // the trivial commitment satisfies the pairing equation and is rejected
+        let trivial_commitment = ACCommitment::<P::G1>::default();
+        assert!(trivial_commitment.validate().is_err());
+        assert!(ac_verify::<P>(
+            &issuer_pk,
+            revealed_attributes.as_slice(),
+            &trivial_commitment,
+            &reveal_sig.pok
         )
+        .is_err());
     }
 
     pub fn no_attributes<P: Pairing>() {
@@ -824,6 +1188,176 @@ pub(crate) mod credentials_tests {
         ten_attributes::<Bls12381>();
     }
 
+    #[test]
+    fn test_reveal_more_attributes_than_issuer_key() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let (issuer_pk, issuer_sk) =
+            super::ac_keygen_issuer::<_, Bls12381>(&mut prng, 2);
+        let (user_pk, user_sk) =
+            super::ac_user_key_gen::<_, Bls12381>(&mut prng, &issuer_pk);
+        let mut attrs = vec![BLSScalar::from_u32(1), BLSScalar::from_u32(2)];
+        let signature = pnk!(super::ac_sign::<_, Bls12381>(
+            &mut prng, &issuer_sk, &user_pk, &attrs
+        ));
+        attrs.push(BLSScalar::from_u32(3));
+        let credential = Credential {
+            signature,
+            attributes: attrs,
+            issuer_pub_key: issuer_pk,
+        };
+        msg_eq!(
+            ZeiError::ParameterError,
+            super::ac_reveal::<_, Bls12381>(
+                &mut prng,
+                &user_sk,
+                &credential,
+                &[false, false, false]
+            )
+            .unwrap_err()
+        );
+    }
+
+    fn blind_issuance<P: Pairing>(reveal_bitmap: &[bool]) {
+        let n = reveal_bitmap.len();
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let (issuer_pk, issuer_sk) = super::ac_keygen_issuer::<_, P>(&mut prng, n);
+        let blind_key = super::ac_blind_issuance_key::<P>(&issuer_sk);
+        let (user_pk, user_sk) = super::ac_user_key_gen::<_, P>(&mut prng, &issuer_pk);
+        let attrs = (0..n)
+            .map(|_| P::ScalarField::random(&mut prng))
+            .collect_vec();
+        let issuer_attrs = attrs
+            .iter()
+            .zip(reveal_bitmap.iter())
+            .map(|(a, b)| {
+                if *b {
+                    Attribute::Revealed(*a)
+                } else {
+                    Attribute::Hidden(None)
+                }
+            })
+            .collect_vec();
+
+        let (request, blinding_key) = super::ac_blind_issue_request::<_, P>(
+            &mut prng,
+            &issuer_pk,
+            &blind_key,
+            &user_sk,
+            &attrs,
+            reveal_bitmap,
+        )
+        .unwrap();
+        let blind_sig = super::ac_issuer_blind_sign::<_, P>(
+            &mut prng,
+            &issuer_sk,
+            &issuer_pk,
+            &user_pk,
+            &issuer_attrs,
+            &request,
+        )
+        .unwrap();
+        let credential = super::ac_unblind_credential::<P>(
+            &issuer_pk,
+            &user_sk,
+            &blind_sig,
+            &blinding_key,
+            attrs.clone(),
+        )
+        .unwrap();
+
+        // the credential is the one of ac_sign
+        let reveal_sig =
+            super::ac_reveal::<_, P>(&mut prng, &user_sk, &credential, reveal_bitmap)
+                .unwrap();
+        assert!(ac_verify::<P>(
+            &issuer_pk,
+            &issuer_attrs,
+            &reveal_sig.sig_commitment,
+            &reveal_sig.pok
+        )
+        .is_ok());
+
+        // the signature is not valid for other attributes or another blinding key
+        if n > 0 {
+            let mut other_attrs = attrs.clone();
+            other_attrs[0] = other_attrs[0].add(&P::ScalarField::from_u32(1));
+            assert!(super::ac_unblind_credential::<P>(
+                &issuer_pk,
+                &user_sk,
+                &blind_sig,
+                &blinding_key,
+                other_attrs,
+            )
+            .is_err());
+        }
+        assert!(super::ac_unblind_credential::<P>(
+            &issuer_pk,
+            &user_sk,
+            &blind_sig,
+            &ACBlindingKey(P::ScalarField::random(&mut prng)),
+            attrs.clone(),
+        )
+        .is_err());
+
+        // the request is rejected for another user or other revealed attributes
+        let (other_user_pk, _) = super::ac_user_key_gen::<_, P>(&mut prng, &issuer_pk);
+        assert!(super::ac_issuer_blind_sign::<_, P>(
+            &mut prng,
+            &issuer_sk,
+            &issuer_pk,
+            &other_user_pk,
+            &issuer_attrs,
+            &request,
+        )
+        .is_err());
+        if let Some(i) = reveal_bitmap.iter().position(|b| *b) {
+            let mut other_issuer_attrs = issuer_attrs.clone();
+            other_issuer_attrs[i] = Attribute::Hidden(None);
+            assert!(super::ac_issuer_blind_sign::<_, P>(
+                &mut prng,
+                &issuer_sk,
+                &issuer_pk,
+                &user_pk,
+                &other_issuer_attrs,
+                &request,
+            )
+            .is_err());
+        }
+        // the user rejects elements that do not match the issuer public key
+        let (other_issuer_pk, other_issuer_sk) =
+            super::ac_keygen_issuer::<_, P>(&mut prng, n);
+        let other_blind_key = super::ac_blind_issuance_key::<P>(&other_issuer_sk);
+        if n > 0 {
+            assert!(super::ac_blind_issue_request::<_, P>(
+                &mut prng,
+                &issuer_pk,
+                &other_blind_key,
+                &user_sk,
+                &attrs,
+                reveal_bitmap,
+            )
+            .is_err());
+        }
+        assert!(super::ac_blind_issue_request::<_, P>(
+            &mut prng,
+            &other_issuer_pk,
+            &other_blind_key,
+            &user_sk,
+            &attrs,
+            reveal_bitmap,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_blind_issuance() {
+        blind_issuance::<Bls12381>(&[]);
+        blind_issuance::<Bls12381>(&[false]);
+        blind_issuance::<Bls12381>(&[true]);
+        blind_issuance::<Bls12381>(&[true, false, false, true]);
+        blind_issuance::<Bls12381>(&[false; 4]);
+    }
+
     pub fn to_json_credential_structures<P: Pairing>() {
         let mut prng: ChaChaRng;
         prng = ChaChaRng::from_seed([0u8; 32]);
@@ -1,4 +1,4 @@
-use algebra::groups::Group;
+use algebra::groups::{Group, Scalar};
 use algebra::ristretto::RistrettoPoint;
 use digest::Digest;
 use itertools::Itertools;
@@ -46,6 +46,113 @@ impl<G: Group> PedersenGens<G> {
         // we use naive multi exp it gives us constant time, and we don't lose when |values| is small
         Ok(G::naive_multi_exp(scalars, bases))
     }
+
+    /// commit to a single value, requires bases for commitments of size 1
+    pub fn commit_opening(
+        &self,
+        opening: &PedersenOpening<G::S>,
+    ) -> Result<PedersenCommitment<G>> {
+        self.commit(&[opening.value], &opening.blinding)
+            .map(PedersenCommitment)
+            .c(d!())
+    }
+
+    /// checks that `opening` opens `commitment`
+    pub fn verify_opening(
+        &self,
+        commitment: &PedersenCommitment<G>,
+        opening: &PedersenOpening<G::S>,
+    ) -> Result<()> {
+        if self.commit_opening(opening).c(d!())? == *commitment {
+            Ok(())
+        } else {
+            Err(eg!(ZeiError::CommitmentVerificationError))
+        }
+    }
+}
+
+/// Pedersen commitment value * B + blinding * B_blinding to a single value.
+/// Commitments are additively homomorphic: the sum of the commitments to (value1, blinding1)
+/// and (value2, blinding2) is the commitment to (value1 + value2, blinding1 + blinding2).
+/// The openings are tracked with the same operations on PedersenOpening.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PedersenCommitment<G>(pub G);
+
+impl<G: Group> PedersenCommitment<G> {
+    /// Returns the commitment to 0 with blinding 0
+    pub fn identity() -> Self {
+        PedersenCommitment(G::get_identity())
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        PedersenCommitment(self.0.add(&other.0))
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        PedersenCommitment(self.0.sub(&other.0))
+    }
+
+    pub fn mul(&self, scalar: &G::S) -> Self {
+        PedersenCommitment(self.0.mul(scalar))
+    }
+
+    pub fn sum(commitments: &[Self]) -> Self {
+        commitments
+            .iter()
+            .fold(Self::identity(), |acc, commitment| acc.add(commitment))
+    }
+}
+
+/// Opening of a PedersenCommitment: the committed value and the blinding
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PedersenOpening<S> {
+    pub value: S,
+    pub blinding: S,
+}
+
+impl<S: Scalar> PedersenOpening<S> {
+    pub fn new(value: S, blinding: S) -> Self {
+        PedersenOpening { value, blinding }
+    }
+
+    /// Returns the opening of PedersenCommitment::identity()
+    pub fn zero() -> Self {
+        PedersenOpening {
+            value: S::from_u32(0),
+            blinding: S::from_u32(0),
+        }
+    }
+
+    /// Returns the opening of the sum of the commitments opened by `self` and `other`
+    pub fn add(&self, other: &Self) -> Self {
+        PedersenOpening {
+            value: self.value.add(&other.value),
+            blinding: self.blinding.add(&other.blinding),
+        }
+    }
+
+    /// Returns the opening of the difference of the commitments opened by `self` and `other`
+    pub fn sub(&self, other: &Self) -> Self {
+        PedersenOpening {
+            value: self.value.sub(&other.value),
+            blinding: self.blinding.sub(&other.blinding),
+        }
+    }
+
+    /// Returns the opening of the commitment opened by `self` multiplied by `scalar`
+    pub fn mul(&self, scalar: &S) -> Self {
+        PedersenOpening {
+            value: self.value.mul(scalar),
+            blinding: self.blinding.mul(scalar),
+        }
+    }
+
+    /// Returns the opening of the sum of the commitments opened by `openings`
+    pub fn sum(openings: &[Self]) -> Self {
+        openings
+            .iter()
+            .fold(Self::zero(), |acc, opening| acc.add(opening))
+    }
 }
 
 impl From<bulletproofs::PedersenGens> for PedersenGens<RistrettoPoint> {
@@ -58,3 +165,87 @@ impl From<bulletproofs::PedersenGens> for PedersenGens<RistrettoPoint> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{PedersenCommitment, PedersenGens, PedersenOpening};
+    use crate::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
+    use algebra::bls12_381::BLSG1;
+    use algebra::groups::{Group, Scalar};
+    use algebra::jubjub::JubjubPoint;
+    use algebra::ristretto::{RistrettoPoint, RistrettoScalar};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn homomorphic_operations<G: Group>() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let gens = PedersenGens::<G>::new(1);
+        let opening = |value: u32, prng: &mut ChaChaRng| {
+            PedersenOpening::new(G::S::from_u32(value), G::S::random(prng))
+        };
+
+        // balance of a transfer: the inputs minus the outputs commit to 0
+        let inputs = vec![opening(70, &mut prng), opening(30, &mut prng)];
+        let outputs = vec![
+            opening(55, &mut prng),
+            opening(40, &mut prng),
+            opening(5, &mut prng),
+        ];
+        let commit_all =
+            |openings: &[PedersenOpening<G::S>]| -> Vec<PedersenCommitment<G>> {
+                openings
+                    .iter()
+                    .map(|o| pnk!(gens.commit_opening(o)))
+                    .collect()
+            };
+        let balance = PedersenCommitment::sum(&commit_all(&inputs))
+            .sub(&PedersenCommitment::sum(&commit_all(&outputs)));
+        let balance_opening =
+            PedersenOpening::sum(&inputs).sub(&PedersenOpening::sum(&outputs));
+        assert_eq!(balance_opening.value, G::S::from_u32(0));
+        pnk!(gens.verify_opening(&balance, &balance_opening));
+
+        let doubled = balance.add(&balance).mul(&G::S::from_u32(3));
+        let doubled_opening = balance_opening
+            .add(&balance_opening)
+            .mul(&G::S::from_u32(3));
+        pnk!(gens.verify_opening(&doubled, &doubled_opening));
+
+        assert_eq!(
+            gens.commit_opening(&PedersenOpening::zero()).unwrap(),
+            PedersenCommitment::identity()
+        );
+        assert!(gens
+            .verify_opening(&balance, &PedersenOpening::sum(&inputs))
+            .is_err());
+        // bases for vector commitments do not commit to openings of a single value
+        assert!(PedersenGens::<G>::new(2)
+            .commit_opening(&balance_opening)
+            .is_err());
+    }
+
+    #[test]
+    fn homomorphic() {
+        homomorphic_operations::<RistrettoPoint>();
+        homomorphic_operations::<BLSG1>();
+        homomorphic_operations::<JubjubPoint>();
+    }
+
+    #[test]
+    fn ristretto_opening() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pc_gens = RistrettoPedersenGens::default();
+        let a = PedersenOpening::new(
+            RistrettoScalar::from_u32(10),
+            RistrettoScalar::random(&mut prng),
+        );
+        let b = PedersenOpening::new(
+            RistrettoScalar::from_u32(20),
+            RistrettoScalar::random(&mut prng),
+        );
+        let sum = pc_gens.commit_opening(&a).add(&pc_gens.commit_opening(&b));
+        pnk!(pc_gens.verify_opening(&sum, &a.add(&b)));
+        assert!(pc_gens.verify_opening(&sum, &a).is_err());
+    }
+}
@@ -1,6 +1,9 @@
+use crate::basics::commitments::pedersen::{PedersenCommitment, PedersenOpening};
 use algebra::ristretto::{RistrettoPoint, RistrettoScalar};
 use bulletproofs::PedersenGens;
 use curve25519_dalek::traits::MultiscalarMul;
+use ruc::*;
+use utils::errors::ZeiError;
 
 #[allow(non_snake_case)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -31,6 +34,26 @@ impl RistrettoPedersenGens {
             ),
         )
     }
+
+    pub fn commit_opening(
+        &self,
+        opening: &PedersenOpening<RistrettoScalar>,
+    ) -> PedersenCommitment<RistrettoPoint> {
+        PedersenCommitment(self.commit(opening.value, opening.blinding))
+    }
+
+    /// checks that `opening` opens `commitment`
+    pub fn verify_opening(
+        &self,
+        commitment: &PedersenCommitment<RistrettoPoint>,
+        opening: &PedersenOpening<RistrettoScalar>,
+    ) -> Result<()> {
+        if self.commit_opening(opening) == *commitment {
+            Ok(())
+        } else {
+            Err(eg!(ZeiError::CommitmentVerificationError))
+        }
+    }
 }
 
 impl From<PedersenGens> for RistrettoPedersenGens {
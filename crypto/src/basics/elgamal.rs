@@ -1,10 +1,13 @@
 use crate::basics::hash::rescue::{RescueCtr, RescueInstance};
+use crate::sigma::{sigma_prove, sigma_verify, SigmaProof};
 use algebra::bls12_381::{BLSScalar, BLS_SCALAR_LEN};
 use algebra::groups::{Group, GroupArithmetic, Scalar};
 use algebra::jubjub::{JubjubPoint, JubjubScalar};
 use algebra::ristretto::RistrettoPoint;
+use merlin::Transcript;
 use rand_core::{CryptoRng, RngCore};
 use ruc::*;
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use utils::errors::ZeiError;
 use utils::serialization::ZeiFromToBytes;
@@ -39,6 +42,66 @@ pub struct ElGamalCiphertext<G> {
     pub e2: G, //m*G + r*PK
 }
 
+/// ElGamal encryption on the exponent is additively homomorphic: the sum of encryptions of
+/// m1 and m2 under the same key, with randomness r1 and r2, is an encryption of m1 + m2 with
+/// randomness r1 + r2. Hence encrypted values can be aggregated before a single decryption,
+/// as long as the aggregated value stays in the range of the brute force decryption.
+impl<G: Group> ElGamalCiphertext<G> {
+    /// Returns the encryption of 0 with randomness 0
+    pub fn identity() -> Self {
+        ElGamalCiphertext {
+            e1: G::get_identity(),
+            e2: G::get_identity(),
+        }
+    }
+
+    /// Returns an encryption of the sum of the values encrypted by `self` and `other`
+    pub fn add(&self, other: &Self) -> Self {
+        ElGamalCiphertext {
+            e1: self.e1.add(&other.e1),
+            e2: self.e2.add(&other.e2),
+        }
+    }
+
+    /// Returns an encryption of the difference of the values encrypted by `self` and `other`
+    pub fn sub(&self, other: &Self) -> Self {
+        ElGamalCiphertext {
+            e1: self.e1.sub(&other.e1),
+            e2: self.e2.sub(&other.e2),
+        }
+    }
+
+    /// Returns an encryption of the value encrypted by `self` multiplied by `scalar`
+    pub fn mul(&self, scalar: &G::S) -> Self {
+        ElGamalCiphertext {
+            e1: self.e1.mul(scalar),
+            e2: self.e2.mul(scalar),
+        }
+    }
+
+    /// Returns an encryption of the sum of the values encrypted by `ctexts`
+    pub fn sum(ctexts: &[Self]) -> Self {
+        ctexts
+            .iter()
+            .fold(Self::identity(), |acc, ctext| acc.add(ctext))
+    }
+
+    /// Returns an encryption under `pub_key` of the value encrypted by `self`, with
+    /// `randomness` added to the encryption randomness. The new ciphertext is unlinkable to
+    /// `self` when `randomness` is uniformly random.
+    pub fn rerandomize(
+        &self,
+        base: &G,
+        pub_key: &ElGamalEncKey<G>,
+        randomness: &G::S,
+    ) -> Self {
+        ElGamalCiphertext {
+            e1: self.e1.add(&base.mul(randomness)),
+            e2: self.e2.add(&pub_key.0.mul(randomness)),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ElGamalHybridCiphertext<G, S> {
     pub e1: G,              // r*G
@@ -59,6 +122,9 @@ impl ZeiFromToBytes for ElGamalCiphertext<RistrettoPoint> {
         v
     }
     fn zei_from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 2 * RistrettoPoint::COMPRESSED_LEN {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
         let e1 = RistrettoPoint::from_compressed_bytes(
             &bytes[0..RistrettoPoint::COMPRESSED_LEN],
         )
@@ -81,6 +147,11 @@ impl ZeiFromToBytes for ElGamalHybridCiphertext<JubjubPoint, BLSScalar> {
         v
     }
     fn zei_from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < JubjubPoint::COMPRESSED_LEN
+            || (bytes.len() - JubjubPoint::COMPRESSED_LEN) % BLS_SCALAR_LEN != 0
+        {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
         let e1 = JubjubPoint::zei_from_bytes(&bytes[0..JubjubPoint::COMPRESSED_LEN])
             .c(d!(ZeiError::DeserializationError))?;
         let mut pos = JubjubPoint::COMPRESSED_LEN;
@@ -233,6 +304,128 @@ fn brute_force<G: Group>(
     Err(eg!(ZeiError::ElGamalDecryptionError))
 }
 
+/// I decrypt an ElGamal ciphertext on the exponent in the range [0..upper_bound] with the
+/// baby-step giant-step algorithm, in O(sqrt(upper_bound)) group operations and memory instead of
+/// the O(upper_bound) group operations of elgamal_decrypt_hinted. It suits large plaintexts,
+/// e.g. the sums of many 32-bit values.
+/// Return ZeiError::ElGamalDecryptionError if value is not in the range.
+pub fn elgamal_decrypt_bsgs<G: Group>(
+    base: &G,
+    ctext: &ElGamalCiphertext<G>,
+    sec_key: &ElGamalDecKey<G::S>,
+    upper_bound: u64,
+) -> Result<u64> {
+    let encoded = elgamal_decrypt_elem(ctext, sec_key);
+    baby_step_giant_step::<G>(base, &encoded, upper_bound).c(d!())
+}
+
+fn baby_step_giant_step<G: Group>(
+    base: &G,
+    encoded: &G,
+    upper_bound: u64,
+) -> Result<u64> {
+    // every value in [0..upper_bound] is i * m + j for i, j < m = ceil(sqrt(upper_bound + 1))
+    let mut m = ((upper_bound as f64).sqrt() as u64).max(1);
+    while m
+        .checked_mul(m)
+        .map_or(false, |square| square <= upper_bound)
+    {
+        m += 1;
+    }
+    let mut baby_steps = HashMap::new();
+    let mut step = G::get_identity();
+    for j in 0..m {
+        baby_steps.entry(step.to_compressed_bytes()).or_insert(j);
+        step = step.add(base);
+    }
+    // step is now m * base
+    let mut giant_step = encoded.clone();
+    for i in 0..m {
+        if let Some(j) = baby_steps.get(&giant_step.to_compressed_bytes()) {
+            let value = i * m + j;
+            if value <= upper_bound {
+                return Ok(value);
+            }
+            break;
+        }
+        giant_step = giant_step.sub(&step);
+    }
+    Err(eg!(ZeiError::ElGamalDecryptionError))
+}
+
+// Statement of a decryption proof: pub_key = sk * base and ctext.e2 - m * base = sk * ctext.e1
+// for each ciphertext, as the elements, lhs matrix and rhs vector of a sigma protocol
+fn decryption_statement<G: Group>(
+    base: &G,
+    pub_key: &ElGamalEncKey<G>,
+    ctexts: &[ElGamalCiphertext<G>],
+    ms: &[G::S],
+) -> (Vec<G>, Vec<Vec<usize>>, Vec<usize>) {
+    let mut elems = vec![base.clone(), pub_key.0.clone()];
+    let mut lhs_matrix = vec![vec![0]];
+    let mut rhs_vec = vec![1];
+    for (ctext, m) in ctexts.iter().zip(ms.iter()) {
+        lhs_matrix.push(vec![elems.len()]);
+        rhs_vec.push(elems.len() + 1);
+        elems.push(ctext.e1.clone());
+        elems.push(ctext.e2.sub(&base.mul(m)));
+    }
+    (elems, lhs_matrix, rhs_vec)
+}
+
+/// I compute a proof that `ctexts` encrypt the values `ms` on the exponent under `pub_key`,
+/// without revealing the secret key. It proves the knowledge of sk such that
+/// pub_key = sk * base and ctext.e2 - m * base = sk * ctext.e1 for each ciphertext.
+/// Return ZeiError::ParameterError if some ciphertext does not encrypt its value.
+pub fn elgamal_prove_decryption<R: CryptoRng + RngCore, G: Group>(
+    transcript: &mut Transcript,
+    prng: &mut R,
+    base: &G,
+    pub_key: &ElGamalEncKey<G>,
+    ctexts: &[ElGamalCiphertext<G>],
+    ms: &[G::S],
+    sec_key: &ElGamalDecKey<G::S>,
+) -> Result<SigmaProof<G::S, G>> {
+    if ctexts.len() != ms.len() || base.mul(&sec_key.0) != pub_key.0 {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    for (ctext, m) in ctexts.iter().zip(ms.iter()) {
+        elgamal_verify(base, m, ctext, sec_key).c(d!(ZeiError::ParameterError))?;
+    }
+    let (elems, lhs_matrix, _) = decryption_statement(base, pub_key, ctexts, ms);
+    let elems_refs: Vec<&G> = elems.iter().collect();
+    Ok(sigma_prove(
+        transcript,
+        prng,
+        &elems_refs,
+        &lhs_matrix,
+        &[&sec_key.0],
+    ))
+}
+
+/// I verify a proof that `ctexts` encrypt the values `ms` on the exponent under `pub_key`
+/// Return ZeiError::ZKProofVerificationError if the proof is not valid.
+pub fn elgamal_verify_decryption<R: CryptoRng + RngCore, G: Group>(
+    transcript: &mut Transcript,
+    prng: &mut R,
+    base: &G,
+    pub_key: &ElGamalEncKey<G>,
+    ctexts: &[ElGamalCiphertext<G>],
+    ms: &[G::S],
+    proof: &SigmaProof<G::S, G>,
+) -> Result<()> {
+    if ctexts.len() != ms.len()
+        || proof.commitments.len() != ctexts.len() + 1
+        || proof.responses.len() != 1
+    {
+        return Err(eg!(ZeiError::ZKProofVerificationError));
+    }
+    let (elems, lhs_matrix, rhs_vec) = decryption_statement(base, pub_key, ctexts, ms);
+    let elems_refs: Vec<&G> = elems.iter().collect();
+    sigma_verify(transcript, prng, &elems_refs, &lhs_matrix, &rhs_vec, proof)
+        .c(d!(ZeiError::ZKProofVerificationError))
+}
+
 #[cfg(test)]
 mod elgamal_test {
     use crate::basics::elgamal::{
@@ -242,6 +435,7 @@ mod elgamal_test {
     use algebra::groups::{Group, Scalar};
     use algebra::jubjub::{JubjubPoint, JubjubScalar};
     use algebra::ristretto::RistrettoPoint;
+    use merlin::Transcript;
     use rand_chacha::ChaChaRng;
     use rand_core::SeedableRng;
     use rmp_serde::Deserializer;
@@ -249,6 +443,7 @@ mod elgamal_test {
     use serde::de::Deserialize;
     use serde::ser::Serialize;
     use utils::errors::ZeiError;
+    use utils::serialization::ZeiFromToBytes;
 
     fn verification<G: Group>() {
         let mut prng = ChaChaRng::from_seed([0u8; 32]);
@@ -376,6 +571,194 @@ mod elgamal_test {
         assert_eq!(ctext, ctext_de);
     }
 
+    fn homomorphic_operations<G: Group>() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let base = G::get_base();
+        let (secret_key, public_key) = super::elgamal_key_gen::<_, G>(&mut prng, &base);
+        let encrypt = |m: u32, prng: &mut ChaChaRng| {
+            let r = G::S::random(prng);
+            super::elgamal_encrypt(&base, &G::S::from_u32(m), &r, &public_key)
+        };
+
+        // e.g. daily amounts aggregated before decryption
+        let amounts = [100u32, 250, 37, 0, 13];
+        let ctexts: Vec<ElGamalCiphertext<G>> =
+            amounts.iter().map(|m| encrypt(*m, &mut prng)).collect();
+        let total = ElGamalCiphertext::sum(&ctexts);
+        assert_eq!(
+            super::elgamal_decrypt_hinted(&base, &total, &secret_key, 0, 1000).unwrap(),
+            400
+        );
+        assert_eq!(
+            ElGamalCiphertext::<G>::sum(&[]),
+            ElGamalCiphertext::identity()
+        );
+
+        let sum = ctexts[0].add(&ctexts[1]);
+        pnk!(super::elgamal_verify(
+            &base,
+            &G::S::from_u32(350),
+            &sum,
+            &secret_key
+        ));
+        let diff = ctexts[1].sub(&ctexts[0]);
+        pnk!(super::elgamal_verify(
+            &base,
+            &G::S::from_u32(150),
+            &diff,
+            &secret_key
+        ));
+        let tripled = ctexts[2].mul(&G::S::from_u32(3));
+        pnk!(super::elgamal_verify(
+            &base,
+            &G::S::from_u32(111),
+            &tripled,
+            &secret_key
+        ));
+
+        let randomness = G::S::random(&mut prng);
+        let rerandomized = ctexts[0].rerandomize(&base, &public_key, &randomness);
+        assert_ne!(rerandomized, ctexts[0]);
+        pnk!(super::elgamal_verify(
+            &base,
+            &G::S::from_u32(100),
+            &rerandomized,
+            &secret_key
+        ));
+    }
+
+    fn bsgs_decryption<G: Group>() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let base = G::get_base();
+        let (secret_key, public_key) = super::elgamal_key_gen::<_, G>(&mut prng, &base);
+        let encrypt = |m: u64, prng: &mut ChaChaRng| {
+            let r = G::S::random(prng);
+            super::elgamal_encrypt(&base, &G::S::from_u64(m), &r, &public_key)
+        };
+
+        for (m, upper_bound) in
+            [(0u64, 0u64), (0, 10), (99, 100), (100, 100), (1000, 5000)]
+        {
+            let ctext = encrypt(m, &mut prng);
+            assert_eq!(
+                pnk!(super::elgamal_decrypt_bsgs(
+                    &base,
+                    &ctext,
+                    &secret_key,
+                    upper_bound
+                )),
+                m
+            );
+        }
+        // values beyond the range of the brute force decryption
+        let m = (1u64 << 32) + 12345;
+        let ctext = encrypt(m, &mut prng);
+        assert_eq!(
+            pnk!(super::elgamal_decrypt_bsgs(
+                &base,
+                &ctext,
+                &secret_key,
+                1 << 33
+            )),
+            m
+        );
+
+        let ctext = encrypt(101, &mut prng);
+        let err = super::elgamal_decrypt_bsgs(&base, &ctext, &secret_key, 100)
+            .err()
+            .unwrap();
+        msg_eq!(ZeiError::ElGamalDecryptionError, err);
+    }
+
+    fn decryption_proofs<G: Group>() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let base = G::get_base();
+        let (secret_key, public_key) = super::elgamal_key_gen::<_, G>(&mut prng, &base);
+        let ms: Vec<G::S> = [7u32, 0, 1 << 20]
+            .iter()
+            .map(|m| G::S::from_u32(*m))
+            .collect();
+        let ctexts: Vec<ElGamalCiphertext<G>> = ms
+            .iter()
+            .map(|m| {
+                super::elgamal_encrypt(&base, m, &G::S::random(&mut prng), &public_key)
+            })
+            .collect();
+
+        let proof = pnk!(super::elgamal_prove_decryption(
+            &mut Transcript::new(b"test"),
+            &mut prng,
+            &base,
+            &public_key,
+            &ctexts,
+            &ms,
+            &secret_key
+        ));
+        pnk!(super::elgamal_verify_decryption(
+            &mut Transcript::new(b"test"),
+            &mut prng,
+            &base,
+            &public_key,
+            &ctexts,
+            &ms,
+            &proof
+        ));
+
+        let mut wrong_ms = ms.clone();
+        wrong_ms[1] = G::S::from_u32(1);
+        assert!(super::elgamal_verify_decryption(
+            &mut Transcript::new(b"test"),
+            &mut prng,
+            &base,
+            &public_key,
+            &ctexts,
+            &wrong_ms,
+            &proof
+        )
+        .is_err());
+        assert!(super::elgamal_verify_decryption(
+            &mut Transcript::new(b"test"),
+            &mut prng,
+            &base,
+            &public_key,
+            &ctexts[1..],
+            &ms[1..],
+            &proof
+        )
+        .is_err());
+        // the prover cannot prove a wrong decryption
+        assert!(super::elgamal_prove_decryption(
+            &mut Transcript::new(b"test"),
+            &mut prng,
+            &base,
+            &public_key,
+            &ctexts,
+            &wrong_ms,
+            &secret_key
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn decrypt_bsgs() {
+        bsgs_decryption::<RistrettoPoint>();
+        bsgs_decryption::<JubjubPoint>();
+    }
+
+    #[test]
+    fn prove_decryption() {
+        decryption_proofs::<RistrettoPoint>();
+        decryption_proofs::<BLSG1>();
+        decryption_proofs::<JubjubPoint>();
+    }
+
+    #[test]
+    fn homomorphic() {
+        homomorphic_operations::<RistrettoPoint>();
+        homomorphic_operations::<BLSG1>();
+        homomorphic_operations::<JubjubPoint>();
+    }
+
     #[test]
     fn verify() {
         verification::<RistrettoPoint>();
@@ -451,5 +834,15 @@ mod elgamal_test {
         let ctext_de: ElGamalHybridCiphertext<JubjubPoint, BLSScalar> =
             Deserialize::deserialize(&mut de).unwrap();
         assert_eq!(ctext, ctext_de);
+
+        // truncated encodings are rejected
+        type HybridCiphertext = ElGamalHybridCiphertext<JubjubPoint, BLSScalar>;
+        let bytes = ctext.zei_to_bytes();
+        pnk!(HybridCiphertext::zei_from_bytes(&bytes));
+        assert!(HybridCiphertext::zei_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(HybridCiphertext::zei_from_bytes(
+            &bytes[..JubjubPoint::COMPRESSED_LEN - 1]
+        )
+        .is_err());
     }
 }
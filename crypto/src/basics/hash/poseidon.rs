@@ -0,0 +1,72 @@
+// This file implements the Poseidon permutation, see https://eprint.iacr.org/2019/458.
+// It provides a generic algorithm for any parameters, and a specific instance for the BLS12_381
+// scalar field in poseidon_bls12_381.rs.
+// Instance parameters are obtained from the reference implementation
+// https://extgit.iaik.tugraz.at/krypto/hadeshash: the round constants and the MDS matrix are
+// generated by the Grain LFSR of generate_parameters_grain.sage.
+
+// Poseidon algorithm, on a state of `width` field elements:
+// 1. Execute `full_rounds / 2` full rounds, `partial_rounds` partial rounds, and
+//    `full_rounds / 2` full rounds, where a round r is:
+//   - state = state + round_constants[r]
+//   - state = S-box(state) for a full round, where S-box(x_1,...,x_m) = (x_1^\alpha, ..., x_m^\alpha),
+//     and state_1 = state_1^\alpha for a partial round
+//   - state = MDS * state
+// 2. return the state
+use algebra::groups::Scalar;
+
+#[allow(non_snake_case)]
+pub struct PoseidonInstance<S> {
+    pub MDS: Vec<Vec<S>>,             // width * width matrix
+    pub round_constants: Vec<Vec<S>>, // one width vector per round
+    pub width: usize,
+    pub alpha: u64,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+}
+
+pub type PoseidonState<S> = Vec<S>;
+
+impl<S: Scalar> PoseidonInstance<S> {
+    pub fn num_rounds(&self) -> usize {
+        self.full_rounds + self.partial_rounds
+    }
+
+    pub fn state_size(&self) -> usize {
+        self.width
+    }
+
+    /// Returns true if round `round` applies the S-box to the whole state
+    pub fn is_full_round(&self, round: usize) -> bool {
+        round < self.full_rounds / 2
+            || round >= self.full_rounds / 2 + self.partial_rounds
+    }
+
+    /// Compute the Poseidon permutation of `input`
+    pub fn permutation(&self, input: &[S]) -> PoseidonState<S> {
+        assert_eq!(input.len(), self.state_size());
+        let mut state = input.to_vec();
+        for (r, round_constants) in self.round_constants.iter().enumerate() {
+            for (elem, constant) in state.iter_mut().zip(round_constants.iter()) {
+                *elem = elem.add(constant);
+            }
+            if self.is_full_round(r) {
+                for elem in state.iter_mut() {
+                    *elem = elem.pow(&[self.alpha]);
+                }
+            } else {
+                state[0] = state[0].pow(&[self.alpha]);
+            }
+            state = self
+                .MDS
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .zip(state.iter())
+                        .fold(S::from_u32(0), |sum, (m, elem)| sum.add(&m.mul(elem)))
+                })
+                .collect();
+        }
+        state
+    }
+}
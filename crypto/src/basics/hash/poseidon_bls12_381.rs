@@ -0,0 +1,298 @@
+use super::poseidon::PoseidonInstance;
+use algebra::bls12_381::BLSScalar;
+use std::str::FromStr;
+
+// instance poseidonperm_x5_255_3 of the reference implementation
+// state size
+const WIDTH: usize = 3;
+// # of full rounds
+const FULL_ROUNDS: usize = 8;
+// # of partial rounds
+const PARTIAL_ROUNDS: usize = 57;
+const ALPHA: u64 = 5;
+
+// MDS matrix
+const MDS: [[&str; WIDTH]; WIDTH] = [
+    [
+        "27854988750630959170337239780597144027224715023811960992659706878268355039181",
+        "25146695260744508059100624982461970690166157722474767565243652164077487269055",
+        "20045359041216123667749848881863965260443684681509271093016182932435520519586",
+    ],
+    [
+        "14489116502293865465195620705098702569149962166993518933952339786917836503875",
+        "13125423966940654332711887575940116829944663267413330181877013057693186361539",
+        "37781904496949962127477230973432217892379931214289750852498713884075794707207",
+    ],
+    [
+        "13626913895298938265545264952401615832299228269982032679076937571883280705196",
+        "1961062001717124873779753860369853658060849384038305407377314938662537282272",
+        "39178371364179396693874733819376491076633720395229958100530484864695867731796",
+    ],
+];
+
+// the round constants of round r are ROUND_CONSTANTS[WIDTH * r..WIDTH * (r + 1)]
+const ROUND_CONSTANTS: [&str; WIDTH * (FULL_ROUNDS + PARTIAL_ROUNDS)] = [
+    "48991097081732275468845314168021420565497297775988823234113406403095118809216",
+    "38385660029618165285848698857635215143135976511856402182142757680787979296154",
+    "45664917788634056160947231182803089169570746657219074370482409200042991921246",
+    "46611823467219910333349433978991031443945697128435279755908258896090196676828",
+    "21239555800391983336673016232252577145979304597102502292785557024177155115319",
+    "5444549814002252718699361548642546874417220826495496552290417094191494299797",
+    "6120941817780228594851185625662354154126315032538247033968198498911791651970",
+    "23268934541565483112488314239282439244757346303484537549209002605218913236536",
+    "34778900561716047730386110499058136122597669775051061603711724688203374984731",
+    "11866412958831620887953860204795878894545618212709331023611019011793447488176",
+    "1292810553955081089139103033821163176614817808018762694232693357405135340213",
+    "29829440149074940820671559824872937980763748927491238614065138142835318453671",
+    "43007325278312980663982452106946226844964622384017700838855297379677047113384",
+    "6207852559847946300667836829798951848361581084433525098597857899536657157132",
+    "51263844854419207560514475863120683772532929850629546992690510884221364990253",
+    "47537207485065031976374469967696134772574834313568026823983918780308518394040",
+    "2221931791899303960239149702171682649773262449196140787838362753706579104592",
+    "39456839086017037141295863080128693714705835125922448198802062180577619415688",
+    "7307684192235537965831376311417883513796535701244096178785218530839409056523",
+    "40363790847223872255995860144037894400158879326818322790255787884037990480527",
+    "46370977865329511267956842930057959446221524060145738210680245530954549945015",
+    "31963375456062604704511762940421329756212766442452555529101241339674782334039",
+    "14931035994999669353073307088521670981122374648927581516990615825314462827897",
+    "9146050314741225622437907700594105481623623087635695897868792721147700541623",
+    "43028866523328004770172322384235815492694573248368601737155468843525625413279",
+    "10642771813466087799681476709295362996886361934733270333728358675267521442184",
+    "26204626472182247586446753357603232226235570940686295317661191583409532523578",
+    "51764778305842182544341507127328333397682018984536762517144144495830254727692",
+    "46323013798997081811959707047808149003166619133464450127989691277775183404349",
+    "5482714761779403197336605367697000529513289823583027739458069397684408687717",
+    "12801259943830582826718901632357112368256632783422449824889858551937326401170",
+    "24705221370028061177410670936487461711735994635988936070623351799675117594850",
+    "34818354068777339891091714877681898548352650337240481539567373888981659308099",
+    "35437981511765462742605234803376772682840664204821301764084738573774616215109",
+    "1433523918194521021731556457516832465819757187635645935518277720319249889445",
+    "1786444825311968572352002116054188762971225383128313206702203805257523693888",
+    "22232073076796622550494050910209988454596433174206874696362037700514082492276",
+    "24042430109235922611027968831657325520072553641473321784508698720854180658031",
+    "45406805567398680921065452923276055166961588153660261520529196040913487916279",
+    "35053262861048825411061280559553895536192334830763062477277235807515959383150",
+    "25108964803188800737437394246442073858261740146181095550988111856238954490309",
+    "35192650141137106058577418514209092904214762437910434967540336800650620041958",
+    "34220944794619662782589792809938215078980533657269200933482014763836254210880",
+    "39884393792242132075258602070541114557272278571033974158755307717930033808078",
+    "6528627567246138898338135471584665860403024864125846353758054588554049365178",
+    "26135348890537017135058266369936506677345001674530050056494732502158573534651",
+    "45940975099728729872716617510434185869788979733816569378448209603957649084497",
+    "15421094974171181812057105309783852016087843260648209913425190920580878315912",
+    "17821536801502538623431403481143359660601434134694528982404802873816360858943",
+    "8010729838943058740614807905113741378835761166137481371357965047712306801123",
+    "18699215163509883263304393673283276029620709331747651039747044003384506899917",
+    "37045787943638220002917633921716309877792707850558591835874081145770158399128",
+    "21575637935417645110089037900895429146838845113516284564671508366546944971174",
+    "1788789771738709712587591109966362080868778924904243569200231458308784197447",
+    "31893695366599021197812621371715665903315747385247436549810717167321695484766",
+    "51153400179598348220410722401172031495931771158209082356586940118519763307990",
+    "27065341612806387486757726552834268222391812301897865130062594135449450311205",
+    "21631377794423816098233500204394685009343254816615902551641496756763638503963",
+    "48126155452550090941025807356211843589751116110477652511672279566428926247148",
+    "41945332685105951593851845839403181725987901258063429769257339995392450728766",
+    "24296067579767080403247766323431204628341605710487447431323947636125286730412",
+    "15881178462681378844988252603563609691162651204658664856493588769950563205407",
+    "33027381395215663927148306470841421013404116814305740800948949823021554274098",
+    "39278310473084767209787340524936392884387815060990743323143945308386189000820",
+    "36914830105593239127583246606078015086694578878061417360363710472659792271157",
+    "2471481831227881021689006198592503194795082772689986463565415296171852015386",
+    "10133170919569185596470854926690039229735632740212998846069400800395437949818",
+    "13713875128407368240685505357662717227751490836079655538057610707920043576169",
+    "8342666644640774986634432327796294683569398370446186977217700283927741456745",
+    "46601389125814748868096111624907238097032545985765609175268428943258314495300",
+    "20955390743109511563797223108807741951396100480021156649651505770632943438749",
+    "30784566406743698397200754777301033281231860349200935908047757137616877875074",
+    "48343196439030272896030042717039190414055291776286919553358305329065060244544",
+    "5454630884154432785537568532823077194524789618913833351503828005963129645447",
+    "5929264687259766357446095238429932392315604113095822327000589827415320983004",
+    "22075444908821639097706881947036304396835729534515628434816919715415538390017",
+    "25941058816975140552446994550948593572939163972016393579803457030200129476973",
+    "39776348414428957147819346902864822521632016599308432283712625663034427240337",
+    "7416720880414633042939600412231360970614004283597614937824398530497243499212",
+    "27759512177446113435859126093069895419463054324674208616122176370583357562941",
+    "2693390255841122228782459820336527344026453452088174693463152401174043438469",
+    "50367239350666539482528955684311280608817276753868085587890812549436189586564",
+    "16174733649048109460569124327899128868049112853807486992529031028618670502840",
+    "25032516686620026063532769674876936116496163673410980298313095252836905833243",
+    "29144403930621998939944109351403497411548441156029659945515675350299265094466",
+    "2003270776024057925128728348175382837282431082428047352264694823915738934597",
+    "33363216671247018657387321397537436143187354110057266627888117938607035196831",
+    "20203086474546098412356910533884833744816739556295954278635367853784856438617",
+    "42960220771318412318176969631346524408076008158165832346168142557674200614679",
+    "6311431299350400649257553117850994107778654765725553469026713480041524237057",
+    "20356164198757608998824195662812920762417225019317083164408248459556033087792",
+    "50934696509775059306730966013034554090787668615778167832259926621090584698298",
+    "12540543785093585171832085015032615168496292565469198040103631290639480719638",
+    "7087832377964131545651220267742883342179930832350845193376391176592931716961",
+    "34984411233898940973869087861225504483500912780307024595154545196097892807889",
+    "35766364158306764887416108757297765472332147961010533956614913565935878448984",
+    "1765971701998656161486995693692800538505518481763639488010072221442068236951",
+    "52296260704967533238281867983484652098827616020272035805695017707768629021210",
+    "4935673489774322197628160742241883723281125866438378640636969542959380659457",
+    "49493374663267588751846054378343301708694531580092984346087290317742537210902",
+    "11234520985865325412206403291118519753189986845681526796638090446788348697652",
+    "24240566602759984788029880030276085623682320979885122363103446030346976862554",
+    "45173673056688650486124798353267048676515652881324846851443098010775612892322",
+    "273339079894952168974065527137723282564095652951909656957160946114792896627",
+    "4470325051640351957976738782642661997153601739638632363210829100051811744274",
+    "35146154431885107533179241729875580217482204780231937987130147605583867466092",
+    "5623976303155942456710618286519758761204923686926813378548021075733755166889",
+    "24016465951530015578209275233668961482322584131459513288081598210134015257997",
+    "17969920097176891022415687639709999939084490545645205326481661860931808113029",
+    "45152206508674411747856285000257938228137174933577379726580072509850619926251",
+    "38945634795250927360607537392732805897873100986379288027606175928019977509609",
+    "32851666289693613044889283133849490343674968726730793059165429991055922454070",
+    "31944620853700630151347751910587969550223781655480776781612692884058563662268",
+    "25256966274452535017610572446887439115046074651331211781708168773655007778872",
+    "9486939021502590608732001628331695421223550406038486802197261945175668785507",
+    "39459143086960362426927505137137876218390935544236059938922871880000296175208",
+    "31894450224048346260322339655447950546670422421242715439734122749915296243605",
+    "26892539091318428420931225040417651442139701587930804697886023619431558542747",
+    "2542844944718735302766446637202404427628413878092734865912744553984157161261",
+    "31883859221346313107414474846252752604992097590133961842848913019073014153010",
+    "51303361359653464050006771537341226976539604964205923399469614564706008834052",
+    "51171387502764330562774849667033034283056080450385872897204773223645085369254",
+    "7237091576916241695047293084522141336268656276386088021954481852199921973216",
+    "25026554458962841467968682601680143746537618788336396538569095145280445662154",
+    "16003513886762983460717836271035484656754723355114772159990269505739759600774",
+    "20742179979178809796122395691368538694837598010689782796398715701486525085958",
+    "44785832974715571208383539748048195425158621451201620091409304675643540484444",
+    "40997683756979855969631370242290487603852436449608298499325558394715696204831",
+    "24039577999618876159836452559464600377553684696598310542830185648570694947325",
+    "214991500380221402745874275507138825943309188151683861156767017258335759518",
+    "37648944229324812379904445632193391903358473357814505256571234492472677352375",
+    "33262001091080721927187326829375441597312853742311915461357184164050334176171",
+    "12889759088432190033171086881844675377815686311282488955569491035800531227592",
+    "38889970121432469903433846063190552781925277874128916432889442865031400486457",
+    "9686759546395317438502700818478291413888291261781927399197594299119600593872",
+    "25228839869827315437841994432860023863461613471517457235105091951188556007171",
+    "29251067411858749210993269168637503659802522399342640488863629751155422442084",
+    "40912660681512278236165911366927220401330409827994264103091984300131586078341",
+    "12796501909444494709088656380507035418412240267936921974592450125220369752821",
+    "41489997591227135571666436387925119767986380278590920811343183082128452793080",
+    "21497862265009693334292006570547451455021214638930393134366176167326805799325",
+    "42759488993366187559528022270353477068325476435317366129099617149236057994173",
+    "51812786435352958751631482409057671996557140765865434087196139886155873550638",
+    "49668984917578993057336571483567900930503120626539459296975328351727319861276",
+    "16647828498038646540925328826301561929374469486623027976723819473821480409681",
+    "48148303340548214354795067112758174231010308760482898449349672592745234924387",
+    "40514099213939369482769058963482609316155051560990264349668700968914554718236",
+    "36567947302783543506732234132138195442155777559454242003814702099955749246290",
+    "22396816925035795192842094319757131771178499933587237012855640944068186589937",
+    "47761479716265566311036142819261705369735044145214592608213591050556455450430",
+    "13277094590686127307617107451297268367321013828763858520220510028318248040673",
+    "6273610774394348396010704017556554992266752629801490457323912355626787108751",
+    "47394279615623798760617602748864924711531390489909756029248999925570450315302",
+    "27952252793623580780344613559829677253211432925530630621608481053048520434744",
+    "1683222943011658234228486862639342402730538635204883039431226239924268835592",
+    "6849709550515639669397513895396396226183305237153796793058311861850242817732",
+    "51524350017816629912679960748295545024593637560633508281874724597080573807830",
+    "26590614177194547630006347843068513496427790322854759433492355517360208924714",
+    "31548830001396651725711310298465958490865636855427227043617585502978053092924",
+    "14291568473806392803367440164088272381690062239638560607879858528716058147676",
+    "21146452903160991922099734199583866923318964586815062550024895407430164358523",
+    "22961005724583382013438450487662047962072123198815308647967555251332825175693",
+    "4752908842318626074338926279870993084957055641402767877988223199262408017438",
+    "41544523600430331260332604149473035199994864893327747257504064038791086157408",
+    "17323878296591859990733132832893641096022161936583121997952997880406237212813",
+    "18014582744613086697405046476881081314871698927785490238333612330034405321202",
+    "45325447140824171211209633262297712878556500592023247082629492785769121758434",
+    "6192753434333002929210820794040779560623421075700800400752599138519650269040",
+    "12937001546279985738495952624875312380127801527837660882855310431015537184413",
+    "45991618799696924909840068913271150748052998998510820293768267349781597832497",
+    "37441188106719457933929221474454571110916912448355945524409576665808556247872",
+    "49875923679586708113406579244909793162425404239213510953269412337363307325571",
+    "15051465698071304017966667797323113094420513709580063806706433232853573089040",
+    "10338905189138871748742400929101717755982978259187828256039071250817040249017",
+    "40261933448177008341539991920645739011692467645144896682394869561245899318641",
+    "38346498339252184147870281431364733631809877281747451440216067081256241485418",
+    "6209216396715641040468803949857167055175110420218294975303260728579180870134",
+    "25923422290512595808420551575642237631007497169886590851128840338102194873726",
+    "11953618934086915505672657493115697182858104796786340137294500949047339928290",
+    "48506710952023206646326838201389789459004051035511888474426942257560405427104",
+    "49584811575438811511092715559885015474424100729555178730940640525393341823572",
+    "25222528947373923151054372702664425173210441980263130389325557963853429239320",
+    "36212452941316997504575803214309342413443151488267891949906815090453746563323",
+    "19548334171603533109137618032918088438321356008712800140019849908969476369140",
+    "13369714008256347363334888026585995433724817786797528430136744458743428376798",
+    "23153174875441426069922538845839074574095797738892298576581895020444392853731",
+    "19950632315767750645780485212179021291844439659606854957365124208057044477001",
+    "4990085320684307481424051057758258811192003289472239932032551966513564492664",
+    "29810043862384409261569733347989054089853302964778668946432779952952625186706",
+    "10937492441648375945337911315608624372433158520395209903090712138844575570844",
+    "24981706249730491732129119057314109520549309496394969130105355950186024721860",
+    "10498082524469215029826843019306692952360905490979497919767209022386939911216",
+    "15682375221169428458922809183562392617423770660027773228464622792081026981791",
+    "41914385147673242564111169184735297479310144571630342213035237856939024640011",
+    "39667818743665708661866396692813914317148400284941420155363896112617842800421",
+];
+
+impl Default for PoseidonInstance<BLSScalar> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PoseidonInstance<BLSScalar> {
+    pub fn new() -> Self {
+        Self {
+            MDS: MDS
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|elem| BLSScalar::from_str(elem).unwrap())
+                        .collect()
+                })
+                .collect(),
+            round_constants: ROUND_CONSTANTS
+                .chunks(WIDTH)
+                .map(|constants| {
+                    constants
+                        .iter()
+                        .map(|elem| BLSScalar::from_str(elem).unwrap())
+                        .collect()
+                })
+                .collect(),
+            width: WIDTH,
+            alpha: ALPHA,
+            full_rounds: FULL_ROUNDS,
+            partial_rounds: PARTIAL_ROUNDS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::basics::hash::poseidon::PoseidonInstance;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::Scalar;
+    use std::str::FromStr;
+
+    // test vector of poseidonperm_x5_255_3 in the reference implementation
+    const OUT0: &str =
+        "18456658763349757341014058622209659766100673761449600566550821987295786346378";
+    const OUT1: &str =
+        "37068251774887509885063625701815026138353041152735229476479055620962268601796";
+    const OUT2: &str =
+        "26763157702141528937904191329664859174584798817251788852101947537759678822298";
+
+    #[test]
+    fn test_poseidon_permutation() {
+        let poseidon = PoseidonInstance::new();
+        assert_eq!(poseidon.num_rounds(), 65);
+        assert_eq!(poseidon.round_constants.len(), 65);
+        let input = [
+            BLSScalar::from_u32(0),
+            BLSScalar::from_u32(1),
+            BLSScalar::from_u32(2),
+        ];
+        let expected_output = vec![
+            BLSScalar::from_str(OUT0).unwrap(),
+            BLSScalar::from_str(OUT1).unwrap(),
+            BLSScalar::from_str(OUT2).unwrap(),
+        ];
+        assert_eq!(poseidon.permutation(&input), expected_output);
+    }
+}
@@ -29,6 +29,8 @@
 //   - K_r = instance.M * S-box(K_r') + key_injection_r, used in second step of round r
 use algebra::groups::Scalar;
 use itertools::Itertools;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 #[allow(non_snake_case)]
 pub struct RescueInstance<S> {
@@ -130,6 +132,27 @@ impl<S: Scalar> RescueInstance<S> {
         self.rescue(input, &key)
     }
 
+    /// Compute the digest of an arbitrary number of elements with the rescue sponge:
+    /// `elems`, padded with a one and zeroes up to a multiple of `rate`, are added `rate` at a
+    /// time to the first elements of the state, which starts at zero and is permuted after each
+    /// block. The last `capacity` elements of the state never absorb an input. The digest is
+    /// the first element of the last state.
+    pub fn rescue_sponge_digest(&self, elems: &[S]) -> S {
+        let round_keys = self.hash_init();
+        let mut padded = elems.to_vec();
+        padded.push(S::from_u32(1));
+        let n_zeroes = (self.rate - padded.len() % self.rate) % self.rate;
+        padded.extend(vec![S::from_u32(0); n_zeroes]);
+        let mut state = vec![S::from_u32(0); self.state_size()];
+        for block in padded.chunks(self.rate) {
+            for (state_i, elem) in state.iter_mut().zip(block) {
+                *state_i = state_i.add(elem);
+            }
+            state = self.rescue_with_round_keys(&state, &round_keys);
+        }
+        state[0]
+    }
+
     /// Compute RESCUE permutation sampling the rounds' keys online
     pub fn rescue(&self, input: &[S], key: &[S]) -> RescueState<S> {
         assert_eq!(input.len(), self.state_size());
@@ -205,6 +228,38 @@ impl<S: Scalar> RescueInstance<S> {
     }
 }
 
+// Bulk hashing. Rescue is far slower than SHA-2, so bulk workloads such as building or
+// rebuilding a Merkle tree, or computing many note commitments, are dominated by it. The hash
+// round keys are computed once, and the hashes on multiple threads with the `parallel` feature.
+impl<S: Scalar + Send + Sync> RescueInstance<S> {
+    /// Computes the rescue hash of each element of `inputs`, each input being a full state.
+    /// The result is the same as calling rescue_hash on each input.
+    pub fn rescue_hash_batch(&self, inputs: &[RescueState<S>]) -> Vec<RescueState<S>> {
+        let round_keys = self.hash_init();
+        #[cfg(feature = "parallel")]
+        let iter = inputs.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let iter = inputs.iter();
+        iter.map(|input| self.rescue_with_round_keys(input, &round_keys))
+            .collect()
+    }
+
+    /// Splits `elems` in chunks of `rate` elements, the last one padded with zeroes, and returns
+    /// the first element of the rescue hash of each chunk padded with `capacity` zeroes.
+    /// E.g. the nodes of a level of a Merkle tree of arity `rate` are the chunked hash of the
+    /// level below.
+    pub fn rescue_hash_chunks(&self, elems: &[S]) -> Vec<S> {
+        let inputs: Vec<RescueState<S>> = elems
+            .chunks(self.rate)
+            .map(|chunk| self.pad_input_to_state_size(chunk))
+            .collect();
+        self.rescue_hash_batch(&inputs)
+            .into_iter()
+            .map(|state| state[0])
+            .collect()
+    }
+}
+
 /// A counter mode encryption based on Rescue block ciphers.
 /// * `round_keys`: the round keys determined by the input secret key.
 /// * `nonce`: a counter.
@@ -200,7 +200,7 @@ impl RescueCtr<BLSScalar> {
 mod test {
     use crate::basics::hash::rescue::{RescueCtr, RescueInstance};
     use algebra::bls12_381::BLSScalar;
-    use algebra::groups::Scalar;
+    use algebra::groups::{Scalar, ScalarArithmetic};
     use rand_chacha::ChaChaRng;
     use rand_core::SeedableRng;
     use std::str::FromStr;
@@ -343,6 +343,79 @@ mod test {
         assert_eq!(hash_state2, expected_output);
     }
 
+    #[test]
+    fn test_rescue_hash_batch() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let hash = RescueInstance::<BLSScalar>::new();
+        let zero = BLSScalar::from_u32(0);
+        let inputs: Vec<Vec<BLSScalar>> = (0..10)
+            .map(|_| {
+                (0..hash.state_size())
+                    .map(|_| BLSScalar::random(&mut prng))
+                    .collect()
+            })
+            .collect();
+        let hashes = hash.rescue_hash_batch(&inputs);
+        assert_eq!(hashes.len(), inputs.len());
+        for (input, state) in inputs.iter().zip(hashes.iter()) {
+            assert_eq!(state, &hash.rescue_hash(input));
+        }
+        assert!(hash.rescue_hash_batch(&[]).is_empty());
+
+        // chunks of `rate` elements, the last one padded with zeroes
+        let elems: Vec<BLSScalar> = (0..3 * hash.rate + 2)
+            .map(|_| BLSScalar::random(&mut prng))
+            .collect();
+        let hashes = hash.rescue_hash_chunks(&elems);
+        assert_eq!(hashes.len(), 4);
+        for (chunk, h) in elems.chunks(hash.rate).zip(hashes.iter()) {
+            let mut input = chunk.to_vec();
+            input.resize(hash.state_size(), zero);
+            assert_eq!(*h, hash.rescue_hash(&input)[0]);
+        }
+    }
+
+    #[test]
+    fn test_rescue_sponge_digest() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let hash = RescueInstance::<BLSScalar>::new();
+        let zero = BLSScalar::from_u32(0);
+        let one = BLSScalar::from_u32(1);
+        let elems: Vec<BLSScalar> =
+            (0..7).map(|_| BLSScalar::random(&mut prng)).collect();
+
+        // a single block is a single hash
+        let digest = hash.rescue_sponge_digest(&elems[..2]);
+        assert_eq!(
+            digest,
+            hash.rescue_hash(&[elems[0], elems[1], one, zero])[0]
+        );
+        let digest = hash.rescue_sponge_digest(&[]);
+        assert_eq!(digest, hash.rescue_hash(&[one, zero, zero, zero])[0]);
+
+        // the blocks are added to the rate part of the state
+        let state = hash.rescue_hash(&[elems[0], elems[1], elems[2], zero]);
+        let state = hash.rescue_hash(&[
+            state[0].add(&elems[3]),
+            state[1].add(&one),
+            state[2],
+            state[3],
+        ]);
+        assert_eq!(hash.rescue_sponge_digest(&elems[..4]), state[0]);
+
+        // messages with trailing zeroes have different digests
+        let mut digests = vec![];
+        for n in 0..elems.len() {
+            let mut msg = elems[..n].to_vec();
+            digests.push(hash.rescue_sponge_digest(&msg));
+            msg.push(zero);
+            digests.push(hash.rescue_sponge_digest(&msg));
+        }
+        for (i, digest) in digests.iter().enumerate() {
+            assert!(!digests[i + 1..].contains(digest));
+        }
+    }
+
     #[test]
     fn test_rescue_ctr() {
         let mut prng = ChaChaRng::from_seed([0u8; 32]);
@@ -247,7 +247,7 @@ fn symmetric_key_from_secret_key(
     symmetric_key_from_x25519_secret_key(&x_secret, ephemeral_public_key)
 }
 
-fn symmetric_encrypt_fresh_key(key: &[u8; 32], plaintext: &[u8]) -> Ctext {
+pub(crate) fn symmetric_encrypt_fresh_key(key: &[u8; 32], plaintext: &[u8]) -> Ctext {
     let kkey = GenericArray::from_slice(key);
     let ctr = GenericArray::from_slice(&[0u8; 16]); // counter can be zero because key is fresh
     let mut ctext_vec = plaintext.to_vec();
@@ -256,7 +256,7 @@ fn symmetric_encrypt_fresh_key(key: &[u8; 32], plaintext: &[u8]) -> Ctext {
     Ctext(ctext_vec)
 }
 
-fn symmetric_decrypt_fresh_key(key: &[u8; 32], ciphertext: &Ctext) -> Vec<u8> {
+pub(crate) fn symmetric_decrypt_fresh_key(key: &[u8; 32], ciphertext: &Ctext) -> Vec<u8> {
     let kkey = GenericArray::from_slice(key);
     let ctr = GenericArray::from_slice(&[0u8; 16]);
     let mut plaintext_vec = ciphertext.0.clone();
@@ -0,0 +1,93 @@
+// This file implements a keyed hash (MAC) from the rescue sponge-based PRF of prf.rs.
+// The PRF absorbs its inputs without padding, hence (m_0, ..., m_{n-1}) and (m_0, ..., m_{n-1}, 0)
+// have the same PRF value. The MAC pads the messages injectively before evaluating the PRF:
+// MAC^p(key, (m_0, ..., m_{n-1})) = PRF^p(key, (m_0, ..., m_{n-1}, 1, 0, ..., 0)),
+// with as many zeroes as needed for the padded message length to be a multiple of the PRF width.
+// The MAC is suited for messages of variable length, e.g. key derivations from a secret and a
+// context, while the PRF suits messages of a fixed length, e.g. nullifiers.
+// TurboPlonkConstraintSystem::mac computes the same function in circuits.
+use crate::basics::prf::PRF;
+use algebra::bls12_381::BLSScalar;
+use algebra::groups::Scalar;
+use ruc::*;
+use utils::errors::ZeiError;
+
+pub struct MAC<S>(PRF<S>);
+
+impl<S: Scalar> MAC<S> {
+    /// Pads `msgs` with a one and zeroes up to a multiple of the PRF width
+    pub fn pad(&self, msgs: &[S]) -> Vec<S> {
+        let width = self.0.width();
+        let mut padded = msgs.to_vec();
+        padded.push(S::from_u32(1));
+        let n_zeroes = (width - padded.len() % width) % width;
+        padded.extend(vec![S::from_u32(0); n_zeroes]);
+        padded
+    }
+
+    /// Returns the MAC tag of `msgs` under `key`
+    /// * `key` - MAC key
+    /// * `msgs` - the messages to authenticate, possibly empty
+    pub fn tag(&self, key: &S, msgs: &[S]) -> S {
+        self.0.eval(key, &self.pad(msgs))
+    }
+
+    /// Checks the MAC tag of `msgs` under `key`. It returns an error if the tag is not valid.
+    pub fn verify(&self, key: &S, msgs: &[S], tag: &S) -> Result<()> {
+        if self.tag(key, msgs) != *tag {
+            return Err(eg!(ZeiError::MACVerificationError));
+        }
+        Ok(())
+    }
+}
+
+impl Default for MAC<BLSScalar> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MAC<BLSScalar> {
+    pub fn new() -> Self {
+        Self(PRF::new())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::basics::mac::MAC;
+    use crate::basics::prf::PRF;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{Scalar, ScalarArithmetic};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    #[test]
+    fn test_mac() {
+        let mac = MAC::<BLSScalar>::new();
+        let prf = PRF::<BLSScalar>::new();
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let key = BLSScalar::random(&mut prng);
+        let zero = BLSScalar::from_u32(0);
+        let one = BLSScalar::from_u32(1);
+        let msgs: Vec<BLSScalar> =
+            (0..5).map(|_| BLSScalar::random(&mut prng)).collect();
+
+        for n in 0..msgs.len() {
+            let padded = mac.pad(&msgs[..n]);
+            assert_eq!(padded.len() % prf.width(), 0);
+            assert_eq!(padded[n], one);
+            let tag = mac.tag(&key, &msgs[..n]);
+            assert_eq!(tag, prf.eval(&key, &padded));
+            pnk!(mac.verify(&key, &msgs[..n], &tag));
+            // messages with trailing zeroes have different tags
+            let mut extended = msgs[..n].to_vec();
+            extended.push(zero);
+            assert!(mac.verify(&key, &extended, &tag).is_err());
+        }
+        let tag = mac.tag(&key, &msgs);
+        assert!(mac.verify(&one, &msgs, &tag).is_err());
+        assert!(mac.verify(&key, &msgs, &tag.add(&one)).is_err());
+    }
+}
@@ -2,5 +2,8 @@ pub mod commitments;
 pub mod elgamal;
 pub mod hash;
 pub mod hybrid_encryption;
+pub mod mac;
+pub mod multi_recipient_encryption;
+pub mod oprf;
 pub mod prf;
 pub mod signatures;
@@ -0,0 +1,250 @@
+// This file implements encryption of a single payload to many ElGamal keys (e.g. the tracing
+// keys of a multi-authority policy), instead of one independent encryption per key.
+// * The payload is encrypted once with AES256-CTR under a key derived from a random group
+//   element M.
+// * M is wrapped to every key PK_i with multi-recipient ElGamal sharing the randomness r:
+//   the ciphertext holds E = r*G and W_i = M + r*PK_i.
+// * A consistency proof shows that every W_i wraps the same M, that is, knowledge of r such that
+//   E = r*G and W_i - W_0 = r*(PK_i - PK_0) for every i. The proof binds the payload ciphertext,
+//   hence every authority is assured that it decrypts the same payload as the others.
+
+use crate::basics::elgamal::{ElGamalDecKey, ElGamalEncKey};
+use crate::basics::hybrid_encryption::{
+    symmetric_decrypt_fresh_key, symmetric_encrypt_fresh_key, Ctext,
+};
+use crate::sigma::{sigma_prove, sigma_verify, SigmaProof};
+use algebra::groups::{Group, Scalar};
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use sha2::{Digest, Sha256};
+use utils::errors::ZeiError;
+
+const MULTI_RECIPIENT_DOMAIN: &[u8] = b"Zei multi-recipient encryption";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiRecipientCiphertext<G: Group> {
+    pub(crate) ephemeral_key: G,     // r*G
+    pub(crate) wrapped_keys: Vec<G>, // M + r*PK_i
+    pub(crate) ciphertext: Ctext,    // AES256-CTR encryption of the payload under H(M)
+    pub(crate) proof: SigmaProof<G::S, G>,
+}
+
+impl<G: Group> MultiRecipientCiphertext<G> {
+    /// Number of keys the payload is encrypted to
+    pub fn num_recipients(&self) -> usize {
+        self.wrapped_keys.len()
+    }
+}
+
+fn symmetric_key_from_point<G: Group>(point: &G) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(MULTI_RECIPIENT_DOMAIN);
+    hasher.update(point.to_compressed_bytes());
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hasher.finalize());
+    key
+}
+
+fn init_transcript<G: Group>(
+    pub_keys: &[ElGamalEncKey<G>],
+    ciphertext: &Ctext,
+) -> Transcript {
+    let mut transcript = Transcript::new(MULTI_RECIPIENT_DOMAIN);
+    for pk in pub_keys {
+        transcript.append_message(b"public key", &pk.0.to_compressed_bytes());
+    }
+    transcript.append_message(b"ciphertext", &ciphertext.0);
+    transcript
+}
+
+// Returns the statement points: the base, the ephemeral key, and the pairs
+// (PK_i - PK_0, W_i - W_0) for i > 0
+fn consistency_statement<G: Group>(
+    base: &G,
+    pub_keys: &[ElGamalEncKey<G>],
+    ephemeral_key: &G,
+    wrapped_keys: &[G],
+) -> (Vec<G>, Vec<Vec<usize>>, Vec<usize>) {
+    let mut points = vec![base.clone(), ephemeral_key.clone()];
+    let mut lhs_matrix = vec![vec![0]];
+    let mut rhs_vec = vec![1];
+    for (pk, wrapped) in pub_keys.iter().zip(wrapped_keys.iter()).skip(1) {
+        lhs_matrix.push(vec![points.len()]);
+        rhs_vec.push(points.len() + 1);
+        points.push(pk.0.sub(&pub_keys[0].0));
+        points.push(wrapped.sub(&wrapped_keys[0]));
+    }
+    (points, lhs_matrix, rhs_vec)
+}
+
+/// I encrypt `message` once and wrap the encryption key to every key in `pub_keys`, together
+/// with a proof that the same key is wrapped to all of them.
+pub fn multi_recipient_encrypt<R: CryptoRng + RngCore, G: Group>(
+    prng: &mut R,
+    base: &G,
+    pub_keys: &[ElGamalEncKey<G>],
+    message: &[u8],
+) -> Result<MultiRecipientCiphertext<G>> {
+    if pub_keys.is_empty() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let key_point = base.mul(&G::S::random(prng));
+    let ciphertext =
+        symmetric_encrypt_fresh_key(&symmetric_key_from_point(&key_point), message);
+
+    let r = G::S::random(prng);
+    let ephemeral_key = base.mul(&r);
+    let wrapped_keys: Vec<G> = pub_keys
+        .iter()
+        .map(|pk| key_point.add(&pk.0.mul(&r)))
+        .collect();
+
+    let (points, lhs_matrix, _) =
+        consistency_statement(base, pub_keys, &ephemeral_key, &wrapped_keys);
+    let elems: Vec<&G> = points.iter().collect();
+    let mut transcript = init_transcript(pub_keys, &ciphertext);
+    let proof = sigma_prove(&mut transcript, prng, &elems, &lhs_matrix, &[&r]);
+
+    Ok(MultiRecipientCiphertext {
+        ephemeral_key,
+        wrapped_keys,
+        ciphertext,
+        proof,
+    })
+}
+
+/// I verify that `ctext` wraps the same key to every key in `pub_keys`.
+pub fn multi_recipient_verify<R: CryptoRng + RngCore, G: Group>(
+    prng: &mut R,
+    base: &G,
+    pub_keys: &[ElGamalEncKey<G>],
+    ctext: &MultiRecipientCiphertext<G>,
+) -> Result<()> {
+    if pub_keys.is_empty()
+        || pub_keys.len() != ctext.wrapped_keys.len()
+        || ctext.proof.commitments.len() != pub_keys.len()
+    {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let (points, lhs_matrix, rhs_vec) =
+        consistency_statement(base, pub_keys, &ctext.ephemeral_key, &ctext.wrapped_keys);
+    let elems: Vec<&G> = points.iter().collect();
+    let mut transcript = init_transcript(pub_keys, &ctext.ciphertext);
+    sigma_verify(
+        &mut transcript,
+        prng,
+        &elems,
+        &lhs_matrix,
+        &rhs_vec,
+        &ctext.proof,
+    )
+    .c(d!(ZeiError::ZKProofVerificationError))
+}
+
+/// I decrypt `ctext` with the secret key of the `index`-th recipient.
+pub fn multi_recipient_decrypt<G: Group>(
+    ctext: &MultiRecipientCiphertext<G>,
+    index: usize,
+    sec_key: &ElGamalDecKey<G::S>,
+) -> Result<Vec<u8>> {
+    let wrapped = ctext.wrapped_keys.get(index).c(d!(ZeiError::IndexError))?;
+    let key_point = wrapped.sub(&ctext.ephemeral_key.mul(&sec_key.0));
+    Ok(symmetric_decrypt_fresh_key(
+        &symmetric_key_from_point(&key_point),
+        &ctext.ciphertext,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::basics::elgamal::elgamal_key_gen;
+    use crate::basics::multi_recipient_encryption::{
+        multi_recipient_decrypt, multi_recipient_encrypt, multi_recipient_verify,
+        MultiRecipientCiphertext,
+    };
+    use algebra::groups::Group;
+    use algebra::jubjub::JubjubPoint;
+    use algebra::ristretto::RistrettoPoint;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn multi_recipient_encryption<G: Group>() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let base = G::get_base();
+        let mut sec_keys = vec![];
+        let mut pub_keys = vec![];
+        for _ in 0..3 {
+            let (sk, pk) = elgamal_key_gen::<_, G>(&mut prng, &base);
+            sec_keys.push(sk);
+            pub_keys.push(pk);
+        }
+        let message = b"tracing record: amount 100, asset type XYZ";
+
+        let ctext = pnk!(multi_recipient_encrypt(
+            &mut prng, &base, &pub_keys, message
+        ));
+        assert_eq!(ctext.num_recipients(), 3);
+        pnk!(multi_recipient_verify(&mut prng, &base, &pub_keys, &ctext));
+        for (i, sk) in sec_keys.iter().enumerate() {
+            let plaintext = pnk!(multi_recipient_decrypt(&ctext, i, sk));
+            assert_eq!(&plaintext[..], &message[..]);
+        }
+        // wrong key or index
+        let plaintext = pnk!(multi_recipient_decrypt(&ctext, 0, &sec_keys[1]));
+        assert_ne!(&plaintext[..], &message[..]);
+        assert!(multi_recipient_decrypt(&ctext, 3, &sec_keys[0]).is_err());
+
+        // a key wrapped to a single recipient breaks the consistency proof
+        let mut bad_ctext: MultiRecipientCiphertext<G> = ctext.clone();
+        bad_ctext.wrapped_keys[2] = bad_ctext.wrapped_keys[2].add(&base);
+        assert!(
+            multi_recipient_verify(&mut prng, &base, &pub_keys, &bad_ctext).is_err()
+        );
+
+        // the proof binds the payload
+        let mut bad_ctext = ctext.clone();
+        bad_ctext.ciphertext.0[0] ^= 1;
+        assert!(
+            multi_recipient_verify(&mut prng, &base, &pub_keys, &bad_ctext).is_err()
+        );
+
+        // the proof binds the recipients
+        assert!(
+            multi_recipient_verify(&mut prng, &base, &pub_keys[0..2], &ctext).is_err()
+        );
+        let mut other_keys = pub_keys.clone();
+        other_keys.swap(0, 1);
+        assert!(multi_recipient_verify(&mut prng, &base, &other_keys, &ctext).is_err());
+
+        // single recipient
+        let ctext = pnk!(multi_recipient_encrypt(
+            &mut prng,
+            &base,
+            &pub_keys[..1],
+            message
+        ));
+        pnk!(multi_recipient_verify(
+            &mut prng,
+            &base,
+            &pub_keys[..1],
+            &ctext
+        ));
+        let plaintext = pnk!(multi_recipient_decrypt(&ctext, 0, &sec_keys[0]));
+        assert_eq!(&plaintext[..], &message[..]);
+        assert!(
+            multi_recipient_encrypt::<_, G>(&mut prng, &base, &[], message).is_err()
+        );
+    }
+
+    #[test]
+    fn multi_recipient_encryption_ristretto() {
+        multi_recipient_encryption::<RistrettoPoint>();
+    }
+
+    #[test]
+    fn multi_recipient_encryption_jubjub() {
+        multi_recipient_encryption::<JubjubPoint>();
+    }
+}
@@ -0,0 +1,244 @@
+// This file implements a verifiable oblivious PRF (VOPRF) following the 2HashDH construction
+// (https://eprint.iacr.org/2014/650.pdf), in the style of RFC 9497.
+// The server holds a key k with public key PK = k * G, and the PRF is defined as
+//       F_k(x) = H2(x, k * H1(x)),
+// where H1 hashes into the group and H2 into bytes. The client learns F_k(x) without revealing x,
+// and the server does not learn anything about x or F_k(x).
+//
+// Protocol:
+// 1. Client: blind(x) samples r and sends B = r * H1(x).
+// 2. Server: evaluate(k, B) returns E = k * B together with a proof that
+//    log_G(PK) = log_B(E) (Chaum-Pedersen DLEQ). Several blinded elements can be evaluated
+//    with a single proof.
+// 3. Client: finalize verifies the proof, computes N = r^{-1} * E = k * H1(x) and outputs H2(x, N).
+// A server can also compute F_k(x) directly for inputs it knows, e.g. to publish the PRF values
+// of a sanctions list that clients check against without revealing their addresses.
+
+use crate::sigma::{sigma_prove, sigma_verify, SigmaProof};
+use algebra::groups::{Group, Scalar, ScalarArithmetic};
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use sha2::{Digest, Sha256, Sha512};
+use utils::errors::ZeiError;
+
+const OPRF_HASH_TO_GROUP_DOMAIN: &[u8] = b"Zei OPRF hash to group";
+const OPRF_FINALIZE_DOMAIN: &[u8] = b"Zei OPRF finalize";
+const OPRF_PROOF_DOMAIN: &[u8] = b"Zei OPRF evaluation proof";
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OPRFSecretKey<S>(pub(crate) S);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OPRFPublicKey<G>(pub G); // PK = k * G
+
+/// Client state kept between blinding and finalization.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OPRFBlindingState<S> {
+    blind: S,
+    input: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OPRFBlindedElement<G>(pub G);
+
+/// Server response: the evaluated elements and a DLEQ proof binding them to the server key.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OPRFEvaluation<G: Group> {
+    pub evaluated_elements: Vec<G>,
+    pub proof: SigmaProof<G::S, G>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OPRFOutput(pub [u8; 32]);
+
+pub fn oprf_key_gen<R: CryptoRng + RngCore, G: Group>(
+    prng: &mut R,
+) -> (OPRFSecretKey<G::S>, OPRFPublicKey<G>) {
+    let sk = G::S::random(prng);
+    let pk = G::get_base().mul(&sk);
+    (OPRFSecretKey(sk), OPRFPublicKey(pk))
+}
+
+fn hash_to_group<G: Group>(input: &[u8]) -> G {
+    let mut hash = Sha512::new();
+    hash.update(OPRF_HASH_TO_GROUP_DOMAIN);
+    hash.update(input);
+    G::from_hash(hash)
+}
+
+fn finalize_hash<G: Group>(input: &[u8], unblinded: &G) -> OPRFOutput {
+    let mut hash = Sha256::new();
+    hash.update(OPRF_FINALIZE_DOMAIN);
+    hash.update(&(input.len() as u64).to_le_bytes());
+    hash.update(input);
+    hash.update(unblinded.to_compressed_bytes());
+    let mut output = [0u8; 32];
+    output.copy_from_slice(&hash.finalize());
+    OPRFOutput(output)
+}
+
+// DLEQ statement: PK = k * G and E_i = k * B_i for every i
+fn oprf_proof_statement<'a, G: Group>(
+    base: &'a G,
+    pk: &'a OPRFPublicKey<G>,
+    blinded: &'a [OPRFBlindedElement<G>],
+    evaluated: &'a [G],
+) -> (Vec<&'a G>, Vec<Vec<usize>>, Vec<usize>) {
+    let mut elems = vec![base, &pk.0];
+    let mut lhs_matrix = vec![vec![0]];
+    let mut rhs_vec = vec![1];
+    for (b, e) in blinded.iter().zip(evaluated.iter()) {
+        lhs_matrix.push(vec![elems.len()]);
+        rhs_vec.push(elems.len() + 1);
+        elems.push(&b.0);
+        elems.push(e);
+    }
+    (elems, lhs_matrix, rhs_vec)
+}
+
+/// Client: blind `input`, returns the state needed to finalize and the element sent to the server.
+pub fn oprf_blind<R: CryptoRng + RngCore, G: Group>(
+    prng: &mut R,
+    input: &[u8],
+) -> (OPRFBlindingState<G::S>, OPRFBlindedElement<G>) {
+    let mut blind = G::S::random(prng);
+    while blind == G::S::from_u32(0) {
+        blind = G::S::random(prng);
+    }
+    let blinded = hash_to_group::<G>(input).mul(&blind);
+    (
+        OPRFBlindingState {
+            blind,
+            input: input.to_vec(),
+        },
+        OPRFBlindedElement(blinded),
+    )
+}
+
+/// Server: evaluate the PRF on the blinded elements and prove correctness of the evaluation.
+pub fn oprf_evaluate<R: CryptoRng + RngCore, G: Group>(
+    prng: &mut R,
+    sk: &OPRFSecretKey<G::S>,
+    pk: &OPRFPublicKey<G>,
+    blinded: &[OPRFBlindedElement<G>],
+) -> Result<OPRFEvaluation<G>> {
+    if blinded.is_empty() || blinded.iter().any(|b| b.0 == G::get_identity()) {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let evaluated_elements: Vec<G> = blinded.iter().map(|b| b.0.mul(&sk.0)).collect();
+    let base = G::get_base();
+    let (elems, lhs_matrix, _) =
+        oprf_proof_statement(&base, pk, blinded, &evaluated_elements);
+    let mut transcript = Transcript::new(OPRF_PROOF_DOMAIN);
+    let proof = sigma_prove(&mut transcript, prng, &elems, &lhs_matrix, &[&sk.0]);
+    Ok(OPRFEvaluation {
+        evaluated_elements,
+        proof,
+    })
+}
+
+/// Client: verify the server evaluation against `pk` and unblind the PRF outputs.
+pub fn oprf_finalize<R: CryptoRng + RngCore, G: Group>(
+    prng: &mut R,
+    pk: &OPRFPublicKey<G>,
+    states: &[OPRFBlindingState<G::S>],
+    blinded: &[OPRFBlindedElement<G>],
+    evaluation: &OPRFEvaluation<G>,
+) -> Result<Vec<OPRFOutput>> {
+    if states.len() != blinded.len()
+        || evaluation.evaluated_elements.len() != blinded.len()
+    {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let base = G::get_base();
+    let (elems, lhs_matrix, rhs_vec) =
+        oprf_proof_statement(&base, pk, blinded, &evaluation.evaluated_elements);
+    let mut transcript = Transcript::new(OPRF_PROOF_DOMAIN);
+    sigma_verify(
+        &mut transcript,
+        prng,
+        &elems,
+        &lhs_matrix,
+        &rhs_vec,
+        &evaluation.proof,
+    )
+    .c(d!(ZeiError::ZKProofVerificationError))?;
+
+    let mut outputs = Vec::with_capacity(states.len());
+    for (state, evaluated) in states.iter().zip(evaluation.evaluated_elements.iter()) {
+        let blind_inv = state.blind.inv().c(d!(ZeiError::ParameterError))?;
+        outputs.push(finalize_hash(&state.input, &evaluated.mul(&blind_inv)));
+    }
+    Ok(outputs)
+}
+
+/// Server: compute the PRF directly on a known input.
+pub fn oprf_eval_direct<G: Group>(sk: &OPRFSecretKey<G::S>, input: &[u8]) -> OPRFOutput {
+    let unblinded = hash_to_group::<G>(input).mul(&sk.0);
+    finalize_hash(input, &unblinded)
+}
+
+#[cfg(test)]
+mod oprf_test {
+    use crate::basics::oprf::{
+        oprf_blind, oprf_eval_direct, oprf_evaluate, oprf_finalize, oprf_key_gen,
+        OPRFBlindedElement,
+    };
+    use algebra::groups::Group;
+    use algebra::jubjub::JubjubPoint;
+    use algebra::ristretto::RistrettoPoint;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    fn oprf_round_trip<G: Group>() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let (sk, pk) = oprf_key_gen::<_, G>(&mut prng);
+        let inputs: [&[u8]; 3] = [b"address 1", b"address 2", b"address 3"];
+
+        let mut states = vec![];
+        let mut blinded = vec![];
+        for input in inputs.iter() {
+            let (state, b) = oprf_blind::<_, G>(&mut prng, input);
+            states.push(state);
+            blinded.push(b);
+        }
+        let evaluation = oprf_evaluate(&mut prng, &sk, &pk, &blinded).unwrap();
+        let outputs =
+            oprf_finalize(&mut prng, &pk, &states, &blinded, &evaluation).unwrap();
+        for (input, output) in inputs.iter().zip(outputs.iter()) {
+            assert_eq!(output, &oprf_eval_direct::<G>(&sk, input));
+        }
+        assert_ne!(outputs[0], outputs[1]);
+
+        // blinding hides the input: two blindings of the same input differ
+        let (_, b) = oprf_blind::<_, G>(&mut prng, inputs[0]);
+        assert_ne!(b, blinded[0]);
+
+        // a different key gives different outputs, and fails against the first public key
+        let (sk2, pk2) = oprf_key_gen::<_, G>(&mut prng);
+        let evaluation2 = oprf_evaluate(&mut prng, &sk2, &pk2, &blinded).unwrap();
+        assert!(oprf_finalize(&mut prng, &pk, &states, &blinded, &evaluation2).is_err());
+        let outputs2 =
+            oprf_finalize(&mut prng, &pk2, &states, &blinded, &evaluation2).unwrap();
+        assert_ne!(outputs, outputs2);
+
+        // a server that does not use its key is caught
+        let mut bad_evaluation = evaluation.clone();
+        bad_evaluation.evaluated_elements[1] =
+            bad_evaluation.evaluated_elements[1].double();
+        assert!(
+            oprf_finalize(&mut prng, &pk, &states, &blinded, &bad_evaluation).is_err()
+        );
+
+        // the identity is rejected
+        let identity = vec![OPRFBlindedElement(G::get_identity())];
+        assert!(oprf_evaluate(&mut prng, &sk, &pk, &identity).is_err());
+    }
+
+    #[test]
+    fn test_oprf() {
+        oprf_round_trip::<RistrettoPoint>();
+        oprf_round_trip::<JubjubPoint>();
+    }
+}
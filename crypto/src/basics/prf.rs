@@ -9,6 +9,9 @@
 //      2.1 Inject the messages into the state: s := s + (m_{i*w}||...||m_{i*w+w-1})
 //      2.2 Permute the state: s := p(s)
 // 3. Return s_0.
+// The PRF over BLS12-381 uses the parameters of RescueInstance::<BLSScalar>::new(), which are the ones
+// of the Rescue gates of the TurboPLONK constraint system (see TurboPlonkConstraintSystem::prf), so that
+// values computed natively (e.g. nullifiers) match the ones computed in circuits.
 use crate::basics::hash::rescue::RescueInstance;
 use algebra::bls12_381::BLSScalar;
 use algebra::groups::Scalar;
@@ -16,6 +19,11 @@ use algebra::groups::Scalar;
 pub struct PRF<S>(RescueInstance<S>);
 
 impl<S: Scalar> PRF<S> {
+    /// Returns the number of messages absorbed by each permutation
+    pub fn width(&self) -> usize {
+        self.0.state_size()
+    }
+
     /// PRF evaluation without padding
     /// * `key` - PRF key
     /// * `msgs` - PRF inputs
@@ -0,0 +1,117 @@
+//! # Blind BLS signatures
+//!
+//! This file implements Boldyreva's blind BLS signatures (<https://eprint.iacr.org/2002/118>).
+//! The unblinded signature is a standard BLS signature (see `bls.rs`) verified with `bls_verify`.
+//! * user: sample a random scalar `r` and send `M'=H(m)^r`
+//! * signer: send `S'=M'^x`
+//! * user: check that `e(g1, S') == e(X, M')` and output `S=S'^(1/r)`
+//!
+//! The signer answers a single group element and keeps no state, so the scheme is not subject to
+//! ROS-style attacks on concurrent sessions.
+
+use crate::basics::signatures::bls::{
+    bls_hash_message, BlsPublicKey, BlsSecretKey, BlsSignature,
+};
+use algebra::groups::{Group, GroupArithmetic, Pairing, Scalar, ScalarArithmetic};
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+/// Blinded message sent to the signer
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct BlsBlindedMessage<P: Pairing>(pub P::G2);
+
+/// Signature on a blinded message
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct BlsBlindSignature<P: Pairing>(pub P::G2);
+
+/// User blinding factor, needed to unblind the signature
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct BlsBlindingFactor<P: Pairing>(P::ScalarField);
+
+/// User: blind `message`
+pub fn bls_blind<R: CryptoRng + RngCore, P: Pairing, B: AsRef<[u8]>>(
+    prng: &mut R,
+    message: &B,
+) -> (BlsBlindingFactor<P>, BlsBlindedMessage<P>) {
+    let mut r = P::ScalarField::random(prng);
+    while r == P::ScalarField::from_u32(0) {
+        r = P::ScalarField::random(prng);
+    }
+    let blinded = bls_hash_message::<P>(message.as_ref()).mul(&r);
+    (BlsBlindingFactor(r), BlsBlindedMessage(blinded))
+}
+
+/// Signer: sign a blinded message
+pub fn bls_blind_sign<P: Pairing>(
+    signing_key: &BlsSecretKey<P>,
+    blinded: &BlsBlindedMessage<P>,
+) -> Result<BlsBlindSignature<P>> {
+    if blinded.0 == P::G2::get_identity() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    Ok(BlsBlindSignature(blinded.0.mul(&signing_key.0)))
+}
+
+/// User: check the blind signature against the blinded message and unblind it
+pub fn bls_unblind<P: Pairing>(
+    ver_key: &BlsPublicKey<P>,
+    factor: &BlsBlindingFactor<P>,
+    blinded: &BlsBlindedMessage<P>,
+    blind_signature: &BlsBlindSignature<P>,
+) -> Result<BlsSignature<P>> {
+    let a = P::pairing(&P::G1::get_base(), &blind_signature.0);
+    let b = P::pairing(&ver_key.0, &blinded.0);
+    if a != b {
+        return Err(eg!(ZeiError::SignatureError));
+    }
+    let r_inv = factor.0.inv().c(d!(ZeiError::ParameterError))?;
+    Ok(BlsSignature(blind_signature.0.mul(&r_inv)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::basics::signatures::bls::{bls_gen_keys, bls_sign, bls_verify};
+    use algebra::bls12_381::Bls12381;
+    use algebra::groups::{Group, GroupArithmetic};
+    use rand_core::SeedableRng;
+    use ruc::*;
+    use utils::errors::ZeiError;
+
+    use super::{bls_blind, bls_blind_sign, bls_unblind, BlsBlindedMessage};
+
+    #[test]
+    fn blind_bls_signatures() {
+        let mut prng = rand_chacha::ChaChaRng::from_seed([1u8; 32]);
+        let (sk, pk) = bls_gen_keys::<_, Bls12381>(&mut prng);
+        let message = b"ecash token";
+
+        let (factor, blinded) = bls_blind::<_, Bls12381, _>(&mut prng, message);
+        let blind_sig = pnk!(bls_blind_sign(&sk, &blinded));
+        let signature = pnk!(bls_unblind(&pk, &factor, &blinded, &blind_sig));
+
+        pnk!(bls_verify(&pk, message, &signature));
+        // BLS signatures are deterministic
+        assert_eq!(signature, bls_sign::<Bls12381, _>(&sk, message));
+        // the blinded message hides the message
+        let (_, blinded2) = bls_blind::<_, Bls12381, _>(&mut prng, message);
+        assert_ne!(blinded, blinded2);
+        msg_eq!(
+            ZeiError::SignatureError,
+            bls_verify(&pk, b"wrong message", &signature).unwrap_err()
+        );
+
+        // a signature from another key is rejected when unblinding
+        let (sk2, _) = bls_gen_keys::<_, Bls12381>(&mut prng);
+        let blind_sig2 = pnk!(bls_blind_sign(&sk2, &blinded));
+        msg_eq!(
+            ZeiError::SignatureError,
+            bls_unblind(&pk, &factor, &blinded, &blind_sig2).unwrap_err()
+        );
+
+        let identity = BlsBlindedMessage::<Bls12381>(Group::get_identity());
+        assert!(bls_blind_sign(&sk, &identity).is_err());
+        let doubled = BlsBlindedMessage::<Bls12381>(blinded.0.double());
+        assert!(bls_unblind(&pk, &factor, &doubled, &blind_sig).is_err());
+    }
+}
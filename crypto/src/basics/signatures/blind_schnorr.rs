@@ -0,0 +1,310 @@
+//! # Blind Schnorr signatures
+//!
+//! This file implements a blind signing protocol that outputs standard Schnorr signatures
+//! (see `schnorr.rs`), so the signed messages are verified with `PublicKey::verify`.
+//!
+//! The plain blind Schnorr protocol works as follows:
+//! * signer: sample `r`, send `R=g^r`
+//! * user: sample blinding factors `a,b`, compute `R'=R.g^a.X^b`, `c'=H(X,R',m)` and send `c=c'+b`
+//! * signer: send `s=r+cx`
+//! * user: output `(R', s'=s+a)`
+//!
+//! The plain protocol is insecure when the signer runs many sessions concurrently: the ROS attack
+//! (<https://eprint.iacr.org/2020/945>) forges `l+1` signatures from `l` parallel sessions.
+//! We implement two defenses:
+//! * Clause blinding (<https://eprint.iacr.org/2019/877>, section 5): the signer sends two
+//!   commitments `R0,R1`, the user blinds both and returns two challenges `c0,c1`, and the signer
+//!   answers only one of them, chosen at random. An ROS solution must now hold for the
+//!   challenges selected by the signer, which the attacker cannot predict.
+//! * Restricted sessions: the signer refuses to open more than `max_open_sessions` concurrent
+//!   sessions, and every session can be answered once.
+
+use crate::basics::signatures::schnorr::{
+    signature_challenge, KeyPair, PublicKey, Signature,
+};
+use algebra::groups::{Group, Scalar, ScalarArithmetic};
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utils::errors::ZeiError;
+
+/// Identifier of a signing session
+pub type SessionId = u64;
+
+/// First message of the signer: the commitments of the two clauses
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct BlindSchnorrCommitments<G> {
+    pub session_id: SessionId,
+    pub R0: G,
+    pub R1: G,
+}
+
+/// Message of the user: the blinded challenges of the two clauses
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindSchnorrChallenges<S> {
+    pub session_id: SessionId,
+    pub c0: S,
+    pub c1: S,
+}
+
+/// Last message of the signer: the response to the selected clause
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindSchnorrResponse<S> {
+    pub session_id: SessionId,
+    pub clause: bool,
+    pub s: S,
+}
+
+/// Signer state, it keeps the nonces of the open sessions.
+pub struct BlindSchnorrSigner<G: Group> {
+    key_pair: KeyPair<G, G::S>,
+    max_open_sessions: usize,
+    next_session_id: SessionId,
+    sessions: HashMap<SessionId, (G::S, G::S)>,
+}
+
+impl<G: Group> BlindSchnorrSigner<G> {
+    /// Create a signer that runs at most `max_open_sessions` sessions concurrently.
+    pub fn new(key_pair: KeyPair<G, G::S>, max_open_sessions: usize) -> Self {
+        BlindSchnorrSigner {
+            key_pair,
+            max_open_sessions,
+            next_session_id: 0,
+            sessions: HashMap::new(),
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey<G> {
+        &self.key_pair.pub_key
+    }
+
+    /// Number of sessions started and not answered yet
+    pub fn open_sessions(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Open a new session and return the signer commitments.
+    pub fn start_session<R: CryptoRng + RngCore>(
+        &mut self,
+        prng: &mut R,
+    ) -> Result<BlindSchnorrCommitments<G>> {
+        if self.sessions.len() >= self.max_open_sessions {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let r0 = G::S::random(prng);
+        let r1 = G::S::random(prng);
+        let g = G::get_base();
+        let session_id = self.next_session_id;
+        self.next_session_id += 1;
+        self.sessions.insert(session_id, (r0, r1));
+        Ok(BlindSchnorrCommitments {
+            session_id,
+            R0: g.mul(&r0),
+            R1: g.mul(&r1),
+        })
+    }
+
+    /// Answer one of the two blinded challenges of an open session, chosen at random.
+    /// The session is closed afterwards.
+    pub fn respond<R: CryptoRng + RngCore>(
+        &mut self,
+        prng: &mut R,
+        challenges: &BlindSchnorrChallenges<G::S>,
+    ) -> Result<BlindSchnorrResponse<G::S>> {
+        let (r0, r1) = self
+            .sessions
+            .remove(&challenges.session_id)
+            .c(d!(ZeiError::ParameterError))?;
+        let clause = prng.next_u32() & 1 == 1;
+        let (r, c) = if clause {
+            (r1, &challenges.c1)
+        } else {
+            (r0, &challenges.c0)
+        };
+        let s = r.add(&c.mul(&self.key_pair.get_secret_scalar()));
+        Ok(BlindSchnorrResponse {
+            session_id: challenges.session_id,
+            clause,
+            s,
+        })
+    }
+
+    /// Drop an open session without answering it.
+    pub fn abort_session(&mut self, session_id: SessionId) -> Result<()> {
+        self.sessions
+            .remove(&session_id)
+            .map(|_| ())
+            .c(d!(ZeiError::ParameterError))
+    }
+}
+
+#[allow(non_snake_case)]
+struct BlindedClause<G: Group> {
+    R: G,
+    challenge: G::S,
+    alpha: G::S,
+}
+
+/// User state of a signing session
+pub struct BlindSchnorrUser<G: Group> {
+    pub_key: PublicKey<G>,
+    msg: Vec<u8>,
+    session_id: SessionId,
+    clauses: [BlindedClause<G>; 2],
+}
+
+impl<G: Group> BlindSchnorrUser<G> {
+    /// Blind both clauses of the signer commitments for message `msg`.
+    /// Returns the user state and the challenges to be sent to the signer.
+    pub fn new<R: CryptoRng + RngCore>(
+        prng: &mut R,
+        pub_key: &PublicKey<G>,
+        msg: &[u8],
+        commitments: &BlindSchnorrCommitments<G>,
+    ) -> (Self, BlindSchnorrChallenges<G::S>) {
+        let clause0 = Self::blind_clause(prng, pub_key, msg, &commitments.R0);
+        let clause1 = Self::blind_clause(prng, pub_key, msg, &commitments.R1);
+        let (c0, c1) = (clause0.1, clause1.1);
+        let user = BlindSchnorrUser {
+            pub_key: pub_key.clone(),
+            msg: msg.to_vec(),
+            session_id: commitments.session_id,
+            clauses: [clause0.0, clause1.0],
+        };
+        (
+            user,
+            BlindSchnorrChallenges {
+                session_id: commitments.session_id,
+                c0,
+                c1,
+            },
+        )
+    }
+
+    // R' = R.g^a.X^b, c' = H(X,R',m), and the blinded challenge c = c' + b
+    #[allow(non_snake_case)]
+    fn blind_clause<R: CryptoRng + RngCore>(
+        prng: &mut R,
+        pub_key: &PublicKey<G>,
+        msg: &[u8],
+        R: &G,
+    ) -> (BlindedClause<G>, G::S) {
+        let alpha = G::S::random(prng);
+        let beta = G::S::random(prng);
+        let blinded_R = R
+            .add(&G::get_base().mul(&alpha))
+            .add(&pub_key.point_ref().mul(&beta));
+        let challenge = signature_challenge(pub_key, msg, &blinded_R);
+        let blinded_challenge = challenge.add(&beta);
+        (
+            BlindedClause {
+                R: blinded_R,
+                challenge,
+                alpha,
+            },
+            blinded_challenge,
+        )
+    }
+
+    /// Unblind the signer response of the selected clause into a Schnorr signature on the
+    /// message, and check it.
+    pub fn finalize(
+        self,
+        response: &BlindSchnorrResponse<G::S>,
+    ) -> Result<Signature<G, G::S>> {
+        if response.session_id != self.session_id {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let clause = &self.clauses[response.clause as usize];
+        let signature = Signature {
+            R: clause.R.clone(),
+            s: response.s.add(&clause.alpha),
+        };
+        debug_assert_eq!(
+            signature_challenge(&self.pub_key, &self.msg, &signature.R),
+            clause.challenge
+        );
+        self.pub_key
+            .verify(&self.msg, &signature)
+            .c(d!(ZeiError::SignatureError))?;
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod blind_schnorr_sigs {
+    use crate::basics::signatures::blind_schnorr::{
+        BlindSchnorrSigner, BlindSchnorrUser,
+    };
+    use crate::basics::signatures::schnorr::KeyPair;
+    use algebra::groups::{Group, ScalarArithmetic};
+    use algebra::jubjub::JubjubPoint;
+    use algebra::ristretto::RistrettoPoint;
+    use rand_chacha::rand_core::SeedableRng;
+
+    fn check_blind_schnorr<G: Group>() {
+        let mut prng = rand_chacha::ChaChaRng::from_seed([0_u8; 32]);
+        let key_pair: KeyPair<G, G::S> = KeyPair::generate(&mut prng);
+        let pub_key = key_pair.pub_key.clone();
+        let mut signer = BlindSchnorrSigner::new(key_pair, 2);
+
+        for _ in 0..10 {
+            let message = b"one-time token";
+            let commitments = signer.start_session(&mut prng).unwrap();
+            let (user, challenges) =
+                BlindSchnorrUser::new(&mut prng, &pub_key, message, &commitments);
+            let response = signer.respond(&mut prng, &challenges).unwrap();
+            let sig = user.finalize(&response).unwrap();
+            assert!(pub_key.verify(message, &sig).is_ok());
+            assert!(pub_key.verify(b"other message", &sig).is_err());
+            // the signature does not reveal the signer commitments
+            assert_ne!(&sig.R, &commitments.R0);
+            assert_ne!(&sig.R, &commitments.R1);
+            assert_eq!(signer.open_sessions(), 0);
+        }
+    }
+
+    fn check_sessions<G: Group>() {
+        let mut prng = rand_chacha::ChaChaRng::from_seed([0_u8; 32]);
+        let key_pair: KeyPair<G, G::S> = KeyPair::generate(&mut prng);
+        let pub_key = key_pair.pub_key.clone();
+        let mut signer = BlindSchnorrSigner::new(key_pair, 2);
+        let message = b"message";
+
+        let commitments1 = signer.start_session(&mut prng).unwrap();
+        let commitments2 = signer.start_session(&mut prng).unwrap();
+        // too many concurrent sessions
+        assert!(signer.start_session(&mut prng).is_err());
+        assert!(signer.abort_session(commitments2.session_id).is_ok());
+        assert!(signer.abort_session(commitments2.session_id).is_err());
+
+        let (user, challenges) =
+            BlindSchnorrUser::new(&mut prng, &pub_key, message, &commitments1);
+        let response = signer.respond(&mut prng, &challenges).unwrap();
+        // a session is answered once
+        assert!(signer.respond(&mut prng, &challenges).is_err());
+
+        // tampered response
+        let mut bad_response = response.clone();
+        bad_response.s = bad_response.s.add(&bad_response.s);
+        let (user2, challenges2) =
+            BlindSchnorrUser::new(&mut prng, &pub_key, message, &commitments1);
+        assert_ne!(challenges, challenges2);
+        assert!(user2.finalize(&bad_response).is_err());
+        assert!(user.finalize(&response).is_ok());
+    }
+
+    #[test]
+    fn blind_schnorr_over_ristretto() {
+        check_blind_schnorr::<RistrettoPoint>();
+        check_sessions::<RistrettoPoint>();
+    }
+
+    #[test]
+    fn blind_schnorr_over_jubjub() {
+        check_blind_schnorr::<JubjubPoint>();
+        check_sessions::<JubjubPoint>();
+    }
+}
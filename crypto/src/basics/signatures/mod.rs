@@ -1,6 +1,8 @@
 use rand_core::{CryptoRng, RngCore};
 use ruc::err::*;
 
+pub mod blind_bls;
+pub mod blind_schnorr;
 pub mod bls;
 pub mod pointcheval_sanders;
 pub mod schnorr;
@@ -153,7 +153,7 @@ pub fn ps_verify_sig_scalar<P: Pairing>(
     m: &P::ScalarField,
     sig: &PSSignature<P::G1>,
 ) -> Result<()> {
-    let a = pk.xx.add(&pk.yy.mul(m));
+    let a = pk.xx.add(&pk.yy.vartime_mul(m));
     let e1 = P::pairing(&sig.s1, &a);
     let e2 = P::pairing(&sig.s2, &P::G2::get_base());
     if e1 != e2 || sig.s1 == P::G1::get_identity() {
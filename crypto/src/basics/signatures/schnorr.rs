@@ -10,6 +10,12 @@
 //! * `key_gen()` => sample a random scalar `x` and compute `X=g^x` where `g` is some group generator. Return return the key pair `(x,X)`
 //! * `sign(m,sk)` => sample a random scalar `r` and compute `R=g^r`. Compute scalars `c=H(X,R,m)` and `s=r+cx`. Return `(R,s)`
 //! * `verify(m,pk,sig)` => parse `sig` as `(R,s)`. Compute `c=H(X,R,m)`. Check that `R.X^c == g^s`.
+//!
+//! Adaptor signatures are supported for conditional transfers (atomic swaps, payment channels):
+//! * `pre_sign(m,sk,T)` => compute `R=g^r`, `c=H(X,R.T,m)` and `s=r+cx`. Return the pre-signature `(R,s)`
+//! * `pre_verify(m,pk,T,pre_sig)` => check that `R.X^c == g^s` with `c=H(X,R.T,m)`
+//! * `adapt(pre_sig,t)` => return the signature `(R.T,s+t)`
+//! * `extract(pre_sig,sig)` => return `t=sig.s-pre_sig.s`
 
 use algebra::groups::{Group, Scalar, ScalarArithmetic};
 use digest::Digest;
@@ -26,8 +32,8 @@ use utils::serialization::ZeiFromToBytes;
 #[allow(non_snake_case)]
 /// A Schnorr signature is composed by some group element R and some scalar s
 pub struct Signature<G, S> {
-    R: G,
-    s: S,
+    pub(crate) R: G,
+    pub(crate) s: S,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -80,6 +86,20 @@ impl<G: Group> PublicKey<G> {
     pub fn verify(&self, msg: &[u8], sign: &Signature<G, G::S>) -> Result<()> {
         verify(self, msg, sign).c(d!())
     }
+
+    /// Verifies an adaptor pre-signature given a message and an adaptor point
+    /// * `msg` - message
+    /// * `adaptor` - adaptor point `T`
+    /// * `pre_sig` - pre-signature
+    /// * `returns` - Nothing if the verification succeeds, an error otherwise
+    pub fn pre_verify(
+        &self,
+        msg: &[u8],
+        adaptor: &G,
+        pre_sig: &PreSignature<G, G::S>,
+    ) -> Result<()> {
+        pre_verify(self, msg, adaptor, pre_sig).c(d!())
+    }
 }
 
 impl<G: Group> ZeiFromToBytes for PublicKey<G> {
@@ -107,6 +127,13 @@ impl<G: Group> KeyPair<G, G::S> {
     pub fn generate<R: CryptoRng + RngCore>(prng: &mut R) -> Self {
         gen_keys(prng)
     }
+    /// Build the keypair of secret key scalar `sec_key`
+    pub fn from_secret_scalar(sec_key: G::S) -> Self {
+        KeyPair {
+            pub_key: PublicKey(G::get_base().mul(&sec_key)),
+            sec_key: SecretKey(sec_key),
+        }
+    }
     /// Return scalar representing secret key
     pub fn get_secret_scalar(&self) -> G::S {
         self.sec_key.scalar()
@@ -118,6 +145,14 @@ impl<G: Group> KeyPair<G, G::S> {
         sign(self, msg)
     }
 
+    /// Computes an adaptor pre-signature for `msg` with respect to the point `adaptor`.
+    /// * `msg` - sequence of bytes to be signed
+    /// * `adaptor` - adaptor point `T`
+    /// * `returns` - a pre-signature that becomes a Schnorr signature given the secret of `T`
+    pub fn pre_sign(&self, msg: &[u8], adaptor: &G) -> PreSignature<G, G::S> {
+        pre_sign(self, msg, adaptor)
+    }
+
     /// Randomize the keypair by `factor`
     pub fn randomize(&self, factor: &G::S) -> Self {
         KeyPair {
@@ -253,6 +288,22 @@ fn deterministic_scalar_gen<G: Group>(
     G::S::from_hash(hasher)
 }
 
+/// Computes the challenge `c=H(X,R,m)` of a signature with commitment `R`.
+/// * `pk` - public key
+/// * `msg` - message
+/// * `R` - signature commitment
+/// * `returns` - the challenge scalar
+#[allow(non_snake_case)]
+pub(crate) fn signature_challenge<G: Group>(
+    pk: &PublicKey<G>,
+    msg: &[u8],
+    R: &G,
+) -> G::S {
+    let mut transcript = Transcript::new(b"schnorr_sig");
+    transcript.update_transcript_with_sig_info::<G>(msg, pk, R);
+    transcript.compute_challenge::<G::S>()
+}
+
 #[allow(clippy::many_single_char_names)]
 #[allow(non_snake_case)]
 /// Computes a signature given a key pair and a message.
@@ -260,8 +311,6 @@ fn deterministic_scalar_gen<G: Group>(
 /// * `message` - sequence of bytes to be signed
 /// * `returns` - a Schnorr signature
 fn sign<G: Group>(signing_key: &KeyPair<G, G::S>, msg: &[u8]) -> Signature<G, G::S> {
-    let mut transcript = Transcript::new(b"schnorr_sig");
-
     let g = G::get_base();
 
     let r = deterministic_scalar_gen::<G>(msg, &signing_key.sec_key);
@@ -269,9 +318,7 @@ fn sign<G: Group>(signing_key: &KeyPair<G, G::S>, msg: &[u8]) -> Signature<G, G:
     let R = g.mul(&r);
     let pk = &signing_key.pub_key;
 
-    transcript.update_transcript_with_sig_info::<G>(msg, pk, &R);
-
-    let c = transcript.compute_challenge::<G::S>();
+    let c = signature_challenge(pk, msg, &R);
 
     let private_key = &(signing_key.sec_key);
     let s: G::S = r.add(&c.mul(&private_key.0));
@@ -306,16 +353,12 @@ fn verify<G: Group>(
     msg: &[u8],
     sig: &Signature<G, G::S>,
 ) -> Result<()> {
-    let mut transcript = Transcript::new(b"schnorr_sig");
-
     let g = G::get_base();
 
-    transcript.update_transcript_with_sig_info(msg, pk, &sig.R);
-
-    let c = transcript.compute_challenge::<G::S>();
+    let c = signature_challenge(pk, msg, &sig.R);
 
-    let left = sig.R.add(&pk.0.mul(&c));
-    let right = g.mul(&sig.s);
+    let left = sig.R.add(&pk.0.vartime_mul(&c));
+    let right = g.vartime_mul(&sig.s);
 
     if left == right {
         Ok(())
@@ -346,6 +389,92 @@ pub fn multisig_verify<G: Group>(
     Ok(())
 }
 
+/// Adaptor pre-signature `(R, s)` on a message with respect to an adaptor point `T=g^t`.
+/// It becomes a valid Schnorr signature `(R.T, s+t)` once the secret `t` is known, and
+/// anyone holding both the pre-signature and the signature can extract `t`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct PreSignature<G, S> {
+    R: G,
+    s: S,
+}
+
+impl<G: Group> PreSignature<G, G::S> {
+    /// Completes the pre-signature into a Schnorr signature using the secret of the
+    /// adaptor point.
+    /// * `adaptor_secret` - discrete logarithm `t` of the adaptor point
+    /// * `returns` - a Schnorr signature
+    pub fn adapt(&self, adaptor_secret: &G::S) -> Signature<G, G::S> {
+        let t_point = G::get_base().mul(adaptor_secret);
+        Signature {
+            R: self.R.add(&t_point),
+            s: self.s.add(adaptor_secret),
+        }
+    }
+
+    /// Extracts the secret of the adaptor point from a signature obtained by adapting
+    /// this pre-signature.
+    /// * `sig` - adapted signature
+    /// * `adaptor` - adaptor point `T`
+    /// * `returns` - the secret `t` or an error if `sig` was not adapted from this pre-signature
+    pub fn extract_secret(&self, sig: &Signature<G, G::S>, adaptor: &G) -> Result<G::S> {
+        let t = sig.s.sub(&self.s);
+        if G::get_base().mul(&t) != *adaptor || self.R.add(adaptor) != sig.R {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(t)
+    }
+}
+
+/// Computes an adaptor pre-signature with respect to the adaptor point `T`.
+/// The commitment `R=g^r` and the scalar `s=r+cx` with `c=H(X,R.T,m)` are such that
+/// `(R.T, s+t)` is a valid signature.
+/// * `signing_key` - key pair. Having both private and public key makes the signature computation more efficient
+/// * `msg` - sequence of bytes to be signed
+/// * `adaptor` - adaptor point `T`
+/// * `returns` - an adaptor pre-signature
+#[allow(non_snake_case)]
+fn pre_sign<G: Group>(
+    signing_key: &KeyPair<G, G::S>,
+    msg: &[u8],
+    adaptor: &G,
+) -> PreSignature<G, G::S> {
+    // the nonce depends on the adaptor point, otherwise two pre-signatures on the same
+    // message with different adaptor points would reveal the secret key
+    let mut nonce_input = b"adaptor".to_vec();
+    nonce_input.extend_from_slice(&adaptor.to_compressed_bytes());
+    nonce_input.extend_from_slice(msg);
+    let r = deterministic_scalar_gen::<G>(&nonce_input, &signing_key.sec_key);
+
+    let R = G::get_base().mul(&r);
+    let c = signature_challenge(&signing_key.pub_key, msg, &R.add(adaptor));
+    let s = r.add(&c.mul(&signing_key.sec_key.0));
+    PreSignature { R, s }
+}
+
+/// Verifies an adaptor pre-signature given a message, a public key and an adaptor point,
+/// that is, checks that `g^s == R.X^c` with `c=H(X,R.T,m)`.
+/// * `pk` - public key
+/// * `msg` - message
+/// * `adaptor` - adaptor point `T`
+/// * `pre_sig` - pre-signature
+/// * `returns` - Nothing if the verification succeeds, an error otherwise
+fn pre_verify<G: Group>(
+    pk: &PublicKey<G>,
+    msg: &[u8],
+    adaptor: &G,
+    pre_sig: &PreSignature<G, G::S>,
+) -> Result<()> {
+    let c = signature_challenge(pk, msg, &pre_sig.R.add(adaptor));
+    let left = pre_sig.R.add(&pk.0.vartime_mul(&c));
+    let right = G::get_base().vartime_mul(&pre_sig.s);
+    if left == right {
+        Ok(())
+    } else {
+        Err(eg!(ZeiError::SignatureError))
+    }
+}
+
 #[cfg(test)]
 mod schnorr_sigs {
 
@@ -494,4 +623,63 @@ mod schnorr_sigs {
             check_schnorr_multisig::<RistrettoPoint>();
         }
     }
+
+    mod schnorr_adaptor_sig {
+
+        use crate::basics::signatures::schnorr::KeyPair;
+        use algebra::groups::{Group, Scalar};
+        use algebra::jubjub::JubjubPoint;
+        use algebra::ristretto::RistrettoPoint;
+        use rand_chacha::rand_core::SeedableRng;
+
+        fn check_schnorr_adaptor<G: Group>() {
+            let seed = [0_u8; 32];
+            let mut prng = rand_chacha::ChaChaRng::from_seed(seed);
+
+            let key_pair: KeyPair<G, G::S> = KeyPair::generate(&mut prng);
+            let public_key = key_pair.pub_key.clone();
+            let message = b"message";
+
+            let secret = G::S::random(&mut prng);
+            let adaptor = G::get_base().mul(&secret);
+
+            let pre_sig = key_pair.pre_sign(message, &adaptor);
+            assert!(public_key.pre_verify(message, &adaptor, &pre_sig).is_ok());
+            assert!(public_key
+                .pre_verify(b"wrong_message", &adaptor, &pre_sig)
+                .is_err());
+            assert!(public_key
+                .pre_verify(message, &adaptor.double(), &pre_sig)
+                .is_err());
+
+            // the pre-signature is not a valid signature by itself
+            let other_secret = G::S::random(&mut prng);
+            assert!(public_key
+                .verify(message, &pre_sig.adapt(&other_secret))
+                .is_err());
+
+            let sig = pre_sig.adapt(&secret);
+            assert!(public_key.verify(message, &sig).is_ok());
+            assert_eq!(pre_sig.extract_secret(&sig, &adaptor).unwrap(), secret);
+
+            // a signature not adapted from the pre-signature does not reveal the secret
+            let plain_sig = key_pair.sign(message);
+            assert!(pre_sig.extract_secret(&plain_sig, &adaptor).is_err());
+
+            // pre-signatures on the same message with different adaptor points use
+            // different nonces
+            let pre_sig2 = key_pair.pre_sign(message, &adaptor.double());
+            assert_ne!(pre_sig.R, pre_sig2.R);
+        }
+
+        #[test]
+        fn schnorr_adaptor_sig_over_jubjub() {
+            check_schnorr_adaptor::<JubjubPoint>();
+        }
+
+        #[test]
+        fn schnorr_adaptor_sig_over_ristretto() {
+            check_schnorr_adaptor::<RistrettoPoint>();
+        }
+    }
 }
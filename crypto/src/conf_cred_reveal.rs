@@ -9,6 +9,7 @@ use merlin::Transcript;
 use rand_core::{CryptoRng, RngCore};
 use ruc::*;
 use utils::errors::ZeiError;
+use utils::serialization::Validate;
 
 const CAC_REVEAL_PROOF_DOMAIN: &[u8] = b"Confidential AC Reveal PoK";
 const CAC_REVEAL_PROOF_NEW_TRANSCRIPT_INSTANCE: &[u8] =
@@ -88,6 +89,16 @@ pub struct CACPoK<G1, G2, S> {
     pub response_rands: Vec<S>,
 }
 
+impl<G1, G2, S> Validate for CACPoK<G1, G2, S> {
+    /// Checks that there is one randomness response for each ciphertext commitment
+    fn validate(&self) -> Result<()> {
+        if self.commitment_ctexts.len() != self.response_rands.len() {
+            return Err(eg!(ZeiError::InconsistentStructureError));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ConfidentialAC<G1, G2, S> {
     pub ctexts: Vec<ElGamalCiphertext<G1>>,
@@ -169,6 +180,8 @@ pub fn ac_confidential_open_verify<P: Pairing>(
     msg: &[u8],
 ) -> Result<()> {
     // 1. error checking
+    sig_commitment.validate().c(d!())?;
+    cac_pok.validate().c(d!())?;
     let n = ctexts.len();
     let revealed_count = reveal_map
         .iter()
@@ -523,6 +536,26 @@ pub(crate) mod test_helper {
             vrfy.unwrap_err(),
             "proof should fail, bad sok message"
         );
+
+        // One more randomness response than ciphertext commitments
+        let mut malformed_pok = conf_reveal_proof.pok.clone();
+        malformed_pok
+            .response_rands
+            .push(P::ScalarField::from_u32(1));
+        let vrfy = ac_confidential_open_verify::<P>(
+            &issuer_pk,
+            &enc_key,
+            &reveal_bitmap,
+            &sig_commitment,
+            &conf_reveal_proof.ctexts,
+            &malformed_pok,
+            proof_message,
+        );
+        msg_eq!(
+            ZeiError::InconsistentStructureError,
+            vrfy.unwrap_err(),
+            "proof should fail, malformed proof"
+        );
     }
 }
 
@@ -0,0 +1,751 @@
+/*
+This file implements predicate proofs over the attributes of an anonymous credential (see
+anon_creds.rs): besides revealing or hiding each attribute, a reveal proof can prove that a
+hidden attribute lies in a range, e.g. age >= 18, or belongs to a set of values, e.g. the
+country is one of an allowed list, without revealing it. The statement is described by a
+policy, ACRevealPolicy, with one predicate per attribute, which the prover and the verifier
+both take as input.
+
+ + Range predicate: attr in [min, max], for a 64-bit attribute attr.
+   i) The prover commits to attr with a Pedersen commitment V = attr * B + rho * H over
+      Ristretto, and proves with Bulletproofs that attr - min and max - attr, committed by
+      V - min * B and max * B - V, are 64-bit values (bp_range_proofs.rs).
+  ii) V is linked to the hidden attribute of the credential: the proof of knowledge of the
+      reveal proof and the proof of knowledge of the opening of V, R = r_v * B + r_rho * H,
+      share the blinding of the attribute and the challenge. As the orders of the groups
+      differ, the blinding r_v is an integer and the response is computed over the integers,
+      as in plonk/pc_link.rs of poly-iops:
+       - the challenge c has 128 bits, r_v is uniform in [0, 2^250), and the response
+         z = r_v + c * attr is accepted if it lies in [2^192, 2^250) (the prover restarts
+         otherwise, with probability 2^-58), so that z does not depend on attr;
+       - z is the response of the attribute in the reveal proof, as z is smaller than the order
+         of the scalar field of the pairing. The verifier checks that z < 2^250, and that
+         z * B + z_rho * H = R + c * V over Ristretto, where z_rho = c * rho + r_rho.
+      From two accepting transcripts, the value attr_2 = dz / dc extracted from the Ristretto
+      proof is a 64-bit value by the range proofs, hence attr_2 * dc = dz over the integers
+      as |attr_2 * dc - dz| < 2^251 is smaller than the order of Ristretto, and the attribute
+      attr_1 = dz / dc extracted from the reveal proof is attr_2.
+
+ + Set membership predicate: attr in {v_1, ..., v_n}.
+   i) The prover commits to attr with a Pedersen commitment C = attr * G1 + rho * H1 over
+      the group G1 of the pairing, and proves that C opens to one of the v_i
+      (pc_membership.rs).
+  ii) C is linked to the hidden attribute of the credential as above, with the proof
+      commitment R = r_attr * G1 + r_rho * H1, where r_attr is the blinding of the attribute
+      in the reveal proof. The verifier checks that z_attr * G1 + z_rho * H1 = R + c * C,
+      where z_attr = c * attr + r_attr is the response of the attribute.
+*/
+
+use crate::anon_creds::{
+    ac_commitment_key_gen, ac_do_challenge_check_commitment, ac_init_transcript,
+    ac_randomize, ACCommitment, ACIssuerPublicKey, ACPoK, ACRevealSig, ACUserSecretKey,
+    Attribute, Credential,
+};
+use crate::basics::commitments::pedersen::PedersenGens;
+use crate::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
+use crate::bp_range_proofs::{prove_ranges, verify_ranges};
+use crate::pc_membership::{
+    pc_membership_prove, pc_membership_verify, PCMembershipProof,
+};
+use crate::sigma::SigmaTranscript;
+use algebra::groups::{GroupArithmetic, Pairing, Scalar, ScalarArithmetic};
+use algebra::ristretto::{CompressedRistretto, RistrettoPoint, RistrettoScalar};
+use bulletproofs::{BulletproofGens, RangeProof};
+use merlin::Transcript;
+use num_bigint::BigUint;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+const AC_PREDICATE_PROOF_NEW_TRANSCRIPT_INSTANCE: &[u8] =
+    b"AC Predicate Reveal PoK Instance";
+const AC_PREDICATE_RANGE_PROOF_NEW_TRANSCRIPT_INSTANCE: &[u8] =
+    b"AC Predicate Range Proof Instance";
+
+// bit sizes of the challenge, the attributes of the range predicates and the upper bound of
+// their responses
+const CHALLENGE_BITS: u64 = 128;
+const VALUE_BITS: u64 = 64;
+const RESPONSE_BITS: u64 = 250;
+
+/// Statement about an attribute of a credential in a reveal proof
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ACAttributePredicate<S> {
+    Revealed,
+    Hidden,
+    /// the attribute is hidden and is a 64-bit value in [min, max]
+    Range {
+        min: u64,
+        max: u64,
+    },
+    /// the attribute is hidden and is one of the values
+    Membership(Vec<S>),
+}
+
+/// I describe the statement of a reveal proof, with one predicate per attribute of the
+/// credential
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACRevealPolicy<S> {
+    pub predicates: Vec<ACAttributePredicate<S>>,
+}
+
+impl<S> ACRevealPolicy<S> {
+    /// Returns the bitmap of the revealed attributes
+    pub fn reveal_bitmap(&self) -> Vec<bool> {
+        self.predicates
+            .iter()
+            .map(|p| matches!(p, ACAttributePredicate::Revealed))
+            .collect()
+    }
+}
+
+/// Proof of a range predicate on a hidden attribute
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ACRangePredicateProof {
+    #[serde(with = "utils::serialization::zei_obj_serde")]
+    pub(crate) range_proof: RangeProof,
+    pub(crate) value_commitments: Vec<CompressedRistretto>, // attr - min, max - attr
+    pub(crate) commitment: RistrettoPoint,                  // r_v*B + r_rho*H
+    pub(crate) response_blind: RistrettoScalar,             // c*rho + r_rho
+}
+
+/// Proof of a set membership predicate on a hidden attribute
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACMembershipPredicateProof<G1, S> {
+    pub(crate) value_commitment: G1, // attr*G1 + rho*H1
+    pub(crate) commitment: G1,       // r_attr*G1 + r_rho*H1
+    pub(crate) response_blind: S,    // c*rho + r_rho
+    pub(crate) membership_proof: PCMembershipProof<S>,
+}
+
+/// I'm a reveal proof extended with the proofs of the predicates of a policy, in the order
+/// of the attributes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ACPredicateRevealSig<G1, G2, S> {
+    pub reveal_sig: ACRevealSig<G1, G2, S>,
+    pub range_proofs: Vec<ACRangePredicateProof>,
+    pub membership_proofs: Vec<ACMembershipPredicateProof<G1, S>>,
+}
+
+// the responses of the range predicates, below 2^RESPONSE_BITS, must be canonical in the
+// scalar field
+fn check_field_size<S: Scalar>() -> Result<()> {
+    if BigUint::from_bytes_le(&S::get_field_size_lsf_bytes()).bits() < RESPONSE_BITS + 2
+    {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    Ok(())
+}
+
+// Returns the attribute as a 64-bit value, if it is one
+fn attribute_to_u64<S: Scalar>(attr: &S) -> Option<u64> {
+    let bytes = attr.to_bytes();
+    if bytes[8..].iter().any(|b| *b != 0) {
+        return None;
+    }
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&bytes[..8]);
+    Some(u64::from_le_bytes(value))
+}
+
+fn ac_predicate_init_transcript<P: Pairing>(
+    transcript: &mut Transcript,
+    issuer_pk: &ACIssuerPublicKey<P::G1, P::G2>,
+    sig_commitment: &ACCommitment<P::G1>,
+    policy: &ACRevealPolicy<P::ScalarField>,
+    range_commitments: &[&[CompressedRistretto]],
+    membership_commitments: &[&P::G1],
+) {
+    ac_init_transcript::<P>(transcript, issuer_pk, sig_commitment);
+    for predicate in policy.predicates.iter() {
+        match predicate {
+            ACAttributePredicate::Revealed => {
+                transcript.append_message(b"predicate", b"revealed");
+            }
+            ACAttributePredicate::Hidden => {
+                transcript.append_message(b"predicate", b"hidden");
+            }
+            ACAttributePredicate::Range { min, max } => {
+                transcript.append_message(b"predicate", b"range");
+                transcript.append_u64(b"min", *min);
+                transcript.append_u64(b"max", *max);
+            }
+            ACAttributePredicate::Membership(values) => {
+                transcript.append_message(b"predicate", b"membership");
+                transcript.append_u64(b"number of values", values.len() as u64);
+                for value in values.iter() {
+                    transcript.append_field_element(b"value", value);
+                }
+            }
+        }
+    }
+    for commitments in range_commitments.iter() {
+        for commitment in commitments.iter() {
+            transcript.append_message(b"range commitment", commitment.0.as_bytes());
+        }
+    }
+    for commitment in membership_commitments.iter() {
+        transcript.append_group_element(b"membership commitment", *commitment);
+    }
+}
+
+fn ac_predicate_challenge(transcript: &mut Transcript) -> u128 {
+    let mut bytes = [0u8; (CHALLENGE_BITS / 8) as usize];
+    transcript.challenge_bytes(b"challenge", &mut bytes);
+    u128::from_le_bytes(bytes)
+}
+
+/// Produce a reveal proof for the policy: the attributes are revealed or hidden as in
+/// ac_reveal, and the predicates on the hidden attributes are proven. Returns an error if
+/// the attributes of the credential do not satisfy the policy.
+pub fn ac_reveal_with_policy<R: CryptoRng + RngCore, P: Pairing>(
+    prng: &mut R,
+    user_sk: &ACUserSecretKey<P::ScalarField>,
+    credential: &Credential<P::G1, P::G2, P::ScalarField>,
+    policy: &ACRevealPolicy<P::ScalarField>,
+    bp_gens: &BulletproofGens,
+) -> Result<ACPredicateRevealSig<P::G1, P::G2, P::ScalarField>> {
+    check_field_size::<P::ScalarField>().c(d!())?;
+    let issuer_pk = &credential.issuer_pub_key;
+    let n = credential.attributes.len();
+    if policy.predicates.len() != n || issuer_pk.num_attrs() != n {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let pc_gens = RistrettoPedersenGens::default();
+    let pc_gens_g1 = PedersenGens::<P::G1>::new(1);
+
+    // 1. commit to the attributes of the predicates and prove the predicates on the
+    // commitments, except for the links to the credential
+    let mut range_values = vec![];
+    let mut range_proofs = vec![];
+    let mut membership_openings = vec![];
+    for (attr, predicate) in credential.attributes.iter().zip(policy.predicates.iter()) {
+        match predicate {
+            ACAttributePredicate::Range { min, max } => {
+                let value = attribute_to_u64(attr).c(d!(ZeiError::ParameterError))?;
+                if value < *min || value > *max {
+                    return Err(eg!(ZeiError::ParameterError));
+                }
+                let rho = RistrettoScalar::random(prng);
+                let mut transcript =
+                    Transcript::new(AC_PREDICATE_RANGE_PROOF_NEW_TRANSCRIPT_INSTANCE);
+                let (range_proof, value_commitments) = prove_ranges(
+                    bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &[value - min, max - value],
+                    &[rho, rho.neg()],
+                    VALUE_BITS as usize,
+                )
+                .c(d!())?;
+                range_values.push((value, rho));
+                range_proofs.push((range_proof, value_commitments));
+            }
+            ACAttributePredicate::Membership(values) => {
+                let index = values
+                    .iter()
+                    .position(|v| v == attr)
+                    .c(d!(ZeiError::ParameterError))?;
+                let rho = P::ScalarField::random(prng);
+                let value_commitment = pc_gens_g1.commit(&[*attr], &rho).c(d!())?;
+                membership_openings.push((values, index, rho, value_commitment));
+            }
+            _ => {}
+        }
+    }
+
+    // 2. reveal proof, whose challenge is shared with the links of the predicates
+    let key = ac_commitment_key_gen::<_, P>(prng);
+    let sig_commitment = ac_randomize::<P>(&credential.signature, &key);
+    let mut transcript = Transcript::new(AC_PREDICATE_PROOF_NEW_TRANSCRIPT_INSTANCE);
+    let range_commitments: Vec<&[CompressedRistretto]> = range_proofs
+        .iter()
+        .map(|(_, coms)| coms.as_slice())
+        .collect();
+    let membership_commitments: Vec<&P::G1> = membership_openings
+        .iter()
+        .map(|(_, _, _, com)| com)
+        .collect();
+    ac_predicate_init_transcript::<P>(
+        &mut transcript,
+        issuer_pk,
+        &sig_commitment,
+        policy,
+        &range_commitments,
+        &membership_commitments,
+    );
+
+    loop {
+        let mut attempt = transcript.clone();
+        let beta_t = P::ScalarField::random(prng);
+        let beta_sk = P::ScalarField::random(prng);
+        let mut commitment = issuer_pk
+            .gen2
+            .mul(&beta_t)
+            .add(&issuer_pk.zz2.mul(&beta_sk));
+        let mut gammas = vec![];
+        let mut range_blindings = vec![];
+        let mut membership_blindings = vec![];
+        for (yy2i, predicate) in issuer_pk.yy2.iter().zip(policy.predicates.iter()) {
+            let gamma = match predicate {
+                ACAttributePredicate::Revealed => continue,
+                ACAttributePredicate::Range { .. } => {
+                    let mut r_value_bytes = [0u8; 32];
+                    prng.fill_bytes(&mut r_value_bytes);
+                    r_value_bytes[31] &= 0x03; // r_value < 2^250
+                    range_blindings.push((r_value_bytes, RistrettoScalar::random(prng)));
+                    P::ScalarField::from_le_bytes(&r_value_bytes).c(d!())?
+                }
+                ACAttributePredicate::Membership(_) => {
+                    let gamma = P::ScalarField::random(prng);
+                    membership_blindings.push((gamma, P::ScalarField::random(prng)));
+                    gamma
+                }
+                ACAttributePredicate::Hidden => P::ScalarField::random(prng),
+            };
+            commitment = commitment.add(&yy2i.mul(&gamma));
+            gammas.push(gamma);
+        }
+        attempt.append_proof_commitment(&commitment);
+        let mut range_link_commitments = vec![];
+        for (r_value_bytes, r_blind) in range_blindings.iter() {
+            let link_commitment = pc_gens.commit(
+                RistrettoScalar::from_le_bytes(r_value_bytes).c(d!())?,
+                *r_blind,
+            );
+            attempt.append_proof_commitment(&link_commitment);
+            range_link_commitments.push(link_commitment);
+        }
+        let mut membership_link_commitments = vec![];
+        for (gamma, r_blind) in membership_blindings.iter() {
+            let link_commitment = pc_gens_g1.commit(&[*gamma], r_blind).c(d!())?;
+            attempt.append_proof_commitment(&link_commitment);
+            membership_link_commitments.push(link_commitment);
+        }
+        let c = ac_predicate_challenge(&mut attempt);
+
+        // the responses of the range predicates must not depend on the attributes
+        let restart = range_blindings.iter().zip(range_values.iter()).any(
+            |((r_value_bytes, _), (value, _))| {
+                let response = BigUint::from_bytes_le(r_value_bytes)
+                    + BigUint::from(c) * BigUint::from(*value);
+                response.bits() <= CHALLENGE_BITS + VALUE_BITS
+                    || response.bits() > RESPONSE_BITS
+            },
+        );
+        if restart {
+            continue;
+        }
+
+        let challenge = P::ScalarField::from_u128(c);
+        let response_t = challenge.mul(&key.t).add(&beta_t);
+        let response_sk = challenge.mul(&user_sk.0).add(&beta_sk);
+        let hidden_attrs = credential
+            .attributes
+            .iter()
+            .zip(policy.predicates.iter())
+            .filter(|(_, p)| !matches!(p, ACAttributePredicate::Revealed))
+            .map(|(attr, _)| attr);
+        let response_attrs = hidden_attrs
+            .zip(gammas.iter())
+            .map(|(attr, gamma)| challenge.mul(attr).add(gamma))
+            .collect();
+
+        let c_r = RistrettoScalar::from_u128(c);
+        let range_proofs = range_proofs
+            .into_iter()
+            .zip(range_values.iter())
+            .zip(range_blindings.iter())
+            .zip(range_link_commitments)
+            .map(
+                |(
+                    (((range_proof, value_commitments), (_, rho)), (_, r_blind)),
+                    commitment,
+                )| ACRangePredicateProof {
+                    range_proof,
+                    value_commitments,
+                    commitment,
+                    response_blind: r_blind.add(&c_r.mul(rho)),
+                },
+            )
+            .collect();
+
+        // 3. proofs of membership of the committed attributes
+        let mut membership_proofs = vec![];
+        for ((values, index, rho, value_commitment), ((_, r_blind), commitment)) in
+            membership_openings
+                .into_iter()
+                .zip(membership_blindings.iter().zip(membership_link_commitments))
+        {
+            let membership_proof = pc_membership_prove(
+                &mut attempt,
+                prng,
+                pc_gens_g1.get_base(0).c(d!())?,
+                pc_gens_g1.get_blinding_base(),
+                &value_commitment,
+                values,
+                index,
+                &rho,
+            )
+            .c(d!())?;
+            membership_proofs.push(ACMembershipPredicateProof {
+                value_commitment,
+                commitment,
+                response_blind: r_blind.add(&challenge.mul(&rho)),
+                membership_proof,
+            });
+        }
+
+        return Ok(ACPredicateRevealSig {
+            reveal_sig: ACRevealSig {
+                sig_commitment,
+                pok: ACPoK {
+                    commitment,
+                    response_t,
+                    response_sk,
+                    response_attrs,
+                },
+            },
+            range_proofs,
+            membership_proofs,
+        });
+    }
+}
+
+/// I verify a reveal proof for the policy, where `revealed_attrs` are the values of the
+/// revealed attributes, in order. Returns an error if the proof is not valid.
+pub fn ac_verify_with_policy<R: CryptoRng + RngCore, P: Pairing>(
+    prng: &mut R,
+    issuer_pub_key: &ACIssuerPublicKey<P::G1, P::G2>,
+    policy: &ACRevealPolicy<P::ScalarField>,
+    revealed_attrs: &[P::ScalarField],
+    bp_gens: &BulletproofGens,
+    reveal_sig: &ACPredicateRevealSig<P::G1, P::G2, P::ScalarField>,
+) -> Result<()> {
+    check_field_size::<P::ScalarField>().c(d!())?;
+    if policy.predicates.len() != issuer_pub_key.num_attrs() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let mut revealed_iter = revealed_attrs.iter();
+    let mut attrs = vec![];
+    let mut num_ranges = 0;
+    let mut num_memberships = 0;
+    for predicate in policy.predicates.iter() {
+        match predicate {
+            ACAttributePredicate::Revealed => {
+                let attr = revealed_iter.next().c(d!(ZeiError::ParameterError))?;
+                attrs.push(Attribute::Revealed(*attr));
+                continue;
+            }
+            ACAttributePredicate::Range { min, max } => {
+                if min > max {
+                    return Err(eg!(ZeiError::ParameterError));
+                }
+                num_ranges += 1;
+            }
+            ACAttributePredicate::Membership(_) => num_memberships += 1,
+            ACAttributePredicate::Hidden => {}
+        }
+        attrs.push(Attribute::Hidden(None));
+    }
+    if revealed_iter.next().is_some() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    if reveal_sig.range_proofs.len() != num_ranges
+        || reveal_sig.membership_proofs.len() != num_memberships
+        || reveal_sig
+            .range_proofs
+            .iter()
+            .any(|proof| proof.value_commitments.len() != 2)
+    {
+        return Err(eg!(ZeiError::IdentityRevealVerifyError));
+    }
+    let pc_gens = RistrettoPedersenGens::default();
+    let pc_gens_g1 = PedersenGens::<P::G1>::new(1);
+
+    // 1. verify the range proofs and get the commitments to the attributes
+    let mut range_proofs = reveal_sig.range_proofs.iter();
+    let mut value_commitments = vec![];
+    for predicate in policy.predicates.iter() {
+        if let ACAttributePredicate::Range { min, max } = predicate {
+            let proof = range_proofs
+                .next()
+                .c(d!(ZeiError::IdentityRevealVerifyError))?;
+            let mut transcript =
+                Transcript::new(AC_PREDICATE_RANGE_PROOF_NEW_TRANSCRIPT_INSTANCE);
+            verify_ranges(
+                prng,
+                bp_gens,
+                &(&pc_gens).into(),
+                &proof.range_proof,
+                &mut transcript,
+                &proof.value_commitments,
+                VALUE_BITS as usize,
+            )
+            .c(d!())?;
+            let com_low = proof.value_commitments[0]
+                .decompress()
+                .c(d!(ZeiError::DecompressElementError))?;
+            let com_high = proof.value_commitments[1]
+                .decompress()
+                .c(d!(ZeiError::DecompressElementError))?;
+            // (attr - min) + (max - attr) = max - min
+            if com_low.add(&com_high)
+                != pc_gens.B.mul(&RistrettoScalar::from_u64(max - min))
+            {
+                return Err(eg!(ZeiError::IdentityRevealVerifyError));
+            }
+            value_commitments
+                .push(com_low.add(&pc_gens.B.mul(&RistrettoScalar::from_u64(*min))));
+        }
+    }
+
+    // 2. compute the challenge
+    let pok = &reveal_sig.reveal_sig.pok;
+    let mut transcript = Transcript::new(AC_PREDICATE_PROOF_NEW_TRANSCRIPT_INSTANCE);
+    let range_commitments: Vec<&[CompressedRistretto]> = reveal_sig
+        .range_proofs
+        .iter()
+        .map(|proof| proof.value_commitments.as_slice())
+        .collect();
+    let membership_commitments: Vec<&P::G1> = reveal_sig
+        .membership_proofs
+        .iter()
+        .map(|proof| &proof.value_commitment)
+        .collect();
+    ac_predicate_init_transcript::<P>(
+        &mut transcript,
+        issuer_pub_key,
+        &reveal_sig.reveal_sig.sig_commitment,
+        policy,
+        &range_commitments,
+        &membership_commitments,
+    );
+    transcript.append_proof_commitment(&pok.commitment);
+    for proof in reveal_sig.range_proofs.iter() {
+        transcript.append_proof_commitment(&proof.commitment);
+    }
+    for proof in reveal_sig.membership_proofs.iter() {
+        transcript.append_proof_commitment(&proof.commitment);
+    }
+    let c = ac_predicate_challenge(&mut transcript);
+    let challenge = P::ScalarField::from_u128(c);
+    let c_r = RistrettoScalar::from_u128(c);
+
+    // 3. verify the links of the commitments to the hidden attributes
+    let mut response_attrs = pok.response_attrs.iter();
+    let mut range_proofs = reveal_sig.range_proofs.iter().zip(value_commitments.iter());
+    let mut membership_proofs = reveal_sig.membership_proofs.iter();
+    for predicate in policy.predicates.iter() {
+        if let ACAttributePredicate::Revealed = predicate {
+            continue;
+        }
+        let response = response_attrs
+            .next()
+            .c(d!(ZeiError::IdentityRevealVerifyError))?;
+        match predicate {
+            ACAttributePredicate::Range { .. } => {
+                let (proof, value_commitment) = range_proofs
+                    .next()
+                    .c(d!(ZeiError::IdentityRevealVerifyError))?;
+                // response < 2^RESPONSE_BITS
+                let response_bytes = response.to_bytes();
+                if response_bytes[32..].iter().any(|b| *b != 0)
+                    || response_bytes[31] >= 1 << (RESPONSE_BITS - 248)
+                {
+                    return Err(eg!(ZeiError::IdentityRevealVerifyError));
+                }
+                let response_r =
+                    RistrettoScalar::from_le_bytes(&response_bytes[..32]).c(d!())?;
+                if pc_gens.commit(response_r, proof.response_blind)
+                    != proof.commitment.add(&value_commitment.mul(&c_r))
+                {
+                    return Err(eg!(ZeiError::IdentityRevealVerifyError));
+                }
+            }
+            ACAttributePredicate::Membership(values) => {
+                let proof = membership_proofs
+                    .next()
+                    .c(d!(ZeiError::IdentityRevealVerifyError))?;
+                if pc_gens_g1
+                    .commit(&[*response], &proof.response_blind)
+                    .c(d!())?
+                    != proof
+                        .commitment
+                        .add(&proof.value_commitment.mul(&challenge))
+                {
+                    return Err(eg!(ZeiError::IdentityRevealVerifyError));
+                }
+                pc_membership_verify(
+                    &mut transcript,
+                    pc_gens_g1.get_base(0).c(d!())?,
+                    pc_gens_g1.get_blinding_base(),
+                    &proof.value_commitment,
+                    values,
+                    &proof.membership_proof,
+                )
+                .c(d!())?;
+            }
+            _ => {}
+        }
+    }
+
+    // 4. verify credential proof
+    ac_do_challenge_check_commitment::<P>(
+        issuer_pub_key,
+        &reveal_sig.reveal_sig.sig_commitment,
+        pok,
+        attrs.as_slice(),
+        &challenge,
+    )
+    .c(d!())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::anon_creds::{ac_keygen_issuer, ac_sign, ac_user_key_gen, Credential};
+    use crate::cred_predicates::{
+        ac_reveal_with_policy, ac_verify_with_policy, ACAttributePredicate,
+        ACPredicateRevealSig, ACRevealPolicy,
+    };
+    use algebra::bls12_381::{BLSScalar, Bls12381, BLSG1, BLSG2};
+    use algebra::groups::{Scalar, ScalarArithmetic};
+    use bulletproofs::BulletproofGens;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    type P = Bls12381;
+
+    #[test]
+    fn test_predicates() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let bp_gens = BulletproofGens::new(64, 2);
+        let (issuer_pk, issuer_sk) = ac_keygen_issuer::<_, P>(&mut prng, 4);
+        let (user_pk, user_sk) = ac_user_key_gen::<_, P>(&mut prng, &issuer_pk);
+        // name, age, country and a secret attribute
+        let attrs = vec![
+            BLSScalar::from_u32(7),
+            BLSScalar::from_u32(30),
+            BLSScalar::from_u32(33),
+            BLSScalar::random(&mut prng),
+        ];
+        let signature =
+            ac_sign::<_, P>(&mut prng, &issuer_sk, &user_pk, &attrs).unwrap();
+        let credential = Credential {
+            signature,
+            attributes: attrs.clone(),
+            issuer_pub_key: issuer_pk.clone(),
+        };
+        let countries = vec![
+            BLSScalar::from_u32(1),
+            BLSScalar::from_u32(33),
+            BLSScalar::from_u32(49),
+        ];
+        let policy = ACRevealPolicy {
+            predicates: vec![
+                ACAttributePredicate::Revealed,
+                ACAttributePredicate::Range {
+                    min: 18,
+                    max: u64::MAX,
+                },
+                ACAttributePredicate::Membership(countries.clone()),
+                ACAttributePredicate::Hidden,
+            ],
+        };
+        assert_eq!(policy.reveal_bitmap(), vec![true, false, false, false]);
+
+        let proof = ac_reveal_with_policy::<_, P>(
+            &mut prng,
+            &user_sk,
+            &credential,
+            &policy,
+            &bp_gens,
+        )
+        .unwrap();
+        let verify =
+            |prng: &mut ChaChaRng,
+             policy: &ACRevealPolicy<BLSScalar>,
+             revealed_attrs: &[BLSScalar],
+             proof: &ACPredicateRevealSig<BLSG1, BLSG2, BLSScalar>| {
+                ac_verify_with_policy::<_, P>(
+                    prng,
+                    &issuer_pk,
+                    policy,
+                    revealed_attrs,
+                    &bp_gens,
+                    proof,
+                )
+            };
+        assert!(verify(&mut prng, &policy, &attrs[..1], &proof).is_ok());
+
+        // serialization
+        let json = serde_json::to_string(&proof).unwrap();
+        let proof_de: ACPredicateRevealSig<BLSG1, BLSG2, BLSScalar> =
+            serde_json::from_str(&json).unwrap();
+        assert!(verify(&mut prng, &policy, &attrs[..1], &proof_de).is_ok());
+
+        // wrong revealed attribute
+        assert!(verify(&mut prng, &policy, &[BLSScalar::from_u32(8)], &proof).is_err());
+
+        // the proof does not hold for another policy
+        let mut other_policy = policy.clone();
+        other_policy.predicates[1] = ACAttributePredicate::Range {
+            min: 21,
+            max: u64::MAX,
+        };
+        assert!(verify(&mut prng, &other_policy, &attrs[..1], &proof).is_err());
+        let mut other_policy = policy.clone();
+        other_policy.predicates[2] =
+            ACAttributePredicate::Membership(countries[..2].to_vec());
+        assert!(verify(&mut prng, &other_policy, &attrs[..1], &proof).is_err());
+
+        // tampered responses
+        let mut bad_proof = proof.clone();
+        bad_proof.reveal_sig.pok.response_attrs[0] =
+            bad_proof.reveal_sig.pok.response_attrs[0].add(&BLSScalar::from_u32(1));
+        assert!(verify(&mut prng, &policy, &attrs[..1], &bad_proof).is_err());
+        let mut bad_proof = proof.clone();
+        bad_proof.membership_proofs[0].response_blind = BLSScalar::random(&mut prng);
+        assert!(verify(&mut prng, &policy, &attrs[..1], &bad_proof).is_err());
+        let mut bad_proof = proof.clone();
+        bad_proof.range_proofs[0].value_commitments.swap(0, 1);
+        assert!(verify(&mut prng, &policy, &attrs[..1], &bad_proof).is_err());
+
+        // the prover cannot prove predicates that do not hold
+        let mut underage_policy = policy.clone();
+        underage_policy.predicates[1] = ACAttributePredicate::Range { min: 31, max: 40 };
+        assert!(ac_reveal_with_policy::<_, P>(
+            &mut prng,
+            &user_sk,
+            &credential,
+            &underage_policy,
+            &bp_gens
+        )
+        .is_err());
+        let mut other_country_policy = policy.clone();
+        other_country_policy.predicates[2] =
+            ACAttributePredicate::Membership(vec![BLSScalar::from_u32(1)]);
+        assert!(ac_reveal_with_policy::<_, P>(
+            &mut prng,
+            &user_sk,
+            &credential,
+            &other_country_policy,
+            &bp_gens
+        )
+        .is_err());
+        // a range predicate requires a 64-bit attribute
+        let mut secret_range_policy = policy.clone();
+        secret_range_policy.predicates[3] = ACAttributePredicate::Range {
+            min: 0,
+            max: u64::MAX,
+        };
+        assert!(ac_reveal_with_policy::<_, P>(
+            &mut prng,
+            &user_sk,
+            &credential,
+            &secret_range_policy,
+            &bp_gens
+        )
+        .is_err());
+    }
+}
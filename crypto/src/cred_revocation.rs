@@ -0,0 +1,646 @@
+/*
+This file implements the revocation of anonymous credentials (see anon_creds.rs) with the
+pairing based accumulator of Lan Nguyen. Accumulators and Applications to ID-based Ring
+Signatures and Group Membership Revocation. CT-RSA 2005.
+<https://eprint.iacr.org/2005/123.pdf>. As in Camenisch, Kohlweiss and Soriente. An
+Accumulator Based on Bilinear Maps and Efficient Revocation for Anonymous Credentials.
+PKC 2009, the issuer computes the witnesses with its secret key.
+
+Each credential contains, as one of its attributes, a revocation handle h: a random scalar
+sampled by the issuer and never revealed. The issuer maintains a public accumulator value V of
+the handles of the non revoked credentials, and gives to each user a witness W for its handle.
+A reveal proof is extended with a zero knowledge proof that the hidden attribute h of the
+credential is accumulated in V, without revealing h nor W, so that the presentations of a
+credential remain unlinkable.
+
+ Specifications:
+ Let G1, G2, Gt be groups of prime order and a bilinear map e: G1 x G2 -> Gt. In what follows,
+ additive notation is used.
+ Revocation secret key: alpha // random scalar
+ Revocation public key:
+     - G2 // random generator of group 2
+     - A2 = alpha * G2
+ Accumulator: V, initially a random element of G1
+
+ + Witness for a handle h: W = 1/(alpha + h) * V, that is e(W, h * G2 + A2) = e(V, G2)
+
+ + Revocation of handle h: V' = 1/(alpha + h) * V. The issuer publishes (h, V'), and the
+   owner of each other handle h' updates its witness W' (valid for V) into
+   (W' - V') / (h - h'), so that (alpha + h') * (W' - V') / (h - h') = V'. The updates must
+   be applied in order of revocation. The owner of h cannot update its witness as h - h = 0.
+   Hence a handle must not be used twice.
+
+ + Non revocation proof, for a witness W of the hidden attribute h:
+    a) Randomize the witness: for a random scalar rho, W_bar = rho * W and
+       B_bar = rho * V - h * W_bar (= alpha * W_bar)
+    b) NI proof of knowledge of rho and h such that B_bar = rho * V - h * W_bar:
+        i) Sample blinding scalar b_rho, and reuse the blinding scalar b_h of the hidden
+           attribute h in the reveal proof of anon_creds.rs
+       ii) Compute the proof commitment R = b_rho * V - b_h * W_bar
+      iii) Compute the challenge c as in the reveal proof, with V, W_bar, B_bar and R
+           appended to the transcript
+       iv) Compute the response r_rho = c * rho + b_rho. The response of h is the response
+           r_attr_h = c * h + b_h of the reveal proof, which proves that the accumulated
+           handle is the signed attribute.
+    c) Output (W_bar, B_bar, R, r_rho) along with the reveal proof
+
+ + Non revocation verification:
+     i) Check that W_bar is not the identity
+    ii) Recompute the challenge c and check r_rho * V - r_attr_h * W_bar = R + c * B_bar
+   iii) Compare e(W_bar, A2) =? e(B_bar, G2), then (alpha + h) * W_bar = rho * V, that is
+        1/rho * W_bar is a witness for h
+    iv) Verify the reveal proof with the same challenge c
+*/
+
+use crate::anon_creds::{
+    ac_commitment_key_gen, ac_do_challenge_check_commitment, ac_randomize, ACCommitment,
+    ACIssuerPublicKey, ACPoK, ACRevealSig, ACUserSecretKey, Attribute, Credential,
+};
+use crate::sigma::{SigmaTranscript, SigmaTranscriptPairing};
+use algebra::groups::{Group, GroupArithmetic, Pairing, Scalar, ScalarArithmetic};
+use algebra::pairing_batch::PairingCheck;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+const AC_NON_REVOCATION_PROOF_DOMAIN: &[u8] = b"AC Non Revocation Reveal PoK";
+const AC_NON_REVOCATION_PROOF_NEW_TRANSCRIPT_INSTANCE: &[u8] =
+    b"AC Non Revocation Reveal PoK Instance";
+
+/// I'm the secret key of the revocation accumulator, held by the credential issuer
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACRevocationSecretKey<S>(pub(crate) S);
+
+/// I contain the public key of the revocation accumulator
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACRevocationPublicKey<G2> {
+    pub gen2: G2, //random generator for G2
+    pub aa2: G2,  //gen2^alpha, alpha in ACRevocationSecretKey
+}
+
+/// I'm the accumulator of the revocation handles of the non revoked credentials
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACAccumulator<G1> {
+    pub value: G1,
+}
+
+/// I'm a witness that a revocation handle is accumulated
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACRevocationWitness<G1, S> {
+    pub handle: S,
+    pub witness: G1, // 1/(alpha + handle) * accumulator value
+}
+
+/// Proof that the hidden revocation handle of a credential is accumulated
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACNonRevocationProof<G1, S> {
+    pub(crate) witness_commitment: G1, // rho * W
+    pub(crate) witness_image: G1,      // rho * V - h * rho * W
+    pub(crate) commitment: G1,         // r_rho * V - r_h * rho * W
+    pub(crate) response_rho: S,        // c*rho + r_rho
+}
+
+/// I'm a reveal proof extended with the proof that the credential is not revoked
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ACNonRevokedRevealSig<G1, G2, S> {
+    pub reveal_sig: ACRevealSig<G1, G2, S>,
+    pub non_revocation_proof: ACNonRevocationProof<G1, S>,
+}
+
+/// I generate the keys and the initial value of a revocation accumulator
+#[allow(clippy::type_complexity)]
+pub fn ac_revocation_keygen<R: CryptoRng + RngCore, P: Pairing>(
+    prng: &mut R,
+) -> (
+    ACRevocationPublicKey<P::G2>,
+    ACRevocationSecretKey<P::ScalarField>,
+    ACAccumulator<P::G1>,
+) {
+    let alpha = P::ScalarField::random(prng);
+    let gen2 = P::G2::get_random_base(prng);
+    let aa2 = gen2.mul(&alpha);
+    let value = P::G1::get_random_base(prng);
+    (
+        ACRevocationPublicKey { gen2, aa2 },
+        ACRevocationSecretKey(alpha),
+        ACAccumulator { value },
+    )
+}
+
+/// I compute the witness of a revocation handle for the current accumulator value. The
+/// handle must be a fresh random scalar, signed as an attribute of the credential.
+pub fn ac_revocation_witness<P: Pairing>(
+    revocation_sk: &ACRevocationSecretKey<P::ScalarField>,
+    accumulator: &ACAccumulator<P::G1>,
+    handle: &P::ScalarField,
+) -> Result<ACRevocationWitness<P::G1, P::ScalarField>> {
+    let exponent = revocation_sk
+        .0
+        .add(handle)
+        .inv()
+        .c(d!(ZeiError::ParameterError))?;
+    Ok(ACRevocationWitness {
+        handle: *handle,
+        witness: accumulator.value.mul(&exponent),
+    })
+}
+
+/// I remove a revocation handle from the accumulator, and return the new accumulator. The
+/// issuer publishes the revoked handle along with the new accumulator so that the users
+/// can update their witnesses with ac_update_revocation_witness.
+pub fn ac_revoke<P: Pairing>(
+    revocation_sk: &ACRevocationSecretKey<P::ScalarField>,
+    accumulator: &ACAccumulator<P::G1>,
+    handle: &P::ScalarField,
+) -> Result<ACAccumulator<P::G1>> {
+    let exponent = revocation_sk
+        .0
+        .add(handle)
+        .inv()
+        .c(d!(ZeiError::ParameterError))?;
+    Ok(ACAccumulator {
+        value: accumulator.value.mul(&exponent),
+    })
+}
+
+/// I update a witness after the revocation of `revoked_handle`, where `new_accumulator` is
+/// the accumulator after the revocation. The revocations must be applied in order.
+/// Returns Err(ZeiError::CredentialRevokedError) if the handle of the witness is revoked.
+pub fn ac_update_revocation_witness<P: Pairing>(
+    witness: &mut ACRevocationWitness<P::G1, P::ScalarField>,
+    revoked_handle: &P::ScalarField,
+    new_accumulator: &ACAccumulator<P::G1>,
+) -> Result<()> {
+    let exponent = revoked_handle
+        .sub(&witness.handle)
+        .inv()
+        .c(d!(ZeiError::CredentialRevokedError))?;
+    witness.witness = witness.witness.sub(&new_accumulator.value).mul(&exponent);
+    Ok(())
+}
+
+/// I check that a witness is valid for the accumulator, e.g. after an update
+pub fn ac_verify_revocation_witness<P: Pairing>(
+    revocation_pk: &ACRevocationPublicKey<P::G2>,
+    accumulator: &ACAccumulator<P::G1>,
+    witness: &ACRevocationWitness<P::G1, P::ScalarField>,
+) -> Result<()> {
+    let gg2 = revocation_pk
+        .gen2
+        .mul(&witness.handle)
+        .add(&revocation_pk.aa2);
+    PairingCheck::<P>::new(
+        vec![(witness.witness.clone(), gg2)],
+        vec![(accumulator.value.clone(), revocation_pk.gen2.clone())],
+    )
+    .verify()
+    .c(d!(ZeiError::CredentialRevokedError))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn ac_non_revocation_init_transcript<P: Pairing>(
+    transcript: &mut Transcript,
+    issuer_pk: &ACIssuerPublicKey<P::G1, P::G2>,
+    sig_commitment: &ACCommitment<P::G1>,
+    revocation_pk: &ACRevocationPublicKey<P::G2>,
+    accumulator: &ACAccumulator<P::G1>,
+    handle_index: usize,
+    witness_commitment: &P::G1,
+    witness_image: &P::G1,
+) {
+    let g1 = P::G1::get_base();
+    let g2 = P::G2::get_base();
+    let g1_elems = vec![
+        &g1,
+        &issuer_pk.zz1,
+        &sig_commitment.0.sigma1,
+        &sig_commitment.0.sigma2,
+        &accumulator.value,
+        witness_commitment,
+        witness_image,
+    ];
+    let mut g2_elems = vec![&g2, &issuer_pk.gen2, &issuer_pk.xx2, &issuer_pk.zz2];
+    for e in issuer_pk.yy2.iter() {
+        g2_elems.push(e);
+    }
+    g2_elems.push(&revocation_pk.gen2);
+    g2_elems.push(&revocation_pk.aa2);
+    transcript.init_sigma_pairing::<P>(
+        AC_NON_REVOCATION_PROOF_DOMAIN,
+        &[],
+        &g1_elems[..],
+        g2_elems.as_slice(),
+        &[],
+    );
+    transcript.append_message(
+        b"revocation handle index",
+        &(handle_index as u64).to_le_bytes(),
+    );
+}
+
+/// Produce a reveal proof along with the proof that the credential is not revoked, the
+/// handle_index-th attribute of the credential being its revocation handle. The revocation
+/// handle is hidden, and `witness` must be valid for `accumulator`.
+#[allow(clippy::too_many_arguments)]
+#[allow(non_snake_case)]
+pub fn ac_reveal_non_revoked<R: CryptoRng + RngCore, P: Pairing>(
+    prng: &mut R,
+    user_sk: &ACUserSecretKey<P::ScalarField>,
+    credential: &Credential<P::G1, P::G2, P::ScalarField>,
+    reveal_bitmap: &[bool],
+    handle_index: usize,
+    revocation_pk: &ACRevocationPublicKey<P::G2>,
+    accumulator: &ACAccumulator<P::G1>,
+    witness: &ACRevocationWitness<P::G1, P::ScalarField>,
+) -> Result<ACNonRevokedRevealSig<P::G1, P::G2, P::ScalarField>> {
+    if credential.attributes.len() != reveal_bitmap.len()
+        || credential.issuer_pub_key.num_attrs() != reveal_bitmap.len()
+        || handle_index >= reveal_bitmap.len()
+        || reveal_bitmap[handle_index]
+        || credential.attributes[handle_index] != witness.handle
+    {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let issuer_pk = &credential.issuer_pub_key;
+    let key = ac_commitment_key_gen::<_, P>(prng);
+    let sig_commitment = ac_randomize::<P>(&credential.signature, &key);
+
+    // 1. randomize the witness
+    let rho = P::ScalarField::random(prng);
+    let W_bar = witness.witness.mul(&rho);
+    let B_bar = accumulator.value.mul(&rho).sub(&W_bar.mul(&witness.handle));
+
+    let mut transcript =
+        Transcript::new(AC_NON_REVOCATION_PROOF_NEW_TRANSCRIPT_INSTANCE);
+    ac_non_revocation_init_transcript::<P>(
+        &mut transcript,
+        issuer_pk,
+        &sig_commitment,
+        revocation_pk,
+        accumulator,
+        handle_index,
+        &W_bar,
+        &B_bar,
+    );
+
+    // 2. proof commitments, with the same blinding for the handle in both proofs
+    let beta_t = P::ScalarField::random(prng);
+    let beta_sk = P::ScalarField::random(prng);
+    let beta_rho = P::ScalarField::random(prng);
+    let mut commitment = issuer_pk
+        .gen2
+        .mul(&beta_t)
+        .add(&issuer_pk.zz2.mul(&beta_sk));
+    let mut gammas = vec![];
+    let mut gamma_handle = None;
+    for (i, (yy2i, b)) in issuer_pk.yy2.iter().zip(reveal_bitmap).enumerate() {
+        if !*b {
+            let gamma_i = P::ScalarField::random(prng);
+            commitment = commitment.add(&yy2i.mul(&gamma_i));
+            if i == handle_index {
+                gamma_handle = Some(gamma_i);
+            }
+            gammas.push(gamma_i);
+        }
+    }
+    let gamma_handle = gamma_handle.c(d!(ZeiError::ParameterError))?;
+    let acc_commitment = accumulator
+        .value
+        .mul(&beta_rho)
+        .sub(&W_bar.mul(&gamma_handle));
+    transcript.append_proof_commitment(&commitment);
+    transcript.append_proof_commitment(&acc_commitment);
+    let challenge = transcript.get_challenge::<P::ScalarField>();
+
+    // 3. responses
+    let response_t = challenge.mul(&key.t).add(&beta_t);
+    let response_sk = challenge.mul(&user_sk.0).add(&beta_sk);
+    let hidden_attrs = credential
+        .attributes
+        .iter()
+        .zip(reveal_bitmap)
+        .filter(|(_, b)| !*(*b))
+        .map(|(attr, _)| attr);
+    let response_attrs = hidden_attrs
+        .zip(gammas.iter())
+        .map(|(attr, gamma)| challenge.mul(attr).add(gamma))
+        .collect();
+    let response_rho = challenge.mul(&rho).add(&beta_rho);
+
+    Ok(ACNonRevokedRevealSig {
+        reveal_sig: ACRevealSig {
+            sig_commitment,
+            pok: ACPoK {
+                commitment,
+                response_t,
+                response_sk,
+                response_attrs,
+            },
+        },
+        non_revocation_proof: ACNonRevocationProof {
+            witness_commitment: W_bar,
+            witness_image: B_bar,
+            commitment: acc_commitment,
+            response_rho,
+        },
+    })
+}
+
+/// I verify a reveal proof and its proof that the credential is not revoked for the
+/// accumulator, where the handle_index-th attribute, which must be hidden, is the revocation
+/// handle. See ac_verify in anon_creds.rs for the revealed attributes `attrs`.
+pub fn ac_verify_non_revoked<P: Pairing>(
+    issuer_pub_key: &ACIssuerPublicKey<P::G1, P::G2>,
+    attrs: &[Attribute<P::ScalarField>],
+    handle_index: usize,
+    revocation_pk: &ACRevocationPublicKey<P::G2>,
+    accumulator: &ACAccumulator<P::G1>,
+    reveal_sig: &ACNonRevokedRevealSig<P::G1, P::G2, P::ScalarField>,
+) -> Result<()> {
+    if attrs.len() != issuer_pub_key.num_attrs() || handle_index >= attrs.len() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    if let Attribute::Revealed(_) = attrs[handle_index] {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let proof = &reveal_sig.non_revocation_proof;
+    let pok = &reveal_sig.reveal_sig.pok;
+    if proof.witness_commitment == P::G1::get_identity() {
+        return Err(eg!(ZeiError::CredentialRevokedError));
+    }
+
+    // 1. compute challenge
+    let mut transcript =
+        Transcript::new(AC_NON_REVOCATION_PROOF_NEW_TRANSCRIPT_INSTANCE);
+    ac_non_revocation_init_transcript::<P>(
+        &mut transcript,
+        issuer_pub_key,
+        &reveal_sig.reveal_sig.sig_commitment,
+        revocation_pk,
+        accumulator,
+        handle_index,
+        &proof.witness_commitment,
+        &proof.witness_image,
+    );
+    transcript.append_proof_commitment(&pok.commitment);
+    transcript.append_proof_commitment(&proof.commitment);
+    let challenge = transcript.get_challenge::<P::ScalarField>();
+
+    // 2. verify the proof of knowledge of rho and of the handle
+    let handle_position = attrs[..handle_index]
+        .iter()
+        .filter(|attr| matches!(attr, Attribute::Hidden(_)))
+        .count();
+    let response_handle = pok
+        .response_attrs
+        .get(handle_position)
+        .c(d!(ZeiError::CredentialRevokedError))?;
+    let lhs = accumulator
+        .value
+        .mul(&proof.response_rho)
+        .sub(&proof.witness_commitment.mul(response_handle));
+    let rhs = proof.commitment.add(&proof.witness_image.mul(&challenge));
+    if lhs != rhs {
+        return Err(eg!(ZeiError::CredentialRevokedError));
+    }
+
+    // 3. verify that the randomized witness is a witness
+    PairingCheck::<P>::new(
+        vec![(proof.witness_commitment.clone(), revocation_pk.aa2.clone())],
+        vec![(proof.witness_image.clone(), revocation_pk.gen2.clone())],
+    )
+    .verify()
+    .c(d!(ZeiError::CredentialRevokedError))?;
+
+    // 4. verify credential proof
+    ac_do_challenge_check_commitment::<P>(
+        issuer_pub_key,
+        &reveal_sig.reveal_sig.sig_commitment,
+        pok,
+        attrs,
+        &challenge,
+    )
+    .c(d!())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::anon_creds::{
+        ac_keygen_issuer, ac_reveal, ac_sign, ac_user_key_gen, ACUserSecretKey,
+        Attribute, Credential,
+    };
+    use crate::cred_revocation::{
+        ac_reveal_non_revoked, ac_revocation_keygen, ac_revocation_witness, ac_revoke,
+        ac_update_revocation_witness, ac_verify_non_revoked,
+        ac_verify_revocation_witness, ACAccumulator, ACNonRevokedRevealSig,
+        ACRevocationWitness,
+    };
+    use algebra::bls12_381::{BLSScalar, Bls12381, BLSG1, BLSG2};
+    use algebra::groups::{Group, Scalar};
+    use itertools::Itertools;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use utils::errors::ZeiError;
+
+    type P = Bls12381;
+    type User = (
+        ACUserSecretKey<BLSScalar>,
+        Credential<BLSG1, BLSG2, BLSScalar>,
+        ACRevocationWitness<BLSG1, BLSScalar>,
+    );
+
+    fn to_attributes(
+        attrs: &[BLSScalar],
+        reveal_bitmap: &[bool],
+    ) -> Vec<Attribute<BLSScalar>> {
+        attrs
+            .iter()
+            .zip(reveal_bitmap.iter())
+            .map(|(a, b)| {
+                if *b {
+                    Attribute::Revealed(*a)
+                } else {
+                    Attribute::Hidden(None)
+                }
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_revocation() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let num_attrs = 3;
+        let handle_index = 1;
+        let reveal_bitmap = [true, false, false];
+        let (issuer_pk, issuer_sk) = ac_keygen_issuer::<_, P>(&mut prng, num_attrs);
+        let (revocation_pk, revocation_sk, mut accumulator) =
+            ac_revocation_keygen::<_, P>(&mut prng);
+
+        // issue three credentials, the second attribute being the revocation handle
+        let mut users = vec![];
+        for i in 0..3 {
+            let (user_pk, user_sk) = ac_user_key_gen::<_, P>(&mut prng, &issuer_pk);
+            let handle = BLSScalar::random(&mut prng);
+            let attrs = vec![BLSScalar::from_u32(i), handle, BLSScalar::from_u32(20)];
+            let signature =
+                ac_sign::<_, P>(&mut prng, &issuer_sk, &user_pk, &attrs).unwrap();
+            let credential = Credential {
+                signature,
+                attributes: attrs,
+                issuer_pub_key: issuer_pk.clone(),
+            };
+            let witness =
+                ac_revocation_witness::<P>(&revocation_sk, &accumulator, &handle)
+                    .unwrap();
+            assert!(ac_verify_revocation_witness::<P>(
+                &revocation_pk,
+                &accumulator,
+                &witness
+            )
+            .is_ok());
+            users.push((user_sk, credential, witness));
+        }
+
+        let prove =
+            |prng: &mut ChaChaRng, user: &User, accumulator: &ACAccumulator<BLSG1>| {
+                let (user_sk, credential, witness) = user;
+                ac_reveal_non_revoked::<_, P>(
+                    prng,
+                    user_sk,
+                    credential,
+                    &reveal_bitmap,
+                    handle_index,
+                    &revocation_pk,
+                    accumulator,
+                    witness,
+                )
+            };
+        let verify =
+            |user: &User,
+             accumulator: &ACAccumulator<BLSG1>,
+             proof: &ACNonRevokedRevealSig<BLSG1, BLSG2, BLSScalar>| {
+                let attrs = to_attributes(&user.1.attributes, &reveal_bitmap);
+                ac_verify_non_revoked::<P>(
+                    &issuer_pk,
+                    &attrs,
+                    handle_index,
+                    &revocation_pk,
+                    accumulator,
+                    proof,
+                )
+            };
+        for user in users.iter() {
+            let proof = prove(&mut prng, user, &accumulator).unwrap();
+            assert!(verify(user, &accumulator, &proof).is_ok());
+        }
+
+        // the proof does not verify for other revealed attributes, handle index or
+        // accumulator
+        let proof = prove(&mut prng, &users[0], &accumulator).unwrap();
+        assert!(verify(&users[1], &accumulator, &proof).is_err());
+        let attrs = to_attributes(&users[0].1.attributes, &reveal_bitmap);
+        assert!(ac_verify_non_revoked::<P>(
+            &issuer_pk,
+            &attrs,
+            2,
+            &revocation_pk,
+            &accumulator,
+            &proof,
+        )
+        .is_err());
+        let other_accumulator =
+            ac_revoke::<P>(&revocation_sk, &accumulator, &BLSScalar::random(&mut prng))
+                .unwrap();
+        assert!(verify(&users[0], &other_accumulator, &proof).is_err());
+        // the handle cannot be revealed
+        let attrs = to_attributes(&users[0].1.attributes, &[true, true, false]);
+        assert!(ac_verify_non_revoked::<P>(
+            &issuer_pk,
+            &attrs,
+            handle_index,
+            &revocation_pk,
+            &accumulator,
+            &proof,
+        )
+        .is_err());
+        assert!(ac_reveal_non_revoked::<_, P>(
+            &mut prng,
+            &users[0].0,
+            &users[0].1,
+            &[true, true, false],
+            handle_index,
+            &revocation_pk,
+            &accumulator,
+            &users[0].2,
+        )
+        .is_err());
+        // a null randomized witness is rejected
+        let mut bad_proof = proof.clone();
+        bad_proof.non_revocation_proof.witness_commitment = BLSG1::get_identity();
+        bad_proof.non_revocation_proof.witness_image = BLSG1::get_identity();
+        assert!(verify(&users[0], &accumulator, &bad_proof).is_err());
+
+        // revoke the credential of the second user
+        let revoked_handle = users[1].2.handle;
+        let old_accumulator = accumulator.clone();
+        accumulator =
+            ac_revoke::<P>(&revocation_sk, &accumulator, &revoked_handle).unwrap();
+        let old_proof = prove(&mut prng, &users[1], &old_accumulator).unwrap();
+        assert!(verify(&users[1], &old_accumulator, &old_proof).is_ok());
+        let err = verify(&users[1], &accumulator, &old_proof).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&ZeiError::CredentialRevokedError.to_string()));
+        let err = ac_update_revocation_witness::<P>(
+            &mut users[1].2,
+            &revoked_handle,
+            &accumulator,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&ZeiError::CredentialRevokedError.to_string()));
+        assert!(ac_verify_revocation_witness::<P>(
+            &revocation_pk,
+            &accumulator,
+            &users[1].2
+        )
+        .is_err());
+        let proof = prove(&mut prng, &users[1], &accumulator).unwrap();
+        assert!(verify(&users[1], &accumulator, &proof).is_err());
+
+        // the other users update their witnesses
+        for user in [0, 2] {
+            let proof = prove(&mut prng, &users[user], &accumulator).unwrap();
+            assert!(verify(&users[user], &accumulator, &proof).is_err());
+            ac_update_revocation_witness::<P>(
+                &mut users[user].2,
+                &revoked_handle,
+                &accumulator,
+            )
+            .unwrap();
+            assert!(ac_verify_revocation_witness::<P>(
+                &revocation_pk,
+                &accumulator,
+                &users[user].2
+            )
+            .is_ok());
+            let proof = prove(&mut prng, &users[user], &accumulator).unwrap();
+            assert!(verify(&users[user], &accumulator, &proof).is_ok());
+        }
+
+        // a plain reveal proof is not a non revocation proof
+        let reveal_sig =
+            ac_reveal::<_, P>(&mut prng, &users[0].0, &users[0].1, &reveal_bitmap)
+                .unwrap();
+        let mut proof = prove(&mut prng, &users[0], &accumulator).unwrap();
+        proof.reveal_sig = reveal_sig;
+        assert!(verify(&users[0], &accumulator, &proof).is_err());
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let proof_de: ACNonRevokedRevealSig<BLSG1, BLSG2, BLSScalar> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(proof, proof_de);
+    }
+}
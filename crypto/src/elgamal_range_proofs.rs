@@ -0,0 +1,373 @@
+// Range proofs directly over ElGamal ciphertexts.
+// An ElGamal ciphertext (e1, e2) = (r*B, m*B + r*PK) contains in e2 a Pedersen commitment to m
+// under the generators (B, PK). Hence a Bulletproofs range proof on e2 with these generators
+// shows that the committed value is in [0, 2^n). A sigma proof of knowledge of (m, r) such that
+// e1 = r*B and e2 = m*B + r*PK binds the randomness of e2 to e1, so that the ciphertext decrypts
+// to the value in range. There is no need for a parallel Pedersen commitment and equality proof.
+// Soundness relies on the binding of the commitment under (B, PK): the prover must not know the
+// secret key of PK.
+
+use crate::basics::elgamal::{ElGamalCiphertext, ElGamalEncKey};
+use crate::sigma::{sigma_prove, sigma_verify, SigmaProof, SigmaTranscript};
+use algebra::groups::{Group, Scalar as _};
+use algebra::ristretto::RistrettoPoint;
+use algebra::ristretto::RistrettoScalar as Scalar;
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use itertools::Itertools;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+use utils::serialization;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ElGamalRangeProof {
+    #[serde(with = "serialization::zei_obj_serde")]
+    range_proof: RangeProof,
+    pok: SigmaProof<Scalar, RistrettoPoint>,
+}
+
+fn init_elgamal_range_proof(
+    transcript: &mut Transcript,
+    base: &RistrettoPoint,
+    public_key: &ElGamalEncKey<RistrettoPoint>,
+    ctexts: &[ElGamalCiphertext<RistrettoPoint>],
+    log_range_upper_bound: usize,
+) {
+    let mut public_elems = vec![base, &public_key.0];
+    for ctext in ctexts {
+        public_elems.push(&ctext.e1);
+        public_elems.push(&ctext.e2);
+    }
+    let bound = Scalar::from_u64(log_range_upper_bound as u64);
+    transcript.init_sigma(b"ElGamalRangeProof", &[&bound], public_elems.as_slice());
+}
+
+// Returns the proof elements, lhs indices matrix and rhs indices vector of the statement
+// e1_i = r_i*B and e2_i = m_i*B + r_i*PK for every i, with secrets (m_0, r_0, m_1, r_1, ...)
+fn elgamal_pok_statement<'a>(
+    identity: &'a RistrettoPoint,
+    base: &'a RistrettoPoint,
+    public_key: &'a ElGamalEncKey<RistrettoPoint>,
+    ctexts: &'a [ElGamalCiphertext<RistrettoPoint>],
+) -> (Vec<&'a RistrettoPoint>, Vec<Vec<usize>>, Vec<usize>) {
+    let mut elems = vec![identity, base, &public_key.0];
+    let mut lhs_matrix = vec![];
+    let mut rhs_vec = vec![];
+    for (i, ctext) in ctexts.iter().enumerate() {
+        let mut row_e1 = vec![0; 2 * ctexts.len()];
+        row_e1[2 * i + 1] = 1; // r_i*B = e1_i
+        let mut row_e2 = vec![0; 2 * ctexts.len()];
+        row_e2[2 * i] = 1; // m_i*B + r_i*PK = e2_i
+        row_e2[2 * i + 1] = 2;
+        lhs_matrix.push(row_e1);
+        lhs_matrix.push(row_e2);
+        rhs_vec.push(elems.len());
+        rhs_vec.push(elems.len() + 1);
+        elems.push(&ctext.e1);
+        elems.push(&ctext.e2);
+    }
+    (elems, lhs_matrix, rhs_vec)
+}
+
+/// I compute a proof that the ciphertexts `ctexts`, obtained by encrypting `values` with
+/// randomness `randomness` under `public_key` and base `base`, encrypt values in
+/// [0..2^{`log_range_upper_bound`}-1]. The number of ciphertexts must be a power of two.
+/// Returns an error if one of the values is out of the range.
+#[allow(clippy::too_many_arguments)]
+pub fn elgamal_range_prove<R: CryptoRng + RngCore>(
+    transcript: &mut Transcript,
+    prng: &mut R,
+    bp_gens: &BulletproofGens,
+    base: &RistrettoPoint,
+    public_key: &ElGamalEncKey<RistrettoPoint>,
+    ctexts: &[ElGamalCiphertext<RistrettoPoint>],
+    values: &[u64],
+    randomness: &[Scalar],
+    log_range_upper_bound: usize,
+) -> Result<ElGamalRangeProof> {
+    if ctexts.is_empty()
+        || ctexts.len() != values.len()
+        || ctexts.len() != randomness.len()
+    {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    if log_range_upper_bound < 64
+        && values.iter().any(|v| v >> log_range_upper_bound != 0)
+    {
+        return Err(eg!(ZeiError::RangeProofProveError));
+    }
+    init_elgamal_range_proof(
+        transcript,
+        base,
+        public_key,
+        ctexts,
+        log_range_upper_bound,
+    );
+
+    let pc_gens = PedersenGens {
+        B: base.0,
+        B_blinding: public_key.0 .0,
+    };
+    let blindings = randomness.iter().map(|r| r.0).collect_vec();
+    let (range_proof, coms) = RangeProof::prove_multiple(
+        bp_gens,
+        &pc_gens,
+        transcript,
+        values,
+        &blindings,
+        log_range_upper_bound,
+    )
+    .c(d!(ZeiError::RangeProofProveError))?;
+    for (com, ctext) in coms.iter().zip(ctexts.iter()) {
+        if *com != ctext.e2.0.compress() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+    }
+
+    let messages = values.iter().map(|v| Scalar::from_u64(*v)).collect_vec();
+    let mut secrets = vec![];
+    for (m, r) in messages.iter().zip(randomness.iter()) {
+        secrets.push(m);
+        secrets.push(r);
+    }
+    let identity = RistrettoPoint::get_identity();
+    let (elems, lhs_matrix, _) =
+        elgamal_pok_statement(&identity, base, public_key, ctexts);
+    let pok = sigma_prove(
+        transcript,
+        prng,
+        elems.as_slice(),
+        lhs_matrix.as_slice(),
+        secrets.as_slice(),
+    );
+    Ok(ElGamalRangeProof { range_proof, pok })
+}
+
+/// I verify a proof that the ciphertexts `ctexts` under `public_key` and base `base` encrypt
+/// values in [0..2^{`log_range_upper_bound`}-1].
+/// State of transcript should match the state just before the proof was computed
+#[allow(clippy::too_many_arguments)]
+pub fn elgamal_range_verify<R: CryptoRng + RngCore>(
+    transcript: &mut Transcript,
+    prng: &mut R,
+    bp_gens: &BulletproofGens,
+    base: &RistrettoPoint,
+    public_key: &ElGamalEncKey<RistrettoPoint>,
+    ctexts: &[ElGamalCiphertext<RistrettoPoint>],
+    proof: &ElGamalRangeProof,
+    log_range_upper_bound: usize,
+) -> Result<()> {
+    if ctexts.is_empty() || proof.pok.commitments.len() != 2 * ctexts.len() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    init_elgamal_range_proof(
+        transcript,
+        base,
+        public_key,
+        ctexts,
+        log_range_upper_bound,
+    );
+
+    let pc_gens = PedersenGens {
+        B: base.0,
+        B_blinding: public_key.0 .0,
+    };
+    let commitments = ctexts
+        .iter()
+        .map(|ctext| ctext.e2.0.compress())
+        .collect_vec();
+    proof
+        .range_proof
+        .verify_multiple_with_rng(
+            bp_gens,
+            &pc_gens,
+            transcript,
+            &commitments,
+            log_range_upper_bound,
+            prng,
+        )
+        .c(d!(ZeiError::RangeProofVerifyError))?;
+
+    let identity = RistrettoPoint::get_identity();
+    let (elems, lhs_matrix, rhs_vec) =
+        elgamal_pok_statement(&identity, base, public_key, ctexts);
+    sigma_verify(
+        transcript,
+        prng,
+        elems.as_slice(),
+        lhs_matrix.as_slice(),
+        rhs_vec.as_slice(),
+        &proof.pok,
+    )
+    .c(d!(ZeiError::ZKProofVerificationError))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{elgamal_range_prove, elgamal_range_verify, ElGamalRangeProof};
+    use crate::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
+    use crate::basics::elgamal::{elgamal_encrypt, elgamal_key_gen, ElGamalCiphertext};
+    use algebra::groups::{GroupArithmetic, Scalar as _};
+    use algebra::ristretto::{RistrettoPoint, RistrettoScalar};
+    use bulletproofs::BulletproofGens;
+    use itertools::Itertools;
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+    use utils::errors::ZeiError;
+
+    #[test]
+    fn elgamal_range_proof() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pc_gens = RistrettoPedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let (_sk, pk) = elgamal_key_gen::<_, RistrettoPoint>(&mut prng, &pc_gens.B);
+
+        let values = [10u64, 1 << 31];
+        let randomness = values
+            .iter()
+            .map(|_| RistrettoScalar::random(&mut prng))
+            .collect_vec();
+        let ctexts = values
+            .iter()
+            .zip(randomness.iter())
+            .map(|(v, r)| {
+                elgamal_encrypt(&pc_gens.B, &RistrettoScalar::from_u64(*v), r, &pk)
+            })
+            .collect_vec();
+
+        let mut prover_transcript = Transcript::new(b"test");
+        let proof = pnk!(elgamal_range_prove(
+            &mut prover_transcript,
+            &mut prng,
+            &bp_gens,
+            &pc_gens.B,
+            &pk,
+            &ctexts,
+            &values,
+            &randomness,
+            32,
+        ));
+        let mut verifier_transcript = Transcript::new(b"test");
+        pnk!(elgamal_range_verify(
+            &mut verifier_transcript,
+            &mut prng,
+            &bp_gens,
+            &pc_gens.B,
+            &pk,
+            &ctexts,
+            &proof,
+            32,
+        ));
+
+        // serialization
+        let json = serde_json::to_string(&proof).unwrap();
+        let proof_de: ElGamalRangeProof = serde_json::from_str(&json).unwrap();
+        let mut verifier_transcript = Transcript::new(b"test");
+        pnk!(elgamal_range_verify(
+            &mut verifier_transcript,
+            &mut prng,
+            &bp_gens,
+            &pc_gens.B,
+            &pk,
+            &ctexts,
+            &proof_de,
+            32,
+        ));
+
+        // a ciphertext with a different first component does not verify
+        let mut bad_ctexts = ctexts.clone();
+        bad_ctexts[0] = ElGamalCiphertext {
+            e1: bad_ctexts[0].e1.double(),
+            e2: bad_ctexts[0].e2,
+        };
+        let mut verifier_transcript = Transcript::new(b"test");
+        msg_eq!(
+            ZeiError::ZKProofVerificationError,
+            elgamal_range_verify(
+                &mut verifier_transcript,
+                &mut prng,
+                &bp_gens,
+                &pc_gens.B,
+                &pk,
+                &bad_ctexts,
+                &proof,
+                32,
+            )
+            .unwrap_err()
+        );
+
+        // mismatched ciphertexts are rejected by the prover
+        let mut prover_transcript = Transcript::new(b"test");
+        assert!(elgamal_range_prove(
+            &mut prover_transcript,
+            &mut prng,
+            &bp_gens,
+            &pc_gens.B,
+            &pk,
+            &bad_ctexts,
+            &values,
+            &randomness,
+            32,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn elgamal_range_proof_out_of_range() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pc_gens = RistrettoPedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let (_sk, pk) = elgamal_key_gen::<_, RistrettoPoint>(&mut prng, &pc_gens.B);
+
+        // the range proof is only sound for 16 bits but the value has 20 bits
+        let value = 1u64 << 20;
+        let r = RistrettoScalar::random(&mut prng);
+        let ctext =
+            elgamal_encrypt(&pc_gens.B, &RistrettoScalar::from_u64(value), &r, &pk);
+        let mut prover_transcript = Transcript::new(b"test");
+        msg_eq!(
+            ZeiError::RangeProofProveError,
+            elgamal_range_prove(
+                &mut prover_transcript,
+                &mut prng,
+                &bp_gens,
+                &pc_gens.B,
+                &pk,
+                &[ctext.clone()],
+                &[value],
+                &[r],
+                16,
+            )
+            .unwrap_err()
+        );
+
+        // a proof for 32 bits is rejected by a 16 bits verifier
+        let mut prover_transcript = Transcript::new(b"test");
+        let proof = pnk!(elgamal_range_prove(
+            &mut prover_transcript,
+            &mut prng,
+            &bp_gens,
+            &pc_gens.B,
+            &pk,
+            &[ctext.clone()],
+            &[value],
+            &[r],
+            32,
+        ));
+        let mut verifier_transcript = Transcript::new(b"test");
+        assert!(elgamal_range_verify(
+            &mut verifier_transcript,
+            &mut prng,
+            &bp_gens,
+            &pc_gens.B,
+            &pk,
+            &[ctext],
+            &proof,
+            16,
+        )
+        .is_err());
+    }
+}
@@ -18,12 +18,19 @@ pub mod bp_circuits;
 pub mod bp_range_proofs;
 pub mod chaum_pedersen;
 pub mod conf_cred_reveal;
+pub mod cred_predicates;
+pub mod cred_revocation;
 pub mod dlog;
+pub mod elgamal_range_proofs;
 pub mod group_signatures;
 pub mod merkle_tree;
 // pub mod inner_product_pairing; // TODO back in when BlsGt is serializable
 pub mod pc_eq_groups;
+pub mod pc_membership;
 pub mod pedersen_elgamal;
+pub mod rerandomization;
+pub mod rsa_accumulator;
 pub mod sigma;
 pub mod solvency;
+pub mod vdf;
 //pub mod whitelist;
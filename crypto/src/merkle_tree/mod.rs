@@ -1,2 +1,3 @@
 pub mod binary_merkle_tree;
 pub mod k_ary_merkle_tree;
+pub mod sparse_merkle_tree;
@@ -0,0 +1,287 @@
+// This file implements an authenticated key-value map as a sparse Merkle tree over the rescue
+// hash, for account-model state: a key is the index of a leaf of a binary tree of fixed depth,
+// the leaf of a key with value v is H(key, v, 0, 0) and the leaf of an absent key is zero.
+// An internal node is H(left, right, 0, 0), hence the root of the empty tree, and of every empty
+// subtree, is known in advance and only the nodes above the present keys are stored.
+// A proof for a key is the list of the siblings of the nodes from the leaf to the root. It shows
+// that the key has a given value or is absent, and, for the same siblings, that an update of the
+// key moves the map from one root to another: the other leaves are left untouched.
+// TurboPlonkConstraintSystem::smt_read and TurboPlonkConstraintSystem::smt_write compute the
+// same roots in circuits.
+
+use crate::basics::hash::rescue::RescueInstance;
+use algebra::bls12_381::BLSScalar;
+use algebra::groups::{Scalar, Zero};
+use ruc::*;
+use std::collections::HashMap;
+use utils::errors::ZeiError;
+
+/// Maximal depth of a sparse Merkle tree, the keys are u64
+pub const SMT_MAX_DEPTH: usize = 64;
+
+/// Siblings of the nodes of the path of a key, from the leaf to the root
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SMTProof {
+    pub siblings: Vec<BLSScalar>,
+}
+
+pub struct SparseMerkleTree {
+    depth: usize,
+    values: HashMap<u64, BLSScalar>,
+    // non-empty nodes, indexed by level (0 for the leaves) and position in the level
+    nodes: HashMap<(usize, u64), BLSScalar>,
+    // empty_nodes[l] is the value of an empty node at level l
+    empty_nodes: Vec<BLSScalar>,
+}
+
+fn hash_node(left: &BLSScalar, right: &BLSScalar) -> BLSScalar {
+    let zero = BLSScalar::zero();
+    RescueInstance::new().rescue_hash(&[*left, *right, zero, zero])[0]
+}
+
+/// Returns the leaf of `key` with value `value`, zero for an absent key
+pub fn smt_leaf(key: u64, value: Option<&BLSScalar>) -> BLSScalar {
+    match value {
+        Some(value) => {
+            let zero = BLSScalar::zero();
+            RescueInstance::new().rescue_hash(&[
+                BLSScalar::from_u64(key),
+                *value,
+                zero,
+                zero,
+            ])[0]
+        }
+        None => BLSScalar::zero(),
+    }
+}
+
+impl SparseMerkleTree {
+    /// Creates an empty tree whose keys are smaller than 2^`depth`.
+    /// Returns an error if `depth` is not between 2 and SMT_MAX_DEPTH.
+    pub fn new(depth: usize) -> Result<SparseMerkleTree> {
+        if !(2..=SMT_MAX_DEPTH).contains(&depth) {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let mut empty_nodes = vec![BLSScalar::zero()];
+        for l in 0..depth {
+            empty_nodes.push(hash_node(&empty_nodes[l], &empty_nodes[l]));
+        }
+        Ok(SparseMerkleTree {
+            depth,
+            values: HashMap::new(),
+            nodes: HashMap::new(),
+            empty_nodes,
+        })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn root(&self) -> BLSScalar {
+        self.node(self.depth, 0)
+    }
+
+    /// Returns the value of `key`, None if it is absent
+    pub fn get(&self, key: u64) -> Option<&BLSScalar> {
+        self.values.get(&key)
+    }
+
+    /// Sets the value of `key` and returns its previous value
+    pub fn insert(&mut self, key: u64, value: BLSScalar) -> Result<Option<BLSScalar>> {
+        self.check_key(key).c(d!())?;
+        self.update_path(key, smt_leaf(key, Some(&value)));
+        Ok(self.values.insert(key, value))
+    }
+
+    /// Removes `key` from the map and returns its previous value
+    pub fn remove(&mut self, key: u64) -> Result<Option<BLSScalar>> {
+        self.check_key(key).c(d!())?;
+        self.update_path(key, BLSScalar::zero());
+        Ok(self.values.remove(&key))
+    }
+
+    /// Returns the proof for the current value of `key`, or its absence
+    pub fn prove(&self, key: u64) -> Result<SMTProof> {
+        self.check_key(key).c(d!())?;
+        let siblings = (0..self.depth)
+            .map(|l| self.node(l, (key >> l) ^ 1))
+            .collect();
+        Ok(SMTProof { siblings })
+    }
+
+    fn check_key(&self, key: u64) -> Result<()> {
+        if self.depth < SMT_MAX_DEPTH && key >> self.depth != 0 {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(())
+    }
+
+    fn node(&self, level: usize, index: u64) -> BLSScalar {
+        match self.nodes.get(&(level, index)) {
+            Some(node) => *node,
+            None => self.empty_nodes[level],
+        }
+    }
+
+    fn update_path(&mut self, key: u64, leaf: BLSScalar) {
+        let mut node = leaf;
+        for l in 0..=self.depth {
+            let index = key.checked_shr(l as u32).unwrap_or(0);
+            if node == self.empty_nodes[l] {
+                self.nodes.remove(&(l, index));
+            } else {
+                self.nodes.insert((l, index), node);
+            }
+            if l < self.depth {
+                let sibling = self.node(l, index ^ 1);
+                node = if index & 1 == 0 {
+                    hash_node(&node, &sibling)
+                } else {
+                    hash_node(&sibling, &node)
+                };
+            }
+        }
+    }
+}
+
+impl SMTProof {
+    /// Returns the root of a tree in which `key` has value `value`, or is absent if `value`
+    /// is None, and the siblings of the path of `key` are the ones of the proof
+    pub fn compute_root(
+        &self,
+        key: u64,
+        value: Option<&BLSScalar>,
+    ) -> Result<BLSScalar> {
+        let depth = self.siblings.len();
+        if !(2..=SMT_MAX_DEPTH).contains(&depth)
+            || (depth < SMT_MAX_DEPTH && key >> depth != 0)
+        {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let mut node = smt_leaf(key, value);
+        for (l, sibling) in self.siblings.iter().enumerate() {
+            node = if (key >> l) & 1 == 0 {
+                hash_node(&node, sibling)
+            } else {
+                hash_node(sibling, &node)
+            };
+        }
+        Ok(node)
+    }
+}
+
+/// Verifies that `key` has value `value` in the map with root `root`, or is absent if `value`
+/// is None
+pub fn smt_verify(
+    root: &BLSScalar,
+    key: u64,
+    value: Option<&BLSScalar>,
+    proof: &SMTProof,
+) -> Result<()> {
+    if proof.compute_root(key, value).c(d!())? != *root {
+        return Err(eg!(ZeiError::MerkleTreeVerificationError));
+    }
+    Ok(())
+}
+
+/// Verifies that setting the value of `key` from `old_value` to `new_value` moves the map from
+/// `old_root` to `new_root`, and leaves the other keys untouched. None stands for an absent key.
+/// `proof` is the proof of `key` before, or equivalently after, the update.
+pub fn smt_verify_update(
+    old_root: &BLSScalar,
+    new_root: &BLSScalar,
+    key: u64,
+    old_value: Option<&BLSScalar>,
+    new_value: Option<&BLSScalar>,
+    proof: &SMTProof,
+) -> Result<()> {
+    smt_verify(old_root, key, old_value, proof).c(d!())?;
+    smt_verify(new_root, key, new_value, proof).c(d!())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use algebra::groups::{One, ScalarArithmetic};
+
+    #[test]
+    fn test_smt() {
+        assert!(SparseMerkleTree::new(1).is_err());
+        assert!(SparseMerkleTree::new(SMT_MAX_DEPTH + 1).is_err());
+
+        let mut tree = pnk!(SparseMerkleTree::new(8));
+        let empty_root = tree.root();
+        let one = BLSScalar::one();
+        let values: Vec<BLSScalar> = (0..4).map(BLSScalar::from_u32).collect();
+        let keys = [0u64, 1, 77, 255];
+        assert!(tree.insert(256, one).is_err());
+        assert!(tree.prove(256).is_err());
+
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let old_root = tree.root();
+            let proof = pnk!(tree.prove(*key));
+            pnk!(smt_verify(&old_root, *key, None, &proof));
+            assert_eq!(pnk!(tree.insert(*key, *value)), None);
+            pnk!(smt_verify_update(
+                &old_root,
+                &tree.root(),
+                *key,
+                None,
+                Some(value),
+                &proof
+            ));
+            assert_eq!(tree.get(*key), Some(value));
+        }
+
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let root = tree.root();
+            let proof = pnk!(tree.prove(*key));
+            pnk!(smt_verify(&root, *key, Some(value), &proof));
+            assert!(smt_verify(&root, *key, None, &proof).is_err());
+            assert!(smt_verify(&root, *key, Some(&value.add(&one)), &proof).is_err());
+            assert!(smt_verify(&root, *key ^ 2, Some(value), &proof).is_err());
+
+            // updates
+            let new_value = value.add(&one);
+            assert_eq!(pnk!(tree.insert(*key, new_value)), Some(*value));
+            pnk!(smt_verify_update(
+                &root,
+                &tree.root(),
+                *key,
+                Some(value),
+                Some(&new_value),
+                &proof
+            ));
+            assert!(smt_verify_update(
+                &root,
+                &tree.root(),
+                *key,
+                Some(value),
+                Some(value),
+                &proof
+            )
+            .is_err());
+            assert_eq!(pnk!(tree.insert(*key, *value)), Some(new_value));
+            assert_eq!(tree.root(), root);
+        }
+
+        // the root only depends on the content of the map
+        for key in keys.iter().rev() {
+            assert!(pnk!(tree.remove(*key)).is_some());
+        }
+        assert_eq!(tree.root(), empty_root);
+        assert!(tree.nodes.is_empty());
+    }
+
+    #[test]
+    fn test_smt_max_depth() {
+        let mut tree = pnk!(SparseMerkleTree::new(SMT_MAX_DEPTH));
+        let value = BLSScalar::from_u32(7);
+        for key in [0, u64::MAX] {
+            pnk!(tree.insert(key, value));
+            let proof = pnk!(tree.prove(key));
+            pnk!(smt_verify(&tree.root(), key, Some(&value), &proof));
+        }
+    }
+}
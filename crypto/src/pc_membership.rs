@@ -0,0 +1,234 @@
+// This file implements a proof that a Pedersen commitment C = v * B + r * H opens to one of the
+// values of a public list (v_1, ..., v_n), without revealing which one.
+// The statement is the disjunction over i of "C - v_i * B = r * H is in the span of H", proven
+// with the OR-composition of Cramer, Damgard and Schoenmakers (https://ia.cr/1994/011) of the
+// Schnorr proofs of knowledge of r:
+// - for the branches i != j the prover simulates: it picks a challenge c_i and a response z_i at
+//   random and sets the proof commitment A_i = z_i * H - c_i * (C - v_i * B);
+// - for the opened branch j it sets A_j = k * H for a random k;
+// - the challenge c of the transcript fixes c_j = c - \sum_{i != j} c_i, and z_j = k + c_j * r.
+// The verifier recomputes the proof commitments A_i from (c_i, z_i), so the proof only contains
+// the challenges and the responses, and checks that the challenges add up to c.
+
+use crate::sigma::SigmaTranscript;
+use algebra::groups::Group;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PCMembershipProof<S> {
+    pub(crate) challenges: Vec<S>,
+    pub(crate) responses: Vec<S>,
+}
+
+fn init_pc_membership<G: Group>(
+    transcript: &mut Transcript,
+    value_base: &G,
+    blinding_base: &G,
+    commitment: &G,
+    values: &[G::S],
+) {
+    let values_refs: Vec<&G::S> = values.iter().collect();
+    transcript.init_sigma(
+        b"PC membership",
+        &values_refs,
+        &[value_base, blinding_base, commitment],
+    );
+}
+
+// proof commitment of the branch of `value` for the challenge and the response
+fn branch_commitment<G: Group>(
+    value_base: &G,
+    blinding_base: &G,
+    commitment: &G,
+    value: &G::S,
+    challenge: &G::S,
+    response: &G::S,
+) -> G {
+    let statement = commitment.sub(&value_base.mul(value));
+    blinding_base.mul(response).sub(&statement.mul(challenge))
+}
+
+/// Proves that `commitment` = `value_base` * values[`index`] + `blinding_base` * `blinding`,
+/// hiding `index`. Returns an error if it is not the case.
+#[allow(clippy::too_many_arguments)]
+pub fn pc_membership_prove<R: CryptoRng + RngCore, G: Group>(
+    transcript: &mut Transcript,
+    prng: &mut R,
+    value_base: &G,
+    blinding_base: &G,
+    commitment: &G,
+    values: &[G::S],
+    index: usize,
+    blinding: &G::S,
+) -> Result<PCMembershipProof<G::S>> {
+    let value = values.get(index).c(d!(ZeiError::ParameterError))?;
+    if value_base.mul(value).add(&blinding_base.mul(blinding)) != *commitment {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    init_pc_membership(transcript, value_base, blinding_base, commitment, values);
+
+    let mut challenges = vec![];
+    let mut responses = vec![];
+    let k = G::S::random(prng);
+    for (i, value) in values.iter().enumerate() {
+        let proof_commitment = if i == index {
+            challenges.push(G::S::from_u32(0));
+            responses.push(G::S::from_u32(0));
+            blinding_base.mul(&k)
+        } else {
+            let challenge = G::S::random(prng);
+            let response = G::S::random(prng);
+            let proof_commitment = branch_commitment(
+                value_base,
+                blinding_base,
+                commitment,
+                value,
+                &challenge,
+                &response,
+            );
+            challenges.push(challenge);
+            responses.push(response);
+            proof_commitment
+        };
+        transcript.append_proof_commitment(&proof_commitment);
+    }
+
+    let challenge = transcript.get_challenge::<G::S>();
+    let challenges_sum = challenges
+        .iter()
+        .fold(G::S::from_u32(0), |sum, c| sum.add(c));
+    challenges[index] = challenge.sub(&challenges_sum);
+    responses[index] = k.add(&challenges[index].mul(blinding));
+
+    Ok(PCMembershipProof {
+        challenges,
+        responses,
+    })
+}
+
+/// Verifies that `commitment` opens, with respect to `value_base` and `blinding_base`, to one
+/// of the `values`
+pub fn pc_membership_verify<G: Group>(
+    transcript: &mut Transcript,
+    value_base: &G,
+    blinding_base: &G,
+    commitment: &G,
+    values: &[G::S],
+    proof: &PCMembershipProof<G::S>,
+) -> Result<()> {
+    if values.is_empty()
+        || proof.challenges.len() != values.len()
+        || proof.responses.len() != values.len()
+    {
+        return Err(eg!(ZeiError::ZKProofVerificationError));
+    }
+    init_pc_membership(transcript, value_base, blinding_base, commitment, values);
+
+    for ((value, challenge), response) in values
+        .iter()
+        .zip(proof.challenges.iter())
+        .zip(proof.responses.iter())
+    {
+        let proof_commitment = branch_commitment(
+            value_base,
+            blinding_base,
+            commitment,
+            value,
+            challenge,
+            response,
+        );
+        transcript.append_proof_commitment(&proof_commitment);
+    }
+
+    let challenge = transcript.get_challenge::<G::S>();
+    let challenges_sum = proof
+        .challenges
+        .iter()
+        .fold(G::S::from_u32(0), |sum, c| sum.add(c));
+    if challenges_sum != challenge {
+        return Err(eg!(ZeiError::ZKProofVerificationError));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{pc_membership_prove, pc_membership_verify};
+    use algebra::groups::{Group, GroupArithmetic, Scalar as _};
+    use algebra::ristretto::{RistrettoPoint, RistrettoScalar as Scalar};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    #[test]
+    fn test_pc_membership() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let value_base = RistrettoPoint::get_base();
+        let blinding_base = value_base.mul(&Scalar::random(&mut prng));
+        let values: Vec<Scalar> = (0..5).map(|_| Scalar::random(&mut prng)).collect();
+        let blinding = Scalar::random(&mut prng);
+
+        for (index, value) in values.iter().enumerate() {
+            let commitment = value_base.mul(value).add(&blinding_base.mul(&blinding));
+            let proof = pnk!(pc_membership_prove(
+                &mut Transcript::new(b"test"),
+                &mut prng,
+                &value_base,
+                &blinding_base,
+                &commitment,
+                &values,
+                index,
+                &blinding,
+            ));
+            pnk!(pc_membership_verify(
+                &mut Transcript::new(b"test"),
+                &value_base,
+                &blinding_base,
+                &commitment,
+                &values,
+                &proof,
+            ));
+            // the proof is bound to the list of values and to the commitment
+            assert!(pc_membership_verify(
+                &mut Transcript::new(b"test"),
+                &value_base,
+                &blinding_base,
+                &commitment,
+                &values[1..],
+                &proof,
+            )
+            .is_err());
+            let other_commitment = commitment.add(&value_base);
+            assert!(pc_membership_verify(
+                &mut Transcript::new(b"test"),
+                &value_base,
+                &blinding_base,
+                &other_commitment,
+                &values,
+                &proof,
+            )
+            .is_err());
+        }
+
+        // the prover cannot prove a value out of the list
+        let other_value = Scalar::random(&mut prng);
+        let commitment = value_base
+            .mul(&other_value)
+            .add(&blinding_base.mul(&blinding));
+        assert!(pc_membership_prove(
+            &mut Transcript::new(b"test"),
+            &mut prng,
+            &value_base,
+            &blinding_base,
+            &commitment,
+            &values,
+            0,
+            &blinding,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,458 @@
+// Rerandomization of Pedersen commitments and ElGamal ciphertexts.
+// A Pedersen commitment C = m*B + r*H is rerandomized into C' = C + d*H, and an ElGamal
+// ciphertext (e1, e2) = (r*G, m*G + r*PK) into (e1 + d*G, e2 + d*PK), for a fresh scalar d.
+// The rerandomized object hides the same value under the blinding r + d.
+// The proof of rerandomization is a one-of-many proof: for a set of candidate objects
+// C_1, ..., C_n that contains the original one, it proves the knowledge of d such that
+// - Pedersen: C' - C_i = d*H
+// - ElGamal: e1' - e1_i = d*G and e2' - e2_i = d*PK
+// for some i, without revealing i (OR composition of Cramer, Damgard and Schoenmakers). For
+// every branch i but the original one, the prover chooses the challenge c_i and the response
+// z_i and derives the commitments z_i*base - c_i*target_i; the challenge of the original
+// branch is the challenge of the transcript minus the sum of the others. Hence the proof links
+// the rerandomized object to the candidate set only: the candidates are the anonymity set of
+// the rerandomization, e.g. the records posted to a mixer. The size of the proof and the cost
+// of its verification are linear in the number of candidates.
+
+use crate::basics::elgamal::{ElGamalCiphertext, ElGamalEncKey};
+use crate::sigma::SigmaTranscript;
+use algebra::groups::{Group, Scalar, ScalarArithmetic, Zero};
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+/// A proof that an object is a rerandomization of one of a set of candidates, see
+/// rerandomization.rs
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RerandomizationProof<G: Group> {
+    // the commitments, challenge and response of each candidate
+    pub(crate) commitments: Vec<Vec<G>>,
+    pub(crate) challenges: Vec<G::S>,
+    pub(crate) responses: Vec<G::S>,
+}
+
+// Returns the targets of the statements target_j = d*base_j of the candidates `candidates` of
+// the Pedersen commitment `rerandomized`
+fn pedersen_targets<G: Group>(candidates: &[G], rerandomized: &G) -> Vec<Vec<G>> {
+    candidates
+        .iter()
+        .map(|commitment| vec![rerandomized.sub(commitment)])
+        .collect()
+}
+
+// Returns the targets of the statements target_j = d*base_j of the candidates `candidates` of
+// the ElGamal ciphertext `rerandomized`
+fn elgamal_targets<G: Group>(
+    candidates: &[ElGamalCiphertext<G>],
+    rerandomized: &ElGamalCiphertext<G>,
+) -> Vec<Vec<G>> {
+    candidates
+        .iter()
+        .map(|ctext| {
+            vec![
+                rerandomized.e1.sub(&ctext.e1),
+                rerandomized.e2.sub(&ctext.e2),
+            ]
+        })
+        .collect()
+}
+
+// Appends the bases and the targets of the statements to the transcript
+fn init_one_of_many<G: Group>(
+    transcript: &mut Transcript,
+    instance_name: &'static [u8],
+    bases: &[G],
+    targets: &[Vec<G>],
+) {
+    let mut public_elems: Vec<&G> = bases.iter().collect();
+    for branch_targets in targets.iter() {
+        public_elems.extend(branch_targets.iter());
+    }
+    transcript.init_sigma::<G>(instance_name, &[], public_elems.as_slice());
+}
+
+// Proves that targets[i][j] = secret*bases[j] for every j, for i = `index`, in a proof that
+// hides `index` among the indices of `targets`
+fn one_of_many_prove<R: CryptoRng + RngCore, G: Group>(
+    transcript: &mut Transcript,
+    prng: &mut R,
+    instance_name: &'static [u8],
+    bases: &[G],
+    targets: &[Vec<G>],
+    index: usize,
+    secret: &G::S,
+) -> RerandomizationProof<G> {
+    init_one_of_many(transcript, instance_name, bases, targets);
+    let blinding = G::S::random(prng);
+    let mut commitments: Vec<Vec<G>> = Vec::with_capacity(targets.len());
+    let mut challenges = Vec::with_capacity(targets.len());
+    let mut responses = Vec::with_capacity(targets.len());
+    for (i, branch_targets) in targets.iter().enumerate() {
+        if i == index {
+            commitments.push(bases.iter().map(|base| base.mul(&blinding)).collect());
+            challenges.push(G::S::zero());
+            responses.push(G::S::zero());
+        } else {
+            // simulated branch
+            let challenge = G::S::random(prng);
+            let response = G::S::random(prng);
+            commitments.push(
+                bases
+                    .iter()
+                    .zip(branch_targets.iter())
+                    .map(|(base, target)| {
+                        base.mul(&response).sub(&target.mul(&challenge))
+                    })
+                    .collect(),
+            );
+            challenges.push(challenge);
+            responses.push(response);
+        }
+    }
+    for elem in commitments.iter().flatten() {
+        transcript.append_proof_commitment(elem);
+    }
+    let challenge = transcript.get_challenge::<G::S>();
+    let simulated = challenges
+        .iter()
+        .fold(G::S::zero(), |sum, challenge| sum.add(challenge));
+    challenges[index] = challenge.sub(&simulated);
+    responses[index] = blinding.add(&challenges[index].mul(secret));
+    RerandomizationProof {
+        commitments,
+        challenges,
+        responses,
+    }
+}
+
+// Verifies a proof of one_of_many_prove
+fn one_of_many_verify<G: Group>(
+    transcript: &mut Transcript,
+    instance_name: &'static [u8],
+    bases: &[G],
+    targets: &[Vec<G>],
+    proof: &RerandomizationProof<G>,
+) -> Result<()> {
+    let n = targets.len();
+    if n == 0
+        || proof.commitments.len() != n
+        || proof.challenges.len() != n
+        || proof.responses.len() != n
+        || proof
+            .commitments
+            .iter()
+            .any(|branch| branch.len() != bases.len())
+    {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    init_one_of_many(transcript, instance_name, bases, targets);
+    for elem in proof.commitments.iter().flatten() {
+        transcript.append_proof_commitment(elem);
+    }
+    let challenge = transcript.get_challenge::<G::S>();
+    let sum = proof
+        .challenges
+        .iter()
+        .fold(G::S::zero(), |sum, challenge| sum.add(challenge));
+    if sum != challenge {
+        return Err(eg!(ZeiError::ZKProofVerificationError));
+    }
+    for (((branch_commitments, branch_targets), challenge), response) in proof
+        .commitments
+        .iter()
+        .zip(targets.iter())
+        .zip(proof.challenges.iter())
+        .zip(proof.responses.iter())
+    {
+        for ((base, target), commitment) in bases
+            .iter()
+            .zip(branch_targets.iter())
+            .zip(branch_commitments.iter())
+        {
+            if base.mul(response) != commitment.add(&target.mul(challenge)) {
+                return Err(eg!(ZeiError::ZKProofVerificationError));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// I rerandomize the Pedersen commitment `candidates[index]` with blinding generator
+/// `blinding_base`, and return the new commitment, the scalar added to the blinding and a proof
+/// that the new commitment hides the same value as one of the commitments of `candidates`,
+/// which does not reveal which one. Returns ParameterError if `index` is not an index of
+/// `candidates`.
+pub fn pedersen_rerandomize<R: CryptoRng + RngCore, G: Group>(
+    transcript: &mut Transcript,
+    prng: &mut R,
+    blinding_base: &G,
+    candidates: &[G],
+    index: usize,
+) -> Result<(G, G::S, RerandomizationProof<G>)> {
+    let commitment = candidates.get(index).c(d!(ZeiError::ParameterError))?;
+    let blinding_delta = G::S::random(prng);
+    let rerandomized = commitment.add(&blinding_base.mul(&blinding_delta));
+    let proof = one_of_many_prove(
+        transcript,
+        prng,
+        b"Pedersen rerandomization proof",
+        &[blinding_base.clone()],
+        &pedersen_targets(candidates, &rerandomized),
+        index,
+        &blinding_delta,
+    );
+    Ok((rerandomized, blinding_delta, proof))
+}
+
+/// I verify that `rerandomized` is a rerandomization of one of the Pedersen commitments of
+/// `candidates`.
+pub fn pedersen_rerandomize_verify<G: Group>(
+    transcript: &mut Transcript,
+    blinding_base: &G,
+    candidates: &[G],
+    rerandomized: &G,
+    proof: &RerandomizationProof<G>,
+) -> Result<()> {
+    one_of_many_verify(
+        transcript,
+        b"Pedersen rerandomization proof",
+        &[blinding_base.clone()],
+        &pedersen_targets(candidates, rerandomized),
+        proof,
+    )
+    .c(d!())
+}
+
+/// I rerandomize the ElGamal ciphertext `candidates[index]` under `pub_key`, and return the new
+/// ciphertext, the scalar added to the encryption randomness and a proof that the new
+/// ciphertext encrypts the same value as one of the ciphertexts of `candidates`, which does not
+/// reveal which one. Returns ParameterError if `index` is not an index of `candidates`.
+pub fn elgamal_rerandomize<R: CryptoRng + RngCore, G: Group>(
+    transcript: &mut Transcript,
+    prng: &mut R,
+    base: &G,
+    pub_key: &ElGamalEncKey<G>,
+    candidates: &[ElGamalCiphertext<G>],
+    index: usize,
+) -> Result<(ElGamalCiphertext<G>, G::S, RerandomizationProof<G>)> {
+    let ctext = candidates.get(index).c(d!(ZeiError::ParameterError))?;
+    let randomness_delta = G::S::random(prng);
+    let rerandomized = ctext.rerandomize(base, pub_key, &randomness_delta);
+    let proof = one_of_many_prove(
+        transcript,
+        prng,
+        b"ElGamal rerandomization proof",
+        &[base.clone(), pub_key.0.clone()],
+        &elgamal_targets(candidates, &rerandomized),
+        index,
+        &randomness_delta,
+    );
+    Ok((rerandomized, randomness_delta, proof))
+}
+
+/// I verify that `rerandomized` is a rerandomization under `pub_key` of one of the ElGamal
+/// ciphertexts of `candidates`.
+pub fn elgamal_rerandomize_verify<G: Group>(
+    transcript: &mut Transcript,
+    base: &G,
+    pub_key: &ElGamalEncKey<G>,
+    candidates: &[ElGamalCiphertext<G>],
+    rerandomized: &ElGamalCiphertext<G>,
+    proof: &RerandomizationProof<G>,
+) -> Result<()> {
+    one_of_many_verify(
+        transcript,
+        b"ElGamal rerandomization proof",
+        &[base.clone(), pub_key.0.clone()],
+        &elgamal_targets(candidates, rerandomized),
+        proof,
+    )
+    .c(d!())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        elgamal_rerandomize, elgamal_rerandomize_verify, pedersen_rerandomize,
+        pedersen_rerandomize_verify,
+    };
+    use crate::basics::elgamal::{
+        elgamal_decrypt_elem, elgamal_encrypt, elgamal_key_gen,
+    };
+    use algebra::groups::{Group, Scalar, ScalarArithmetic};
+    use algebra::jubjub::JubjubPoint;
+    use algebra::ristretto::RistrettoPoint;
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn pedersen_rerandomization<G: Group>() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let base = G::get_base();
+        let blinding_base = G::get_random_base(&mut prng);
+        let values: Vec<G::S> = (0..4).map(|i| G::S::from_u32(100 + i)).collect();
+        let blindings: Vec<G::S> = (0..4).map(|_| G::S::random(&mut prng)).collect();
+        let candidates: Vec<G> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(m, r)| base.mul(m).add(&blinding_base.mul(r)))
+            .collect();
+
+        for index in 0..candidates.len() {
+            let mut prover_transcript = Transcript::new(b"test");
+            let (rerandomized, delta, proof) = pnk!(pedersen_rerandomize(
+                &mut prover_transcript,
+                &mut prng,
+                &blinding_base,
+                &candidates,
+                index,
+            ));
+            assert!(!candidates.contains(&rerandomized));
+            assert_eq!(
+                rerandomized,
+                base.mul(&values[index])
+                    .add(&blinding_base.mul(&blindings[index].add(&delta)))
+            );
+            let mut verifier_transcript = Transcript::new(b"test");
+            pnk!(pedersen_rerandomize_verify(
+                &mut verifier_transcript,
+                &blinding_base,
+                &candidates,
+                &rerandomized,
+                &proof
+            ));
+
+            // a commitment to another value is not a rerandomization of a candidate
+            let other = rerandomized.add(&base);
+            let mut verifier_transcript = Transcript::new(b"test");
+            assert!(pedersen_rerandomize_verify(
+                &mut verifier_transcript,
+                &blinding_base,
+                &candidates,
+                &other,
+                &proof
+            )
+            .is_err());
+
+            // the original commitment must be a candidate
+            let mut others = candidates.clone();
+            others.remove(index);
+            let mut verifier_transcript = Transcript::new(b"test");
+            assert!(pedersen_rerandomize_verify(
+                &mut verifier_transcript,
+                &blinding_base,
+                &others,
+                &rerandomized,
+                &proof
+            )
+            .is_err());
+        }
+
+        let mut prover_transcript = Transcript::new(b"test");
+        assert!(pedersen_rerandomize(
+            &mut prover_transcript,
+            &mut prng,
+            &blinding_base,
+            &candidates,
+            candidates.len(),
+        )
+        .is_err());
+    }
+
+    fn elgamal_rerandomization<G: Group>() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let base = G::get_base();
+        let (sk, pk) = elgamal_key_gen::<_, G>(&mut prng, &base);
+        let values: Vec<G::S> = (0..3).map(|i| G::S::from_u32(100 + i)).collect();
+        let randomness: Vec<G::S> = (0..3).map(|_| G::S::random(&mut prng)).collect();
+        let candidates: Vec<_> = values
+            .iter()
+            .zip(randomness.iter())
+            .map(|(m, r)| elgamal_encrypt(&base, m, r, &pk))
+            .collect();
+
+        let index = 1;
+        let mut prover_transcript = Transcript::new(b"test");
+        let (rerandomized, delta, proof) = pnk!(elgamal_rerandomize(
+            &mut prover_transcript,
+            &mut prng,
+            &base,
+            &pk,
+            &candidates,
+            index,
+        ));
+        assert!(!candidates.contains(&rerandomized));
+        assert_eq!(
+            rerandomized,
+            elgamal_encrypt(&base, &values[index], &randomness[index].add(&delta), &pk)
+        );
+        assert_eq!(
+            elgamal_decrypt_elem(&rerandomized, &sk),
+            elgamal_decrypt_elem(&candidates[index], &sk)
+        );
+
+        let mut verifier_transcript = Transcript::new(b"test");
+        pnk!(elgamal_rerandomize_verify(
+            &mut verifier_transcript,
+            &base,
+            &pk,
+            &candidates,
+            &rerandomized,
+            &proof
+        ));
+
+        let mut other = rerandomized.clone();
+        other.e2 = other.e2.add(&base);
+        let mut verifier_transcript = Transcript::new(b"test");
+        assert!(elgamal_rerandomize_verify(
+            &mut verifier_transcript,
+            &base,
+            &pk,
+            &candidates,
+            &other,
+            &proof
+        )
+        .is_err());
+
+        // the proof is bound to the public key
+        let (_, pk2) = elgamal_key_gen::<_, G>(&mut prng, &base);
+        let mut verifier_transcript = Transcript::new(b"test");
+        assert!(elgamal_rerandomize_verify(
+            &mut verifier_transcript,
+            &base,
+            &pk2,
+            &candidates,
+            &rerandomized,
+            &proof
+        )
+        .is_err());
+
+        // the challenges must add up to the challenge of the transcript
+        let mut bad_proof = proof.clone();
+        bad_proof.challenges[0] = bad_proof.challenges[0].add(&G::S::from_u32(1));
+        let mut verifier_transcript = Transcript::new(b"test");
+        assert!(elgamal_rerandomize_verify(
+            &mut verifier_transcript,
+            &base,
+            &pk,
+            &candidates,
+            &rerandomized,
+            &bad_proof
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rerandomization_ristretto() {
+        pedersen_rerandomization::<RistrettoPoint>();
+        elgamal_rerandomization::<RistrettoPoint>();
+    }
+
+    #[test]
+    fn rerandomization_jubjub() {
+        pedersen_rerandomization::<JubjubPoint>();
+        elgamal_rerandomization::<JubjubPoint>();
+    }
+}
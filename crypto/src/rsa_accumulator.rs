@@ -0,0 +1,567 @@
+// This file implements a dynamic RSA accumulator (Camenisch-Lysyanskaya, Boneh-Bünz-Fisch).
+// The accumulator lives in a group of unknown order Z_N^*, where N is an RSA modulus whose
+// factorization is unknown to everyone. By default the RSA-2048 challenge modulus is used,
+// so no trusted setup is needed.
+//
+// Elements are first mapped to 256-bit primes using `hash_to_prime`, then:
+// - Accumulator value: A = g^{\prod_i p_i} mod N
+// - Membership witness for p: w = g^{\prod_{i: p_i != p} p_i}, such that w^p = A
+// - Aggregated witness for a set S: w_S such that w_S^{\prod_{p \in S} p} = A. Witnesses are
+//   aggregated using the Shamir trick, and the aggregated witness has the same size as a
+//   single one.
+// - Batch additions: A' = A^{\prod added}, and every witness is updated with a single
+//   exponentiation w' = w^{\prod added}.
+// - Batch deletions: given the (aggregated) witness w_D of the deleted set, A' = w_D. Witnesses
+//   of the remaining elements are updated with the Shamir trick: if w^x = A and A'^d = A with
+//   gcd(x, d) = 1, then w' = w^b * A'^a where a*x + b*d = 1 satisfies w'^x = A'.
+// No pairing is needed by any of the operations.
+
+use num_bigint::{BigInt, BigUint, Sign};
+use ruc::*;
+use sha2::{Digest, Sha256};
+use utils::errors::ZeiError;
+
+const HASH_TO_PRIME_DOMAIN: &[u8] = b"Zei RSA accumulator hash to prime";
+const MILLER_RABIN_ROUNDS: usize = 30;
+const SMALL_PRIMES: [u32; 25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79,
+    83, 89, 97,
+];
+
+// RSA-2048 factoring challenge modulus. Its factorization is unknown.
+pub(crate) const RSA_2048_MODULUS: &[u8] = b"25195908475657893494027183240048398571429282126204032027777137836043662020707595556264018525880784406918290641249515082189298559149176184502808489120072844992687392807287776735971418347270261896375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172654632282216869987549182422433637259085141865462043576798423387184774447920739934236584823824281198163815010674810451660377306056201619676256133844143603833904414952634432190114657544454178424020924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951378636564391212010397122822120720357";
+
+/// Public parameters of the accumulator: an RSA modulus and a generator of Z_N^*.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RsaAccumulatorParams {
+    modulus: BigUint,
+    generator: BigUint,
+}
+
+impl RsaAccumulatorParams {
+    /// Build parameters from a modulus `modulus` and a generator `generator`.
+    /// Whoever knows the factorization of `modulus` can forge witnesses, so the modulus must
+    /// come either from a trusted setup or from a public source such as RSA-2048.
+    pub fn new(modulus: BigUint, generator: BigUint) -> Result<RsaAccumulatorParams> {
+        let one = BigUint::from(1u8);
+        if modulus <= BigUint::from(3u8)
+            || generator <= one
+            || generator >= &modulus - &one
+        {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let (gcd, _, _) = extended_gcd(&to_bigint(&generator), &to_bigint(&modulus));
+        if gcd != BigInt::from(1u8) {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(RsaAccumulatorParams { modulus, generator })
+    }
+
+    /// Parameters over the RSA-2048 challenge modulus with generator 3.
+    pub fn rsa2048() -> RsaAccumulatorParams {
+        RsaAccumulatorParams {
+            modulus: BigUint::parse_bytes(RSA_2048_MODULUS, 10).unwrap(), // safe unwrap
+            generator: BigUint::from(3u8),
+        }
+    }
+
+    pub fn get_modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    pub fn get_generator(&self) -> &BigUint {
+        &self.generator
+    }
+
+    // base^exp mod N for a possibly negative exponent
+    fn pow_signed(&self, base: &BigUint, exp: &BigInt) -> Result<BigUint> {
+        match exp.sign() {
+            Sign::Minus => {
+                let inv = mod_inverse(base, &self.modulus).c(d!())?;
+                Ok(inv.modpow(exp.magnitude(), &self.modulus))
+            }
+            _ => Ok(base.modpow(exp.magnitude(), &self.modulus)),
+        }
+    }
+
+    /// Shamir trick: given w1^x1 = w2^x2 = A with gcd(x1, x2) = 1, compute w such that
+    /// w^{x1 * x2} = A.
+    fn shamir_trick(
+        &self,
+        w1: &BigUint,
+        x1: &BigUint,
+        w2: &BigUint,
+        x2: &BigUint,
+    ) -> Result<BigUint> {
+        let bx1 = to_bigint(x1);
+        let bx2 = to_bigint(x2);
+        let (gcd, a, b) = extended_gcd(&bx1, &bx2);
+        if gcd != BigInt::from(1u8) {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let w1_b = self.pow_signed(w1, &b).c(d!())?;
+        let w2_a = self.pow_signed(w2, &a).c(d!())?;
+        Ok((w1_b * w2_a) % &self.modulus)
+    }
+}
+
+/// Map an arbitrary byte string to a 256-bit prime.
+pub fn hash_to_prime(elem: &[u8]) -> BigUint {
+    let mut counter = 0u64;
+    loop {
+        let mut hasher = Sha256::new();
+        hasher.update(HASH_TO_PRIME_DOMAIN);
+        hasher.update(&(elem.len() as u64).to_le_bytes());
+        hasher.update(elem);
+        hasher.update(&counter.to_le_bytes());
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&hasher.finalize());
+        // force a 256-bit odd candidate
+        bytes[0] |= 0x80;
+        bytes[31] |= 0x01;
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if is_probable_prime(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Miller-Rabin primality test. The witnesses are derived deterministically from `n`.
+pub fn is_probable_prime(n: &BigUint) -> bool {
+    let zero = BigUint::from(0u8);
+    let one = BigUint::from(1u8);
+    let two = BigUint::from(2u8);
+    if n < &two {
+        return false;
+    }
+    for p in SMALL_PRIMES.iter() {
+        let p = BigUint::from(*p);
+        if n == &p {
+            return true;
+        }
+        if n % &p == zero {
+            return false;
+        }
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let n_minus_one = n - &one;
+    let mut d = n_minus_one.clone();
+    let mut s = 0usize;
+    while &d % 2u32 == zero {
+        d >>= 1;
+        s += 1;
+    }
+
+    let n_bytes = n.to_bytes_le();
+    let n_minus_three = n - &BigUint::from(3u8);
+    'witness: for round in 0..MILLER_RABIN_ROUNDS {
+        let mut hasher = Sha256::new();
+        hasher.update(b"Miller-Rabin witness");
+        hasher.update(&n_bytes);
+        hasher.update(&(round as u64).to_le_bytes());
+        // witness a in [2, n - 2]
+        let a = BigUint::from_bytes_le(&hasher.finalize()) % &n_minus_three + &two;
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// A (possibly aggregated) membership witness. It proves that every prime dividing
+/// `exponent` is accumulated, that is, `witness^exponent = A`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MembershipWitness {
+    exponent: BigUint,
+    witness: BigUint,
+}
+
+impl MembershipWitness {
+    pub fn new(exponent: BigUint, witness: BigUint) -> MembershipWitness {
+        MembershipWitness { exponent, witness }
+    }
+
+    pub fn get_exponent(&self) -> &BigUint {
+        &self.exponent
+    }
+
+    pub fn get_witness(&self) -> &BigUint {
+        &self.witness
+    }
+
+    /// Aggregate several membership witnesses for the same accumulator value into a
+    /// constant-size witness for the union of their elements.
+    pub fn aggregate(
+        params: &RsaAccumulatorParams,
+        witnesses: &[MembershipWitness],
+    ) -> Result<MembershipWitness> {
+        if witnesses.is_empty() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let mut aggregated = witnesses[0].clone();
+        for w in witnesses.iter().skip(1) {
+            let witness = params
+                .shamir_trick(
+                    &aggregated.witness,
+                    &aggregated.exponent,
+                    &w.witness,
+                    &w.exponent,
+                )
+                .c(d!())?;
+            aggregated = MembershipWitness {
+                exponent: &aggregated.exponent * &w.exponent,
+                witness,
+            };
+        }
+        Ok(aggregated)
+    }
+
+    /// Update the witness to be valid for the accumulator value resulting from `update`.
+    /// Returns an error if an element of this witness has been deleted.
+    pub fn apply_update(
+        &mut self,
+        params: &RsaAccumulatorParams,
+        update: &AccumulatorUpdate,
+    ) -> Result<()> {
+        let one = BigUint::from(1u8);
+        if update.deleted != one {
+            self.witness = params
+                .shamir_trick(
+                    &self.witness,
+                    &self.exponent,
+                    &update.value_after_deletion,
+                    &update.deleted,
+                )
+                .c(d!(ZeiError::ParameterError))?;
+        }
+        if update.added != one {
+            self.witness = self.witness.modpow(&update.added, &params.modulus);
+        }
+        Ok(())
+    }
+}
+
+/// Update the witnesses `witnesses` to be valid for the accumulator value resulting from
+/// `update`.
+pub fn batch_update_witnesses(
+    params: &RsaAccumulatorParams,
+    witnesses: &mut [MembershipWitness],
+    update: &AccumulatorUpdate,
+) -> Result<()> {
+    for w in witnesses.iter_mut() {
+        w.apply_update(params, update).c(d!())?;
+    }
+    Ok(())
+}
+
+/// Public information broadcast after a batch of deletions followed by a batch of additions.
+/// It is enough for every holder to update its witness without knowing the accumulated set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccumulatorUpdate {
+    deleted: BigUint, // product of the deleted primes
+    value_after_deletion: BigUint,
+    added: BigUint, // product of the added primes
+}
+
+impl AccumulatorUpdate {
+    pub fn get_deleted_product(&self) -> &BigUint {
+        &self.deleted
+    }
+
+    pub fn get_added_product(&self) -> &BigUint {
+        &self.added
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RsaAccumulator {
+    params: RsaAccumulatorParams,
+    value: BigUint,
+}
+
+impl RsaAccumulator {
+    /// Create an empty accumulator, its value is the generator.
+    pub fn new(params: RsaAccumulatorParams) -> RsaAccumulator {
+        let value = params.generator.clone();
+        RsaAccumulator { params, value }
+    }
+
+    pub fn get_params(&self) -> &RsaAccumulatorParams {
+        &self.params
+    }
+
+    pub fn get_value(&self) -> &BigUint {
+        &self.value
+    }
+
+    /// Add a single element to the accumulator and return its membership witness.
+    pub fn add(&mut self, elem: &[u8]) -> MembershipWitness {
+        let prime = hash_to_prime(elem);
+        let witness = self.value.clone();
+        self.value = self.value.modpow(&prime, &self.params.modulus);
+        MembershipWitness {
+            exponent: prime,
+            witness,
+        }
+    }
+
+    /// Delete an element from the accumulator given its membership witness.
+    pub fn delete(&mut self, witness: &MembershipWitness) -> Result<AccumulatorUpdate> {
+        self.batch_update::<&[u8]>(&[], &[witness.clone()])
+            .map(|(update, _)| update)
+    }
+
+    /// Apply a batch of deletions followed by a batch of additions.
+    /// * `additions` - elements to be added
+    /// * `deletions` - membership witnesses of the elements to be removed
+    /// * Returns the update to be broadcast to witness holders, together with the membership
+    /// witnesses of the added elements with respect to the new accumulator value.
+    pub fn batch_update<T: AsRef<[u8]>>(
+        &mut self,
+        additions: &[T],
+        deletions: &[MembershipWitness],
+    ) -> Result<(AccumulatorUpdate, Vec<MembershipWitness>)> {
+        let one = BigUint::from(1u8);
+        let (deleted, value_after_deletion) = if deletions.is_empty() {
+            (one.clone(), self.value.clone())
+        } else {
+            let aggregated =
+                MembershipWitness::aggregate(&self.params, deletions).c(d!())?;
+            self.verify_membership(&aggregated).c(d!())?;
+            (aggregated.exponent, aggregated.witness)
+        };
+
+        let primes: Vec<BigUint> = additions
+            .iter()
+            .map(|elem| hash_to_prime(elem.as_ref()))
+            .collect();
+        let added = primes.iter().fold(one, |acc, p| acc * p);
+        let new_witnesses =
+            compute_membership_witnesses(&self.params, &value_after_deletion, &primes)
+                .into_iter()
+                .zip(primes)
+                .map(|(witness, exponent)| MembershipWitness { exponent, witness })
+                .collect();
+
+        self.value = value_after_deletion.modpow(&added, &self.params.modulus);
+        Ok((
+            AccumulatorUpdate {
+                deleted,
+                value_after_deletion,
+                added,
+            },
+            new_witnesses,
+        ))
+    }
+
+    /// Verify a (possibly aggregated) membership witness against the current value.
+    pub fn verify_membership(&self, witness: &MembershipWitness) -> Result<()> {
+        verify_membership(&self.params, &self.value, witness)
+    }
+}
+
+/// Verify that `witness.witness^witness.exponent = value`.
+pub fn verify_membership(
+    params: &RsaAccumulatorParams,
+    value: &BigUint,
+    witness: &MembershipWitness,
+) -> Result<()> {
+    if witness.exponent <= BigUint::from(1u8) || &witness.witness >= params.get_modulus()
+    {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    if &witness.witness.modpow(&witness.exponent, &params.modulus) == value {
+        Ok(())
+    } else {
+        Err(eg!(ZeiError::ZKProofVerificationError))
+    }
+}
+
+/// Verify that element `elem` is accumulated in `value`.
+pub fn verify_element_membership(
+    params: &RsaAccumulatorParams,
+    value: &BigUint,
+    elem: &[u8],
+    witness: &BigUint,
+) -> Result<()> {
+    let witness = MembershipWitness {
+        exponent: hash_to_prime(elem),
+        witness: witness.clone(),
+    };
+    verify_membership(params, value, &witness)
+}
+
+/// Compute all the membership witnesses `base^{\prod_{j != i} primes[j]}` using the
+/// RootFactor divide and conquer algorithm, in O(n log n) exponentiations instead of O(n^2).
+pub fn compute_membership_witnesses(
+    params: &RsaAccumulatorParams,
+    base: &BigUint,
+    primes: &[BigUint],
+) -> Vec<BigUint> {
+    match primes.len() {
+        0 => vec![],
+        1 => vec![base.clone()],
+        n => {
+            let (left, right) = primes.split_at(n / 2);
+            let one = BigUint::from(1u8);
+            let left_prod = left.iter().fold(one.clone(), |acc, p| acc * p);
+            let right_prod = right.iter().fold(one, |acc, p| acc * p);
+            let left_base = base.modpow(&right_prod, &params.modulus);
+            let right_base = base.modpow(&left_prod, &params.modulus);
+            let mut witnesses = compute_membership_witnesses(params, &left_base, left);
+            witnesses.extend(compute_membership_witnesses(params, &right_base, right));
+            witnesses
+        }
+    }
+}
+
+fn to_bigint(x: &BigUint) -> BigInt {
+    BigInt::from_biguint(Sign::Plus, x.clone())
+}
+
+// Returns (gcd, x, y) such that a * x + b * y = gcd
+fn extended_gcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let zero = BigInt::from(0u8);
+    let (mut old_r, mut r) = (a.clone(), b.clone());
+    let (mut old_s, mut s) = (BigInt::from(1u8), zero.clone());
+    let (mut old_t, mut t) = (zero.clone(), BigInt::from(1u8));
+    while r != zero {
+        let q = &old_r / &r;
+        let new_r = &old_r - &q * &r;
+        old_r = std::mem::replace(&mut r, new_r);
+        let new_s = &old_s - &q * &s;
+        old_s = std::mem::replace(&mut s, new_s);
+        let new_t = &old_t - &q * &t;
+        old_t = std::mem::replace(&mut t, new_t);
+    }
+    (old_r, old_s, old_t)
+}
+
+fn mod_inverse(x: &BigUint, modulus: &BigUint) -> Result<BigUint> {
+    let bmodulus = to_bigint(modulus);
+    let (gcd, s, _) = extended_gcd(&to_bigint(x), &bmodulus);
+    if gcd != BigInt::from(1u8) {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let inv = ((s % &bmodulus) + &bmodulus) % &bmodulus;
+    Ok(inv.magnitude().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_to_prime() {
+        let p1 = hash_to_prime(b"elem 1");
+        let p2 = hash_to_prime(b"elem 2");
+        assert_ne!(p1, p2);
+        assert_eq!(p1, hash_to_prime(b"elem 1"));
+        assert_eq!(p1.bits(), 256);
+        assert!(is_probable_prime(&p1));
+        assert!(is_probable_prime(&BigUint::from(1_000_000_007u64)));
+        assert!(!is_probable_prime(&BigUint::from(1_000_000_011u64 * 7)));
+        // Carmichael number
+        assert!(!is_probable_prime(&BigUint::from(561u32)));
+    }
+
+    #[test]
+    fn test_membership() {
+        let params = RsaAccumulatorParams::rsa2048();
+        let mut acc = RsaAccumulator::new(params.clone());
+        let mut w1 = acc.add(b"elem 1");
+        let (update, mut new_witnesses) = acc
+            .batch_update(&[b"elem 2", b"elem 3", b"elem 4"], &[])
+            .unwrap();
+        assert!(acc.verify_membership(&w1).is_err());
+        w1.apply_update(&params, &update).unwrap();
+        pnk!(acc.verify_membership(&w1));
+        for w in new_witnesses.iter() {
+            pnk!(acc.verify_membership(w));
+        }
+        pnk!(verify_element_membership(
+            &params,
+            acc.get_value(),
+            b"elem 3",
+            new_witnesses[1].get_witness()
+        ));
+        assert!(verify_element_membership(
+            &params,
+            acc.get_value(),
+            b"elem 5",
+            new_witnesses[1].get_witness()
+        )
+        .is_err());
+
+        // aggregation
+        let mut witnesses = vec![w1.clone()];
+        witnesses.append(&mut new_witnesses);
+        let aggregated = MembershipWitness::aggregate(&params, &witnesses).unwrap();
+        pnk!(acc.verify_membership(&aggregated));
+        let mut wrong = aggregated.clone();
+        wrong.exponent = &wrong.exponent * hash_to_prime(b"elem 5");
+        assert!(acc.verify_membership(&wrong).is_err());
+    }
+
+    #[test]
+    fn test_batch_witness_update() {
+        let params = RsaAccumulatorParams::rsa2048();
+        let mut acc = RsaAccumulator::new(params.clone());
+        let (_, mut witnesses) = acc
+            .batch_update(&[b"elem 1", b"elem 2", b"elem 3", b"elem 4"], &[])
+            .unwrap();
+
+        // delete elem 2 and 3, and add elem 5
+        let deleted = vec![witnesses[1].clone(), witnesses[2].clone()];
+        let (update, added) = acc.batch_update(&[b"elem 5"], &deleted).unwrap();
+        let mut remaining = vec![witnesses[0].clone(), witnesses[3].clone()];
+        pnk!(batch_update_witnesses(&params, &mut remaining, &update));
+        for w in remaining.iter().chain(added.iter()) {
+            pnk!(acc.verify_membership(w));
+        }
+
+        // witnesses of deleted elements cannot be updated
+        assert!(witnesses[1].apply_update(&params, &update).is_err());
+        assert!(acc.verify_membership(&witnesses[2]).is_err());
+
+        // deleting an element which is not accumulated fails
+        let mut other = RsaAccumulator::new(params.clone());
+        let not_member = other.add(b"elem 6");
+        assert!(acc.delete(&not_member).is_err());
+
+        // single deletion
+        let update = acc.delete(&remaining[0]).unwrap();
+        let mut w4 = remaining[1].clone();
+        pnk!(w4.apply_update(&params, &update));
+        pnk!(acc.verify_membership(&w4));
+        assert!(acc.verify_membership(&remaining[0]).is_err());
+    }
+
+    #[test]
+    fn test_params() {
+        let params = RsaAccumulatorParams::rsa2048();
+        assert_eq!(params.get_modulus().bits(), 2048);
+        assert!(RsaAccumulatorParams::new(
+            params.get_modulus().clone(),
+            BigUint::from(1u8)
+        )
+        .is_err());
+        assert!(
+            RsaAccumulatorParams::new(BigUint::from(35u8), BigUint::from(7u8)).is_err()
+        );
+        pnk!(RsaAccumulatorParams::new(
+            BigUint::from(35u8),
+            BigUint::from(3u8)
+        ));
+    }
+}
@@ -0,0 +1,243 @@
+// This file implements the Wesolowski verifiable delay function (https://eprint.iacr.org/2018/623)
+// over the RSA group Z_N^* / {1, -1}. By default N is the RSA-2048 challenge modulus, so no
+// trusted setup is needed.
+//
+// - Input: a seed is hashed into the group, x = H(seed).
+// - Evaluation: y = x^{2^T}, computed with T sequential squarings. This is the delay.
+// - Proof: for the prime l = H_prime(x, y, T), pi = x^{floor(2^T / l)}.
+// - Verification: with r = 2^T mod l, check that pi^l * x^r = y. It only needs two small
+//   exponentiations.
+// Elements are represented by min(v, N - v) so that v and -v, which cannot be distinguished
+// without the factorization of N, have a unique representation.
+//
+// The output y is unbiased once the seed is fixed, which makes the VDF usable as a randomness
+// beacon (e.g. for sampling a tracing committee from a public seed), and a value encrypted under
+// a key derived from y can only be revealed after T squarings (time-locked reveal).
+
+use crate::rsa_accumulator::{hash_to_prime, RSA_2048_MODULUS};
+use num_bigint::BigUint;
+use ruc::*;
+use sha2::{Digest, Sha256};
+use utils::errors::ZeiError;
+
+const VDF_HASH_TO_GROUP_DOMAIN: &[u8] = b"Zei VDF hash to group";
+const VDF_CHALLENGE_DOMAIN: &[u8] = b"Zei VDF challenge";
+const VDF_OUTPUT_DOMAIN: &[u8] = b"Zei VDF output";
+
+/// Public parameters of the VDF: an RSA modulus whose factorization is unknown.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VdfParams {
+    modulus: BigUint,
+}
+
+impl VdfParams {
+    /// Build parameters from `modulus`. Whoever knows the factorization of `modulus` can
+    /// evaluate the VDF without delay.
+    pub fn new(modulus: BigUint) -> Result<VdfParams> {
+        if modulus <= BigUint::from(3u8) || &modulus % 2u32 == BigUint::from(0u8) {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(VdfParams { modulus })
+    }
+
+    /// Parameters over the RSA-2048 challenge modulus.
+    pub fn rsa2048() -> VdfParams {
+        VdfParams {
+            modulus: BigUint::parse_bytes(RSA_2048_MODULUS, 10).unwrap(), // safe unwrap
+        }
+    }
+
+    pub fn get_modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    // representative of {v, -v}
+    fn normalize(&self, v: BigUint) -> BigUint {
+        let neg = &self.modulus - &v;
+        if neg < v {
+            neg
+        } else {
+            v
+        }
+    }
+
+    // check that v is the canonical representative of an element, other than the identity
+    // if `non_trivial` is set
+    fn check_element(&self, v: &BigUint, non_trivial: bool) -> Result<()> {
+        let min = if non_trivial { 2u8 } else { 1u8 };
+        if v < &BigUint::from(min) || v * 2u32 >= self.modulus {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(())
+    }
+
+    /// Hash `seed` into the group.
+    pub fn hash_to_group(&self, seed: &[u8]) -> BigUint {
+        let len = (self.modulus.bits() as usize + 7) / 8 + 16;
+        let mut counter = 0u64;
+        loop {
+            let mut bytes = vec![];
+            let mut block = 0u64;
+            while bytes.len() < len {
+                let mut hasher = Sha256::new();
+                hasher.update(VDF_HASH_TO_GROUP_DOMAIN);
+                hasher.update(&(seed.len() as u64).to_le_bytes());
+                hasher.update(seed);
+                hasher.update(&counter.to_le_bytes());
+                hasher.update(&block.to_le_bytes());
+                bytes.extend_from_slice(&hasher.finalize());
+                block += 1;
+            }
+            let x = self.normalize(BigUint::from_bytes_le(&bytes) % &self.modulus);
+            if self.check_element(&x, true).is_ok() {
+                return x;
+            }
+            counter += 1;
+        }
+    }
+
+    fn challenge_prime(&self, input: &BigUint, output: &BigUint, time: u64) -> BigUint {
+        let mut bytes = VDF_CHALLENGE_DOMAIN.to_vec();
+        bytes.extend_from_slice(&self.modulus.to_bytes_le());
+        bytes.extend_from_slice(&time.to_le_bytes());
+        let input_bytes = input.to_bytes_le();
+        bytes.extend_from_slice(&(input_bytes.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&input_bytes);
+        bytes.extend_from_slice(&output.to_bytes_le());
+        hash_to_prime(&bytes)
+    }
+}
+
+/// VDF output together with its proof of correct evaluation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VdfProof {
+    output: BigUint,
+    proof: BigUint,
+}
+
+impl VdfProof {
+    pub fn new(output: BigUint, proof: BigUint) -> VdfProof {
+        VdfProof { output, proof }
+    }
+
+    pub fn get_output(&self) -> &BigUint {
+        &self.output
+    }
+
+    pub fn get_proof(&self) -> &BigUint {
+        &self.proof
+    }
+
+    /// 32 bytes of randomness derived from the VDF output.
+    pub fn randomness(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(VDF_OUTPUT_DOMAIN);
+        hasher.update(&self.output.to_bytes_le());
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&hasher.finalize());
+        bytes
+    }
+}
+
+/// Evaluate the VDF on `seed` with `time` sequential squarings and prove the evaluation.
+pub fn vdf_prove(params: &VdfParams, seed: &[u8], time: u64) -> Result<VdfProof> {
+    if time == 0 {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let n = &params.modulus;
+    let x = params.hash_to_group(seed);
+    let mut y = x.clone();
+    for _ in 0..time {
+        y = &y * &y % n;
+    }
+    let y = params.normalize(y);
+    let l = params.challenge_prime(&x, &y, time);
+
+    // pi = x^{floor(2^T / l)}, computed bit by bit with the long division of 2^T by l
+    let one = BigUint::from(1u8);
+    let mut proof = one.clone();
+    let mut remainder = one;
+    for _ in 0..time {
+        remainder <<= 1;
+        proof = &proof * &proof % n;
+        if remainder >= l {
+            remainder -= &l;
+            proof = proof * &x % n;
+        }
+    }
+    Ok(VdfProof {
+        output: y,
+        proof: params.normalize(proof),
+    })
+}
+
+/// Verify that `proof` is a correct evaluation of the VDF on `seed` with `time` squarings.
+pub fn vdf_verify(
+    params: &VdfParams,
+    seed: &[u8],
+    time: u64,
+    proof: &VdfProof,
+) -> Result<()> {
+    if time == 0 {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    params.check_element(&proof.output, false).c(d!())?;
+    params.check_element(&proof.proof, false).c(d!())?;
+    let n = &params.modulus;
+    let x = params.hash_to_group(seed);
+    let l = params.challenge_prime(&x, &proof.output, time);
+    let r = BigUint::from(2u8).modpow(&BigUint::from(time), &l);
+    let y = proof.proof.modpow(&l, n) * x.modpow(&r, n) % n;
+    if params.normalize(y) != proof.output {
+        return Err(eg!(ZeiError::ZKProofVerificationError));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vdf() {
+        let params = VdfParams::rsa2048();
+        let seed = b"tracing committee epoch 1";
+        let time = 1000;
+        let proof = pnk!(vdf_prove(&params, seed, time));
+        pnk!(vdf_verify(&params, seed, time, &proof));
+
+        // the output is unique: -y is rejected
+        let n = params.get_modulus();
+        let neg_output =
+            VdfProof::new(n - proof.get_output(), proof.get_proof().clone());
+        assert!(vdf_verify(&params, seed, time, &neg_output).is_err());
+
+        assert!(vdf_verify(&params, b"other seed", time, &proof).is_err());
+        assert!(vdf_verify(&params, seed, time + 1, &proof).is_err());
+        let wrong_output = VdfProof::new(
+            proof.get_output() * proof.get_output() % n,
+            proof.get_proof().clone(),
+        );
+        assert!(vdf_verify(&params, seed, time, &wrong_output).is_err());
+
+        // y = x^{2^T}
+        let x = params.hash_to_group(seed);
+        let y = x.modpow(&(BigUint::from(1u8) << time as usize), n);
+        assert_eq!(&params.normalize(y), proof.get_output());
+
+        let proof2 = pnk!(vdf_prove(&params, b"tracing committee epoch 2", time));
+        assert_ne!(proof.randomness(), proof2.randomness());
+        assert!(vdf_prove(&params, seed, 0).is_err());
+    }
+
+    #[test]
+    fn test_vdf_small_modulus() {
+        // 1000003 * 1000033
+        let params = VdfParams::new(BigUint::from(1_000_036_000_099u64)).unwrap();
+        for time in 1..20 {
+            let proof = pnk!(vdf_prove(&params, b"seed", time));
+            pnk!(vdf_verify(&params, b"seed", time, &proof));
+        }
+        assert!(VdfParams::new(BigUint::from(1_000_036_000_098u64)).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+// Compares the MSM of SimdBackend with the one of CpuBackend, run with
+//    cargo bench -p poly-iops --features simd --bench backend
+// The FFTs of SimdBackend are the ones of CpuBackend, hence they are not compared.
+use algebra::{
+    bls12_381::{BLSScalar, BLSG1},
+    groups::{Group, Scalar},
+};
+use poly_iops::backend::{simd::SimdBackend, ArithmeticBackend, CpuBackend};
+use rand_chacha::ChaChaRng;
+use rand_core::SeedableRng;
+use std::time::Instant;
+
+fn time_msm<B: ArithmeticBackend>(
+    backend: &B,
+    scalars: &[&BLSScalar],
+    points: &[&BLSG1],
+) -> (BLSG1, f32) {
+    let start = Instant::now();
+    let result = backend.msm(scalars, points);
+    (result, start.elapsed().as_secs_f32())
+}
+
+fn main() {
+    let mut prng = ChaChaRng::from_seed([0u8; 32]);
+
+    for log_count in [10, 14, 16] {
+        let count = 1 << log_count;
+
+        // Sample random points and scalars
+        let points: Vec<BLSG1> = (0..count)
+            .map(|_| BLSG1::get_random_base(&mut prng))
+            .collect();
+        let scalars: Vec<BLSScalar> =
+            (0..count).map(|_| BLSScalar::random(&mut prng)).collect();
+        let points_ptr = points.iter().collect::<Vec<&BLSG1>>();
+        let scalars_ptr = scalars.iter().collect::<Vec<&BLSScalar>>();
+
+        let (cpu_result, cpu_time) = time_msm(&CpuBackend, &scalars_ptr, &points_ptr);
+        let (simd_result, simd_time) = time_msm(&SimdBackend, &scalars_ptr, &points_ptr);
+        assert_eq!(cpu_result, simd_result);
+
+        println!("MSM of size 2^{}:", log_count);
+        println!("  CpuBackend: {} s", cpu_time);
+        println!("  SimdBackend: {} s", simd_time);
+        println!("  speedup: {}", cpu_time / simd_time);
+    }
+}
@@ -0,0 +1,124 @@
+// This file defines the hooks through which the KZG committer and the PLONK prover run their
+// heaviest arithmetic, multi-scalar multiplications (MSM) and FFTs, so that the arithmetic can
+// be moved to an accelerated implementation (GPU, SIMD) without changing the protocols.
+// CpuBackend, the default, runs the pure Rust implementations: the vartime_multi_exp of the
+// group (the MSM of arkworks for the arkworks curves, Pippenger's algorithm of
+// algebra::multi_exp otherwise) and the FFT of polynomials::field_polynomial. With the feature `simd`,
+// SimdBackend (see simd.rs) recodes the scalars of the MSM with AVX2 or NEON intrinsics, but
+// runs the rest of the MSM and the FFTs with scalar arithmetic, so it is not faster.
+// A backend is selected per call, with PolyComScheme::commit_with_backend, which the KZG
+// scheme implements with the MSM of the backend, and
+// plonk::protocol::prover::prover_with_backend; the functions without a backend use
+// CpuBackend. The results of a backend must be equal to the ones of CpuBackend, as the
+// proofs are checked by verifiers that do not know the backend.
+
+#[cfg(feature = "simd")]
+pub mod simd;
+
+use crate::polynomials::field_polynomial::FpPolynomial;
+use algebra::groups::{Group, Scalar};
+
+/// Implementation of the MSM and FFT of the prover
+pub trait ArithmeticBackend: Sync {
+    /// Returns \sum_i scalars[i] * bases[i], the identity if there is no scalar
+    fn msm<G: Group>(&self, scalars: &[&G::S], bases: &[&G]) -> G;
+
+    /// Returns the evaluations of `polynomial` on the `num_points` powers of the primitive
+    /// root of unity `root`, of the form 2^k or 3 * 2^k
+    fn fft<F: Scalar>(
+        &self,
+        polynomial: &FpPolynomial<F>,
+        root: &F,
+        num_points: usize,
+    ) -> Vec<F>;
+
+    /// Returns the polynomial whose evaluations on the powers of the primitive root of unity
+    /// `root` are `values`
+    fn ifft<F: Scalar>(&self, root: &F, values: &[F]) -> FpPolynomial<F>;
+
+    /// Returns the evaluations of `polynomial` on the coset k * <root>
+    fn coset_fft<F: Scalar>(
+        &self,
+        polynomial: &FpPolynomial<F>,
+        root: &F,
+        num_points: usize,
+        k: &F,
+    ) -> Vec<F> {
+        self.fft(&polynomial.mul_var(k), root, num_points)
+    }
+
+    /// Returns the polynomial whose evaluations on the coset k * <root> are `values`, where
+    /// `k_inv` is the inverse of k
+    fn coset_ifft<F: Scalar>(
+        &self,
+        root: &F,
+        values: &[F],
+        k_inv: &F,
+    ) -> FpPolynomial<F> {
+        self.ifft(root, values).mul_var(k_inv)
+    }
+}
+
+/// The pure Rust backend
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuBackend;
+
+impl ArithmeticBackend for CpuBackend {
+    fn msm<G: Group>(&self, scalars: &[&G::S], bases: &[&G]) -> G {
+        G::vartime_multi_exp(scalars, bases)
+    }
+
+    fn fft<F: Scalar>(
+        &self,
+        polynomial: &FpPolynomial<F>,
+        root: &F,
+        num_points: usize,
+    ) -> Vec<F> {
+        polynomial.fft_with_unity_root(root, num_points)
+    }
+
+    fn ifft<F: Scalar>(&self, root: &F, values: &[F]) -> FpPolynomial<F> {
+        FpPolynomial::ffti(root, values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::backend::{ArithmeticBackend, CpuBackend};
+    use crate::polynomials::field_polynomial::{
+        primitive_nth_root_of_unity, FpPolynomial,
+    };
+    use algebra::bls12_381::{BLSScalar, BLSG1};
+    use algebra::groups::{Group, GroupArithmetic, Scalar, ScalarArithmetic};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_cpu_backend() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let scalars: Vec<BLSScalar> =
+            (0..10).map(|_| BLSScalar::random(&mut prng)).collect();
+        let bases: Vec<BLSG1> =
+            (0..10).map(|_| BLSG1::get_random_base(&mut prng)).collect();
+        let scalars_ref: Vec<&BLSScalar> = scalars.iter().collect();
+        let bases_ref: Vec<&BLSG1> = bases.iter().collect();
+        let mut expected = BLSG1::get_identity();
+        for (scalar, base) in scalars.iter().zip(bases.iter()) {
+            expected = expected.add(&base.mul(scalar));
+        }
+        assert_eq!(CpuBackend.msm(&scalars_ref, &bases_ref), expected);
+        assert_eq!(CpuBackend.msm::<BLSG1>(&[], &[]), BLSG1::get_identity());
+
+        let polynomial = FpPolynomial::from_coefs(scalars.clone());
+        let root = primitive_nth_root_of_unity::<BLSScalar>(12).unwrap();
+        let k = BLSScalar::random(&mut prng);
+        let evals = CpuBackend.coset_fft(&polynomial, &root, 12, &k);
+        let mut point = k;
+        for eval in evals.iter() {
+            assert_eq!(eval, &polynomial.eval(&point));
+            point = point.mul(&root);
+        }
+        let k_inv = k.inv().unwrap();
+        assert_eq!(CpuBackend.coset_ifft(&root, &evals, &k_inv), polynomial);
+    }
+}
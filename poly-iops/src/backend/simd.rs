@@ -0,0 +1,306 @@
+// This file implements SimdBackend, the backend of the feature `simd`. Its MSM is Pippenger's
+// algorithm with signed windows of w bits, as algebra::multi_exp, except that the windows of
+// all the scalars are extracted at once from their 64-bit limbs with vector instructions: AVX2
+// on x86_64 when the CPU supports it (4 scalars per instruction), NEON on aarch64 (2 scalars
+// per instruction), and a portable loop otherwise. The result does not depend on the
+// instruction set.
+// Only this recoding of the scalars is vectorized. The bucket accumulation, that is the group
+// additions which make almost all the cost of an MSM, runs the scalar group arithmetic of
+// algebra, which has no vector implementation, and the FFTs are the ones of CpuBackend. Hence
+// the feature gives no measurable speedup: the MSM of SimdBackend runs at the speed of
+// algebra::multi_exp, and for the groups whose vartime_multi_exp is the MSM of arkworks
+// (e.g. BLSG1, which adds affine points in batches and in parallel with the feature
+// `parallel`) CpuBackend is faster. The benchmark benches/backend.rs compares both backends.
+// SimdBackend is the place where vectorized field and group arithmetic would plug in.
+
+use crate::backend::{ArithmeticBackend, CpuBackend};
+use crate::polynomials::field_polynomial::FpPolynomial;
+use algebra::groups::{Group, GroupArithmetic, Scalar};
+
+/// The backend of the feature `simd`, see simd.rs
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SimdBackend;
+
+// Width of the windows of Pippenger's algorithm for `size` scalars, as in algebra::multi_exp
+fn window_width(size: usize) -> usize {
+    if size < 500 {
+        6
+    } else if size < 800 {
+        7
+    } else {
+        8
+    }
+}
+
+// Writes in out[i] the `width` bits at position `shift` of the integer lo[i] + 2^64 * hi[i],
+// for shift < 64, where hi is only given if shift + width > 64
+fn window_portable(
+    lo: &[u64],
+    hi: Option<&[u64]>,
+    shift: usize,
+    width: usize,
+    out: &mut [u64],
+) {
+    let mask = (1u64 << width) - 1;
+    for (i, (lo, out)) in lo.iter().zip(out.iter_mut()).enumerate() {
+        let mut value = lo >> shift;
+        if let Some(hi) = hi {
+            value |= hi[i] << (64 - shift);
+        }
+        *out = value & mask;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn window_avx2(
+    lo: &[u64],
+    hi: Option<&[u64]>,
+    shift: usize,
+    width: usize,
+    out: &mut [u64],
+) {
+    use std::arch::x86_64::*;
+    let mask = _mm256_set1_epi64x(((1u64 << width) - 1) as i64);
+    let right = _mm256_set1_epi64x(shift as i64);
+    let left = _mm256_set1_epi64x(64 - shift as i64);
+    let n_chunks = lo.len() / 4;
+    for chunk in 0..n_chunks {
+        let x = _mm256_loadu_si256(lo.as_ptr().add(4 * chunk) as *const __m256i);
+        let mut value = _mm256_srlv_epi64(x, right);
+        if let Some(hi) = hi {
+            let y = _mm256_loadu_si256(hi.as_ptr().add(4 * chunk) as *const __m256i);
+            value = _mm256_or_si256(value, _mm256_sllv_epi64(y, left));
+        }
+        _mm256_storeu_si256(
+            out.as_mut_ptr().add(4 * chunk) as *mut __m256i,
+            _mm256_and_si256(value, mask),
+        );
+    }
+    let done = 4 * n_chunks;
+    window_portable(
+        &lo[done..],
+        hi.map(|hi| &hi[done..]),
+        shift,
+        width,
+        &mut out[done..],
+    );
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn window_neon(
+    lo: &[u64],
+    hi: Option<&[u64]>,
+    shift: usize,
+    width: usize,
+    out: &mut [u64],
+) {
+    use std::arch::aarch64::*;
+    let mask = vdupq_n_u64((1u64 << width) - 1);
+    // vshlq_u64 shifts to the right for negative counts
+    let right = vdupq_n_s64(-(shift as i64));
+    let left = vdupq_n_s64(64 - shift as i64);
+    let n_chunks = lo.len() / 2;
+    for chunk in 0..n_chunks {
+        let x = vld1q_u64(lo.as_ptr().add(2 * chunk));
+        let mut value = vshlq_u64(x, right);
+        if let Some(hi) = hi {
+            let y = vld1q_u64(hi.as_ptr().add(2 * chunk));
+            value = vorrq_u64(value, vshlq_u64(y, left));
+        }
+        vst1q_u64(out.as_mut_ptr().add(2 * chunk), vandq_u64(value, mask));
+    }
+    let done = 2 * n_chunks;
+    window_portable(
+        &lo[done..],
+        hi.map(|hi| &hi[done..]),
+        shift,
+        width,
+        &mut out[done..],
+    );
+}
+
+#[cfg(target_arch = "x86_64")]
+fn window(lo: &[u64], hi: Option<&[u64]>, shift: usize, width: usize, out: &mut [u64]) {
+    if is_x86_feature_detected!("avx2") {
+        // safe: the CPU supports AVX2, and the slices have the same length
+        unsafe { window_avx2(lo, hi, shift, width, out) }
+    } else {
+        window_portable(lo, hi, shift, width, out)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn window(lo: &[u64], hi: Option<&[u64]>, shift: usize, width: usize, out: &mut [u64]) {
+    // safe: NEON is part of aarch64, and the slices have the same length
+    unsafe { window_neon(lo, hi, shift, width, out) }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn window(lo: &[u64], hi: Option<&[u64]>, shift: usize, width: usize, out: &mut [u64]) {
+    window_portable(lo, hi, shift, width, out)
+}
+
+// Returns the signed digits in base 2^width of the scalars, in [-2^(width-1), 2^(width-1)),
+// window after window: the j-th digit of the i-th scalar is at index j * scalars.len() + i
+fn signed_digits<S: Scalar>(scalars: &[&S], width: usize) -> (Vec<i64>, usize) {
+    let size = scalars.len();
+    let limbs: Vec<Vec<u64>> =
+        scalars.iter().map(|s| s.get_little_endian_u64()).collect();
+    let n_limbs = limbs.iter().map(|l| l.len()).max().unwrap_or(0);
+    // limb after limb, for the vector instructions
+    let mut columns = vec![0u64; n_limbs * size];
+    for (i, scalar_limbs) in limbs.iter().enumerate() {
+        for (l, limb) in scalar_limbs.iter().enumerate() {
+            columns[l * size + i] = *limb;
+        }
+    }
+    // one more window for the last carry
+    let n_windows = (64 * n_limbs + width - 1) / width + 1;
+    let mut digits = vec![0u64; n_windows * size];
+    for j in 0..n_windows - 1 {
+        let offset = j * width;
+        let (l, shift) = (offset / 64, offset % 64);
+        let lo = &columns[l * size..(l + 1) * size];
+        let hi = if shift + width > 64 && l + 1 < n_limbs {
+            Some(&columns[(l + 1) * size..(l + 2) * size])
+        } else {
+            None
+        };
+        window(lo, hi, shift, width, &mut digits[j * size..(j + 1) * size]);
+    }
+    let radix = 1i64 << width;
+    let mut signed = vec![0i64; n_windows * size];
+    for i in 0..size {
+        let mut carry = 0i64;
+        for j in 0..n_windows {
+            let digit = digits[j * size + i] as i64 + carry;
+            if digit >= radix / 2 {
+                signed[j * size + i] = digit - radix;
+                carry = 1;
+            } else {
+                signed[j * size + i] = digit;
+                carry = 0;
+            }
+        }
+    }
+    (signed, n_windows)
+}
+
+fn pippenger<G: Group>(scalars: &[&G::S], bases: &[&G]) -> G {
+    let size = scalars.len().min(bases.len());
+    if size == 0 {
+        return G::get_identity();
+    }
+    let width = window_width(size);
+    let (digits, n_windows) = signed_digits(&scalars[..size], width);
+    let mut buckets = vec![G::get_identity(); 1 << (width - 1)];
+    let radix = G::S::from_u64(1 << width);
+    let mut result = G::get_identity();
+    for j in (0..n_windows).rev() {
+        for bucket in buckets.iter_mut() {
+            *bucket = G::get_identity();
+        }
+        for (digit, base) in digits[j * size..(j + 1) * size].iter().zip(bases) {
+            if *digit > 0 {
+                let index = (*digit - 1) as usize;
+                buckets[index] = buckets[index].add(base);
+            } else if *digit < 0 {
+                let index = (-*digit - 1) as usize;
+                buckets[index] = buckets[index].sub(base);
+            }
+        }
+        // \sum_b (b + 1) * buckets[b]
+        let mut running_sum = G::get_identity();
+        let mut window_sum = G::get_identity();
+        for bucket in buckets.iter().rev() {
+            running_sum = running_sum.add(bucket);
+            window_sum = window_sum.add(&running_sum);
+        }
+        result = result.mul(&radix).add(&window_sum);
+    }
+    result
+}
+
+impl ArithmeticBackend for SimdBackend {
+    fn msm<G: Group>(&self, scalars: &[&G::S], bases: &[&G]) -> G {
+        pippenger(scalars, bases)
+    }
+
+    fn fft<F: Scalar>(
+        &self,
+        polynomial: &FpPolynomial<F>,
+        root: &F,
+        num_points: usize,
+    ) -> Vec<F> {
+        CpuBackend.fft(polynomial, root, num_points)
+    }
+
+    fn ifft<F: Scalar>(&self, root: &F, values: &[F]) -> FpPolynomial<F> {
+        CpuBackend.ifft(root, values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::backend::simd::{signed_digits, window, window_portable, SimdBackend};
+    use crate::backend::{ArithmeticBackend, CpuBackend};
+    use algebra::bls12_381::{BLSScalar, BLSG1};
+    use algebra::bn254::BN254G1;
+    use algebra::groups::{Group, Scalar, ScalarArithmetic};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_signed_digits() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let mut scalars: Vec<BLSScalar> =
+            (0..11).map(|_| BLSScalar::random(&mut prng)).collect();
+        scalars.push(BLSScalar::from_u32(0));
+        scalars.push(BLSScalar::from_u32(1).neg());
+        let scalars_ref: Vec<&BLSScalar> = scalars.iter().collect();
+        for width in [6, 7, 8] {
+            let (digits, n_windows) = signed_digits(&scalars_ref, width);
+            let radix = BLSScalar::from_u64(1 << width);
+            for (i, scalar) in scalars.iter().enumerate() {
+                let mut value = BLSScalar::from_u32(0);
+                for j in (0..n_windows).rev() {
+                    let digit = digits[j * scalars.len() + i];
+                    assert!(digit >= -(1 << (width - 1)) && digit < 1 << (width - 1));
+                    value = value.mul(&radix).add(&BLSScalar::from_i64(digit));
+                }
+                assert_eq!(&value, scalar);
+            }
+        }
+        // the vector instructions agree with the portable loop
+        let lo: Vec<u64> = (0..9).map(|i| u64::MAX / (i + 1)).collect();
+        let hi: Vec<u64> = (0..9).map(|i| u64::MAX / (i + 3)).collect();
+        let mut portable = vec![0u64; 9];
+        let mut vector = vec![0u64; 9];
+        window_portable(&lo, Some(&hi), 60, 8, &mut portable);
+        window(&lo, Some(&hi), 60, 8, &mut vector);
+        assert_eq!(portable, vector);
+    }
+
+    fn check_msm<G: Group>(sizes: &[usize]) {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        for size in sizes {
+            let scalars: Vec<G::S> =
+                (0..*size).map(|_| G::S::random(&mut prng)).collect();
+            let bases: Vec<G> =
+                (0..*size).map(|_| G::get_random_base(&mut prng)).collect();
+            let scalars_ref: Vec<&G::S> = scalars.iter().collect();
+            let bases_ref: Vec<&G> = bases.iter().collect();
+            assert_eq!(
+                SimdBackend.msm(&scalars_ref, &bases_ref),
+                CpuBackend.msm(&scalars_ref, &bases_ref)
+            );
+        }
+    }
+
+    #[test]
+    fn test_simd_msm() {
+        check_msm::<BLSG1>(&[0, 1, 5, 33]);
+        check_msm::<BN254G1>(&[2, 17]);
+    }
+}
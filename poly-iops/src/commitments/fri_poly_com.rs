@@ -0,0 +1,1039 @@
+// This file implements a polynomial commitment scheme based on FRI
+// (https://eccc.weizmann.ac.il/report/2017/134/), the Fast Reed-Solomon Interactive Oracle
+// Proof of Proximity, which only relies on a collision resistant hash function (SHA3-256).
+//
+// Let n (a power of two) be the number of coefficients of the polynomials of the scheme, and
+// D = s * <w> the coset of size N = 2^l * n of the N-th roots of unity, for the blowup factor
+// 2^l and s the multiplicative generator of the field.
+//
+// commit(f: polynomial with at most n coefficients)
+//    return the root of the Merkle tree whose leaves are the evaluations of f on D
+//
+// Merkle roots are not homomorphic, whereas PLONK combines commitments. A commitment is thus
+// a formal linear combination sum_j c_j * root_j + k of the Merkle roots of the committed
+// polynomials f_j, which the verifier of an evaluation proof opens at the queried positions to
+// compute the combined polynomial f = sum_j c_j * f_j + k. The commitment to a constant
+// polynomial is the constant itself.
+//
+// prove_eval(f: polynomial, z: evaluation point)
+//    Let y = f(z) and g_0 = (f - y) / (X - z), with at most n coefficients if f(z) = y.
+//    Commit phase: for i = 0, ..., log(n) - 1, get a challenge beta_i and fold
+//       g_{i + 1}(x^2) = (g_i(x) + g_i(-x)) / 2 + beta_i * (g_i(x) - g_i(-x)) / (2 * x)
+//    on the domain D_{i + 1} = {x^2, x in D_i} of half the size, committing to g_{i + 1}
+//    with a Merkle tree if it is not the last layer. The last layer g_{log(n)} is a constant.
+//    g_0 is not committed to, the verifier computes it from the openings of the f_j.
+//    Proof of work: find a nonce such that H(seed || nonce) starts with a given number of zero
+//    bits, for a seed derived from the transcript, which makes the queries more expensive to
+//    grind for a cheating prover.
+//    Query phase: for each of the queried positions x in D_0, open g_i(x^{2^i}) and
+//    g_i(-x^{2^i}) in each layer, with the Merkle paths.
+//
+// verify_eval(C: commitment, z, y, proof)
+//    For each query, check the Merkle paths, compute g_0 on the queried positions and check
+//    that each layer is the folding of the previous one, the last one being the constant of
+//    the proof.
+//
+// Proofs have O(q * log(N)^2) hashes for q queries, and a verifier in O(q * log(N)^2) as well.
+// Soundness: in the unique decoding regime, the soundness error of each query is about
+// (1 + 2^{-l}) / 2, so that q queries and b bits of proof of work give about
+// q * log2(2 / (1 + 2^{-l})) + b bits of security, less than q + b bits, e.g. 15 bits for
+// q = 16, l = 2 and b = 4. The usual estimate q * l + b bits, i.e. a soundness error of 2^{-l}
+// per query, relies on the proximity gaps conjecture for Reed-Solomon codes up to the list
+// decoding capacity (https://eprint.iacr.org/2020/654, and the ethSTARK conjecture of
+// https://eprint.iacr.org/2021/582), which is not proven: choose q for the first estimate
+// unless the conjecture is assumed.
+// Zero knowledge: the scheme is binding but not hiding. Each query reveals the evaluations of
+// every committed polynomial at 2 points of D, that is 2 * q evaluations per polynomial and
+// proof, whereas the PLONK prover blinds the witness polynomials with random polynomials of
+// degree 1 and Sigma with one of degree 2, which only hide 2 and 3 evaluations. PLONK proofs
+// with this scheme are therefore NOT zero knowledge and leak information on the witness: use
+// them only for statements without secret witness, e.g. to prove a computation on public
+// data, or use a hiding scheme (KZG, IPA) otherwise.
+
+use crate::commitments::pcs::{
+    HomomorphicPolyComElem, PolyComScheme, PolyComSchemeError, ToBytes,
+};
+use crate::commitments::transcript::PolyComTranscript;
+use crate::plonk::transcript::PlonkTranscript;
+use crate::polynomials::evaluation_domain::EvaluationDomain;
+use crate::polynomials::field_polynomial::FpPolynomial;
+use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
+use merlin::Transcript;
+use ruc::*;
+use sha3::{Digest, Sha3_256};
+use std::sync::Arc;
+use utils::errors::ZeiError;
+
+fn hash_leaf<F: Scalar>(value: &F) -> [u8; 32] {
+    let mut hash = Sha3_256::new();
+    hash.update([0u8]);
+    hash.update(value.to_bytes());
+    hash.finalize().into()
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hash = Sha3_256::new();
+    hash.update([1u8]);
+    hash.update(left);
+    hash.update(right);
+    hash.finalize().into()
+}
+
+// Merkle tree over a vector of field elements, layers[0] being the hashes of the leaves and
+// the last layer the root
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct MerkleTree {
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    // the number of values must be a power of two
+    fn new<F: Scalar>(values: &[F]) -> MerkleTree {
+        let mut layers = vec![values.iter().map(hash_leaf).collect::<Vec<_>>()];
+        while layers.last().unwrap().len() > 1 {
+            let layer = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hash_node(&pair[0], &pair[1]))
+                .collect();
+            layers.push(layer);
+        }
+        MerkleTree { layers }
+    }
+
+    fn root(&self) -> [u8; 32] {
+        self.layers.last().unwrap()[0]
+    }
+
+    fn path(&self, mut index: usize) -> Vec<[u8; 32]> {
+        let mut path = vec![];
+        for layer in self.layers[..self.layers.len() - 1].iter() {
+            path.push(layer[index ^ 1]);
+            index >>= 1;
+        }
+        path
+    }
+}
+
+fn verify_path<F: Scalar>(
+    root: &[u8; 32],
+    mut index: usize,
+    value: &F,
+    path: &[[u8; 32]],
+) -> bool {
+    let mut node = hash_leaf(value);
+    for sibling in path {
+        node = if index & 1 == 0 {
+            hash_node(&node, sibling)
+        } else {
+            hash_node(sibling, &node)
+        };
+        index >>= 1;
+    }
+    index == 0 && &node == root
+}
+
+/// Evaluations of a committed polynomial on the domain of the scheme, and their Merkle tree
+#[derive(Debug, PartialEq, Eq)]
+pub struct FRICodeword<F> {
+    polynomial: FpPolynomial<F>,
+    evals: Vec<F>,
+    tree: MerkleTree,
+}
+
+// Adds the terms of `other` to `terms`, both sorted by root, keeping the non zero terms
+fn merge_terms<K: Clone, F: Scalar>(
+    terms: &[(K, F)],
+    other: &[(K, F)],
+    root: impl Fn(&K) -> [u8; 32],
+) -> Vec<(K, F)> {
+    let mut merged: Vec<(K, F)> = terms.to_vec();
+    for (key, coef) in other {
+        match merged.binary_search_by_key(&root(key), |(k, _)| root(k)) {
+            Ok(i) => merged[i].1.add_assign(coef),
+            Err(i) => merged.insert(i, (key.clone(), *coef)),
+        }
+    }
+    merged.retain(|(_, coef)| !coef.is_zero());
+    merged
+}
+
+/// Commitment to the polynomial sum_j c_j * f_j + k, as the Merkle roots of the committed
+/// polynomials f_j with their coefficients c_j, sorted by root, and the constant k
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FRICommitment<F> {
+    pub(crate) terms: Vec<([u8; 32], F)>,
+    pub(crate) constant: F,
+}
+
+impl<F: Scalar> ToBytes for FRICommitment<F> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = (self.terms.len() as u64).to_le_bytes().to_vec();
+        for (root, coef) in self.terms.iter() {
+            bytes.extend_from_slice(root);
+            bytes.extend_from_slice(&coef.to_bytes());
+        }
+        bytes.extend_from_slice(&self.constant.to_bytes());
+        bytes
+    }
+}
+
+impl<F: Scalar> HomomorphicPolyComElem for FRICommitment<F> {
+    type Scalar = F;
+
+    // commitment to the polynomial 1
+    fn get_base() -> Self {
+        FRICommitment {
+            terms: vec![],
+            constant: F::one(),
+        }
+    }
+
+    fn get_identity() -> Self {
+        FRICommitment {
+            terms: vec![],
+            constant: F::zero(),
+        }
+    }
+
+    fn op(&self, other: &Self) -> Self {
+        FRICommitment {
+            terms: merge_terms(&self.terms, &other.terms, |root| *root),
+            constant: self.constant.add(&other.constant),
+        }
+    }
+
+    fn op_assign(&mut self, other: &Self) {
+        *self = self.op(other);
+    }
+
+    fn exp(&self, exp: &F) -> Self {
+        let mut result = self.clone();
+        result.exp_assign(exp);
+        result
+    }
+
+    fn exp_assign(&mut self, exp: &F) {
+        if exp.is_zero() {
+            *self = Self::get_identity();
+            return;
+        }
+        for (_, coef) in self.terms.iter_mut() {
+            coef.mul_assign(exp);
+        }
+        self.constant.mul_assign(exp);
+    }
+
+    fn inv(&self) -> Self {
+        self.exp(&F::one().neg())
+    }
+}
+
+/// Opening of a commitment sum_j c_j * f_j + k: the codewords of the polynomials f_j with
+/// their coefficients c_j, sorted by root, and the constant k
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FRIOpening<F> {
+    pub(crate) terms: Vec<(Arc<FRICodeword<F>>, F)>,
+    pub(crate) constant: F,
+}
+
+impl<F: Scalar> FRIOpening<F> {
+    fn commitment(&self) -> FRICommitment<F> {
+        FRICommitment {
+            terms: self
+                .terms
+                .iter()
+                .map(|(codeword, coef)| (codeword.tree.root(), *coef))
+                .collect(),
+            constant: self.constant,
+        }
+    }
+}
+
+impl<F: Scalar> ToBytes for FRIOpening<F> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.commitment().to_bytes()
+    }
+}
+
+impl<F: Scalar> HomomorphicPolyComElem for FRIOpening<F> {
+    type Scalar = F;
+
+    fn get_base() -> Self {
+        FRIOpening {
+            terms: vec![],
+            constant: F::one(),
+        }
+    }
+
+    fn get_identity() -> Self {
+        FRIOpening {
+            terms: vec![],
+            constant: F::zero(),
+        }
+    }
+
+    fn op(&self, other: &Self) -> Self {
+        FRIOpening {
+            terms: merge_terms(&self.terms, &other.terms, |codeword| {
+                codeword.tree.root()
+            }),
+            constant: self.constant.add(&other.constant),
+        }
+    }
+
+    fn op_assign(&mut self, other: &Self) {
+        *self = self.op(other);
+    }
+
+    fn exp(&self, exp: &F) -> Self {
+        let mut result = self.clone();
+        result.exp_assign(exp);
+        result
+    }
+
+    fn exp_assign(&mut self, exp: &F) {
+        if exp.is_zero() {
+            *self = Self::get_identity();
+            return;
+        }
+        for (_, coef) in self.terms.iter_mut() {
+            coef.mul_assign(exp);
+        }
+        self.constant.mul_assign(exp);
+    }
+
+    fn inv(&self) -> Self {
+        self.exp(&F::one().neg())
+    }
+}
+
+/// Values of a codeword on the queried positions x and -x, with their Merkle paths
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FRIOpenedPair<F> {
+    pub(crate) values: [F; 2],
+    pub(crate) paths: [Vec<[u8; 32]>; 2],
+}
+
+/// Openings of a query: the committed polynomials, in the order of the terms of the
+/// commitment, then the committed layers
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FRIQueryProof<F> {
+    pub(crate) initial: Vec<FRIOpenedPair<F>>,
+    pub(crate) layers: Vec<FRIOpenedPair<F>>,
+}
+
+/// FRI evaluation proof. `quotient` is only set in the proofs of multi_point_prove_eval, see
+/// PolyComScheme::multi_point_reduce.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FRIEvalProof<F> {
+    pub(crate) layer_roots: Vec<[u8; 32]>,
+    pub(crate) final_value: F,
+    pub(crate) nonce: u64,
+    pub(crate) queries: Vec<FRIQueryProof<F>>,
+    pub(crate) quotient: Option<FRICommitment<F>>,
+}
+
+impl<F: Scalar> ToBytes for FRIEvalProof<F> {
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+fn open_pair<F: Scalar>(
+    evals: &[F],
+    tree: &MerkleTree,
+    index: usize,
+) -> FRIOpenedPair<F> {
+    let half = evals.len() / 2;
+    FRIOpenedPair {
+        values: [evals[index], evals[index + half]],
+        paths: [tree.path(index), tree.path(index + half)],
+    }
+}
+
+fn verify_pair<F: Scalar>(
+    root: &[u8; 32],
+    size: usize,
+    index: usize,
+    pair: &FRIOpenedPair<F>,
+) -> bool {
+    let height = size.trailing_zeros() as usize;
+    pair.paths.iter().all(|path| path.len() == height)
+        && verify_path(root, index, &pair.values[0], &pair.paths[0])
+        && verify_path(root, index + size / 2, &pair.values[1], &pair.paths[1])
+}
+
+// (g(x) + g(-x)) / 2 + beta * (g(x) - g(-x)) / (2 * x)
+fn fold_pair<F: Scalar>(g_x: &F, g_minus_x: &F, x_inv: &F, beta: &F, two_inv: &F) -> F {
+    let even = g_x.add(g_minus_x);
+    let odd = g_x.sub(g_minus_x).mul(x_inv).mul(beta);
+    even.add(&odd).mul(two_inv)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FRICommitmentScheme<F> {
+    // number of coefficients of the committed polynomials
+    pub(crate) num_coefs: usize,
+    pub(crate) log_blowup: usize,
+    pub(crate) num_queries: usize,
+    pub(crate) grinding_bits: u32,
+    pub(crate) domain: EvaluationDomain<F>,
+}
+
+impl<F: Scalar> FRICommitmentScheme<F> {
+    /// Creates a new instance of the scheme for polynomials of degree at most `max_degree`,
+    /// at least one and rounded up to a power of two minus one, with a blowup factor
+    /// 2^`log_blowup`, `num_queries` queries and `grinding_bits` bits of proof of work.
+    /// Returns an error if the field has no domain of the required size.
+    /// The scheme is not hiding: PLONK proofs with it are not zero knowledge, see
+    /// fri_poly_com.rs.
+    pub fn new(
+        max_degree: usize,
+        log_blowup: usize,
+        num_queries: usize,
+        grinding_bits: u32,
+    ) -> Result<FRICommitmentScheme<F>> {
+        if max_degree == 0 || log_blowup == 0 || num_queries == 0 || grinding_bits > 32 {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let num_coefs = (max_degree + 1).next_power_of_two();
+        let domain = EvaluationDomain::new(num_coefs << log_blowup)
+            .and_then(|domain| domain.coset(F::multiplicative_generator()))
+            .c(d!(ZeiError::ParameterError))?;
+        Ok(FRICommitmentScheme {
+            num_coefs,
+            log_blowup,
+            num_queries,
+            grinding_bits,
+            domain,
+        })
+    }
+
+    /// Returns a digest of the parameters of the scheme
+    pub fn digest(&self) -> [u8; 32] {
+        let mut transcript = Transcript::new(b"FRI parameters digest");
+        transcript.append_u64(b"coefficients", self.num_coefs as u64);
+        transcript.append_u64(b"log blowup", self.log_blowup as u64);
+        transcript.append_u64(b"queries", self.num_queries as u64);
+        transcript.append_u64(b"grinding bits", self.grinding_bits as u64);
+        let mut digest = [0u8; 32];
+        transcript.challenge_bytes(b"digest", &mut digest);
+        digest
+    }
+
+    fn codeword_opening(&self, polynomial: FpPolynomial<F>) -> FRIOpening<F> {
+        if polynomial.degree() == 0 {
+            return FRIOpening {
+                terms: vec![],
+                constant: polynomial
+                    .get_coefs_ref()
+                    .get(0)
+                    .cloned()
+                    .unwrap_or(F::zero()),
+            };
+        }
+        let evals = self.domain.fft(&polynomial);
+        let tree = MerkleTree::new(&evals);
+        FRIOpening {
+            terms: vec![(
+                Arc::new(FRICodeword {
+                    polynomial,
+                    evals,
+                    tree,
+                }),
+                F::one(),
+            )],
+            constant: F::zero(),
+        }
+    }
+
+    // Appends the statement C, z, y to the transcript
+    fn fri_transcript_init<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &FRICommitment<F>,
+        point: &F,
+        value: &F,
+    ) {
+        transcript.append_message(b"Domain Separator", b"New PCS-FRI-Eval Protocol");
+        transcript.append_message(b"parameters", &self.digest());
+        transcript.append_commitment::<FRICommitment<F>>(commitment);
+        transcript.append_field_elem(point);
+        transcript.append_field_elem(value);
+    }
+
+    // Derives the proof of work seed and the query positions, in [0, N/2), after checking the
+    // proof of work of the prover if `nonce` is given, or computing it otherwise
+    fn fri_queries<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        nonce: Option<u64>,
+    ) -> Result<(u64, Vec<usize>)> {
+        let mut seed = [0u8; 32];
+        transcript.challenge_bytes(b"proof of work seed", &mut seed);
+        let pow_ok = |nonce: u64| {
+            let mut hash = Sha3_256::new();
+            hash.update(seed);
+            hash.update(nonce.to_le_bytes());
+            let digest: [u8; 32] = hash.finalize().into();
+            u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]])
+                .leading_zeros()
+                >= self.grinding_bits
+        };
+        let nonce = match nonce {
+            Some(nonce) if pow_ok(nonce) => nonce,
+            Some(_) => return Err(eg!(PolyComSchemeError::PCSProveEvalError)),
+            None => (0u64..).find(|nonce| pow_ok(*nonce)).unwrap(),
+        };
+        transcript.append_u64(b"nonce", nonce);
+
+        let half = self.domain.size() / 2;
+        let queries = (0..self.num_queries)
+            .map(|_| {
+                let mut bytes = [0u8; 8];
+                transcript.challenge_bytes(b"query", &mut bytes);
+                (u64::from_le_bytes(bytes) % half as u64) as usize
+            })
+            .collect();
+        Ok((nonce, queries))
+    }
+
+    // Proves that the polynomial of `opening`, committed in `commitment`, evaluates to
+    // `value` on `point`
+    fn fri_prove<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &FRICommitment<F>,
+        opening: &FRIOpening<F>,
+        point: &F,
+        value: &F,
+    ) -> Result<FRIEvalProof<F>> {
+        self.fri_transcript_init(transcript, commitment, point, value);
+
+        // g_0 = (f - y) / (X - z) on D
+        let mut g = vec![opening.constant.sub(value); self.domain.size()];
+        for (codeword, coef) in opening.terms.iter() {
+            for (g_i, eval) in g.iter_mut().zip(codeword.evals.iter()) {
+                g_i.add_assign(&eval.mul(coef));
+            }
+        }
+        for (g_i, x) in g.iter_mut().zip(self.domain.elements()) {
+            let x_minus_z_inv = x
+                .sub(point)
+                .inv()
+                .c(d!(PolyComSchemeError::PCSProveEvalError))?;
+            g_i.mul_assign(&x_minus_z_inv);
+        }
+
+        // commit phase, layers[i] being g_{i + 1} and its tree
+        let two_inv = F::from_u32(2).inv().c(d!())?;
+        let rounds = self.num_coefs.trailing_zeros() as usize;
+        let mut layers: Vec<(Vec<F>, MerkleTree)> = vec![];
+        let mut layer_roots = vec![];
+        let mut final_value = F::zero();
+        let mut offset = *self.domain.offset();
+        let mut generator = *self.domain.generator();
+        for round in 0..rounds {
+            let beta = transcript.get_challenge_field_elem::<F>(b"beta");
+            let current = if round == 0 { &g } else { &layers[round - 1].0 };
+            let half = current.len() / 2;
+            let offset_inv = offset.inv().c(d!())?;
+            let generator_inv = generator.inv().c(d!())?;
+            let mut x_inv = offset_inv;
+            let mut next = Vec::with_capacity(half);
+            for i in 0..half {
+                next.push(fold_pair(
+                    &current[i],
+                    &current[i + half],
+                    &x_inv,
+                    &beta,
+                    &two_inv,
+                ));
+                x_inv.mul_assign(&generator_inv);
+            }
+            offset = offset.mul(&offset);
+            generator = generator.mul(&generator);
+            if round + 1 < rounds {
+                let tree = MerkleTree::new(&next);
+                transcript.append_message(b"layer root", &tree.root());
+                layer_roots.push(tree.root());
+                layers.push((next, tree));
+            } else {
+                // the last layer is constant if the degree of g_0 is small enough
+                final_value = next[0];
+                if next.iter().any(|value| value != &final_value) {
+                    return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+                }
+            }
+        }
+        transcript.append_field_elem(&final_value);
+
+        // query phase
+        let (nonce, queries) = self.fri_queries(transcript, None).c(d!())?;
+        let queries = queries
+            .into_iter()
+            .map(|index| FRIQueryProof {
+                initial: opening
+                    .terms
+                    .iter()
+                    .map(|(codeword, _)| {
+                        open_pair(&codeword.evals, &codeword.tree, index)
+                    })
+                    .collect(),
+                layers: layers
+                    .iter()
+                    .map(|(evals, tree)| {
+                        open_pair(evals, tree, index % (evals.len() / 2))
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(FRIEvalProof {
+            layer_roots,
+            final_value,
+            nonce,
+            queries,
+            quotient: None,
+        })
+    }
+
+    // Verifies the FRI proof of `proof`, ignoring its quotient
+    fn fri_verify<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &FRICommitment<F>,
+        point: &F,
+        value: &F,
+        proof: &FRIEvalProof<F>,
+    ) -> Result<()> {
+        let rounds = self.num_coefs.trailing_zeros() as usize;
+        if proof.layer_roots.len() + 1 != rounds
+            || proof.queries.len() != self.num_queries
+        {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        self.fri_transcript_init(transcript, commitment, point, value);
+        let mut betas = vec![transcript.get_challenge_field_elem::<F>(b"beta")];
+        for root in proof.layer_roots.iter() {
+            transcript.append_message(b"layer root", root);
+            betas.push(transcript.get_challenge_field_elem::<F>(b"beta"));
+        }
+        transcript.append_field_elem(&proof.final_value);
+        let (_, queries) = self.fri_queries(transcript, Some(proof.nonce)).c(d!())?;
+
+        let two_inv = F::from_u32(2).inv().c(d!())?;
+        let size = self.domain.size();
+        for (index, query) in queries.into_iter().zip(proof.queries.iter()) {
+            if query.initial.len() != commitment.terms.len()
+                || query.layers.len() != proof.layer_roots.len()
+            {
+                return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+            }
+            // g_0 on x and -x
+            let x = self.domain.element(index);
+            let mut g = [commitment.constant.sub(value); 2];
+            for ((root, coef), pair) in commitment.terms.iter().zip(query.initial.iter())
+            {
+                if !verify_pair(root, size, index, pair) {
+                    return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+                }
+                g[0].add_assign(&pair.values[0].mul(coef));
+                g[1].add_assign(&pair.values[1].mul(coef));
+            }
+            g[0].mul_assign(
+                &x.sub(point)
+                    .inv()
+                    .c(d!(PolyComSchemeError::PCSProveEvalError))?,
+            );
+            g[1].mul_assign(
+                &x.neg()
+                    .sub(point)
+                    .inv()
+                    .c(d!(PolyComSchemeError::PCSProveEvalError))?,
+            );
+
+            let mut folded =
+                fold_pair(&g[0], &g[1], &x.inv().c(d!())?, &betas[0], &two_inv);
+            let mut layer_size = size / 2;
+            let mut position = index;
+            let mut x = x.mul(&x);
+            for ((root, pair), beta) in proof
+                .layer_roots
+                .iter()
+                .zip(query.layers.iter())
+                .zip(betas[1..].iter())
+            {
+                let pair_index = position % (layer_size / 2);
+                let expected = &pair.values[usize::from(position >= layer_size / 2)];
+                if !verify_pair(root, layer_size, pair_index, pair)
+                    || expected != &folded
+                {
+                    return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+                }
+                // x is the element of the layer at `position`, -x if it is in the upper half
+                if position >= layer_size / 2 {
+                    x = x.neg();
+                }
+                folded = fold_pair(
+                    &pair.values[0],
+                    &pair.values[1],
+                    &x.inv().c(d!())?,
+                    beta,
+                    &two_inv,
+                );
+                position = pair_index;
+                layer_size /= 2;
+                x = x.mul(&x);
+            }
+            if folded != proof.final_value {
+                return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Scalar> PolyComScheme for FRICommitmentScheme<F> {
+    type Field = F;
+    type Commitment = FRICommitment<F>;
+    type EvalProof = FRIEvalProof<F>;
+    type Opening = FRIOpening<F>;
+
+    fn commit(
+        &self,
+        polynomial: FpPolynomial<F>,
+    ) -> Result<(Self::Commitment, Self::Opening)> {
+        if polynomial.get_coefs_ref().len() > self.num_coefs {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        let opening = self.codeword_opening(polynomial);
+        Ok((opening.commitment(), opening))
+    }
+
+    fn opening(&self, polynomial: &FpPolynomial<F>) -> Self::Opening {
+        self.codeword_opening(polynomial.clone())
+    }
+
+    fn eval_opening(&self, opening: &Self::Opening, point: &F) -> F {
+        let mut value = opening.constant;
+        for (codeword, coef) in opening.terms.iter() {
+            value.add_assign(&codeword.polynomial.eval(point).mul(coef));
+        }
+        value
+    }
+
+    fn commitment_from_opening(&self, opening: &Self::Opening) -> Self::Commitment {
+        opening.commitment()
+    }
+
+    fn polynomial_from_opening_ref(&self, opening: &Self::Opening) -> FpPolynomial<F> {
+        let mut polynomial = FpPolynomial::from_coefs(vec![opening.constant]);
+        for (codeword, coef) in opening.terms.iter() {
+            polynomial.add_assign(&codeword.polynomial.mul_scalar(coef));
+        }
+        polynomial
+    }
+
+    fn polynomial_from_opening(&self, opening: Self::Opening) -> FpPolynomial<F> {
+        self.polynomial_from_opening_ref(&opening)
+    }
+
+    fn prove_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        opening: &Self::Opening,
+        point: &F,
+        max_degree: usize,
+    ) -> Result<(F, Self::EvalProof)> {
+        if self.polynomial_from_opening_ref(opening).degree() > max_degree {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        let value = self.eval_opening(opening, point);
+        let proof = self
+            .fri_prove(transcript, &opening.commitment(), opening, point, &value)
+            .c(d!())?;
+        Ok((value, proof))
+    }
+
+    fn verify_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &Self::Commitment,
+        _degree: usize,
+        point: &F,
+        value: &F,
+        proof: &Self::EvalProof,
+    ) -> Result<()> {
+        if proof.quotient.is_some() {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        self.fri_verify(transcript, commitment, point, value, proof)
+            .c(d!())
+    }
+
+    fn multi_point_prove_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        openings: &[&Self::Opening],
+        points: &[&[F]],
+        max_degree: usize,
+    ) -> Result<(Vec<Vec<F>>, Self::EvalProof)> {
+        if openings.len() != points.len() {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        let polys: Vec<FpPolynomial<F>> = openings
+            .iter()
+            .map(|opening| self.polynomial_from_opening_ref(opening))
+            .collect();
+        if polys.iter().any(|poly| poly.degree() > max_degree) {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        let values: Vec<Vec<F>> = polys
+            .iter()
+            .zip(points)
+            .map(|(poly, points_i)| points_i.iter().map(|x| poly.eval(x)).collect())
+            .collect();
+        let gamma = Self::multi_point_eval_challenge(
+            transcript,
+            commitments,
+            max_degree,
+            points,
+            &values,
+        )
+        .c(d!())?;
+
+        // h = sum_i gamma^i * q_i for f_i = q_i * Z_{S_i} + r_i
+        let mut h = FpPolynomial::zero();
+        let mut gamma_i = F::one();
+        for (poly, points_i) in polys.iter().zip(points) {
+            let (q_i, _) = poly.div_rem(&FpPolynomial::from_zeroes(points_i));
+            h.add_assign(&q_i.mul_scalar(&gamma_i));
+            gamma_i.mul_assign(&gamma);
+        }
+        let (quotient, quotient_opening) = self.commit(h).c(d!())?;
+        let (z, scalars, z_t_eval, r_eval) =
+            Self::multi_point_reduce(transcript, points, &values, &gamma, &quotient)
+                .c(d!())?;
+
+        let mut g = quotient_opening.exp(&z_t_eval.neg());
+        for (opening, scalar) in openings.iter().zip(scalars.iter()) {
+            g.op_assign(&opening.exp(scalar));
+        }
+        g.constant.sub_assign(&r_eval);
+        let mut proof = self
+            .fri_prove(transcript, &g.commitment(), &g, &z, &F::zero())
+            .c(d!())?;
+        proof.quotient = Some(quotient);
+        Ok((values, proof))
+    }
+
+    fn multi_point_verify_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        max_degree: usize,
+        points: &[&[F]],
+        values: &[Vec<F>],
+        proof: &Self::EvalProof,
+    ) -> Result<()> {
+        let quotient = proof
+            .quotient
+            .as_ref()
+            .ok_or(eg!(PolyComSchemeError::PCSProveEvalError))?;
+        let gamma = Self::multi_point_eval_challenge(
+            transcript,
+            commitments,
+            max_degree,
+            points,
+            values,
+        )
+        .c(d!())?;
+        let (z, scalars, z_t_eval, r_eval) =
+            Self::multi_point_reduce(transcript, points, values, &gamma, quotient)
+                .c(d!())?;
+        let mut g = quotient.exp(&z_t_eval.neg());
+        for (commitment, scalar) in commitments.iter().zip(scalars.iter()) {
+            g.op_assign(&commitment.exp(scalar));
+        }
+        g.constant.sub_assign(&r_eval);
+        self.fri_verify(transcript, &g, &z, &F::zero(), proof)
+            .c(d!())
+    }
+
+    fn srs_digest(&self) -> Vec<u8> {
+        self.digest().to_vec()
+    }
+
+    fn max_degree(&self) -> usize {
+        self.num_coefs - 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::fri_poly_com::FRICommitmentScheme;
+    use crate::commitments::pcs::{HomomorphicPolyComElem, PolyComScheme};
+    use crate::polynomials::field_polynomial::FpPolynomial;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn random_poly(degree: usize, prng: &mut ChaChaRng) -> FpPolynomial<BLSScalar> {
+        FpPolynomial::from_coefs(
+            (0..degree + 1).map(|_| BLSScalar::random(prng)).collect(),
+        )
+    }
+
+    #[test]
+    fn test_fri_eval() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs = pnk!(FRICommitmentScheme::<BLSScalar>::new(15, 2, 20, 4));
+        assert_eq!(pcs.max_degree(), 15);
+        for degree in [1, 7, 15] {
+            let (com, open) = pnk!(pcs.commit(random_poly(degree, &mut prng)));
+            let point = BLSScalar::random(&mut prng);
+            let (value, proof) = {
+                let mut transcript = Transcript::new(b"TestFRI");
+                pnk!(pcs.prove_eval(&mut transcript, &open, &point, degree))
+            };
+            assert_eq!(value, pcs.eval_opening(&open, &point));
+            let mut transcript = Transcript::new(b"TestFRI");
+            assert!(pcs
+                .verify_eval(&mut transcript, &com, degree, &point, &value, &proof)
+                .is_ok());
+            let mut transcript = Transcript::new(b"TestFRI");
+            let wrong_value = value.add(&BLSScalar::one());
+            assert!(pcs
+                .verify_eval(&mut transcript, &com, degree, &point, &wrong_value, &proof)
+                .is_err());
+            let mut transcript = Transcript::new(b"TestFRI");
+            let mut wrong_proof = proof.clone();
+            wrong_proof.final_value.add_assign(&BLSScalar::one());
+            assert!(pcs
+                .verify_eval(&mut transcript, &com, degree, &point, &value, &wrong_proof)
+                .is_err());
+            let mut transcript = Transcript::new(b"TestFRI");
+            let mut wrong_proof = proof.clone();
+            wrong_proof.queries[3].initial[0].values[1].add_assign(&BLSScalar::one());
+            assert!(pcs
+                .verify_eval(&mut transcript, &com, degree, &point, &value, &wrong_proof)
+                .is_err());
+        }
+        assert!(pcs.commit(random_poly(16, &mut prng)).is_err());
+    }
+
+    #[test]
+    fn test_fri_homomorphic_commitments() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs = pnk!(FRICommitmentScheme::<BLSScalar>::new(15, 2, 20, 4));
+        let poly1 = random_poly(15, &mut prng);
+        let poly2 = random_poly(10, &mut prng);
+        let (com1, open1) = pnk!(pcs.commit(poly1.clone()));
+        let (com2, open2) = pnk!(pcs.commit(poly2.clone()));
+        let two = BLSScalar::from_u32(2);
+        let (com3, open3) = pnk!(pcs.commit(FpPolynomial::from_coefs(vec![two])));
+
+        // 2 * f_1 - f_2 + 2
+        let com = com1.exp(&two).op(&com2.inv()).op(&com3);
+        let open = open1.exp(&two).op(&open2.inv()).op(&open3);
+        assert_eq!(pcs.commitment_from_opening(&open), com);
+        let mut poly = poly1.mul_scalar(&two).sub(&poly2);
+        poly.add_assign(&FpPolynomial::from_coefs(vec![two]));
+        assert_eq!(pcs.polynomial_from_opening_ref(&open), poly);
+        // the order of the operations does not matter
+        assert_eq!(com3.op(&com2.inv()).op(&com1.exp(&two)), com);
+        assert_eq!(com1.op(&com1.inv()), com3.exp(&BLSScalar::from_u32(0)));
+
+        let point = BLSScalar::random(&mut prng);
+        let (value, proof) = {
+            let mut transcript = Transcript::new(b"TestFRI");
+            pnk!(pcs.prove_eval(&mut transcript, &open, &point, 15))
+        };
+        assert_eq!(value, poly.eval(&point));
+        let mut transcript = Transcript::new(b"TestFRI");
+        assert!(pcs
+            .verify_eval(&mut transcript, &com, 15, &point, &value, &proof)
+            .is_ok());
+        let mut transcript = Transcript::new(b"TestFRI");
+        assert!(pcs
+            .verify_eval(&mut transcript, &com1, 15, &point, &value, &proof)
+            .is_err());
+    }
+
+    #[test]
+    fn test_fri_multi_point_eval() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs = pnk!(FRICommitmentScheme::<BLSScalar>::new(15, 2, 20, 4));
+        let (coms, opens): (Vec<_>, Vec<_>) = (0..3)
+            .map(|_| pnk!(pcs.commit(random_poly(15, &mut prng))))
+            .unzip();
+        let a = BLSScalar::random(&mut prng);
+        let b = BLSScalar::random(&mut prng);
+        let points: [&[BLSScalar]; 3] = [&[a, b], &[b], &[a]];
+        let coms_ref: Vec<_> = coms.iter().collect();
+        let opens_ref: Vec<_> = opens.iter().collect();
+        let (values, proof) = {
+            let mut transcript = Transcript::new(b"TestFRI");
+            pnk!(pcs.multi_point_prove_eval(
+                &mut transcript,
+                &coms_ref,
+                &opens_ref,
+                &points,
+                15
+            ))
+        };
+        let mut transcript = Transcript::new(b"TestFRI");
+        assert!(pcs
+            .multi_point_verify_eval(
+                &mut transcript,
+                &coms_ref,
+                15,
+                &points,
+                &values,
+                &proof
+            )
+            .is_ok());
+        let mut wrong_values = values.clone();
+        wrong_values[0][0].add_assign(&BLSScalar::one());
+        let mut transcript = Transcript::new(b"TestFRI");
+        assert!(pcs
+            .multi_point_verify_eval(
+                &mut transcript,
+                &coms_ref,
+                15,
+                &points,
+                &wrong_values,
+                &proof
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_fri_proof_of_work() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs = pnk!(FRICommitmentScheme::<BLSScalar>::new(7, 1, 10, 12));
+        let (com, open) = pnk!(pcs.commit(random_poly(7, &mut prng)));
+        let point = BLSScalar::random(&mut prng);
+        let (value, mut proof) = {
+            let mut transcript = Transcript::new(b"TestFRI");
+            pnk!(pcs.prove_eval(&mut transcript, &open, &point, 7))
+        };
+        let mut transcript = Transcript::new(b"TestFRI");
+        assert!(pcs
+            .verify_eval(&mut transcript, &com, 7, &point, &value, &proof)
+            .is_ok());
+        proof.nonce += 1;
+        let mut transcript = Transcript::new(b"TestFRI");
+        assert!(pcs
+            .verify_eval(&mut transcript, &com, 7, &point, &value, &proof)
+            .is_err());
+        assert!(FRICommitmentScheme::<BLSScalar>::new(0, 1, 10, 0).is_err());
+        assert!(FRICommitmentScheme::<BLSScalar>::new(1 << 40, 1, 10, 0).is_err());
+    }
+}
@@ -0,0 +1,672 @@
+// This file implements a polynomial commitment scheme based on the inner product argument of
+// Bulletproofs (https://eprint.iacr.org/2017/1066.pdf), as used in Halo
+// (https://eprint.iacr.org/2019/1021.pdf), with no trusted setup: the public parameters are
+// n = 2^k generators G_0, ..., G_{n-1} and a generator U of a group of prime order p, derived
+// by hashing, hence nobody knows a discrete logarithm relation between them.
+//
+// commit(f: polynomial of degree lower than n)
+//    return C = \sum_i f_i * G_i, a Pedersen vector commitment to the coefficients of f
+//
+// prove_eval(f: polynomial, x: evaluation point)
+//    Let a = (f_0, ..., f_{n-1}) and b = (1, x, ..., x^{n-1}), so that f(x) = <a, b>.
+//    Let U' = xi * U for a challenge xi, the statement is P = C + <a, b> * U'.
+//    For each of the k rounds, split the vectors a, b and G in halves lo and hi, send
+//       L = <a_lo, G_hi> + <a_lo, b_hi> * U'
+//       R = <a_hi, G_lo> + <a_hi, b_lo> * U'
+//    get a challenge c, and fold
+//       a = c * a_lo + c^{-1} * a_hi
+//       b = c^{-1} * b_lo + c * b_hi
+//       G = c^{-1} * G_lo + c * G_hi
+//    so that the statement becomes P = c^2 * L + P + c^{-2} * R.
+//    return (L_1, R_1, ..., L_k, R_k, a) where a is the last element of the folded vector
+//
+// verify_eval(C: commitment, x, y, proof)
+//    The folded generator is G = <s, G> and the folded b is <s, b>, where
+//    s_i = \prod_j c_j^{+1 or -1}, the sign being the j-th most significant bit of i.
+//    Check that
+//       C + y * U' + \sum_j (c_j^2 * L_j + c_j^{-2} * R_j) == a * G + (a * <s, b>) * U'
+//
+// Proofs have 2k + 1 elements and the verifier computes a multi-exponentiation of size n,
+// against a constant size proof and two pairings for KZG. As KZGCommitmentScheme, the scheme
+// is binding but not hiding. PLONK needs a scalar field with large 2-adicity for its
+// evaluation domains, which is the case of BLS12-381 but not Ristretto.
+
+use crate::commitments::pcs::{
+    HomomorphicPolyComElem, PolyComScheme, PolyComSchemeError, ToBytes,
+};
+use crate::commitments::transcript::PolyComTranscript;
+use crate::plonk::transcript::PlonkTranscript;
+use crate::polynomials::field_polynomial::FpPolynomial;
+use algebra::bls12_381::BLSG1;
+use algebra::groups::{Group, GroupArithmetic, One, Scalar, ScalarArithmetic, Zero};
+use merlin::Transcript;
+use ruc::*;
+use sha3::{Digest, Sha3_512};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct IPACommitment<G> {
+    pub(crate) value: G,
+}
+
+impl<G: Group> ToBytes for IPACommitment<G> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.value.to_compressed_bytes()
+    }
+}
+
+impl<G: Group> HomomorphicPolyComElem for IPACommitment<G> {
+    type Scalar = G::S;
+    fn get_base() -> Self {
+        IPACommitment {
+            value: G::get_base(),
+        }
+    }
+
+    fn get_identity() -> Self {
+        IPACommitment {
+            value: G::get_identity(),
+        }
+    }
+
+    fn op(&self, other: &Self) -> Self {
+        IPACommitment {
+            value: self.value.add(&other.value),
+        }
+    }
+
+    fn op_assign(&mut self, other: &Self) {
+        self.value = self.value.add(&other.value);
+    }
+
+    // commitments and the scalars they are raised to are public
+    fn exp(&self, exp: &G::S) -> Self {
+        IPACommitment {
+            value: self.value.vartime_mul(exp),
+        }
+    }
+
+    fn exp_assign(&mut self, exp: &G::S) {
+        self.value = self.value.vartime_mul(exp);
+    }
+
+    fn inv(&self) -> Self {
+        IPACommitment {
+            value: G::get_identity().sub(&self.value),
+        }
+    }
+}
+
+/// Evaluation proof of the inner product argument. `quotient` is only set in the proofs of
+/// multi_point_prove_eval, which reduce to a single evaluation proof of a polynomial
+/// combining the quotient polynomial.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct IPAEvalProof<G, S> {
+    pub(crate) l_vec: Vec<G>,
+    pub(crate) r_vec: Vec<G>,
+    pub(crate) a: S,
+    pub(crate) quotient: Option<G>,
+}
+
+impl<G: Group> ToBytes for IPAEvalProof<G, G::S> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for (l, r) in self.l_vec.iter().zip(self.r_vec.iter()) {
+            bytes.extend_from_slice(&l.to_compressed_bytes());
+            bytes.extend_from_slice(&r.to_compressed_bytes());
+        }
+        bytes.extend_from_slice(&self.a.to_bytes());
+        if let Some(quotient) = &self.quotient {
+            bytes.extend_from_slice(&quotient.to_compressed_bytes());
+        }
+        bytes
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IPACommitmentScheme<G: Group> {
+    #[serde(with = "algebra::serialization::batch_points")]
+    pub(crate) generators: Vec<G>,
+    pub(crate) u: G,
+}
+
+impl<G: Group> IPACommitmentScheme<G> {
+    /// Creates a new instance of the scheme for polynomials of degree at most `max_degree`,
+    /// rounded up to a power of two minus one. The scheme is deterministic: all the instances
+    /// with the same max degree are equal.
+    pub fn new(max_degree: usize) -> IPACommitmentScheme<G> {
+        let n = (max_degree + 1).next_power_of_two();
+        let generators = (0..n)
+            .map(|i| {
+                let mut hash = Sha3_512::new();
+                hash.update(b"Zei IPA generator");
+                hash.update(&(i as u64).to_le_bytes());
+                G::from_hash(hash)
+            })
+            .collect();
+        let mut hash = Sha3_512::new();
+        hash.update(b"Zei IPA inner product generator");
+        IPACommitmentScheme {
+            generators,
+            u: G::from_hash(hash),
+        }
+    }
+
+    /// Returns a digest of the generators
+    pub fn digest(&self) -> [u8; 32] {
+        let mut transcript = Transcript::new(b"IPA generators digest");
+        transcript.append_u64(b"generators", self.generators.len() as u64);
+        for generator in self.generators.iter() {
+            transcript.append_message(b"generator", &generator.to_compressed_bytes());
+        }
+        transcript
+            .append_message(b"inner product generator", &self.u.to_compressed_bytes());
+        let mut digest = [0u8; 32];
+        transcript.challenge_bytes(b"digest", &mut digest);
+        digest
+    }
+
+    // Appends the statement C, x, y to the transcript and returns U' = xi * U
+    fn ipa_transcript_init<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &IPACommitment<G>,
+        point: &G::S,
+        value: &G::S,
+    ) -> G {
+        transcript.append_message(b"Domain Separator", b"New PCS-IPA-Eval Protocol");
+        transcript.append_u64(b"generators", self.generators.len() as u64);
+        transcript.append_commitment::<IPACommitment<G>>(commitment);
+        transcript.append_field_elem(point);
+        transcript.append_field_elem(value);
+        let xi = transcript.get_challenge_field_elem::<G::S>(b"xi");
+        self.u.vartime_mul(&xi)
+    }
+
+    // Proves that the polynomial committed in `commitment` with coefficients `coefs`
+    // evaluates to `value` on `point`
+    fn ipa_prove<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &IPACommitment<G>,
+        coefs: &[G::S],
+        point: &G::S,
+        value: &G::S,
+    ) -> Result<IPAEvalProof<G, G::S>> {
+        let n = self.generators.len();
+        if coefs.len() > n {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        let u = self.ipa_transcript_init(transcript, commitment, point, value);
+
+        let mut a = coefs.to_vec();
+        a.resize(n, G::S::zero());
+        let mut b = Vec::with_capacity(n);
+        let mut power = G::S::one();
+        for _ in 0..n {
+            b.push(power);
+            power.mul_assign(point);
+        }
+        let mut g = self.generators.clone();
+
+        let mut l_vec = vec![];
+        let mut r_vec = vec![];
+        while a.len() > 1 {
+            let m = a.len() / 2;
+            let (a_lo, a_hi) = a.split_at(m);
+            let (b_lo, b_hi) = b.split_at(m);
+            let (g_lo, g_hi) = g.split_at(m);
+            let l = msm(a_lo, g_hi).add(&u.vartime_mul(&inner_product(a_lo, b_hi)));
+            let r = msm(a_hi, g_lo).add(&u.vartime_mul(&inner_product(a_hi, b_lo)));
+            transcript.append_message(b"L", &l.to_compressed_bytes());
+            transcript.append_message(b"R", &r.to_compressed_bytes());
+            let c = transcript.get_challenge_field_elem::<G::S>(b"c");
+            let c_inv = c.inv().c(d!(PolyComSchemeError::PCSProveEvalError))?;
+
+            a = fold(a_lo, a_hi, &c, &c_inv);
+            b = fold(b_lo, b_hi, &c_inv, &c);
+            g = g_lo
+                .iter()
+                .zip(g_hi)
+                .map(|(lo, hi)| lo.vartime_mul(&c_inv).add(&hi.vartime_mul(&c)))
+                .collect();
+            l_vec.push(l);
+            r_vec.push(r);
+        }
+        Ok(IPAEvalProof {
+            l_vec,
+            r_vec,
+            a: a[0],
+            quotient: None,
+        })
+    }
+
+    // Verifies the inner product argument of `proof`, ignoring its quotient
+    fn ipa_verify<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &IPACommitment<G>,
+        point: &G::S,
+        value: &G::S,
+        proof: &IPAEvalProof<G, G::S>,
+    ) -> Result<()> {
+        let n = self.generators.len();
+        let k = n.trailing_zeros() as usize;
+        if proof.l_vec.len() != k || proof.r_vec.len() != k {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        let u = self.ipa_transcript_init(transcript, commitment, point, value);
+
+        let mut challenges = Vec::with_capacity(k);
+        for (l, r) in proof.l_vec.iter().zip(proof.r_vec.iter()) {
+            transcript.append_message(b"L", &l.to_compressed_bytes());
+            transcript.append_message(b"R", &r.to_compressed_bytes());
+            let c = transcript.get_challenge_field_elem::<G::S>(b"c");
+            let c_inv = c.inv().c(d!(PolyComSchemeError::PCSProveEvalError))?;
+            challenges.push((c, c_inv));
+        }
+
+        // s_i = \prod_j c_j^{+1 or -1}, the first challenge giving the most significant bit
+        let mut s = vec![G::S::one()];
+        for (c, c_inv) in challenges.iter() {
+            s = s
+                .iter()
+                .flat_map(|s_i| vec![s_i.mul(c_inv), s_i.mul(c)])
+                .collect();
+        }
+        let mut b = G::S::zero();
+        let mut power = G::S::one();
+        for s_i in s.iter() {
+            b.add_assign(&s_i.mul(&power));
+            power.mul_assign(point);
+        }
+
+        // a * <s, G> + (a * b - y) * U' - \sum_j (c_j^2 * L_j + c_j^{-2} * R_j) == C
+        let mut scalars: Vec<G::S> = s.iter().map(|s_i| s_i.mul(&proof.a)).collect();
+        let mut points: Vec<&G> = self.generators.iter().collect();
+        scalars.push(proof.a.mul(&b).sub(value));
+        points.push(&u);
+        for ((c, c_inv), (l, r)) in challenges
+            .iter()
+            .zip(proof.l_vec.iter().zip(proof.r_vec.iter()))
+        {
+            scalars.push(c.mul(c).neg());
+            points.push(l);
+            scalars.push(c_inv.mul(c_inv).neg());
+            points.push(r);
+        }
+        let scalars_ref: Vec<&G::S> = scalars.iter().collect();
+        if G::vartime_multi_exp(&scalars_ref, &points) == commitment.value {
+            Ok(())
+        } else {
+            Err(eg!(PolyComSchemeError::PCSProveEvalError))
+        }
+    }
+}
+
+/// IPA commitments over the group 1 of BLS12-381, whose scalar field supports PLONK
+pub type IPACommitmentSchemeBLS = IPACommitmentScheme<BLSG1>;
+
+fn msm<G: Group>(scalars: &[G::S], points: &[G]) -> G {
+    let scalars_ref: Vec<&G::S> = scalars.iter().collect();
+    let points_ref: Vec<&G> = points.iter().collect();
+    G::vartime_multi_exp(&scalars_ref, &points_ref)
+}
+
+fn inner_product<S: Scalar>(a: &[S], b: &[S]) -> S {
+    let mut result = S::zero();
+    for (a_i, b_i) in a.iter().zip(b) {
+        result.add_assign(&a_i.mul(b_i));
+    }
+    result
+}
+
+// returns x * lo + y * hi
+fn fold<S: Scalar>(lo: &[S], hi: &[S], x: &S, y: &S) -> Vec<S> {
+    lo.iter()
+        .zip(hi)
+        .map(|(lo_i, hi_i)| lo_i.mul(x).add(&hi_i.mul(y)))
+        .collect()
+}
+
+impl<G: Group> PolyComScheme for IPACommitmentScheme<G> {
+    type Field = G::S;
+    type Commitment = IPACommitment<G>;
+    type EvalProof = IPAEvalProof<G, G::S>;
+    type Opening = FpPolynomial<Self::Field>;
+
+    fn commit(
+        &self,
+        polynomial: FpPolynomial<Self::Field>,
+    ) -> Result<(Self::Commitment, Self::Opening)> {
+        let coefs = polynomial.get_coefs_ref();
+        if coefs.len() > self.generators.len() {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        let value = msm(coefs, &self.generators[..coefs.len()]);
+        Ok((IPACommitment { value }, polynomial))
+    }
+
+    fn opening(&self, polynomial: &FpPolynomial<Self::Field>) -> Self::Opening {
+        (*polynomial).clone()
+    }
+
+    fn eval_opening(
+        &self,
+        opening: &FpPolynomial<Self::Field>,
+        point: &Self::Field,
+    ) -> Self::Field {
+        opening.eval(point)
+    }
+
+    fn commitment_from_opening(&self, opening: &Self::Opening) -> Self::Commitment {
+        let (c, _) = self.commit(opening.clone()).unwrap();
+        c
+    }
+
+    fn polynomial_from_opening_ref(
+        &self,
+        opening: &Self::Opening,
+    ) -> FpPolynomial<Self::Field> {
+        (*opening).clone()
+    }
+
+    fn polynomial_from_opening(
+        &self,
+        opening: Self::Opening,
+    ) -> FpPolynomial<Self::Field> {
+        opening
+    }
+
+    fn prove_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        opening: &FpPolynomial<Self::Field>,
+        point: &Self::Field,
+        max_degree: usize,
+    ) -> Result<(Self::Field, Self::EvalProof)> {
+        if opening.degree() > max_degree {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        let value = opening.eval(point);
+        let commitment = self.commitment_from_opening(opening);
+        let proof = self
+            .ipa_prove(
+                transcript,
+                &commitment,
+                opening.get_coefs_ref(),
+                point,
+                &value,
+            )
+            .c(d!())?;
+        Ok((value, proof))
+    }
+
+    fn verify_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &Self::Commitment,
+        _degree: usize,
+        point: &Self::Field,
+        value: &Self::Field,
+        proof: &Self::EvalProof,
+    ) -> Result<()> {
+        if proof.quotient.is_some() {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        self.ipa_verify(transcript, commitment, point, value, proof)
+            .c(d!())
+    }
+
+    fn multi_point_prove_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        openings: &[&Self::Opening],
+        points: &[&[Self::Field]],
+        max_degree: usize,
+    ) -> Result<(Vec<Vec<Self::Field>>, Self::EvalProof)> {
+        if openings.len() != points.len() {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        if openings.iter().any(|opening| opening.degree() > max_degree) {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        let values: Vec<Vec<Self::Field>> = openings
+            .iter()
+            .zip(points)
+            .map(|(opening, points_i)| {
+                points_i.iter().map(|x| opening.eval(x)).collect()
+            })
+            .collect();
+        let gamma = Self::multi_point_eval_challenge(
+            transcript,
+            commitments,
+            max_degree,
+            points,
+            &values,
+        )
+        .c(d!())?;
+
+        // h = sum_i gamma^i * q_i for f_i = q_i * Z_{S_i} + r_i
+        let mut h = FpPolynomial::zero();
+        let mut gamma_i = Self::Field::one();
+        for (opening, points_i) in openings.iter().zip(points) {
+            let (q_i, _) = opening.div_rem(&FpPolynomial::from_zeroes(points_i));
+            h.add_assign(&q_i.mul_scalar(&gamma_i));
+            gamma_i.mul_assign(&gamma);
+        }
+        let (quotient, _) = self.commit(h.clone()).c(d!())?;
+        let (z, scalars, z_t_eval, r_eval) =
+            Self::multi_point_reduce(transcript, points, &values, &gamma, &quotient)
+                .c(d!())?;
+
+        // g = sum_i gamma^i * Z_{T - S_i}(z) * f_i - [sum_i ... * r_i(z)] - Z_T(z) * h
+        // vanishes on z
+        let mut g = h.mul_scalar(&z_t_eval.neg());
+        for (opening, scalar) in openings.iter().zip(scalars.iter()) {
+            g.add_assign(&opening.mul_scalar(scalar));
+        }
+        g.sub_assign(&FpPolynomial::from_coefs(vec![r_eval]));
+        let (g_commitment, _) = self.commit(g.clone()).c(d!())?;
+        let mut proof = self
+            .ipa_prove(
+                transcript,
+                &g_commitment,
+                g.get_coefs_ref(),
+                &z,
+                &Self::Field::zero(),
+            )
+            .c(d!())?;
+        proof.quotient = Some(quotient.value);
+        Ok((values, proof))
+    }
+
+    fn multi_point_verify_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        max_degree: usize,
+        points: &[&[Self::Field]],
+        values: &[Vec<Self::Field>],
+        proof: &Self::EvalProof,
+    ) -> Result<()> {
+        let quotient = proof
+            .quotient
+            .as_ref()
+            .ok_or(eg!(PolyComSchemeError::PCSProveEvalError))?;
+        let gamma = Self::multi_point_eval_challenge(
+            transcript,
+            commitments,
+            max_degree,
+            points,
+            values,
+        )
+        .c(d!())?;
+        let (z, scalars, z_t_eval, r_eval) = Self::multi_point_reduce(
+            transcript,
+            points,
+            values,
+            &gamma,
+            &IPACommitment {
+                value: quotient.clone(),
+            },
+        )
+        .c(d!())?;
+        let mut g_commitment = quotient.vartime_mul(&z_t_eval.neg());
+        for (commitment, scalar) in commitments.iter().zip(scalars.iter()) {
+            g_commitment = g_commitment.add(&commitment.value.vartime_mul(scalar));
+        }
+        g_commitment = g_commitment.sub(&self.generators[0].vartime_mul(&r_eval));
+        self.ipa_verify(
+            transcript,
+            &IPACommitment {
+                value: g_commitment,
+            },
+            &z,
+            &Self::Field::zero(),
+            proof,
+        )
+        .c(d!())
+    }
+
+    fn srs_digest(&self) -> Vec<u8> {
+        self.digest().to_vec()
+    }
+
+    fn max_degree(&self) -> usize {
+        self.generators.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::ipa_poly_com::{
+        IPACommitmentScheme, IPACommitmentSchemeBLS,
+    };
+    use crate::commitments::pcs::PolyComScheme;
+    use crate::polynomials::field_polynomial::FpPolynomial;
+    use algebra::bls12_381::{BLSScalar, BLSG1};
+    use algebra::groups::{Group, One, Scalar, ScalarArithmetic};
+    use algebra::ristretto::RistrettoPoint;
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn check_eval<G: Group>(pcs: &IPACommitmentScheme<G>) {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        for degree in [0, 1, 5, pcs.max_degree()] {
+            let poly = FpPolynomial::from_coefs(
+                (0..degree + 1).map(|_| G::S::random(&mut prng)).collect(),
+            );
+            let (com, open) = pnk!(pcs.commit(poly));
+            let point = G::S::random(&mut prng);
+            let (value, proof) = {
+                let mut transcript = Transcript::new(b"TestIPA");
+                pnk!(pcs.prove_eval(&mut transcript, &open, &point, degree))
+            };
+            assert_eq!(value, open.eval(&point));
+            let mut transcript = Transcript::new(b"TestIPA");
+            assert!(pcs
+                .verify_eval(&mut transcript, &com, degree, &point, &value, &proof)
+                .is_ok());
+            let mut transcript = Transcript::new(b"TestIPA");
+            let wrong_value = value.add(&G::S::one());
+            assert!(pcs
+                .verify_eval(&mut transcript, &com, degree, &point, &wrong_value, &proof)
+                .is_err());
+            let mut transcript = Transcript::new(b"TestIPA");
+            let mut wrong_proof = proof.clone();
+            wrong_proof.a.add_assign(&G::S::one());
+            assert!(pcs
+                .verify_eval(&mut transcript, &com, degree, &point, &value, &wrong_proof)
+                .is_err());
+        }
+        // polynomials of degree larger than the max degree
+        let poly = FpPolynomial::from_coefs(vec![G::S::one(); pcs.max_degree() + 2]);
+        assert!(pcs.commit(poly).is_err());
+    }
+
+    #[test]
+    fn test_ipa_eval() {
+        let pcs: IPACommitmentSchemeBLS = IPACommitmentScheme::new(12);
+        assert_eq!(pcs.max_degree(), 15);
+        check_eval(&pcs);
+        check_eval(&IPACommitmentScheme::<RistrettoPoint>::new(8));
+    }
+
+    #[test]
+    fn test_ipa_multi_point_eval() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs = IPACommitmentScheme::<BLSG1>::new(15);
+        let degree = 15;
+        let polys: Vec<_> = (0..3)
+            .map(|_| {
+                FpPolynomial::from_coefs(
+                    (0..degree + 1)
+                        .map(|_| BLSScalar::random(&mut prng))
+                        .collect(),
+                )
+            })
+            .collect();
+        let (coms, opens): (Vec<_>, Vec<_>) =
+            polys.into_iter().map(|poly| pnk!(pcs.commit(poly))).unzip();
+        let a = BLSScalar::random(&mut prng);
+        let b = BLSScalar::random(&mut prng);
+        let c = BLSScalar::random(&mut prng);
+        let points: [&[BLSScalar]; 3] = [&[a, b], &[b], &[a, c]];
+        let coms_ref: Vec<_> = coms.iter().collect();
+        let opens_ref: Vec<_> = opens.iter().collect();
+        let (values, proof) = {
+            let mut transcript = Transcript::new(b"TestIPA");
+            pnk!(pcs.multi_point_prove_eval(
+                &mut transcript,
+                &coms_ref,
+                &opens_ref,
+                &points,
+                degree
+            ))
+        };
+        assert_eq!(values[2], vec![opens[2].eval(&a), opens[2].eval(&c)]);
+        let mut transcript = Transcript::new(b"TestIPA");
+        assert!(pcs
+            .multi_point_verify_eval(
+                &mut transcript,
+                &coms_ref,
+                degree,
+                &points,
+                &values,
+                &proof
+            )
+            .is_ok());
+        let mut wrong_values = values.clone();
+        wrong_values[0][1] = wrong_values[0][1].add(&BLSScalar::one());
+        let mut transcript = Transcript::new(b"TestIPA");
+        assert!(pcs
+            .multi_point_verify_eval(
+                &mut transcript,
+                &coms_ref,
+                degree,
+                &points,
+                &wrong_values,
+                &proof
+            )
+            .is_err());
+
+        // single point and multi-point proofs are not interchangeable
+        let mut transcript = Transcript::new(b"TestIPA");
+        assert!(pcs
+            .verify_eval(&mut transcript, &coms[1], degree, &b, &values[1][0], &proof)
+            .is_err());
+    }
+
+    #[test]
+    fn test_ipa_transparent_setup() {
+        let pcs = IPACommitmentSchemeBLS::new(15);
+        assert_eq!(pcs.digest(), IPACommitmentSchemeBLS::new(10).digest());
+        assert_ne!(pcs.digest(), IPACommitmentSchemeBLS::new(16).digest());
+        let bytes = bincode::serialize(&pcs).unwrap();
+        let recovered: IPACommitmentSchemeBLS = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(recovered.digest(), pcs.digest());
+    }
+}
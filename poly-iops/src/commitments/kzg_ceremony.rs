@@ -0,0 +1,447 @@
+// This file loads the SRS (structured reference string) of the KZG polynomial commitment
+// scheme from the transcripts of public powers-of-tau ceremonies, so that production
+// deployments use powers of a secret that nobody knows rather than a trapdoor generated by
+// KZGCommitmentScheme::new. Both supported ceremonies are over BN254:
+//   - the perpetual powers of tau, in the .ptau format of snarkjs: the magic "ptau", the
+//     version and the number of sections as u32, then sections made of a u32 type, a u64 size
+//     and the data, all little endian. Section 1 is the header: the size n8 of a field element
+//     in bytes as u32, the order q of the base field in n8 bytes, and the power p of the file
+//     as u32. Section 2 holds the 2^{p+1} - 1 powers of tau in G1, as (x, y), and section 3
+//     the 2^p powers in G2, as (x.c0, x.c1, y.c0, y.c1). Coordinates are n8 little-endian
+//     bytes in Montgomery form, that is x * 2^{8 * n8} mod q.
+//   - the Aztec Ignition ceremony, split into transcript files that start with a manifest of
+//     7 big-endian u32: the number of the transcript, the number of transcripts, the total
+//     numbers of points in G1 and in G2, the numbers of points in G1 and in G2 of the file,
+//     and the index of its first point. The powers x^1, x^2, ... in G1 follow, then in the first
+//     transcript the power x in G2, with the same layouts as above. Coordinates are 4 limbs of
+//     64 bits, least significant limb first, each of them big endian. The generators are not
+//     part of the transcripts, and the checksum at the end of each file is not checked.
+// Every point is checked to be on the curve and in the prime order subgroup, and the loaded
+// SRS is checked with KZGCommitmentScheme::check_integrity, so that the powers in G1 and G2
+// are powers of the same secret.
+
+use crate::commitments::kzg_poly_com::{KZGCommitmentScheme, KZGCommitmentSchemeBN254};
+use algebra::bn254::{BN254G1, BN254G2};
+use algebra::groups::Group;
+use num_bigint::BigUint;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+const BN254_FQ_LEN: usize = 32;
+
+// order of the base field of BN254, little endian
+const BN254_FQ_MODULUS: [u8; BN254_FQ_LEN] = [
+    0x47, 0xfd, 0x7c, 0xd8, 0x16, 0x8c, 0x20, 0x3c, 0x8d, 0xca, 0x71, 0x68, 0x91, 0x6a,
+    0x81, 0x97, 0x5d, 0x58, 0x81, 0x81, 0xb6, 0x45, 0x50, 0xb8, 0x29, 0xa0, 0x31, 0xe1,
+    0x72, 0x4e, 0x64, 0x30,
+];
+
+const PTAU_MAGIC: &[u8; 4] = b"ptau";
+const PTAU_HEADER_SECTION: u32 = 1;
+const PTAU_TAU_G1_SECTION: u32 = 2;
+const PTAU_TAU_G2_SECTION: u32 = 3;
+
+const IGNITION_MANIFEST_LEN: usize = 28;
+
+// Returns the `n` bytes at `*pos` and moves `*pos` after them
+fn take<'a>(bytes: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8]> {
+    let end = pos
+        .checked_add(n)
+        .filter(|end| *end <= bytes.len())
+        .ok_or(eg!(ZeiError::DeserializationError))?;
+    let res = &bytes[*pos..end];
+    *pos = end;
+    Ok(res)
+}
+
+fn take_u32_le(bytes: &[u8], pos: &mut usize) -> Result<u32> {
+    let mut array = [0u8; 4];
+    array.copy_from_slice(take(bytes, pos, 4).c(d!())?);
+    Ok(u32::from_le_bytes(array))
+}
+
+fn take_u32_be(bytes: &[u8], pos: &mut usize) -> Result<u32> {
+    let mut array = [0u8; 4];
+    array.copy_from_slice(take(bytes, pos, 4).c(d!())?);
+    Ok(u32::from_be_bytes(array))
+}
+
+fn take_u64_le(bytes: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut array = [0u8; 8];
+    array.copy_from_slice(take(bytes, pos, 8).c(d!())?);
+    Ok(u64::from_le_bytes(array))
+}
+
+// Converts coordinates in Montgomery form into their canonical little-endian encoding
+struct MontgomeryDecoder {
+    modulus: BigUint,
+    r_inv: BigUint,
+}
+
+impl MontgomeryDecoder {
+    fn new() -> MontgomeryDecoder {
+        let modulus = BigUint::from_bytes_le(&BN254_FQ_MODULUS);
+        let r = BigUint::from(1u32) << (8 * BN254_FQ_LEN);
+        // the inverse of R = 2^256 modulo q is R^{q-2}
+        let r_inv = r.modpow(&(&modulus - 2u32), &modulus);
+        MontgomeryDecoder { modulus, r_inv }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let value = BigUint::from_bytes_le(bytes);
+        if value >= self.modulus {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
+        let mut le_bytes = (value * &self.r_inv % &self.modulus).to_bytes_le();
+        le_bytes.resize(BN254_FQ_LEN, 0);
+        Ok(le_bytes)
+    }
+}
+
+// Converts a coordinate made of 4 big-endian limbs, least significant limb first, into its
+// little-endian encoding
+fn ignition_coordinate(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .chunks(8)
+        .flat_map(|limb| limb.iter().rev().copied())
+        .collect()
+}
+
+/// Loads the SRS for polynomials of degree at most `max_degree` from the .ptau file of a
+/// perpetual powers of tau ceremony over BN254, read into `bytes`, and checks its integrity
+/// with randomness sampled from `prng`
+pub fn srs_from_ptau<R: CryptoRng + RngCore>(
+    bytes: &[u8],
+    max_degree: usize,
+    prng: &mut R,
+) -> Result<KZGCommitmentSchemeBN254> {
+    let mut pos = 0;
+    if take(bytes, &mut pos, 4).c(d!())? != PTAU_MAGIC {
+        return Err(eg!(ZeiError::DeserializationError));
+    }
+    let _version = take_u32_le(bytes, &mut pos).c(d!())?;
+    let n_sections = take_u32_le(bytes, &mut pos).c(d!())?;
+    let mut header = None;
+    let mut tau_g1 = None;
+    let mut tau_g2 = None;
+    for _ in 0..n_sections {
+        let section_type = take_u32_le(bytes, &mut pos).c(d!())?;
+        let size = take_u64_le(bytes, &mut pos).c(d!())?;
+        let size = usize::try_from(size).c(d!(ZeiError::DeserializationError))?;
+        let data = take(bytes, &mut pos, size).c(d!())?;
+        let section = match section_type {
+            PTAU_HEADER_SECTION => &mut header,
+            PTAU_TAU_G1_SECTION => &mut tau_g1,
+            PTAU_TAU_G2_SECTION => &mut tau_g2,
+            _ => continue,
+        };
+        if section.replace(data).is_some() {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
+    }
+    let (header, tau_g1, tau_g2) = match (header, tau_g1, tau_g2) {
+        (Some(header), Some(tau_g1), Some(tau_g2)) => (header, tau_g1, tau_g2),
+        _ => return Err(eg!(ZeiError::DeserializationError)),
+    };
+
+    let mut pos = 0;
+    let n8 = take_u32_le(header, &mut pos).c(d!())?;
+    if n8 as usize != BN254_FQ_LEN
+        || take(header, &mut pos, BN254_FQ_LEN).c(d!())? != BN254_FQ_MODULUS
+    {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let power = take_u32_le(header, &mut pos).c(d!())?;
+    if power >= 32 {
+        return Err(eg!(ZeiError::DeserializationError));
+    }
+    let n_powers_g1 = (1usize << (power + 1)) - 1;
+    let n_powers_g2 = 1usize << power;
+    if tau_g1.len() != n_powers_g1 * 2 * BN254_FQ_LEN
+        || tau_g2.len() != n_powers_g2 * 4 * BN254_FQ_LEN
+    {
+        return Err(eg!(ZeiError::DeserializationError));
+    }
+    if max_degree >= n_powers_g1 || n_powers_g2 < 2 {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+
+    let decoder = MontgomeryDecoder::new();
+    let powers_g1 = tau_g1
+        .chunks(2 * BN254_FQ_LEN)
+        .take(max_degree + 1)
+        .map(|point| {
+            let (x, y) = point.split_at(BN254_FQ_LEN);
+            BN254G1::from_affine_le_bytes(&decoder.decode(x)?, &decoder.decode(y)?)
+        })
+        .collect::<Result<Vec<BN254G1>>>()
+        .c(d!(ZeiError::DeserializationError))?;
+    let powers_g2 = tau_g2
+        .chunks(4 * BN254_FQ_LEN)
+        .take(2)
+        .map(|point| {
+            let coordinates = point
+                .chunks(BN254_FQ_LEN)
+                .map(|c| decoder.decode(c))
+                .collect::<Result<Vec<Vec<u8>>>>()?;
+            BN254G2::from_affine_le_bytes(
+                [&coordinates[0], &coordinates[1]],
+                [&coordinates[2], &coordinates[3]],
+            )
+        })
+        .collect::<Result<Vec<BN254G2>>>()
+        .c(d!(ZeiError::DeserializationError))?;
+
+    let pcs = KZGCommitmentScheme::from_powers(powers_g1, powers_g2).c(d!())?;
+    pcs.check_integrity(prng).c(d!())?;
+    Ok(pcs)
+}
+
+/// Loads the SRS for polynomials of degree at most `max_degree` from the transcript files
+/// of the Aztec Ignition ceremony, read into `transcripts` in the order of the ceremony,
+/// and checks its integrity with randomness sampled from `prng`. Only the first transcripts
+/// holding the needed powers are required.
+pub fn srs_from_ignition<R: CryptoRng + RngCore>(
+    transcripts: &[&[u8]],
+    max_degree: usize,
+    prng: &mut R,
+) -> Result<KZGCommitmentSchemeBN254> {
+    let mut powers_g1 = vec![BN254G1::get_base()];
+    let mut powers_g2 = vec![BN254G2::get_base()];
+    for (i, transcript) in transcripts.iter().enumerate() {
+        if powers_g1.len() > max_degree {
+            break;
+        }
+        let mut pos = 0;
+        let transcript_number = take_u32_be(transcript, &mut pos).c(d!())?;
+        let _total_transcripts = take_u32_be(transcript, &mut pos).c(d!())?;
+        let total_g1_points = take_u32_be(transcript, &mut pos).c(d!())?;
+        let _total_g2_points = take_u32_be(transcript, &mut pos).c(d!())?;
+        let num_g1_points = take_u32_be(transcript, &mut pos).c(d!())? as usize;
+        let num_g2_points = take_u32_be(transcript, &mut pos).c(d!())? as usize;
+        let start_from = take_u32_be(transcript, &mut pos).c(d!())? as usize;
+        debug_assert_eq!(pos, IGNITION_MANIFEST_LEN);
+        // the transcript must continue the powers loaded so far, x^{start_from + 1} being
+        // the first power in the transcript
+        if transcript_number as usize != i || start_from + 1 != powers_g1.len() {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
+        if max_degree > total_g1_points as usize {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+
+        let points =
+            take(transcript, &mut pos, num_g1_points * 2 * BN254_FQ_LEN).c(d!())?;
+        let n_needed = max_degree + 1 - powers_g1.len();
+        for point in points.chunks(2 * BN254_FQ_LEN).take(n_needed) {
+            let (x, y) = point.split_at(BN254_FQ_LEN);
+            powers_g1.push(
+                BN254G1::from_affine_le_bytes(
+                    &ignition_coordinate(x),
+                    &ignition_coordinate(y),
+                )
+                .c(d!(ZeiError::DeserializationError))?,
+            );
+        }
+        if i == 0 {
+            if num_g2_points == 0 {
+                return Err(eg!(ZeiError::DeserializationError));
+            }
+            let point = take(transcript, &mut pos, 4 * BN254_FQ_LEN).c(d!())?;
+            let coordinates: Vec<Vec<u8>> = point
+                .chunks(BN254_FQ_LEN)
+                .map(ignition_coordinate)
+                .collect();
+            powers_g2.push(
+                BN254G2::from_affine_le_bytes(
+                    [&coordinates[0], &coordinates[1]],
+                    [&coordinates[2], &coordinates[3]],
+                )
+                .c(d!(ZeiError::DeserializationError))?,
+            );
+        }
+    }
+    if powers_g1.len() <= max_degree {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+
+    let pcs = KZGCommitmentScheme::from_powers(powers_g1, powers_g2).c(d!())?;
+    pcs.check_integrity(prng).c(d!())?;
+    Ok(pcs)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_ceremony::{
+        srs_from_ignition, srs_from_ptau, BN254_FQ_LEN, BN254_FQ_MODULUS,
+    };
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitmentScheme, KZGCommitmentSchemeBN254,
+    };
+    use algebra::bn254::{BN254G1, BN254G2};
+    use num_bigint::BigUint;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn montgomery(bytes: Vec<u8>) -> Vec<u8> {
+        let modulus = BigUint::from_bytes_le(&BN254_FQ_MODULUS);
+        let value = (BigUint::from_bytes_le(&bytes) << (8 * BN254_FQ_LEN)) % modulus;
+        let mut le_bytes = value.to_bytes_le();
+        le_bytes.resize(BN254_FQ_LEN, 0);
+        le_bytes
+    }
+
+    fn big_endian_limbs(bytes: Vec<u8>) -> Vec<u8> {
+        bytes
+            .chunks(8)
+            .flat_map(|limb| limb.iter().rev().copied())
+            .collect()
+    }
+
+    fn g1_coordinates(point: &BN254G1) -> Vec<Vec<u8>> {
+        let (x, y) = point.to_affine_le_bytes().unwrap();
+        vec![x, y]
+    }
+
+    fn g2_coordinates(point: &BN254G2) -> Vec<Vec<u8>> {
+        let ([x_c0, x_c1], [y_c0, y_c1]) = point.to_affine_le_bytes().unwrap();
+        vec![x_c0, x_c1, y_c0, y_c1]
+    }
+
+    fn ptau_bytes(pcs: &KZGCommitmentSchemeBN254, power: u32) -> Vec<u8> {
+        let (powers_g1, powers_g2) = pcs.powers();
+        let mut header = (BN254_FQ_LEN as u32).to_le_bytes().to_vec();
+        header.extend_from_slice(&BN254_FQ_MODULUS);
+        header.extend_from_slice(&power.to_le_bytes());
+        header.extend_from_slice(&power.to_le_bytes());
+        let tau_g1: Vec<u8> = powers_g1
+            .iter()
+            .flat_map(g1_coordinates)
+            .flat_map(montgomery)
+            .collect();
+        let tau_g2: Vec<u8> = powers_g2
+            .iter()
+            .flat_map(g2_coordinates)
+            .flat_map(montgomery)
+            .collect();
+
+        let mut bytes = b"ptau".to_vec();
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        for (section_type, data) in
+            [(1u32, header), (2, tau_g1), (3, tau_g2), (4, vec![0u8; 10])]
+        {
+            bytes.extend_from_slice(&section_type.to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&data);
+        }
+        bytes
+    }
+
+    fn ignition_bytes(
+        pcs: &KZGCommitmentSchemeBN254,
+        transcript_number: u32,
+        start_from: usize,
+        num_g1_points: usize,
+    ) -> Vec<u8> {
+        let (powers_g1, powers_g2) = pcs.powers();
+        let num_g2_points = if transcript_number == 0 { 1 } else { 0 };
+        let mut bytes = vec![];
+        for value in [
+            transcript_number,
+            2,
+            (powers_g1.len() - 1) as u32,
+            1,
+            num_g1_points as u32,
+            num_g2_points,
+            start_from as u32,
+        ] {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        for power in &powers_g1[start_from + 1..start_from + 1 + num_g1_points] {
+            for coordinate in g1_coordinates(power) {
+                bytes.extend(big_endian_limbs(coordinate));
+            }
+        }
+        if transcript_number == 0 {
+            for coordinate in g2_coordinates(&powers_g2[1]) {
+                bytes.extend(big_endian_limbs(coordinate));
+            }
+        }
+        // checksum
+        bytes.extend_from_slice(&[0u8; 64]);
+        bytes
+    }
+
+    #[test]
+    fn test_srs_from_ptau() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        // a file of power 3 has 15 powers in G1 and 8 powers in G2
+        let pcs: KZGCommitmentSchemeBN254 =
+            KZGCommitmentScheme::new_with_g2_powers(14, 7, &mut prng);
+        let bytes = ptau_bytes(&pcs, 3);
+
+        let loaded = pnk!(srs_from_ptau(&bytes, 14, &mut prng));
+        assert_eq!(loaded.powers().0, pcs.powers().0);
+        assert_eq!(loaded.powers().1, &pcs.powers().1[..2]);
+        let loaded = pnk!(srs_from_ptau(&bytes, 5, &mut prng));
+        assert_eq!(loaded.digest(), pnk!(pcs.truncate(5)).digest());
+        assert!(srs_from_ptau(&bytes, 15, &mut prng).is_err());
+
+        // wrong magic, truncated file
+        let mut bad_bytes = bytes.clone();
+        bad_bytes[0] ^= 1;
+        assert!(srs_from_ptau(&bad_bytes, 5, &mut prng).is_err());
+        assert!(srs_from_ptau(&bytes[..bytes.len() - 20], 5, &mut prng).is_err());
+        // a coordinate of the second power in G1 is off the curve
+        let offset = 12 + 12 + 4 + 32 + 8 + 12 + 64;
+        let mut bad_bytes = bytes.clone();
+        bad_bytes[offset] ^= 1;
+        assert!(srs_from_ptau(&bad_bytes, 5, &mut prng).is_err());
+        // the powers in G2 are powers of another secret
+        let other_pcs: KZGCommitmentSchemeBN254 =
+            KZGCommitmentScheme::new_with_g2_powers(14, 7, &mut prng);
+        let mixed_pcs = pnk!(KZGCommitmentScheme::from_powers(
+            pcs.powers().0.to_vec(),
+            other_pcs.powers().1.to_vec()
+        ));
+        let bad_bytes = ptau_bytes(&mixed_pcs, 3);
+        assert!(srs_from_ptau(&bad_bytes, 5, &mut prng).is_err());
+    }
+
+    #[test]
+    fn test_srs_from_ignition() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs: KZGCommitmentSchemeBN254 = KZGCommitmentScheme::new(20, &mut prng);
+        let transcript_0 = ignition_bytes(&pcs, 0, 0, 12);
+        let transcript_1 = ignition_bytes(&pcs, 1, 12, 8);
+
+        let loaded = pnk!(srs_from_ignition(
+            &[&transcript_0, &transcript_1],
+            20,
+            &mut prng
+        ));
+        assert_eq!(loaded.powers(), pcs.powers());
+        // the first transcript holds the powers up to x^12
+        let loaded = pnk!(srs_from_ignition(&[&transcript_0], 12, &mut prng));
+        assert_eq!(loaded.digest(), pnk!(pcs.truncate(12)).digest());
+        assert!(srs_from_ignition(&[&transcript_0], 13, &mut prng).is_err());
+        assert!(
+            srs_from_ignition(&[&transcript_0, &transcript_1], 21, &mut prng).is_err()
+        );
+        // transcripts out of order
+        assert!(
+            srs_from_ignition(&[&transcript_1, &transcript_0], 20, &mut prng).is_err()
+        );
+        // a power of another secret
+        let other_pcs: KZGCommitmentSchemeBN254 =
+            KZGCommitmentScheme::new(20, &mut prng);
+        let other_transcript_1 = ignition_bytes(&other_pcs, 1, 12, 8);
+        assert!(
+            srs_from_ignition(&[&transcript_0, &other_transcript_1], 20, &mut prng)
+                .is_err()
+        );
+    }
+}
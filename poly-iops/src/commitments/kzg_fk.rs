@@ -0,0 +1,173 @@
+// This file implements the Feist-Khovratovich technique (https://eprint.iacr.org/2023/033) to
+// compute the KZG eval proofs of a polynomial on all the points of a domain at once.
+// Let f(X) = f_0 + f_1 X + ... + f_d X^d, and S_a = g1^{s^a} the powers of the SRS in group 1.
+// The eval proof of f on z is g1^{q_z(s)} for q_z(X) = (f(X) - f(z))/(X - z), and
+//    q_z(s) = \sum_k f_k (s^k - z^k)/(s - z) = \sum_{b=0}^{d-1} z^b \sum_{a=0}^{d-1-b} f_{a+b+1} s^a
+// hence the proof is \sum_b z^b h_b, where h_b = \prod_{a=0}^{d-1-b} S_a^{f_{a+b+1}} (in additive
+// notation, \sum_a f_{a+b+1} S_a) does not depend on z.
+// - The vector h is a Toeplitz matrix-vector product, computed as a cyclic convolution of the
+//   reversed coefficients (f_d, ..., f_1) with (S_0, ..., S_{d-1}), in O(d log d) with FFTs.
+// - The proofs on the points w^i of a domain of size n are then the DFT of h (folded modulo n,
+//   as w^n = 1), computed with one more FFT in group 1.
+// This takes O(n log n) group operations instead of O(n d) for n separate prove_eval calls.
+
+use crate::commitments::kzg_poly_com::{KZGCommitmentScheme, KZGEvalProof};
+use crate::commitments::pcs::PolyComSchemeError;
+use crate::polynomials::field_polynomial::{primitive_nth_root_of_unity, FpPolynomial};
+use algebra::groups::{Group, Pairing};
+use ruc::*;
+use utils::errors::ZeiError;
+
+// DFT of `elems` in the group, for a primitive n-th root of unity `root`, n a power of two
+pub(crate) fn group_fft<G: Group>(elems: &[G], root: &G::S) -> Vec<G> {
+    let n = elems.len();
+    assert!(n.is_power_of_two());
+    if n == 1 {
+        return elems.to_vec();
+    }
+    let even: Vec<G> = elems.iter().step_by(2).cloned().collect();
+    let odd: Vec<G> = elems.iter().skip(1).step_by(2).cloned().collect();
+    let root_sq = root.square();
+    let y_even = group_fft(&even, &root_sq);
+    let y_odd = group_fft(&odd, &root_sq);
+
+    let mut omega = G::S::one();
+    let mut dft = vec![G::get_identity(); n];
+    for (i, (e, o)) in y_even.iter().zip(y_odd.iter()).enumerate() {
+        let omega_o = o.mul(&omega);
+        dft[i] = e.add(&omega_o);
+        dft[n / 2 + i] = e.sub(&omega_o);
+        omega.mul_assign(root);
+    }
+    dft
+}
+
+// Inverse DFT of `values` in the group, for a primitive n-th root of unity `root`
+pub(crate) fn group_ifft<G: Group>(values: &[G], root: &G::S) -> Result<Vec<G>> {
+    let n = values.len();
+    let root_inv = root.inv().c(d!())?;
+    let n_inv = G::S::from_u64(n as u64).inv().c(d!())?;
+    Ok(group_fft(values, &root_inv)
+        .into_iter()
+        .map(|x| x.mul(&n_inv))
+        .collect())
+}
+
+impl<P: Pairing> KZGCommitmentScheme<P> {
+    /// Computes the evaluations of `polynomial` on the domain of the `domain_size`-th roots of
+    /// unity (1, w, ..., w^{n-1}) together with their eval proofs, in O(n log n) group
+    /// operations. The i-th proof is the same as the proof of prove_eval on point w^i.
+    /// `domain_size` - size of the domain, it must be a power of two
+    #[allow(clippy::type_complexity)]
+    pub fn prove_all_evals(
+        &self,
+        polynomial: &FpPolynomial<P::ScalarField>,
+        domain_size: usize,
+    ) -> Result<(Vec<P::ScalarField>, Vec<KZGEvalProof<P::G1>>)> {
+        if domain_size == 0 || !domain_size.is_power_of_two() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let d = polynomial.degree();
+        if d > self.max_degree() {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        let root = primitive_nth_root_of_unity::<P::ScalarField>(domain_size)
+            .c(d!(ZeiError::ParameterError))?;
+        // f is the same as f mod (X^n - 1) on the domain
+        let (_, reduced) = polynomial.div_by_vanishing(domain_size);
+        let evals = reduced.fft_with_unity_root(&root, domain_size);
+        if d == 0 {
+            return Ok((
+                evals,
+                vec![KZGEvalProof(P::G1::get_identity()); domain_size],
+            ));
+        }
+
+        // h_b = (c * S)_{d-1-b} for c = (f_d, ..., f_1), as a cyclic convolution of size N
+        // large enough for the linear convolution up to index d - 1 not to wrap around
+        let coefs = polynomial.get_coefs_ref();
+        let conv_size = (2 * d).next_power_of_two();
+        let conv_root = primitive_nth_root_of_unity::<P::ScalarField>(conv_size)
+            .c(d!(ZeiError::ParameterError))?;
+        let mut reversed: Vec<P::ScalarField> =
+            coefs[1..].iter().rev().cloned().collect();
+        reversed.resize(conv_size, P::ScalarField::zero());
+        let reversed_evals = FpPolynomial::from_coefs(reversed)
+            .fft_with_unity_root(&conv_root, conv_size);
+        let mut powers = self.public_parameter_group_1[..d].to_vec();
+        powers.resize(conv_size, P::G1::get_identity());
+        let powers_evals = group_fft(&powers, &conv_root);
+        let product: Vec<P::G1> = powers_evals
+            .iter()
+            .zip(reversed_evals.iter())
+            .map(|(s, c)| s.mul(c))
+            .collect();
+        let conv = group_ifft(&product, &conv_root).c(d!())?;
+
+        // fold h modulo n, then the proofs are its DFT on the domain
+        let mut h = vec![P::G1::get_identity(); domain_size];
+        for b in 0..d {
+            h[b % domain_size] = h[b % domain_size].add(&conv[d - 1 - b]);
+        }
+        let proofs = group_fft(&h, &root).into_iter().map(KZGEvalProof).collect();
+        Ok((evals, proofs))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitmentScheme, KZGCommitmentSchemeBLS,
+    };
+    use crate::commitments::pcs::PolyComScheme;
+    use crate::polynomials::field_polynomial::{
+        primitive_nth_root_of_unity, FpPolynomial,
+    };
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    #[test]
+    fn test_prove_all_evals() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(20, &mut prng);
+        // polynomials of degree lower, equal and larger than the size of the domain
+        for (degree, domain_size) in [(0, 4), (1, 1), (5, 8), (7, 8), (8, 8), (20, 4)] {
+            let coefs = (0..degree + 1)
+                .map(|_| BLSScalar::random(&mut prng))
+                .collect();
+            let polynomial = FpPolynomial::from_coefs(coefs);
+            let (commitment, _) = pnk!(pcs.commit(polynomial.clone()));
+            let (evals, proofs) = pnk!(pcs.prove_all_evals(&polynomial, domain_size));
+            assert_eq!(evals.len(), domain_size);
+            assert_eq!(proofs.len(), domain_size);
+
+            let root = primitive_nth_root_of_unity::<BLSScalar>(domain_size).unwrap();
+            let mut point = BLSScalar::one();
+            for (eval, proof) in evals.iter().zip(proofs.iter()) {
+                let mut transcript = Transcript::new(b"Test");
+                let (expected_eval, expected_proof) =
+                    pnk!(pcs.prove_eval(&mut transcript, &polynomial, &point, degree));
+                assert_eq!(eval, &expected_eval);
+                assert_eq!(proof, &expected_proof);
+                let mut transcript = Transcript::new(b"Test");
+                pnk!(pcs.verify_eval(
+                    &mut transcript,
+                    &commitment,
+                    degree,
+                    &point,
+                    eval,
+                    proof
+                ));
+                point.mul_assign(&root);
+            }
+        }
+
+        let polynomial = FpPolynomial::from_coefs(vec![BLSScalar::one(); 22]);
+        assert!(pcs.prove_all_evals(&polynomial, 8).is_err());
+        assert!(pcs.prove_all_evals(&FpPolynomial::one(), 6).is_err());
+    }
+}
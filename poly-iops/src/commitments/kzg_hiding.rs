@@ -0,0 +1,343 @@
+// This file implements the hiding variant of the KZG polynomial commitment scheme, as in
+// Marlin (https://eprint.iacr.org/2019/1047.pdf, appendix B). Let h be a generator of G1
+// whose discrete logarithm in base g1 is unknown. A polynomial f is committed along with a
+// random blinding polynomial r of degree b, the hiding bound:
+//    C = g1^{f(s)} * h^{r(s)}
+// which is uniformly distributed whatever f, so that committing twice to the same polynomial,
+// e.g. to a witness polynomial, gives independent commitments.
+//
+// setup(n: max polynomial degree, b: hiding bound)
+//    Pick random scalars s and gamma in Z_p, let h = g1^gamma
+//    return the SRS of the KZG scheme and (h, h^s, ..., h^{s^b})
+//
+// prove_eval(f, r, x)
+//    Let y = f(x), q = (f - y) / (X - x) and q_r = (r - r(x)) / (X - x)
+//    return (g1^{q(s)} * h^{q_r(s)}, r(x))
+//
+// verify_eval(C, x, y, (w, r_x))
+//    Check that e(C / (g1^y * h^{r_x}), g2) == e(w, g2^s / g2^x)
+//
+// Each evaluation proof reveals one evaluation of the blinding polynomial, hence a commitment
+// stays hiding as long as at most b of its evaluations are proven. The commitments are of the
+// same type as those of KZGCommitmentScheme, with the same SRS in group 1 and 2, so that they
+// can be combined, e.g. a hiding witness commitment with a non-hiding selector commitment.
+
+use crate::commitments::kzg_poly_com::{KZGCommitment, KZGCommitmentScheme};
+use crate::commitments::pcs::{PolyComScheme, PolyComSchemeError};
+use crate::polynomials::field_polynomial::FpPolynomial;
+use algebra::bls12_381::Bls12381;
+use algebra::groups::{Group, GroupArithmetic, One, Pairing, Scalar, ScalarArithmetic};
+use algebra::pairing_batch::PairingCheck;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+/// Opening of a hiding KZG commitment: the committed polynomial and its blinding polynomial
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HidingKZGOpening<F> {
+    pub(crate) polynomial: FpPolynomial<F>,
+    pub(crate) blinding: FpPolynomial<F>,
+}
+
+impl<F: Scalar> HidingKZGOpening<F> {
+    /// Returns the committed polynomial
+    pub fn polynomial(&self) -> &FpPolynomial<F> {
+        &self.polynomial
+    }
+
+    /// Returns the blinding polynomial
+    pub fn blinding(&self) -> &FpPolynomial<F> {
+        &self.blinding
+    }
+
+    /// Returns the opening of the sum of the commitments of `self` and `other`
+    pub fn add(&self, other: &Self) -> Self {
+        HidingKZGOpening {
+            polynomial: self.polynomial.add(&other.polynomial),
+            blinding: self.blinding.add(&other.blinding),
+        }
+    }
+
+    /// Returns the opening of the commitment of `self` raised to `scalar`
+    pub fn mul_scalar(&self, scalar: &F) -> Self {
+        HidingKZGOpening {
+            polynomial: self.polynomial.mul_scalar(scalar),
+            blinding: self.blinding.mul_scalar(scalar),
+        }
+    }
+}
+
+/// Evaluation proof of the hiding KZG scheme
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HidingKZGEvalProof<G1, F> {
+    pub(crate) witness: G1,
+    pub(crate) blinding_eval: F,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HidingKZGCommitmentScheme<P: Pairing> {
+    pub(crate) kzg: KZGCommitmentScheme<P>,
+    // h, h^s, ..., h^{s^b}
+    #[serde(with = "algebra::serialization::batch_points")]
+    pub(crate) blinding_powers: Vec<P::G1>,
+}
+
+impl<P: Pairing> HidingKZGCommitmentScheme<P> {
+    /// Creates a new instance of the hiding KZG polynomial commitment scheme
+    /// `max_degree` - max degree of the polynomial
+    /// `hiding_bound` - max number of evaluation proofs of a commitment keeping it hiding
+    /// `prng` - pseudo-random generator
+    pub fn new<R: CryptoRng + RngCore>(
+        max_degree: usize,
+        hiding_bound: usize,
+        prng: &mut R,
+    ) -> Result<HidingKZGCommitmentScheme<P>> {
+        if hiding_bound == 0 {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let s = P::ScalarField::random(prng);
+        let gamma = P::ScalarField::random(prng);
+        let kzg = KZGCommitmentScheme::from_secret(&s, max_degree, 1);
+        let mut blinding_powers = Vec::with_capacity(hiding_bound + 1);
+        let mut elem = P::G1::get_base().mul(&gamma);
+        for _ in 0..hiding_bound + 1 {
+            blinding_powers.push(elem.clone());
+            elem = elem.mul(&s);
+        }
+        Ok(HidingKZGCommitmentScheme {
+            kzg,
+            blinding_powers,
+        })
+    }
+
+    /// Returns the underlying non-hiding scheme, with the same SRS
+    pub fn non_hiding(&self) -> &KZGCommitmentScheme<P> {
+        &self.kzg
+    }
+
+    /// Returns the max degree of the polynomials the scheme can commit to
+    pub fn max_degree(&self) -> usize {
+        self.kzg.max_degree()
+    }
+
+    /// Returns the degree of the blinding polynomials
+    pub fn hiding_bound(&self) -> usize {
+        self.blinding_powers.len() - 1
+    }
+
+    // h^{r(s)}
+    fn commit_blinding(&self, blinding: &FpPolynomial<P::ScalarField>) -> P::G1 {
+        let coefs = blinding.get_coefs_ref();
+        let coefs_ref: Vec<&P::ScalarField> = coefs.iter().collect();
+        let powers_ref: Vec<&P::G1> =
+            self.blinding_powers[..coefs.len()].iter().collect();
+        P::G1::vartime_multi_exp(&coefs_ref, &powers_ref)
+    }
+
+    /// Commits to `polynomial` with a fresh blinding polynomial sampled from `prng`
+    pub fn commit<R: CryptoRng + RngCore>(
+        &self,
+        prng: &mut R,
+        polynomial: FpPolynomial<P::ScalarField>,
+    ) -> Result<(KZGCommitment<P::G1>, HidingKZGOpening<P::ScalarField>)> {
+        let blinding = FpPolynomial::random(prng, self.hiding_bound());
+        let (commitment, polynomial) = self.kzg.commit(polynomial).c(d!())?;
+        let commitment = KZGCommitment {
+            value: commitment.value.add(&self.commit_blinding(&blinding)),
+        };
+        Ok((
+            commitment,
+            HidingKZGOpening {
+                polynomial,
+                blinding,
+            },
+        ))
+    }
+
+    /// Returns the commitment of `opening`
+    pub fn commitment_from_opening(
+        &self,
+        opening: &HidingKZGOpening<P::ScalarField>,
+    ) -> Result<KZGCommitment<P::G1>> {
+        if opening.blinding.degree() > self.hiding_bound() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let (commitment, _) = self.kzg.commit(opening.polynomial.clone()).c(d!())?;
+        Ok(KZGCommitment {
+            value: commitment
+                .value
+                .add(&self.commit_blinding(&opening.blinding)),
+        })
+    }
+
+    /// Evaluates the committed polynomial of `opening` at `x`, returning the evaluation and
+    /// its proof
+    pub fn prove_eval(
+        &self,
+        opening: &HidingKZGOpening<P::ScalarField>,
+        x: &P::ScalarField,
+    ) -> Result<(P::ScalarField, HidingKZGEvalProof<P::G1, P::ScalarField>)> {
+        if opening.blinding.degree() > self.hiding_bound() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        if opening.polynomial.degree() > self.max_degree() {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        let divisor = FpPolynomial::from_coefs(vec![x.neg(), P::ScalarField::one()]);
+
+        let eval = opening.polynomial.eval(x);
+        let (quotient, remainder) = opening
+            .polynomial
+            .sub(&FpPolynomial::from_coefs(vec![eval]))
+            .div_rem(&divisor);
+        if !remainder.is_zero() {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+
+        let blinding_eval = opening.blinding.eval(x);
+        let (blinding_quotient, remainder) = opening
+            .blinding
+            .sub(&FpPolynomial::from_coefs(vec![blinding_eval]))
+            .div_rem(&divisor);
+        if !remainder.is_zero() {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+
+        let (witness, _) = self.kzg.commit(quotient).c(d!())?;
+        let witness = witness.value.add(&self.commit_blinding(&blinding_quotient));
+        Ok((
+            eval,
+            HidingKZGEvalProof {
+                witness,
+                blinding_eval,
+            },
+        ))
+    }
+
+    /// Returns the pairing check verifying that the polynomial in `commitment` evaluates to
+    /// `y` on `x`, to be batched with the pairing checks of other proofs
+    pub fn eval_pairing_check(
+        &self,
+        commitment: &KZGCommitment<P::G1>,
+        x: &P::ScalarField,
+        y: &P::ScalarField,
+        proof: &HidingKZGEvalProof<P::G1, P::ScalarField>,
+    ) -> PairingCheck<P> {
+        let (powers_g1, powers_g2) = self.kzg.powers();
+        let lhs_g1 = commitment
+            .value
+            .sub(&powers_g1[0].vartime_mul(y))
+            .sub(&self.blinding_powers[0].vartime_mul(&proof.blinding_eval));
+        let rhs_g2 = powers_g2[1].sub(&powers_g2[0].vartime_mul(x));
+        PairingCheck::new(
+            vec![(lhs_g1, powers_g2[0].clone())],
+            vec![(proof.witness.clone(), rhs_g2)],
+        )
+    }
+
+    /// Verifies that the polynomial in `commitment` evaluates to `y` on `x`
+    pub fn verify_eval(
+        &self,
+        commitment: &KZGCommitment<P::G1>,
+        x: &P::ScalarField,
+        y: &P::ScalarField,
+        proof: &HidingKZGEvalProof<P::G1, P::ScalarField>,
+    ) -> Result<()> {
+        self.eval_pairing_check(commitment, x, y, proof)
+            .verify()
+            .c(d!(PolyComSchemeError::PCSProveEvalError))
+    }
+
+    /// Returns a digest of the SRS, including the powers of the blinding generator, see
+    /// KZGCommitmentScheme::digest
+    pub fn digest(&self) -> [u8; 32] {
+        let mut transcript = Transcript::new(b"Hiding KZG SRS digest");
+        transcript.append_message(b"KZG SRS digest", &self.kzg.digest());
+        transcript.append_u64(b"blinding powers", self.blinding_powers.len() as u64);
+        for power in self.blinding_powers.iter() {
+            transcript.append_message(b"power", &power.to_compressed_bytes());
+        }
+        let mut digest = [0u8; 32];
+        transcript.challenge_bytes(b"digest", &mut digest);
+        digest
+    }
+}
+
+pub type HidingKZGCommitmentSchemeBLS = HidingKZGCommitmentScheme<Bls12381>;
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_hiding::{
+        HidingKZGCommitmentScheme, HidingKZGCommitmentSchemeBLS,
+    };
+    use crate::commitments::pcs::{HomomorphicPolyComElem, PolyComScheme};
+    use crate::polynomials::field_polynomial::FpPolynomial;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::bn254::Bn254;
+    use algebra::groups::{One, Pairing, Scalar, ScalarArithmetic};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn check_hiding_eval<P: Pairing>(pcs: &HidingKZGCommitmentScheme<P>) {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let poly = FpPolynomial::random(&mut prng, pcs.max_degree());
+        let (com, opening) = pnk!(pcs.commit(&mut prng, poly.clone()));
+        assert_eq!(pnk!(pcs.commitment_from_opening(&opening)), com);
+
+        // the same polynomial committed twice gives different commitments, none of them
+        // being the non-hiding commitment
+        let (com2, opening2) = pnk!(pcs.commit(&mut prng, poly.clone()));
+        assert_ne!(com, com2);
+        let (non_hiding_com, _) = pnk!(pcs.non_hiding().commit(poly.clone()));
+        assert_ne!(com, non_hiding_com);
+
+        let x = P::ScalarField::random(&mut prng);
+        let (y, proof) = pnk!(pcs.prove_eval(&opening, &x));
+        assert_eq!(y, poly.eval(&x));
+        assert!(pcs.verify_eval(&com, &x, &y, &proof).is_ok());
+        assert!(pcs.verify_eval(&com2, &x, &y, &proof).is_err());
+        let wrong_y = y.add(&P::ScalarField::one());
+        assert!(pcs.verify_eval(&com, &x, &wrong_y, &proof).is_err());
+        let wrong_x = x.add(&P::ScalarField::one());
+        assert!(pcs.verify_eval(&com, &wrong_x, &y, &proof).is_err());
+
+        // commitments are homomorphic
+        let c = P::ScalarField::from_u32(7);
+        let combined_opening = opening.add(&opening2.mul_scalar(&c));
+        let combined_com = com.op(&com2.exp(&c));
+        assert_eq!(
+            pnk!(pcs.commitment_from_opening(&combined_opening)),
+            combined_com
+        );
+        let (y, proof) = pnk!(pcs.prove_eval(&combined_opening, &x));
+        assert_eq!(y, poly.eval(&x).mul(&P::ScalarField::from_u32(8)));
+        assert!(pcs.verify_eval(&combined_com, &x, &y, &proof).is_ok());
+
+        let too_large = FpPolynomial::random(&mut prng, pcs.max_degree() + 1);
+        assert!(pcs.commit(&mut prng, too_large).is_err());
+    }
+
+    #[test]
+    fn test_hiding_kzg() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs = pnk!(HidingKZGCommitmentSchemeBLS::new(10, 2, &mut prng));
+        assert_eq!(pcs.hiding_bound(), 2);
+        check_hiding_eval(&pcs);
+        check_hiding_eval(&pnk!(HidingKZGCommitmentScheme::<Bn254>::new(
+            10, 1, &mut prng
+        )));
+        assert!(HidingKZGCommitmentSchemeBLS::new(10, 0, &mut prng).is_err());
+
+        // serialization
+        let bytes = bincode::serialize(&pcs).unwrap();
+        let recovered: HidingKZGCommitmentSchemeBLS =
+            bincode::deserialize(&bytes).unwrap();
+        assert_eq!(recovered.digest(), pcs.digest());
+        assert_ne!(pcs.digest(), pcs.non_hiding().digest());
+        let poly = FpPolynomial::<BLSScalar>::random(&mut prng, 10);
+        let (com, opening) = pnk!(pcs.commit(&mut prng, poly));
+        assert_eq!(pnk!(recovered.commitment_from_opening(&opening)), com);
+    }
+}
@@ -0,0 +1,163 @@
+// This file implements the Lagrange form of the SRS of the KZG polynomial commitment scheme
+// for the domain H = (1, w, ..., w^{n-1}) of the n-th roots of unity: the commitments
+// g1^{L_i(s)} to the Lagrange polynomials L_i of H, with L_i(w^j) = 1 if i = j and 0
+// otherwise.
+// A polynomial f of degree lower than n is f = \sum_i f(w^i) L_i, hence its commitment is the
+// multi-exponentiation of the Lagrange form by its evaluations on H, and committing to a
+// polynomial given by its evaluations, such as a witness polynomial, does not need an inverse
+// FFT. As L_i(X) = 1/n \sum_j w^{-ij} X^j, the Lagrange form is the inverse DFT of the first n
+// powers of the SRS, computed once in O(n log n) group operations and cached, e.g. serialized
+// along the SRS.
+// A smaller SRS is derived with KZGCommitmentScheme::truncate. A larger SRS cannot be derived
+// without the secret, it is loaded from a ceremony instead (see kzg_ceremony).
+
+use crate::commitments::kzg_fk::group_ifft;
+use crate::commitments::kzg_poly_com::{KZGCommitment, KZGCommitmentScheme};
+use crate::polynomials::field_polynomial::primitive_nth_root_of_unity;
+use algebra::groups::{Group, Pairing};
+use ruc::*;
+use utils::errors::ZeiError;
+
+/// Lagrange form of the SRS of the KZG polynomial commitment scheme for the domain of the
+/// `domain_size`-th roots of unity
+#[derive(Serialize, Deserialize)]
+pub struct KZGLagrangeSRS<P: Pairing> {
+    domain_size: usize,
+    #[serde(with = "algebra::serialization::batch_points")]
+    lagrange_powers: Vec<P::G1>,
+}
+
+impl<P: Pairing> KZGLagrangeSRS<P> {
+    /// Returns the size of the domain
+    pub fn domain_size(&self) -> usize {
+        self.domain_size
+    }
+
+    /// Returns the commitments g1^{L_i(s)} to the Lagrange polynomials of the domain
+    pub fn lagrange_powers(&self) -> &[P::G1] {
+        &self.lagrange_powers
+    }
+
+    /// Commits to the polynomial of degree lower than the size of the domain whose evaluations
+    /// on the domain are `evals`, in the order of the powers of the root of unity returned by
+    /// primitive_nth_root_of_unity. The commitment is the same as the commitment of
+    /// PolyComScheme::commit to the interpolated polynomial.
+    pub fn commit_evals(
+        &self,
+        evals: &[P::ScalarField],
+    ) -> Result<KZGCommitment<P::G1>> {
+        if evals.len() != self.domain_size {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let evals_ref: Vec<&P::ScalarField> = evals.iter().collect();
+        let powers_ref: Vec<&P::G1> = self.lagrange_powers.iter().collect();
+        Ok(KZGCommitment {
+            value: P::G1::vartime_multi_exp(&evals_ref, &powers_ref),
+        })
+    }
+}
+
+impl<P: Pairing> KZGCommitmentScheme<P> {
+    /// Computes the Lagrange form of the SRS for the domain of the `domain_size`-th roots of
+    /// unity. `domain_size` must be a power of two, not larger than max_degree() + 1.
+    pub fn lagrange_srs(&self, domain_size: usize) -> Result<KZGLagrangeSRS<P>> {
+        if domain_size == 0
+            || !domain_size.is_power_of_two()
+            || domain_size > self.public_parameter_group_1.len()
+        {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let root = primitive_nth_root_of_unity::<P::ScalarField>(domain_size)
+            .c(d!(ZeiError::ParameterError))?;
+        let lagrange_powers =
+            group_ifft(&self.public_parameter_group_1[..domain_size], &root).c(d!())?;
+        Ok(KZGLagrangeSRS {
+            domain_size,
+            lagrange_powers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_lagrange::KZGLagrangeSRS;
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitmentScheme, KZGCommitmentSchemeBLS, KZGCommitmentSchemeBN254,
+    };
+    use crate::commitments::pcs::PolyComScheme;
+    use crate::polynomials::evaluation_domain::EvaluationDomain;
+    use crate::polynomials::field_polynomial::FpPolynomial;
+    use algebra::bls12_381::{BLSScalar, Bls12381};
+    use algebra::groups::{One, Pairing, Scalar, Zero};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn check_lagrange_srs<P: Pairing>(pcs: &KZGCommitmentScheme<P>) {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        for domain_size in [1, 2, 8, 16] {
+            let lagrange_srs = pnk!(pcs.lagrange_srs(domain_size));
+            assert_eq!(lagrange_srs.domain_size(), domain_size);
+            let domain = EvaluationDomain::<P::ScalarField>::new(domain_size).unwrap();
+
+            // the i-th element is the commitment to L_i
+            let mut unit = vec![P::ScalarField::zero(); domain_size];
+            unit[domain_size - 1] = P::ScalarField::one();
+            let (expected, _) = pnk!(pcs.commit(domain.ifft(&unit)));
+            assert_eq!(
+                lagrange_srs.lagrange_powers()[domain_size - 1],
+                expected.value
+            );
+
+            let evals: Vec<P::ScalarField> = (0..domain_size)
+                .map(|_| P::ScalarField::random(&mut prng))
+                .collect();
+            let (expected, _) = pnk!(pcs.commit(domain.ifft(&evals)));
+            assert_eq!(pnk!(lagrange_srs.commit_evals(&evals)), expected);
+            assert!(lagrange_srs.commit_evals(&evals[1..]).is_err());
+        }
+        assert!(pcs.lagrange_srs(0).is_err());
+        assert!(pcs.lagrange_srs(12).is_err());
+        assert!(pcs.lagrange_srs(32).is_err());
+    }
+
+    #[test]
+    fn test_lagrange_srs() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(20, &mut prng);
+        check_lagrange_srs(&pcs);
+        check_lagrange_srs(&pnk!(pcs.truncate(15)));
+        let pcs: KZGCommitmentSchemeBN254 = KZGCommitmentScheme::new(20, &mut prng);
+        check_lagrange_srs(&pcs);
+
+        // committing to a constant polynomial
+        let pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(20, &mut prng);
+        let lagrange_srs = pnk!(pcs.lagrange_srs(8));
+        let two = BLSScalar::from_u32(2);
+        let (expected, _) = pnk!(pcs.commit(FpPolynomial::from_coefs(vec![two])));
+        assert_eq!(pnk!(lagrange_srs.commit_evals(&[two; 8])), expected);
+    }
+
+    #[test]
+    fn test_lagrange_srs_serialization() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(20, &mut prng);
+        let lagrange_srs = pnk!(pcs.lagrange_srs(16));
+        let bytes = bincode::serialize(&lagrange_srs).unwrap();
+        let recovered: KZGLagrangeSRS<Bls12381> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(recovered.domain_size(), 16);
+        assert_eq!(recovered.lagrange_powers(), lagrange_srs.lagrange_powers());
+        let json = serde_json::to_string(&lagrange_srs).unwrap();
+        let recovered: KZGLagrangeSRS<Bls12381> = serde_json::from_str(&json).unwrap();
+        assert_eq!(recovered.lagrange_powers(), lagrange_srs.lagrange_powers());
+
+        // the truncated SRS and its Lagrange form are serialized separately
+        let small = pnk!(pcs.truncate(7));
+        let bytes = bincode::serialize(&small).unwrap();
+        let recovered: KZGCommitmentSchemeBLS = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(
+            pnk!(recovered.lagrange_srs(8)).lagrange_powers(),
+            pnk!(pcs.lagrange_srs(8)).lagrange_powers()
+        );
+    }
+}
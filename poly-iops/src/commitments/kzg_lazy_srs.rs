@@ -0,0 +1,205 @@
+// This file implements a lazily loaded SRS (structured reference string) for the KZG polynomial
+// commitment scheme. The SRS is read from the file format of KZGCommitmentScheme::from_file
+// (the bincode encoding of KZGCommitmentScheme), but only the powers of the secret in group 2
+// are loaded upfront. The powers in group 1 are loaded on demand, up to the max degree of the
+// polynomials of the circuit at hand, so that a service hosting many small circuits does not
+// materialize the whole universal SRS.
+// The powers needed by a KZGCommitmentScheme of degree d are the first d + 1 powers in group 1,
+// hence the loaded powers are always a prefix of the SRS, and loading more powers resumes
+// where the previous load stopped. Every element has a fixed size in the encoding, so the
+// position of a power in the file is computed rather than searched for.
+
+use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
+use algebra::groups::{batch_from_compressed_bytes, Group, Pairing};
+use ruc::*;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use utils::errors::ZeiError;
+
+// bincode encodes vector lengths and byte string lengths as u64
+const LENGTH_PREFIX_SIZE: u64 = 8;
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut bytes = [0u8; LENGTH_PREFIX_SIZE as usize];
+    reader
+        .read_exact(&mut bytes)
+        .c(d!(ZeiError::DeserializationError))?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// Reads `n` elements, each encoded as a length prefix followed by its compressed bytes.
+// The elements are read one by one, so that a corrupted length cannot trigger a large
+// allocation, then decompressed in a batch.
+fn read_group_elements<G: Group, R: Read>(reader: &mut R, n: usize) -> Result<Vec<G>> {
+    let mut compressed = vec![];
+    for _ in 0..n {
+        if read_u64(reader).c(d!())? != G::COMPRESSED_LEN as u64 {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
+        let mut bytes = vec![0u8; G::COMPRESSED_LEN];
+        reader
+            .read_exact(&mut bytes)
+            .c(d!(ZeiError::DeserializationError))?;
+        compressed.push(bytes);
+    }
+    batch_from_compressed_bytes(&compressed).c(d!(ZeiError::DeserializationError))
+}
+
+fn encoded_size<G: Group>() -> u64 {
+    LENGTH_PREFIX_SIZE + G::COMPRESSED_LEN as u64
+}
+
+/// SRS of the KZG polynomial commitment scheme whose powers in group 1 are loaded on demand
+/// from `reader`
+pub struct KZGLazySRS<P: Pairing, R: Read + Seek> {
+    reader: R,
+    n_powers_group_1: usize,
+    public_parameter_group_1: Vec<P::G1>,
+    public_parameter_group_2: Vec<P::G2>,
+}
+
+impl<P: Pairing, R: Read + Seek> KZGLazySRS<P, R> {
+    /// Opens the SRS encoded in `reader`, loading only its powers in group 2
+    pub fn new(mut reader: R) -> Result<KZGLazySRS<P, R>> {
+        reader
+            .seek(SeekFrom::Start(0))
+            .c(d!(ZeiError::DeserializationError))?;
+        let n_powers_group_1 = read_u64(&mut reader).c(d!())?;
+        if n_powers_group_1 == 0 {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let group_2_offset = n_powers_group_1
+            .checked_mul(encoded_size::<P::G1>())
+            .and_then(|size| size.checked_add(LENGTH_PREFIX_SIZE))
+            .c(d!(ZeiError::DeserializationError))?;
+        reader
+            .seek(SeekFrom::Start(group_2_offset))
+            .c(d!(ZeiError::DeserializationError))?;
+        let n_powers_group_2 = read_u64(&mut reader).c(d!())?;
+        if n_powers_group_2 < 2 {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let public_parameter_group_2 =
+            read_group_elements(&mut reader, n_powers_group_2 as usize).c(d!())?;
+        Ok(KZGLazySRS {
+            reader,
+            n_powers_group_1: n_powers_group_1 as usize,
+            public_parameter_group_1: vec![],
+            public_parameter_group_2,
+        })
+    }
+
+    /// Returns the max degree of the polynomials the SRS supports
+    pub fn max_degree(&self) -> usize {
+        self.n_powers_group_1 - 1
+    }
+
+    /// Returns the max degree of the polynomials supported by the powers loaded so far, or
+    /// None if no power in group 1 is loaded
+    pub fn loaded_degree(&self) -> Option<usize> {
+        self.public_parameter_group_1.len().checked_sub(1)
+    }
+
+    /// Hint that circuits with polynomials of degree up to `max_degree` are coming:
+    /// loads the missing powers in group 1 ahead of time. Does nothing if they are loaded already.
+    pub fn prefetch(&mut self, max_degree: usize) -> Result<()> {
+        if max_degree > self.max_degree() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let n_loaded = self.public_parameter_group_1.len();
+        if max_degree < n_loaded {
+            return Ok(());
+        }
+        let offset = LENGTH_PREFIX_SIZE + n_loaded as u64 * encoded_size::<P::G1>();
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .c(d!(ZeiError::DeserializationError))?;
+        let mut powers =
+            read_group_elements(&mut self.reader, max_degree + 1 - n_loaded).c(d!())?;
+        self.public_parameter_group_1.append(&mut powers);
+        Ok(())
+    }
+
+    /// Releases the powers in group 1 of degree larger than `max_degree`
+    pub fn shrink_to(&mut self, max_degree: usize) {
+        self.public_parameter_group_1
+            .truncate(max_degree.saturating_add(1));
+        self.public_parameter_group_1.shrink_to_fit();
+    }
+
+    /// Returns a KZG polynomial commitment scheme for polynomials of degree at most
+    /// `max_degree`, loading the missing powers in group 1 first
+    pub fn commitment_scheme(
+        &mut self,
+        max_degree: usize,
+    ) -> Result<KZGCommitmentScheme<P>> {
+        self.prefetch(max_degree).c(d!())?;
+        KZGCommitmentScheme::from_powers(
+            self.public_parameter_group_1[..max_degree + 1].to_vec(),
+            self.public_parameter_group_2.clone(),
+        )
+        .c(d!())
+    }
+}
+
+impl<P: Pairing> KZGLazySRS<P, BufReader<File>> {
+    /// Opens the SRS in the file `filename`, generated for KZGCommitmentScheme::from_file
+    pub fn from_file(filename: &str) -> Result<KZGLazySRS<P, BufReader<File>>> {
+        let file = File::open(filename).c(d!(ZeiError::ParameterError))?;
+        Self::new(BufReader::new(file)).c(d!())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::KZGLazySRS;
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitmentScheme, KZGCommitmentSchemeBLS,
+    };
+    use algebra::bls12_381::Bls12381;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_lazy_srs() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(40, &mut prng);
+        let srs_bytes = bincode::serialize(&pcs).unwrap();
+
+        let mut lazy_srs = pnk!(KZGLazySRS::<Bls12381, _>::new(Cursor::new(
+            srs_bytes.clone()
+        )));
+        assert_eq!(lazy_srs.max_degree(), 40);
+        assert_eq!(lazy_srs.loaded_degree(), None);
+
+        let small_pcs = pnk!(lazy_srs.commitment_scheme(5));
+        assert_eq!(lazy_srs.loaded_degree(), Some(5));
+        assert_eq!(small_pcs.powers(), pnk!(pcs.truncate(5)).powers());
+        // a smaller circuit reuses the loaded powers
+        let smaller_pcs = pnk!(lazy_srs.commitment_scheme(2));
+        assert_eq!(lazy_srs.loaded_degree(), Some(5));
+        assert_eq!(smaller_pcs.powers(), pnk!(pcs.truncate(2)).powers());
+
+        pnk!(lazy_srs.prefetch(20));
+        assert_eq!(lazy_srs.loaded_degree(), Some(20));
+        let full_pcs = pnk!(lazy_srs.commitment_scheme(40));
+        assert_eq!(full_pcs.powers(), pcs.powers());
+        assert!(lazy_srs.commitment_scheme(41).is_err());
+        assert!(lazy_srs.prefetch(41).is_err());
+
+        lazy_srs.shrink_to(3);
+        assert_eq!(lazy_srs.loaded_degree(), Some(3));
+        let pcs_10 = pnk!(lazy_srs.commitment_scheme(10));
+        assert_eq!(pcs_10.powers(), pnk!(pcs.truncate(10)).powers());
+
+        // truncated or corrupted SRS
+        let mut truncated = srs_bytes.clone();
+        truncated.pop();
+        assert!(KZGLazySRS::<Bls12381, _>::new(Cursor::new(truncated)).is_err());
+        let mut corrupted = srs_bytes;
+        corrupted[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(KZGLazySRS::<Bls12381, _>::new(Cursor::new(corrupted)).is_err());
+    }
+}
@@ -1,9 +1,13 @@
+use crate::backend::{ArithmeticBackend, CpuBackend};
 use crate::commitments::pcs::{
     HomomorphicPolyComElem, PolyComScheme, PolyComSchemeError, ToBytes,
 };
+use crate::plonk::transcript::PlonkTranscript;
 use crate::polynomials::field_polynomial::FpPolynomial;
-use algebra::bls12_381::{BLSScalar, Bls12381, BLSG1};
+use algebra::bls12_381::Bls12381;
+use algebra::bn254::Bn254;
 use algebra::groups::{Group, GroupArithmetic, One, Pairing, Scalar, ScalarArithmetic};
+use algebra::pairing_batch::{PairingCheck, PairingCheckBatch};
 use merlin::Transcript;
 use rand_core::{CryptoRng, RngCore};
 use ruc::*;
@@ -34,12 +38,13 @@ use ruc::*;
 ///    The goal of this verification procedure is to check that indeed P(X)-y=Q(X)(X-x) using pairings
 ///    Check that e(C/g1^y,g2) == e(proof,g2^s/g2^x)
 ///
+use std::cmp::max;
 use std::fs;
 use utils::errors::ZeiError;
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct KZGCommitment<G> {
-    value: G,
+    pub(crate) value: G,
 }
 impl<'a, G> ToBytes for KZGCommitment<G>
 where
@@ -49,17 +54,17 @@ where
         self.value.to_compressed_bytes()
     }
 }
-impl HomomorphicPolyComElem for KZGCommitment<BLSG1> {
-    type Scalar = BLSScalar;
+impl<G: Group> HomomorphicPolyComElem for KZGCommitment<G> {
+    type Scalar = G::S;
     fn get_base() -> Self {
         KZGCommitment {
-            value: BLSG1::get_base(),
+            value: G::get_base(),
         }
     }
 
     fn get_identity() -> Self {
         KZGCommitment {
-            value: BLSG1::get_identity(),
+            value: G::get_identity(),
         }
     }
 
@@ -73,18 +78,19 @@ impl HomomorphicPolyComElem for KZGCommitment<BLSG1> {
         self.value = self.value.add(&other.value); // TODO have real add_assign
     }
 
-    fn exp(&self, exp: &BLSScalar) -> Self {
+    // commitments and the scalars they are raised to are public
+    fn exp(&self, exp: &G::S) -> Self {
         KZGCommitment {
-            value: self.value.mul(exp),
+            value: self.value.vartime_mul(exp),
         }
     }
 
-    fn exp_assign(&mut self, exp: &BLSScalar) {
-        self.value = self.value.mul(&exp); // TODO have real add_assign
+    fn exp_assign(&mut self, exp: &G::S) {
+        self.value = self.value.vartime_mul(&exp); // TODO have real add_assign
     }
 
     fn inv(&self) -> Self {
-        let minus_one_scalar = BLSScalar::one().neg();
+        let minus_one_scalar = G::S::one().neg();
         KZGCommitment {
             value: self.value.mul(&minus_one_scalar),
         }
@@ -130,7 +136,7 @@ impl<F: Scalar> HomomorphicPolyComElem for FpPolynomial<F> {
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
-pub struct KZGEvalProof<G1>(G1);
+pub struct KZGEvalProof<G1>(pub(crate) G1);
 
 impl<G: Group> ToBytes for KZGEvalProof<G> {
     fn to_bytes(&self) -> Vec<u8> {
@@ -140,8 +146,10 @@ impl<G: Group> ToBytes for KZGEvalProof<G> {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KZGCommitmentScheme<P: Pairing> {
-    public_parameter_group_1: Vec<P::G1>,
-    public_parameter_group_2: Vec<P::G2>,
+    #[serde(with = "algebra::serialization::batch_points")]
+    pub(crate) public_parameter_group_1: Vec<P::G1>,
+    #[serde(with = "algebra::serialization::batch_points")]
+    pub(crate) public_parameter_group_2: Vec<P::G2>,
 }
 
 impl<P: Pairing> KZGCommitmentScheme<P> {
@@ -151,9 +159,30 @@ impl<P: Pairing> KZGCommitmentScheme<P> {
     pub fn new<R: CryptoRng + RngCore>(
         max_degree: usize,
         prng: &mut R,
+    ) -> KZGCommitmentScheme<P> {
+        Self::new_with_g2_powers(max_degree, 1, prng)
+    }
+
+    /// Creates a new instance of a KZG polynomial commitment scheme with `max_degree_g2 + 1`
+    /// powers of the secret in group 2, as needed to verify openings on several points at once.
+    /// `max_degree` - max degree of the polynomial
+    /// `max_degree_g2` - max degree of the vanishing polynomials used in verification
+    /// `prng` - pseudo-random generator
+    pub fn new_with_g2_powers<R: CryptoRng + RngCore>(
+        max_degree: usize,
+        max_degree_g2: usize,
+        prng: &mut R,
     ) -> KZGCommitmentScheme<P> {
         let s = P::ScalarField::random(prng);
+        Self::from_secret(&s, max_degree, max_degree_g2)
+    }
 
+    // Computes the powers of the secret `s`, which the caller must forget
+    pub(crate) fn from_secret(
+        s: &P::ScalarField,
+        max_degree: usize,
+        max_degree_g2: usize,
+    ) -> KZGCommitmentScheme<P> {
         let mut public_parameter_group_1: Vec<P::G1> = Vec::new();
 
         let mut elem_g1 = P::G1::get_base();
@@ -161,13 +190,15 @@ impl<P: Pairing> KZGCommitmentScheme<P> {
         for _ in 0..max_degree + 1 {
             //for _ in 0..max_degree + 1 {
             public_parameter_group_1.push(elem_g1.clone());
-            elem_g1 = elem_g1.mul(&s);
+            elem_g1 = elem_g1.mul(s);
         }
 
         let mut public_parameter_group_2: Vec<P::G2> = Vec::new();
-        let elem_g2 = P::G2::get_base();
-        public_parameter_group_2.push(elem_g2.clone());
-        public_parameter_group_2.push(elem_g2.mul(&s));
+        let mut elem_g2 = P::G2::get_base();
+        for _ in 0..max(max_degree_g2, 1) + 1 {
+            public_parameter_group_2.push(elem_g2.clone());
+            elem_g2 = elem_g2.mul(s);
+        }
 
         KZGCommitmentScheme {
             public_parameter_group_1,
@@ -175,6 +206,251 @@ impl<P: Pairing> KZGCommitmentScheme<P> {
         }
     }
 
+    /// Creates an instance of a KZG polynomial commitment scheme from the powers of a secret
+    /// computed elsewhere (e.g. in a setup ceremony)
+    /// `public_parameter_group_1` - powers g1, g1^s, ..., g1^{s^n} of the secret in group 1
+    /// `public_parameter_group_2` - powers g2, g2^s, ... of the secret in group 2
+    pub fn from_powers(
+        public_parameter_group_1: Vec<P::G1>,
+        public_parameter_group_2: Vec<P::G2>,
+    ) -> Result<KZGCommitmentScheme<P>> {
+        if public_parameter_group_1.is_empty() || public_parameter_group_2.len() < 2 {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(KZGCommitmentScheme {
+            public_parameter_group_1,
+            public_parameter_group_2,
+        })
+    }
+
+    /// Returns the max degree of the polynomials the scheme can commit to
+    pub fn max_degree(&self) -> usize {
+        self.public_parameter_group_1.len() - 1
+    }
+
+    /// Returns the powers of the secret in group 1 and in group 2
+    pub fn powers(&self) -> (&[P::G1], &[P::G2]) {
+        (
+            &self.public_parameter_group_1,
+            &self.public_parameter_group_2,
+        )
+    }
+
+    /// Derives a scheme for polynomials of degree at most `max_degree` by dropping the extra
+    /// powers in group 1
+    pub fn truncate(&self, max_degree: usize) -> Result<KZGCommitmentScheme<P>> {
+        if max_degree > self.max_degree() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(KZGCommitmentScheme {
+            public_parameter_group_1: self.public_parameter_group_1[..max_degree + 1]
+                .to_vec(),
+            public_parameter_group_2: self.public_parameter_group_2.clone(),
+        })
+    }
+
+    /// Returns the inputs of the two pairings compared when verifying that the polynomial
+    /// in `C` evaluates to `y` on `x`: ((g1^{P(s)-y}, g2), (proof, g2^{s-x}))
+    #[allow(non_snake_case)]
+    #[allow(clippy::type_complexity)]
+    pub fn verify_eval_pairing_inputs(
+        &self,
+        C: &KZGCommitment<P::G1>,
+        x: &P::ScalarField,
+        y: &P::ScalarField,
+        proof: &KZGEvalProof<P::G1>,
+    ) -> ((P::G1, P::G2), (P::G1, P::G2)) {
+        let g1_0 = &self.public_parameter_group_1[0];
+        let g2_0 = &self.public_parameter_group_2[0];
+        let g2_1 = &self.public_parameter_group_2[1];
+        (
+            (C.value.sub(&g1_0.vartime_mul(y)), g2_0.clone()),
+            (proof.0.clone(), g2_1.sub(&g2_0.vartime_mul(x))),
+        )
+    }
+
+    /// Returns the pairing check verifying that the polynomial in `C` evaluates to `y` on `x`,
+    /// to be batched with the pairing checks of other proofs
+    #[allow(non_snake_case)]
+    pub fn eval_pairing_check(
+        &self,
+        C: &KZGCommitment<P::G1>,
+        x: &P::ScalarField,
+        y: &P::ScalarField,
+        proof: &KZGEvalProof<P::G1>,
+    ) -> PairingCheck<P> {
+        let (lhs, rhs) = self.verify_eval_pairing_inputs(C, x, y, proof);
+        PairingCheck::new(vec![lhs], vec![rhs])
+    }
+
+    /// Returns the pairing check verifying a proof of multi_point_prove_eval, to be batched
+    /// with the pairing checks of other proofs. Let T be the union of the points, Z_S the
+    /// vanishing polynomial of a set S, and r_i the polynomial of degree lower than |S_i|
+    /// interpolating the values of the i-th polynomial f_i on its points S_i. The proof is
+    /// W = g1^{h(s)} for h = sum_i gamma^i * (f_i - r_i) / Z_{S_i}, hence
+    /// e(W, g2^{Z_T(s)}) == prod_i e(gamma^i * (C_i - g1^{r_i(s)}), g2^{Z_{T - S_i}(s)}),
+    /// with one pairing per distinct set of points on the right-hand side. The scheme needs
+    /// |T| + 1 powers of the secret in group 2, see new_with_g2_powers.
+    pub fn multi_point_pairing_check(
+        &self,
+        commitments: &[&KZGCommitment<P::G1>],
+        points: &[&[P::ScalarField]],
+        values: &[Vec<P::ScalarField>],
+        gamma: &P::ScalarField,
+        proof: &KZGEvalProof<P::G1>,
+    ) -> Result<PairingCheck<P>> {
+        let mut all_points: Vec<P::ScalarField> = vec![];
+        for point in points.iter().flat_map(|points_i| points_i.iter()) {
+            if !all_points.contains(point) {
+                all_points.push(*point);
+            }
+        }
+        if all_points.len() >= self.public_parameter_group_2.len() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let powers_2_ref: Vec<&P::G2> = self.public_parameter_group_2.iter().collect();
+        let commit_g2 = |poly: &FpPolynomial<P::ScalarField>| {
+            let coefs: Vec<&P::ScalarField> = poly.get_coefs_ref().iter().collect();
+            P::G2::vartime_multi_exp(&coefs, &powers_2_ref[..coefs.len()])
+        };
+
+        // group the polynomials by set of points, and combine each group into
+        // sum_i gamma^i * C_i - g1^{sum_i gamma^i * r_i(s)}
+        let mut groups: Vec<(&[P::ScalarField], P::G1, FpPolynomial<P::ScalarField>)> =
+            vec![];
+        let mut gamma_i = P::ScalarField::one();
+        for ((commitment, points_i), values_i) in
+            commitments.iter().zip(points).zip(values)
+        {
+            let r_i = FpPolynomial::from_interpolation(points_i, values_i)
+                .c(d!(PolyComSchemeError::PCSProveEvalError))?
+                .mul_scalar(&gamma_i);
+            let c_i = commitment.value.vartime_mul(&gamma_i);
+            match groups.iter_mut().find(|(set, _, _)| *set == *points_i) {
+                Some((_, c, r)) => {
+                    *c = c.add(&c_i);
+                    r.add_assign(&r_i);
+                }
+                None => groups.push((*points_i, c_i, r_i)),
+            }
+            gamma_i.mul_assign(gamma);
+        }
+
+        let mut rhs = vec![];
+        for (set, c, r) in groups.iter() {
+            let (r_commitment, _) = self.commit(r.clone()).c(d!())?;
+            let others: Vec<P::ScalarField> = all_points
+                .iter()
+                .filter(|point| !set.contains(*point))
+                .cloned()
+                .collect();
+            rhs.push((
+                c.sub(&r_commitment.value),
+                commit_g2(&FpPolynomial::from_zeroes(&others)),
+            ));
+        }
+        let lhs = vec![(
+            proof.0.clone(),
+            commit_g2(&FpPolynomial::from_zeroes(&all_points)),
+        )];
+        Ok(PairingCheck::new(lhs, rhs))
+    }
+
+    /// Checks that the SRS is well formed, e.g. after loading it from an untrusted source: the
+    /// powers start with the generators g1 and g2, and are powers of the same secret s in both
+    /// groups, that is e(g1^{s^{i+1}}, g2) == e(g1^{s^i}, g2^s) for the powers in group 1 and
+    /// e(g1, g2^{s^{j+1}}) == e(g1^s, g2^{s^j}) for the powers in group 2. The equations are
+    /// combined with random scalars sampled from `prng` and checked with a single pairing
+    /// check. Membership of the powers in the prime order subgroups is checked when the SRS is
+    /// deserialized.
+    pub fn check_integrity<R: CryptoRng + RngCore>(&self, prng: &mut R) -> Result<()> {
+        let powers_1 = &self.public_parameter_group_1;
+        let powers_2 = &self.public_parameter_group_2;
+        if powers_1.is_empty()
+            || powers_2.len() < 2
+            || powers_1[0] != P::G1::get_base()
+            || powers_2[0] != P::G2::get_base()
+            || powers_2[1] == P::G2::get_identity()
+        {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+
+        let mut batch = PairingCheckBatch::new();
+        let n_1 = powers_1.len() - 1;
+        let r: Vec<P::ScalarField> =
+            (0..n_1).map(|_| P::ScalarField::random(prng)).collect();
+        let r_ref: Vec<&P::ScalarField> = r.iter().collect();
+        let powers_1_ref: Vec<&P::G1> = powers_1.iter().collect();
+        // e(sum_i r_i * g1^{s^{i+1}}, g2) == e(sum_i r_i * g1^{s^i}, g2^s)
+        batch.add_check(PairingCheck::new(
+            vec![(
+                P::G1::vartime_multi_exp(&r_ref, &powers_1_ref[1..]),
+                powers_2[0].clone(),
+            )],
+            vec![(
+                P::G1::vartime_multi_exp(&r_ref, &powers_1_ref[..n_1]),
+                powers_2[1].clone(),
+            )],
+        ));
+        if n_1 > 0 && powers_2.len() > 2 {
+            let n_2 = powers_2.len() - 1;
+            let t: Vec<P::ScalarField> =
+                (0..n_2).map(|_| P::ScalarField::random(prng)).collect();
+            let t_ref: Vec<&P::ScalarField> = t.iter().collect();
+            let powers_2_ref: Vec<&P::G2> = powers_2.iter().collect();
+            // e(g1, sum_j t_j * g2^{s^{j+1}}) == e(g1^s, sum_j t_j * g2^{s^j})
+            batch.add_check(PairingCheck::new(
+                vec![(
+                    powers_1[0].clone(),
+                    P::G2::vartime_multi_exp(&t_ref, &powers_2_ref[1..]),
+                )],
+                vec![(
+                    powers_1[1].clone(),
+                    P::G2::vartime_multi_exp(&t_ref, &powers_2_ref[..n_2]),
+                )],
+            ));
+        }
+        batch.verify(prng).c(d!(ZeiError::ParameterError))
+    }
+
+    /// Returns a digest of the SRS, which identifies it, e.g. to pin the SRS that verifier
+    /// parameters are preprocessed for. Two schemes have the same digest iff they have the
+    /// same powers, so a truncated SRS has a digest of its own.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut transcript = Transcript::new(b"KZG SRS digest");
+        transcript.append_u64(
+            b"powers in group 1",
+            self.public_parameter_group_1.len() as u64,
+        );
+        for power in self.public_parameter_group_1.iter() {
+            transcript.append_message(b"power", &power.to_compressed_bytes());
+        }
+        transcript.append_u64(
+            b"powers in group 2",
+            self.public_parameter_group_2.len() as u64,
+        );
+        for power in self.public_parameter_group_2.iter() {
+            transcript.append_message(b"power", &power.to_compressed_bytes());
+        }
+        let mut digest = [0u8; 32];
+        transcript.challenge_bytes(b"digest", &mut digest);
+        digest
+    }
+
+    /// Creates a scheme from the bincode encoding of its SRS, as written by
+    /// `zkp-params-utils` or by srs_from_ptau and srs_from_ignition after serialization, and
+    /// checks its integrity with randomness sampled from `prng`, so that an SRS from an
+    /// untrusted source is never used unchecked
+    pub fn from_srs_bytes<R: CryptoRng + RngCore>(
+        bytes: &[u8],
+        prng: &mut R,
+    ) -> Result<KZGCommitmentScheme<P>> {
+        let pcs: KZGCommitmentScheme<P> =
+            bincode::deserialize(bytes).c(d!(ZeiError::DeserializationError))?;
+        pcs.check_integrity(prng).c(d!())?;
+        Ok(pcs)
+    }
+
     /// Get the public parameters from a file
     /// This file is generated by the executable `zkp-params-utils`
     /// * `filename` - name of the file containing the data of the public parameters
@@ -193,15 +469,27 @@ impl<P: Pairing> KZGCommitmentScheme<P> {
     }
 }
 pub type KZGCommitmentSchemeBLS = KZGCommitmentScheme<Bls12381>;
-impl<'b> PolyComScheme for KZGCommitmentSchemeBLS {
-    type Field = BLSScalar;
-    type Commitment = KZGCommitment<BLSG1>;
-    type EvalProof = KZGEvalProof<BLSG1>;
+
+/// KZG commitments over BN254, whose proofs are cheap to verify on EVM chains
+pub type KZGCommitmentSchemeBN254 = KZGCommitmentScheme<Bn254>;
+
+impl<P: Pairing> PolyComScheme for KZGCommitmentScheme<P> {
+    type Field = P::ScalarField;
+    type Commitment = KZGCommitment<P::G1>;
+    type EvalProof = KZGEvalProof<P::G1>;
     type Opening = FpPolynomial<Self::Field>;
 
     fn commit(
         &self,
-        polynomial: FpPolynomial<BLSScalar>,
+        polynomial: FpPolynomial<Self::Field>,
+    ) -> Result<(Self::Commitment, Self::Opening)> {
+        self.commit_with_backend(&CpuBackend, polynomial)
+    }
+
+    fn commit_with_backend<B: ArithmeticBackend>(
+        &self,
+        backend: &B,
+        polynomial: FpPolynomial<Self::Field>,
     ) -> Result<(Self::Commitment, Self::Opening)> {
         let coefs_poly = polynomial.get_coefs_ref();
 
@@ -210,15 +498,13 @@ impl<'b> PolyComScheme for KZGCommitmentSchemeBLS {
             return Err(eg!(PolyComSchemeError::PCSProveEvalError));
         }
 
-        let coefs_poly_bls_scalar_ref: Vec<&BLSScalar> = coefs_poly.iter().collect();
-        let pub_param_group_1_as_ref: Vec<&BLSG1> = self.public_parameter_group_1
+        let coefs_poly_scalar_ref: Vec<&Self::Field> = coefs_poly.iter().collect();
+        let pub_param_group_1_as_ref: Vec<&P::G1> = self.public_parameter_group_1
             [0..pol_degree + 1]
             .iter()
             .collect();
-        let commitment_value = BLSG1::vartime_multi_exp(
-            &coefs_poly_bls_scalar_ref[..],
-            &pub_param_group_1_as_ref[..],
-        );
+        let commitment_value = backend
+            .msm::<P::G1>(&coefs_poly_scalar_ref[..], &pub_param_group_1_as_ref[..]);
 
         Ok((
             KZGCommitment {
@@ -260,9 +546,9 @@ impl<'b> PolyComScheme for KZGCommitmentSchemeBLS {
         opening
     }
 
-    fn prove_eval(
+    fn prove_eval<T: PlonkTranscript>(
         &self,
-        _transcript: &mut Transcript,
+        _transcript: &mut T,
         opening: &FpPolynomial<Self::Field>,
         x: &Self::Field,
         max_degree: usize,
@@ -292,38 +578,28 @@ impl<'b> PolyComScheme for KZGCommitmentSchemeBLS {
 
         let proof_value = self.commit(quotient_polynomial).unwrap().0.value;
 
-        let res = (
-            evaluation,
-            KZGEvalProof::<algebra::bls12_381::BLSG1>(proof_value),
-        );
+        let res = (evaluation, KZGEvalProof(proof_value));
         Ok(res)
     }
 
     #[allow(non_snake_case)]
-    fn verify_eval(
+    fn verify_eval<T: PlonkTranscript>(
         &self,
-        _transcript: &mut Transcript,
+        _transcript: &mut T,
         C: &Self::Commitment,
         _degree: usize,
         x: &Self::Field,
         y: &Self::Field,
         proof: &Self::EvalProof,
     ) -> Result<()> {
-        let g1_0 = self.public_parameter_group_1[0].clone();
-        let g2_0 = self.public_parameter_group_2[0].clone();
-        let g2_1 = self.public_parameter_group_2[1].clone();
-
-        let x_minus_point_group_element_group_2 = &g2_1.sub(&g2_0.mul(x));
+        let ((left_g1, left_g2), (right_g1, right_g2)) =
+            self.verify_eval_pairing_inputs(C, x, y, proof);
 
         // e(g1^{P(X)-P(x)},g2)
-        let left_pairing_eval =
-            algebra::bls12_381::Bls12381::pairing(&C.value.sub(&g1_0.mul(y)), &g2_0);
+        let left_pairing_eval = P::pairing(&left_g1, &left_g2);
 
         // e(g1^{Q(X)},g1^{X-x})
-        let right_pairing_eval = algebra::bls12_381::Bls12381::pairing(
-            &proof.0,
-            &x_minus_point_group_element_group_2,
-        );
+        let right_pairing_eval = P::pairing(&right_g1, &right_g2);
 
         // e(g1^{P(X)-P(x)},g2) == e(g1^{Q(X)},g2^{X-v})
         if left_pairing_eval == right_pairing_eval {
@@ -332,25 +608,233 @@ impl<'b> PolyComScheme for KZGCommitmentSchemeBLS {
             Err(eg!(PolyComSchemeError::PCSProveEvalError))
         }
     }
+
+    fn multi_point_prove_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        openings: &[&Self::Opening],
+        points: &[&[Self::Field]],
+        max_degree: usize,
+    ) -> Result<(Vec<Vec<Self::Field>>, Self::EvalProof)> {
+        if openings.len() != points.len() {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        if openings.iter().any(|opening| opening.degree() > max_degree) {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        let values: Vec<Vec<Self::Field>> = openings
+            .iter()
+            .zip(points)
+            .map(|(opening, points_i)| {
+                points_i.iter().map(|x| opening.eval(x)).collect()
+            })
+            .collect();
+        let gamma = Self::multi_point_eval_challenge(
+            transcript,
+            commitments,
+            max_degree,
+            points,
+            &values,
+        )
+        .c(d!())?;
+
+        // h = sum_i gamma^i * q_i for f_i = q_i * Z_{S_i} + r_i
+        let mut h = FpPolynomial::zero();
+        let mut gamma_i = Self::Field::one();
+        for (opening, points_i) in openings.iter().zip(points) {
+            let (q_i, _) = opening.div_rem(&FpPolynomial::from_zeroes(points_i));
+            h.add_assign(&q_i.mul_scalar(&gamma_i));
+            gamma_i.mul_assign(&gamma);
+        }
+        let (proof, _) = self.commit(h).c(d!())?;
+        Ok((values, KZGEvalProof(proof.value)))
+    }
+
+    fn multi_point_verify_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        max_degree: usize,
+        points: &[&[Self::Field]],
+        values: &[Vec<Self::Field>],
+        proof: &Self::EvalProof,
+    ) -> Result<()> {
+        let gamma = Self::multi_point_eval_challenge(
+            transcript,
+            commitments,
+            max_degree,
+            points,
+            values,
+        )
+        .c(d!())?;
+        self.multi_point_pairing_check(commitments, points, values, &gamma, proof)
+            .c(d!())?
+            .verify()
+            .c(d!(PolyComSchemeError::PCSProveEvalError))
+    }
+
+    fn srs_digest(&self) -> Vec<u8> {
+        self.digest().to_vec()
+    }
+
+    fn max_degree(&self) -> usize {
+        self.public_parameter_group_1.len() - 1
+    }
 }
 
 #[cfg(test)]
 mod tests_kzg_impl {
     use crate::commitments::kzg_poly_com::{
-        KZGCommitmentScheme, KZGCommitmentSchemeBLS,
+        KZGCommitmentScheme, KZGCommitmentSchemeBLS, KZGCommitmentSchemeBN254,
+    };
+    use crate::commitments::pcs::{
+        DegreeBoundCommitment, HomomorphicPolyComElem, PolyComScheme,
     };
-    use crate::commitments::pcs::{HomomorphicPolyComElem, PolyComScheme};
     use algebra::groups::{Group, Pairing};
 
     use crate::polynomials::field_polynomial::FpPolynomial;
-    use algebra::bls12_381::{BLSScalar, Bls12381, BLSG1};
-    use algebra::groups::{GroupArithmetic, One, ScalarArithmetic};
+    use algebra::bls12_381::{BLSScalar, Bls12381, BLSG1, BLSG2};
+    use algebra::bn254::Bn254;
+    use algebra::groups::{GroupArithmetic, One, Scalar, ScalarArithmetic};
     use itertools::Itertools;
     use merlin::Transcript;
     use rand_chacha::ChaChaRng;
     use rand_core::SeedableRng;
     use ruc::*;
 
+    #[test]
+    fn test_degree_bound() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(20, &mut prng);
+        let poly = FpPolynomial::from_coefs(
+            (0..6).map(|i| BLSScalar::from_u32(i + 1)).collect_vec(),
+        );
+
+        for degree_bound in [5, 12, 20] {
+            let (commitment, opening, shifted_opening) =
+                pnk!(pcs.commit_with_degree_bound(poly.clone(), degree_bound));
+            assert_eq!(commitment.commitment(), &pnk!(pcs.commit(poly.clone())).0);
+            let proof = pnk!(pcs.prove_degree_bound(
+                &mut Transcript::new(b"TestDegreeBound"),
+                &commitment,
+                &opening,
+                &shifted_opening,
+                degree_bound
+            ));
+            pnk!(pcs.verify_degree_bound(
+                &mut Transcript::new(b"TestDegreeBound"),
+                &commitment,
+                degree_bound,
+                &proof
+            ));
+            // the proof does not hold for a smaller bound
+            assert!(pcs
+                .verify_degree_bound(
+                    &mut Transcript::new(b"TestDegreeBound"),
+                    &commitment,
+                    degree_bound - 1,
+                    &proof
+                )
+                .is_err());
+        }
+
+        // the degree of the polynomial exceeds the bound
+        assert!(pcs.commit_with_degree_bound(poly.clone(), 4).is_err());
+        assert!(pcs.commit_with_degree_bound(poly.clone(), 21).is_err());
+        // a shifted commitment to a polynomial of too low degree is rejected
+        let (commitment, opening, _) =
+            pnk!(pcs.commit_with_degree_bound(poly.clone(), 5));
+        let (_, bad_shifted_opening) = pnk!(pcs.commit(poly.shift(10)));
+        let bad_commitment = DegreeBoundCommitment {
+            commitment: commitment.commitment.clone(),
+            shifted_commitment: pnk!(pcs.commit(poly.shift(10))).0,
+        };
+        let proof = pcs.prove_degree_bound(
+            &mut Transcript::new(b"TestDegreeBound"),
+            &bad_commitment,
+            &opening,
+            &bad_shifted_opening,
+            4,
+        );
+        assert!(proof.is_err());
+    }
+
+    #[test]
+    fn test_check_integrity() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs: KZGCommitmentSchemeBLS =
+            KZGCommitmentScheme::new_with_g2_powers(10, 3, &mut prng);
+        pnk!(pcs.check_integrity(&mut prng));
+        pnk!(pnk!(pcs.truncate(0)).check_integrity(&mut prng));
+
+        let (powers_1, powers_2) = pcs.powers();
+        let random_g1 = BLSG1::get_random_base(&mut prng);
+        let random_g2 = BLSG2::get_random_base(&mut prng);
+        // a wrong power in group 1
+        let mut bad_powers_1 = powers_1.to_vec();
+        bad_powers_1[4] = random_g1.clone();
+        let bad_pcs = pnk!(KZGCommitmentScheme::<Bls12381>::from_powers(
+            bad_powers_1,
+            powers_2.to_vec()
+        ));
+        assert!(bad_pcs.check_integrity(&mut prng).is_err());
+        // a wrong power in group 2
+        let mut bad_powers_2 = powers_2.to_vec();
+        bad_powers_2[3] = random_g2.clone();
+        let bad_pcs = pnk!(KZGCommitmentScheme::<Bls12381>::from_powers(
+            powers_1.to_vec(),
+            bad_powers_2
+        ));
+        assert!(bad_pcs.check_integrity(&mut prng).is_err());
+        // powers of another secret in group 2
+        let other_pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(10, &mut prng);
+        let bad_pcs = pnk!(KZGCommitmentScheme::<Bls12381>::from_powers(
+            powers_1.to_vec(),
+            other_pcs.powers().1.to_vec()
+        ));
+        assert!(bad_pcs.check_integrity(&mut prng).is_err());
+        // wrong generators
+        let mut bad_powers_1 = powers_1.to_vec();
+        bad_powers_1[0] = random_g1;
+        let bad_pcs = pnk!(KZGCommitmentScheme::<Bls12381>::from_powers(
+            bad_powers_1,
+            powers_2.to_vec()
+        ));
+        assert!(bad_pcs.check_integrity(&mut prng).is_err());
+
+        // digests
+        assert_eq!(pcs.digest(), pnk!(pcs.truncate(10)).digest());
+        assert_ne!(pcs.digest(), pnk!(pcs.truncate(9)).digest());
+        assert_ne!(pcs.digest(), other_pcs.digest());
+        assert_eq!(pcs.srs_digest(), pcs.digest().to_vec());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(10, &mut prng);
+
+        let bytes = bincode::serialize(&pcs).unwrap();
+        // same encoding as the vectors of group elements
+        let (powers_1, powers_2) = pcs.powers();
+        let mut expected_bytes = bincode::serialize(powers_1).unwrap();
+        expected_bytes.append(&mut bincode::serialize(powers_2).unwrap());
+        assert_eq!(bytes, expected_bytes);
+        let recovered: KZGCommitmentSchemeBLS = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(recovered.powers(), pcs.powers());
+
+        let json = serde_json::to_string(&pcs).unwrap();
+        let recovered: KZGCommitmentSchemeBLS = serde_json::from_str(&json).unwrap();
+        assert_eq!(recovered.powers(), pcs.powers());
+
+        // a point of group 2 out of the curve
+        let mut bad_bytes = bytes;
+        let last = bad_bytes.len() - 2;
+        bad_bytes[last] ^= 1;
+        assert!(bincode::deserialize::<KZGCommitmentSchemeBLS>(&bad_bytes).is_err());
+    }
+
     fn _check_public_parameters_generation<P: Pairing>() {
         let param_size = 5;
         let mut prng = ChaChaRng::from_seed([0u8; 32]);
@@ -397,11 +881,45 @@ mod tests_kzg_impl {
     }
 
     #[test]
-    pub fn test_homomorphic_poly_com_elem() {
+    fn test_truncate_and_from_powers() {
         let mut prng = ChaChaRng::from_seed([0_u8; 32]);
         let pcs = KZGCommitmentSchemeBLS::new(20, &mut prng);
-        type Field = BLSScalar;
-        let one = Field::one();
+        assert_eq!(pcs.max_degree(), 20);
+        let small = pnk!(pcs.truncate(10));
+        assert_eq!(small.max_degree(), 10);
+        assert_eq!(
+            small.public_parameter_group_1[..],
+            pcs.public_parameter_group_1[..11]
+        );
+        assert!(pcs.truncate(21).is_err());
+
+        let (g1_powers, g2_powers) = small.powers();
+        let rebuilt = pnk!(KZGCommitmentSchemeBLS::from_powers(
+            g1_powers.to_vec(),
+            g2_powers.to_vec()
+        ));
+        let mut poly_coefs = vec![];
+        for i in 0..11 {
+            poly_coefs.push(BLSScalar::from_u32(i));
+        }
+        let poly = FpPolynomial::from_coefs(poly_coefs);
+        let (com_pcs, _) = pnk!(pcs.commit(poly.clone()));
+        let (com_rebuilt, _) = pnk!(rebuilt.commit(poly));
+        assert_eq!(com_pcs, com_rebuilt);
+        assert!(
+            KZGCommitmentSchemeBLS::from_powers(vec![], g2_powers.to_vec()).is_err()
+        );
+        assert!(KZGCommitmentSchemeBLS::from_powers(
+            g1_powers.to_vec(),
+            g2_powers[..1].to_vec()
+        )
+        .is_err());
+    }
+
+    fn _check_homomorphic_poly_com_elem<P: Pairing>() {
+        let mut prng = ChaChaRng::from_seed([0_u8; 32]);
+        let pcs = KZGCommitmentScheme::<P>::new(20, &mut prng);
+        let one = P::ScalarField::one();
         let two = one.add(&one);
         let three = two.add(&one);
         let four = three.add(&one);
@@ -442,22 +960,10 @@ mod tests_kzg_impl {
         );
     }
 
-    #[test]
-    fn test_public_parameters() {
-        _check_public_parameters_generation::<Bls12381>();
-    }
-
-    #[test]
-    fn test_generation_of_crs() {
-        _generation_of_crs::<Bls12381>();
-    }
-
-    #[test]
-    fn test_commit() {
+    fn _check_commit<P: Pairing>() {
         let mut prng = ChaChaRng::from_seed([0_u8; 32]);
-        let pcs = KZGCommitmentSchemeBLS::new(10, &mut prng);
-        type Field = BLSScalar;
-        let one = Field::one();
+        let pcs = KZGCommitmentScheme::<P>::new(10, &mut prng);
+        let one = P::ScalarField::one();
         let two = one.add(&one);
         let three = two.add(&one);
         let six = three.add(&three);
@@ -465,23 +971,21 @@ mod tests_kzg_impl {
         let fq_poly = FpPolynomial::from_coefs(vec![two, three, six]);
         let (commitment, open) = pcs.commit(fq_poly).unwrap();
 
-        let coefs_poly_blsscalar = open.get_coefs_ref().iter().collect_vec();
-        let mut expected_committed_value = BLSG1::get_identity();
+        let coefs_poly_scalar = open.get_coefs_ref().iter().collect_vec();
+        let mut expected_committed_value = P::G1::get_identity();
 
         // Doing the multiexp by hand
-        for (i, coef) in coefs_poly_blsscalar.iter().enumerate() {
+        for (i, coef) in coefs_poly_scalar.iter().enumerate() {
             let g_i = pcs.public_parameter_group_1[i].clone();
             expected_committed_value = expected_committed_value.add(&g_i.mul(&coef));
         }
         assert_eq!(expected_committed_value, commitment.value);
     }
 
-    #[test]
-    fn test_eval() {
+    fn _check_eval<P: Pairing>() {
         let mut prng = ChaChaRng::from_seed([0_u8; 32]);
-        let pcs = KZGCommitmentSchemeBLS::new(10, &mut prng);
-        type Field = BLSScalar;
-        let one = Field::one();
+        let pcs = KZGCommitmentScheme::<P>::new(10, &mut prng);
+        let one = P::ScalarField::one();
         let two = one.add(&one);
         let three = two.add(&one);
         let four = three.add(&one);
@@ -532,4 +1036,64 @@ mod tests_kzg_impl {
         );
         assert!(wrong_value_verif.is_err());
     }
+
+    #[test]
+    pub fn test_homomorphic_poly_com_elem() {
+        _check_homomorphic_poly_com_elem::<Bls12381>();
+        _check_homomorphic_poly_com_elem::<Bn254>();
+    }
+
+    #[test]
+    fn test_public_parameters() {
+        _check_public_parameters_generation::<Bls12381>();
+        _check_public_parameters_generation::<Bn254>();
+    }
+
+    #[test]
+    fn test_generation_of_crs() {
+        _generation_of_crs::<Bls12381>();
+        _generation_of_crs::<Bn254>();
+    }
+
+    #[test]
+    fn test_commit() {
+        _check_commit::<Bls12381>();
+        _check_commit::<Bn254>();
+    }
+
+    #[test]
+    fn test_eval() {
+        _check_eval::<Bls12381>();
+        _check_eval::<Bn254>();
+    }
+
+    #[test]
+    fn test_bn254_srs() {
+        let mut prng = ChaChaRng::from_seed([0_u8; 32]);
+        let pcs: KZGCommitmentSchemeBN254 =
+            KZGCommitmentScheme::new_with_g2_powers(10, 3, &mut prng);
+        pnk!(pcs.check_integrity(&mut prng));
+        let bytes = bincode::serialize(&pcs).unwrap();
+        let recovered: KZGCommitmentSchemeBN254 = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(recovered.powers(), pcs.powers());
+        let recovered =
+            pnk!(KZGCommitmentSchemeBN254::from_srs_bytes(&bytes, &mut prng));
+        assert_eq!(recovered.powers(), pcs.powers());
+        assert!(
+            KZGCommitmentSchemeBN254::from_srs_bytes(&bytes[1..], &mut prng).is_err()
+        );
+        // an SRS with a wrong power
+        let (powers_1, powers_2) = pcs.powers();
+        let mut bad_powers_1 = powers_1.to_vec();
+        bad_powers_1[2] = bad_powers_1[3].clone();
+        let bad_pcs = pnk!(KZGCommitmentSchemeBN254::from_powers(
+            bad_powers_1,
+            powers_2.to_vec()
+        ));
+        let bad_bytes = bincode::serialize(&bad_pcs).unwrap();
+        assert!(
+            KZGCommitmentSchemeBN254::from_srs_bytes(&bad_bytes, &mut prng).is_err()
+        );
+        assert_ne!(pcs.digest(), pnk!(pcs.truncate(9)).digest());
+    }
 }
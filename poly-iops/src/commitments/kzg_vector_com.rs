@@ -0,0 +1,435 @@
+use crate::commitments::kzg_poly_com::{
+    KZGCommitment, KZGCommitmentScheme, KZGCommitmentSchemeBLS, KZGEvalProof,
+};
+use crate::commitments::pcs::{PolyComScheme, PolyComSchemeError};
+use crate::polynomials::field_polynomial::{primitive_nth_root_of_unity, FpPolynomial};
+use algebra::bls12_381::{BLSScalar, Bls12381, BLSG1, BLSG2};
+use algebra::groups::{Group, GroupArithmetic, One, Pairing, ScalarArithmetic, Zero};
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+/// Vector commitment scheme built on top of the KZG polynomial commitment scheme.
+/// A vector (v_0, ..., v_{N-1}) is encoded as the unique polynomial P of degree < N such
+/// that P(w^i) = v_i, where w is a primitive N-th root of unity, and committed as C = g1^{P(s)}.
+///
+/// open(i)
+///    The KZG eval proof of P at point w^i, i.e. g1^{Q(s)} for Q(X) = (P(X) - v_i)/(X - w^i).
+///
+/// open_subvector(S)
+///    Let I(X) be the polynomial interpolating {(w^i, v_i)}_{i \in S}, and
+///    Z_S(X) = \prod_{i \in S} (X - w^i). The proof is a single group element g1^{Q(s)} for
+///    Q(X) = (P(X) - I(X))/Z_S(X).
+///
+/// verify_subvector(C, S, {v_i}, proof)
+///    Check that e(C/g1^{I(s)}, g2) == e(proof, g2^{Z_S(s)})
+///
+/// update(i, v_i -> v_i')
+///    C' = C * g1^{(v_i' - v_i) L_i(s)}, where L_i(X) is the i-th Lagrange basis polynomial of
+///    the domain. The commitments to the Lagrange basis are computed once at setup.
+pub struct KZGVectorCommitmentScheme {
+    pcs: KZGCommitmentSchemeBLS,
+    root: BLSScalar,
+    domain: Vec<BLSScalar>,
+    lagrange_bases: Vec<BLSG1>,
+}
+
+pub type KZGVectorCommitment = KZGCommitment<BLSG1>;
+pub type KZGVectorProof = KZGEvalProof<BLSG1>;
+
+/// Prover side information of a vector commitment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KZGVectorOpening {
+    values: Vec<BLSScalar>,
+    polynomial: FpPolynomial<BLSScalar>,
+}
+
+impl KZGVectorOpening {
+    pub fn get_values(&self) -> &[BLSScalar] {
+        &self.values
+    }
+}
+
+const VECTOR_COM_DOMAIN: &[u8] = b"KZG vector commitment";
+
+impl KZGVectorCommitmentScheme {
+    /// Creates a new instance of a KZG vector commitment scheme
+    /// `size` - length of the committed vectors, it must be a power of two
+    /// `prng` - pseudo-random generator
+    pub fn new<R: CryptoRng + RngCore>(
+        size: usize,
+        prng: &mut R,
+    ) -> Result<KZGVectorCommitmentScheme> {
+        if size == 0 || !size.is_power_of_two() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let pcs = KZGCommitmentScheme::new_with_g2_powers(size - 1, size, prng);
+        Self::from_pcs(pcs, size)
+    }
+
+    /// Build a vector commitment scheme from existing KZG parameters.
+    /// Opening subvectors of size k requires at least k + 1 powers in group 2.
+    pub fn from_pcs(
+        pcs: KZGCommitmentSchemeBLS,
+        size: usize,
+    ) -> Result<KZGVectorCommitmentScheme> {
+        if size == 0
+            || !size.is_power_of_two()
+            || pcs.public_parameter_group_1.len() < size
+        {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let root = primitive_nth_root_of_unity::<BLSScalar>(size)
+            .c(d!(ZeiError::ParameterError))?;
+        let mut domain = Vec::with_capacity(size);
+        let mut point = BLSScalar::one();
+        for _ in 0..size {
+            domain.push(point);
+            point.mul_assign(&root);
+        }
+        let mut lagrange_bases = Vec::with_capacity(size);
+        for i in 0..size {
+            let (c, _) = pcs.commit(lagrange_polynomial(&root, size, i)).c(d!())?;
+            lagrange_bases.push(c.value);
+        }
+        Ok(KZGVectorCommitmentScheme {
+            pcs,
+            root,
+            domain,
+            lagrange_bases,
+        })
+    }
+
+    /// Return the length of the committed vectors.
+    pub fn size(&self) -> usize {
+        self.domain.len()
+    }
+
+    /// Commit to `values`. Vectors shorter than `self.size()` are padded with zeroes.
+    pub fn commit(
+        &self,
+        values: &[BLSScalar],
+    ) -> Result<(KZGVectorCommitment, KZGVectorOpening)> {
+        if values.len() > self.size() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let mut values = values.to_vec();
+        values.resize(self.size(), BLSScalar::zero());
+        let polynomial = FpPolynomial::ffti(&self.root, &values);
+        let (commitment, polynomial) = self.pcs.commit(polynomial).c(d!())?;
+        Ok((commitment, KZGVectorOpening { values, polynomial }))
+    }
+
+    /// Open position `index`, returns the value and a proof.
+    pub fn open(
+        &self,
+        opening: &KZGVectorOpening,
+        index: usize,
+    ) -> Result<(BLSScalar, KZGVectorProof)> {
+        if index >= self.size() {
+            return Err(eg!(ZeiError::IndexError));
+        }
+        let mut transcript = Transcript::new(VECTOR_COM_DOMAIN);
+        let (value, proof) = self
+            .pcs
+            .prove_eval(
+                &mut transcript,
+                &opening.polynomial,
+                &self.domain[index],
+                self.size() - 1,
+            )
+            .c(d!())?;
+        Ok((value, proof))
+    }
+
+    /// Open all the positions at once, returns the proofs of the positions in order.
+    /// It takes O(n log n) group operations, see KZGCommitmentScheme::prove_all_evals.
+    pub fn open_all(&self, opening: &KZGVectorOpening) -> Result<Vec<KZGVectorProof>> {
+        let (_, proofs) = self
+            .pcs
+            .prove_all_evals(&opening.polynomial, self.size())
+            .c(d!())?;
+        Ok(proofs)
+    }
+
+    /// Verify that position `index` of the vector committed in `commitment` is `value`.
+    pub fn verify(
+        &self,
+        commitment: &KZGVectorCommitment,
+        index: usize,
+        value: &BLSScalar,
+        proof: &KZGVectorProof,
+    ) -> Result<()> {
+        if index >= self.size() {
+            return Err(eg!(ZeiError::IndexError));
+        }
+        let mut transcript = Transcript::new(VECTOR_COM_DOMAIN);
+        self.pcs
+            .verify_eval(
+                &mut transcript,
+                commitment,
+                self.size() - 1,
+                &self.domain[index],
+                value,
+                proof,
+            )
+            .c(d!())
+    }
+
+    /// Open the positions in `indices` with a single group element.
+    pub fn open_subvector(
+        &self,
+        opening: &KZGVectorOpening,
+        indices: &[usize],
+    ) -> Result<(Vec<BLSScalar>, KZGVectorProof)> {
+        let points = self.subvector_points(indices).c(d!())?;
+        let values: Vec<BLSScalar> =
+            indices.iter().map(|i| opening.values[*i]).collect();
+        let interpolation = FpPolynomial::from_interpolation(&points, &values)
+            .c(d!(ZeiError::ParameterError))?;
+        let vanishing = FpPolynomial::from_zeroes(&points);
+        let (quotient, remainder) =
+            opening.polynomial.sub(&interpolation).div_rem(&vanishing);
+        if !remainder.is_zero() {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        let (c, _) = self.pcs.commit(quotient).c(d!())?;
+        Ok((values, KZGEvalProof(c.value)))
+    }
+
+    /// Verify that the positions `indices` of the vector committed in `commitment` are
+    /// `values`.
+    pub fn verify_subvector(
+        &self,
+        commitment: &KZGVectorCommitment,
+        indices: &[usize],
+        values: &[BLSScalar],
+        proof: &KZGVectorProof,
+    ) -> Result<()> {
+        if indices.len() != values.len() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let points = self.subvector_points(indices).c(d!())?;
+        let g2_powers = &self.pcs.public_parameter_group_2;
+        if points.len() + 1 > g2_powers.len() {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        let interpolation = FpPolynomial::from_interpolation(&points, values)
+            .c(d!(ZeiError::ParameterError))?;
+        let (c_interpolation, _) = self.pcs.commit(interpolation).c(d!())?;
+
+        let vanishing = FpPolynomial::from_zeroes(&points);
+        let vanishing_coefs: Vec<&BLSScalar> =
+            vanishing.get_coefs_ref().iter().collect();
+        let g2_powers_ref: Vec<&BLSG2> =
+            g2_powers[..vanishing_coefs.len()].iter().collect();
+        let vanishing_g2 = BLSG2::vartime_multi_exp(&vanishing_coefs, &g2_powers_ref);
+
+        // e(C/g1^{I(s)}, g2) == e(proof, g2^{Z_S(s)})
+        let left = Bls12381::pairing(
+            &commitment.value.sub(&c_interpolation.value),
+            &g2_powers[0],
+        );
+        let right = Bls12381::pairing(&proof.0, &vanishing_g2);
+        if left == right {
+            Ok(())
+        } else {
+            Err(eg!(PolyComSchemeError::PCSProveEvalError))
+        }
+    }
+
+    /// Update `commitment` after position `index` changes from `old_value` to `new_value`.
+    /// It takes a single scalar multiplication and does not require the vector.
+    pub fn update_commitment(
+        &self,
+        commitment: &KZGVectorCommitment,
+        index: usize,
+        old_value: &BLSScalar,
+        new_value: &BLSScalar,
+    ) -> Result<KZGVectorCommitment> {
+        if index >= self.size() {
+            return Err(eg!(ZeiError::IndexError));
+        }
+        let delta = new_value.sub(old_value);
+        Ok(KZGCommitment {
+            value: commitment
+                .value
+                .add(&self.lagrange_bases[index].mul(&delta)),
+        })
+    }
+
+    /// Update the prover information after position `index` changes to `new_value`.
+    pub fn update_opening(
+        &self,
+        opening: &mut KZGVectorOpening,
+        index: usize,
+        new_value: &BLSScalar,
+    ) -> Result<()> {
+        if index >= self.size() {
+            return Err(eg!(ZeiError::IndexError));
+        }
+        let delta = new_value.sub(&opening.values[index]);
+        let lagrange = lagrange_polynomial(&self.root, self.size(), index);
+        opening.polynomial.add_assign(&lagrange.mul_scalar(&delta));
+        opening.values[index] = *new_value;
+        Ok(())
+    }
+
+    /// Update the proof for position `proof_index` after position `changed_index` changes
+    /// from `old_value` to `new_value`, without access to the vector.
+    /// The proof is multiplied by g1^{(new_value - old_value) * U(s)}, where
+    /// U(X) = (L_c(X) - L_c(w^p))/(X - w^p).
+    pub fn update_proof(
+        &self,
+        proof: &KZGVectorProof,
+        proof_index: usize,
+        changed_index: usize,
+        old_value: &BLSScalar,
+        new_value: &BLSScalar,
+    ) -> Result<KZGVectorProof> {
+        if proof_index >= self.size() || changed_index >= self.size() {
+            return Err(eg!(ZeiError::IndexError));
+        }
+        let mut lagrange = lagrange_polynomial(&self.root, self.size(), changed_index);
+        if proof_index == changed_index {
+            lagrange.sub_assign(&FpPolynomial::one());
+        }
+        let divisor = FpPolynomial::from_coefs(vec![
+            self.domain[proof_index].neg(),
+            BLSScalar::one(),
+        ]);
+        let (quotient, _) = lagrange.div_rem(&divisor);
+        let (c, _) = self.pcs.commit(quotient).c(d!())?;
+        let delta = new_value.sub(old_value);
+        Ok(KZGEvalProof(proof.0.add(&c.value.mul(&delta))))
+    }
+
+    fn subvector_points(&self, indices: &[usize]) -> Result<Vec<BLSScalar>> {
+        if indices.is_empty() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != indices.len() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        if sorted[sorted.len() - 1] >= self.size() {
+            return Err(eg!(ZeiError::IndexError));
+        }
+        Ok(indices.iter().map(|i| self.domain[*i]).collect())
+    }
+}
+
+// The i-th Lagrange basis polynomial of the domain generated by `root`.
+fn lagrange_polynomial(
+    root: &BLSScalar,
+    size: usize,
+    i: usize,
+) -> FpPolynomial<BLSScalar> {
+    let mut values = vec![BLSScalar::zero(); size];
+    values[i] = BLSScalar::one();
+    FpPolynomial::ffti(root, &values)
+}
+
+#[cfg(test)]
+mod tests_kzg_vector_com {
+    use crate::commitments::kzg_vector_com::KZGVectorCommitmentScheme;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{Scalar, ScalarArithmetic};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    #[test]
+    fn test_position_openings() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let vc = KZGVectorCommitmentScheme::new(8, &mut prng).unwrap();
+        let values: Vec<BLSScalar> =
+            (0..7).map(|_| BLSScalar::random(&mut prng)).collect();
+        let (commitment, opening) = vc.commit(&values).unwrap();
+        for (i, v) in values.iter().enumerate() {
+            let (value, proof) = vc.open(&opening, i).unwrap();
+            assert_eq!(&value, v);
+            pnk!(vc.verify(&commitment, i, v, &proof));
+            assert!(vc.verify(&commitment, (i + 1) % 8, v, &proof).is_err());
+        }
+        // padded position
+        let (value, proof) = vc.open(&opening, 7).unwrap();
+        assert_eq!(value, BLSScalar::from_u32(0));
+        pnk!(vc.verify(&commitment, 7, &value, &proof));
+        assert!(vc.open(&opening, 8).is_err());
+        assert!(vc.commit(&[BLSScalar::from_u32(1); 9]).is_err());
+
+        let proofs = vc.open_all(&opening).unwrap();
+        assert_eq!(proofs.len(), 8);
+        for (i, proof) in proofs.iter().enumerate() {
+            assert_eq!(proof, &vc.open(&opening, i).unwrap().1);
+            pnk!(vc.verify(&commitment, i, &opening.get_values()[i], proof));
+        }
+    }
+
+    #[test]
+    fn test_subvector_openings() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let vc = KZGVectorCommitmentScheme::new(8, &mut prng).unwrap();
+        let values: Vec<BLSScalar> =
+            (0..8).map(|_| BLSScalar::random(&mut prng)).collect();
+        let (commitment, opening) = vc.commit(&values).unwrap();
+
+        let indices = [6, 1, 3];
+        let (sub_values, proof) = vc.open_subvector(&opening, &indices).unwrap();
+        assert_eq!(sub_values, vec![values[6], values[1], values[3]]);
+        pnk!(vc.verify_subvector(&commitment, &indices, &sub_values, &proof));
+
+        let mut wrong_values = sub_values.clone();
+        wrong_values[2] = wrong_values[2].add(&BLSScalar::from_u32(1));
+        assert!(vc
+            .verify_subvector(&commitment, &indices, &wrong_values, &proof)
+            .is_err());
+        assert!(vc
+            .verify_subvector(&commitment, &[6, 1, 4], &sub_values, &proof)
+            .is_err());
+        assert!(vc.open_subvector(&opening, &[1, 1]).is_err());
+
+        let all: Vec<usize> = (0..8).collect();
+        let (all_values, proof) = vc.open_subvector(&opening, &all).unwrap();
+        pnk!(vc.verify_subvector(&commitment, &all, &all_values, &proof));
+    }
+
+    #[test]
+    fn test_updates() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let vc = KZGVectorCommitmentScheme::new(4, &mut prng).unwrap();
+        let values: Vec<BLSScalar> =
+            (0..4).map(|_| BLSScalar::random(&mut prng)).collect();
+        let (commitment, mut opening) = vc.commit(&values).unwrap();
+        let (_, proof0) = vc.open(&opening, 0).unwrap();
+        let (_, proof2) = vc.open(&opening, 2).unwrap();
+
+        let new_value = BLSScalar::random(&mut prng);
+        let new_commitment = vc
+            .update_commitment(&commitment, 2, &values[2], &new_value)
+            .unwrap();
+        let mut new_values = values.clone();
+        new_values[2] = new_value;
+        let (expected_commitment, expected_opening) = vc.commit(&new_values).unwrap();
+        assert_eq!(new_commitment, expected_commitment);
+
+        pnk!(vc.update_opening(&mut opening, 2, &new_value));
+        assert_eq!(opening.get_values(), expected_opening.get_values());
+        let (value, proof) = vc.open(&opening, 2).unwrap();
+        assert_eq!(value, new_value);
+        pnk!(vc.verify(&new_commitment, 2, &value, &proof));
+
+        let new_proof0 = vc
+            .update_proof(&proof0, 0, 2, &values[2], &new_value)
+            .unwrap();
+        pnk!(vc.verify(&new_commitment, 0, &values[0], &new_proof0));
+        let new_proof2 = vc
+            .update_proof(&proof2, 2, 2, &values[2], &new_value)
+            .unwrap();
+        pnk!(vc.verify(&new_commitment, 2, &new_value, &new_proof2));
+        assert!(vc.verify(&new_commitment, 2, &new_value, &proof2).is_err());
+    }
+}
@@ -1,4 +1,13 @@
+pub mod fri_poly_com;
+pub mod ipa_poly_com;
+pub mod kzg_ceremony;
+pub mod kzg_fk;
+pub mod kzg_hiding;
+pub mod kzg_lagrange;
+pub mod kzg_lazy_srs;
 pub mod kzg_poly_com;
+pub mod kzg_vector_com;
+pub mod multilinear_kzg;
 pub mod oracle;
 pub mod pcs;
 pub mod transcript;
@@ -0,0 +1,269 @@
+// This file implements the multilinear variant of the KZG polynomial commitment scheme, by
+// Papamanthou, Shi and Tamassia (https://eprint.iacr.org/2011/587.pdf), for the multilinear
+// polynomials of polynomials::multilinear.
+//
+// setup(n: number of variables)
+//    Pick random scalars s = (s_0, ..., s_{n-1}).
+//    For each k = 0, ..., n, compute the Lagrange form of level k
+//       g1^{eq((s_k, ..., s_{n-1}), b)} for b in {0, 1}^{n - k}
+//    and compute g2, g2^{s_0}, ..., g2^{s_{n-1}}.
+//
+// commit(f)
+//    return C = g1^{f(s)} = \prod_b (g1^{eq(s, b)})^{f(b)}
+//
+// prove_eval(f, z = (z_0, ..., z_{n-1}))
+//    As f is linear in x_0,
+//       f(x) - f(z) = (x_0 - z_0) * q_0(x_1, ...) + f(z_0, x_1, ...) - f(z)
+//    where q_0 = f(1, x_1, ...) - f(0, x_1, ...). Repeating on f(z_0, x_1, ...) gives
+//       f(x) - f(z) = \sum_i (x_i - z_i) * q_i(x_{i+1}, ..., x_{n-1})
+//    return the commitments g1^{q_i(s_{i+1}, ...)} with the Lagrange form of level i + 1
+//
+// verify_eval(C, z, y, proofs)
+//    Check that e(C / g1^y, g2) == \prod_i e(proof_i, g2^{s_i - z_i})
+//
+// Proofs have n group elements and the verifier computes n + 1 pairings. Commitments are
+// elements of G1, as the commitments of the univariate scheme.
+
+use crate::commitments::kzg_poly_com::KZGCommitment;
+use crate::commitments::pcs::PolyComSchemeError;
+use crate::polynomials::multilinear::MultilinearPolynomial;
+use algebra::bls12_381::Bls12381;
+use algebra::groups::{Group, GroupArithmetic, Pairing, Scalar};
+use algebra::pairing_batch::PairingCheck;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+/// Evaluation proof of the multilinear KZG scheme: the commitments to the quotients q_i
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MultilinearKZGProof<G1>(pub(crate) Vec<G1>);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultilinearKZGCommitmentScheme<P: Pairing> {
+    num_vars: usize,
+    // the Lagrange forms of levels 0, 1, ..., n, level k having 2^{n - k} elements
+    #[serde(with = "algebra::serialization::batch_points")]
+    lagrange_powers: Vec<P::G1>,
+    // g2, g2^{s_0}, ..., g2^{s_{n-1}}
+    #[serde(with = "algebra::serialization::batch_points")]
+    powers_g2: Vec<P::G2>,
+}
+
+impl<P: Pairing> MultilinearKZGCommitmentScheme<P> {
+    /// Creates a new instance of the scheme for multilinear polynomials in `num_vars`
+    /// variables. The secret scalars are sampled with `prng` and forgotten.
+    pub fn new<R: CryptoRng + RngCore>(
+        num_vars: usize,
+        prng: &mut R,
+    ) -> MultilinearKZGCommitmentScheme<P> {
+        let s: Vec<P::ScalarField> = (0..num_vars)
+            .map(|_| P::ScalarField::random(prng))
+            .collect();
+        let g1 = P::G1::get_base();
+        let mut lagrange_powers = Vec::with_capacity(2 << num_vars);
+        for k in 0..=num_vars {
+            let eq = MultilinearPolynomial::eq(&s[k..]);
+            lagrange_powers.extend(eq.evals().iter().map(|eval| g1.mul(eval)));
+        }
+        let g2 = P::G2::get_base();
+        let mut powers_g2 = vec![g2.clone()];
+        powers_g2.extend(s.iter().map(|s_i| g2.mul(s_i)));
+        MultilinearKZGCommitmentScheme {
+            num_vars,
+            lagrange_powers,
+            powers_g2,
+        }
+    }
+
+    /// Returns the number of variables of the polynomials of the scheme
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    // Lagrange form of level k, for the polynomials in the variables x_k, ..., x_{n-1}
+    fn level(&self, k: usize) -> &[P::G1] {
+        let n = self.num_vars;
+        let offset = (2 << n) - (2 << (n - k));
+        &self.lagrange_powers[offset..offset + (1 << (n - k))]
+    }
+
+    fn commit_level(&self, k: usize, evals: &[P::ScalarField]) -> P::G1 {
+        let evals_ref: Vec<&P::ScalarField> = evals.iter().collect();
+        let powers_ref: Vec<&P::G1> = self.level(k).iter().collect();
+        P::G1::vartime_multi_exp(&evals_ref, &powers_ref)
+    }
+
+    /// Commits to `polynomial`, which must have as many variables as the scheme
+    pub fn commit(
+        &self,
+        polynomial: &MultilinearPolynomial<P::ScalarField>,
+    ) -> Result<KZGCommitment<P::G1>> {
+        if polynomial.num_vars() != self.num_vars {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(KZGCommitment {
+            value: self.commit_level(0, polynomial.evals()),
+        })
+    }
+
+    /// Evaluates `polynomial` at `point`, returning the evaluation and its proof
+    pub fn prove_eval(
+        &self,
+        polynomial: &MultilinearPolynomial<P::ScalarField>,
+        point: &[P::ScalarField],
+    ) -> Result<(P::ScalarField, MultilinearKZGProof<P::G1>)> {
+        if polynomial.num_vars() != self.num_vars || point.len() != self.num_vars {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let mut poly = polynomial.clone();
+        let mut proof = Vec::with_capacity(self.num_vars);
+        for (i, z_i) in point.iter().enumerate() {
+            // q_i = f(1, x_{i+1}, ...) - f(0, x_{i+1}, ...)
+            let q_i: Vec<P::ScalarField> = poly
+                .evals()
+                .chunks(2)
+                .map(|pair| pair[1].sub(&pair[0]))
+                .collect();
+            proof.push(self.commit_level(i + 1, &q_i));
+            poly = poly.fix_first_variable(z_i);
+        }
+        Ok((poly.evals()[0], MultilinearKZGProof(proof)))
+    }
+
+    /// Returns the pairing check verifying that the polynomial in `commitment` evaluates to
+    /// `value` at `point`, to be batched with the pairing checks of other proofs
+    pub fn eval_pairing_check(
+        &self,
+        commitment: &KZGCommitment<P::G1>,
+        point: &[P::ScalarField],
+        value: &P::ScalarField,
+        proof: &MultilinearKZGProof<P::G1>,
+    ) -> Result<PairingCheck<P>> {
+        if point.len() != self.num_vars || proof.0.len() != self.num_vars {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        let g1_y = P::G1::get_base().mul(value);
+        let g2 = &self.powers_g2[0];
+        let lhs = vec![(commitment.value.sub(&g1_y), g2.clone())];
+        let rhs = proof
+            .0
+            .iter()
+            .zip(point.iter().zip(self.powers_g2[1..].iter()))
+            .map(|(proof_i, (z_i, g2_s_i))| (proof_i.clone(), g2_s_i.sub(&g2.mul(z_i))))
+            .collect();
+        Ok(PairingCheck::new(lhs, rhs))
+    }
+
+    /// Verifies that the polynomial in `commitment` evaluates to `value` at `point`
+    pub fn verify_eval(
+        &self,
+        commitment: &KZGCommitment<P::G1>,
+        point: &[P::ScalarField],
+        value: &P::ScalarField,
+        proof: &MultilinearKZGProof<P::G1>,
+    ) -> Result<()> {
+        self.eval_pairing_check(commitment, point, value, proof)
+            .c(d!())?
+            .verify()
+            .c(d!(PolyComSchemeError::PCSProveEvalError))
+    }
+}
+
+pub type MultilinearKZGCommitmentSchemeBLS = MultilinearKZGCommitmentScheme<Bls12381>;
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::multilinear_kzg::{
+        MultilinearKZGCommitmentScheme, MultilinearKZGCommitmentSchemeBLS,
+    };
+    use crate::polynomials::multilinear::MultilinearPolynomial;
+    use crate::sumcheck::{prove_sumcheck, verify_sumcheck};
+    use algebra::bls12_381::BLSScalar;
+    use algebra::bn254::Bn254;
+    use algebra::groups::{One, Pairing, Scalar, ScalarArithmetic, Zero};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn random_poly<F: Scalar>(
+        prng: &mut ChaChaRng,
+        num_vars: usize,
+    ) -> MultilinearPolynomial<F> {
+        let evals = (0..1 << num_vars).map(|_| F::random(prng)).collect();
+        pnk!(MultilinearPolynomial::from_evals(evals))
+    }
+
+    fn check_eval<P: Pairing>(pcs: &MultilinearKZGCommitmentScheme<P>) {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let n = pcs.num_vars();
+        let poly = random_poly::<P::ScalarField>(&mut prng, n);
+        let com = pnk!(pcs.commit(&poly));
+        let point: Vec<P::ScalarField> =
+            (0..n).map(|_| P::ScalarField::random(&mut prng)).collect();
+        let (value, proof) = pnk!(pcs.prove_eval(&poly, &point));
+        assert_eq!(value, pnk!(poly.evaluate(&point)));
+        assert!(pcs.verify_eval(&com, &point, &value, &proof).is_ok());
+        let wrong_value = value.add(&P::ScalarField::one());
+        assert!(pcs.verify_eval(&com, &point, &wrong_value, &proof).is_err());
+        let mut wrong_point = point.clone();
+        wrong_point[0] = wrong_point[0].add(&P::ScalarField::one());
+        assert!(pcs.verify_eval(&com, &wrong_point, &value, &proof).is_err());
+        assert!(pcs.verify_eval(&com, &point[1..], &value, &proof).is_err());
+        assert!(pcs.commit(&random_poly(&mut prng, n + 1)).is_err());
+    }
+
+    #[test]
+    fn test_multilinear_kzg() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        for num_vars in [0, 1, 5] {
+            let pcs: MultilinearKZGCommitmentSchemeBLS =
+                MultilinearKZGCommitmentScheme::new(num_vars, &mut prng);
+            check_eval(&pcs);
+        }
+        check_eval(&MultilinearKZGCommitmentScheme::<Bn254>::new(4, &mut prng));
+
+        // serialization
+        let pcs = MultilinearKZGCommitmentSchemeBLS::new(3, &mut prng);
+        let bytes = bincode::serialize(&pcs).unwrap();
+        let recovered: MultilinearKZGCommitmentSchemeBLS =
+            bincode::deserialize(&bytes).unwrap();
+        let poly = random_poly(&mut prng, 3);
+        assert_eq!(pnk!(pcs.commit(&poly)), pnk!(recovered.commit(&poly)));
+    }
+
+    #[test]
+    fn test_sumcheck_with_multilinear_kzg() {
+        // proves \sum_b f(b) * g(b) = claim for committed f and g
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs = MultilinearKZGCommitmentSchemeBLS::new(4, &mut prng);
+        let f = random_poly::<BLSScalar>(&mut prng, 4);
+        let g = random_poly::<BLSScalar>(&mut prng, 4);
+        let (com_f, com_g) = (pnk!(pcs.commit(&f)), pnk!(pcs.commit(&g)));
+        let mut claim = BLSScalar::zero();
+        for (f_b, g_b) in f.evals().iter().zip(g.evals()) {
+            claim.add_assign(&f_b.mul(g_b));
+        }
+
+        let (proof, point, evals) = {
+            let mut transcript = Transcript::new(b"TestSumcheck");
+            pnk!(prove_sumcheck(
+                &mut transcript,
+                &[f.clone(), g.clone()],
+                &claim
+            ))
+        };
+        let (_, proof_f) = pnk!(pcs.prove_eval(&f, &point));
+        let (_, proof_g) = pnk!(pcs.prove_eval(&g, &point));
+
+        let mut transcript = Transcript::new(b"TestSumcheck");
+        let (point, expected) =
+            pnk!(verify_sumcheck(&mut transcript, 4, 2, &claim, &proof));
+        assert_eq!(evals[0].mul(&evals[1]), expected);
+        assert!(pcs.verify_eval(&com_f, &point, &evals[0], &proof_f).is_ok());
+        assert!(pcs.verify_eval(&com_g, &point, &evals[1], &proof_g).is_ok());
+        assert!(pcs
+            .verify_eval(&com_g, &point, &evals[0], &proof_g)
+            .is_err());
+    }
+}
@@ -1,5 +1,6 @@
+use crate::backend::ArithmeticBackend;
 use crate::commitments::transcript::PolyComTranscript;
-use merlin::Transcript;
+use crate::plonk::transcript::PlonkTranscript;
 use std::fmt::Debug;
 
 use crate::polynomials::field_polynomial::FpPolynomial;
@@ -45,8 +46,8 @@ pub trait HomomorphicPolyComElem: ToBytes {
 
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize, Clone)]
 pub struct BatchProofEval<C, E> {
-    commitment: C,
-    eval_proof: E,
+    pub(crate) commitment: C,
+    pub(crate) eval_proof: E,
 }
 
 pub struct BatchEvalParams<'a, C, F> {
@@ -58,6 +59,25 @@ pub type BatchPfEval<PCS> = BatchProofEval<
     <PCS as PolyComScheme>::Commitment,
     <PCS as PolyComScheme>::EvalProof,
 >;
+
+/// Commitment to a polynomial f(X) of degree at most a bound d, together with the commitment
+/// to the shifted polynomial X^{D - d} * f(X), where D is the max degree of the scheme.
+/// The shifted polynomial has degree greater than D if f(X) has degree greater than d, in
+/// which case it cannot be committed to.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize, Clone)]
+pub struct DegreeBoundCommitment<C> {
+    pub(crate) commitment: C,
+    pub(crate) shifted_commitment: C,
+}
+
+impl<C> DegreeBoundCommitment<C> {
+    /// Returns the commitment to the polynomial itself
+    pub fn commitment(&self) -> &C {
+        &self.commitment
+    }
+}
+
+pub type DegreeBoundCom<PCS> = DegreeBoundCommitment<<PCS as PolyComScheme>::Commitment>;
 pub type OptionParams<'a, PCS> = Option<
     BatchEvalParams<
         'a,
@@ -86,7 +106,8 @@ pub trait PolyComScheme {
         + for<'de> Deserialize<'de>
         + Debug
         + PartialEq
-        + Eq;
+        + Eq
+        + Clone;
 
     /// Type of Opening
     type Opening: HomomorphicPolyComElem<Scalar = Self::Field>
@@ -101,6 +122,16 @@ pub trait PolyComScheme {
         polynomial: FpPolynomial<Self::Field>,
     ) -> Result<(Self::Commitment, Self::Opening)>;
 
+    /// Same as commit, with the multi-scalar multiplications of `backend` for the schemes that
+    /// compute their commitments with them (see backend). The other schemes ignore `backend`.
+    fn commit_with_backend<B: ArithmeticBackend>(
+        &self,
+        _backend: &B,
+        polynomial: FpPolynomial<Self::Field>,
+    ) -> Result<(Self::Commitment, Self::Opening)> {
+        self.commit(polynomial)
+    }
+
     /// Returns the opening of an original commitment of the polynomial
     fn opening(&self, polynomial: &FpPolynomial<Self::Field>) -> Self::Opening;
 
@@ -123,18 +154,18 @@ pub trait PolyComScheme {
     ) -> FpPolynomial<Self::Field>;
 
     /// Evaluate the polynomial producing a proof for it.
-    fn prove_eval(
+    fn prove_eval<T: PlonkTranscript>(
         &self,
-        transcript: &mut Transcript,
+        transcript: &mut T,
         opening: &Self::Opening,
         point: &Self::Field,
         max_degree: usize,
     ) -> Result<(Self::Field, Self::EvalProof)>;
 
     /// Verify an evaluation proof that polynomial inside commitment evaluates to `value` on input `point `
-    fn verify_eval(
+    fn verify_eval<T: PlonkTranscript>(
         &self,
-        transcript: &mut Transcript,
+        transcript: &mut T,
         commitment: &Self::Commitment,
         degree: usize,
         point: &Self::Field,
@@ -142,13 +173,146 @@ pub trait PolyComScheme {
         proof: &Self::EvalProof,
     ) -> Result<()>;
 
+    /// Returns a digest of the public parameters of the scheme, which identifies them
+    fn srs_digest(&self) -> Vec<u8>;
+
+    /// Returns the max degree of the polynomials the scheme can commit to
+    fn max_degree(&self) -> usize;
+
+    /// Proves with a single evaluation proof the evaluations of the polynomials `openings[i]`,
+    /// committed in `commitments[i]`, on every point of `points[i]`. Different polynomials
+    /// can be evaluated on different points. Returns the evaluations, `values[i][j]` being the
+    /// evaluation of the i-th polynomial on `points[i][j]`, and the proof.
+    fn multi_point_prove_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        openings: &[&Self::Opening],
+        points: &[&[Self::Field]],
+        max_degree: usize,
+    ) -> Result<(Vec<Vec<Self::Field>>, Self::EvalProof)>;
+
+    /// Verifies a proof of multi_point_prove_eval that the polynomial in `commitments[i]`
+    /// evaluates to `values[i][j]` on `points[i][j]` for all i and j
+    #[allow(clippy::too_many_arguments)]
+    fn multi_point_verify_eval<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        max_degree: usize,
+        points: &[&[Self::Field]],
+        values: &[Vec<Self::Field>],
+        proof: &Self::EvalProof,
+    ) -> Result<()>;
+
+    /// Commits to `polynomial`, whose degree must be at most `degree_bound`, so that the bound
+    /// can be proven with prove_degree_bound. Returns the commitment, and the openings of the
+    /// polynomial and of the shifted polynomial.
+    fn commit_with_degree_bound(
+        &self,
+        polynomial: FpPolynomial<Self::Field>,
+        degree_bound: usize,
+    ) -> Result<(DegreeBoundCom<Self>, Self::Opening, Self::Opening)> {
+        if degree_bound > self.max_degree() || polynomial.degree() > degree_bound {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        let shifted = polynomial.shift(self.max_degree() - degree_bound);
+        let (commitment, opening) = self.commit(polynomial).c(d!())?;
+        let (shifted_commitment, shifted_opening) = self.commit(shifted).c(d!())?;
+        Ok((
+            DegreeBoundCommitment {
+                commitment,
+                shifted_commitment,
+            },
+            opening,
+            shifted_opening,
+        ))
+    }
+
+    /// Proves that the polynomial committed in `commitment` has degree at most `degree_bound`,
+    /// that is the shifted commitment is a commitment to X^{D - d} * f(X): the evaluation proof
+    /// that X^{D - d} * f(X) - shifted(X) vanishes on a random challenge z.
+    fn prove_degree_bound<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &DegreeBoundCom<Self>,
+        opening: &Self::Opening,
+        shifted_opening: &Self::Opening,
+        degree_bound: usize,
+    ) -> Result<Self::EvalProof> {
+        let (z, shift) = Self::degree_bound_challenge(
+            transcript,
+            commitment,
+            self.max_degree(),
+            degree_bound,
+        )
+        .c(d!())?;
+        let combined_opening = shifted_opening.op(&opening.exp(&shift).inv());
+        let (value, proof) = self
+            .prove_eval(transcript, &combined_opening, &z, self.max_degree())
+            .c(d!())?;
+        if !value.is_zero() {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        Ok(proof)
+    }
+
+    /// Verifies that the polynomial committed in `commitment` has degree at most `degree_bound`
+    fn verify_degree_bound<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitment: &DegreeBoundCom<Self>,
+        degree_bound: usize,
+        proof: &Self::EvalProof,
+    ) -> Result<()> {
+        let (z, shift) = Self::degree_bound_challenge(
+            transcript,
+            commitment,
+            self.max_degree(),
+            degree_bound,
+        )
+        .c(d!())?;
+        let combined_commitment = commitment
+            .shifted_commitment
+            .op(&commitment.commitment.exp(&shift).inv());
+        self.verify_eval(
+            transcript,
+            &combined_commitment,
+            self.max_degree(),
+            &z,
+            &Self::Field::zero(),
+            proof,
+        )
+        .c(d!(PolyComSchemeError::DegreeError))
+    }
+
+    /// Returns the challenge z of a degree bound proof and z^{D - d}
+    fn degree_bound_challenge<T: PlonkTranscript>(
+        transcript: &mut T,
+        commitment: &DegreeBoundCom<Self>,
+        max_degree: usize,
+        degree_bound: usize,
+    ) -> Result<(Self::Field, Self::Field)> {
+        if degree_bound > max_degree {
+            return Err(eg!(PolyComSchemeError::DegreeError));
+        }
+        transcript.append_message(b"Domain Separator", b"New PCS-Degree-Bound Protocol");
+        transcript.append_u64(b"max_degree", max_degree as u64);
+        transcript.append_u64(b"degree_bound", degree_bound as u64);
+        transcript.append_commitment::<Self::Commitment>(&commitment.commitment);
+        transcript.append_commitment::<Self::Commitment>(&commitment.shifted_commitment);
+        let z = transcript.get_challenge_field_elem::<Self::Field>(b"z");
+        let shift = z.pow_u64((max_degree - degree_bound) as u64);
+        Ok((z, shift))
+    }
+
     /// Batch proof for polynomial evaluation.
     /// `param` stores the instance parameters to be appended to the transcript.
     /// When `param` is `None`, our function assumes `params` are implicit in the transcript already.
     #[allow(non_snake_case)]
-    fn batch_prove_eval(
+    fn batch_prove_eval<T: PlonkTranscript>(
         &self,
-        transcript: &mut Transcript,
+        transcript: &mut T,
         openings: &[&Self::Opening],
         points: &[Self::Field],
         max_degree: usize,
@@ -239,11 +403,10 @@ pub trait PolyComScheme {
     /// Verify batch eval proof
     /// Optimized according to Sec 4.1 in <https://eprint.iacr.org/2020/081.pdf>
     /// Saves |points| G1 exps
-    #[allow(non_snake_case)]
     #[allow(clippy::too_many_arguments)]
-    fn batch_verify_eval(
+    fn batch_verify_eval<T: PlonkTranscript>(
         &self,
-        transcript: &mut Transcript,
+        transcript: &mut T,
         commitments: &[&Self::Commitment],
         max_degree: usize,
         points: &[Self::Field],
@@ -251,6 +414,43 @@ pub trait PolyComScheme {
         proof: &BatchPfEval<Self>,
         params: OptionParams<Self>,
     ) -> Result<()> {
+        let (_, rho, derived_commitment) = self
+            .batch_eval_reduce(
+                transcript,
+                commitments,
+                max_degree,
+                points,
+                values,
+                proof,
+                params,
+            )
+            .c(d!())?;
+        self.verify_eval(
+            transcript,
+            &derived_commitment,
+            max_degree,
+            &rho,
+            &Self::Field::zero(),
+            &proof.eval_proof,
+        )
+        .c(d!())
+    }
+
+    /// Reduces a batch eval proof to a single evaluation proof that the polynomial in the
+    /// derived commitment evaluates to zero on the challenge rho.
+    /// Returns the challenges alpha and rho, and the derived commitment.
+    #[allow(non_snake_case)]
+    #[allow(clippy::too_many_arguments)]
+    fn batch_eval_reduce<T: PlonkTranscript>(
+        &self,
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        max_degree: usize,
+        points: &[Self::Field],
+        values: &[Self::Field],
+        proof: &BatchPfEval<Self>,
+        params: OptionParams<Self>,
+    ) -> Result<(Self::Field, Self::Field, Self::Commitment)> {
         Self::init_pcs_batch_eval_transcript(transcript, max_degree, points, params);
         let alpha = transcript.get_challenge_field_elem::<Self::Field>(b"alpha");
         transcript.append_commitment::<Self::Commitment>(&proof.commitment);
@@ -289,19 +489,11 @@ pub trait PolyComScheme {
         // - Com(q(X) * z(\rho))
         let com_z_q = proof.commitment.exp(&z_eval_rho);
         let derived_commitment = com_lc.op(&com_z_q.inv());
-        self.verify_eval(
-            transcript,
-            &derived_commitment,
-            max_degree,
-            &rho,
-            &Self::Field::zero(),
-            &proof.eval_proof,
-        )
-        .c(d!())
+        Ok((alpha, rho, derived_commitment))
     }
 
-    fn init_pcs_batch_eval_transcript(
-        transcript: &mut Transcript,
+    fn init_pcs_batch_eval_transcript<T: PlonkTranscript>(
+        transcript: &mut T,
         max_degree: usize,
         points: &[Self::Field],
         params: Option<BatchEvalParams<Self::Commitment, Self::Field>>,
@@ -310,8 +502,96 @@ pub trait PolyComScheme {
         Self::transcript_append_params(transcript, max_degree, points, params);
     }
 
-    fn transcript_append_params(
-        transcript: &mut Transcript,
+    /// Checks the statement of a multi-point evaluation proof, appends it to the transcript
+    /// and returns the challenge gamma combining the polynomials
+    fn multi_point_eval_challenge<T: PlonkTranscript>(
+        transcript: &mut T,
+        commitments: &[&Self::Commitment],
+        max_degree: usize,
+        points: &[&[Self::Field]],
+        values: &[Vec<Self::Field>],
+    ) -> Result<Self::Field> {
+        let n = commitments.len();
+        if n == 0 || points.len() != n || values.len() != n {
+            return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+        }
+        for (points_i, values_i) in points.iter().zip(values) {
+            // evaluations of a polynomial on distinct points
+            if points_i.is_empty()
+                || points_i.len() != values_i.len()
+                || points_i
+                    .iter()
+                    .enumerate()
+                    .any(|(j, point)| points_i[..j].contains(point))
+            {
+                return Err(eg!(PolyComSchemeError::PCSProveEvalError));
+            }
+        }
+
+        transcript
+            .append_message(b"Domain Separator", b"New PCS-Multi-Point-Eval Protocol");
+        transcript
+            .append_message(b"field size", &Self::Field::get_field_size_lsf_bytes());
+        transcript.append_u64(b"max_degree", max_degree as u64);
+        for ((commitment, points_i), values_i) in
+            commitments.iter().zip(points).zip(values)
+        {
+            transcript.append_commitment::<Self::Commitment>(commitment);
+            transcript.append_u64(b"number of points", points_i.len() as u64);
+            for (point, value) in points_i.iter().zip(values_i) {
+                transcript.append_field_elem(point);
+                transcript.append_field_elem(value);
+            }
+        }
+        Ok(transcript.get_challenge_field_elem(b"gamma"))
+    }
+
+    /// Reduces a multi-point evaluation proof to the evaluation proof that
+    /// g = sum_i gamma^i * Z_{T - S_i}(z) * (f_i - r_i(z)) - Z_T(z) * h vanishes on a challenge z,
+    /// where T is the union of the points, Z_S the vanishing polynomial of S, r_i the
+    /// polynomial interpolating the values of f_i on its points S_i, and `quotient` the
+    /// commitment to h = sum_i gamma^i * (f_i - r_i) / Z_{S_i}. Appends the quotient to the
+    /// transcript, and returns z, the scalars gamma^i * Z_{T - S_i}(z), Z_T(z) and
+    /// sum_i gamma^i * Z_{T - S_i}(z) * r_i(z).
+    fn multi_point_reduce<T: PlonkTranscript>(
+        transcript: &mut T,
+        points: &[&[Self::Field]],
+        values: &[Vec<Self::Field>],
+        gamma: &Self::Field,
+        quotient: &Self::Commitment,
+    ) -> Result<(Self::Field, Vec<Self::Field>, Self::Field, Self::Field)> {
+        let mut all_points: Vec<Self::Field> = vec![];
+        for point in points.iter().flat_map(|points_i| points_i.iter()) {
+            if !all_points.contains(point) {
+                all_points.push(*point);
+            }
+        }
+        transcript.append_commitment::<Self::Commitment>(quotient);
+        let z = transcript.get_challenge_field_elem::<Self::Field>(b"z");
+
+        let mut scalars = vec![];
+        let mut r_eval = Self::Field::zero();
+        let mut gamma_i = Self::Field::one();
+        for (points_i, values_i) in points.iter().zip(values) {
+            let mut scalar = gamma_i;
+            for point in all_points.iter().filter(|point| !points_i.contains(*point)) {
+                scalar.mul_assign(&z.sub(point));
+            }
+            let r_i = FpPolynomial::from_interpolation(points_i, values_i)
+                .c(d!(PolyComSchemeError::PCSProveEvalError))?;
+            r_eval.add_assign(&scalar.mul(&r_i.eval(&z)));
+            scalars.push(scalar);
+            gamma_i.mul_assign(gamma);
+        }
+        let mut z_t_eval = Self::Field::one();
+        for point in all_points.iter() {
+            z_t_eval.mul_assign(&z.sub(point));
+        }
+        Ok((z, scalars, z_t_eval, r_eval))
+    }
+
+    fn transcript_append_params<T: PlonkTranscript>(
+        transcript: &mut T,
         max_degree: usize,
         points: &[Self::Field],
         params: Option<BatchEvalParams<Self::Commitment, Self::Field>>,
@@ -478,6 +758,100 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_pcs_multi_point_eval() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        type Field = BLSScalar;
+        let degree = 10;
+        let pcs = KZGCommitmentScheme::new_with_g2_powers(degree, 3, &mut prng);
+        let polys: Vec<FpPolynomial<Field>> = (0..3)
+            .map(|_| {
+                FpPolynomial::from_coefs(
+                    (0..degree + 1).map(|_| Field::random(&mut prng)).collect(),
+                )
+            })
+            .collect();
+        let (coms, opens): (Vec<_>, Vec<_>) = polys
+            .into_iter()
+            .map(|poly| pcs.commit(poly).unwrap())
+            .unzip();
+        let a = Field::random(&mut prng);
+        let b = Field::random(&mut prng);
+        let c = Field::random(&mut prng);
+        let points: [&[Field]; 3] = [&[a, b], &[a, b], &[c]];
+        let coms_ref: Vec<_> = coms.iter().collect();
+        let opens_ref: Vec<_> = opens.iter().collect();
+
+        let (values, proof) = {
+            let mut transcript = Transcript::new(b"TestPCS");
+            pcs.multi_point_prove_eval(
+                &mut transcript,
+                &coms_ref,
+                &opens_ref,
+                &points,
+                degree,
+            )
+            .unwrap()
+        };
+        assert_eq!(values[0], vec![opens[0].eval(&a), opens[0].eval(&b)]);
+        assert_eq!(values[2], vec![opens[2].eval(&c)]);
+        let verify = |values: &[Vec<Field>]| {
+            let mut transcript = Transcript::new(b"TestPCS");
+            pcs.multi_point_verify_eval(
+                &mut transcript,
+                &coms_ref,
+                degree,
+                &points,
+                values,
+                &proof,
+            )
+        };
+        assert!(verify(&values).is_ok());
+        let mut wrong_values = values.clone();
+        wrong_values[1][1].add_assign(&Field::one());
+        assert!(verify(&wrong_values).is_err());
+        assert!(verify(&values[..2]).is_err());
+
+        // the scheme needs as many powers of s in group 2 as distinct points
+        let small_pcs = KZGCommitmentScheme::new(degree, &mut prng);
+        let (values, proof) = {
+            let mut transcript = Transcript::new(b"TestPCS");
+            small_pcs
+                .multi_point_prove_eval(
+                    &mut transcript,
+                    &coms_ref,
+                    &opens_ref,
+                    &points,
+                    degree,
+                )
+                .unwrap()
+        };
+        let mut transcript = Transcript::new(b"TestPCS");
+        assert!(small_pcs
+            .multi_point_verify_eval(
+                &mut transcript,
+                &coms_ref,
+                degree,
+                &points,
+                &values,
+                &proof
+            )
+            .is_err());
+
+        // the points of a polynomial must be distinct
+        let mut transcript = Transcript::new(b"TestPCS");
+        let points: [&[Field]; 3] = [&[a, a], &[a, b], &[c]];
+        assert!(pcs
+            .multi_point_prove_eval(
+                &mut transcript,
+                &coms_ref,
+                &opens_ref,
+                &points,
+                degree
+            )
+            .is_err());
+    }
+
     #[test]
     fn test_pcs_batch_eval_simulate_plonk() {
         let mut prng = ChaChaRng::from_seed([0u8; 32]);
@@ -16,27 +16,27 @@ use crate::commitments::pcs::{
     HidingPCS, HomomorphicPolyComElem, PolyComScheme, ShiftPCS,
 };
 use crate::commitments::transcript::PolyComTranscript;
+use crate::plonk::transcript::PlonkTranscript;
 use crate::polynomials::field_polynomial::FpPolynomial;
 use algebra::groups::{Scalar, ScalarArithmetic, Zero};
-use merlin::Transcript;
 use rand_core::{CryptoRng, RngCore};
 use ruc::*;
 
 const ZK_EVAL_CHALLENGE: &[u8] = b"zk_eval challenge";
 
-fn init_zk_eval_transcript<PCS: PolyComScheme>(
-    transcript: &mut Transcript,
+fn init_zk_eval_transcript<T: PlonkTranscript, PCS: PolyComScheme>(
+    transcript: &mut T,
     degree: usize,
     commitment: &PCS::Commitment,
     point: &PCS::Field,
     eval: &PCS::Field,
 ) {
     transcript.append_message(b"Domain Separator", b"New ZK-Eval Protocol");
-    transcript_append_params::<PCS>(transcript, degree, commitment, point, eval);
+    transcript_append_params::<_, PCS>(transcript, degree, commitment, point, eval);
 }
 
-fn init_non_hiding_poly_zk_eval_transcript<PCS: PolyComScheme>(
-    transcript: &mut Transcript,
+fn init_non_hiding_poly_zk_eval_transcript<T: PlonkTranscript, PCS: PolyComScheme>(
+    transcript: &mut T,
     degree: usize,
     commitment: &PCS::Commitment,
     point: &PCS::Field,
@@ -44,11 +44,11 @@ fn init_non_hiding_poly_zk_eval_transcript<PCS: PolyComScheme>(
 ) {
     transcript
         .append_message(b"Domain Separator", b"New Non-Hiding Poly ZK-Eval Protocol");
-    transcript_append_params::<PCS>(transcript, degree, commitment, point, eval);
+    transcript_append_params::<_, PCS>(transcript, degree, commitment, point, eval);
 }
 
-fn transcript_append_params<PCS: PolyComScheme>(
-    transcript: &mut Transcript,
+fn transcript_append_params<T: PlonkTranscript, PCS: PolyComScheme>(
+    transcript: &mut T,
     degree: usize,
     commitment: &PCS::Commitment,
     point: &PCS::Field,
@@ -77,16 +77,16 @@ pub type ZKEvalPf<PCS> = ZKEvalProof<
     <PCS as PolyComScheme>::Field,
 >;
 #[allow(non_snake_case)]
-pub fn prove_zk_eval<R: CryptoRng + RngCore, PCS: PolyComScheme>(
+pub fn prove_zk_eval<T: PlonkTranscript, R: CryptoRng + RngCore, PCS: PolyComScheme>(
     prng: &mut R,
-    transcript: &mut Transcript,
+    transcript: &mut T,
     hpcs: &HidingPCS<PCS>,
     polynomial: &FpPolynomial<PCS::Field>,
     blind: &PCS::Field,
     point: &PCS::Field,
 ) -> Result<ZKEvalPf<PCS>> {
     let degree = polynomial.degree();
-    init_zk_eval_transcript::<PCS>(
+    init_zk_eval_transcript::<_, PCS>(
         transcript,
         degree,
         &hpcs.commit(polynomial, blind),
@@ -134,8 +134,8 @@ pub fn prove_zk_eval<R: CryptoRng + RngCore, PCS: PolyComScheme>(
 
 #[allow(non_snake_case)]
 #[allow(clippy::too_many_arguments)]
-pub fn verify_zk_eval<PCS: PolyComScheme>(
-    transcript: &mut Transcript,
+pub fn verify_zk_eval<T: PlonkTranscript, PCS: PolyComScheme>(
+    transcript: &mut T,
     hpcs: &HidingPCS<PCS>,
     degree: usize,
     commitment: &PCS::Commitment,
@@ -143,7 +143,7 @@ pub fn verify_zk_eval<PCS: PolyComScheme>(
     eval_value: &PCS::Field,
     proof: &ZKEvalPf<PCS>,
 ) -> Result<()> {
-    init_zk_eval_transcript::<PCS>(transcript, degree, commitment, point, eval_value);
+    init_zk_eval_transcript::<_, PCS>(transcript, degree, commitment, point, eval_value);
 
     // 1. first message, append to transcript
     let C_alpha = &proof.C_alpha;
@@ -192,14 +192,18 @@ pub fn verify_zk_eval<PCS: PolyComScheme>(
 }
 
 #[allow(non_snake_case)]
-pub fn prove_non_hiding_poly_zk_eval<R: CryptoRng + RngCore, PCS: PolyComScheme>(
+pub fn prove_non_hiding_poly_zk_eval<
+    T: PlonkTranscript,
+    R: CryptoRng + RngCore,
+    PCS: PolyComScheme,
+>(
     prng: &mut R,
-    transcript: &mut Transcript,
+    transcript: &mut T,
     pcs: &PCS,
     polynomial: &FpPolynomial<PCS::Field>,
     point: &PCS::Field,
 ) -> Result<ZKEvalPf<PCS>> {
-    init_non_hiding_poly_zk_eval_transcript::<PCS>(
+    init_non_hiding_poly_zk_eval_transcript::<_, PCS>(
         transcript,
         polynomial.degree(),
         &pcs.commit(polynomial.clone()).unwrap().0, // FIXME
@@ -222,8 +226,8 @@ pub fn prove_non_hiding_poly_zk_eval<R: CryptoRng + RngCore, PCS: PolyComScheme>
 
 #[allow(non_snake_case)]
 #[allow(clippy::too_many_arguments)]
-pub fn verify_non_hiding_poly_zk_eval<SPCS: ShiftPCS>(
-    transcript: &mut Transcript,
+pub fn verify_non_hiding_poly_zk_eval<T: PlonkTranscript, SPCS: ShiftPCS>(
+    transcript: &mut T,
     pcs: &SPCS,
     degree: usize,
     commitment: &SPCS::Commitment,
@@ -231,7 +235,7 @@ pub fn verify_non_hiding_poly_zk_eval<SPCS: ShiftPCS>(
     eval_value: &SPCS::Field,
     proof: &ZKEvalPf<SPCS>,
 ) -> Result<()> {
-    init_non_hiding_poly_zk_eval_transcript::<SPCS>(
+    init_non_hiding_poly_zk_eval_transcript::<_, SPCS>(
         transcript, degree, commitment, point, eval_value,
     );
     let hpcs = HidingPCS::new(pcs);
@@ -5,7 +5,9 @@
 #[macro_use]
 extern crate serde_derive;
 
+pub mod backend;
 pub mod commitments;
 pub mod ioputils;
 pub mod plonk;
 pub mod polynomials;
+pub mod sumcheck;
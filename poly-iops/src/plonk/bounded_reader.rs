@@ -0,0 +1,277 @@
+// This file implements a PLONK verifier that reads a proof, in the bincode encoding of PlonkProof,
+// from an untrusted reader while bounding the size of the input.
+// The proof is decoded one element at a time: the length of every vector is checked against the
+// number of wires of the constraint system before anything is allocated for it, and decoding an
+// element never reads more than MAX_PROOF_ELEMENT_SIZE bytes. Hence a malformed or oversized
+// input is rejected after reading at most the bytes of a proof of the expected shape, and cannot
+// make the decoder allocate more than such a proof.
+// This only bounds the input: the decoded proof is then checked by plonk::prover::verifier, which
+// allocates its intermediate vectors (challenges, evaluations, the batch of the evaluation proof)
+// as for a deserialized proof. It is not a constant-memory verifier.
+use crate::commitments::pcs::{BatchProofEval, PolyComScheme};
+use crate::plonk::errors::PlonkError;
+use crate::plonk::lookup::PlonkLookupProof;
+use crate::plonk::plonk_setup::{ConstraintSystem, VerifierParams};
+use crate::plonk::protocol::prover::{verifier, PlonkPf, PlonkProof};
+use crate::plonk::transcript::PlonkTranscript;
+use bincode::Options;
+use ruc::*;
+use serde::de::DeserializeOwned;
+use std::io::Read;
+use utils::serialization::versioned_serde::VERSIONED_ENCODING_TAG;
+
+/// Max number of bytes of the encoding of a single commitment, evaluation or evaluation proof
+pub const MAX_PROOF_ELEMENT_SIZE: u64 = 1024;
+
+// Same encoding as bincode::serialize, with a bound on the bytes read per element
+fn read_element<T: DeserializeOwned, R: Read>(reader: &mut R) -> Result<T> {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(MAX_PROOF_ELEMENT_SIZE)
+        .deserialize_from(reader)
+        .c(d!(PlonkError::ProofError))
+}
+
+// Reads the length prefix of a vector, which must be `expected_len`, then its elements
+fn read_vector<T: DeserializeOwned, R: Read>(
+    reader: &mut R,
+    expected_len: usize,
+) -> Result<Vec<T>> {
+    let len: u64 = read_element(reader).c(d!())?;
+    read_vector_elements(reader, len, expected_len).c(d!())
+}
+
+// Reads the elements of a vector whose length prefix `len` must be `expected_len`
+fn read_vector_elements<T: DeserializeOwned, R: Read>(
+    reader: &mut R,
+    len: u64,
+    expected_len: usize,
+) -> Result<Vec<T>> {
+    if len != expected_len as u64 {
+        return Err(eg!(PlonkError::ProofError));
+    }
+    let mut elements = Vec::with_capacity(expected_len);
+    for _ in 0..expected_len {
+        elements.push(read_element(reader).c(d!())?);
+    }
+    Ok(elements)
+}
+
+/// Decodes a proof for a constraint system with `n_wires_per_gate` wires per gate from its
+/// bincode encoding in `reader`. Rejects proofs of another shape before allocating for them.
+/// Also decodes the proofs encoded before the version was recorded, with version 0.
+#[allow(non_snake_case)]
+pub fn read_proof<PCS: PolyComScheme, R: Read>(
+    reader: &mut R,
+    n_wires_per_gate: usize,
+) -> Result<PlonkPf<PCS>> {
+    if n_wires_per_gate == 0 {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+    // versioned encoding or length of the witness commitments of a legacy proof, see
+    // utils::serialization::versioned_serde
+    let head: u64 = read_element(reader).c(d!())?;
+    let (version, C_witness_polys, legacy) = if head == VERSIONED_ENCODING_TAG {
+        let version = read_element(reader).c(d!())?;
+        (
+            version,
+            read_vector(reader, n_wires_per_gate).c(d!())?,
+            false,
+        )
+    } else {
+        let C_witness_polys =
+            read_vector_elements(reader, head, n_wires_per_gate).c(d!())?;
+        (0, C_witness_polys, true)
+    };
+    let C_q_polys = read_vector(reader, n_wires_per_gate).c(d!())?;
+    let C_Sigma = read_element(reader).c(d!())?;
+    let witness_polys_eval_beta = read_vector(reader, n_wires_per_gate).c(d!())?;
+    let Sigma_eval_g_beta = read_element(reader).c(d!())?;
+    let perms_eval_beta = read_vector(reader, n_wires_per_gate - 1).c(d!())?;
+    // the legacy proofs send L(beta) after the permutation evaluations
+    let L_eval_beta = if legacy {
+        Some(read_element(reader).c(d!())?)
+    } else {
+        None
+    };
+    let commitment = read_element(reader).c(d!())?;
+    let eval_proof = read_element(reader).c(d!())?;
+    // an option is encoded as a tag followed by the fields of its value, and the legacy proofs
+    // have no lookup argument
+    let lookup_tag: u8 = if legacy {
+        0
+    } else {
+        read_element(reader).c(d!())?
+    };
+    let lookup = match lookup_tag {
+        0 => None,
+        1 => Some(PlonkLookupProof {
+            C_h1: read_element(reader).c(d!())?,
+            C_h2: read_element(reader).c(d!())?,
+            C_Z: read_element(reader).c(d!())?,
+            q_lookup_eval_beta: read_element(reader).c(d!())?,
+            q_table_eval_beta: read_element(reader).c(d!())?,
+            t_eval_beta: read_element(reader).c(d!())?,
+            h1_eval_beta: read_element(reader).c(d!())?,
+            h2_eval_beta: read_element(reader).c(d!())?,
+            Z_eval_beta: read_element(reader).c(d!())?,
+            t_eval_g_beta: read_element(reader).c(d!())?,
+            h1_eval_g_beta: read_element(reader).c(d!())?,
+            h2_eval_g_beta: read_element(reader).c(d!())?,
+            Z_eval_g_beta: read_element(reader).c(d!())?,
+        }),
+        _ => return Err(eg!(PlonkError::ProofError)),
+    };
+    Ok(PlonkProof {
+        C_witness_polys,
+        C_q_polys,
+        C_Sigma,
+        witness_polys_eval_beta,
+        Sigma_eval_g_beta,
+        perms_eval_beta,
+        L_eval_beta,
+        batch_eval_proof: BatchProofEval {
+            commitment,
+            eval_proof,
+        },
+        version,
+        lookup,
+    })
+}
+
+/// Verify a proof read from `reader` for a constraint system previously preprocessed into
+/// `cs_params`, reading at most the bytes of a proof of the shape of `cs`, see read_proof.
+/// The proof is decoded before it is verified: same as plonk::prover::verifier otherwise.
+pub fn verifier_from_bounded_reader<
+    T: PlonkTranscript,
+    PCS: PolyComScheme,
+    CS: ConstraintSystem<Field = PCS::Field>,
+    R: Read,
+>(
+    transcript: &mut T,
+    pcs: &PCS,
+    cs: &CS,
+    cs_params: &VerifierParams<PCS>,
+    public_values: &[PCS::Field],
+    reader: &mut R,
+) -> Result<()> {
+    let proof = read_proof::<PCS, R>(reader, cs.n_wires_per_gate()).c(d!())?;
+    verifier(transcript, pcs, cs, cs_params, public_values, &proof).c(d!())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_proof, verifier_from_bounded_reader};
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitmentScheme, KZGCommitmentSchemeBLS,
+    };
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, PlonkConstraintSystem,
+    };
+    use crate::plonk::protocol::prover::prover;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    #[test]
+    fn test_verifier_from_bounded_reader() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        // circuit x_0 + x_1 = x_2 with public output x_2
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+
+        let common_seed = [0u8; 32];
+        let mut prover_params = pnk!(preprocess_prover(&cs, &pcs, common_seed));
+        let mut transcript = Transcript::new(b"Test");
+        let proof = pnk!(prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three]
+        ));
+        let verifier_params = pnk!(preprocess_verifier(&cs, &pcs, common_seed));
+        let proof_bytes = bincode::serialize(&proof).unwrap();
+
+        let decoded = pnk!(read_proof::<KZGCommitmentSchemeBLS, _>(
+            &mut &proof_bytes[..],
+            3
+        ));
+        assert_eq!(decoded, proof);
+
+        let mut transcript = Transcript::new(b"Test");
+        pnk!(verifier_from_bounded_reader(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[three],
+            &mut &proof_bytes[..]
+        ));
+        let mut transcript = Transcript::new(b"Test");
+        assert!(verifier_from_bounded_reader(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[two],
+            &mut &proof_bytes[..]
+        )
+        .is_err());
+
+        // the proofs of version 0 have the encoding from before the version was recorded,
+        // with L(beta) after the permutation evaluations
+        pnk!(prover_params.set_version(0));
+        let mut transcript = Transcript::new(b"Test");
+        let legacy_proof = pnk!(prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three]
+        ));
+        let legacy_bytes = bincode::serialize(&legacy_proof).unwrap();
+        assert_eq!(
+            pnk!(read_proof::<KZGCommitmentSchemeBLS, _>(
+                &mut &legacy_bytes[..],
+                3
+            )),
+            legacy_proof
+        );
+        let mut transcript = Transcript::new(b"Test");
+        pnk!(verifier_from_bounded_reader(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[three],
+            &mut &legacy_bytes[..]
+        ));
+
+        // truncated proof
+        let mut reader = &proof_bytes[..proof_bytes.len() - 1];
+        assert!(read_proof::<KZGCommitmentSchemeBLS, _>(&mut reader, 3).is_err());
+        // proof of another shape, or a huge length prefix, is rejected before allocating
+        assert!(
+            read_proof::<KZGCommitmentSchemeBLS, _>(&mut &proof_bytes[..], 5).is_err()
+        );
+        let mut bad_bytes = proof_bytes.clone();
+        // after the version tag and the version
+        bad_bytes[12..20].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(
+            read_proof::<KZGCommitmentSchemeBLS, _>(&mut &bad_bytes[..], 3).is_err()
+        );
+    }
+}
@@ -0,0 +1,269 @@
+// This file implements the deterministic mode of the PLONK prover, in which all the randomness
+// of the prover, i.e. the blinding factors of the witness, Sigma and lookup polynomials, is
+// derived from the witness and the circuit, so that two provers with the same inputs produce
+// byte-identical proofs, e.g. for multiparty signing workflows or regression tests.
+// The randomness is the output of the deterministic nonce generator of RFC 6979 (section 3.2),
+// a HMAC_DRBG instantiated with HMAC-SHA3-256, in which:
+//   - the private key x is the witness, each value encoded with Scalar::to_bytes,
+//   - the message hash h(m) is the SHA3-256 digest of the verifier key of the circuit (see
+//     verifier_key.rs), which binds the circuit, the SRS and the coset shifts,
+//   - the additional data k' of section 3.6 is an application `context`.
+// The generator is updated as in step h.3 after each request of random bytes.
+// Security: as for the nonces of RFC 6979, the blinding factors are unpredictable only to the
+// ones who do not know the witness, hence the proofs are zero-knowledge as long as the
+// witness has enough entropy unknown to the verifier, e.g. a secret key. Proving the same
+// witness twice gives the same proof, but proving it for two different transcripts would
+// reuse the blinding factors on different challenges: the `context` must then differ, e.g. be
+// the label of the transcript. Use transcript::transcript_prover_rng to add a secret key of
+// the prover.
+
+use crate::commitments::pcs::PolyComScheme;
+use crate::plonk::plonk_setup::{ConstraintSystem, ProverParams};
+use crate::plonk::protocol::prover::{prover, PlonkPf};
+use crate::plonk::transcript::PlonkTranscript;
+use algebra::groups::Scalar;
+use rand_core::{impls, CryptoRng, Error, RngCore};
+use ruc::*;
+use sha3::{Digest, Sha3_256};
+
+// Size of the blocks of the SHA3-256 sponge, i.e. its rate, as in the HMAC of FIPS 202
+const SHA3_256_BLOCK_SIZE: usize = 136;
+
+// HMAC-SHA3-256 of the concatenation of `parts` under a 32 byte key
+fn hmac_sha3(key: &[u8; 32], parts: &[&[u8]]) -> [u8; 32] {
+    let mut inner_key = [0x36u8; SHA3_256_BLOCK_SIZE];
+    let mut outer_key = [0x5cu8; SHA3_256_BLOCK_SIZE];
+    for (i, byte) in key.iter().enumerate() {
+        inner_key[i] ^= byte;
+        outer_key[i] ^= byte;
+    }
+    let mut inner = Sha3_256::new();
+    inner.update(&inner_key[..]);
+    for part in parts {
+        inner.update(part);
+    }
+    let mut outer = Sha3_256::new();
+    outer.update(&outer_key[..]);
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+/// The deterministic nonce generator of RFC 6979 with HMAC-SHA3-256, see deterministic.rs
+#[derive(Clone, Debug)]
+pub struct DeterministicNonceRng {
+    k: [u8; 32],
+    v: [u8; 32],
+}
+
+impl DeterministicNonceRng {
+    /// Seeds the generator with the private key `key`, the message hash `message` and the
+    /// additional data `extra`, as in steps b. to g. of RFC 6979, section 3.2
+    pub fn new(key: &[u8], message: &[u8], extra: &[u8]) -> DeterministicNonceRng {
+        let mut rng = DeterministicNonceRng {
+            k: [0u8; 32],
+            v: [1u8; 32],
+        };
+        for separator in [0u8, 1u8] {
+            rng.k = hmac_sha3(&rng.k, &[&rng.v, &[separator], key, message, extra]);
+            rng.v = hmac_sha3(&rng.k, &[&rng.v]);
+        }
+        rng
+    }
+}
+
+impl RngCore for DeterministicNonceRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(32) {
+            self.v = hmac_sha3(&self.k, &[&self.v]);
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+        // step h.3, before the next request
+        self.k = hmac_sha3(&self.k, &[&self.v, &[0u8]]);
+        self.v = hmac_sha3(&self.k, &[&self.v]);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> core::result::Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for DeterministicNonceRng {}
+
+/// Returns the generator of the prover randomness for `witness` in the circuit preprocessed
+/// into `params`, with the application `context`, see deterministic.rs
+pub fn deterministic_prover_rng<PCS: PolyComScheme>(
+    params: &ProverParams<PCS>,
+    witness: &[PCS::Field],
+    context: &[u8],
+) -> DeterministicNonceRng {
+    let mut key = (witness.len() as u64).to_le_bytes().to_vec();
+    for value in witness.iter() {
+        key.extend(value.to_bytes());
+    }
+    let mut hash = Sha3_256::new();
+    hash.update(b"PLONK deterministic prover");
+    hash.update(params.verifier_params.to_bytes());
+    DeterministicNonceRng::new(&key, &hash.finalize(), context)
+}
+
+/// Same as plonk::prover::prover in the deterministic mode: the randomness of the prover is
+/// derived by deterministic_prover_rng from `witness`, the circuit and `context`, so that the
+/// same inputs give byte-identical proofs. See deterministic.rs for the conditions under which
+/// the proofs are zero-knowledge.
+pub fn deterministic_prover<
+    T: PlonkTranscript,
+    PCS: PolyComScheme,
+    CS: ConstraintSystem<Field = PCS::Field>,
+>(
+    transcript: &mut T,
+    pcs: &PCS,
+    cs: &CS,
+    params: &ProverParams<PCS>,
+    witness: &[PCS::Field],
+    context: &[u8],
+) -> Result<PlonkPf<PCS>> {
+    let mut prng = deterministic_prover_rng(params, witness, context);
+    prover(&mut prng, transcript, pcs, cs, params, witness).c(d!())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
+    use crate::plonk::deterministic::{
+        deterministic_prover, hmac_sha3, DeterministicNonceRng,
+    };
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, PlonkConstraintSystem,
+    };
+    use crate::plonk::protocol::prover::verifier;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::Scalar;
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::{RngCore, SeedableRng};
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_nonce_rng() {
+        // test vectors of the HMAC-SHA3-256 of Python's hmac module
+        assert_eq!(
+            to_hex(&hmac_sha3(&[3u8; 32], &[b"mess", b"age"])),
+            "b20481dfa2ba281488813f4840472025006332cc764de6f50f179533a1acdfb9"
+        );
+        let mut rng = DeterministicNonceRng::new(b"key", b"message", b"");
+        let mut bytes = [0u8; 80];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(
+            to_hex(&bytes[..32]),
+            "7149d15a648d323aaf4005ac49e69425703a65bb9de37d92d30558f8ac142132"
+        );
+        let mut same_rng = DeterministicNonceRng::new(b"key", b"message", b"");
+        let mut same_bytes = [0u8; 80];
+        same_rng.fill_bytes(&mut same_bytes);
+        assert_eq!(bytes[..], same_bytes[..]);
+        assert_eq!(rng.next_u64(), same_rng.next_u64());
+        // a shorter request gives a prefix
+        let mut prefix_rng = DeterministicNonceRng::new(b"key", b"message", b"");
+        let mut prefix = [0u8; 40];
+        prefix_rng.fill_bytes(&mut prefix);
+        assert_eq!(prefix[..], bytes[..40]);
+        // the key, message and additional data change the outputs
+        for (key, message, extra) in [
+            (&b"kez"[..], &b"message"[..], &b""[..]),
+            (b"key", b"messagf", b""),
+            (b"key", b"message", b"context"),
+        ] {
+            let mut other_rng = DeterministicNonceRng::new(key, message, extra);
+            let mut other_bytes = [0u8; 80];
+            other_rng.fill_bytes(&mut other_bytes);
+            assert_ne!(other_bytes[..], bytes[..]);
+        }
+    }
+
+    #[test]
+    fn test_deterministic_prover() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        // circuit x_0 * x_1 = x_2 with public output x_2
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_mul_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let common_seed = [0u8; 32];
+        let prover_params = preprocess_prover(&cs, &pcs, common_seed).unwrap();
+        let verifier_params = preprocess_verifier(&cs, &pcs, common_seed).unwrap();
+
+        let prove = |witness: &[BLSScalar], context: &[u8]| {
+            let mut transcript = Transcript::new(b"TestPlonk");
+            deterministic_prover(
+                &mut transcript,
+                &pcs,
+                &cs,
+                &prover_params,
+                witness,
+                context,
+            )
+            .unwrap()
+        };
+        let witness = [
+            BLSScalar::from_u32(2),
+            BLSScalar::from_u32(3),
+            BLSScalar::from_u32(6),
+        ];
+        let proof = prove(&witness, b"");
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[witness[2]],
+            &proof
+        )
+        .is_ok());
+
+        // two provers with the same inputs give byte-identical proofs
+        assert_eq!(
+            bincode::serialize(&prove(&witness, b"")).unwrap(),
+            bincode::serialize(&proof).unwrap()
+        );
+        // another context, witness or circuit changes the blinding factors
+        assert_ne!(
+            prove(&witness, b"context").C_witness_polys,
+            proof.C_witness_polys
+        );
+        let other_witness = [
+            BLSScalar::from_u32(1),
+            BLSScalar::from_u32(6),
+            BLSScalar::from_u32(6),
+        ];
+        assert_ne!(
+            prove(&other_witness, b"").C_witness_polys,
+            proof.C_witness_polys
+        );
+        let other_params = preprocess_prover(&cs, &pcs, [1u8; 32]).unwrap();
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let other_proof = deterministic_prover(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &other_params,
+            &witness,
+            b"",
+        )
+        .unwrap();
+        assert_ne!(other_proof.C_witness_polys, proof.C_witness_polys);
+    }
+}
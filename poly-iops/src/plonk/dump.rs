@@ -0,0 +1,256 @@
+// This file implements a debug API that replays the PLONK verifier on a proof and dumps every
+// value it computes, in the order of the protocol:
+// - the commitments of the proof,
+// - the challenges gamma, delta, alpha and beta,
+// - the evaluations of the proof and the values derived by the verifier (public inputs
+//   polynomial and linearization polynomial evaluations, linearization and combined quotient
+//   commitments),
+// - the statement and challenges of the batch evaluation proof, with the derived commitment
+//   whose opening is checked last.
+// For KZG, the inputs of the final pairing check can be derived from the dump.
+// Comparing the dumps of the prover and verifier sides shows where a failed verification
+// diverged.
+
+use crate::commitments::kzg_poly_com::KZGCommitmentSchemeBLS;
+use crate::commitments::pcs::PolyComScheme;
+use crate::plonk::plonk_setup::{ConstraintSystem, VerifierParams};
+use crate::plonk::protocol::prover::{verifier_batch_eval_statement, PlonkPf};
+use crate::plonk::transcript::PlonkTranscript;
+use algebra::bls12_381::{BLSScalar, BLSG1, BLSG2};
+use algebra::groups::Zero;
+use ruc::*;
+
+/// Values computed by the PLONK verifier on a proof
+/// Generic on the commitment group C, the eval proof type E, and Field elements F.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlonkVerifierDump<C, E, F> {
+    // commitments of the proof
+    pub witness_commitments: Vec<C>,
+    pub sigma_commitment: C,
+    pub quotient_commitments: Vec<C>,
+    // challenges
+    pub gamma: F,
+    pub delta: F,
+    pub alpha: F,
+    pub beta: F,
+    // evaluations of the proof
+    pub witness_polys_eval_beta: Vec<F>,
+    pub perms_eval_beta: Vec<F>,
+    pub sigma_eval_g_beta: F,
+    // values derived by the verifier
+    pub public_vars_eval_beta: F,
+    pub linearization_eval_beta: F,
+    pub combined_quotient_commitment: C,
+    pub linearization_commitment: C,
+    // batch evaluation proof
+    pub batch_eval_commitments: Vec<C>,
+    pub batch_eval_points: Vec<F>,
+    pub batch_eval_values: Vec<F>,
+    pub batch_eval_alpha: F,
+    pub batch_eval_rho: F,
+    pub batch_eval_quotient_commitment: C,
+    pub batch_eval_derived_commitment: C,
+    pub batch_eval_proof: E,
+    // outcome of the final evaluation check
+    pub verified: bool,
+}
+
+pub type PlonkVerifierDmp<PCS> = PlonkVerifierDump<
+    <PCS as PolyComScheme>::Commitment,
+    <PCS as PolyComScheme>::EvalProof,
+    <PCS as PolyComScheme>::Field,
+>;
+
+/// Inputs of the pairings compared in the final check of a KZG verifier:
+/// e(lhs.0, lhs.1) == e(rhs.0, rhs.1)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KZGPairingInputs {
+    pub lhs: (BLSG1, BLSG2),
+    pub rhs: (BLSG1, BLSG2),
+}
+
+/// Replays the verifier on `proof` and dumps the values it computes.
+/// Takes the same arguments as plonk::prover::verifier, and never fails on an invalid proof:
+/// the outcome of the verification is in the `verified` field of the dump.
+/// Returns an error only if the batch evaluation proof cannot be reduced to a single
+/// evaluation, which happens when a challenge collides with an evaluation point.
+pub fn dump_verification<
+    T: PlonkTranscript,
+    PCS: PolyComScheme,
+    CS: ConstraintSystem<Field = PCS::Field>,
+>(
+    transcript: &mut T,
+    pcs: &PCS,
+    cs: &CS,
+    cs_params: &VerifierParams<PCS>,
+    public_values: &[PCS::Field],
+    proof: &PlonkPf<PCS>,
+) -> Result<PlonkVerifierDmp<PCS>> {
+    let statement =
+        verifier_batch_eval_statement(transcript, cs, cs_params, public_values, proof)
+            .c(d!())?;
+    let commitments: Vec<&PCS::Commitment> = statement.commitments.iter().collect();
+    let max_degree = cs_params.cs_size + 2;
+    let (batch_eval_alpha, batch_eval_rho, batch_eval_derived_commitment) = pcs
+        .batch_eval_reduce(
+            transcript,
+            &commitments[..],
+            max_degree,
+            &statement.points[..],
+            &statement.values[..],
+            &proof.batch_eval_proof,
+            None,
+        )
+        .c(d!())?;
+    let verified = pcs
+        .verify_eval(
+            transcript,
+            &batch_eval_derived_commitment,
+            max_degree,
+            &batch_eval_rho,
+            &PCS::Field::zero(),
+            &proof.batch_eval_proof.eval_proof,
+        )
+        .is_ok();
+
+    Ok(PlonkVerifierDump {
+        witness_commitments: proof.C_witness_polys.clone(),
+        sigma_commitment: proof.C_Sigma.clone(),
+        quotient_commitments: proof.C_q_polys.clone(),
+        gamma: statement.gamma,
+        delta: statement.delta,
+        alpha: statement.alpha,
+        beta: statement.beta,
+        witness_polys_eval_beta: proof.witness_polys_eval_beta.clone(),
+        perms_eval_beta: proof.perms_eval_beta.clone(),
+        sigma_eval_g_beta: proof.Sigma_eval_g_beta,
+        public_vars_eval_beta: statement.public_vars_eval_beta,
+        linearization_eval_beta: statement.derived_L_eval_beta,
+        combined_quotient_commitment: statement.C_q_combined,
+        linearization_commitment: statement.C_L,
+        batch_eval_commitments: statement.commitments,
+        batch_eval_points: statement.points,
+        batch_eval_values: statement.values,
+        batch_eval_alpha,
+        batch_eval_rho,
+        batch_eval_quotient_commitment: proof.batch_eval_proof.commitment.clone(),
+        batch_eval_derived_commitment,
+        batch_eval_proof: proof.batch_eval_proof.eval_proof.clone(),
+        verified,
+    })
+}
+
+/// Derives from `dump` the inputs of the final pairing check of the KZG verifier
+pub fn kzg_pairing_inputs(
+    pcs: &KZGCommitmentSchemeBLS,
+    dump: &PlonkVerifierDmp<KZGCommitmentSchemeBLS>,
+) -> KZGPairingInputs {
+    let (lhs, rhs) = pcs.verify_eval_pairing_inputs(
+        &dump.batch_eval_derived_commitment,
+        &dump.batch_eval_rho,
+        &BLSScalar::zero(),
+        &dump.batch_eval_proof,
+    );
+    KZGPairingInputs { lhs, rhs }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{dump_verification, kzg_pairing_inputs};
+    use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, PlonkConstraintSystem,
+    };
+    use crate::plonk::protocol::prover::{prover, verifier};
+    use algebra::bls12_381::{BLSScalar, Bls12381};
+    use algebra::groups::{One, Pairing, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    #[test]
+    fn test_dump_verification() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        // circuit x_0 * x_1 = x_2 with public output x_2
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_mul_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+        let six = three.add(&three);
+        let witness = [two, three, six];
+
+        let common_seed = [0u8; 32];
+        let prover_params = pnk!(preprocess_prover(&cs, &pcs, common_seed));
+        let mut transcript = Transcript::new(b"Test");
+        let proof = pnk!(prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness
+        ));
+        let verifier_params = pnk!(preprocess_verifier(&cs, &pcs, common_seed));
+
+        let mut transcript = Transcript::new(b"Test");
+        let dump = pnk!(dump_verification(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[six],
+            &proof
+        ));
+        assert!(dump.verified);
+        assert_eq!(dump.witness_commitments, proof.C_witness_polys);
+        assert_eq!(
+            dump.batch_eval_commitments.len(),
+            dump.batch_eval_points.len()
+        );
+        assert_eq!(dump.batch_eval_values.len(), dump.batch_eval_points.len());
+        // witness and permutation polynomials, linearization polynomial and Sigma
+        assert_eq!(dump.batch_eval_points.len(), 3 + 2 + 1 + 1);
+        assert_eq!(dump.batch_eval_points[0], dump.beta);
+        let pairing_inputs = kzg_pairing_inputs(&pcs, &dump);
+        assert_eq!(
+            Bls12381::pairing(&pairing_inputs.lhs.0, &pairing_inputs.lhs.1),
+            Bls12381::pairing(&pairing_inputs.rhs.0, &pairing_inputs.rhs.1)
+        );
+        // the dump serializes, e.g. to be logged
+        let dump_json = serde_json::to_string(&dump).unwrap();
+        assert!(!dump_json.is_empty());
+
+        // a wrong public input changes the challenges already
+        let mut transcript = Transcript::new(b"Test");
+        let bad_dump = pnk!(dump_verification(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[three],
+            &proof
+        ));
+        let mut transcript = Transcript::new(b"Test");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[three],
+            &proof
+        )
+        .is_err());
+        assert!(!bad_dump.verified);
+        assert_ne!(bad_dump.gamma, dump.gamma);
+        let pairing_inputs = kzg_pairing_inputs(&pcs, &bad_dump);
+        assert_ne!(
+            Bls12381::pairing(&pairing_inputs.lhs.0, &pairing_inputs.lhs.1),
+            Bls12381::pairing(&pairing_inputs.rhs.0, &pairing_inputs.rhs.1)
+        );
+    }
+}
@@ -11,6 +11,22 @@ pub enum PlonkError {
     VerificationError,
     DivisionByZero,
     FuncParamsError,
+    UnsupportedVersion,
+    DeserializationError,
+    /// A gate wires a variable that is not in the constraint system:
+    /// (index of the variable, number of variables)
+    WireIndexOutOfBounds(usize, usize),
+    /// A selector index or a number of selector values does not match the constraint
+    /// system: (index or number given, number of selectors)
+    SelectorMismatch(usize, usize),
+    /// A lookup gate refers to a table that is not registered:
+    /// (id of the table, number of tables)
+    LookupTableNotFound(usize, usize),
+    /// An evaluation domain is too small: (size of the domain, required size)
+    DomainTooSmall(usize, usize),
+    /// The SRS does not support the degree of the polynomials of the circuit:
+    /// (max degree of the SRS, required degree)
+    SrsTooShort(usize, usize),
 }
 
 impl fmt::Display for PlonkError {
@@ -25,6 +41,43 @@ impl fmt::Display for PlonkError {
             PlonkError::VerificationError => "VerificationError",
             PlonkError::DivisionByZero => "DivisionByZero",
             PlonkError::FuncParamsError => "FuncParamsError",
+            PlonkError::UnsupportedVersion => "UnsupportedVersion",
+            PlonkError::DeserializationError => "DeserializationError",
+            PlonkError::WireIndexOutOfBounds(index, num_vars) => {
+                return write!(
+                    f,
+                    "WireIndexOutOfBounds: variable {} of {} variables",
+                    index, num_vars
+                );
+            }
+            PlonkError::SelectorMismatch(given, num_selectors) => {
+                return write!(
+                    f,
+                    "SelectorMismatch: {} given for {} selectors",
+                    given, num_selectors
+                );
+            }
+            PlonkError::LookupTableNotFound(table_id, num_tables) => {
+                return write!(
+                    f,
+                    "LookupTableNotFound: table {} of {} tables",
+                    table_id, num_tables
+                );
+            }
+            PlonkError::DomainTooSmall(size, min_size) => {
+                return write!(
+                    f,
+                    "DomainTooSmall: domain of size {}, at least {} required",
+                    size, min_size
+                );
+            }
+            PlonkError::SrsTooShort(max_degree, degree) => {
+                return write!(
+                    f,
+                    "SrsTooShort: SRS of max degree {}, degree {} required",
+                    max_degree, degree
+                );
+            }
         };
 
         write!(f, "{}", c)
@@ -0,0 +1,295 @@
+// This file exports PLONK proofs, public inputs and verifier parameters over BLS12-381 with KZG
+// commitments as JSON, so that JS tooling and explorers can parse and display zei proofs without
+// decoding zei's binary encodings:
+// - field elements are decimal strings,
+// - G1 points are projective triples [x, y, "1"] (["0", "1", "0"] for the identity),
+// - G2 points are [[x.c0, x.c1], [y.c0, y.c1], ["1", "0"]],
+// - the fields are named after the fields of PlonkProof and PlonkVerifierParams, lists keep the
+//   order of the wires and selectors of the constraint system, and lookup fields are null if the
+//   constraint system has no lookup table.
+// Any proof can be exported, TurboPLONK proofs and proofs with lookup gates included.
+// This is zei's own layout, it is *not* the layout of snarkjs nor of any other PLONK
+// implementation: zei derives the challenges with a merlin transcript and opens all polynomials
+// with a single multi-point batch proof, so no external PLONK verifier can check these proofs.
+// The export is for inspection, zei's verifier remains the one that checks the proofs.
+
+use crate::commitments::kzg_poly_com::{KZGCommitment, KZGCommitmentSchemeBLS};
+use crate::plonk::errors::PlonkError;
+use crate::plonk::plonk_setup::VerifierParams;
+use crate::plonk::protocol::prover::PlonkPf;
+use algebra::bls12_381::{BLSScalar, BLSG1, BLSG2};
+use algebra::groups::Scalar;
+use num_bigint::BigUint;
+use ruc::*;
+use serde_json::{json, Value};
+
+const PROTOCOL: &str = "zei-plonk";
+const CURVE: &str = "bls12381";
+
+fn le_bytes_to_decimal(bytes: &[u8]) -> String {
+    BigUint::from_bytes_le(bytes).to_str_radix(10)
+}
+
+fn scalar_to_json(scalar: &BLSScalar) -> Value {
+    Value::String(le_bytes_to_decimal(&scalar.to_bytes()))
+}
+
+fn scalars_to_json(scalars: &[BLSScalar]) -> Value {
+    Value::Array(scalars.iter().map(scalar_to_json).collect())
+}
+
+fn g1_to_json(point: &BLSG1) -> Value {
+    match point.to_affine_le_bytes() {
+        Some((x, y)) => json!([le_bytes_to_decimal(&x), le_bytes_to_decimal(&y), "1"]),
+        None => json!(["0", "1", "0"]),
+    }
+}
+
+fn commitments_to_json(commitments: &[KZGCommitment<BLSG1>]) -> Value {
+    Value::Array(commitments.iter().map(|c| g1_to_json(&c.value)).collect())
+}
+
+fn g2_to_json(point: &BLSG2) -> Value {
+    match point.to_affine_le_bytes() {
+        Some((x, y)) => json!([
+            [le_bytes_to_decimal(&x[0]), le_bytes_to_decimal(&x[1])],
+            [le_bytes_to_decimal(&y[0]), le_bytes_to_decimal(&y[1])],
+            ["1", "0"]
+        ]),
+        None => json!([["0", "0"], ["1", "0"], ["0", "0"]]),
+    }
+}
+
+/// Export `proof` as JSON
+pub fn proof_json(proof: &PlonkPf<KZGCommitmentSchemeBLS>) -> Value {
+    let lookup = proof.lookup.as_ref().map(|lookup| {
+        json!({
+            "C_h1": g1_to_json(&lookup.C_h1.value),
+            "C_h2": g1_to_json(&lookup.C_h2.value),
+            "C_Z": g1_to_json(&lookup.C_Z.value),
+            "q_lookup_eval_beta": scalar_to_json(&lookup.q_lookup_eval_beta),
+            "q_table_eval_beta": scalar_to_json(&lookup.q_table_eval_beta),
+            "t_eval_beta": scalar_to_json(&lookup.t_eval_beta),
+            "h1_eval_beta": scalar_to_json(&lookup.h1_eval_beta),
+            "h2_eval_beta": scalar_to_json(&lookup.h2_eval_beta),
+            "Z_eval_beta": scalar_to_json(&lookup.Z_eval_beta),
+            "t_eval_g_beta": scalar_to_json(&lookup.t_eval_g_beta),
+            "h1_eval_g_beta": scalar_to_json(&lookup.h1_eval_g_beta),
+            "h2_eval_g_beta": scalar_to_json(&lookup.h2_eval_g_beta),
+            "Z_eval_g_beta": scalar_to_json(&lookup.Z_eval_g_beta),
+        })
+    });
+    json!({
+        "protocol": PROTOCOL,
+        "curve": CURVE,
+        "version": proof.version,
+        "C_witness_polys": commitments_to_json(&proof.C_witness_polys),
+        "C_q_polys": commitments_to_json(&proof.C_q_polys),
+        "C_Sigma": g1_to_json(&proof.C_Sigma.value),
+        "witness_polys_eval_beta": scalars_to_json(&proof.witness_polys_eval_beta),
+        "Sigma_eval_g_beta": scalar_to_json(&proof.Sigma_eval_g_beta),
+        "perms_eval_beta": scalars_to_json(&proof.perms_eval_beta),
+        "L_eval_beta": proof.L_eval_beta.as_ref().map(scalar_to_json),
+        "batch_eval_commitment": g1_to_json(&proof.batch_eval_proof.commitment.value),
+        "batch_eval_proof": g1_to_json(&proof.batch_eval_proof.eval_proof.0),
+        "lookup": lookup,
+    })
+}
+
+/// Export the public inputs of a proof, in the order of the public variables of the constraint system
+pub fn public_inputs_json(public_inputs: &[BLSScalar]) -> Value {
+    scalars_to_json(public_inputs)
+}
+
+/// Export the verifier parameters `params`, preprocessed with `pcs`, as JSON, with the G2 element
+/// of the SRS that the pairing check of the batch evaluation proof uses
+pub fn verifier_params_json(
+    pcs: &KZGCommitmentSchemeBLS,
+    params: &VerifierParams<KZGCommitmentSchemeBLS>,
+) -> Result<Value> {
+    if !params.cs_size.is_power_of_two() || pcs.public_parameter_group_2.len() < 2 {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+    let lookup = params.lookup.as_ref().map(|lookup| {
+        json!({
+            "q_lookup": g1_to_json(&lookup.q_lookup.value),
+            "q_table": g1_to_json(&lookup.q_table.value),
+            "table": commitments_to_json(&lookup.table),
+            "default_entry": scalars_to_json(&lookup.default_entry),
+        })
+    });
+    Ok(json!({
+        "protocol": PROTOCOL,
+        "curve": CURVE,
+        "version": params.version,
+        "cs_size": params.cs_size,
+        "n_public": params.public_vars_constraint_indices.len(),
+        "public_vars_constraint_indices": params.public_vars_constraint_indices,
+        "root": scalar_to_json(&params.root),
+        "k": scalars_to_json(&params.k),
+        "selectors": commitments_to_json(&params.selectors),
+        "extended_permutations": commitments_to_json(&params.extended_permutations),
+        "lookup": lookup,
+        "srs_g2": g2_to_json(&pcs.public_parameter_group_2[1]),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        g1_to_json, le_bytes_to_decimal, proof_json, public_inputs_json,
+        verifier_params_json,
+    };
+    use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, PlonkConstraintSystem,
+    };
+    use crate::plonk::protocol::prover::{prover, verifier};
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::{BLSScalar, BLSG1};
+    use algebra::groups::{Group, One, Scalar, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    #[test]
+    fn test_json_export() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        // circuit x_0 + x_1 = x_2 with public output x_2
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+        let witness = [one, two, three];
+
+        let common_seed = [0u8; 32];
+        let prover_params = pnk!(preprocess_prover(&cs, &pcs, common_seed));
+        let mut transcript = Transcript::new(b"Test");
+        let proof = pnk!(prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness
+        ));
+        let verifier_params = pnk!(preprocess_verifier(&cs, &pcs, common_seed));
+        let mut transcript = Transcript::new(b"Test");
+        pnk!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[three],
+            &proof
+        ));
+
+        let proof_json = proof_json(&proof);
+        assert_eq!(proof_json["protocol"], "zei-plonk");
+        assert_eq!(proof_json["curve"], "bls12381");
+        assert_eq!(proof_json["version"], proof.version());
+        assert_eq!(proof_json["C_witness_polys"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            proof_json["C_witness_polys"][0],
+            g1_to_json(&proof.C_witness_polys[0].value)
+        );
+        assert_eq!(proof_json["C_witness_polys"][0][2], "1");
+        assert_eq!(
+            proof_json["Sigma_eval_g_beta"],
+            le_bytes_to_decimal(&proof.Sigma_eval_g_beta.to_bytes()).as_str()
+        );
+        assert!(proof_json["L_eval_beta"].is_null());
+        assert!(proof_json["lookup"].is_null());
+        for key in &[
+            "C_q_polys",
+            "C_Sigma",
+            "witness_polys_eval_beta",
+            "perms_eval_beta",
+            "batch_eval_commitment",
+            "batch_eval_proof",
+        ] {
+            assert!(!proof_json[key].is_null());
+        }
+
+        let public_json = public_inputs_json(&[three]);
+        assert_eq!(public_json, serde_json::json!(["3"]));
+
+        let vk_json = pnk!(verifier_params_json(&pcs, &verifier_params));
+        assert_eq!(vk_json["n_public"], 1);
+        assert_eq!(vk_json["cs_size"], verifier_params.cs_size);
+        assert_eq!(
+            vk_json["selectors"][0],
+            g1_to_json(&verifier_params.selectors[0].value)
+        );
+        assert_eq!(vk_json["srs_g2"][2], serde_json::json!(["1", "0"]));
+        assert_eq!(vk_json["srs_g2"][0].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_json_export_turbo_plonk_lookup() {
+        let mut prng = ChaChaRng::from_seed([2u8; 32]);
+        let pcs = KZGCommitmentScheme::new(40, &mut prng);
+        // 0 <= a < 8 and a + b is public
+        let mut cs = TurboPlonkConstraintSystem::<BLSScalar>::new();
+        let num: Vec<BLSScalar> = (0..8).map(BLSScalar::from_u32).collect();
+        let range_id = cs.add_lookup_table(&num);
+        let a = cs.new_variable(num[5]);
+        let b = cs.new_variable(num[2]);
+        cs.lookup(a, range_id);
+        let c = cs.add(a, b);
+        cs.prepare_io_variable(c);
+        cs.pad();
+        let witness = cs.get_and_clear_witness();
+
+        let prover_params = pnk!(preprocess_prover(&cs, &pcs, [0u8; 32]));
+        let mut transcript = Transcript::new(b"Test");
+        let proof = pnk!(prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness
+        ));
+
+        let proof_json = proof_json(&proof);
+        assert_eq!(
+            proof_json["C_witness_polys"].as_array().unwrap().len(),
+            proof.C_witness_polys.len()
+        );
+        let lookup = proof.lookup.as_ref().unwrap();
+        assert_eq!(proof_json["lookup"]["C_h1"], g1_to_json(&lookup.C_h1.value));
+        assert_eq!(
+            proof_json["lookup"]["Z_eval_g_beta"],
+            le_bytes_to_decimal(&lookup.Z_eval_g_beta.to_bytes()).as_str()
+        );
+
+        let vk_json = pnk!(verifier_params_json(&pcs, &prover_params.verifier_params));
+        assert_eq!(
+            vk_json["selectors"].as_array().unwrap().len(),
+            prover_params.verifier_params.selectors.len()
+        );
+        assert!(!vk_json["lookup"]["table"].is_null());
+    }
+
+    #[test]
+    fn test_json_g1_encoding() {
+        let identity = BLSG1::get_identity();
+        assert_eq!(g1_to_json(&identity), serde_json::json!(["0", "1", "0"]));
+        // generator of BLS12-381 G1
+        let base = g1_to_json(&BLSG1::get_base());
+        assert_eq!(
+            base[0],
+            "3685416753713387016781088315183077757961620795782546409894578378688607592378376318836054947676345821548104185464507"
+        );
+        assert_eq!(
+            base[1],
+            "1339506544944476473020471379941921221584933875938349620426543736416511423956333506472724655353366534992391756441569"
+        );
+    }
+}
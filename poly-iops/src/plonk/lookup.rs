@@ -0,0 +1,781 @@
+// This file implements the lookup argument of Plookup (Gabizon, Williamson) for the lookup gates of
+// a constraint system (see ConstraintSystem::lookup_columns).
+// A lookup gate at g^i asserts that (f_0(g^i), f_1(g^i), q_table(g^i)) is an entry (input, output,
+// table id) of the lookup tables of the constraint system, where f_0, f_1 are the first two witness
+// polynomials and q_table is a selector. The selector q_lookup is one on the lookup gates and zero
+// elsewhere. For a challenge zeta, the entries of the table are compressed into
+//   t(g^j) = t_0(g^j) + zeta * t_1(g^j) + zeta^2 * t_2(g^j)
+// after padding the table to the n elements of H by repeating its last entry, and the queries into
+//   f(X) = q_lookup(X) * (f_0(X) + zeta * f_1(X) + zeta^2 * q_table(X) - d) + d
+// where d = t(1) is the first entry of the table, queried by the gates that are not lookup gates.
+// Only the queries f(g^0), ..., f(g^{n-2}) are checked, the last gate is never a lookup gate.
+
+// The prover sorts s = (f(g^0), ..., f(g^{n-2}), t(g^0), ..., t(g^{n-1})) by t, that is, it places
+// each query next to an equal entry of the table, and interpolates h1 on s[0..n] and h2 on
+// s[n-1..2n-1], which overlap in one value. For random challenges epsilon and theta, it
+// interpolates Z(X) such that Z(1) = 1 and, for i in {0..n-2},
+//   Z(g^{i+1}) = Z(g^i) * (1 + epsilon)(theta + f(g^i))(theta(1 + epsilon) + t(g^i) + epsilon * t(g^{i+1}))
+//     / [(theta(1 + epsilon) + h1(g^i) + epsilon * h1(g^{i+1}))(theta(1 + epsilon) + h2(g^i) + epsilon * h2(g^{i+1}))]
+// The queries are entries of the table if and only if, with overwhelming probability, Z(g^{n-1}) = 1
+// and h1(g^{n-1}) = h2(1) (Plookup, claim 3.1). The verifier checks it with the following terms of
+// P(X), which follow the terms of the gates and the permutation with the next powers of alpha:
+//   alpha^3 * (X - g^{n-1}) * [Z(g*X) (theta(1 + epsilon) + h1(X) + epsilon * h1(g*X))(theta(1 + epsilon) + h2(X) + epsilon * h2(g*X))
+//              - Z(X) (1 + epsilon)(theta + f(X))(theta(1 + epsilon) + t(X) + epsilon * t(g*X))]
+//   + alpha^4 * L_1(X) * (Z(X) - 1)
+//   + alpha^5 * L_n(X) * (h1(X) - h2(g*X))
+//   + alpha^6 * L_n(X) * (Z(X) - 1)
+// where L_1(X) = (X^n - 1) / (X - 1) and L_n(X) = (X^n - 1) / (X - g^{n-1}).
+
+// Protocol, on top of the steps of plonk::protocol:
+// 2'. After the challenges gamma, delta, the challenge zeta is sampled. The prover commits to h1
+// and h2, then the challenges epsilon and theta are sampled.
+// 3'. The prover commits to Z along with Sigma.
+// 8'. The prover appends the evaluations of q_lookup, q_table, t, h1, h2, Z at beta and of t, h1,
+// h2, Z at g * beta to the proof and to the batch evaluation proof. The verifier derives the
+// commitment to t from the commitments to the columns of the table, and computes the lookup terms
+// of P(beta), which it subtracts from L(beta).
+// h1, h2 and Z are opened in two points, hence they are blinded with degree 2 polynomials.
+
+use crate::commitments::pcs::{HomomorphicPolyComElem, PolyComScheme, ToBytes};
+use crate::commitments::transcript::PolyComTranscript;
+use crate::plonk::errors::PlonkError;
+use crate::plonk::plonk_helpers::hide_polynomial;
+use crate::plonk::plonk_setup::ProverParams;
+use crate::plonk::transcript::{
+    transcript_get_plonk_challenge_epsilon, transcript_get_plonk_challenge_theta,
+    transcript_get_plonk_challenge_zeta, PlonkTranscript,
+};
+use crate::polynomials::field_polynomial::FpPolynomial;
+use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use std::collections::HashMap;
+
+// Number of evaluations of the lookup part of a proof at beta and at g * beta
+const N_LOOKUP_EVALS_BETA: usize = 6;
+const N_LOOKUP_EVALS_G_BETA: usize = 4;
+
+/// Lookup gates of a constraint system, over the n gates of the constraint system
+pub struct LookupColumns<F> {
+    /// The i-th value is one if the i-th gate is a lookup gate and zero otherwise
+    pub q_lookup: Vec<F>,
+    /// The i-th value is the id of the table looked up by the i-th gate
+    pub q_table: Vec<F>,
+    /// The entries (input, output, table id) of the tables, at most n
+    pub table: Vec<[F; 3]>,
+}
+
+/// Commitments of the verifier to the lookup gates of a constraint system
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlonkLookupVerifierParams<C, F> {
+    pub(crate) q_lookup: C,
+    pub(crate) q_table: C,
+    // input, output and table id columns of the padded table
+    pub(crate) table: Vec<C>,
+    // first entry of the table, queried by the gates that are not lookup gates
+    pub(crate) default_entry: Vec<F>,
+}
+
+/// Lookup gates of a constraint system preprocessed for the prover
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct PlonkLookupProverParams<O, F> {
+    pub(crate) q_lookup: O,
+    pub(crate) q_table: O,
+    pub(crate) table: Vec<O>,
+    // values over H
+    pub(crate) q_lookup_values: Vec<F>,
+    pub(crate) q_table_values: Vec<F>,
+    pub(crate) table_values: Vec<Vec<F>>,
+    // evaluations over the domain of the quotient polynomial
+    pub(crate) q_lookup_coset_evals: Vec<F>,
+    pub(crate) q_table_coset_evals: Vec<F>,
+    pub(crate) table_coset_evals: Vec<Vec<F>>,
+    pub(crate) Ln_coset_evals: Vec<F>,
+}
+
+pub type LookupProverParams<PCS> = PlonkLookupProverParams<
+    <PCS as PolyComScheme>::Opening,
+    <PCS as PolyComScheme>::Field,
+>;
+
+pub type LookupVerifierParams<PCS> = PlonkLookupVerifierParams<
+    <PCS as PolyComScheme>::Commitment,
+    <PCS as PolyComScheme>::Field,
+>;
+
+/// Commitments and evaluations of the lookup argument in a PLONK proof
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize, Clone)]
+#[allow(non_snake_case)]
+pub struct PlonkLookupProof<C, F> {
+    pub(crate) C_h1: C,
+    pub(crate) C_h2: C,
+    pub(crate) C_Z: C,
+    pub(crate) q_lookup_eval_beta: F,
+    pub(crate) q_table_eval_beta: F,
+    pub(crate) t_eval_beta: F,
+    pub(crate) h1_eval_beta: F,
+    pub(crate) h2_eval_beta: F,
+    pub(crate) Z_eval_beta: F,
+    pub(crate) t_eval_g_beta: F,
+    pub(crate) h1_eval_g_beta: F,
+    pub(crate) h2_eval_g_beta: F,
+    pub(crate) Z_eval_g_beta: F,
+}
+
+impl<C, F: Copy> PlonkLookupProof<C, F> {
+    /// Returns the evaluations in the order of the batch evaluation proof, see lookup_eval_points
+    pub(crate) fn evals(&self) -> Vec<F> {
+        vec![
+            self.q_lookup_eval_beta,
+            self.q_table_eval_beta,
+            self.t_eval_beta,
+            self.h1_eval_beta,
+            self.h2_eval_beta,
+            self.Z_eval_beta,
+            self.t_eval_g_beta,
+            self.h1_eval_g_beta,
+            self.h2_eval_g_beta,
+            self.Z_eval_g_beta,
+        ]
+    }
+}
+
+pub(crate) struct LookupChallenges<F> {
+    pub(crate) zeta: F,
+    pub(crate) epsilon: F,
+    pub(crate) theta: F,
+}
+
+// Values of the polynomials of the lookup argument at a point X, and at g * X for the `_g` ones
+#[allow(non_snake_case)]
+struct LookupEvals<F> {
+    w0: F,
+    w1: F,
+    q_lookup: F,
+    q_table: F,
+    t: F,
+    t_g: F,
+    h1: F,
+    h1_g: F,
+    h2: F,
+    h2_g: F,
+    Z: F,
+    Z_g: F,
+}
+
+// a + zeta * b + zeta^2 * c
+fn compress<F: Scalar>(a: &F, b: &F, c: &F, zeta: &F) -> F {
+    a.add(&zeta.mul(&b.add(&zeta.mul(c))))
+}
+
+// Combines the columns of the table into t(X) = t_0(X) + zeta * t_1(X) + zeta^2 * t_2(X)
+fn combine_table<F: Scalar, PCSType: HomomorphicPolyComElem<Scalar = F>>(
+    table: &[PCSType],
+    zeta: &F,
+) -> PCSType {
+    table[0]
+        .op(&table[1].exp(zeta))
+        .op(&table[2].exp(&zeta.square()))
+}
+
+// alpha^3, alpha^4, alpha^5, alpha^6
+fn lookup_alphas<F: Scalar>(alpha: &F) -> [F; 4] {
+    let alpha_3 = alpha.square().mul(alpha);
+    let alpha_4 = alpha_3.mul(alpha);
+    let alpha_5 = alpha_4.mul(alpha);
+    let alpha_6 = alpha_5.mul(alpha);
+    [alpha_3, alpha_4, alpha_5, alpha_6]
+}
+
+// Computes the lookup terms of P(X), where `x_minus_last` = X - g^{n-1}, `L1` = L_1(X),
+// `Ln` = L_n(X) and `default_entry` is the compressed first entry of the table
+#[allow(non_snake_case)]
+fn lookup_terms<F: Scalar>(
+    evals: &LookupEvals<F>,
+    default_entry: &F,
+    challenges: &LookupChallenges<F>,
+    alphas: &[F; 4],
+    x_minus_last: &F,
+    L1: &F,
+    Ln: &F,
+) -> F {
+    let one = F::one();
+    let epsilon = &challenges.epsilon;
+    let theta = &challenges.theta;
+    let one_plus_epsilon = one.add(epsilon);
+    let theta_one_plus_epsilon = theta.mul(&one_plus_epsilon);
+    let pair = |x: &F, x_g: &F| theta_one_plus_epsilon.add(x).add(&epsilon.mul(x_g));
+
+    // f(X) = q_lookup(X) * (w0(X) + zeta * w1(X) + zeta^2 * q_table(X) - d) + d
+    let query = compress(&evals.w0, &evals.w1, &evals.q_table, &challenges.zeta);
+    let f = evals
+        .q_lookup
+        .mul(&query.sub(default_entry))
+        .add(default_entry);
+
+    let num = evals
+        .Z
+        .mul(&one_plus_epsilon)
+        .mul(&theta.add(&f))
+        .mul(&pair(&evals.t, &evals.t_g));
+    let den = evals
+        .Z_g
+        .mul(&pair(&evals.h1, &evals.h1_g))
+        .mul(&pair(&evals.h2, &evals.h2_g));
+    let Z_minus_one = evals.Z.sub(&one);
+
+    let term1 = alphas[0].mul(x_minus_last).mul(&den.sub(&num));
+    let term2 = alphas[1].mul(L1).mul(&Z_minus_one);
+    let term3 = alphas[2].mul(Ln).mul(&evals.h1.sub(&evals.h2_g));
+    let term4 = alphas[3].mul(Ln).mul(&Z_minus_one);
+    term1.add(&term2).add(&term3).add(&term4)
+}
+
+/// Preprocesses the lookup gates `columns` of a constraint system over the group H = `group`: pads
+/// the table to n = |H| entries by repeating its last entry, interpolates and commits to the
+/// selectors and to the columns of the table, and, if `with_coset_evals`, evaluates them over the
+/// domain of the quotient polynomial, the coset `k1` * <`root_m`> of size `m`.
+/// Returns SetupError if the columns do not match H, if the table is empty, if q_lookup is not
+/// boolean or if the last gate is a lookup gate, and DomainTooSmall if the table is larger than
+/// H.
+#[allow(non_snake_case, clippy::type_complexity)]
+pub(crate) fn preprocess_lookup<PCS: PolyComScheme>(
+    pcs: &PCS,
+    columns: &LookupColumns<PCS::Field>,
+    group: &[PCS::Field],
+    root_m: &PCS::Field,
+    m: usize,
+    k1: &PCS::Field,
+    with_coset_evals: bool,
+) -> Result<(LookupProverParams<PCS>, LookupVerifierParams<PCS>)> {
+    let n = group.len();
+    let zero = PCS::Field::zero();
+    let one = PCS::Field::one();
+    if columns.table.len() > n {
+        return Err(eg!(PlonkError::DomainTooSmall(n, columns.table.len())));
+    }
+    if n < 2
+        || columns.q_lookup.len() != n
+        || columns.q_table.len() != n
+        || columns.table.is_empty()
+        || columns.q_lookup[n - 1] != zero
+        || columns.q_lookup.iter().any(|q| *q != zero && *q != one)
+    {
+        return Err(eg!(PlonkError::SetupError));
+    }
+    let root = &group[1];
+
+    let mut table_values = vec![Vec::with_capacity(n); 3];
+    for i in 0..n {
+        let entry = &columns.table[i.min(columns.table.len() - 1)];
+        for (column, value) in table_values.iter_mut().zip(entry.iter()) {
+            column.push(*value);
+        }
+    }
+
+    let coset_evals = |poly: &FpPolynomial<PCS::Field>| {
+        if with_coset_evals {
+            poly.coset_fft_with_unity_root(root_m, m, k1)
+        } else {
+            vec![]
+        }
+    };
+    let commit = |values: &[PCS::Field]| -> Result<(
+        PCS::Commitment,
+        PCS::Opening,
+        Vec<PCS::Field>,
+    )> {
+        let poly = FpPolynomial::ffti(root, values);
+        let coset_evals = coset_evals(&poly);
+        let (C, O) = pcs.commit(poly).c(d!(PlonkError::SetupError))?;
+        Ok((C, O, coset_evals))
+    };
+    let (C_q_lookup, O_q_lookup, q_lookup_coset_evals) =
+        commit(&columns.q_lookup).c(d!())?;
+    let (C_q_table, O_q_table, q_table_coset_evals) =
+        commit(&columns.q_table).c(d!())?;
+    let mut C_table = vec![];
+    let mut O_table = vec![];
+    let mut table_coset_evals = vec![];
+    for column in table_values.iter() {
+        let (C, O, coset_evals) = commit(column).c(d!())?;
+        C_table.push(C);
+        O_table.push(O);
+        table_coset_evals.push(coset_evals);
+    }
+    let Ln = FpPolynomial::from_zeroes(&group[..n - 1]);
+    let Ln_coset_evals = coset_evals(&Ln);
+
+    let verifier_params = PlonkLookupVerifierParams {
+        q_lookup: C_q_lookup,
+        q_table: C_q_table,
+        table: C_table,
+        default_entry: table_values.iter().map(|column| column[0]).collect(),
+    };
+    let prover_params = PlonkLookupProverParams {
+        q_lookup: O_q_lookup,
+        q_table: O_q_table,
+        table: O_table,
+        q_lookup_values: columns.q_lookup.clone(),
+        q_table_values: columns.q_table.clone(),
+        table_values,
+        q_lookup_coset_evals,
+        q_table_coset_evals,
+        table_coset_evals,
+        Ln_coset_evals,
+    };
+    Ok((prover_params, verifier_params))
+}
+
+/// Values and openings of the prover in the lookup argument
+#[allow(non_snake_case)]
+pub(crate) struct LookupProverWitness<PCS: PolyComScheme> {
+    pub(crate) challenges: LookupChallenges<PCS::Field>,
+    // compressed queries and table over H
+    f_values: Vec<PCS::Field>,
+    t_values: Vec<PCS::Field>,
+    h1_values: Vec<PCS::Field>,
+    h2_values: Vec<PCS::Field>,
+    pub(crate) C_h1: PCS::Commitment,
+    pub(crate) C_h2: PCS::Commitment,
+    O_h1: PCS::Opening,
+    O_h2: PCS::Opening,
+}
+
+/// Step 2' of the prover: samples zeta, sorts the compressed queries of the gates by the
+/// compressed table, commits to the hidden polynomials h1 and h2, then samples epsilon and theta.
+/// Returns ProofErrorInvalidWitness if a query of `extended_witness` is not an entry of the table.
+#[allow(non_snake_case)]
+pub(crate) fn prover_lookup_sorted<
+    T: PlonkTranscript,
+    R: CryptoRng + RngCore,
+    PCS: PolyComScheme,
+>(
+    prng: &mut R,
+    transcript: &mut T,
+    pcs: &PCS,
+    params: &ProverParams<PCS>,
+    lookup_params: &LookupProverParams<PCS>,
+    extended_witness: &[PCS::Field],
+) -> Result<LookupProverWitness<PCS>> {
+    let n = params.verifier_params.cs_size;
+    let root = &params.verifier_params.root;
+    let zeta: PCS::Field = transcript_get_plonk_challenge_zeta(transcript, n);
+
+    let t_values: Vec<PCS::Field> = (0..n)
+        .map(|i| {
+            compress(
+                &lookup_params.table_values[0][i],
+                &lookup_params.table_values[1][i],
+                &lookup_params.table_values[2][i],
+                &zeta,
+            )
+        })
+        .collect();
+    let default_entry = t_values[0];
+    let f_values: Vec<PCS::Field> = (0..n)
+        .map(|i| {
+            if lookup_params.q_lookup_values[i] == PCS::Field::zero() {
+                default_entry
+            } else {
+                compress(
+                    &extended_witness[i],
+                    &extended_witness[n + i],
+                    &lookup_params.q_table_values[i],
+                    &zeta,
+                )
+            }
+        })
+        .collect();
+
+    // sort the queries by the table: count the queries of each entry, at its first position
+    let mut positions = HashMap::new();
+    for (j, t) in t_values.iter().enumerate() {
+        positions.entry(t.to_bytes()).or_insert(j);
+    }
+    let mut counts = vec![0usize; n];
+    for f in f_values[..n - 1].iter() {
+        let j = positions
+            .get(&f.to_bytes())
+            .c(d!(PlonkError::ProofErrorInvalidWitness))?;
+        counts[*j] += 1;
+    }
+    let mut sorted = Vec::with_capacity(2 * n - 1);
+    for (t, count) in t_values.iter().zip(counts.iter()) {
+        sorted.extend(std::iter::repeat(*t).take(count + 1));
+    }
+    let h1_values = sorted[..n].to_vec();
+    let h2_values = sorted[n - 1..].to_vec();
+
+    let mut h1 = FpPolynomial::ffti(root, &h1_values);
+    hide_polynomial(prng, &mut h1, 2, n);
+    let (C_h1, O_h1) = pcs.commit(h1).c(d!(PlonkError::CommitmentError))?;
+    let mut h2 = FpPolynomial::ffti(root, &h2_values);
+    hide_polynomial(prng, &mut h2, 2, n);
+    let (C_h2, O_h2) = pcs.commit(h2).c(d!(PlonkError::CommitmentError))?;
+    transcript.append_commitment::<PCS::Commitment>(&C_h1);
+    transcript.append_commitment::<PCS::Commitment>(&C_h2);
+
+    let epsilon = transcript_get_plonk_challenge_epsilon(transcript, n);
+    let theta = transcript_get_plonk_challenge_theta(transcript, n);
+    Ok(LookupProverWitness {
+        challenges: LookupChallenges {
+            zeta,
+            epsilon,
+            theta,
+        },
+        f_values,
+        t_values,
+        h1_values,
+        h2_values,
+        C_h1,
+        C_h2,
+        O_h1,
+        O_h2,
+    })
+}
+
+/// Step 3' of the prover: interpolates the grand product polynomial Z, hides it and commits.
+/// The caller appends the commitment to the transcript after the commitment to Sigma.
+#[allow(non_snake_case)]
+pub(crate) fn prover_lookup_grand_product<R: CryptoRng + RngCore, PCS: PolyComScheme>(
+    prng: &mut R,
+    pcs: &PCS,
+    params: &ProverParams<PCS>,
+    witness: &LookupProverWitness<PCS>,
+) -> Result<(PCS::Commitment, PCS::Opening)> {
+    let n = params.verifier_params.cs_size;
+    let epsilon = &witness.challenges.epsilon;
+    let theta = &witness.challenges.theta;
+    let one_plus_epsilon = PCS::Field::one().add(epsilon);
+    let theta_one_plus_epsilon = theta.mul(&one_plus_epsilon);
+    let pair = |values: &[PCS::Field], i: usize| {
+        theta_one_plus_epsilon
+            .add(&values[i])
+            .add(&epsilon.mul(&values[i + 1]))
+    };
+
+    let mut Z_values = Vec::with_capacity(n);
+    let mut prev = PCS::Field::one();
+    Z_values.push(prev);
+    for i in 0..n - 1 {
+        let num = one_plus_epsilon
+            .mul(&theta.add(&witness.f_values[i]))
+            .mul(&pair(&witness.t_values, i));
+        let den = pair(&witness.h1_values, i).mul(&pair(&witness.h2_values, i));
+        let den_inv = den.inv().c(d!(PlonkError::DivisionByZero))?;
+        prev.mul_assign(&num.mul(&den_inv));
+        Z_values.push(prev);
+    }
+    let mut Z = FpPolynomial::ffti(&params.verifier_params.root, &Z_values);
+    hide_polynomial(prng, &mut Z, 2, n);
+    pcs.commit(Z).c(d!(PlonkError::CommitmentError))
+}
+
+/// The lookup argument of a proof, from which the lookup terms of the quotient polynomial are
+/// computed: the parameters, the witness of step 2' and the opening of Z(X) of step 3'
+#[allow(non_snake_case)]
+pub(crate) struct LookupQuotientInputs<'a, PCS: PolyComScheme> {
+    pub(crate) params: &'a LookupProverParams<PCS>,
+    pub(crate) witness: &'a LookupProverWitness<PCS>,
+    pub(crate) O_Z: &'a PCS::Opening,
+}
+
+impl<'a, PCS: PolyComScheme> LookupQuotientInputs<'a, PCS> {
+    /// Returns the polynomials h1(X), h2(X) and Z(X)
+    #[allow(non_snake_case)]
+    pub(crate) fn sorted_and_Z_polys(&self, pcs: &PCS) -> [FpPolynomial<PCS::Field>; 3] {
+        [
+            pcs.polynomial_from_opening_ref(&self.witness.O_h1),
+            pcs.polynomial_from_opening_ref(&self.witness.O_h2),
+            pcs.polynomial_from_opening_ref(self.O_Z),
+        ]
+    }
+
+    /// Returns the compressed first entry of the table
+    pub(crate) fn default_entry(&self) -> &PCS::Field {
+        &self.witness.t_values[0]
+    }
+}
+
+/// Evaluations over a domain of the polynomials of the lookup terms of P(X), see
+/// lookup_terms_evals
+#[allow(non_snake_case)]
+pub(crate) struct LookupDomainEvals<'a, F> {
+    pub(crate) points: &'a [F],
+    pub(crate) w0: &'a [F],
+    pub(crate) w1: &'a [F],
+    pub(crate) q_lookup: &'a [F],
+    pub(crate) q_table: &'a [F],
+    pub(crate) table: [&'a [F]; 3],
+    pub(crate) h1: &'a [F],
+    pub(crate) h2: &'a [F],
+    pub(crate) Z: &'a [F],
+    pub(crate) L1: &'a [F],
+    pub(crate) Ln: &'a [F],
+}
+
+/// Evaluations of the lookup terms of P(X) over a domain of `evals.points.len()` points such that
+/// the point (i + `shift`) % len of the domain is g times its point i, e.g. the domain of the
+/// quotient polynomial or one of its sub-cosets (see plonk::low_memory). `last` is g^{n-1}.
+pub(crate) fn lookup_terms_evals<F: Scalar>(
+    evals: &LookupDomainEvals<F>,
+    shift: usize,
+    default_entry: &F,
+    challenges: &LookupChallenges<F>,
+    alpha: &F,
+    last: &F,
+) -> Vec<F> {
+    let len = evals.points.len();
+    let t_evals: Vec<F> = (0..len)
+        .map(|point| {
+            compress(
+                &evals.table[0][point],
+                &evals.table[1][point],
+                &evals.table[2][point],
+                &challenges.zeta,
+            )
+        })
+        .collect();
+
+    let alphas = lookup_alphas(alpha);
+    (0..len)
+        .map(|point| {
+            let g_point = (point + shift) % len;
+            let point_evals = LookupEvals {
+                w0: evals.w0[point],
+                w1: evals.w1[point],
+                q_lookup: evals.q_lookup[point],
+                q_table: evals.q_table[point],
+                t: t_evals[point],
+                t_g: t_evals[g_point],
+                h1: evals.h1[point],
+                h1_g: evals.h1[g_point],
+                h2: evals.h2[point],
+                h2_g: evals.h2[g_point],
+                Z: evals.Z[point],
+                Z_g: evals.Z[g_point],
+            };
+            lookup_terms(
+                &point_evals,
+                default_entry,
+                challenges,
+                &alphas,
+                &evals.points[point].sub(last),
+                &evals.L1[point],
+                &evals.Ln[point],
+            )
+        })
+        .collect()
+}
+
+/// Evaluations of the lookup terms of P(X) over the domain of the quotient polynomial, for the
+/// (hidden) witness polynomials `witness_polys`. Returns FuncParamsError if the parameters have
+/// no evaluations over the domain, e.g. those of plonk::low_memory.
+#[allow(non_snake_case)]
+pub(crate) fn lookup_terms_coset_evals<PCS: PolyComScheme>(
+    pcs: &PCS,
+    params: &ProverParams<PCS>,
+    lookup: &LookupQuotientInputs<PCS>,
+    witness_polys: &[FpPolynomial<PCS::Field>],
+    alpha: &PCS::Field,
+) -> Result<Vec<PCS::Field>> {
+    let n = params.verifier_params.cs_size;
+    let m = params.coset_quot.len();
+    let lookup_params = lookup.params;
+    if lookup_params.Ln_coset_evals.len() != m {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+    let root_m = &params.root_m;
+    let k1 = &params.verifier_params.k[1];
+    let w0_coset_evals = witness_polys[0].coset_fft_with_unity_root(root_m, m, k1);
+    let w1_coset_evals = witness_polys[1].coset_fft_with_unity_root(root_m, m, k1);
+    let [h1_coset_evals, h2_coset_evals, Z_coset_evals] = lookup
+        .sorted_and_Z_polys(pcs)
+        .map(|poly| poly.coset_fft_with_unity_root(root_m, m, k1));
+    let evals = LookupDomainEvals {
+        points: &params.coset_quot,
+        w0: &w0_coset_evals,
+        w1: &w1_coset_evals,
+        q_lookup: &lookup_params.q_lookup_coset_evals,
+        q_table: &lookup_params.q_table_coset_evals,
+        table: [
+            &lookup_params.table_coset_evals[0],
+            &lookup_params.table_coset_evals[1],
+            &lookup_params.table_coset_evals[2],
+        ],
+        h1: &h1_coset_evals,
+        h2: &h2_coset_evals,
+        Z: &Z_coset_evals,
+        L1: &params.L1_coset_evals,
+        Ln: &lookup_params.Ln_coset_evals,
+    };
+    Ok(lookup_terms_evals(
+        &evals,
+        m / n,
+        lookup.default_entry(),
+        &lookup.witness.challenges,
+        alpha,
+        &params.group[n - 1],
+    ))
+}
+
+/// Step 8' of the prover: evaluates the lookup polynomials at `beta` and `g_beta`. Returns the
+/// lookup part of the proof and the opening of t(X), see lookup_openings
+#[allow(non_snake_case)]
+#[allow(clippy::type_complexity)]
+pub(crate) fn prover_lookup_evals<PCS: PolyComScheme>(
+    pcs: &PCS,
+    lookup_params: &LookupProverParams<PCS>,
+    witness: &LookupProverWitness<PCS>,
+    C_Z: PCS::Commitment,
+    O_Z: &PCS::Opening,
+    beta: &PCS::Field,
+    g_beta: &PCS::Field,
+) -> (PlonkLookupProof<PCS::Commitment, PCS::Field>, PCS::Opening) {
+    let O_t = combine_table(&lookup_params.table, &witness.challenges.zeta);
+    let proof = PlonkLookupProof {
+        C_h1: witness.C_h1.clone(),
+        C_h2: witness.C_h2.clone(),
+        C_Z,
+        q_lookup_eval_beta: pcs.eval_opening(&lookup_params.q_lookup, beta),
+        q_table_eval_beta: pcs.eval_opening(&lookup_params.q_table, beta),
+        t_eval_beta: pcs.eval_opening(&O_t, beta),
+        h1_eval_beta: pcs.eval_opening(&witness.O_h1, beta),
+        h2_eval_beta: pcs.eval_opening(&witness.O_h2, beta),
+        Z_eval_beta: pcs.eval_opening(O_Z, beta),
+        t_eval_g_beta: pcs.eval_opening(&O_t, g_beta),
+        h1_eval_g_beta: pcs.eval_opening(&witness.O_h1, g_beta),
+        h2_eval_g_beta: pcs.eval_opening(&witness.O_h2, g_beta),
+        Z_eval_g_beta: pcs.eval_opening(O_Z, g_beta),
+    };
+    (proof, O_t)
+}
+
+/// Openings of the lookup polynomials in the batch evaluation proof, in the order of
+/// PlonkLookupProof::evals
+#[allow(non_snake_case)]
+pub(crate) fn lookup_openings<'a, PCS: PolyComScheme>(
+    lookup_params: &'a LookupProverParams<PCS>,
+    witness: &'a LookupProverWitness<PCS>,
+    O_t: &'a PCS::Opening,
+    O_Z: &'a PCS::Opening,
+) -> Vec<&'a PCS::Opening> {
+    vec![
+        &lookup_params.q_lookup,
+        &lookup_params.q_table,
+        O_t,
+        &witness.O_h1,
+        &witness.O_h2,
+        O_Z,
+        O_t,
+        &witness.O_h1,
+        &witness.O_h2,
+        O_Z,
+    ]
+}
+
+/// Commitments to the lookup polynomials in the batch evaluation proof, in the order of
+/// PlonkLookupProof::evals
+pub(crate) fn lookup_commitments<PCS: PolyComScheme>(
+    lookup_params: &LookupVerifierParams<PCS>,
+    proof: &PlonkLookupProof<PCS::Commitment, PCS::Field>,
+    challenges: &LookupChallenges<PCS::Field>,
+) -> Vec<PCS::Commitment> {
+    let C_t = combine_table(&lookup_params.table, &challenges.zeta);
+    vec![
+        lookup_params.q_lookup.clone(),
+        lookup_params.q_table.clone(),
+        C_t.clone(),
+        proof.C_h1.clone(),
+        proof.C_h2.clone(),
+        proof.C_Z.clone(),
+        C_t,
+        proof.C_h1.clone(),
+        proof.C_h2.clone(),
+        proof.C_Z.clone(),
+    ]
+}
+
+/// Points of the lookup evaluations in the batch evaluation proof, see PlonkLookupProof::evals
+pub(crate) fn lookup_eval_points<F: Copy>(beta: &F, g_beta: &F) -> Vec<F> {
+    let mut points = vec![*beta; N_LOOKUP_EVALS_BETA];
+    points.extend(vec![*g_beta; N_LOOKUP_EVALS_G_BETA]);
+    points
+}
+
+/// Replays the lookup challenges of the verifier (step 2' of the prover), right after the
+/// challenges gamma and delta
+pub(crate) fn verifier_lookup_challenges<T: PlonkTranscript, C: ToBytes, F: Scalar>(
+    transcript: &mut T,
+    proof: &PlonkLookupProof<C, F>,
+    n: usize,
+) -> LookupChallenges<F> {
+    let zeta = transcript_get_plonk_challenge_zeta(transcript, n);
+    transcript.append_commitment::<C>(&proof.C_h1);
+    transcript.append_commitment::<C>(&proof.C_h2);
+    let epsilon = transcript_get_plonk_challenge_epsilon(transcript, n);
+    let theta = transcript_get_plonk_challenge_theta(transcript, n);
+    LookupChallenges {
+        zeta,
+        epsilon,
+        theta,
+    }
+}
+
+/// Computes the lookup terms of P(beta) from the evaluations of the proof, where `w0_eval_beta`
+/// and `w1_eval_beta` are the evaluations of the first two witness polynomials at beta, and
+/// `root` generates the group H of size `n`
+#[allow(non_snake_case, clippy::too_many_arguments)]
+pub(crate) fn lookup_terms_eval_beta<C, F: Scalar>(
+    lookup_params: &PlonkLookupVerifierParams<C, F>,
+    proof: &PlonkLookupProof<C, F>,
+    challenges: &LookupChallenges<F>,
+    alpha: &F,
+    beta: &F,
+    n: usize,
+    root: &F,
+    w0_eval_beta: &F,
+    w1_eval_beta: &F,
+) -> Result<F> {
+    if lookup_params.default_entry.len() != 3 {
+        return Err(eg!(PlonkError::VerificationError));
+    }
+    let one = F::one();
+    let Z_H_eval_beta = beta.pow_u64(n as u64).sub(&one);
+    let last = root.pow_u64(n as u64 - 1);
+    let beta_minus_last = beta.sub(&last);
+    let L1 = Z_H_eval_beta.mul(&beta.sub(&one).inv().c(d!(PlonkError::DivisionByZero))?);
+    let Ln =
+        Z_H_eval_beta.mul(&beta_minus_last.inv().c(d!(PlonkError::DivisionByZero))?);
+    let default_entry = compress(
+        &lookup_params.default_entry[0],
+        &lookup_params.default_entry[1],
+        &lookup_params.default_entry[2],
+        &challenges.zeta,
+    );
+    let evals = LookupEvals {
+        w0: *w0_eval_beta,
+        w1: *w1_eval_beta,
+        q_lookup: proof.q_lookup_eval_beta,
+        q_table: proof.q_table_eval_beta,
+        t: proof.t_eval_beta,
+        t_g: proof.t_eval_g_beta,
+        h1: proof.h1_eval_beta,
+        h1_g: proof.h1_eval_g_beta,
+        h2: proof.h2_eval_beta,
+        h2_g: proof.h2_eval_g_beta,
+        Z: proof.Z_eval_beta,
+        Z_g: proof.Z_eval_g_beta,
+    };
+    Ok(lookup_terms(
+        &evals,
+        &default_entry,
+        challenges,
+        &lookup_alphas(alpha),
+        &beta_minus_last,
+        &L1,
+        &Ln,
+    ))
+}
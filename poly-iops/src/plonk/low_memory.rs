@@ -0,0 +1,649 @@
+// This file implements a PLONK prover for large circuits whose memory does not grow with the
+// size of the domain of the quotient polynomial times the number of preprocessed polynomials.
+// The prover of plonk::protocol holds the evaluations of every selector, permutation, witness
+// and identity permutation polynomial over the coset D = k_1 * <root_m> of size m = factor * n,
+// e.g. several gigabytes for 2^20 gates. The quotient polynomial is instead computed in `factor`
+// chunks, one per sub-coset C_j = c_j * H of D, where H is the group of order n and
+// c_j = k_1 * root_m^j. Since the points of C_j are the points j + factor * i of D, and
+// g * x is in C_j for x in C_j, every term of Q(x) for x in C_j is computed from evaluations
+// over C_j only:
+// - the evaluations of the selectors, the permutations and L1 over C_j, and for the lookup gates
+//   those of q_lookup, q_table, the columns of the table and L_n, are precomputed by
+//   preprocess_prover_low_memory and read from a CosetEvalStorage, e.g. a file read with
+//   positioned reads, for which the page cache of the OS plays the role of a memory mapping;
+// - the evaluations of the witness polynomials, Sigma and IO over C_j, and those of h1, h2 and
+//   Z of the lookup argument, are computed by an FFT of size n of the coefficients of
+//   f(c_j * X) folded modulo X^n - 1;
+// - Z_H(x) = c_j^n - 1 is constant over C_j, and the identity permutations are k_w * x.
+// With the `parallel` feature, as many chunks as the memory cap of prover_low_memory allows are
+// computed at once. The proof is the proof of plonk::prover::prover with the same parameters
+// and randomness, e.g. for the circuits of anonymous transfers, whose range checks are lookup
+// gates.
+
+use crate::backend::CpuBackend;
+use crate::commitments::pcs::PolyComScheme;
+use crate::plonk::errors::PlonkError;
+use crate::plonk::lookup::{
+    lookup_terms_evals, LookupDomainEvals, LookupQuotientInputs,
+};
+use crate::plonk::plonk_helpers::{quotient_eval, PlonkChallenges, QuotientPointEvals};
+use crate::plonk::plonk_setup::{
+    preprocess_prover_with_coset_evals, ConstraintSystem, CosetShifts, ProverParams,
+};
+use crate::plonk::protocol::prover::{
+    extended_perms_values, prover_with_quotient, PlonkPf,
+};
+use crate::plonk::transcript::PlonkTranscript;
+use crate::polynomials::field_polynomial::FpPolynomial;
+use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use ruc::*;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// The storage of the evaluations of the selectors, the permutations and L1 over the sub-cosets
+/// of the domain of the quotient polynomial, one chunk per sub-coset, see low_memory.rs
+pub trait CosetEvalStorage<F: Scalar> {
+    /// Stores the evaluations `evals` of the chunk `chunk`
+    fn write_chunk(&mut self, chunk: usize, evals: &[F]) -> Result<()>;
+
+    /// Returns the evaluations of the chunk `chunk`
+    fn read_chunk(&self, chunk: usize) -> Result<Vec<F>>;
+}
+
+/// Chunks stored in memory, e.g. for the tests or for circuits whose chunks fit in memory
+pub struct MemoryCosetEvalStorage<F> {
+    chunks: Vec<Vec<F>>,
+}
+
+impl<F> MemoryCosetEvalStorage<F> {
+    pub fn new() -> MemoryCosetEvalStorage<F> {
+        MemoryCosetEvalStorage { chunks: vec![] }
+    }
+}
+
+impl<F> Default for MemoryCosetEvalStorage<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Scalar> CosetEvalStorage<F> for MemoryCosetEvalStorage<F> {
+    fn write_chunk(&mut self, chunk: usize, evals: &[F]) -> Result<()> {
+        if chunk >= self.chunks.len() {
+            self.chunks.resize(chunk + 1, vec![]);
+        }
+        self.chunks[chunk] = evals.to_vec();
+        Ok(())
+    }
+
+    fn read_chunk(&self, chunk: usize) -> Result<Vec<F>> {
+        self.chunks
+            .get(chunk)
+            .cloned()
+            .c(d!(PlonkError::FuncParamsError))
+    }
+}
+
+/// Chunks stored in a file: the number of elements of a chunk as a little-endian u64, then the
+/// chunks in order, each element in the Scalar::to_bytes encoding. A chunk is read by a
+/// positioned read of its bytes only.
+pub struct FileCosetEvalStorage {
+    path: PathBuf,
+    chunk_len: usize,
+}
+
+// Size of the header of the file of FileCosetEvalStorage
+const FILE_HEADER_SIZE: u64 = 8;
+
+impl FileCosetEvalStorage {
+    /// Creates the file `path` of new storage, truncating an existing file
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<FileCosetEvalStorage> {
+        File::create(path.as_ref()).c(d!(PlonkError::SetupError))?;
+        Ok(FileCosetEvalStorage {
+            path: path.as_ref().to_path_buf(),
+            chunk_len: 0,
+        })
+    }
+
+    /// Opens the storage written in the file `path` by a previous preprocessing
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<FileCosetEvalStorage> {
+        let mut file = File::open(path.as_ref()).c(d!(PlonkError::SetupError))?;
+        let mut header = [0u8; FILE_HEADER_SIZE as usize];
+        file.read_exact(&mut header).c(d!(PlonkError::SetupError))?;
+        Ok(FileCosetEvalStorage {
+            path: path.as_ref().to_path_buf(),
+            chunk_len: u64::from_le_bytes(header) as usize,
+        })
+    }
+
+    // Position of the chunk `chunk` in the file, for elements of `elem_len` bytes
+    fn chunk_position(&self, chunk: usize, elem_len: usize) -> u64 {
+        FILE_HEADER_SIZE + (chunk * self.chunk_len * elem_len) as u64
+    }
+}
+
+impl<F: Scalar> CosetEvalStorage<F> for FileCosetEvalStorage {
+    fn write_chunk(&mut self, chunk: usize, evals: &[F]) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .c(d!(PlonkError::SetupError))?;
+        if self.chunk_len == 0 {
+            self.chunk_len = evals.len();
+            file.write_all(&(evals.len() as u64).to_le_bytes())
+                .c(d!(PlonkError::SetupError))?;
+        }
+        if evals.len() != self.chunk_len {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
+        let mut bytes = Vec::with_capacity(evals.len() * F::bytes_len());
+        for eval in evals.iter() {
+            bytes.extend(eval.to_bytes());
+        }
+        file.seek(SeekFrom::Start(self.chunk_position(chunk, F::bytes_len())))
+            .c(d!(PlonkError::SetupError))?;
+        file.write_all(&bytes).c(d!(PlonkError::SetupError))
+    }
+
+    fn read_chunk(&self, chunk: usize) -> Result<Vec<F>> {
+        let elem_len = F::bytes_len();
+        let mut file = File::open(&self.path).c(d!(PlonkError::SetupError))?;
+        file.seek(SeekFrom::Start(self.chunk_position(chunk, elem_len)))
+            .c(d!(PlonkError::SetupError))?;
+        let mut bytes = vec![0u8; self.chunk_len * elem_len];
+        file.read_exact(&mut bytes).c(d!(PlonkError::SetupError))?;
+        bytes
+            .chunks(elem_len)
+            .map(|elem| F::from_bytes(elem).c(d!(PlonkError::SetupError)))
+            .collect()
+    }
+}
+
+// Number of polynomials of the lookup argument evaluated by preprocess_prover_low_memory:
+// q_lookup, q_table, the three columns of the table and L_n
+const N_STORED_LOOKUP_POLYS: usize = 6;
+
+/// Returns the number of bytes of the evaluations held by prover_low_memory to compute a chunk
+/// of the quotient polynomial of `cs`: the chunk of the storage, the points, the witness
+/// polynomials, Sigma and IO over the sub-coset, and the quotient polynomial over the
+/// sub-coset, plus for the lookup gates h1, h2, Z, the compressed table and the lookup terms
+/// over the sub-coset.
+pub fn low_memory_chunk_bytes<CS: ConstraintSystem>(cs: &CS) -> usize {
+    let mut n_evals = cs.num_selectors() + 2 * cs.n_wires_per_gate() + 5;
+    if cs.lookup_columns().is_some() {
+        n_evals += N_STORED_LOOKUP_POLYS + 5;
+    }
+    n_evals * cs.size() * CS::Field::bytes_len()
+}
+
+/// Same as plonk_setup::preprocess_prover, with the evaluations over the domain of the quotient
+/// polynomial written in chunks to `storage` for prover_low_memory instead of being part of
+/// the prover parameters.
+pub fn preprocess_prover_low_memory<
+    PCS: PolyComScheme,
+    CS: ConstraintSystem<Field = PCS::Field>,
+    S: CosetEvalStorage<PCS::Field>,
+>(
+    cs: &CS,
+    pcs: &PCS,
+    prg_seed: [u8; 32],
+    storage: &mut S,
+) -> Result<ProverParams<PCS>> {
+    let params = preprocess_prover_with_coset_evals(
+        cs,
+        pcs,
+        &CosetShifts::Seeded(prg_seed),
+        false,
+    )
+    .c(d!())?;
+    let n = cs.size();
+    let factor = cs.quot_eval_dom_size() / n;
+    let root = &params.verifier_params.root;
+    let mut polys: Vec<FpPolynomial<PCS::Field>> = params
+        .selectors
+        .iter()
+        .chain(params.extended_permutations.iter())
+        .map(|open| pcs.polynomial_from_opening_ref(open))
+        .collect();
+    polys.push(params.L1.clone());
+    if let Some(lookup) = &params.lookup {
+        polys.extend(
+            [&lookup.q_lookup, &lookup.q_table]
+                .into_iter()
+                .chain(lookup.table.iter())
+                .map(|open| pcs.polynomial_from_opening_ref(open)),
+        );
+        polys.push(FpPolynomial::from_zeroes(&params.group[..n - 1]));
+    }
+    for chunk in 0..factor {
+        let offset = &params.coset_quot[chunk];
+        let mut evals = Vec::with_capacity(polys.len() * n);
+        for poly in polys.iter() {
+            evals.extend(sub_coset_evals(poly, root, n, offset));
+        }
+        storage.write_chunk(chunk, &evals).c(d!())?;
+    }
+    Ok(params)
+}
+
+/// Same as plonk::prover::prover for the parameters `params` and the chunks `storage` computed
+/// by preprocess_prover_low_memory, with at most `max_memory` bytes of evaluations over the
+/// domain of the quotient polynomial at once, on top of the polynomials of the proof and of the
+/// evaluations of the quotient polynomial. Returns an error if `max_memory` is lower than
+/// low_memory_chunk_bytes.
+#[allow(clippy::too_many_arguments)]
+pub fn prover_low_memory<
+    T: PlonkTranscript,
+    R: CryptoRng + RngCore,
+    PCS: PolyComScheme,
+    CS: ConstraintSystem<Field = PCS::Field>,
+    S: CosetEvalStorage<PCS::Field>,
+>(
+    prng: &mut R,
+    transcript: &mut T,
+    pcs: &PCS,
+    cs: &CS,
+    params: &ProverParams<PCS>,
+    storage: &S,
+    max_memory: usize,
+    witness: &[PCS::Field],
+) -> Result<PlonkPf<PCS>> {
+    let chunk_bytes = low_memory_chunk_bytes(cs);
+    if chunk_bytes > max_memory {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+    let perms_values = extended_perms_values(cs, params).c(d!())?;
+    prover_with_quotient(
+        prng,
+        transcript,
+        pcs,
+        cs,
+        params,
+        &perms_values,
+        witness,
+        &CpuBackend,
+        |witness_polys, Sigma, challenges, IO, lookup| {
+            quotient_polynomial_in_chunks::<PCS, CS, S>(
+                pcs,
+                cs,
+                params,
+                storage,
+                max_memory / chunk_bytes,
+                witness_polys,
+                Sigma,
+                challenges,
+                IO,
+                lookup,
+            )
+        },
+    )
+    .map(|(proof, _)| proof)
+}
+
+// Computes the quotient polynomial of plonk_helpers::Quotient_polynomial over the sub-cosets,
+// `batch_size` chunks at a time, with the lookup terms of lookup::lookup_terms_coset_evals for
+// the lookup argument `lookup`.
+#[allow(clippy::too_many_arguments)]
+fn quotient_polynomial_in_chunks<
+    PCS: PolyComScheme,
+    CS: ConstraintSystem<Field = PCS::Field>,
+    S: CosetEvalStorage<PCS::Field>,
+>(
+    pcs: &PCS,
+    cs: &CS,
+    params: &ProverParams<PCS>,
+    storage: &S,
+    batch_size: usize,
+    witness_polys: &[FpPolynomial<PCS::Field>],
+    Sigma: &FpPolynomial<PCS::Field>,
+    challenges: &PlonkChallenges<PCS::Field>,
+    IO: &FpPolynomial<PCS::Field>,
+    lookup: Option<LookupQuotientInputs<PCS>>,
+) -> Result<FpPolynomial<PCS::Field>> {
+    let n = cs.size();
+    let m = cs.quot_eval_dom_size();
+    let factor = m / n;
+    if n * factor != m || params.coset_quot.len() != m {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+    let n_selectors = cs.num_selectors();
+    let n_wires = cs.n_wires_per_gate();
+    let mut chunk_len = (n_selectors + n_wires + 1) * n;
+    if lookup.is_some() {
+        chunk_len += N_STORED_LOOKUP_POLYS * n;
+    }
+
+    // The closure borrows the vectors of the prover parameters and the values of the lookup
+    // argument it uses, as the parameters themselves are not Sync for every commitment scheme.
+    let root = &params.verifier_params.root;
+    let k = &params.verifier_params.k;
+    let group = &params.group;
+    let coset_quot = &params.coset_quot;
+    let last = &group[n - 1];
+    let alpha = challenges.get_alpha().c(d!())?;
+    let lookup_values = lookup.map(|lookup| {
+        (
+            lookup.sorted_and_Z_polys(pcs),
+            *lookup.default_entry(),
+            &lookup.witness.challenges,
+        )
+    });
+    let chunk_quot_evals = |chunk: usize,
+                            stored: &[PCS::Field]|
+     -> Option<Vec<PCS::Field>> {
+        let offset = &coset_quot[chunk];
+        let wires_evals: Vec<Vec<PCS::Field>> = witness_polys
+            .iter()
+            .map(|poly| sub_coset_evals(poly, root, n, offset))
+            .collect();
+        let IO_evals = sub_coset_evals(IO, root, n, offset);
+        let Sigma_evals = sub_coset_evals(Sigma, root, n, offset);
+        let (selectors_evals, stored) = stored.split_at(n_selectors * n);
+        let (perms_evals, stored) = stored.split_at(n_wires * n);
+        let (L1_evals, lookup_stored) = stored.split_at(n);
+        let selectors_evals: Vec<&[PCS::Field]> = selectors_evals.chunks(n).collect();
+        let perms_evals: Vec<&[PCS::Field]> = perms_evals.chunks(n).collect();
+        let points: Vec<PCS::Field> = group.iter().map(|x| offset.mul(x)).collect();
+        let lookup_terms =
+            lookup_values
+                .as_ref()
+                .map(|(polys, default_entry, lookup_challenges)| {
+                    let stored: Vec<&[PCS::Field]> = lookup_stored.chunks(n).collect();
+                    // h1, h2 and Z
+                    let polys_evals: Vec<Vec<PCS::Field>> = polys
+                        .iter()
+                        .map(|poly| sub_coset_evals(poly, root, n, offset))
+                        .collect();
+                    let evals = LookupDomainEvals {
+                        points: &points,
+                        w0: &wires_evals[0],
+                        w1: &wires_evals[1],
+                        q_lookup: stored[0],
+                        q_table: stored[1],
+                        table: [stored[2], stored[3], stored[4]],
+                        h1: &polys_evals[0],
+                        h2: &polys_evals[1],
+                        Z: &polys_evals[2],
+                        L1: L1_evals,
+                        Ln: stored[5],
+                    };
+                    lookup_terms_evals(
+                        &evals,
+                        1,
+                        default_entry,
+                        lookup_challenges,
+                        alpha,
+                        last,
+                    )
+                });
+        // Z_H(x) = offset^n - 1 for every x in the sub-coset
+        let Z_H_inv = offset
+            .pow_u64(n as u64)
+            .sub(&PCS::Field::one())
+            .inv()
+            .ok()?;
+        (0..n)
+            .map(|i| {
+                let identity_perms: Vec<PCS::Field> =
+                    k.iter().map(|k_w| k_w.mul(&points[i])).collect();
+                let evals = QuotientPointEvals {
+                    wires: wires_evals.iter().map(|evals| &evals[i]).collect(),
+                    selectors: selectors_evals.iter().map(|evals| &evals[i]).collect(),
+                    IO: &IO_evals[i],
+                    identity_perms: identity_perms.iter().collect(),
+                    perms: perms_evals.iter().map(|evals| &evals[i]).collect(),
+                    Sigma: &Sigma_evals[i],
+                    Sigma_next: &Sigma_evals[(i + 1) % n],
+                    L1: &L1_evals[i],
+                    Z_H_inv: &Z_H_inv,
+                    lookup_term: lookup_terms.as_ref().map(|terms| &terms[i]),
+                };
+                quotient_eval(cs, challenges, &evals)
+            })
+            .collect()
+    };
+
+    // The point i of the chunk j is the point j + factor * i of the domain.
+    let mut quot_coset_evals = vec![PCS::Field::zero(); m];
+    let chunks: Vec<usize> = (0..factor).collect();
+    for batch in chunks.chunks(batch_size) {
+        let stored: Vec<Vec<PCS::Field>> = batch
+            .iter()
+            .map(|chunk| storage.read_chunk(*chunk))
+            .collect::<Result<_>>()
+            .c(d!())?;
+        if stored.iter().any(|evals| evals.len() != chunk_len) {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
+        #[cfg(feature = "parallel")]
+        let iter = batch.par_iter().zip(stored.par_iter());
+        #[cfg(not(feature = "parallel"))]
+        let iter = batch.iter().zip(stored.iter());
+        let batch_quot_evals: Vec<Vec<PCS::Field>> = iter
+            .map(|(chunk, stored)| chunk_quot_evals(*chunk, stored))
+            .collect::<Option<_>>()
+            .c(d!(PlonkError::ProofError))?;
+        for (chunk, evals) in batch.iter().zip(batch_quot_evals) {
+            for (i, eval) in evals.into_iter().enumerate() {
+                quot_coset_evals[chunk + factor * i] = eval;
+            }
+        }
+    }
+
+    let k_inv = k[1].inv().c(d!(PlonkError::DivisionByZero))?;
+    Ok(FpPolynomial::coset_ffti(
+        &params.root_m,
+        &quot_coset_evals,
+        &k_inv,
+    ))
+}
+
+// Returns the evaluations of `poly` over offset * H, where H is the group of order n generated
+// by `root`: the FFT of the coefficients of poly(offset * X) folded modulo X^n - 1, as the
+// polynomials of the proof have degree larger than n
+fn sub_coset_evals<F: Scalar>(
+    poly: &FpPolynomial<F>,
+    root: &F,
+    n: usize,
+    offset: &F,
+) -> Vec<F> {
+    let mut folded = vec![F::zero(); n];
+    let mut power = F::one();
+    for (i, coef) in poly.get_coefs_ref().iter().enumerate() {
+        folded[i % n].add_assign(&coef.mul(&power));
+        power.mul_assign(offset);
+    }
+    FpPolynomial::from_coefs(folded).fft_with_unity_root(root, n)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
+    use crate::plonk::low_memory::{
+        low_memory_chunk_bytes, preprocess_prover_low_memory, prover_low_memory,
+        sub_coset_evals, CosetEvalStorage, FileCosetEvalStorage, MemoryCosetEvalStorage,
+    };
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, ConstraintSystem,
+    };
+    use crate::plonk::protocol::prover::{prover, verifier};
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use crate::polynomials::field_polynomial::{
+        primitive_nth_root_of_unity, FpPolynomial,
+    };
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{Scalar, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use std::path::PathBuf;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_sub_coset_evals() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let poly =
+            FpPolynomial::from_coefs((0..11).map(|_| F::random(&mut prng)).collect());
+        let root = primitive_nth_root_of_unity::<F>(4).unwrap();
+        let offset = F::from_u32(7);
+        let evals = sub_coset_evals(&poly, &root, 4, &offset);
+        let mut x = offset;
+        for eval in evals.iter() {
+            assert_eq!(*eval, poly.eval(&x));
+            x.mul_assign(&root);
+        }
+    }
+
+    // a circuit whose range check is made of lookup gates if `lookup`, as in the circuits of
+    // anonymous transfers, and of a binary decomposition otherwise
+    fn test_circuit(lookup: bool) -> (TurboPlonkConstraintSystem<F>, Vec<F>) {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let a = cs.new_variable(F::from_u32(3));
+        let b = cs.new_variable(F::from_u32(5));
+        let c = cs.mul(a, b);
+        let d = cs.add(c, a);
+        if lookup {
+            let _ = cs.range_check_chunks(d, 8);
+        } else {
+            let _ = cs.range_check(d, 8);
+        }
+        cs.prepare_io_variable(d);
+        cs.pad();
+        let witness = cs.get_and_clear_witness();
+        (cs, witness)
+    }
+
+    fn check_low_memory_prover<S: CosetEvalStorage<F>>(storage: &mut S, lookup: bool) {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(100, &mut prng);
+        let (cs, witness) = test_circuit(lookup);
+        assert_eq!(cs.lookup_columns().is_some(), lookup);
+        let online_vars = vec![F::from_u32(18)];
+        let seed = [0u8; 32];
+        let params = preprocess_prover(&cs, &pcs, seed).unwrap();
+        let low_memory_params =
+            preprocess_prover_low_memory(&cs, &pcs, seed, storage).unwrap();
+
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let proof = prover(
+            &mut ChaChaRng::from_seed([2u8; 32]),
+            &mut transcript,
+            &pcs,
+            &cs,
+            &params,
+            &witness,
+        )
+        .unwrap();
+        // one chunk at a time, and all the chunks at once
+        let chunk_bytes = low_memory_chunk_bytes(&cs);
+        for max_memory in [chunk_bytes, 100 * chunk_bytes] {
+            let mut transcript = Transcript::new(b"TestPlonk");
+            let low_memory_proof = prover_low_memory(
+                &mut ChaChaRng::from_seed([2u8; 32]),
+                &mut transcript,
+                &pcs,
+                &cs,
+                &low_memory_params,
+                storage,
+                max_memory,
+                &witness,
+            )
+            .unwrap();
+            assert_eq!(low_memory_proof, proof);
+        }
+
+        let verifier_params = preprocess_verifier(&cs, &pcs, seed).unwrap();
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &online_vars,
+            &proof
+        )
+        .is_ok());
+
+        // a memory cap below the size of a chunk
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(prover_low_memory(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &low_memory_params,
+            storage,
+            chunk_bytes - 1,
+            &witness,
+        )
+        .is_err());
+        // the parameters of the low memory prover lack the evaluations of the prover
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &low_memory_params,
+            &witness
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_low_memory_prover_in_memory() {
+        check_low_memory_prover(&mut MemoryCosetEvalStorage::new(), false);
+    }
+
+    #[test]
+    fn test_low_memory_prover_with_lookup() {
+        check_low_memory_prover(&mut MemoryCosetEvalStorage::new(), true);
+    }
+
+    // a file of the temporary directory, removed on drop even if the test fails
+    struct TempFile(PathBuf);
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_low_memory_prover_with_file() {
+        check_low_memory_prover_with_file(false);
+        check_low_memory_prover_with_file(true);
+    }
+
+    fn check_low_memory_prover_with_file(lookup: bool) {
+        let file = TempFile(std::env::temp_dir().join(format!(
+            "zei_test_low_memory_prover_{}_{:016x}.bin",
+            std::process::id(),
+            rand::random::<u64>()
+        )));
+        let path = file.0.clone();
+        let mut storage = FileCosetEvalStorage::create(&path).unwrap();
+        check_low_memory_prover(&mut storage, lookup);
+        // the chunks are read back from the file by a new storage
+        let storage = FileCosetEvalStorage::open(&path).unwrap();
+        let mut memory_storage = MemoryCosetEvalStorage::new();
+        let (cs, _) = test_circuit(lookup);
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(100, &mut prng);
+        let _ = preprocess_prover_low_memory(&cs, &pcs, [0u8; 32], &mut memory_storage)
+            .unwrap();
+        for chunk in 0..cs.quot_eval_dom_size() / cs.size() {
+            assert_eq!(
+                CosetEvalStorage::<F>::read_chunk(&storage, chunk).unwrap(),
+                memory_storage.read_chunk(chunk).unwrap()
+            );
+        }
+        drop(file);
+        assert!(!path.exists());
+    }
+}
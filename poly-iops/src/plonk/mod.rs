@@ -1,9 +1,24 @@
+pub mod bounded_reader;
+pub mod deterministic;
+pub mod dump;
 pub mod errors;
+pub mod json_export;
+#[allow(non_snake_case)]
+pub mod lookup;
+#[allow(non_snake_case)]
+pub mod low_memory;
+pub mod pc_link;
 #[allow(non_snake_case)]
 pub(crate) mod plonk_helpers;
 #[allow(non_snake_case)]
 pub mod plonk_setup;
 #[allow(non_snake_case)]
+pub mod proof_bytes;
+#[allow(non_snake_case)]
 pub mod protocol;
+pub mod prover_context;
+pub mod solidity;
+pub mod standard_plonk_cs;
 pub mod transcript;
 pub mod turbo_plonk_cs;
+pub mod verifier_key;
@@ -0,0 +1,539 @@
+// This file implements a commit-and-prove link between a wire value of a PLONK proof over a
+// KZG polynomial commitment scheme and the value of an external Pedersen commitment over
+// Ristretto, as used by the Bulletproofs of xfr, without revealing the value. E.g. the amount
+// of a confidential transfer is linked to the amount of an anonymous transfer circuit.
+//
+// Let w be the witness polynomial of the wire, C = g1^{w(s)} its commitment in the PLONK proof
+// and x = root^i the point of the gate i, so that v = w(x). Let V = v * B + rho * B_blinding
+// be the Pedersen commitment. The KZG opening proof of w at x, W = g1^{q(s)} with
+// q = (w - v) / (X - x), would reveal v, and is hidden as W' = W + tau * g1 for a random tau.
+// Then the verifier computes the target group elements
+//    gt = e(g1, g2), gt_x = e(g1, g2^s - x * g2), T = e(C, g2) - e(W', g2^s - x * g2)
+// and T = v * gt - tau * gt_x. The prover proves knowledge of (v, rho, tau) such that
+//    V = v * B + rho * B_blinding and T = v * gt - tau * gt_x
+// with a sigma protocol over Ristretto and the target group, whose response for v is computed
+// over the integers, as the orders of both groups differ:
+//  - the challenge c has 128 bits and the response z_v = r_v + c * v is accepted if it lies in
+//    [2^192, 2^250), with r_v uniform in [0, 2^250), so that z_v is uniform in [2^192, 2^250)
+//    whatever the 64-bit value v (the prover restarts otherwise, with probability 2^-58);
+//  - the verifier checks that z_v < 2^250.
+// From two accepting transcripts, the extracted values v_1 = dz / dc in the scalar field of
+// the pairing and v_2 = dz / dc in the scalar field of Ristretto satisfy v_k * dc = dz over
+// the integers when v_k < 2^64, as |v_k * dc - dz| < 2^251 is smaller than both orders, hence
+// v_1 = v_2. The values must therefore be proven to be 64-bit values in both systems, as
+// done by the range proofs of the amounts of xfr and by range gates in the circuit. Then
+// W' - tau * g1 is a valid opening proof of C at x for v_1, so that v_1 is the wire value by
+// the evaluation binding of the KZG scheme.
+
+use crate::commitments::kzg_poly_com::{KZGCommitment, KZGCommitmentScheme};
+use crate::commitments::pcs::PolyComScheme;
+use crate::plonk::errors::PlonkError;
+use crate::plonk::plonk_setup::{ConstraintSystem, ProverParams, VerifierParams};
+use crate::plonk::protocol::prover::PlonkPf;
+use crate::polynomials::field_polynomial::FpPolynomial;
+use algebra::groups::{Group, GroupArithmetic, One, Pairing, Scalar, ScalarArithmetic};
+use algebra::ristretto::{RistrettoPoint, RistrettoScalar};
+use crypto::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
+use merlin::Transcript;
+use num_bigint::BigUint;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+
+// bit sizes of the challenge, the value and the upper bound of the response for the value
+const CHALLENGE_BITS: u64 = 128;
+const VALUE_BITS: u64 = 64;
+const RESPONSE_BITS: u64 = 250;
+
+/// Proof that the value of a KZG committed polynomial at a point is the value of a Ristretto
+/// Pedersen commitment
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PCLinkProof<G1, Gt, F> {
+    pub(crate) blinded_eval_proof: G1,
+    pub(crate) com_pedersen: RistrettoPoint,
+    pub(crate) com_pairing: Gt,
+    pub(crate) response_value: [u8; 32],
+    pub(crate) response_pedersen_blind: RistrettoScalar,
+    pub(crate) response_eval_blind: F,
+}
+
+pub type PCLinkPf<P> =
+    PCLinkProof<<P as Pairing>::G1, <P as Pairing>::Gt, <P as Pairing>::ScalarField>;
+
+// the responses for the value, below 2^RESPONSE_BITS, must be canonical in both scalar fields
+fn check_field_size<F: Scalar>() -> Result<()> {
+    if BigUint::from_bytes_le(&F::get_field_size_lsf_bytes()).bits() < RESPONSE_BITS + 2
+    {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+    Ok(())
+}
+
+fn transcript_init_pc_link<P: Pairing>(
+    transcript: &mut Transcript,
+    pc_gens: &RistrettoPedersenGens,
+    commitment: &KZGCommitment<P::G1>,
+    point: &P::ScalarField,
+    value_commitment: &RistrettoPoint,
+    blinded_eval_proof: &P::G1,
+) {
+    transcript.append_message(b"Domain Separator", b"KZG Pedersen Commitment Link");
+    transcript.append_message(b"B", &pc_gens.B.to_compressed_bytes());
+    transcript.append_message(b"B blinding", &pc_gens.B_blinding.to_compressed_bytes());
+    transcript
+        .append_message(b"KZG commitment", &commitment.value.to_compressed_bytes());
+    transcript.append_message(b"point", &point.to_bytes());
+    transcript.append_message(
+        b"Pedersen commitment",
+        &value_commitment.to_compressed_bytes(),
+    );
+    transcript.append_message(
+        b"blinded eval proof",
+        &blinded_eval_proof.to_compressed_bytes(),
+    );
+}
+
+fn transcript_get_pc_link_challenge<P: Pairing>(
+    transcript: &mut Transcript,
+    com_pedersen: &RistrettoPoint,
+    com_pairing: &P::Gt,
+) -> u128 {
+    transcript.append_message(b"com Pedersen", &com_pedersen.to_compressed_bytes());
+    transcript.append_message(b"com pairing", &com_pairing.to_compressed_bytes());
+    let mut bytes = [0u8; (CHALLENGE_BITS / 8) as usize];
+    transcript.challenge_bytes(b"challenge", &mut bytes);
+    u128::from_le_bytes(bytes)
+}
+
+// gt = e(g1, g2) and gt_x = e(g1, g2^s - x * g2)
+fn pairing_bases<P: Pairing>(
+    pcs: &KZGCommitmentScheme<P>,
+    point: &P::ScalarField,
+) -> (P::Gt, P::Gt, P::G2) {
+    let (powers_g1, powers_g2) = pcs.powers();
+    let g2_s_minus_x = powers_g2[1].sub(&powers_g2[0].mul(point));
+    (
+        P::pairing(&powers_g1[0], &powers_g2[0]),
+        P::pairing(&powers_g1[0], &g2_s_minus_x),
+        g2_s_minus_x,
+    )
+}
+
+/// Proves that `polynomial` evaluates at `point` to `value`, the value of the Pedersen
+/// commitment `value * B + blind * B_blinding` for the generators `pc_gens`. `value` must be
+/// proven to be a 64-bit value in both systems, see the comment above.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_kzg_pc_link<R: CryptoRng + RngCore, P: Pairing>(
+    prng: &mut R,
+    transcript: &mut Transcript,
+    pcs: &KZGCommitmentScheme<P>,
+    polynomial: &FpPolynomial<P::ScalarField>,
+    point: &P::ScalarField,
+    value: u64,
+    pc_gens: &RistrettoPedersenGens,
+    blind: &RistrettoScalar,
+) -> Result<PCLinkPf<P>> {
+    check_field_size::<P::ScalarField>().c(d!())?;
+    let value_f = P::ScalarField::from_u64(value);
+    let (quotient, remainder) = polynomial
+        .sub(&FpPolynomial::from_coefs(vec![value_f]))
+        .div_rem(&FpPolynomial::from_coefs(vec![
+            point.neg(),
+            P::ScalarField::one(),
+        ]));
+    if !remainder.is_zero() {
+        return Err(eg!(PlonkError::ProofErrorInvalidWitness));
+    }
+    let (commitment, _) = pcs.commit(polynomial.clone()).c(d!())?;
+    let (eval_proof, _) = pcs.commit(quotient).c(d!())?;
+    let tau = P::ScalarField::random(prng);
+    let blinded_eval_proof = eval_proof.value.add(&pcs.powers().0[0].mul(&tau));
+    let value_commitment = pc_gens.commit(RistrettoScalar::from_u64(value), *blind);
+    transcript_init_pc_link::<P>(
+        transcript,
+        pc_gens,
+        &commitment,
+        point,
+        &value_commitment,
+        &blinded_eval_proof,
+    );
+    let (gt, gt_x, _) = pairing_bases(pcs, point);
+
+    let value_big = BigUint::from(value);
+    loop {
+        let mut attempt = transcript.clone();
+        let mut r_value_bytes = [0u8; 32];
+        prng.fill_bytes(&mut r_value_bytes);
+        r_value_bytes[31] &= 0x03; // r_value < 2^250
+        let r_value = BigUint::from_bytes_le(&r_value_bytes);
+        let r_pedersen_blind = RistrettoScalar::random(prng);
+        let r_eval_blind = P::ScalarField::random(prng);
+
+        let com_pedersen = pc_gens.commit(
+            RistrettoScalar::from_le_bytes(&r_value_bytes).c(d!())?,
+            r_pedersen_blind,
+        );
+        let com_pairing = gt
+            .mul(&P::ScalarField::from_le_bytes(&r_value_bytes).c(d!())?)
+            .sub(&gt_x.mul(&r_eval_blind));
+        let c = transcript_get_pc_link_challenge::<P>(
+            &mut attempt,
+            &com_pedersen,
+            &com_pairing,
+        );
+
+        let response_value = r_value + BigUint::from(c) * &value_big;
+        let bits = response_value.bits();
+        if bits <= CHALLENGE_BITS + VALUE_BITS || bits > RESPONSE_BITS {
+            continue;
+        }
+        let mut response_value_bytes = [0u8; 32];
+        let response_le = response_value.to_bytes_le();
+        response_value_bytes[..response_le.len()].copy_from_slice(&response_le);
+
+        *transcript = attempt;
+        return Ok(PCLinkProof {
+            blinded_eval_proof,
+            com_pedersen,
+            com_pairing,
+            response_value: response_value_bytes,
+            response_pedersen_blind: r_pedersen_blind
+                .add(&RistrettoScalar::from_u128(c).mul(blind)),
+            response_eval_blind: r_eval_blind
+                .add(&P::ScalarField::from_u128(c).mul(&tau)),
+        });
+    }
+}
+
+/// Verifies that the polynomial in `commitment` evaluates at `point` to the value of the
+/// Pedersen commitment `value_commitment` for the generators `pc_gens`
+pub fn verify_kzg_pc_link<P: Pairing>(
+    transcript: &mut Transcript,
+    pcs: &KZGCommitmentScheme<P>,
+    commitment: &KZGCommitment<P::G1>,
+    point: &P::ScalarField,
+    pc_gens: &RistrettoPedersenGens,
+    value_commitment: &RistrettoPoint,
+    proof: &PCLinkPf<P>,
+) -> Result<()> {
+    check_field_size::<P::ScalarField>().c(d!())?;
+    // response_value < 2^RESPONSE_BITS
+    if proof.response_value[31] >= 1 << (RESPONSE_BITS - 248) {
+        return Err(eg!(PlonkError::VerificationError));
+    }
+    transcript_init_pc_link::<P>(
+        transcript,
+        pc_gens,
+        commitment,
+        point,
+        value_commitment,
+        &proof.blinded_eval_proof,
+    );
+    let c = transcript_get_pc_link_challenge::<P>(
+        transcript,
+        &proof.com_pedersen,
+        &proof.com_pairing,
+    );
+
+    // z_v * B + z_rho * B_blinding == A_r + c * V
+    let response_value_r =
+        RistrettoScalar::from_le_bytes(&proof.response_value).c(d!())?;
+    let c_r = RistrettoScalar::from_u128(c);
+    if pc_gens.commit(response_value_r, proof.response_pedersen_blind)
+        != proof.com_pedersen.add(&value_commitment.mul(&c_r))
+    {
+        return Err(eg!(PlonkError::VerificationError));
+    }
+
+    // z_v * gt - z_tau * gt_x == A_t + c * (e(C, g2) - e(W', g2^s - x * g2))
+    let (gt, gt_x, g2_s_minus_x) = pairing_bases(pcs, point);
+    let target = P::pairing(&commitment.value, &pcs.powers().1[0])
+        .sub(&P::pairing(&proof.blinded_eval_proof, &g2_s_minus_x));
+    let response_value_f =
+        P::ScalarField::from_le_bytes(&proof.response_value).c(d!())?;
+    let c_f = P::ScalarField::from_u128(c);
+    if gt
+        .mul(&response_value_f)
+        .sub(&gt_x.mul(&proof.response_eval_blind))
+        != proof.com_pairing.add(&target.mul(&c_f))
+    {
+        return Err(eg!(PlonkError::VerificationError));
+    }
+    Ok(())
+}
+
+/// Returns the first position (wire, gate) of the variable `var` in the wiring of `cs`, whose
+/// value in a PLONK proof is linked to a Pedersen commitment by prove_plonk_pc_link
+pub fn wire_position<CS: ConstraintSystem>(
+    cs: &CS,
+    var: usize,
+) -> Result<(usize, usize)> {
+    for (wire, wire_slice) in cs.wiring().iter().enumerate() {
+        if let Some(gate) = wire_slice.iter().position(|v| *v == var) {
+            return Ok((wire, gate));
+        }
+    }
+    Err(eg!(PlonkError::FuncParamsError))
+}
+
+/// Proves that the value of the variable `var` in a PLONK proof of `cs`, whose witness
+/// polynomials have the openings `witness_openings` (see prover_with_witness_openings), is
+/// the value of the Pedersen commitment `value * B + blind * B_blinding`. `value` must be
+/// proven to be a 64-bit value in `cs` and for the Pedersen commitment.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_plonk_pc_link<
+    R: CryptoRng + RngCore,
+    P: Pairing,
+    CS: ConstraintSystem<Field = P::ScalarField>,
+>(
+    prng: &mut R,
+    transcript: &mut Transcript,
+    pcs: &KZGCommitmentScheme<P>,
+    cs: &CS,
+    params: &ProverParams<KZGCommitmentScheme<P>>,
+    witness_openings: &[FpPolynomial<P::ScalarField>],
+    var: usize,
+    value: u64,
+    pc_gens: &RistrettoPedersenGens,
+    blind: &RistrettoScalar,
+) -> Result<PCLinkPf<P>> {
+    let (wire, gate) = wire_position(cs, var).c(d!())?;
+    let polynomial = witness_openings
+        .get(wire)
+        .c(d!(PlonkError::FuncParamsError))?;
+    let point = params.group.get(gate).c(d!(PlonkError::FuncParamsError))?;
+    prove_kzg_pc_link(
+        prng, transcript, pcs, polynomial, point, value, pc_gens, blind,
+    )
+    .c(d!())
+}
+
+/// Verifies that the value of the variable `var` in the PLONK proof `plonk_proof` of `cs` is
+/// the value of the Pedersen commitment `value_commitment`
+#[allow(clippy::too_many_arguments)]
+pub fn verify_plonk_pc_link<P: Pairing, CS: ConstraintSystem<Field = P::ScalarField>>(
+    transcript: &mut Transcript,
+    pcs: &KZGCommitmentScheme<P>,
+    cs: &CS,
+    params: &VerifierParams<KZGCommitmentScheme<P>>,
+    plonk_proof: &PlonkPf<KZGCommitmentScheme<P>>,
+    var: usize,
+    pc_gens: &RistrettoPedersenGens,
+    value_commitment: &RistrettoPoint,
+    proof: &PCLinkPf<P>,
+) -> Result<()> {
+    let (wire, gate) = wire_position(cs, var).c(d!())?;
+    let commitment = plonk_proof
+        .witness_commitments()
+        .get(wire)
+        .c(d!(PlonkError::VerificationError))?;
+    if gate >= params.cs_size {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+    let point = params.root.pow(&[gate as u64]);
+    verify_kzg_pc_link(
+        transcript,
+        pcs,
+        commitment,
+        &point,
+        pc_gens,
+        value_commitment,
+        proof,
+    )
+    .c(d!())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitment, KZGCommitmentScheme, KZGCommitmentSchemeBLS,
+    };
+    use crate::commitments::pcs::PolyComScheme;
+    use crate::plonk::pc_link::{
+        prove_kzg_pc_link, prove_plonk_pc_link, verify_kzg_pc_link, verify_plonk_pc_link,
+    };
+    use crate::plonk::plonk_setup::{preprocess_prover, preprocess_verifier};
+    use crate::plonk::protocol::prover::{prover_with_witness_openings, verifier};
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use crate::polynomials::field_polynomial::FpPolynomial;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::bn254::Bn254;
+    use algebra::groups::{Pairing, Scalar};
+    use algebra::ristretto::{RistrettoPoint, RistrettoScalar};
+    use crypto::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn check_kzg_pc_link<P: Pairing>(pcs: &KZGCommitmentScheme<P>) {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pc_gens = RistrettoPedersenGens::default();
+        let point = P::ScalarField::random(&mut prng);
+        let value = 0xfedc_ba98_7654_3210u64;
+        let mut polynomial = FpPolynomial::random(&mut prng, 8);
+        let shift = P::ScalarField::from_u64(value).sub(&polynomial.eval(&point));
+        polynomial.add_coef_assign(&shift, 0);
+        let (commitment, _) = pnk!(pcs.commit(polynomial.clone()));
+        let blind = RistrettoScalar::random(&mut prng);
+        let value_commitment = pc_gens.commit(RistrettoScalar::from_u64(value), blind);
+
+        let proof = {
+            let mut transcript = Transcript::new(b"Test");
+            pnk!(prove_kzg_pc_link(
+                &mut prng,
+                &mut transcript,
+                pcs,
+                &polynomial,
+                &point,
+                value,
+                &pc_gens,
+                &blind
+            ))
+        };
+        let verify = |commitment: &KZGCommitment<P::G1>,
+                      point: &P::ScalarField,
+                      value_commitment: &RistrettoPoint| {
+            let mut transcript = Transcript::new(b"Test");
+            verify_kzg_pc_link(
+                &mut transcript,
+                pcs,
+                commitment,
+                point,
+                &pc_gens,
+                value_commitment,
+                &proof,
+            )
+        };
+        assert!(verify(&commitment, &point, &value_commitment).is_ok());
+        let other_value = pc_gens.commit(RistrettoScalar::from_u64(value + 1), blind);
+        assert!(verify(&commitment, &point, &other_value).is_err());
+        let other_point = point.add(&P::ScalarField::from_u32(1));
+        assert!(verify(&commitment, &other_point, &value_commitment).is_err());
+        let (other_commitment, _) = pnk!(pcs.commit(polynomial.mul_scalar(&point)));
+        assert!(verify(&other_commitment, &point, &value_commitment).is_err());
+
+        // the response for the value is bounded
+        let mut wrong_proof = proof.clone();
+        wrong_proof.response_value[31] = 0x04;
+        let mut transcript = Transcript::new(b"Test");
+        assert!(verify_kzg_pc_link(
+            &mut transcript,
+            pcs,
+            &commitment,
+            &point,
+            &pc_gens,
+            &value_commitment,
+            &wrong_proof,
+        )
+        .is_err());
+
+        // the polynomial does not evaluate to the value
+        let mut transcript = Transcript::new(b"Test");
+        assert!(prove_kzg_pc_link(
+            &mut prng,
+            &mut transcript,
+            pcs,
+            &polynomial,
+            &point,
+            value + 1,
+            &pc_gens,
+            &blind
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_kzg_pc_link() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        check_kzg_pc_link(&KZGCommitmentSchemeBLS::new(10, &mut prng));
+        check_kzg_pc_link(&KZGCommitmentScheme::<Bn254>::new(10, &mut prng));
+    }
+
+    #[test]
+    fn test_plonk_pc_link() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs = KZGCommitmentSchemeBLS::new(20, &mut prng);
+        let pc_gens = RistrettoPedersenGens::default();
+
+        // amount = amount_0 + amount_1
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let amount_0 = cs.new_variable(BLSScalar::from_u64(100));
+        let amount_1 = cs.new_variable(BLSScalar::from_u64(23));
+        let amount = cs.add(amount_0, amount_1);
+        cs.pad();
+        let witness = cs.get_and_clear_witness();
+        let prover_params = pnk!(preprocess_prover(&cs, &pcs, [0u8; 32]));
+        let verifier_params = pnk!(preprocess_verifier(&cs, &pcs, [0u8; 32]));
+
+        let (plonk_proof, witness_openings) = {
+            let mut transcript = Transcript::new(b"Test");
+            pnk!(prover_with_witness_openings(
+                &mut prng,
+                &mut transcript,
+                &pcs,
+                &cs,
+                &prover_params,
+                &witness
+            ))
+        };
+        let mut transcript = Transcript::new(b"Test");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[],
+            &plonk_proof
+        )
+        .is_ok());
+
+        let blind = RistrettoScalar::random(&mut prng);
+        let value_commitment = pc_gens.commit(RistrettoScalar::from_u64(123), blind);
+        let proof = {
+            let mut transcript = Transcript::new(b"Test link");
+            pnk!(prove_plonk_pc_link(
+                &mut prng,
+                &mut transcript,
+                &pcs,
+                &cs,
+                &prover_params,
+                &witness_openings,
+                amount,
+                123,
+                &pc_gens,
+                &blind
+            ))
+        };
+        for (var, expected) in [(amount, true), (amount_0, false), (amount_1, false)] {
+            let mut transcript = Transcript::new(b"Test link");
+            let result = verify_plonk_pc_link(
+                &mut transcript,
+                &pcs,
+                &cs,
+                &verifier_params,
+                &plonk_proof,
+                var,
+                &pc_gens,
+                &value_commitment,
+                &proof,
+            );
+            assert_eq!(result.is_ok(), expected);
+        }
+
+        // a wrong value is rejected by the prover
+        let mut transcript = Transcript::new(b"Test link");
+        assert!(prove_plonk_pc_link(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness_openings,
+            amount_0,
+            123,
+            &pc_gens,
+            &blind
+        )
+        .is_err());
+    }
+}
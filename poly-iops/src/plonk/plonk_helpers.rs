@@ -1,3 +1,4 @@
+use crate::backend::ArithmeticBackend;
 use crate::commitments::pcs::{HomomorphicPolyComElem, PolyComScheme};
 use crate::plonk::errors::PlonkError;
 use crate::plonk::plonk_setup::{ConstraintSystem, ProverParams, VerifierParams};
@@ -6,6 +7,8 @@ use crate::polynomials::field_polynomial::FpPolynomial;
 use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
 use itertools::Itertools;
 use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use ruc::*;
 
 pub fn build_group<F: Scalar>(generator: &F, max_elems: usize) -> Result<Vec<F>> {
@@ -126,6 +129,7 @@ pub(super) fn hide_polynomial<R: CryptoRng + RngCore, F: Scalar>(
 // Build polynomial Sigma, by interpolating
 // \Sigma(g^{i+1}) = \Sigma(g^i)\prod_{j=1}^{n_wires_per_gate}(fj(g^i) + \gamma*k_j*g^i +\delta)/(fj(g^i) + \gamma*perm_j(g^i) +\delta)
 // and setting \Sigma(1) = 1 for the base case
+// `perms_values` are the values perm_j(g^i), as computed by prover::prover_offline
 #[allow(non_snake_case)]
 pub(super) fn Sigma_polynomial<
     PCS: PolyComScheme,
@@ -133,33 +137,17 @@ pub(super) fn Sigma_polynomial<
 >(
     cs: &CS,
     params: &ProverParams<PCS>,
+    perms_values: &[PCS::Field],
     witness: &[PCS::Field],
     challenges: &PlonkChallenges<PCS::Field>,
 ) -> FpPolynomial<PCS::Field> {
     let n_wires_per_gate = cs.n_wires_per_gate();
     let (gamma, delta) = challenges.get_gamma_delta().unwrap();
     let mut sigma_values = vec![];
-    let perm = cs.compute_permutation();
     let n_constraints = witness.len() / n_wires_per_gate;
     let mut prev = PCS::Field::one();
     sigma_values.push(PCS::Field::one());
     let group = &params.group[..];
-
-    // computes permutation values
-    fn p_of_x<Field: Scalar>(
-        perm_value: usize,
-        n: usize,
-        group: &[Field],
-        k: &[Field],
-    ) -> Field {
-        for (i, ki) in k.iter().enumerate().skip(1) {
-            if perm_value < (i + 1) * n && perm_value >= i * n {
-                return ki.mul(&group[perm_value % n]);
-            }
-        }
-        k[0].mul(&group[perm_value])
-    }
-
     let k = &params.verifier_params.k;
     for i in 0..n_constraints - 1 {
         // 1. a = prod_{j=1..n_wires_per_gate}(fj(g^i) + \gamma*k_j*g^i +\delta)
@@ -172,13 +160,8 @@ pub(super) fn Sigma_polynomial<
             let f_plus_gamma_id_plus_delta = &f_x.add(delta).add(&gamma.mul(&k_x));
             a.mul_assign(&f_plus_gamma_id_plus_delta);
 
-            let p_x = p_of_x::<PCS::Field>(
-                perm[j * n_constraints + i],
-                n_constraints,
-                group,
-                k,
-            );
-            let f_plus_gamma_perm_plus_delta = f_x.add(delta).add(&gamma.mul(&p_x));
+            let p_x = &perms_values[j * n_constraints + i];
+            let f_plus_gamma_perm_plus_delta = f_x.add(delta).add(&gamma.mul(p_x));
             b.mul_assign(&f_plus_gamma_perm_plus_delta);
         }
 
@@ -202,12 +185,14 @@ pub(super) fn Sigma_polynomial<
 // 1. Evaluations of selector polynomials at point z: {qj(z)}.
 // 2. Evaluations of permutation polynomials at point z: {perm_j(z)}_{j=1..n_wires_per_gate}.
 // 3. The evaluation of Z_H^{-1}(z), where Z_H(X) is the vanishing polynomial of H.
+// 4. Evaluations of identity permutations at point z: {k_j * z}, computed by prover::prover_offline.
 // Then Q(z) is evaluated as
 // Q(z) = P(z) * Z_H^{-1}(z), for P(z)=
 //     constraint_equation({fj(z)}, {qj(z)}, IO(z))
 //   + alpha * \Sigma(z)\prod_j (fj(z) + gamma * kj * z + delta)
 //   - alpha * \Sigma(g*z)\prod_j (fj(z) + gamma * perm_j(z) + delta)
-//   + alpha^2 (Sigma(z) - 1) * (z^n - 1) / (z - 1).
+//   + alpha^2 (Sigma(z) - 1) * (z^n - 1) / (z - 1)
+//   + the lookup terms of the constraint system, if any, evaluated by lookup::lookup_terms_coset_evals.
 //
 // To guarantee that Z_H^{-1}(z) is well-defined for any z \in H', we have to make sure
 // z \notin H, hence we choose H' to be a set that does not overlap with H. In particular,
@@ -221,16 +206,21 @@ pub(super) fn Sigma_polynomial<
 // ({fj(X)}, IO(X), Sigma(X)}), O(m) ops to evaluate {Q(z)}_{z\in H'}, and 1 deg-m
 // ifft to recover Q(X).
 #[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
 pub(super) fn Quotient_polynomial<
     PCS: PolyComScheme,
     CS: ConstraintSystem<Field = PCS::Field>,
+    B: ArithmeticBackend,
 >(
+    backend: &B,
     cs: &CS,
     params: &ProverParams<PCS>,
+    identity_perms_coset_evals: &[Vec<PCS::Field>],
     witness_polys: &[FpPolynomial<PCS::Field>],
     Sigma: &FpPolynomial<PCS::Field>,
     challenges: &PlonkChallenges<PCS::Field>,
     IO: &FpPolynomial<PCS::Field>,
+    lookup_terms_coset_evals: Option<&[PCS::Field]>,
 ) -> Result<FpPolynomial<PCS::Field>> {
     let n = cs.size();
     let m = cs.quot_eval_dom_size();
@@ -238,83 +228,143 @@ pub(super) fn Quotient_polynomial<
     if n * factor != m {
         return Err(eg!(PlonkError::SetupError));
     }
+    // the parameters of plonk::low_memory have no evaluations over the coset
+    if params.L1_coset_evals.len() != m {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
     let root_m = &params.root_m;
     let k = &params.verifier_params.k;
 
     // Compute the evaluations of witness/IO/Sigma polynomials on the coset k[1] * <root_m>.
-    let witness_polys_coset_evals: Vec<Vec<PCS::Field>> = witness_polys
-        .iter()
-        .map(|poly| poly.coset_fft_with_unity_root(root_m, m, &k[1]))
+    #[cfg(feature = "parallel")]
+    let iter = witness_polys.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let iter = witness_polys.iter();
+    let witness_polys_coset_evals: Vec<Vec<PCS::Field>> = iter
+        .map(|poly| backend.coset_fft(poly, root_m, m, &k[1]))
         .collect();
-    let IO_coset_evals = IO.coset_fft_with_unity_root(root_m, m, &k[1]);
-    let Sigma_coset_evals = Sigma.coset_fft_with_unity_root(root_m, m, &k[1]);
+    let IO_coset_evals = backend.coset_fft(IO, root_m, m, &k[1]);
+    let Sigma_coset_evals = backend.coset_fft(Sigma, root_m, m, &k[1]);
+
+    // Compute the evaluations of the quotient polynomial on the coset, on multiple threads
+    // with the `parallel` feature. The closure borrows the vectors of the prover parameters it
+    // uses, as the parameters themselves are not Sync for every commitment scheme.
+    let selectors_coset_evals = &params.selectors_coset_evals;
+    let perms_coset_evals = &params.perms_coset_evals;
+    let L1_coset_evals = &params.L1_coset_evals;
+    let Z_H_inv_coset_evals = &params.Z_H_inv_coset_evals;
+    let quot_coset_eval = |point: usize| -> Option<PCS::Field> {
+        let evals = QuotientPointEvals {
+            wires: evals_at(&witness_polys_coset_evals, point),
+            selectors: evals_at(selectors_coset_evals, point),
+            IO: &IO_coset_evals[point],
+            identity_perms: evals_at(identity_perms_coset_evals, point),
+            perms: evals_at(perms_coset_evals, point),
+            Sigma: &Sigma_coset_evals[point],
+            Sigma_next: &Sigma_coset_evals[(point + factor) % m],
+            L1: &L1_coset_evals[point],
+            Z_H_inv: &Z_H_inv_coset_evals[point],
+            lookup_term: lookup_terms_coset_evals.map(|terms| &terms[point]),
+        };
+        quotient_eval(cs, challenges, &evals)
+    };
+    #[cfg(feature = "parallel")]
+    let points = (0..m).into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let points = 0..m;
+    let quot_coset_evals: Vec<PCS::Field> = points
+        .map(quot_coset_eval)
+        .collect::<Option<Vec<PCS::Field>>>()
+        .c(d!(PlonkError::ProofError))?;
 
-    // Compute the evaluations of the quotient polynomial on the coset.
-    let (gamma, delta) = challenges.get_gamma_delta().unwrap();
-    let alpha = challenges.get_alpha().unwrap();
-    let alpha_sq = alpha.mul(&alpha);
-    let mut quot_coset_evals = vec![];
+    let k_inv = k[1].inv().c(d!(PlonkError::DivisionByZero))?;
+    Ok(backend.coset_ifft(root_m, &quot_coset_evals, &k_inv))
+}
 
-    for point in 0..m {
-        let wire_vals: Vec<&PCS::Field> = witness_polys_coset_evals
-            .iter()
-            .map(|poly_coset_evals| &poly_coset_evals[point])
-            .collect();
-        let sel_vals: Vec<&PCS::Field> = params
-            .selectors_coset_evals
-            .iter()
-            .map(|poly_coset_evals| &poly_coset_evals[point])
-            .collect();
-        let term1 = cs.eval_gate_func(&wire_vals, &sel_vals, &IO_coset_evals[point])?;
-
-        // alpha * [\Sigma(X)\prod_j (fj(X) + gamma * kj * X + delta)]
-        let mut term2 = alpha.mul(&Sigma_coset_evals[point]);
-        for j in 0..cs.n_wires_per_gate() {
-            let tmp = witness_polys_coset_evals[j][point]
-                .add(&delta)
-                .add(&gamma.mul(&k[j].mul(&params.coset_quot[point])));
-            term2.mul_assign(&tmp);
-        }
+// Returns the evaluations of index `point` of each polynomial of `evals`
+fn evals_at<F>(evals: &[Vec<F>], point: usize) -> Vec<&F> {
+    evals.iter().map(|evals| &evals[point]).collect()
+}
 
-        // alpha * [\Sigma(g*X)\prod_j (fj(X) + gamma * perm_j(X) + delta)]
-        let mut term3 = alpha.mul(&Sigma_coset_evals[(point + factor) % m]);
-        for (w_poly_coset_evals, perm_coset_evals) in witness_polys_coset_evals
-            .iter()
-            .zip(params.perms_coset_evals.iter())
-        {
-            let tmp = &w_poly_coset_evals[point]
-                .add(&delta)
-                .add(&gamma.mul(&perm_coset_evals[point]));
-            term3.mul_assign(&tmp);
-        }
+// The evaluations at a point x of the domain of the quotient polynomial from which the
+// quotient polynomial is evaluated at x, see quotient_eval
+#[allow(non_snake_case)]
+pub(super) struct QuotientPointEvals<'a, F> {
+    pub(super) wires: Vec<&'a F>,
+    pub(super) selectors: Vec<&'a F>,
+    pub(super) IO: &'a F,
+    // k_j * x
+    pub(super) identity_perms: Vec<&'a F>,
+    pub(super) perms: Vec<&'a F>,
+    pub(super) Sigma: &'a F,
+    // Sigma(g * x)
+    pub(super) Sigma_next: &'a F,
+    pub(super) L1: &'a F,
+    pub(super) Z_H_inv: &'a F,
+    pub(super) lookup_term: Option<&'a F>,
+}
 
-        // alpha^2 * (Sigma(X) - 1) * L_1(X)
-        let term4 = alpha_sq
-            .mul(&params.L1_coset_evals[point])
-            .mul(&Sigma_coset_evals[point].sub(&PCS::Field::one()));
+// Returns Q(x) = P(x) / Z_H(x) from the evaluations at x of the polynomials of P(X), or None if
+// the evaluations do not match the shape of the gates of `cs`
+pub(super) fn quotient_eval<F: Scalar, CS: ConstraintSystem<Field = F>>(
+    cs: &CS,
+    challenges: &PlonkChallenges<F>,
+    evals: &QuotientPointEvals<F>,
+) -> Option<F> {
+    let (gamma, delta) = challenges.get_gamma_delta().unwrap();
+    let alpha = challenges.get_alpha().unwrap();
+    let term1 = cs
+        .eval_gate_func(&evals.wires, &evals.selectors, evals.IO)
+        .ok()?;
+
+    // alpha * [\Sigma(X)\prod_j (fj(X) + gamma * kj * X + delta)]
+    let mut term2 = alpha.mul(evals.Sigma);
+    for (wire, id_perm) in evals.wires.iter().zip(evals.identity_perms.iter()) {
+        let tmp = wire.add(delta).add(&gamma.mul(id_perm));
+        term2.mul_assign(&tmp);
+    }
 
-        let numerator = term1.add(&term2).add(&term4.sub(&term3));
-        quot_coset_evals.push(numerator.mul(&params.Z_H_inv_coset_evals[point]));
+    // alpha * [\Sigma(g*X)\prod_j (fj(X) + gamma * perm_j(X) + delta)]
+    let mut term3 = alpha.mul(evals.Sigma_next);
+    for (wire, perm) in evals.wires.iter().zip(evals.perms.iter()) {
+        let tmp = wire.add(delta).add(&gamma.mul(perm));
+        term3.mul_assign(&tmp);
     }
 
-    let k_inv = k[1].inv().c(d!(PlonkError::DivisionByZero))?;
-    Ok(FpPolynomial::coset_ffti(root_m, &quot_coset_evals, &k_inv))
+    // alpha^2 * (Sigma(X) - 1) * L_1(X)
+    let term4 = alpha
+        .square()
+        .mul(evals.L1)
+        .mul(&evals.Sigma.sub(&F::one()));
+
+    let mut numerator = term1.add(&term2).add(&term4.sub(&term3));
+    if let Some(lookup_term) = evals.lookup_term {
+        numerator.add_assign(lookup_term);
+    }
+    Some(numerator.mul(evals.Z_H_inv))
 }
 
 // Compute linearization polynomial opening/commitment.
 // Denote cs.eval_selector_multipliers(f1(beta), .., f_{n_wires_per_gate}(beta)) = (w1, .., w_{n_selectors}).
 // Denote the selector polynomials [q1(X), ..., q_{n_selectors}(X)].
 // Denote the selector polynomials commitments [C_q1(X), ..., C_q_{n_selectors}(X)].
+// Denote Q(X) = \sum_i beta^{i*(n+2)} * Q_i(X) the combination of the split quotient polynomials.
 // The opening:
 //  L(X) = sum_{i=1..n_selectors} wi * qi(X)
 //       + \Sigma(X) [alpha * prod_j (fj(beta) + gamma * kj * beta + delta) + alpha^2 * L1(beta)]
 //       - perm_{n_wires_per_gate}(X) [alpha * \Sigma(g*beta) * gamma
 //         * prod_{j=1..n_wires_per_gate-1}(fj(beta) + gamma * perm_j(beta) + delta)]
+//       - (beta^n - 1) * Q(X)
 // The commitment:
 //  C_L(X) = sum_{i=1..n_selectors} wi * qi(X)
 //       + C_\Sigma(X) [alpha * prod_j (fj(beta) + gamma * kj * beta + delta) + alpha^2 * L1(beta)]
 //       - C_perm_{n_wires_per_gate}(X) [alpha * \Sigma(g*beta) * gamma
 //         * prod_{j=1..n_wires_per_gate-1}(fj(beta) + gamma * perm_j(beta) + delta)]
+//       - (beta^n - 1) * C_Q(X)
+// Since P(beta) = Q(beta) * (beta^n - 1), the verifier derives L(beta) from the other evaluations
+// (see derive_L_eval_beta), hence L(beta) is not part of the proof, and Q(X) is not opened on its own.
+// The proofs of version 0 have no (beta^n - 1) * Q(X) term (`q_combined` is None): they send L(beta)
+// and open Q(X), whose evaluation at beta is derived by the verifier (see derive_Q_eval_beta).
 #[allow(non_snake_case)]
 #[allow(clippy::too_many_arguments)]
 fn linearization<F: Scalar, PCSType: HomomorphicPolyComElem<Scalar = F>>(
@@ -324,6 +374,7 @@ fn linearization<F: Scalar, PCSType: HomomorphicPolyComElem<Scalar = F>>(
     k: &[F],
     last_extended_perm: &PCSType,
     Sigma: &PCSType,
+    q_combined: Option<&PCSType>,
     witness_polys_eval_beta: &[&F],
     perms_eval_beta: &[&F],
     Sigma_eval_g_beta: &F,
@@ -354,6 +405,13 @@ fn linearization<F: Scalar, PCSType: HomomorphicPolyComElem<Scalar = F>>(
         b.mul_assign(&bi);
     }
     L.op_assign(&last_extended_perm.exp(&b).inv());
+
+    // 4. - (beta^n - 1) * Q(X)
+    if let Some(q_combined) = q_combined {
+        let beta = challenges.get_beta().unwrap();
+        let Z_H_eval_beta = beta.pow_u64(n as u64).sub(&F::one());
+        L.op_assign(&q_combined.exp(&Z_H_eval_beta).inv());
+    }
     L
 }
 
@@ -365,6 +423,7 @@ pub(super) fn linearization_polynomial_opening<
     cs: &CS,
     params: &ProverParams<PCS>,
     Sigma: &PCS::Opening,
+    q_combined: Option<&PCS::Opening>,
     witness_polys_eval_beta: &[&PCS::Field],
     perms_eval_beta: &[&PCS::Field],
     Sigma_eval_g_beta: &PCS::Field,
@@ -380,6 +439,7 @@ pub(super) fn linearization_polynomial_opening<
         &params.verifier_params.k,
         &params.extended_permutations[cs.n_wires_per_gate() - 1],
         Sigma,
+        q_combined,
         witness_polys_eval_beta,
         perms_eval_beta,
         Sigma_eval_g_beta,
@@ -395,6 +455,7 @@ pub(super) fn linearization_commitment<
     cs: &CS,
     params: &VerifierParams<PCS>,
     C_Sigma: &PCS::Commitment,
+    C_q_combined: Option<&PCS::Commitment>,
     witness_polys_eval_beta: &[&PCS::Field],
     perms_eval_beta: &[&PCS::Field],
     Sigma_eval_g_beta: &PCS::Field,
@@ -410,6 +471,7 @@ pub(super) fn linearization_commitment<
         &params.k,
         &params.extended_permutations[cs.n_wires_per_gate() - 1],
         C_Sigma,
+        C_q_combined,
         witness_polys_eval_beta,
         perms_eval_beta,
         Sigma_eval_g_beta,
@@ -426,7 +488,7 @@ pub(super) fn eval_public_var_poly<PCS: PolyComScheme>(
 ) -> PCS::Field {
     let mut eval = PCS::Field::zero();
     // (X^n -1) lagrange numerator
-    let x_to_n = eval_point.pow(&[params.cs_size as u64]);
+    let x_to_n = eval_point.pow_u64(params.cs_size as u64);
     let num = x_to_n.sub(&PCS::Field::one());
     for ((constraint_index, public_value), lagrange_constant) in params
         .public_vars_constraint_indices
@@ -435,7 +497,7 @@ pub(super) fn eval_public_var_poly<PCS: PolyComScheme>(
         .zip(params.lagrange_constants.iter())
     {
         // X - g^j j-th lagrange denominator
-        let root_to_j = params.root.pow(&[*constraint_index as u64]);
+        let root_to_j = params.root.pow_u64(*constraint_index as u64);
         let den = eval_point.sub(&root_to_j);
         let den_inv = den.inv().unwrap();
         let lagrange_i = lagrange_constant.mul(&num.mul(&den_inv));
@@ -480,8 +542,8 @@ fn compute_sigma_scalar_in_L<F: Scalar>(
     }
 
     // 2. alpha^2*(beta^n - 1) / (beta - 1)
-    let alpha_sq = alpha.mul(&alpha);
-    let beta_pow_n = beta.pow(&[n as u64]);
+    let alpha_sq = alpha.square();
+    let beta_pow_n = beta.pow_u64(n as u64);
     let L1_eval_beta = beta_pow_n
         .sub(&F::one())
         .mul(&beta.sub(&F::one()).inv().unwrap());
@@ -490,12 +552,11 @@ fn compute_sigma_scalar_in_L<F: Scalar>(
     a.add(&c)
 }
 
-// derive Q(beta) such that P(\beta) - Q(beta)*Z_H(beta) = 0
-// That is Q(beta) = P(\beta)/Z_H(beta) =
-//  (L(\beta) + PI(\beta) - alpha * \Sigma(g * beta) * prod_{i=1..n_wires_per_gate-1}(fi(beta) + gamma * permi(beta) + delta) * (f_{n_wires_per_gate}(beta) + delta) -
-//     alpha^2 *(\beta^n - 1) / (\beta - 1) ) / (\beta^n - 1)
+// derive L(beta) such that P(\beta) - Q(beta)*Z_H(beta) = 0
+// That is L(beta) = alpha * \Sigma(g * beta) * prod_{i=1..n_wires_per_gate-1}(fi(beta) + gamma * permi(beta) + delta)
+//     * (f_{n_wires_per_gate}(beta) + delta) + alpha^2 *(\beta^n - 1) / (\beta - 1) - PI(\beta)
 #[allow(non_snake_case)]
-pub(super) fn derive_Q_eval_beta<PCS: PolyComScheme>(
+pub(super) fn derive_L_eval_beta<PCS: PolyComScheme>(
     params: &VerifierParams<PCS>,
     proof: &PlonkPf<PCS>,
     challenges: &PlonkChallenges<PCS::Field>,
@@ -505,7 +566,6 @@ pub(super) fn derive_Q_eval_beta<PCS: PolyComScheme>(
     let alpha = challenges.get_alpha().unwrap();
     let (gamma, delta) = challenges.get_gamma_delta().unwrap();
 
-    let term0 = proof.L_eval_beta.add(&public_vars_eval_beta);
     let mut term1 = alpha.mul(&proof.Sigma_eval_g_beta);
     let n_wires_per_gate = &proof.witness_polys_eval_beta.len();
     for i in 0..n_wires_per_gate - 1 {
@@ -517,23 +577,41 @@ pub(super) fn derive_Q_eval_beta<PCS: PolyComScheme>(
     term1.mul_assign(&proof.witness_polys_eval_beta[n_wires_per_gate - 1].add(delta));
 
     let one = PCS::Field::one();
-    let beta_n = beta.pow(&[params.cs_size as u64]);
+    let beta_n = beta.pow_u64(params.cs_size as u64);
     let Z_H_eval_beta = beta_n.sub(&one);
     let beta_minus_one = beta.sub(&one);
     let first_lagrange_eval_beta = Z_H_eval_beta.mul(&beta_minus_one.inv().unwrap());
-    let term2 = first_lagrange_eval_beta.mul(&alpha.mul(&alpha));
+    let term2 = first_lagrange_eval_beta.mul(&alpha.square());
 
-    let term1_plus_term2 = term1.add(&term2);
-
-    let dividend = term0.sub(&term1_plus_term2);
+    term1.add(&term2).sub(public_vars_eval_beta)
+}
 
+// derive Q(beta) such that P(\beta) - Q(beta)*Z_H(beta) = 0 from the L(beta) sent in the proofs of
+// version 0, that is Q(beta) = (L(beta) - derive_L_eval_beta(..)) / (\beta^n - 1)
+#[allow(non_snake_case)]
+pub(super) fn derive_Q_eval_beta<PCS: PolyComScheme>(
+    params: &VerifierParams<PCS>,
+    proof: &PlonkPf<PCS>,
+    L_eval_beta: &PCS::Field,
+    challenges: &PlonkChallenges<PCS::Field>,
+    public_vars_eval_beta: &PCS::Field,
+) -> PCS::Field {
+    let beta = challenges.get_beta().unwrap();
+    let Z_H_eval_beta = beta.pow_u64(params.cs_size as u64).sub(&PCS::Field::one());
+    let dividend = L_eval_beta.sub(&derive_L_eval_beta::<PCS>(
+        params,
+        proof,
+        challenges,
+        public_vars_eval_beta,
+    ));
     dividend.mul(&Z_H_eval_beta.inv().unwrap())
 }
 
 /// Split the quotient polynomial into `n_wires_per_gate` degree-`n` polynomials and commit.
 #[allow(non_snake_case, clippy::type_complexity)]
-pub(crate) fn split_Q_and_commit<PCS: PolyComScheme>(
+pub(crate) fn split_Q_and_commit<PCS: PolyComScheme, B: ArithmeticBackend>(
     pcs: &PCS,
+    backend: &B,
     Q: &FpPolynomial<PCS::Field>,
     n_wires_per_gate: usize,
     n: usize,
@@ -547,7 +625,9 @@ pub(crate) fn split_Q_and_commit<PCS: PolyComScheme>(
             Q.get_coefs_ref()[(n_wires_per_gate - 1) * n..].to_vec()
         };
         let q_poly = FpPolynomial::from_coefs(coefs);
-        let (C_q, O_q) = pcs.commit(q_poly).c(d!(PlonkError::CommitmentError))?;
+        let (C_q, O_q) = pcs
+            .commit_with_backend(backend, q_poly)
+            .c(d!(PlonkError::CommitmentError))?;
         C_q_polys.push(C_q);
         O_q_polys.push(O_q);
     }
@@ -564,7 +644,7 @@ pub(crate) fn combine_q_polys<
     beta: &F,
     n: usize,
 ) -> PCSType {
-    let factor = beta.pow(&[n as u64]);
+    let factor = beta.pow_u64(n as u64);
     let mut exponent = factor;
     let mut q_poly_combined = q_polys[0].clone();
     for q_poly in q_polys.iter().skip(1) {
@@ -581,6 +661,7 @@ mod test {
     };
     use crate::plonk::plonk_helpers::{PlonkChallenges, Sigma_polynomial};
     use crate::plonk::plonk_setup::{preprocess_prover, PlonkConstraintSystem};
+    use crate::plonk::protocol::prover::prover_offline;
     use algebra::bls12_381::BLSScalar;
     use algebra::groups::{One, ScalarArithmetic, Zero};
     use rand::SeedableRng;
@@ -614,9 +695,11 @@ mod test {
         ];
         let mut challenges = PlonkChallenges::<F>::new();
         challenges.insert_gamma_delta(one, zero).unwrap();
+        let offline = prover_offline(&cs, &params).unwrap();
         let Q = Sigma_polynomial::<KZGCommitmentSchemeBLS, PlonkConstraintSystem<F>>(
             &cs,
             &params,
+            &offline.perms_values,
             &witness[..],
             &challenges,
         );
@@ -1,15 +1,28 @@
 use crate::commitments::pcs::PolyComScheme;
 use crate::ioputils::u8_lsf_slice_to_u64_lsf_le_vec;
 use crate::plonk::errors::PlonkError;
+use crate::plonk::lookup::{
+    preprocess_lookup, LookupColumns, PlonkLookupProverParams, PlonkLookupVerifierParams,
+};
 use crate::plonk::plonk_helpers::{build_group, compute_lagrange_constant};
+use crate::plonk::turbo_plonk_cs::check_var_indices;
+use crate::polynomials::evaluation_domain::{is_fft_size, EvaluationDomain};
 use crate::polynomials::field_polynomial::{primitive_nth_root_of_unity, FpPolynomial};
 use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
 use rand_chacha::ChaChaRng;
 use rand_core::{CryptoRng, RngCore, SeedableRng};
 use ruc::*;
+use serde::de::{SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::marker::PhantomData;
+use utils::serialization::versioned_serde::{
+    next_element, next_versioned_head, VersionedHead, VERSIONED_ENCODING_MAX_LEN,
+    VERSIONED_ENCODING_TAG,
+};
 
 /// Trait for Turbo PLONK constraint systems.
-pub trait ConstraintSystem {
+pub trait ConstraintSystem: Sync {
     type Field: Scalar;
     /// Return the number of constraints in the system.
     /// `size should divide q-1 where q is the size of the prime field.
@@ -100,8 +113,77 @@ pub trait ConstraintSystem {
         &self,
         wire_vals: &[&Self::Field],
     ) -> Result<Vec<Self::Field>>;
+
+    /// Return the lookup gates of the constraint system, None if it has no lookup table.
+    /// A lookup gate asserts that the values of its first two wires and its table id are an
+    /// entry of the lookup tables (see plonk::lookup).
+    fn lookup_columns(&self) -> Option<LookupColumns<Self::Field>> {
+        None
+    }
+}
+
+/// How a constraint system of n constraints chooses the size m of the domain of the quotient
+/// polynomial, a multiple of n larger than the degree of the quotient polynomial
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuotEvalDomainStrategy {
+    /// m = factor * n, or small_factor * n if n is at most 4
+    Factor { factor: usize, small_factor: usize },
+    /// The smallest multiple of n supported by the FFT (see evaluation_domain::is_fft_size)
+    /// that is larger than the degree of the quotient polynomial, e.g. 6n for a TurboPLONK
+    /// circuit of 2^k constraints and 8n for one of 3 * 2^k constraints
+    Smallest,
+}
+
+impl QuotEvalDomainStrategy {
+    /// Returns the size of the domain of the quotient polynomial for `cs_size` constraints,
+    /// where `min_size` is the degree of the quotient polynomial plus one. Returns 0, which
+    /// preprocess_prover rejects, if the strategy is Smallest and `cs_size` is not supported
+    /// by the FFT.
+    /// # Example
+    /// ```
+    /// use poly_iops::plonk::plonk_setup::QuotEvalDomainStrategy;
+    /// let factor = QuotEvalDomainStrategy::Factor { factor: 6, small_factor: 16 };
+    /// assert_eq!(factor.quot_eval_dom_size(1024, 5 * 1024 + 8), 6 * 1024);
+    /// assert_eq!(factor.quot_eval_dom_size(4, 5 * 4 + 8), 16 * 4);
+    /// let smallest = QuotEvalDomainStrategy::Smallest;
+    /// assert_eq!(smallest.quot_eval_dom_size(1024, 5 * 1024 + 8), 6 * 1024);
+    /// assert_eq!(smallest.quot_eval_dom_size(768, 5 * 768 + 8), 8 * 768);
+    /// assert_eq!(smallest.quot_eval_dom_size(4, 5 * 4 + 8), 8 * 4);
+    /// assert_eq!(smallest.quot_eval_dom_size(5, 5 * 5 + 8), 0);
+    /// ```
+    pub fn quot_eval_dom_size(&self, cs_size: usize, min_size: usize) -> usize {
+        match *self {
+            QuotEvalDomainStrategy::Factor {
+                factor,
+                small_factor,
+            } => {
+                if cs_size > 4 {
+                    cs_size * factor
+                } else {
+                    cs_size * small_factor
+                }
+            }
+            QuotEvalDomainStrategy::Smallest => {
+                if !is_fft_size(cs_size) {
+                    return 0;
+                }
+                let mut multiple = ((min_size + cs_size - 1) / cs_size).max(1);
+                while !is_fft_size(multiple * cs_size) {
+                    multiple += 1;
+                }
+                multiple * cs_size
+            }
+        }
+    }
 }
 
+/// The domain of the quotient polynomial of a standard PLONK circuit, of degree 3n + 5
+pub const STANDARD_QUOT_EVAL_DOM_STRATEGY: QuotEvalDomainStrategy =
+    QuotEvalDomainStrategy::Factor {
+        factor: 4,
+        small_factor: 8,
+    };
+
 #[allow(non_snake_case)]
 pub struct PlonkConstraintSystem<F> {
     pub selectors: Vec<Vec<F>>,
@@ -128,11 +210,7 @@ impl<F: Scalar> ConstraintSystem for PlonkConstraintSystem<F> {
     }
 
     fn quot_eval_dom_size(&self) -> usize {
-        if self.size > 4 {
-            self.size * 4
-        } else {
-            self.size * 8
-        }
+        STANDARD_QUOT_EVAL_DOM_STRATEGY.quot_eval_dom_size(self.size, 3 * self.size + 6)
     }
 
     fn n_wires_per_gate(&self) -> usize {
@@ -153,7 +231,10 @@ impl<F: Scalar> ConstraintSystem for PlonkConstraintSystem<F> {
 
     fn selector(&self, index: usize) -> Result<&[F]> {
         if index >= self.selectors.len() {
-            return Err(eg!(PlonkError::FuncParamsError));
+            return Err(eg!(PlonkError::SelectorMismatch(
+                index,
+                self.selectors.len()
+            )));
         }
         Ok(&self.selectors[index])
     }
@@ -165,7 +246,10 @@ impl<F: Scalar> ConstraintSystem for PlonkConstraintSystem<F> {
         sel_vals: &[&Self::Field],
         pub_input: &Self::Field,
     ) -> Result<Self::Field> {
-        if wire_vals.len() < 3 || sel_vals.len() < 5 {
+        if sel_vals.len() < 5 {
+            return Err(eg!(PlonkError::SelectorMismatch(sel_vals.len(), 5)));
+        }
+        if wire_vals.len() < 3 {
             return Err(eg!(PlonkError::FuncParamsError));
         }
         let left = sel_vals[0].mul(wire_vals[0]);
@@ -212,7 +296,18 @@ impl<F: Scalar> PlonkConstraintSystem<F> {
         right_var_index: usize,
         out_var_index: usize,
     ) {
-        self.insert_add_gate_with_inputs_multiplier(
+        pnk!(self.try_insert_add_gate(left_var_index, right_var_index, out_var_index))
+    }
+
+    /// Same as insert_add_gate, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system
+    pub fn try_insert_add_gate(
+        &mut self,
+        left_var_index: usize,
+        right_var_index: usize,
+        out_var_index: usize,
+    ) -> Result<()> {
+        self.try_insert_add_gate_with_inputs_multiplier(
             left_var_index,
             right_var_index,
             out_var_index,
@@ -220,6 +315,7 @@ impl<F: Scalar> PlonkConstraintSystem<F> {
             F::one(),
         )
     }
+
     pub fn insert_add_gate_with_inputs_multiplier(
         &mut self,
         left_var_index: usize,
@@ -228,18 +324,30 @@ impl<F: Scalar> PlonkConstraintSystem<F> {
         left_var_multiplier: F,
         right_var_multiplier: F,
     ) {
-        assert!(
-            left_var_index < self.num_vars,
-            "Variable index out of Constraint System bound"
-        );
-        assert!(
-            right_var_index < self.num_vars,
-            "Variable index out of Constraint System bound"
-        );
-        assert!(
-            out_var_index < self.num_vars,
-            "Variable index out of Constraint System bound"
-        );
+        pnk!(self.try_insert_add_gate_with_inputs_multiplier(
+            left_var_index,
+            right_var_index,
+            out_var_index,
+            left_var_multiplier,
+            right_var_multiplier,
+        ))
+    }
+
+    /// Same as insert_add_gate_with_inputs_multiplier, returning WireIndexOutOfBounds instead
+    /// of panicking if a variable is not in the constraint system
+    pub fn try_insert_add_gate_with_inputs_multiplier(
+        &mut self,
+        left_var_index: usize,
+        right_var_index: usize,
+        out_var_index: usize,
+        left_var_multiplier: F,
+        right_var_multiplier: F,
+    ) -> Result<()> {
+        check_var_indices(
+            &[left_var_index, right_var_index, out_var_index],
+            self.num_vars,
+        )
+        .c(d!())?;
         self.selectors[0].push(left_var_multiplier);
         self.selectors[1].push(right_var_multiplier);
         self.selectors[2].push(F::zero());
@@ -249,6 +357,7 @@ impl<F: Scalar> PlonkConstraintSystem<F> {
         self.wiring[1].push(right_var_index);
         self.wiring[2].push(out_var_index);
         self.size += 1;
+        Ok(())
     }
 
     pub fn insert_mul_gate(
@@ -257,7 +366,18 @@ impl<F: Scalar> PlonkConstraintSystem<F> {
         right_var_index: usize,
         out_var_index: usize,
     ) {
-        self.insert_mul_gate_with_input_multiplier(
+        pnk!(self.try_insert_mul_gate(left_var_index, right_var_index, out_var_index))
+    }
+
+    /// Same as insert_mul_gate, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system
+    pub fn try_insert_mul_gate(
+        &mut self,
+        left_var_index: usize,
+        right_var_index: usize,
+        out_var_index: usize,
+    ) -> Result<()> {
+        self.try_insert_mul_gate_with_input_multiplier(
             left_var_index,
             right_var_index,
             out_var_index,
@@ -272,18 +392,28 @@ impl<F: Scalar> PlonkConstraintSystem<F> {
         out_var_index: usize,
         in_vars_multiplier: F,
     ) {
-        assert!(
-            left_var_index < self.num_vars,
-            "Variable index out of Constraint System bound"
-        );
-        assert!(
-            right_var_index < self.num_vars,
-            "Variable index out of Constraint System bound"
-        );
-        assert!(
-            out_var_index < self.num_vars,
-            "Variable index out of Constraint System bound"
-        );
+        pnk!(self.try_insert_mul_gate_with_input_multiplier(
+            left_var_index,
+            right_var_index,
+            out_var_index,
+            in_vars_multiplier,
+        ))
+    }
+
+    /// Same as insert_mul_gate_with_input_multiplier, returning WireIndexOutOfBounds instead
+    /// of panicking if a variable is not in the constraint system
+    pub fn try_insert_mul_gate_with_input_multiplier(
+        &mut self,
+        left_var_index: usize,
+        right_var_index: usize,
+        out_var_index: usize,
+        in_vars_multiplier: F,
+    ) -> Result<()> {
+        check_var_indices(
+            &[left_var_index, right_var_index, out_var_index],
+            self.num_vars,
+        )
+        .c(d!())?;
         self.selectors[0].push(F::zero());
         self.selectors[1].push(F::zero());
         self.selectors[2].push(in_vars_multiplier);
@@ -293,13 +423,17 @@ impl<F: Scalar> PlonkConstraintSystem<F> {
         self.wiring[1].push(right_var_index);
         self.wiring[2].push(out_var_index);
         self.size += 1;
+        Ok(())
     }
 
     pub fn insert_boolean_gate(&mut self, var_index: usize) {
-        assert!(
-            var_index < self.num_vars,
-            "Variable index out of Constraint System bound"
-        );
+        pnk!(self.try_insert_boolean_gate(var_index))
+    }
+
+    /// Same as insert_boolean_gate, returning WireIndexOutOfBounds instead of panicking if the
+    /// variable is not in the constraint system
+    pub fn try_insert_boolean_gate(&mut self, var_index: usize) -> Result<()> {
+        check_var_indices(&[var_index], self.num_vars).c(d!())?;
         self.selectors[0].push(F::zero());
         self.selectors[1].push(F::zero());
         self.selectors[2].push(F::one());
@@ -309,14 +443,18 @@ impl<F: Scalar> PlonkConstraintSystem<F> {
         self.wiring[1].push(var_index);
         self.wiring[2].push(var_index);
         self.size += 1;
+        Ok(())
     }
 
     /// Insert a constant in the constraint system
     pub fn insert_constant(&mut self, var_index: usize, constant: F) {
-        assert!(
-            var_index < self.num_vars,
-            "Variable index out of Constraint System bound"
-        );
+        pnk!(self.try_insert_constant(var_index, constant))
+    }
+
+    /// Same as insert_constant, returning WireIndexOutOfBounds instead of panicking if the
+    /// variable is not in the constraint system
+    pub fn try_insert_constant(&mut self, var_index: usize, constant: F) -> Result<()> {
+        check_var_indices(&[var_index], self.num_vars).c(d!())?;
         self.selectors[0].push(F::zero());
         self.selectors[1].push(F::zero());
         self.selectors[2].push(F::zero());
@@ -326,6 +464,7 @@ impl<F: Scalar> PlonkConstraintSystem<F> {
         self.wiring[1].push(0);
         self.wiring[2].push(var_index);
         self.size += 1;
+        Ok(())
     }
 
     /// Insert constraint of a public IO value to be decided online
@@ -444,6 +583,9 @@ pub struct PlonkProverParams<O, C, F> {
     pub(crate) perms_coset_evals: Vec<Vec<F>>,
     pub(crate) L1_coset_evals: Vec<F>,
     pub(crate) Z_H_inv_coset_evals: Vec<F>,
+    // lookup gates, None if the constraint system has no lookup table
+    #[serde(default)]
+    pub(crate) lookup: Option<PlonkLookupProverParams<O, F>>,
 }
 
 impl<O, C, F> PlonkProverParams<O, C, F> {
@@ -454,9 +596,55 @@ impl<O, C, F> PlonkProverParams<O, C, F> {
     pub fn get_verifier_params_ref(&self) -> &PlonkVerifierParams<C, F> {
         &self.verifier_params
     }
+
+    /// Sets the protocol version of the proofs computed with these parameters,
+    /// see PlonkVerifierParams::set_version
+    pub fn set_version(&mut self, version: u32) -> Result<()> {
+        self.verifier_params.set_version(version).c(d!())
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl<O, C, F: Scalar> PlonkProverParams<O, C, F> {
+    /// Returns the domain H over which the witness and selector polynomials are interpolated
+    pub fn eval_domain(&self) -> EvaluationDomain<F> {
+        self.verifier_params.eval_domain()
+    }
+
+    /// Returns the coset k_1 * H' over which the prover computes the quotient polynomial, where
+    /// H' is a subgroup larger than H
+    pub fn quot_eval_domain(&self) -> EvaluationDomain<F> {
+        EvaluationDomain::from_generator(
+            self.coset_quot.len(),
+            self.root_m,
+            self.verifier_params.k[1],
+        )
+    }
+}
+
+/// Version of the PLONK protocol of the proofs computed with freshly preprocessed parameters.
+/// Version 0 stands for the proofs and parameters serialized before the version was recorded,
+/// which decode with version 0, see utils::serialization::versioned_serde: their proofs send the
+/// evaluation L(beta) of the linearization polynomial and open the combined quotient polynomial.
+/// Version 1 binds the version of the proof to the transcript, and has the verifier derive L(beta)
+/// from the other evaluations instead, see plonk_helpers::linearization.
+pub const PLONK_PROTOCOL_VERSION: u32 = 1;
+
+/// Versions of the PLONK protocol that the verifier accepts
+pub const PLONK_SUPPORTED_VERSIONS: &[u32] = &[0, 1];
+
+/// Returns an error if the verifier does not accept proofs of version `version`
+pub fn check_plonk_version(version: u32) -> Result<()> {
+    if PLONK_SUPPORTED_VERSIONS.contains(&version) {
+        Ok(())
+    } else {
+        Err(eg!(PlonkError::UnsupportedVersion))
+    }
+}
+
+/// The parameters of the PLONK verifier. The binary encoding starts with the version, see
+/// utils::serialization::versioned_serde, and the encodings from before the version was
+/// recorded decode with version 0, no SRS digest and no lookup gates.
+#[derive(Debug)]
 #[allow(non_snake_case)]
 pub struct PlonkVerifierParams<C, F> {
     pub(crate) selectors: Vec<C>,
@@ -466,6 +654,177 @@ pub struct PlonkVerifierParams<C, F> {
     pub(crate) cs_size: usize,
     pub(crate) public_vars_constraint_indices: Vec<usize>,
     pub(crate) lagrange_constants: Vec<F>,
+    // digest of the SRS of the polynomial commitment scheme used in preprocessing
+    pub(crate) srs_digest: Vec<u8>,
+    // protocol version of the proofs computed with these parameters
+    pub(crate) version: u32,
+    // lookup gates, None if the constraint system has no lookup table
+    pub(crate) lookup: Option<PlonkLookupVerifierParams<C, F>>,
+}
+
+// Encoding of the fields by name, for self-describing encodings
+#[derive(Serialize)]
+struct PlonkVerifierParamsFieldsRef<'a, C, F> {
+    selectors: &'a Vec<C>,
+    extended_permutations: &'a Vec<C>,
+    k: &'a Vec<F>,
+    root: &'a F,
+    cs_size: usize,
+    public_vars_constraint_indices: &'a Vec<usize>,
+    lagrange_constants: &'a Vec<F>,
+    srs_digest: &'a Vec<u8>,
+    version: u32,
+    lookup: &'a Option<PlonkLookupVerifierParams<C, F>>,
+}
+
+#[derive(Deserialize)]
+struct PlonkVerifierParamsFields<C, F> {
+    selectors: Vec<C>,
+    extended_permutations: Vec<C>,
+    k: Vec<F>,
+    root: F,
+    cs_size: usize,
+    public_vars_constraint_indices: Vec<usize>,
+    lagrange_constants: Vec<F>,
+    #[serde(default)]
+    srs_digest: Vec<u8>,
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    lookup: Option<PlonkLookupVerifierParams<C, F>>,
+}
+
+impl<C, F> PlonkVerifierParams<C, F> {
+    // Encoding of the fields by name, also used by verifier_key.rs in the fields order
+    pub(crate) fn fields_ref(&self) -> impl Serialize + '_
+    where
+        C: Serialize,
+        F: Serialize,
+    {
+        PlonkVerifierParamsFieldsRef {
+            selectors: &self.selectors,
+            extended_permutations: &self.extended_permutations,
+            k: &self.k,
+            root: &self.root,
+            cs_size: self.cs_size,
+            public_vars_constraint_indices: &self.public_vars_constraint_indices,
+            lagrange_constants: &self.lagrange_constants,
+            srs_digest: &self.srs_digest,
+            version: self.version,
+            lookup: &self.lookup,
+        }
+    }
+
+    // Decodes the encoding of fields_ref
+    pub(crate) fn deserialize_fields<'de, D>(
+        deserializer: D,
+    ) -> core::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+        C: Deserialize<'de>,
+        F: Deserialize<'de>,
+    {
+        let fields = PlonkVerifierParamsFields::deserialize(deserializer)?;
+        Ok(PlonkVerifierParams {
+            selectors: fields.selectors,
+            extended_permutations: fields.extended_permutations,
+            k: fields.k,
+            root: fields.root,
+            cs_size: fields.cs_size,
+            public_vars_constraint_indices: fields.public_vars_constraint_indices,
+            lagrange_constants: fields.lagrange_constants,
+            srs_digest: fields.srs_digest,
+            version: fields.version,
+            lookup: fields.lookup,
+        })
+    }
+}
+
+impl<C: Serialize, F: Serialize> Serialize for PlonkVerifierParams<C, F> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            return self.fields_ref().serialize(serializer);
+        }
+        let mut tuple = serializer.serialize_tuple(11)?;
+        tuple.serialize_element(&VERSIONED_ENCODING_TAG)?;
+        tuple.serialize_element(&self.version)?;
+        tuple.serialize_element(&self.selectors)?;
+        tuple.serialize_element(&self.extended_permutations)?;
+        tuple.serialize_element(&self.k)?;
+        tuple.serialize_element(&self.root)?;
+        tuple.serialize_element(&self.cs_size)?;
+        tuple.serialize_element(&self.public_vars_constraint_indices)?;
+        tuple.serialize_element(&self.lagrange_constants)?;
+        tuple.serialize_element(&self.srs_digest)?;
+        tuple.serialize_element(&self.lookup)?;
+        tuple.end()
+    }
+}
+
+impl<'de, C: Deserialize<'de>, F: Deserialize<'de>> Deserialize<'de>
+    for PlonkVerifierParams<C, F>
+{
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            return PlonkVerifierParams::deserialize_fields(deserializer);
+        }
+        deserializer.deserialize_tuple(
+            VERSIONED_ENCODING_MAX_LEN,
+            PlonkVerifierParamsVisitor(PhantomData),
+        )
+    }
+}
+
+struct PlonkVerifierParamsVisitor<C, F>(PhantomData<(C, F)>);
+
+impl<'de, C: Deserialize<'de>, F: Deserialize<'de>> Visitor<'de>
+    for PlonkVerifierParamsVisitor<C, F>
+{
+    type Value = PlonkVerifierParams<C, F>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("PLONK verifier parameters")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> core::result::Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        // the parameters from before the version was recorded have no SRS digest and no lookup
+        // gates
+        let (version, selectors, legacy) = match next_versioned_head(&mut seq)? {
+            VersionedHead::Versioned(version) => {
+                (version, next_element(&mut seq)?, false)
+            }
+            VersionedHead::Legacy(selectors) => (0, selectors, true),
+        };
+        Ok(PlonkVerifierParams {
+            selectors,
+            extended_permutations: next_element(&mut seq)?,
+            k: next_element(&mut seq)?,
+            root: next_element(&mut seq)?,
+            cs_size: next_element(&mut seq)?,
+            public_vars_constraint_indices: next_element(&mut seq)?,
+            lagrange_constants: next_element(&mut seq)?,
+            srs_digest: if legacy {
+                vec![]
+            } else {
+                next_element(&mut seq)?
+            },
+            version,
+            lookup: if legacy {
+                None
+            } else {
+                next_element(&mut seq)?
+            },
+        })
+    }
 }
 
 pub type VerifierParams<PCS> = PlonkVerifierParams<
@@ -473,6 +832,52 @@ pub type VerifierParams<PCS> = PlonkVerifierParams<
     <PCS as PolyComScheme>::Field,
 >;
 
+impl<C, F> PlonkVerifierParams<C, F> {
+    /// Checks that `pcs` has the SRS the parameters were preprocessed with, e.g. after loading
+    /// both from files. Proofs verified with another SRS would fail with no hint of the cause.
+    /// The parameters from before the SRS digest was recorded have none and fail the check.
+    pub fn check_srs<PCS: PolyComScheme<Commitment = C, Field = F>>(
+        &self,
+        pcs: &PCS,
+    ) -> Result<()> {
+        if pcs.srs_digest() == self.srs_digest {
+            Ok(())
+        } else {
+            Err(eg!(PlonkError::SetupError))
+        }
+    }
+
+    /// Returns the protocol version of the proofs computed with these parameters
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Sets the protocol version of the proofs computed with these parameters, e.g. to keep
+    /// computing proofs of an older version until all the verifiers accept the newer one.
+    /// The verifier accepts proofs of all the supported versions whatever the version of its
+    /// parameters. Returns an error if `version` is not supported.
+    pub fn set_version(&mut self, version: u32) -> Result<()> {
+        check_plonk_version(version).c(d!())?;
+        self.version = version;
+        Ok(())
+    }
+}
+
+impl<C, F: Scalar> PlonkVerifierParams<C, F> {
+    /// Returns the domain H over which the witness and selector polynomials are interpolated
+    pub fn eval_domain(&self) -> EvaluationDomain<F> {
+        EvaluationDomain::from_generator(self.cs_size, self.root, F::one())
+    }
+
+    /// Returns the coset k_i * H labelling the values of the i-th wire in the permutation
+    /// argument, or None if there is no i-th wire
+    pub fn wire_coset(&self, i: usize) -> Option<EvaluationDomain<F>> {
+        self.k
+            .get(i)
+            .map(|k_i| EvaluationDomain::from_generator(self.cs_size, self.root, *k_i))
+    }
+}
+
 pub fn perm_values<F: Scalar>(group: &[F], perm: &[usize], k: &[F]) -> Vec<F> {
     let n = group.len();
     perm.iter()
@@ -513,6 +918,70 @@ pub fn choose_ks<R: CryptoRng + RngCore, F: Scalar>(
     k
 }
 
+/// Choice of the shifts k_0 = 1, k_1, ..., k_{w-1} of the cosets k_i * H labelling the values
+/// of the w wires in the permutation argument. k_1 is also the offset of the domain k_1 * H'
+/// of the quotient polynomial.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CosetShifts<F> {
+    /// Random quadratic non-residues of choose_ks, with a ChaChaRng seeded by the seed, as in
+    /// preprocess_prover
+    Seeded([u8; 32]),
+    /// The powers 1, g, g^2, ... of the multiplicative generator g of the field, which
+    /// external verifiers can compute without the seed
+    GeneratorPowers,
+    /// Shifts chosen by the caller, as many as the wires of a gate
+    Custom(Vec<F>),
+}
+
+impl<F: Scalar> CosetShifts<F> {
+    /// Returns the shifts of `n_wires_per_gate` wires
+    pub fn ks(&self, n_wires_per_gate: usize) -> Vec<F> {
+        match self {
+            CosetShifts::Seeded(seed) => {
+                let mut prng = ChaChaRng::from_seed(*seed);
+                choose_ks(&mut prng, n_wires_per_gate)
+            }
+            CosetShifts::GeneratorPowers => {
+                let generator = F::multiplicative_generator();
+                let mut k = vec![F::one()];
+                for i in 1..n_wires_per_gate {
+                    k.push(k[i - 1].mul(&generator));
+                }
+                k
+            }
+            CosetShifts::Custom(k) => k.clone(),
+        }
+    }
+}
+
+// Checks that the shifts `k` of the wires are valid for a domain H of size n and a domain H'
+// of the quotient polynomial of size m: k_0 = 1, the cosets k_i * H are pairwise disjoint,
+// i.e. the k_i^n are pairwise distinct, and k_1 * H' is disjoint from H', i.e. k_1^m != 1, so
+// that the vanishing polynomial of H has no root on k_1 * H'.
+fn check_coset_shifts<F: Scalar>(
+    k: &[F],
+    n_wires_per_gate: usize,
+    n: usize,
+    m: usize,
+) -> Result<()> {
+    if k.len() != n_wires_per_gate || k.first() != Some(&F::one()) {
+        return Err(eg!(PlonkError::SetupError));
+    }
+    if k.iter().skip(1).any(|k_i| k_i == &F::zero()) {
+        return Err(eg!(PlonkError::SetupError));
+    }
+    let k_n: Vec<F> = k.iter().map(|k_i| k_i.pow(&[n as u64])).collect();
+    for i in 1..k_n.len() {
+        if k_n[..i].contains(&k_n[i]) {
+            return Err(eg!(PlonkError::SetupError));
+        }
+    }
+    if k.len() > 1 && k[1].pow(&[m as u64]) == F::one() {
+        return Err(eg!(PlonkError::SetupError));
+    }
+    Ok(())
+}
+
 pub type ProverParams<PCS> = PlonkProverParams<
     <PCS as PolyComScheme>::Opening,
     <PCS as PolyComScheme>::Commitment,
@@ -520,10 +989,9 @@ pub type ProverParams<PCS> = PlonkProverParams<
 >;
 /// Precompute the prover parameters.
 /// Before invoking preprocess_prover(), the constraint system `cs` should pad the number of
-/// constraints to a power of two.
+/// constraints to a power of two, or to 3 * 2^k with TurboConstraintSystem::pad_mixed_radix.
 /// # Example
 /// See plonk::prover::prover
-#[allow(non_snake_case)]
 pub fn preprocess_prover<
     PCS: PolyComScheme,
     CS: ConstraintSystem<Field = PCS::Field>,
@@ -532,22 +1000,90 @@ pub fn preprocess_prover<
     pcs: &PCS,
     prg_seed: [u8; 32],
 ) -> Result<ProverParams<PCS>> {
-    let mut prng = ChaChaRng::from_seed(prg_seed);
+    preprocess_prover_with_coset_shifts(cs, pcs, &CosetShifts::Seeded(prg_seed))
+}
+
+/// Same as preprocess_prover, with the coset shifts `shifts` instead of the ones sampled from
+/// a seed. Returns an error if the shifts are not valid for the domains of the circuit, that
+/// is, if the cosets of the wires are not disjoint or if the domain of the quotient polynomial
+/// intersects H.
+/// # Example
+/// ```
+/// use poly_iops::commitments::kzg_poly_com::KZGCommitmentSchemeBLS;
+/// use poly_iops::plonk::plonk_setup::{
+///     preprocess_prover_with_coset_shifts, preprocess_verifier_with_coset_shifts,
+///     CosetShifts,
+/// };
+/// use poly_iops::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+/// use algebra::bls12_381::BLSScalar;
+/// use algebra::groups::{One, Scalar};
+/// use rand_chacha::ChaChaRng;
+/// use rand_core::SeedableRng;
+/// let mut prng = ChaChaRng::from_seed([0u8; 32]);
+/// let pcs = KZGCommitmentSchemeBLS::new(20, &mut prng);
+/// let mut cs = TurboPlonkConstraintSystem::<BLSScalar>::new();
+/// let one = cs.new_variable(BLSScalar::one());
+/// cs.add(one, one);
+/// cs.pad();
+/// let shifts = CosetShifts::GeneratorPowers;
+/// let prover_params = preprocess_prover_with_coset_shifts(&cs, &pcs, &shifts).unwrap();
+/// let verifier_params = preprocess_verifier_with_coset_shifts(&cs, &pcs, &shifts).unwrap();
+/// let wire_coset = verifier_params.wire_coset(1).unwrap();
+/// assert_eq!(wire_coset.offset(), &BLSScalar::multiplicative_generator());
+/// assert_eq!(prover_params.quot_eval_domain().offset(), wire_coset.offset());
+/// // the coset of the second wire is H itself
+/// let invalid = CosetShifts::Custom(vec![BLSScalar::one(); 5]);
+/// assert!(preprocess_prover_with_coset_shifts(&cs, &pcs, &invalid).is_err());
+/// ```
+pub fn preprocess_prover_with_coset_shifts<
+    PCS: PolyComScheme,
+    CS: ConstraintSystem<Field = PCS::Field>,
+>(
+    cs: &CS,
+    pcs: &PCS,
+    shifts: &CosetShifts<PCS::Field>,
+) -> Result<ProverParams<PCS>> {
+    preprocess_prover_with_coset_evals(cs, pcs, shifts, true)
+}
+
+// Same as preprocess_prover, without the evaluations of the selectors, the permutations, L1 and
+// Z_H^{-1} over the domain of the quotient polynomial if `with_coset_evals` is false, for the
+// prover of plonk::low_memory and for preprocess_verifier
+#[allow(non_snake_case)]
+pub(crate) fn preprocess_prover_with_coset_evals<
+    PCS: PolyComScheme,
+    CS: ConstraintSystem<Field = PCS::Field>,
+>(
+    cs: &CS,
+    pcs: &PCS,
+    shifts: &CosetShifts<PCS::Field>,
+    with_coset_evals: bool,
+) -> Result<ProverParams<PCS>> {
     let n_wires_per_gate = cs.n_wires_per_gate();
     let n = cs.size();
     let m = cs.quot_eval_dom_size();
+    // the selectors and the permutations have degree n - 1
+    if pcs.max_degree() + 1 < n {
+        return Err(eg!(PlonkError::SrsTooShort(pcs.max_degree(), n - 1)));
+    }
+    if m <= n {
+        return Err(eg!(PlonkError::DomainTooSmall(m, n + 1)));
+    }
     let factor = m / n;
     if n * factor != m {
         return Err(eg!(PlonkError::SetupError));
     }
+    if !is_fft_size(m) {
+        return Err(eg!(PlonkError::GroupNotFound(m)));
+    }
     // Compute evaluation domains.
     let root_m = primitive_nth_root_of_unity::<PCS::Field>(m)
         .c(d!(PlonkError::GroupNotFound(m)))?;
     let group_m = build_group(&root_m, m)?;
     let root = group_m[factor % m];
     let group = build_group(&root, n)?;
-    // TODO: we can fix the set k for different circuits.
-    let k = choose_ks::<_, PCS::Field>(&mut prng, n_wires_per_gate);
+    let k = shifts.ks(n_wires_per_gate);
+    check_coset_shifts(&k, n_wires_per_gate, n, m).c(d!())?;
     let coset_quot = group_m.iter().map(|x| k[1].mul(x)).collect();
 
     // Compute the openings, commitments, and point evaluations of the permutation polynomials.
@@ -561,7 +1097,10 @@ pub fn preprocess_prover<
     let mut verifier_extended_perms = vec![];
     for i in 0..n_wires_per_gate {
         let perm = FpPolynomial::ffti(&root, &p_values[i * n..(i + 1) * n]);
-        perms_coset_evals[i].extend(perm.coset_fft_with_unity_root(&root_m, m, &k[1]));
+        if with_coset_evals {
+            perms_coset_evals[i]
+                .extend(perm.coset_fft_with_unity_root(&root_m, m, &k[1]));
+        }
         let (C_perm, O_perm) = pcs.commit(perm).c(d!(PlonkError::SetupError))?;
         prover_extended_perms.push(O_perm);
         verifier_extended_perms.push(C_perm);
@@ -573,7 +1112,9 @@ pub fn preprocess_prover<
     let mut verifier_selectors = vec![];
     for (i, selector_coset_evals) in selectors_coset_evals.iter_mut().enumerate() {
         let q = FpPolynomial::ffti(&root, cs.selector(i)?);
-        selector_coset_evals.extend(q.coset_fft_with_unity_root(&root_m, m, &k[1]));
+        if with_coset_evals {
+            selector_coset_evals.extend(q.coset_fft_with_unity_root(&root_m, m, &k[1]));
+        }
         let (C_q, O_q) = pcs.commit(q).c(d!(PlonkError::SetupError))?;
         prover_selectors.push(O_q);
         verifier_selectors.push(C_q);
@@ -581,22 +1122,51 @@ pub fn preprocess_prover<
 
     // Compute polynomials L1, Z_H, and point evaluations of L1 and Z_H^{-1}.
     let L1 = FpPolynomial::from_zeroes(&group[1..]);
-    let L1_coset_evals = L1.coset_fft_with_unity_root(&root_m, m, &k[1]);
+    let L1_coset_evals = if with_coset_evals {
+        L1.coset_fft_with_unity_root(&root_m, m, &k[1])
+    } else {
+        vec![]
+    };
     let mut Z_H_coefs = vec![PCS::Field::zero(); n + 1];
     Z_H_coefs[0] = PCS::Field::one().neg();
     Z_H_coefs[n] = PCS::Field::one();
     let Z_H = FpPolynomial::from_coefs(Z_H_coefs);
-    let Z_H_inv_coset_evals = Z_H
-        .coset_fft_with_unity_root(&root_m, m, &k[1])
-        .into_iter()
-        .map(|x| x.inv().unwrap())
-        .collect();
+    let Z_H_inv_coset_evals = if with_coset_evals {
+        Z_H.coset_fft_with_unity_root(&root_m, m, &k[1])
+            .into_iter()
+            .map(|x| x.inv().unwrap())
+            .collect()
+    } else {
+        vec![]
+    };
 
     let mut lagrange_constants = vec![];
     for constraint_index in cs.public_vars_constraint_indices().iter() {
         lagrange_constants.push(compute_lagrange_constant(&group, *constraint_index));
     }
 
+    // Compute the openings, commitments, and point evaluations of the lookup gates.
+    let (prover_lookup, verifier_lookup) = match cs.lookup_columns() {
+        Some(columns) => {
+            // the lookup gates read the first two wires
+            if n_wires_per_gate < 2 {
+                return Err(eg!(PlonkError::SetupError));
+            }
+            let (prover_lookup, verifier_lookup) = preprocess_lookup(
+                pcs,
+                &columns,
+                &group,
+                &root_m,
+                m,
+                &k[1],
+                with_coset_evals,
+            )
+            .c(d!())?;
+            (Some(prover_lookup), Some(verifier_lookup))
+        }
+        None => (None, None),
+    };
+
     let verifier_params = PlonkVerifierParams {
         selectors: verifier_selectors,
         extended_permutations: verifier_extended_perms,
@@ -605,6 +1175,9 @@ pub fn preprocess_prover<
         cs_size: n,
         public_vars_constraint_indices: cs.public_vars_constraint_indices().to_vec(),
         lagrange_constants,
+        srs_digest: pcs.srs_digest(),
+        version: PLONK_PROTOCOL_VERSION,
+        lookup: verifier_lookup,
     };
 
     Ok(PlonkProverParams {
@@ -620,12 +1193,14 @@ pub fn preprocess_prover<
         perms_coset_evals,
         L1_coset_evals,
         Z_H_inv_coset_evals,
+        lookup: prover_lookup,
     })
 }
 
-/// Precompute the verifier parameters.
+/// Precompute the verifier parameters, the verifier key of plonk::verifier_key.
 /// Before invoking preprocess_verifier(), the constraint system `cs` should pad the number of
-/// constraints to a power of two.
+/// constraints to a power of two. Unlike preprocess_prover, the evaluations over the domain of
+/// the quotient polynomial are not computed, and the prover parameters are dropped.
 /// # Example
 /// See plonk::prover::prover
 pub fn preprocess_verifier<
@@ -636,16 +1211,35 @@ pub fn preprocess_verifier<
     pcs: &PCS,
     prg_seed: [u8; 32],
 ) -> Result<VerifierParams<PCS>> {
-    let prover_params = preprocess_prover(cs, pcs, prg_seed).c(d!())?;
+    preprocess_verifier_with_coset_shifts(cs, pcs, &CosetShifts::Seeded(prg_seed))
+}
+
+/// Same as preprocess_verifier, with the coset shifts `shifts`, see
+/// preprocess_prover_with_coset_shifts
+pub fn preprocess_verifier_with_coset_shifts<
+    PCS: PolyComScheme,
+    CS: ConstraintSystem<Field = PCS::Field>,
+>(
+    cs: &CS,
+    pcs: &PCS,
+    shifts: &CosetShifts<PCS::Field>,
+) -> Result<VerifierParams<PCS>> {
+    let prover_params =
+        preprocess_prover_with_coset_evals(cs, pcs, shifts, false).c(d!())?;
     Ok(prover_params.verifier_params)
 }
 
 #[cfg(test)]
 mod test {
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitmentScheme, KZGCommitmentSchemeBLS,
+    };
     use crate::ioputils::u8_lsf_slice_to_u64_lsf_le_vec;
     use crate::plonk::plonk_setup::{
-        choose_ks, ConstraintSystem, PlonkConstraintSystem,
+        check_coset_shifts, choose_ks, preprocess_prover, ConstraintSystem, CosetShifts,
+        PlonkConstraintSystem,
     };
+    use crate::polynomials::field_polynomial::primitive_nth_root_of_unity;
     use algebra::bls12_381::BLSScalar;
     use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
     use rand_chacha::ChaChaRng;
@@ -687,6 +1281,31 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_coset_shifts() {
+        let g = F::multiplicative_generator();
+        let k = CosetShifts::<F>::GeneratorPowers.ks(3);
+        assert_eq!(k, vec![F::one(), g, g.mul(&g)]);
+        assert!(check_coset_shifts(&k, 3, 4, 32).is_ok());
+        let seeded = CosetShifts::<F>::Seeded([0u8; 32]);
+        assert_eq!(
+            seeded.ks(3),
+            choose_ks(&mut ChaChaRng::from_seed([0u8; 32]), 3)
+        );
+        assert!(check_coset_shifts(&seeded.ks(3), 3, 4, 32).is_ok());
+
+        // wrong number of shifts, k_0 != 1, k_2 = 0
+        assert!(check_coset_shifts(&k, 4, 4, 32).is_err());
+        assert!(check_coset_shifts(&[g, g.mul(&g), F::one()], 3, 4, 32).is_err());
+        assert!(check_coset_shifts(&[F::one(), g, F::zero()], 3, 4, 32).is_err());
+        // k_2 * H = k_1 * H
+        let omega = primitive_nth_root_of_unity::<F>(4).unwrap();
+        assert!(check_coset_shifts(&[F::one(), g, g.mul(&omega)], 3, 4, 32).is_err());
+        // k_1 * H' = H'
+        let omega_m = primitive_nth_root_of_unity::<F>(32).unwrap();
+        assert!(check_coset_shifts(&[F::one(), omega_m, g], 3, 4, 32).is_err());
+    }
+
     #[test]
     fn test_circuit_cs() {
         let mut cs = PlonkConstraintSystem::<F>::new(3);
@@ -720,4 +1339,34 @@ mod test {
         assert!(cs.verify_witness(&[two, one, three, two], &[]).is_err());
         assert!(cs.verify_witness(&[two, one, three, three], &[]).is_ok());
     }
+
+    #[test]
+    fn test_eval_domains() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(20, &mut prng);
+        let mut cs = PlonkConstraintSystem::<F>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.insert_mul_gate(0, 1, 2);
+        cs.pad();
+        let params = preprocess_prover(&cs, &pcs, [0u8; 32]).unwrap();
+
+        let domain = params.eval_domain();
+        assert_eq!(domain.size(), cs.size());
+        assert_eq!(domain.elements().collect::<Vec<F>>(), params.group);
+        let quot_domain = params.quot_eval_domain();
+        assert_eq!(quot_domain.size(), cs.quot_eval_dom_size());
+        assert_eq!(
+            quot_domain.elements().collect::<Vec<F>>(),
+            params.coset_quot
+        );
+        assert_eq!(quot_domain.ifft(&params.L1_coset_evals), params.L1);
+
+        let verifier_params = params.get_verifier_params_ref();
+        assert_eq!(verifier_params.eval_domain(), domain);
+        for i in 0..cs.n_wires_per_gate() {
+            let coset = verifier_params.wire_coset(i).unwrap();
+            assert_eq!(coset.offset(), &verifier_params.k[i]);
+        }
+        assert!(verifier_params.wire_coset(cs.n_wires_per_gate()).is_none());
+    }
 }
@@ -0,0 +1,481 @@
+// This file implements the canonical binary encoding of PlonkProof, a stable wire format for
+// the proofs stored on chain. Every element of a proof has a fixed size encoding with a single
+// valid encoding per element, see CanonicalBytes, so that a proof has exactly one encoding, of
+// a size determined by its shape, see proof_bytes_len.
+// Layout, integers in little endian:
+// - the format version PROOF_FORMAT_VERSION (u8),
+// - the protocol version of the proof (u32), see plonk_setup::PLONK_PROTOCOL_VERSION,
+// - the number of wires per gate (u32),
+// - a tag (u8), 1 if the proof has a lookup argument and 0 otherwise,
+// - the commitments to the witness polynomials, to the split quotient polynomial (one per wire)
+//   and to Sigma,
+// - the evaluations of the witness polynomials at beta, of Sigma at g * beta and of the
+//   permutations but the last at beta, then for the proofs of version 0, the evaluation of the
+//   linearization polynomial at beta,
+// - the commitment and the evaluation proof of the batch evaluation proof,
+// - for the lookup argument, the commitments to h1, h2 and Z, then its evaluations in the order
+//   of the fields of PlonkLookupProof.
+// Decoding rejects an encoding of another format version, of an unsupported protocol version,
+// of a size other than the size of its shape, e.g. with trailing bytes, and non-canonical
+// encodings of scalars and group elements. With KZG over BLS12-381, commitments and evaluation
+// proofs are compressed G1 points of 48 bytes and evaluations are scalars of 32 bytes, hence a
+// proof has 10 + 160 * n_wires_per_gate + 144 bytes, e.g. 954 bytes for the 5 wires of
+// TurboPlonkConstraintSystem, plus 464 bytes for the lookup argument, or 32 bytes for L(beta) in
+// the proofs of version 0.
+// The serde encoding of the proofs is the one of protocol.rs; fields annotated with
+// `#[serde(with = "utils::serialization::zei_obj_serde")]` use the canonical encoding instead.
+
+use crate::commitments::kzg_poly_com::{KZGCommitment, KZGEvalProof};
+use crate::commitments::pcs::BatchProofEval;
+use crate::plonk::errors::PlonkError;
+use crate::plonk::lookup::PlonkLookupProof;
+use crate::plonk::plonk_setup::check_plonk_version;
+use crate::plonk::protocol::prover::PlonkProof;
+use algebra::bls12_381::{BLSScalar, BLSG1};
+use algebra::groups::{Group, Scalar};
+use ruc::*;
+use utils::serialization::ZeiFromToBytes;
+
+/// Version of the encoding of proofs written by `to_bytes`
+pub const PROOF_FORMAT_VERSION: u8 = 1;
+
+// Length of the format version, the protocol version, the number of wires and the lookup tag
+const PROOF_HEADER_LEN: usize = 10;
+
+/// Fixed size encoding of an element of a proof, with a single valid encoding per element
+pub trait CanonicalBytes: Sized {
+    /// Number of bytes of an encoding
+    const BYTES_LEN: usize;
+
+    /// Encodes the element in BYTES_LEN bytes
+    fn to_canonical_bytes(&self) -> Vec<u8>;
+
+    /// Decodes an element encoded by to_canonical_bytes, and rejects any other encoding
+    fn from_canonical_bytes(bytes: &[u8]) -> Result<Self>;
+}
+
+// Decodes `bytes` with `decode` and checks that `bytes` is the canonical encoding of the result
+fn decode_canonical<T: CanonicalBytes, D: Fn(&[u8]) -> Result<T>>(
+    bytes: &[u8],
+    decode: D,
+) -> Result<T> {
+    if bytes.len() != T::BYTES_LEN {
+        return Err(eg!(PlonkError::DeserializationError));
+    }
+    let value = decode(bytes).c(d!(PlonkError::DeserializationError))?;
+    if value.to_canonical_bytes() != bytes {
+        return Err(eg!(PlonkError::DeserializationError));
+    }
+    Ok(value)
+}
+
+impl CanonicalBytes for BLSScalar {
+    const BYTES_LEN: usize = 32;
+
+    fn to_canonical_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    // Scalar::from_bytes reduces the integer modulo the order of the field, the canonical
+    // encoding is the one of an integer lower than the order
+    fn from_canonical_bytes(bytes: &[u8]) -> Result<BLSScalar> {
+        decode_canonical(bytes, BLSScalar::from_bytes)
+    }
+}
+
+impl CanonicalBytes for KZGCommitment<BLSG1> {
+    const BYTES_LEN: usize = 48;
+
+    fn to_canonical_bytes(&self) -> Vec<u8> {
+        self.value.to_compressed_bytes()
+    }
+
+    fn from_canonical_bytes(bytes: &[u8]) -> Result<KZGCommitment<BLSG1>> {
+        decode_canonical(bytes, |bytes| {
+            let value = BLSG1::from_compressed_bytes(bytes).c(d!())?;
+            Ok(KZGCommitment { value })
+        })
+    }
+}
+
+impl CanonicalBytes for KZGEvalProof<BLSG1> {
+    const BYTES_LEN: usize = 48;
+
+    fn to_canonical_bytes(&self) -> Vec<u8> {
+        self.0.to_compressed_bytes()
+    }
+
+    fn from_canonical_bytes(bytes: &[u8]) -> Result<KZGEvalProof<BLSG1>> {
+        decode_canonical(bytes, |bytes| {
+            Ok(KZGEvalProof(BLSG1::from_compressed_bytes(bytes).c(d!())?))
+        })
+    }
+}
+
+/// Returns the number of bytes of the encoding of a proof of protocol version `version` for
+/// `n_wires_per_gate` wires per gate, with a lookup argument if `lookup` is true, see
+/// proof_bytes.rs.
+pub fn proof_bytes_len<C: CanonicalBytes, E: CanonicalBytes, F: CanonicalBytes>(
+    version: u32,
+    n_wires_per_gate: usize,
+    lookup: bool,
+) -> usize {
+    let mut len = PROOF_HEADER_LEN
+        + (2 * n_wires_per_gate + 2) * C::BYTES_LEN
+        + E::BYTES_LEN
+        + 2 * n_wires_per_gate * F::BYTES_LEN;
+    if version == 0 {
+        len += F::BYTES_LEN;
+    }
+    if lookup {
+        len += 3 * C::BYTES_LEN + 10 * F::BYTES_LEN;
+    }
+    len
+}
+
+// Reads the elements of an encoded proof from a byte slice
+struct ProofReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ProofReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let mut array = [0u8; 4];
+        array.copy_from_slice(self.read_bytes(4).c(d!())?);
+        Ok(u32::from_le_bytes(array))
+    }
+
+    fn read<T: CanonicalBytes>(&mut self) -> Result<T> {
+        T::from_canonical_bytes(self.read_bytes(T::BYTES_LEN).c(d!())?).c(d!())
+    }
+
+    fn read_vec<T: CanonicalBytes>(&mut self, len: usize) -> Result<Vec<T>> {
+        (0..len).map(|_| self.read()).collect()
+    }
+}
+
+impl<C: CanonicalBytes, E: CanonicalBytes, F: CanonicalBytes> PlonkProof<C, E, F> {
+    /// Encodes the proof in the binary format of version PROOF_FORMAT_VERSION, of
+    /// proof_bytes_len bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let n_wires_per_gate = self.C_witness_polys.len();
+        let mut bytes = Vec::with_capacity(proof_bytes_len::<C, E, F>(
+            self.version,
+            n_wires_per_gate,
+            self.lookup.is_some(),
+        ));
+        bytes.push(PROOF_FORMAT_VERSION);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&(n_wires_per_gate as u32).to_le_bytes());
+        bytes.push(self.lookup.is_some() as u8);
+        for commitment in self
+            .C_witness_polys
+            .iter()
+            .chain(self.C_q_polys.iter())
+            .chain([&self.C_Sigma])
+        {
+            bytes.extend(commitment.to_canonical_bytes());
+        }
+        for eval in self
+            .witness_polys_eval_beta
+            .iter()
+            .chain([&self.Sigma_eval_g_beta])
+            .chain(self.perms_eval_beta.iter())
+            .chain(self.L_eval_beta.iter())
+        {
+            bytes.extend(eval.to_canonical_bytes());
+        }
+        bytes.extend(self.batch_eval_proof.commitment.to_canonical_bytes());
+        bytes.extend(self.batch_eval_proof.eval_proof.to_canonical_bytes());
+        if let Some(lookup) = &self.lookup {
+            for commitment in [&lookup.C_h1, &lookup.C_h2, &lookup.C_Z] {
+                bytes.extend(commitment.to_canonical_bytes());
+            }
+            for eval in [
+                &lookup.q_lookup_eval_beta,
+                &lookup.q_table_eval_beta,
+                &lookup.t_eval_beta,
+                &lookup.h1_eval_beta,
+                &lookup.h2_eval_beta,
+                &lookup.Z_eval_beta,
+                &lookup.t_eval_g_beta,
+                &lookup.h1_eval_g_beta,
+                &lookup.h2_eval_g_beta,
+                &lookup.Z_eval_g_beta,
+            ] {
+                bytes.extend(eval.to_canonical_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Decodes a proof encoded by `to_bytes`.
+    /// Returns UnsupportedVersion for an encoding of another format version or a proof of an
+    /// unsupported protocol version, and DeserializationError for a malformed or non-canonical
+    /// encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PlonkProof<C, E, F>> {
+        let mut reader = ProofReader { bytes };
+        if reader.read_bytes(1).c(d!())?[0] != PROOF_FORMAT_VERSION {
+            return Err(eg!(PlonkError::UnsupportedVersion));
+        }
+        let version = reader.read_u32().c(d!())?;
+        check_plonk_version(version).c(d!())?;
+        let n_wires_per_gate = reader.read_u32().c(d!())? as usize;
+        let lookup = match reader.read_bytes(1).c(d!())?[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(eg!(PlonkError::DeserializationError)),
+        };
+        // the size check bounds the number of wires before anything is allocated
+        if n_wires_per_gate == 0
+            || n_wires_per_gate > bytes.len()
+            || bytes.len()
+                != proof_bytes_len::<C, E, F>(version, n_wires_per_gate, lookup)
+            || (version == 0 && lookup)
+        {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        let C_witness_polys = reader.read_vec(n_wires_per_gate).c(d!())?;
+        let C_q_polys = reader.read_vec(n_wires_per_gate).c(d!())?;
+        let C_Sigma = reader.read().c(d!())?;
+        let witness_polys_eval_beta = reader.read_vec(n_wires_per_gate).c(d!())?;
+        let Sigma_eval_g_beta = reader.read().c(d!())?;
+        let perms_eval_beta = reader.read_vec(n_wires_per_gate - 1).c(d!())?;
+        let L_eval_beta = if version == 0 {
+            Some(reader.read().c(d!())?)
+        } else {
+            None
+        };
+        let batch_eval_proof = BatchProofEval {
+            commitment: reader.read().c(d!())?,
+            eval_proof: reader.read().c(d!())?,
+        };
+        let lookup = if lookup {
+            Some(PlonkLookupProof {
+                C_h1: reader.read().c(d!())?,
+                C_h2: reader.read().c(d!())?,
+                C_Z: reader.read().c(d!())?,
+                q_lookup_eval_beta: reader.read().c(d!())?,
+                q_table_eval_beta: reader.read().c(d!())?,
+                t_eval_beta: reader.read().c(d!())?,
+                h1_eval_beta: reader.read().c(d!())?,
+                h2_eval_beta: reader.read().c(d!())?,
+                Z_eval_beta: reader.read().c(d!())?,
+                t_eval_g_beta: reader.read().c(d!())?,
+                h1_eval_g_beta: reader.read().c(d!())?,
+                h2_eval_g_beta: reader.read().c(d!())?,
+                Z_eval_g_beta: reader.read().c(d!())?,
+            })
+        } else {
+            None
+        };
+        Ok(PlonkProof {
+            C_witness_polys,
+            C_q_polys,
+            C_Sigma,
+            witness_polys_eval_beta,
+            Sigma_eval_g_beta,
+            perms_eval_beta,
+            L_eval_beta,
+            batch_eval_proof,
+            version,
+            lookup,
+        })
+    }
+}
+
+impl<C: CanonicalBytes, E: CanonicalBytes, F: CanonicalBytes> ZeiFromToBytes
+    for PlonkProof<C, E, F>
+{
+    fn zei_to_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn zei_from_bytes(bytes: &[u8]) -> Result<PlonkProof<C, E, F>> {
+        PlonkProof::from_bytes(bytes).c(d!())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitment, KZGCommitmentScheme, KZGCommitmentSchemeBLS, KZGEvalProof,
+    };
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, PlonkConstraintSystem,
+    };
+    use crate::plonk::proof_bytes::{proof_bytes_len, PROOF_FORMAT_VERSION};
+    use crate::plonk::protocol::prover::{prover, verifier, PlonkPf};
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::{BLSScalar, BLSG1};
+    use algebra::groups::{One, Scalar, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    type Proof = PlonkPf<KZGCommitmentSchemeBLS>;
+
+    fn kzg_proof_bytes_len(
+        version: u32,
+        n_wires_per_gate: usize,
+        lookup: bool,
+    ) -> usize {
+        proof_bytes_len::<KZGCommitment<BLSG1>, KZGEvalProof<BLSG1>, BLSScalar>(
+            version,
+            n_wires_per_gate,
+            lookup,
+        )
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct StoredProof {
+        #[serde(with = "utils::serialization::zei_obj_serde")]
+        proof: Proof,
+    }
+
+    #[test]
+    fn test_proof_bytes() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let prover_params = preprocess_prover(&cs, &pcs, [0u8; 32]).unwrap();
+        let verifier_params = preprocess_verifier(&cs, &pcs, [0u8; 32]).unwrap();
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three],
+        )
+        .unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), kzg_proof_bytes_len(1, 3, false));
+        assert_eq!(bytes.len(), 10 + 160 * 3 + 144);
+        assert_eq!(bytes[0], PROOF_FORMAT_VERSION);
+        let decoded = Proof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, proof);
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[three],
+            &decoded
+        )
+        .is_ok());
+
+        // the serde encoding of the annotated fields is the canonical encoding
+        let stored = StoredProof { proof };
+        let encoded = bincode::serialize(&stored).unwrap();
+        let stored: StoredProof = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(stored.proof.to_bytes(), bytes);
+        let json = serde_json::to_string(&stored).unwrap();
+        let stored: StoredProof = serde_json::from_str(&json).unwrap();
+        assert_eq!(stored.proof.to_bytes(), bytes);
+
+        // trailing bytes
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(Proof::from_bytes(&longer).is_err());
+        // truncated proof
+        assert!(Proof::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        // another format version
+        let mut other = bytes.clone();
+        other[0] = PROOF_FORMAT_VERSION + 1;
+        assert!(Proof::from_bytes(&other).is_err());
+        // an unsupported protocol version
+        let mut other = bytes.clone();
+        other[1..5].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Proof::from_bytes(&other).is_err());
+        // another number of wires
+        let mut other = bytes.clone();
+        other[5..9].copy_from_slice(&4u32.to_le_bytes());
+        assert!(Proof::from_bytes(&other).is_err());
+        // a non-canonical scalar: the first evaluation is replaced by an integer larger than
+        // the order of the field
+        let first_eval = 10 + 7 * 48;
+        let mut other = bytes.clone();
+        for byte in other[first_eval..first_eval + 32].iter_mut() {
+            *byte = 0xff;
+        }
+        assert!(Proof::from_bytes(&other).is_err());
+        // an invalid group element
+        let mut other = bytes;
+        for byte in other[10..10 + 48].iter_mut() {
+            *byte = 0xff;
+        }
+        assert!(Proof::from_bytes(&other).is_err());
+
+        // the proofs of version 0 also have L(beta)
+        let mut prover_params = prover_params;
+        prover_params.set_version(0).unwrap();
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three],
+        )
+        .unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), kzg_proof_bytes_len(0, 3, false));
+        assert_eq!(bytes.len(), 10 + 160 * 3 + 144 + 32);
+        assert_eq!(Proof::from_bytes(&bytes).unwrap(), proof);
+        let mut other = bytes;
+        other[1..5].copy_from_slice(&1u32.to_le_bytes());
+        assert!(Proof::from_bytes(&other).is_err());
+    }
+
+    #[test]
+    fn test_lookup_proof_bytes() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(100, &mut prng);
+        let mut cs = TurboPlonkConstraintSystem::<BLSScalar>::new();
+        let values: Vec<BLSScalar> = (0..4).map(BLSScalar::from_u32).collect();
+        let table_id = cs.add_lookup_table(&values);
+        let x = cs.new_variable(BLSScalar::from_u32(3));
+        cs.lookup(x, table_id);
+        cs.pad();
+        let witness = cs.get_and_clear_witness();
+        let prover_params = preprocess_prover(&cs, &pcs, [0u8; 32]).unwrap();
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness,
+        )
+        .unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), kzg_proof_bytes_len(1, 5, true));
+        assert_eq!(bytes.len(), 954 + 464);
+        assert_eq!(Proof::from_bytes(&bytes).unwrap(), proof);
+        // the tag of a proof without lookup argument
+        let mut other = bytes;
+        other[9] = 0;
+        assert!(Proof::from_bytes(&other).is_err());
+    }
+}
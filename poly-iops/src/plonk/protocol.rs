@@ -47,6 +47,11 @@
 // Verifier and prover deterministically samples a group H of order n, k1,k2,k3, and compute polynomials q_L, q_R, q_M, q_O, \pi_1, \pi_2, \pi_3.
 // The verifier store a commitment representation to each of them.
 
+// Offline:
+// The prover computes the values of \pi_1, \pi_2, \pi_3 on H, and the evaluations of k_j * X on the coset of the quotient
+// polynomial. They depend on the circuit only, hence they are computed once and reused for every witness
+// (see prover::prover_offline and prover::prover_online).
+
 // Online:
 // 1. Prover uses extended witness to interpolate polynomials f1,f2,f3, commit to them, and append the commitments to the proof.
 // 2. The random challenges \gamma and \delta are sampled.
@@ -58,15 +63,21 @@
 //  - L(X): f1(\beta) * q_L(X) + f2(\beta) * q_R(X) + f1(\beta) * f2(\beta) * q_M(X) - f3(\beta) * q_O(X) +
 //        \alpha * \Sigma(X)* prod_{j=1}^3(f_j(\beta) + gamma * k_j*\beta + delta)) -
 //        \alpha * (\Sigma(g*\beta)* prod_{j=2}^3(f_j(\beta) + gamma * \pi_j(\beta) + delta)*gamma*\pi_3(X) +
-//        \alpha^2 * Sigma(X) (\beta^n - 1)/(\beta - 1)
-// 8. Prover appends f1(\beta), f2(\beta), f3(\beta), \pi_1(\beta), \pi_2(\beta), L(\beta), \Sigma(beta*g) to the proof, together with a batch
-// proof of the correctness of these values, including a proof for Q(\beta) = Q0(\beta) + \beta^{n} * Q1(\beta) + \beta^{2*n} * Q2(\beta).
-// 9. Verifier computes element Q(\beta) = P(\beta)/(\beta^n -1) =
-//   (L(\beta) -  \alpha * (\Sigma(g*\beta)* prod_{j=2}^3(f_j(\beta) + gamma * \pi_j(\beta) + delta)*f3(\beta + delta) -
-//   (\beta^n - 1)/(\beta - 1))/ (\beta^n -1).
-// 10. Verifier homomorphically derives commitment to L(X).
-// 11. Verifier batch verify the eval proofs for f1(\beta), f2(\beta), f3(\beta), \pi_1(\beta), \pi_2(\beta), L(\beta), \Sigma(beta*g),
-// Q(\beta) =  = Q0(\beta) + \beta^{n} * Q1(\beta) + \beta^{2*n} * Q2(\beta).
+//        \alpha^2 * Sigma(X) (\beta^n - 1)/(\beta - 1) -
+//        (\beta^n - 1) * (Q0(X) + \beta^{n+2} * Q1(X) + \beta^{2*(n+2)} * Q2(X))
+// 8. Prover appends f1(\beta), f2(\beta), f3(\beta), \pi_1(\beta), \pi_2(\beta), \Sigma(beta*g) to the proof, together with a batch
+// proof of the correctness of these values and of L(\beta).
+// 9. Verifier computes element L(\beta), which is determined by P(\beta) - Q(\beta) * (\beta^n -1) = 0:
+//   L(\beta) = \alpha * \Sigma(g*\beta)* prod_{j=1}^2(f_j(\beta) + gamma * \pi_j(\beta) + delta)*(f3(\beta) + delta) +
+//   \alpha^2 * (\beta^n - 1)/(\beta - 1) - PI(\beta).
+// Hence L(\beta) is not sent in the proof, and Q(X) is not opened on its own (linearization trick).
+// 10. Verifier homomorphically derives commitment to L(X), from the commitments to the selectors, \pi_3, \Sigma and Q0, Q1, Q2.
+// 11. Verifier batch verify the eval proofs for f1(\beta), f2(\beta), f3(\beta), \pi_1(\beta), \pi_2(\beta), L(\beta), \Sigma(beta*g).
+
+// Lookup gates:
+// The lookup gates of a constraint system (e.g. TurboPlonkConstraintSystem::lookup) are checked with
+// the Plookup argument of plonk::lookup, which adds commitments, evaluations and terms of P(X)
+// to the steps above. The proofs of constraint systems without lookup tables are unchanged.
 
 // Adding Zero-Knowledge:
 //  - each fi polynomial is randomized by adding a blinding polynomial of degree 1 that vanishes on H: fi(X) -> (bi1 + X bi2) * (X^n - 1) + fi(X)
@@ -75,29 +86,52 @@
 
 #[allow(non_snake_case)]
 pub mod prover {
+    use crate::backend::{ArithmeticBackend, CpuBackend};
+    use crate::commitments::kzg_poly_com::{KZGCommitment, KZGCommitmentScheme};
     use crate::commitments::pcs::{BatchProofEval, PolyComScheme};
     use crate::commitments::transcript::PolyComTranscript;
     use crate::plonk::errors::PlonkError;
+    use crate::plonk::lookup::{
+        lookup_commitments, lookup_eval_points, lookup_openings,
+        lookup_terms_coset_evals, lookup_terms_eval_beta, prover_lookup_evals,
+        prover_lookup_grand_product, prover_lookup_sorted, verifier_lookup_challenges,
+        LookupQuotientInputs, PlonkLookupProof,
+    };
     use crate::plonk::plonk_helpers::{
-        combine_q_polys, derive_Q_eval_beta, eval_public_var_poly, hide_polynomial,
-        linearization_commitment, linearization_polynomial_opening, split_Q_and_commit,
-        PlonkChallenges, PublicVars_polynomial, Quotient_polynomial, Sigma_polynomial,
+        combine_q_polys, derive_L_eval_beta, derive_Q_eval_beta, eval_public_var_poly,
+        hide_polynomial, linearization_commitment, linearization_polynomial_opening,
+        split_Q_and_commit, PlonkChallenges, PublicVars_polynomial, Quotient_polynomial,
+        Sigma_polynomial,
+    };
+    use crate::plonk::plonk_setup::{
+        check_plonk_version, perm_values, ConstraintSystem, ProverParams, VerifierParams,
     };
-    use crate::plonk::plonk_setup::{ConstraintSystem, ProverParams, VerifierParams};
     use crate::plonk::transcript::{
         transcript_get_plonk_challenge_alpha, transcript_get_plonk_challenge_beta,
         transcript_get_plonk_challenge_delta, transcript_get_plonk_challenge_gamma,
-        transcript_init_plonk,
+        transcript_init_plonk, PlonkTranscript,
     };
     use crate::polynomials::field_polynomial::FpPolynomial;
-    use algebra::groups::ScalarArithmetic;
-    use merlin::Transcript;
+    use algebra::groups::{Pairing, ScalarArithmetic, Zero};
+    use algebra::pairing_batch::{PairingCheck, PairingCheckBatch};
     use rand_core::{CryptoRng, RngCore};
     use ruc::*;
+    use serde::de::{SeqAccess, Visitor};
+    use serde::ser::{Error as _, SerializeTuple};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::marker::PhantomData;
+    use utils::serialization::versioned_serde::{
+        next_element, next_versioned_head, VersionedHead, VERSIONED_ENCODING_MAX_LEN,
+        VERSIONED_ENCODING_TAG,
+    };
+    use utils::serialization::Validate;
 
     /// A PlonkProof is generic on the polynomial commitment scheme, PCS.
     /// PCS is generic in the commitment group C, the eval proof type E, and Field elements F.
-    #[derive(Debug, Deserialize, Eq, PartialEq, Serialize, Clone)]
+    /// The binary encoding starts with the version, see utils::serialization::versioned_serde,
+    /// except for the proofs of version 0, which keep the encoding from before the version was
+    /// recorded and decode with version 0.
+    #[derive(Debug, Eq, PartialEq, Clone)]
     pub struct PlonkProof<C, E, F> {
         pub(crate) C_witness_polys: Vec<C>,
         pub(crate) C_q_polys: Vec<C>, // splitted quotient polynomials
@@ -105,8 +139,223 @@ pub mod prover {
         pub(crate) witness_polys_eval_beta: Vec<F>,
         pub(crate) Sigma_eval_g_beta: F,
         pub(crate) perms_eval_beta: Vec<F>,
-        pub(crate) L_eval_beta: F,
+        // evaluation of the linearization polynomial at beta, only sent in the proofs of
+        // version 0, see PLONK_PROTOCOL_VERSION
+        pub(crate) L_eval_beta: Option<F>,
         pub(crate) batch_eval_proof: BatchProofEval<C, E>,
+        // protocol version, see plonk_setup::PLONK_PROTOCOL_VERSION
+        pub(crate) version: u32,
+        // lookup argument, None if the constraint system has no lookup table
+        pub(crate) lookup: Option<PlonkLookupProof<C, F>>,
+    }
+
+    impl<C, E, F> PlonkProof<C, E, F> {
+        /// Returns the protocol version of the proof
+        pub fn version(&self) -> u32 {
+            self.version
+        }
+
+        /// Returns the commitments to the witness polynomials, one per wire
+        pub fn witness_commitments(&self) -> &[C] {
+            &self.C_witness_polys
+        }
+    }
+
+    // Encoding of the fields by name, for self-describing encodings
+    #[derive(Serialize)]
+    struct PlonkProofFieldsRef<'a, C, E, F> {
+        C_witness_polys: &'a Vec<C>,
+        C_q_polys: &'a Vec<C>,
+        C_Sigma: &'a C,
+        witness_polys_eval_beta: &'a Vec<F>,
+        Sigma_eval_g_beta: &'a F,
+        perms_eval_beta: &'a Vec<F>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        L_eval_beta: &'a Option<F>,
+        batch_eval_proof: &'a BatchProofEval<C, E>,
+        version: u32,
+        lookup: &'a Option<PlonkLookupProof<C, F>>,
+    }
+
+    #[derive(Deserialize)]
+    struct PlonkProofFields<C, E, F> {
+        C_witness_polys: Vec<C>,
+        C_q_polys: Vec<C>,
+        C_Sigma: C,
+        witness_polys_eval_beta: Vec<F>,
+        Sigma_eval_g_beta: F,
+        perms_eval_beta: Vec<F>,
+        #[serde(default)]
+        L_eval_beta: Option<F>,
+        batch_eval_proof: BatchProofEval<C, E>,
+        #[serde(default)]
+        version: u32,
+        #[serde(default)]
+        lookup: Option<PlonkLookupProof<C, F>>,
+    }
+
+    impl<C: Serialize, E: Serialize, F: Serialize> Serialize for PlonkProof<C, E, F> {
+        fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            if serializer.is_human_readable() {
+                return PlonkProofFieldsRef {
+                    C_witness_polys: &self.C_witness_polys,
+                    C_q_polys: &self.C_q_polys,
+                    C_Sigma: &self.C_Sigma,
+                    witness_polys_eval_beta: &self.witness_polys_eval_beta,
+                    Sigma_eval_g_beta: &self.Sigma_eval_g_beta,
+                    perms_eval_beta: &self.perms_eval_beta,
+                    L_eval_beta: &self.L_eval_beta,
+                    batch_eval_proof: &self.batch_eval_proof,
+                    version: self.version,
+                    lookup: &self.lookup,
+                }
+                .serialize(serializer);
+            }
+            if self.version == 0 {
+                // the encoding from before the version was recorded
+                let L_eval_beta = match (&self.L_eval_beta, &self.lookup) {
+                    (Some(L_eval_beta), None) => L_eval_beta,
+                    _ => {
+                        return Err(S::Error::custom(
+                            "malformed PLONK proof of version 0",
+                        ))
+                    }
+                };
+                let mut tuple = serializer.serialize_tuple(8)?;
+                tuple.serialize_element(&self.C_witness_polys)?;
+                tuple.serialize_element(&self.C_q_polys)?;
+                tuple.serialize_element(&self.C_Sigma)?;
+                tuple.serialize_element(&self.witness_polys_eval_beta)?;
+                tuple.serialize_element(&self.Sigma_eval_g_beta)?;
+                tuple.serialize_element(&self.perms_eval_beta)?;
+                tuple.serialize_element(L_eval_beta)?;
+                tuple.serialize_element(&self.batch_eval_proof)?;
+                return tuple.end();
+            }
+            let mut tuple = serializer.serialize_tuple(10)?;
+            tuple.serialize_element(&VERSIONED_ENCODING_TAG)?;
+            tuple.serialize_element(&self.version)?;
+            tuple.serialize_element(&self.C_witness_polys)?;
+            tuple.serialize_element(&self.C_q_polys)?;
+            tuple.serialize_element(&self.C_Sigma)?;
+            tuple.serialize_element(&self.witness_polys_eval_beta)?;
+            tuple.serialize_element(&self.Sigma_eval_g_beta)?;
+            tuple.serialize_element(&self.perms_eval_beta)?;
+            tuple.serialize_element(&self.batch_eval_proof)?;
+            tuple.serialize_element(&self.lookup)?;
+            tuple.end()
+        }
+    }
+
+    impl<'de, C, E, F> Deserialize<'de> for PlonkProof<C, E, F>
+    where
+        C: Deserialize<'de>,
+        E: Deserialize<'de>,
+        F: Deserialize<'de>,
+    {
+        fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            if deserializer.is_human_readable() {
+                let fields = PlonkProofFields::deserialize(deserializer)?;
+                return Ok(PlonkProof {
+                    C_witness_polys: fields.C_witness_polys,
+                    C_q_polys: fields.C_q_polys,
+                    C_Sigma: fields.C_Sigma,
+                    witness_polys_eval_beta: fields.witness_polys_eval_beta,
+                    Sigma_eval_g_beta: fields.Sigma_eval_g_beta,
+                    perms_eval_beta: fields.perms_eval_beta,
+                    L_eval_beta: fields.L_eval_beta,
+                    batch_eval_proof: fields.batch_eval_proof,
+                    version: fields.version,
+                    lookup: fields.lookup,
+                });
+            }
+            deserializer.deserialize_tuple(
+                VERSIONED_ENCODING_MAX_LEN,
+                PlonkProofVisitor(PhantomData),
+            )
+        }
+    }
+
+    struct PlonkProofVisitor<C, E, F>(PhantomData<(C, E, F)>);
+
+    impl<'de, C, E, F> Visitor<'de> for PlonkProofVisitor<C, E, F>
+    where
+        C: Deserialize<'de>,
+        E: Deserialize<'de>,
+        F: Deserialize<'de>,
+    {
+        type Value = PlonkProof<C, E, F>;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("a PLONK proof")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> core::result::Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            // the proofs from before the version was recorded send L(beta) after the
+            // permutation evaluations and have no lookup argument
+            let (version, C_witness_polys, legacy) = match next_versioned_head(&mut seq)?
+            {
+                VersionedHead::Versioned(version) => {
+                    (version, next_element(&mut seq)?, false)
+                }
+                VersionedHead::Legacy(C_witness_polys) => (0, C_witness_polys, true),
+            };
+            let C_q_polys = next_element(&mut seq)?;
+            let C_Sigma = next_element(&mut seq)?;
+            let witness_polys_eval_beta = next_element(&mut seq)?;
+            let Sigma_eval_g_beta = next_element(&mut seq)?;
+            let perms_eval_beta = next_element(&mut seq)?;
+            let L_eval_beta = if legacy {
+                Some(next_element(&mut seq)?)
+            } else {
+                None
+            };
+            Ok(PlonkProof {
+                C_witness_polys,
+                C_q_polys,
+                C_Sigma,
+                witness_polys_eval_beta,
+                Sigma_eval_g_beta,
+                perms_eval_beta,
+                L_eval_beta,
+                batch_eval_proof: next_element(&mut seq)?,
+                version,
+                lookup: if legacy {
+                    None
+                } else {
+                    next_element(&mut seq)?
+                },
+            })
+        }
+    }
+
+    impl<C, E, F> Validate for PlonkProof<C, E, F> {
+        /// Checks that the proof has a supported version, an evaluation for each witness
+        /// polynomial commitment, one permutation evaluation less, some quotient polynomial
+        /// commitments, and L(beta) but no lookup argument if and only if it has version 0
+        fn validate(&self) -> Result<()> {
+            check_plonk_version(self.version).c(d!())?;
+            let n_wires_per_gate = self.C_witness_polys.len();
+            if n_wires_per_gate == 0
+                || self.C_q_polys.is_empty()
+                || self.witness_polys_eval_beta.len() != n_wires_per_gate
+                || self.perms_eval_beta.len() != n_wires_per_gate - 1
+                || self.L_eval_beta.is_some() != (self.version == 0)
+                || (self.version == 0 && self.lookup.is_some())
+            {
+                return Err(eg!(PlonkError::VerificationError));
+            }
+            Ok(())
+        }
     }
 
     pub type PlonkPf<PCS> = PlonkProof<
@@ -158,30 +407,305 @@ pub mod prover {
     /// assert!(verifier(&mut transcript, &pcs, &cs, &verifier_params, &[], &proof).is_ok())
     /// ```
     pub fn prover<
+        T: PlonkTranscript,
+        R: CryptoRng + RngCore,
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+    >(
+        prng: &mut R,
+        transcript: &mut T,
+        pcs: &PCS,
+        cs: &CS,
+        params: &ProverParams<PCS>,
+        witness: &[PCS::Field],
+    ) -> Result<PlonkPf<PCS>> {
+        let offline = prover_offline(cs, params).c(d!())?;
+        prover_online(prng, transcript, pcs, cs, params, &offline, witness).c(d!())
+    }
+
+    /// Same as plonk::prover::prover, with the MSMs and FFTs of the witness, Sigma and
+    /// quotient polynomials computed by `backend` (see crate::backend). The proof is the one
+    /// of plonk::prover::prover with the same randomness
+    pub fn prover_with_backend<
+        T: PlonkTranscript,
+        R: CryptoRng + RngCore,
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+        B: ArithmeticBackend,
+    >(
+        prng: &mut R,
+        transcript: &mut T,
+        pcs: &PCS,
+        cs: &CS,
+        params: &ProverParams<PCS>,
+        witness: &[PCS::Field],
+        backend: &B,
+    ) -> Result<PlonkPf<PCS>> {
+        let offline = prover_offline(cs, params).c(d!())?;
+        prover_online_with_backend(
+            prng, transcript, pcs, cs, params, &offline, witness, backend,
+        )
+        .c(d!())
+    }
+
+    /// Witness independent values of the PLONK prover for a circuit, on top of the prover
+    /// parameters: the values of the extended permutations over the group H and the evaluations
+    /// of the identity permutations k_j * X over the domain of the quotient polynomial.
+    /// They are computed once per circuit by prover_offline and can be cached, so that the
+    /// proofs of the circuit only pay for the witness dependent work of prover_online
+    #[derive(Debug, Deserialize, Eq, PartialEq, Serialize, Clone)]
+    pub struct PlonkProverOffline<F> {
+        pub(crate) cs_size: usize,
+        // perm_1(g^0), ..., perm_1(g^{n-1}), perm_2(g^0), ...
+        pub(crate) perms_values: Vec<F>,
+        pub(crate) identity_perms_coset_evals: Vec<Vec<F>>,
+    }
+
+    /// Offline phase of plonk::prover::prover: computes the witness independent values of the
+    /// prover for the constraint system `cs` preprocessed into `params`
+    pub fn prover_offline<
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+    >(
+        cs: &CS,
+        params: &ProverParams<PCS>,
+    ) -> Result<PlonkProverOffline<PCS::Field>> {
+        let perms_values = extended_perms_values(cs, params).c(d!())?;
+        let identity_perms_coset_evals = params
+            .verifier_params
+            .k
+            .iter()
+            .map(|k_j| params.coset_quot.iter().map(|x| k_j.mul(x)).collect())
+            .collect();
+        Ok(PlonkProverOffline {
+            cs_size: cs.size(),
+            perms_values,
+            identity_perms_coset_evals,
+        })
+    }
+
+    // Returns the values of the extended permutations over the group H, perm_1 first
+    pub(in crate::plonk) fn extended_perms_values<
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+    >(
+        cs: &CS,
+        params: &ProverParams<PCS>,
+    ) -> Result<Vec<PCS::Field>> {
+        let n = cs.size();
+        let k = &params.verifier_params.k;
+        if n != params.verifier_params.cs_size || k.len() != cs.n_wires_per_gate() {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
+        let perm = cs.compute_permutation();
+        let mut perms_values = Vec::with_capacity(perm.len());
+        for perm_j in perm.chunks(n) {
+            perms_values.extend(perm_values(&params.group, perm_j, k));
+        }
+        Ok(perms_values)
+    }
+
+    /// Online phase of plonk::prover::prover: same as plonk::prover::prover, with the witness
+    /// independent values `offline` computed by prover_offline for `cs` and `params`
+    pub fn prover_online<
+        T: PlonkTranscript,
         R: CryptoRng + RngCore,
         PCS: PolyComScheme,
         CS: ConstraintSystem<Field = PCS::Field>,
     >(
         prng: &mut R,
-        transcript: &mut Transcript,
+        transcript: &mut T,
         pcs: &PCS,
         cs: &CS,
         params: &ProverParams<PCS>,
+        offline: &PlonkProverOffline<PCS::Field>,
         witness: &[PCS::Field],
     ) -> Result<PlonkPf<PCS>> {
+        prover_online_with_backend(
+            prng,
+            transcript,
+            pcs,
+            cs,
+            params,
+            offline,
+            witness,
+            &CpuBackend,
+        )
+        .c(d!())
+    }
+
+    /// Same as prover_online, with the MSMs and FFTs computed by `backend`, see
+    /// prover_with_backend
+    #[allow(clippy::too_many_arguments)]
+    pub fn prover_online_with_backend<
+        T: PlonkTranscript,
+        R: CryptoRng + RngCore,
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+        B: ArithmeticBackend,
+    >(
+        prng: &mut R,
+        transcript: &mut T,
+        pcs: &PCS,
+        cs: &CS,
+        params: &ProverParams<PCS>,
+        offline: &PlonkProverOffline<PCS::Field>,
+        witness: &[PCS::Field],
+        backend: &B,
+    ) -> Result<PlonkPf<PCS>> {
+        prover_online_with_witness_openings(
+            prng, transcript, pcs, cs, params, offline, witness, backend,
+        )
+        .map(|(proof, _)| proof)
+    }
+
+    /// Same as plonk::prover::prover, also returning the openings of the commitments to the
+    /// witness polynomials in the proof, one per wire, e.g. to prove that a wire value is the
+    /// value of an external commitment with plonk::pc_link
+    pub fn prover_with_witness_openings<
+        T: PlonkTranscript,
+        R: CryptoRng + RngCore,
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+    >(
+        prng: &mut R,
+        transcript: &mut T,
+        pcs: &PCS,
+        cs: &CS,
+        params: &ProverParams<PCS>,
+        witness: &[PCS::Field],
+    ) -> Result<(PlonkPf<PCS>, Vec<PCS::Opening>)> {
+        let offline = prover_offline(cs, params).c(d!())?;
+        prover_online_with_witness_openings(
+            prng,
+            transcript,
+            pcs,
+            cs,
+            params,
+            &offline,
+            witness,
+            &CpuBackend,
+        )
+        .c(d!())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn prover_online_with_witness_openings<
+        T: PlonkTranscript,
+        R: CryptoRng + RngCore,
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+        B: ArithmeticBackend,
+    >(
+        prng: &mut R,
+        transcript: &mut T,
+        pcs: &PCS,
+        cs: &CS,
+        params: &ProverParams<PCS>,
+        offline: &PlonkProverOffline<PCS::Field>,
+        witness: &[PCS::Field],
+        backend: &B,
+    ) -> Result<(PlonkPf<PCS>, Vec<PCS::Opening>)> {
+        if offline.cs_size != cs.size()
+            || offline.perms_values.len() != cs.n_wires_per_gate() * cs.size()
+        {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
+        prover_with_quotient(
+            prng,
+            transcript,
+            pcs,
+            cs,
+            params,
+            &offline.perms_values,
+            witness,
+            backend,
+            |witness_polys, Sigma, challenges, IO, lookup| {
+                let lookup_terms = match lookup {
+                    Some(lookup) => Some(
+                        lookup_terms_coset_evals(
+                            pcs,
+                            params,
+                            &lookup,
+                            witness_polys,
+                            challenges.get_alpha().unwrap(), // safe unwrap
+                        )
+                        .c(d!())?,
+                    ),
+                    None => None,
+                };
+                Quotient_polynomial::<PCS, CS, B>(
+                    backend,
+                    cs,
+                    params,
+                    &offline.identity_perms_coset_evals,
+                    witness_polys,
+                    Sigma,
+                    challenges,
+                    IO,
+                    lookup_terms.as_deref(),
+                )
+            },
+        )
+    }
+
+    /// Same as prover_online, with the values of the extended permutations over the group H
+    /// `perms_values`, and the quotient polynomial Q(X) computed by `quotient` from the witness
+    /// polynomials, Sigma(X), the challenges, the public inputs polynomial, and the lookup
+    /// argument, if any, e.g. in chunks by plonk::low_memory. The other MSMs
+    /// and FFTs of the witness, Sigma and quotient polynomials are computed by `backend`.
+    /// Also returns the openings of the witness polynomials
+    #[allow(clippy::too_many_arguments)]
+    pub(in crate::plonk) fn prover_with_quotient<
+        T: PlonkTranscript,
+        R: CryptoRng + RngCore,
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+        B: ArithmeticBackend,
+        Q: FnOnce(
+            &[FpPolynomial<PCS::Field>],
+            &FpPolynomial<PCS::Field>,
+            &PlonkChallenges<PCS::Field>,
+            &FpPolynomial<PCS::Field>,
+            Option<LookupQuotientInputs<PCS>>,
+        ) -> Result<FpPolynomial<PCS::Field>>,
+    >(
+        prng: &mut R,
+        transcript: &mut T,
+        pcs: &PCS,
+        cs: &CS,
+        params: &ProverParams<PCS>,
+        perms_values: &[PCS::Field],
+        witness: &[PCS::Field],
+        backend: &B,
+        quotient: Q,
+    ) -> Result<(PlonkPf<PCS>, Vec<PCS::Opening>)> {
+        // the proofs of version 0 have no lookup argument
+        let legacy = params.verifier_params.version == 0;
+        if legacy && params.lookup.is_some() {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
         let online_values: Vec<PCS::Field> = cs
             .public_vars_witness_indices()
             .iter()
             .map(|index| witness[*index])
             .collect();
         // Init transcript
-        transcript_init_plonk::<_, PCS::Field>(
+        transcript_init_plonk::<_, _, PCS::Field>(
             transcript,
             &params.verifier_params,
             &online_values,
+            params.verifier_params.version,
         );
         let mut challenges = PlonkChallenges::new();
         let n_constraints = cs.size();
+        // the hidden witness polynomials and Sigma(X) have degree up to n + 2
+        if pcs.max_degree() < n_constraints + 2 {
+            return Err(eg!(PlonkError::SrsTooShort(
+                pcs.max_degree(),
+                n_constraints + 2
+            )));
+        }
 
         // Prepare extended witness
         let extended_witness = cs.extend_witness(witness);
@@ -193,12 +717,14 @@ pub mod prover {
         let mut witness_openings = vec![];
         let mut C_witness_polys = vec![];
         for i in 0..n_wires_per_gate {
-            let mut f = FpPolynomial::ffti(
+            let mut f = backend.ifft(
                 root,
                 &extended_witness[i * n_constraints..(i + 1) * n_constraints],
             );
             hide_polynomial(prng, &mut f, 1, n_constraints);
-            let (C_f, O_f) = pcs.commit(f).c(d!(PlonkError::CommitmentError))?;
+            let (C_f, O_f) = pcs
+                .commit_with_backend(backend, f)
+                .c(d!(PlonkError::CommitmentError))?;
             transcript.append_commitment::<PCS::Commitment>(&C_f);
             witness_openings.push(O_f);
             C_witness_polys.push(C_f);
@@ -209,13 +735,49 @@ pub mod prover {
         let delta = transcript_get_plonk_challenge_delta(transcript, n_constraints);
         challenges.insert_gamma_delta(gamma, delta).unwrap(); // safe unwrap
 
+        // 2'. for the lookup gates, get challenge zeta, build the sorted polynomials h1, h2,
+        // hide them and commit, then get challenges epsilon and theta (see plonk::lookup)
+        let lookup_witness = match &params.lookup {
+            Some(lookup_params) => Some(
+                prover_lookup_sorted(
+                    prng,
+                    transcript,
+                    pcs,
+                    params,
+                    lookup_params,
+                    &extended_witness,
+                )
+                .c(d!())?,
+            ),
+            None => None,
+        };
+
         // 3. build sigma, hide it and commit
-        let mut Sigma =
-            Sigma_polynomial::<PCS, CS>(cs, params, &extended_witness, &challenges);
+        let mut Sigma = Sigma_polynomial::<PCS, CS>(
+            cs,
+            params,
+            perms_values,
+            &extended_witness,
+            &challenges,
+        );
         hide_polynomial(prng, &mut Sigma, 2, n_constraints);
-        let (C_Sigma, O_Sigma) = pcs.commit(Sigma).c(d!(PlonkError::CommitmentError))?;
+        let (C_Sigma, O_Sigma) = pcs
+            .commit_with_backend(backend, Sigma)
+            .c(d!(PlonkError::CommitmentError))?;
         transcript.append_commitment::<PCS::Commitment>(&C_Sigma);
 
+        // 3'. build the lookup grand product Z, hide it and commit
+        let lookup_Z = match &lookup_witness {
+            Some(lookup_witness) => {
+                let (C_Z, O_Z) =
+                    prover_lookup_grand_product(prng, pcs, params, lookup_witness)
+                        .c(d!())?;
+                transcript.append_commitment::<PCS::Commitment>(&C_Z);
+                Some((C_Z, O_Z))
+            }
+            None => None,
+        };
+
         // 4. get challenge alpha
         let alpha = transcript_get_plonk_challenge_alpha(transcript, n_constraints);
         challenges.insert_alpha(alpha).unwrap();
@@ -227,17 +789,20 @@ pub mod prover {
             .map(|open| pcs.polynomial_from_opening_ref(open))
             .collect();
         let Sigma = pcs.polynomial_from_opening_ref(&O_Sigma);
-        let Q = Quotient_polynomial::<PCS, CS>(
-            cs,
-            params,
-            &witness_polys,
-            &Sigma,
-            &challenges,
-            &IO,
-        )
-        .c(d!())?;
+        let lookup = match (&params.lookup, &lookup_witness, &lookup_Z) {
+            (Some(lookup_params), Some(lookup_witness), Some((_, O_Z))) => {
+                Some(LookupQuotientInputs {
+                    params: lookup_params,
+                    witness: lookup_witness,
+                    O_Z,
+                })
+            }
+            _ => None,
+        };
+        let Q = quotient(&witness_polys, &Sigma, &challenges, &IO, lookup).c(d!())?;
         let (C_q_polys, O_q_polys) =
-            split_Q_and_commit(pcs, &Q, n_wires_per_gate, n_constraints + 2).c(d!())?;
+            split_Q_and_commit(pcs, backend, &Q, n_wires_per_gate, n_constraints + 2)
+                .c(d!())?;
         for C_q in C_q_polys.iter() {
             transcript.append_commitment::<PCS::Commitment>(C_q);
         }
@@ -262,7 +827,11 @@ pub mod prover {
         let Sigma_eval_g_beta = pcs.eval_opening(&O_Sigma, &g_beta);
 
         challenges.insert_beta(beta).unwrap();
-        //  b). build linearization polynomial r_beta(X), and eval at beta
+        //  b). build linearization polynomial r_beta(X), which includes the combined quotient
+        //  polynomial, so that its evaluation at beta is derived by the verifier, except in the
+        //  proofs of version 0, which send L(beta) and open the combined quotient polynomial
+        let beta = challenges.get_beta().unwrap();
+        let O_q_combined = combine_q_polys(&O_q_polys, beta, n_constraints + 2);
         let witness_polys_eval_beta_as_ref: Vec<&PCS::Field> =
             witness_polys_eval_beta.iter().collect();
         let perms_eval_beta_as_ref: Vec<&PCS::Field> = perms_eval_beta.iter().collect();
@@ -270,6 +839,7 @@ pub mod prover {
             cs,
             params,
             &O_Sigma,
+            if legacy { None } else { Some(&O_q_combined) },
             &witness_polys_eval_beta_as_ref[..],
             &perms_eval_beta_as_ref[..],
             &Sigma_eval_g_beta,
@@ -278,10 +848,34 @@ pub mod prover {
         for eval_beta in witness_polys_eval_beta.iter().chain(perms_eval_beta.iter()) {
             transcript.append_field_elem(eval_beta);
         }
-        let beta = challenges.get_beta().unwrap();
-        let L_eval_beta = pcs.eval_opening(&O_L, &beta);
         transcript.append_field_elem(&Sigma_eval_g_beta);
-        transcript.append_field_elem(&L_eval_beta);
+        let L_eval_beta = if legacy {
+            let L_eval_beta = pcs.eval_opening(&O_L, beta);
+            transcript.append_field_elem(&L_eval_beta);
+            Some(L_eval_beta)
+        } else {
+            None
+        };
+
+        // 7'. c) evaluate the lookup polynomials at beta and g * beta
+        let lookup = match (&params.lookup, &lookup_witness, lookup_Z) {
+            (Some(lookup_params), Some(lookup_witness), Some((C_Z, O_Z))) => {
+                let (lookup_proof, O_t) = prover_lookup_evals(
+                    pcs,
+                    lookup_params,
+                    lookup_witness,
+                    C_Z,
+                    &O_Z,
+                    beta,
+                    &g_beta,
+                );
+                for eval in lookup_proof.evals().iter() {
+                    transcript.append_field_elem(eval);
+                }
+                Some((lookup_params, lookup_witness, lookup_proof, O_t, O_Z))
+            }
+            _ => None,
+        };
 
         // 8. batch eval proofs
         let mut openings: Vec<&PCS::Opening> = witness_openings
@@ -293,15 +887,22 @@ pub mod prover {
                     .take(cs.n_wires_per_gate() - 1),
             )
             .collect();
-        let O_q_combined = combine_q_polys(&O_q_polys, &beta, n_constraints + 2);
-        openings.push(&O_q_combined);
+        if legacy {
+            openings.push(&O_q_combined);
+        }
         openings.push(&O_L);
         openings.push(&O_Sigma);
         // n_wires_per_gate opening proofs for witness polynomials; n_wires_per_gate-1 opening proofs
-        // for the first n_wires_per_gate-1 extended permutations; 1 opening proof for each of [Q(X), L(X)]
-        let mut points = vec![*beta; 2 * n_wires_per_gate + 1];
+        // for the first n_wires_per_gate-1 extended permutations; 1 opening proof for L(X), and
+        // 1 for Q(X) in the proofs of version 0
+        let mut points = vec![*beta; openings.len() - 1];
         // One opening proof for Sigma(X) at point g * beta
         points.push(g_beta);
+        // Opening proofs for the lookup polynomials, see plonk::lookup
+        if let Some((lookup_params, lookup_witness, _, O_t, O_Z)) = &lookup {
+            openings.extend(lookup_openings(lookup_params, lookup_witness, O_t, O_Z));
+            points.extend(lookup_eval_points(beta, &g_beta));
+        }
         let (_, batch_eval_proof) = pcs
             .batch_prove_eval(
                 transcript,
@@ -313,7 +914,7 @@ pub mod prover {
             .c(d!(PlonkError::ProofError))?;
 
         // return proof
-        Ok(PlonkProof {
+        let proof = PlonkProof {
             C_witness_polys,
             C_q_polys,
             C_Sigma,
@@ -322,7 +923,10 @@ pub mod prover {
             perms_eval_beta,
             L_eval_beta,
             batch_eval_proof,
-        })
+            version: params.verifier_params.version,
+            lookup: lookup.map(|(_, _, lookup_proof, _, _)| lookup_proof),
+        };
+        Ok((proof, witness_openings))
     }
 
     /// Verify a proof for a constraint system previously preprocessed into `cs_params`
@@ -330,15 +934,167 @@ pub mod prover {
     /// Polynomial Commitement parameters must be shared between prover and verifier.
     /// # Example
     /// See plonk::prover::prover
-    pub fn verifier<PCS: PolyComScheme, CS: ConstraintSystem<Field = PCS::Field>>(
-        transcript: &mut Transcript,
+    pub fn verifier<
+        T: PlonkTranscript,
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+    >(
+        transcript: &mut T,
         pcs: &PCS,
         cs: &CS,
         cs_params: &VerifierParams<PCS>,
         public_values: &[PCS::Field],
         proof: &PlonkPf<PCS>,
     ) -> Result<()> {
-        transcript_init_plonk(transcript, cs_params, public_values);
+        let statement = verifier_batch_eval_statement(
+            transcript,
+            cs,
+            cs_params,
+            public_values,
+            proof,
+        )
+        .c(d!())?;
+        let commitments: Vec<&PCS::Commitment> = statement.commitments.iter().collect();
+        pcs.batch_verify_eval(
+            transcript,
+            &commitments[..],
+            cs_params.cs_size + 2,
+            &statement.points[..],
+            &statement.values[..],
+            &proof.batch_eval_proof,
+            None,
+        )
+        .c(d!(PlonkError::VerificationError))
+    }
+
+    /// Same as plonk::prover::verifier for KZG commitments, but returns the final pairing
+    /// check of the verification instead of checking it, so that it can be batched with the
+    /// pairing checks of other proofs (see algebra::pairing_batch::PairingCheckBatch)
+    pub fn verifier_pairing_check<
+        T: PlonkTranscript,
+        P: Pairing,
+        CS: ConstraintSystem<Field = P::ScalarField>,
+    >(
+        transcript: &mut T,
+        pcs: &KZGCommitmentScheme<P>,
+        cs: &CS,
+        cs_params: &VerifierParams<KZGCommitmentScheme<P>>,
+        public_values: &[P::ScalarField],
+        proof: &PlonkPf<KZGCommitmentScheme<P>>,
+    ) -> Result<PairingCheck<P>> {
+        let statement = verifier_batch_eval_statement(
+            transcript,
+            cs,
+            cs_params,
+            public_values,
+            proof,
+        )
+        .c(d!())?;
+        let commitments: Vec<&KZGCommitment<P::G1>> =
+            statement.commitments.iter().collect();
+        let (_, rho, derived_commitment) = pcs
+            .batch_eval_reduce(
+                transcript,
+                &commitments[..],
+                cs_params.cs_size + 2,
+                &statement.points[..],
+                &statement.values[..],
+                &proof.batch_eval_proof,
+                None,
+            )
+            .c(d!(PlonkError::VerificationError))?;
+        Ok(pcs.eval_pairing_check(
+            &derived_commitment,
+            &rho,
+            &P::ScalarField::zero(),
+            &proof.batch_eval_proof.eval_proof,
+        ))
+    }
+
+    /// Verifies the KZG proofs `proofs` at once, e.g. the proofs of the transactions of a block:
+    /// the i-th proof is verified as by plonk::prover::verifier with the transcript
+    /// `transcripts[i]`, the constraint system `cs_list[i]`, its parameters `vks[i]`, and the
+    /// public inputs `public_inputs[i]`, except that the final pairing checks of the proofs are
+    /// combined with random scalars sampled from `prng` into a single multi-pairing (see
+    /// algebra::pairing_batch::PairingCheckBatch). Fails if any of the proofs is invalid.
+    pub fn batch_verifier<
+        T: PlonkTranscript,
+        R: CryptoRng + RngCore,
+        P: Pairing,
+        CS: ConstraintSystem<Field = P::ScalarField>,
+    >(
+        prng: &mut R,
+        transcripts: &mut [T],
+        pcs: &KZGCommitmentScheme<P>,
+        cs_list: &[&CS],
+        vks: &[&VerifierParams<KZGCommitmentScheme<P>>],
+        public_inputs: &[&[P::ScalarField]],
+        proofs: &[&PlonkPf<KZGCommitmentScheme<P>>],
+    ) -> Result<()> {
+        let n = proofs.len();
+        if transcripts.len() != n
+            || cs_list.len() != n
+            || vks.len() != n
+            || public_inputs.len() != n
+        {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
+        let mut batch = PairingCheckBatch::new();
+        for (i, transcript) in transcripts.iter_mut().enumerate() {
+            let check = verifier_pairing_check(
+                transcript,
+                pcs,
+                cs_list[i],
+                vks[i],
+                public_inputs[i],
+                proofs[i],
+            )
+            .c(d!())?;
+            batch.add_check(check);
+        }
+        batch.verify(prng).c(d!(PlonkError::VerificationError))
+    }
+
+    /// Values derived by the verifier from a proof, up to the batch evaluation proof
+    pub(crate) struct VerifierBatchEvalStatement<PCS: PolyComScheme> {
+        pub(crate) gamma: PCS::Field,
+        pub(crate) delta: PCS::Field,
+        pub(crate) alpha: PCS::Field,
+        pub(crate) beta: PCS::Field,
+        pub(crate) public_vars_eval_beta: PCS::Field,
+        pub(crate) derived_L_eval_beta: PCS::Field,
+        pub(crate) C_q_combined: PCS::Commitment,
+        pub(crate) C_L: PCS::Commitment,
+        // statement of the batch evaluation proof
+        pub(crate) commitments: Vec<PCS::Commitment>,
+        pub(crate) points: Vec<PCS::Field>,
+        pub(crate) values: Vec<PCS::Field>,
+    }
+
+    /// Replays the verifier transcript on `proof` and derives the statement of its batch
+    /// evaluation proof, leaving the transcript ready to verify the batch evaluation proof
+    pub(crate) fn verifier_batch_eval_statement<
+        T: PlonkTranscript,
+        PCS: PolyComScheme,
+        CS: ConstraintSystem<Field = PCS::Field>,
+    >(
+        transcript: &mut T,
+        cs: &CS,
+        cs_params: &VerifierParams<PCS>,
+        public_values: &[PCS::Field],
+        proof: &PlonkPf<PCS>,
+    ) -> Result<VerifierBatchEvalStatement<PCS>> {
+        // proofs and public values come from untrusted sources, check their shape before
+        // indexing them
+        proof.validate().c(d!())?;
+        if proof.C_witness_polys.len() != cs.n_wires_per_gate()
+            || public_values.len() != cs_params.public_vars_constraint_indices.len()
+            || proof.lookup.is_some() != cs_params.lookup.is_some()
+            || (proof.lookup.is_some() && cs.n_wires_per_gate() < 2)
+        {
+            return Err(eg!(PlonkError::VerificationError));
+        }
+        transcript_init_plonk(transcript, cs_params, public_values, proof.version);
 
         let mut challenges = PlonkChallenges::new();
 
@@ -349,9 +1105,16 @@ pub mod prover {
         let gamma = transcript_get_plonk_challenge_gamma(transcript, cs.size());
         let delta = transcript_get_plonk_challenge_delta(transcript, cs.size());
         challenges.insert_gamma_delta(gamma, delta).unwrap();
+        let lookup_challenges = proof
+            .lookup
+            .as_ref()
+            .map(|lookup| verifier_lookup_challenges(transcript, lookup, cs.size()));
 
         // 2. compute alpha challenge
         transcript.append_commitment::<PCS::Commitment>(&proof.C_Sigma);
+        if let Some(lookup) = &proof.lookup {
+            transcript.append_commitment::<PCS::Commitment>(&lookup.C_Z);
+        }
         let alpha = transcript_get_plonk_challenge_alpha(transcript, cs.size());
         challenges.insert_alpha(alpha).unwrap();
         for C_q in &proof.C_q_polys {
@@ -369,7 +1132,14 @@ pub mod prover {
             transcript.append_field_elem(eval_beta);
         }
         transcript.append_field_elem(&proof.Sigma_eval_g_beta);
-        transcript.append_field_elem(&proof.L_eval_beta);
+        if let Some(L_eval_beta) = &proof.L_eval_beta {
+            transcript.append_field_elem(L_eval_beta);
+        }
+        if let Some(lookup) = &proof.lookup {
+            for eval in lookup.evals().iter() {
+                transcript.append_field_elem(eval);
+            }
+        }
 
         let public_vars_eval_beta = eval_public_var_poly::<PCS>(
             cs_params,
@@ -378,6 +1148,9 @@ pub mod prover {
         );
 
         // 4. derive linearization polynomial commitment
+        let beta = challenges.get_beta().unwrap();
+        let C_q_combined =
+            combine_q_polys(&proof.C_q_polys[..], beta, cs_params.cs_size + 2);
         let witness_polys_eval_beta_as_ref: Vec<&PCS::Field> =
             proof.witness_polys_eval_beta.iter().collect();
         let perms_eval_beta_as_ref: Vec<&PCS::Field> =
@@ -386,26 +1159,65 @@ pub mod prover {
             cs,
             cs_params,
             &proof.C_Sigma,
+            if proof.L_eval_beta.is_none() {
+                Some(&C_q_combined)
+            } else {
+                None
+            },
             &witness_polys_eval_beta_as_ref[..],
             &perms_eval_beta_as_ref[..],
             &proof.Sigma_eval_g_beta,
             &challenges,
         );
-        // Note: for completeness steps 5 and 6 is analogous to getting Q(beta) in the proof, verify it, and then
-        // check that P(\beta) - Q(\beta) * Z_H(\beta) (plus checking all eval proofs)
+        // Note: for completeness step 5 is analogous to getting L(beta) and Q(beta) in the proof,
+        // verify them, and then check that P(\beta) - Q(\beta) * Z_H(\beta) = 0
 
-        // 5. derive value of Q(\beta) such that P(\beta) - Q(\beta) * Z_H(\beta) = 0
-        let beta = challenges.get_beta().unwrap();
-        let derived_q_eval_beta = derive_Q_eval_beta::<PCS>(
+        // 5. derive value of L(\beta) such that P(\beta) - Q(\beta) * Z_H(\beta) = 0, or in the
+        // proofs of version 0, the value of Q(\beta) from the L(\beta) sent in the proof
+        let derived_q_eval_beta = proof.L_eval_beta.as_ref().map(|L_eval_beta| {
+            derive_Q_eval_beta::<PCS>(
+                cs_params,
+                proof,
+                L_eval_beta,
+                &challenges,
+                &public_vars_eval_beta,
+            )
+        });
+        let mut derived_L_eval_beta = derive_L_eval_beta::<PCS>(
             cs_params,
             proof,
             &challenges,
             &public_vars_eval_beta,
         );
         let g_beta = beta.mul(&cs_params.root);
+        // the lookup terms of P(beta) are computed from their evaluations, see plonk::lookup
+        let lookup = match (&cs_params.lookup, &proof.lookup, lookup_challenges) {
+            (Some(lookup_params), Some(lookup), Some(lookup_challenges)) => {
+                let lookup_terms = lookup_terms_eval_beta(
+                    lookup_params,
+                    lookup,
+                    &lookup_challenges,
+                    challenges.get_alpha().unwrap(),
+                    beta,
+                    cs_params.cs_size,
+                    &cs_params.root,
+                    &proof.witness_polys_eval_beta[0],
+                    &proof.witness_polys_eval_beta[1],
+                )
+                .c(d!())?;
+                derived_L_eval_beta.sub_assign(&lookup_terms);
+                Some((lookup_params, lookup, lookup_challenges))
+            }
+            _ => None,
+        };
 
-        // 6. verify batch eval proofs for witness/permutation polynomials evaluations at point beta, and Q(beta), L(beta), \Sigma(g*beta)
-        let mut commitments: Vec<&PCS::Commitment> = proof
+        if let Some(L_eval_beta) = proof.L_eval_beta {
+            derived_L_eval_beta = L_eval_beta;
+        }
+
+        // 6. verify batch eval proofs for witness/permutation polynomials evaluations at point beta, and L(beta), \Sigma(g*beta),
+        // and Q(beta) in the proofs of version 0
+        let mut commitments: Vec<PCS::Commitment> = proof
             .C_witness_polys
             .iter()
             .chain(
@@ -414,49 +1226,78 @@ pub mod prover {
                     .iter()
                     .take(cs.n_wires_per_gate() - 1),
             )
+            .cloned()
             .collect();
-        let C_q_combined =
-            combine_q_polys(&proof.C_q_polys[..], &beta, cs_params.cs_size + 2);
-        commitments.push(&C_q_combined);
-        commitments.push(&C_L);
-        commitments.push(&proof.C_Sigma);
-        let mut points = vec![*beta; 2 * cs.n_wires_per_gate() + 1];
-        points.push(g_beta);
         let mut values: Vec<PCS::Field> = proof
             .witness_polys_eval_beta
             .iter()
             .chain(proof.perms_eval_beta.iter())
             .cloned()
             .collect();
-        values.push(derived_q_eval_beta);
-        values.push(proof.L_eval_beta);
+        if let Some(derived_q_eval_beta) = derived_q_eval_beta {
+            commitments.push(C_q_combined.clone());
+            values.push(derived_q_eval_beta);
+        }
+        commitments.push(C_L.clone());
+        commitments.push(proof.C_Sigma.clone());
+        let mut points = vec![*beta; commitments.len() - 1];
+        points.push(g_beta);
+        values.push(derived_L_eval_beta);
         values.push(proof.Sigma_eval_g_beta);
-        pcs.batch_verify_eval(
-            transcript,
-            &commitments[..],
-            cs_params.cs_size + 2,
-            &points[..],
-            &values[..],
-            &proof.batch_eval_proof,
-            None,
-        )
-        .c(d!(PlonkError::VerificationError))
+        if let Some((lookup_params, lookup, lookup_challenges)) = lookup {
+            commitments.extend(lookup_commitments::<PCS>(
+                lookup_params,
+                lookup,
+                &lookup_challenges,
+            ));
+            points.extend(lookup_eval_points(beta, &g_beta));
+            values.extend(lookup.evals());
+        }
+        let (gamma, delta) = challenges.get_gamma_delta().unwrap();
+        Ok(VerifierBatchEvalStatement {
+            gamma: *gamma,
+            delta: *delta,
+            alpha: *challenges.get_alpha().unwrap(),
+            beta: *beta,
+            public_vars_eval_beta,
+            derived_L_eval_beta,
+            C_q_combined,
+            C_L,
+            commitments,
+            points,
+            values,
+        })
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
-    use crate::commitments::pcs::PolyComScheme;
+    use crate::backend::{ArithmeticBackend, CpuBackend};
+    use crate::commitments::fri_poly_com::FRICommitmentScheme;
+    use crate::commitments::ipa_poly_com::IPACommitmentSchemeBLS;
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitmentScheme, KZGCommitmentSchemeBLS, KZGCommitmentSchemeBN254,
+    };
+    use crate::commitments::pcs::{BatchProofEval, PolyComScheme};
     use crate::plonk::plonk_setup::{
-        preprocess_prover, preprocess_verifier, PlonkConstraintSystem,
+        preprocess_prover, preprocess_verifier, PlonkConstraintSystem, ProverParams,
+        VerifierParams, PLONK_PROTOCOL_VERSION,
+    };
+    use crate::plonk::protocol::prover::{
+        batch_verifier, prover, prover_offline, prover_online, prover_with_backend,
+        verifier, verifier_pairing_check, PlonkPf, PlonkProverOffline,
     };
-    use crate::plonk::protocol::prover::{prover, verifier, PlonkPf};
+    use crate::plonk::transcript::transcript_prover_rng;
+    use crate::polynomials::field_polynomial::FpPolynomial;
     use algebra::bls12_381::BLSScalar;
-    use algebra::groups::{One, ScalarArithmetic};
+    use algebra::bn254::BN254Scalar;
+    use algebra::groups::{One, Scalar, ScalarArithmetic};
+    use algebra::pairing_batch::PairingCheckBatch;
     use merlin::Transcript;
     use rand_chacha::ChaChaRng;
     use rand_core::{CryptoRng, RngCore, SeedableRng};
+    use serde::{Deserialize, Serialize};
+    use utils::serialization::versioned_serde::VERSIONED_ENCODING_TAG;
 
     #[test]
     fn test_plonk_kzg() {
@@ -465,6 +1306,634 @@ mod test {
         test_plonk(&pcs, &mut prng);
     }
 
+    #[test]
+    fn test_plonk_ipa() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = IPACommitmentSchemeBLS::new(30);
+        test_plonk(&pcs, &mut prng);
+    }
+
+    #[test]
+    fn test_plonk_fri() {
+        // the proofs with FRI are sound but not zero knowledge, see fri_poly_com.rs
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = FRICommitmentScheme::<BLSScalar>::new(30, 2, 16, 4).unwrap();
+        test_plonk(&pcs, &mut prng);
+    }
+
+    #[test]
+    fn test_plonk_kzg_bn254() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentSchemeBN254::new(30, &mut prng);
+        test_plonk(&pcs, &mut prng);
+        // the final pairing check of the verifier over BN254
+        let one = BN254Scalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+        let mut cs = PlonkConstraintSystem::<BN254Scalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let prover_params = preprocess_prover(&cs, &pcs, [0u8; 32]).unwrap();
+        let verifier_params = preprocess_verifier(&cs, &pcs, [0u8; 32]).unwrap();
+        let proof = prover(
+            &mut prng,
+            &mut Transcript::new(b"TestPlonk"),
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three],
+        )
+        .unwrap();
+        let mut batch = PairingCheckBatch::new();
+        for public_value in &[three, two] {
+            let check = verifier_pairing_check(
+                &mut Transcript::new(b"TestPlonk"),
+                &pcs,
+                &cs,
+                &verifier_params,
+                &[*public_value],
+                &proof,
+            )
+            .unwrap();
+            batch.add_check(check);
+        }
+        assert_eq!(batch.failed_checks(&mut prng), vec![1]);
+    }
+
+    #[test]
+    fn test_plonk_pairing_check_batch() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        // circuit x_0 + x_1 = x_2 with public output x_2
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+
+        let common_seed = [0u8; 32];
+        let prover_params = preprocess_prover(&cs, &pcs, common_seed).unwrap();
+        let verifier_params = preprocess_verifier(&cs, &pcs, common_seed).unwrap();
+        assert!(verifier_params.check_srs(&pcs).is_ok());
+        assert!(verifier_params
+            .check_srs(&pcs.truncate(10).unwrap())
+            .is_err());
+        let mut batch = PairingCheckBatch::new();
+        let mut bad_indices = vec![];
+        for public_value in &[three, three, two] {
+            let mut transcript = Transcript::new(b"TestPlonk");
+            let proof = prover(
+                &mut prng,
+                &mut transcript,
+                &pcs,
+                &cs,
+                &prover_params,
+                &[one, two, three],
+            )
+            .unwrap();
+            let mut transcript = Transcript::new(b"TestPlonk");
+            let check = verifier_pairing_check(
+                &mut transcript,
+                &pcs,
+                &cs,
+                &verifier_params,
+                &[*public_value],
+                &proof,
+            )
+            .unwrap();
+            let index = batch.add_check(check);
+            if *public_value != three {
+                bad_indices.push(index);
+            }
+        }
+
+        // a KZG opening in the same batch
+        let poly = FpPolynomial::from_coefs(vec![one, two, three]);
+        let (C, opening) = pcs.commit(poly).unwrap();
+        let mut transcript = Transcript::new(b"TestKZG");
+        let (y, eval_proof) =
+            pcs.prove_eval(&mut transcript, &opening, &two, 2).unwrap();
+        batch.add_check(pcs.eval_pairing_check(&C, &two, &y, &eval_proof));
+
+        assert!(batch.verify(&mut prng).is_err());
+        assert_eq!(batch.failed_checks(&mut prng), bad_indices);
+    }
+
+    #[test]
+    fn test_plonk_batch_verifier() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+        let six = three.add(&three);
+        // circuits x_0 + x_1 = x_2 and x_0 * x_1 = x_2 with public output x_2
+        let mut add_cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        add_cs.insert_add_gate(0, 1, 2);
+        add_cs.prepare_io_variable(2);
+        add_cs.pad();
+        let mut mul_cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        mul_cs.insert_mul_gate(0, 1, 2);
+        mul_cs.prepare_io_variable(2);
+        mul_cs.pad();
+
+        let common_seed = [0u8; 32];
+        let mut cs_list = vec![];
+        let mut vks = vec![];
+        let mut proofs = vec![];
+        let mut public_inputs = vec![];
+        for (cs, output) in [(&add_cs, three), (&mul_cs, six), (&add_cs, three)] {
+            let prover_params = preprocess_prover(cs, &pcs, common_seed).unwrap();
+            let mut transcript = Transcript::new(b"TestPlonk");
+            let proof = prover(
+                &mut prng,
+                &mut transcript,
+                &pcs,
+                cs,
+                &prover_params,
+                &[one, two, output],
+            )
+            .unwrap();
+            cs_list.push(cs);
+            vks.push(preprocess_verifier(cs, &pcs, common_seed).unwrap());
+            proofs.push(proof);
+            public_inputs.push(vec![output]);
+        }
+        let vks_ref: Vec<_> = vks.iter().collect();
+        let proofs_ref: Vec<_> = proofs.iter().collect();
+        let mut batch_verify = |public_inputs: &[Vec<BLSScalar>]| {
+            let mut transcripts: Vec<Transcript> =
+                (0..3).map(|_| Transcript::new(b"TestPlonk")).collect();
+            let public_inputs_ref: Vec<&[BLSScalar]> =
+                public_inputs.iter().map(|inputs| &inputs[..]).collect();
+            batch_verifier(
+                &mut prng,
+                &mut transcripts,
+                &pcs,
+                &cs_list,
+                &vks_ref,
+                &public_inputs_ref,
+                &proofs_ref,
+            )
+        };
+        assert!(batch_verify(&public_inputs).is_ok());
+        // a wrong public input in the batch
+        public_inputs[1] = vec![three];
+        assert!(batch_verify(&public_inputs).is_err());
+        // a missing public input
+        assert!(batch_verify(&public_inputs[..2]).is_err());
+    }
+
+    #[test]
+    fn test_plonk_proof_versions() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+
+        let common_seed = [0u8; 32];
+        let mut prover_params = preprocess_prover(&cs, &pcs, common_seed).unwrap();
+        let verifier_params = preprocess_verifier(&cs, &pcs, common_seed).unwrap();
+        assert_eq!(verifier_params.version(), PLONK_PROTOCOL_VERSION);
+        assert!(prover_params
+            .set_version(PLONK_PROTOCOL_VERSION + 1)
+            .is_err());
+        let mut prove_and_verify =
+            |prover_params: &ProverParams<KZGCommitmentSchemeBLS>,
+             proof_version: Option<u32>| {
+                let mut transcript = Transcript::new(b"TestPlonk");
+                let mut proof = prover(
+                    &mut prng,
+                    &mut transcript,
+                    &pcs,
+                    &cs,
+                    prover_params,
+                    &[one, two, three],
+                )
+                .unwrap();
+                if let Some(version) = proof_version {
+                    proof.version = version;
+                }
+                let mut transcript = Transcript::new(b"TestPlonk");
+                verifier(
+                    &mut transcript,
+                    &pcs,
+                    &cs,
+                    &verifier_params,
+                    &[three],
+                    &proof,
+                )
+            };
+
+        assert!(prove_and_verify(&prover_params, None).is_ok());
+        // the version is bound to the transcript
+        assert!(prove_and_verify(&prover_params, Some(0)).is_err());
+        assert!(prove_and_verify(&prover_params, Some(2)).is_err());
+        // the proofs of the previous version are still accepted
+        prover_params.set_version(0).unwrap();
+        assert!(prove_and_verify(&prover_params, None).is_ok());
+        assert!(prove_and_verify(&prover_params, Some(1)).is_err());
+    }
+
+    // the proofs and verifier parameters as defined before the version was recorded
+    #[allow(non_snake_case)]
+    #[derive(Serialize, Deserialize)]
+    struct LegacyPlonkProof<C, E, F> {
+        C_witness_polys: Vec<C>,
+        C_q_polys: Vec<C>,
+        C_Sigma: C,
+        witness_polys_eval_beta: Vec<F>,
+        Sigma_eval_g_beta: F,
+        perms_eval_beta: Vec<F>,
+        L_eval_beta: F,
+        batch_eval_proof: BatchProofEval<C, E>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct LegacyPlonkVerifierParams<C, F> {
+        selectors: Vec<C>,
+        extended_permutations: Vec<C>,
+        k: Vec<F>,
+        root: F,
+        cs_size: usize,
+        public_vars_constraint_indices: Vec<usize>,
+        lagrange_constants: Vec<F>,
+    }
+
+    #[test]
+    fn test_plonk_legacy_encodings() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+
+        let common_seed = [0u8; 32];
+        let mut prover_params = preprocess_prover(&cs, &pcs, common_seed).unwrap();
+        prover_params.set_version(0).unwrap();
+        let verifier_params = preprocess_verifier(&cs, &pcs, common_seed).unwrap();
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three],
+        )
+        .unwrap();
+        assert!(proof.L_eval_beta.is_some());
+
+        // the proofs of version 0 have the legacy encodings
+        let legacy_proof = LegacyPlonkProof {
+            C_witness_polys: proof.C_witness_polys.clone(),
+            C_q_polys: proof.C_q_polys.clone(),
+            C_Sigma: proof.C_Sigma.clone(),
+            witness_polys_eval_beta: proof.witness_polys_eval_beta.clone(),
+            Sigma_eval_g_beta: proof.Sigma_eval_g_beta,
+            perms_eval_beta: proof.perms_eval_beta.clone(),
+            L_eval_beta: proof.L_eval_beta.unwrap(),
+            batch_eval_proof: proof.batch_eval_proof.clone(),
+        };
+        let legacy_proof_bytes = bincode::serialize(&legacy_proof).unwrap();
+        assert_eq!(bincode::serialize(&proof).unwrap(), legacy_proof_bytes);
+        let decoded: PlonkPf<KZGCommitmentSchemeBLS> =
+            bincode::deserialize(&legacy_proof_bytes).unwrap();
+        assert_eq!(decoded, proof);
+        let legacy_proof_json = serde_json::to_string(&legacy_proof).unwrap();
+        let decoded: PlonkPf<KZGCommitmentSchemeBLS> =
+            serde_json::from_str(&legacy_proof_json).unwrap();
+        assert_eq!(decoded, proof);
+
+        // the legacy verifier parameters decode with version 0, no SRS digest and no lookup gates
+        let legacy_params = LegacyPlonkVerifierParams {
+            selectors: verifier_params.selectors.clone(),
+            extended_permutations: verifier_params.extended_permutations.clone(),
+            k: verifier_params.k.clone(),
+            root: verifier_params.root,
+            cs_size: verifier_params.cs_size,
+            public_vars_constraint_indices: verifier_params
+                .public_vars_constraint_indices
+                .clone(),
+            lagrange_constants: verifier_params.lagrange_constants.clone(),
+        };
+        let legacy_params_bytes = bincode::serialize(&legacy_params).unwrap();
+        let legacy_params_json = serde_json::to_string(&legacy_params).unwrap();
+        for decoded in [
+            bincode::deserialize::<VerifierParams<KZGCommitmentSchemeBLS>>(
+                &legacy_params_bytes,
+            )
+            .unwrap(),
+            serde_json::from_str(&legacy_params_json).unwrap(),
+        ] {
+            assert_eq!(decoded.version(), 0);
+            assert!(decoded.srs_digest.is_empty());
+            assert!(decoded.lookup.is_none());
+            assert!(decoded.check_srs(&pcs).is_err());
+            let mut transcript = Transcript::new(b"TestPlonk");
+            assert!(
+                verifier(&mut transcript, &pcs, &cs, &decoded, &[three], &proof).is_ok()
+            );
+        }
+
+        // the verifier checks the L(beta) sent in the proofs of version 0
+        let mut bad_proof = proof.clone();
+        bad_proof.L_eval_beta = Some(proof.L_eval_beta.unwrap().add(&one));
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[three],
+            &bad_proof
+        )
+        .is_err());
+        let mut bad_proof = proof.clone();
+        bad_proof.L_eval_beta = None;
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[three],
+            &bad_proof
+        )
+        .is_err());
+
+        // the current encodings start with the version
+        prover_params.set_version(PLONK_PROTOCOL_VERSION).unwrap();
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three],
+        )
+        .unwrap();
+        assert!(proof.L_eval_beta.is_none());
+        let proof_bytes = bincode::serialize(&proof).unwrap();
+        assert_eq!(proof_bytes[..8], VERSIONED_ENCODING_TAG.to_le_bytes());
+        let decoded: PlonkPf<KZGCommitmentSchemeBLS> =
+            bincode::deserialize(&proof_bytes).unwrap();
+        assert_eq!(decoded, proof);
+        let params_bytes = bincode::serialize(&verifier_params).unwrap();
+        let decoded: VerifierParams<KZGCommitmentSchemeBLS> =
+            bincode::deserialize(&params_bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), verifier_params.to_bytes());
+        let decoded: PlonkPf<KZGCommitmentSchemeBLS> =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(decoded, proof);
+    }
+
+    #[test]
+    fn test_plonk_transcript_prover_rng() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        // circuit x_0 * x_1 = x_2 with public output x_2
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_mul_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let common_seed = [0u8; 32];
+        let prover_params = preprocess_prover(&cs, &pcs, common_seed).unwrap();
+        let verifier_params = preprocess_verifier(&cs, &pcs, common_seed).unwrap();
+
+        let prove = |secret_key: &[u8; 32], witness: &[BLSScalar]| {
+            let mut transcript = Transcript::new(b"TestPlonk");
+            let mut prover_prng =
+                transcript_prover_rng(&transcript, secret_key, witness);
+            prover(
+                &mut prover_prng,
+                &mut transcript,
+                &pcs,
+                &cs,
+                &prover_params,
+                witness,
+            )
+            .unwrap()
+        };
+        let witness = [
+            BLSScalar::from_u32(2),
+            BLSScalar::from_u32(3),
+            BLSScalar::from_u32(6),
+        ];
+        let proof = prove(&[7u8; 32], &witness);
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[witness[2]],
+            &proof
+        )
+        .is_ok());
+
+        // the proof is reproducible
+        assert_eq!(prove(&[7u8; 32], &witness), proof);
+        assert_eq!(
+            bincode::serialize(&prove(&[7u8; 32], &witness)).unwrap(),
+            bincode::serialize(&proof).unwrap()
+        );
+        // a different key, or witness for the same statement, changes the blinding factors
+        assert_ne!(prove(&[8u8; 32], &witness), proof);
+        let other_witness = [
+            BLSScalar::from_u32(1),
+            BLSScalar::from_u32(6),
+            BLSScalar::from_u32(6),
+        ];
+        assert_ne!(
+            prove(&[7u8; 32], &other_witness).C_witness_polys,
+            proof.C_witness_polys
+        );
+    }
+
+    #[test]
+    fn test_plonk_offline_online_prover() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        // circuit x_0 * x_1 = x_2 with public output x_2
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_mul_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let common_seed = [0u8; 32];
+        let prover_params = preprocess_prover(&cs, &pcs, common_seed).unwrap();
+        let verifier_params = preprocess_verifier(&cs, &pcs, common_seed).unwrap();
+
+        // the offline phase is computed once and cached for all the witnesses
+        let offline = prover_offline(&cs, &prover_params).unwrap();
+        let offline_bytes = bincode::serialize(&offline).unwrap();
+        let offline: PlonkProverOffline<BLSScalar> =
+            bincode::deserialize(&offline_bytes).unwrap();
+        for (x_0, x_1) in [(2, 3), (4, 5), (1, 1)] {
+            let witness = [
+                BLSScalar::from_u32(x_0),
+                BLSScalar::from_u32(x_1),
+                BLSScalar::from_u32(x_0 * x_1),
+            ];
+            let mut transcript = Transcript::new(b"TestPlonk");
+            let mut prover_prng =
+                transcript_prover_rng(&transcript, &[7u8; 32], &witness);
+            let proof = prover_online(
+                &mut prover_prng,
+                &mut transcript,
+                &pcs,
+                &cs,
+                &prover_params,
+                &offline,
+                &witness,
+            )
+            .unwrap();
+            let mut transcript = Transcript::new(b"TestPlonk");
+            assert!(verifier(
+                &mut transcript,
+                &pcs,
+                &cs,
+                &verifier_params,
+                &[witness[2]],
+                &proof
+            )
+            .is_ok());
+
+            // same proof as the one phase prover
+            let mut transcript = Transcript::new(b"TestPlonk");
+            let mut prover_prng =
+                transcript_prover_rng(&transcript, &[7u8; 32], &witness);
+            let one_phase_proof = prover(
+                &mut prover_prng,
+                &mut transcript,
+                &pcs,
+                &cs,
+                &prover_params,
+                &witness,
+            )
+            .unwrap();
+            assert_eq!(one_phase_proof, proof);
+        }
+
+        // the offline values of another circuit are rejected
+        let mut other_cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        for _ in 0..5 {
+            other_cs.insert_mul_gate(0, 1, 2);
+        }
+        other_cs.pad();
+        let other_params = preprocess_prover(&other_cs, &pcs, common_seed).unwrap();
+        let other_offline = prover_offline(&other_cs, &other_params).unwrap();
+        assert!(prover_offline(&cs, &other_params).is_err());
+        let witness = [
+            BLSScalar::from_u32(2),
+            BLSScalar::from_u32(3),
+            BLSScalar::from_u32(6),
+        ];
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(prover_online(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &other_offline,
+            &witness,
+        )
+        .is_err());
+    }
+
+    fn prove_with_backend<B: ArithmeticBackend>(
+        pcs: &KZGCommitmentSchemeBLS,
+        cs: &PlonkConstraintSystem<BLSScalar>,
+        params: &ProverParams<KZGCommitmentSchemeBLS>,
+        witness: &[BLSScalar],
+        backend: &B,
+    ) -> PlonkPf<KZGCommitmentSchemeBLS> {
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let mut prng = transcript_prover_rng(&transcript, &[7u8; 32], witness);
+        prover_with_backend(
+            &mut prng,
+            &mut transcript,
+            pcs,
+            cs,
+            params,
+            witness,
+            backend,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plonk_prover_with_backend() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentSchemeBLS::new(20, &mut prng);
+        // circuit (x_0 + x_1) * x_2 = x_3 with public output x_3
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(5);
+        cs.insert_add_gate(0, 1, 4);
+        cs.insert_mul_gate(4, 2, 3);
+        cs.prepare_io_variable(3);
+        cs.pad();
+        let common_seed = [0u8; 32];
+        let prover_params = preprocess_prover(&cs, &pcs, common_seed).unwrap();
+        let verifier_params = preprocess_verifier(&cs, &pcs, common_seed).unwrap();
+        let witness = [
+            BLSScalar::from_u32(2),
+            BLSScalar::from_u32(3),
+            BLSScalar::from_u32(4),
+            BLSScalar::from_u32(20),
+            BLSScalar::from_u32(5),
+        ];
+        let proof = prove_with_backend(&pcs, &cs, &prover_params, &witness, &CpuBackend);
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[witness[3]],
+            &proof
+        )
+        .is_ok());
+
+        // same proof as the prover without backend
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let mut prover_prng = transcript_prover_rng(&transcript, &[7u8; 32], &witness);
+        let default_proof = prover(
+            &mut prover_prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness,
+        )
+        .unwrap();
+        assert_eq!(default_proof, proof);
+        #[cfg(feature = "simd")]
+        {
+            let simd_proof = prove_with_backend(
+                &pcs,
+                &cs,
+                &prover_params,
+                &witness,
+                &crate::backend::simd::SimdBackend,
+            );
+            assert_eq!(simd_proof, proof);
+        }
+    }
+
     fn test_plonk<PCS: PolyComScheme, R: CryptoRng + RngCore>(pcs: &PCS, prng: &mut R) {
         // circuit (x_0 + x_1) * (x_2 + x_3) + x_0;
         let mut cs = PlonkConstraintSystem::<PCS::Field>::new(8);
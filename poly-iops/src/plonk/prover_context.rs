@@ -0,0 +1,355 @@
+// This file implements a cache of the preprocessing of circuits, for provers that prove the
+// same circuits many times, e.g. a service proving the same transfer circuit thousands of
+// times. ProverContext keeps, for each circuit, the prover parameters of
+// plonk_setup::preprocess_prover_with_coset_shifts (the openings of the selector and
+// permutation polynomials and their evaluations over the domain of the quotient polynomial) and
+// the witness independent values of prover::prover_offline. Each entry is keyed by the digest
+// of the circuit (see circuit_digest), of the coset shifts and of the SRS of the commitment
+// scheme, so that a circuit preprocessed with other shifts or another SRS is never reused. A
+// proof of a cached circuit only pays for prover::prover_online.
+// The SRS itself is held by the commitment scheme, and the commitments are variable-base
+// multi-exponentiations over it (see algebra::multi_exp), which have no window table to cache.
+
+use crate::commitments::pcs::PolyComScheme;
+use crate::plonk::plonk_setup::{
+    preprocess_prover_with_coset_shifts, ConstraintSystem, CosetShifts, ProverParams,
+};
+use crate::plonk::protocol::prover::{
+    prover_offline, prover_online, PlonkPf, PlonkProverOffline,
+};
+use crate::plonk::transcript::PlonkTranscript;
+use algebra::groups::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use sha3::{Digest, Sha3_256};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn hash_usize(hash: &mut Sha3_256, value: usize) {
+    hash.update((value as u64).to_le_bytes());
+}
+
+fn hash_indices(hash: &mut Sha3_256, indices: &[usize]) {
+    hash_usize(hash, indices.len());
+    for index in indices {
+        hash_usize(hash, *index);
+    }
+}
+
+fn hash_field_elems<F: Scalar>(hash: &mut Sha3_256, elems: &[F]) {
+    hash_usize(hash, elems.len());
+    for elem in elems {
+        hash.update(elem.to_bytes());
+    }
+}
+
+/// Returns a digest of the constraint system `cs`: its sizes, selectors, wiring, public inputs
+/// and lookup gates, i.e. everything preprocess_prover reads from it. Two constraint systems
+/// with the same digest have the same prover and verifier parameters for the same SRS and
+/// coset shifts.
+pub fn circuit_digest<CS: ConstraintSystem>(cs: &CS) -> Result<[u8; 32]> {
+    let mut hash = Sha3_256::new();
+    hash.update(b"PLONK circuit digest");
+    for value in [
+        cs.size(),
+        cs.num_vars(),
+        cs.n_wires_per_gate(),
+        cs.num_selectors(),
+        cs.quot_eval_dom_size(),
+    ] {
+        hash_usize(&mut hash, value);
+    }
+    for i in 0..cs.num_selectors() {
+        hash_field_elems(&mut hash, cs.selector(i).c(d!())?);
+    }
+    for wire in cs.wiring() {
+        hash_indices(&mut hash, wire);
+    }
+    hash_indices(&mut hash, cs.public_vars_constraint_indices());
+    hash_indices(&mut hash, cs.public_vars_witness_indices());
+    match cs.lookup_columns() {
+        Some(columns) => {
+            hash.update([1u8]);
+            hash_field_elems(&mut hash, &columns.q_lookup);
+            hash_field_elems(&mut hash, &columns.q_table);
+            hash_usize(&mut hash, columns.table.len());
+            for entry in columns.table.iter() {
+                hash_field_elems(&mut hash, entry);
+            }
+        }
+        None => hash.update([0u8]),
+    }
+    Ok(hash.finalize().into())
+}
+
+/// A circuit preprocessed for the prover: the prover parameters and the witness independent
+/// values of prover_offline
+pub struct PreprocessedCircuit<PCS: PolyComScheme> {
+    params: ProverParams<PCS>,
+    offline: PlonkProverOffline<PCS::Field>,
+}
+
+impl<PCS: PolyComScheme> PreprocessedCircuit<PCS> {
+    /// Returns the prover parameters, whose field verifier_params is the verifier key
+    pub fn params(&self) -> &ProverParams<PCS> {
+        &self.params
+    }
+
+    /// Returns the witness independent values of prover_offline
+    pub fn offline(&self) -> &PlonkProverOffline<PCS::Field> {
+        &self.offline
+    }
+}
+
+/// Cache of preprocessed circuits, see prover_context.rs
+pub struct ProverContext<PCS: PolyComScheme> {
+    circuits: HashMap<[u8; 32], Arc<PreprocessedCircuit<PCS>>>,
+}
+
+impl<PCS: PolyComScheme> Default for ProverContext<PCS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<PCS: PolyComScheme> ProverContext<PCS> {
+    /// Returns an empty context
+    pub fn new() -> Self {
+        ProverContext {
+            circuits: HashMap::new(),
+        }
+    }
+
+    /// Returns the key of the circuit `cs` preprocessed with the commitment scheme `pcs` and
+    /// the coset shifts `shifts`
+    pub fn key<CS: ConstraintSystem<Field = PCS::Field>>(
+        cs: &CS,
+        pcs: &PCS,
+        shifts: &CosetShifts<PCS::Field>,
+    ) -> Result<[u8; 32]> {
+        let mut hash = Sha3_256::new();
+        hash.update(b"PLONK prover context key");
+        hash.update(circuit_digest(cs).c(d!())?);
+        let srs_digest = pcs.srs_digest();
+        hash_usize(&mut hash, srs_digest.len());
+        hash.update(&srs_digest);
+        match shifts {
+            CosetShifts::Seeded(seed) => {
+                hash.update([0u8]);
+                hash.update(seed);
+            }
+            CosetShifts::GeneratorPowers => hash.update([1u8]),
+            CosetShifts::Custom(k) => {
+                hash.update([2u8]);
+                hash_field_elems(&mut hash, k);
+            }
+        }
+        Ok(hash.finalize().into())
+    }
+
+    /// Returns the preprocessing of the circuit `cs` with `pcs` and `shifts`, computed and
+    /// cached on the first call. Use CosetShifts::Seeded(prg_seed) for the parameters of
+    /// preprocess_prover(cs, pcs, prg_seed).
+    pub fn preprocess<CS: ConstraintSystem<Field = PCS::Field>>(
+        &mut self,
+        cs: &CS,
+        pcs: &PCS,
+        shifts: &CosetShifts<PCS::Field>,
+    ) -> Result<Arc<PreprocessedCircuit<PCS>>> {
+        let key = Self::key(cs, pcs, shifts).c(d!())?;
+        if let Some(circuit) = self.circuits.get(&key) {
+            return Ok(circuit.clone());
+        }
+        let params = preprocess_prover_with_coset_shifts(cs, pcs, shifts).c(d!())?;
+        let offline = prover_offline(cs, &params).c(d!())?;
+        let circuit = Arc::new(PreprocessedCircuit { params, offline });
+        self.circuits.insert(key, circuit.clone());
+        Ok(circuit)
+    }
+
+    /// Same as plonk::prover::prover with the parameters of
+    /// preprocess_prover_with_coset_shifts(cs, pcs, shifts), preprocessed once per circuit
+    /// # Example
+    /// ```
+    /// use poly_iops::commitments::kzg_poly_com::KZGCommitmentSchemeBLS;
+    /// use poly_iops::plonk::plonk_setup::{preprocess_verifier, CosetShifts};
+    /// use poly_iops::plonk::protocol::prover::verifier;
+    /// use poly_iops::plonk::prover_context::ProverContext;
+    /// use poly_iops::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    /// use algebra::bls12_381::BLSScalar;
+    /// use algebra::groups::Scalar;
+    /// use merlin::Transcript;
+    /// use rand_chacha::ChaChaRng;
+    /// use rand_core::SeedableRng;
+    /// let mut prng = ChaChaRng::from_seed([0u8; 32]);
+    /// let pcs = KZGCommitmentSchemeBLS::new(20, &mut prng);
+    /// let mut context = ProverContext::new();
+    /// let prg_seed = [0u8; 32];
+    /// for value in 0..3 {
+    ///     // x + x = 2x, the same circuit for every value of x
+    ///     let mut cs = TurboPlonkConstraintSystem::<BLSScalar>::new();
+    ///     let x = cs.new_variable(BLSScalar::from_u32(value));
+    ///     cs.add(x, x);
+    ///     cs.pad();
+    ///     let witness = cs.get_and_clear_witness();
+    ///     let shifts = CosetShifts::Seeded(prg_seed);
+    ///     let mut transcript = Transcript::new(b"Test");
+    ///     let proof = context
+    ///         .prove(&mut prng, &mut transcript, &pcs, &cs, &shifts, &witness)
+    ///         .unwrap();
+    ///     let verifier_params = preprocess_verifier(&cs, &pcs, prg_seed).unwrap();
+    ///     let mut transcript = Transcript::new(b"Test");
+    ///     assert!(verifier(&mut transcript, &pcs, &cs, &verifier_params, &[], &proof).is_ok());
+    /// }
+    /// assert_eq!(context.len(), 1);
+    /// ```
+    pub fn prove<
+        T: PlonkTranscript,
+        R: CryptoRng + RngCore,
+        CS: ConstraintSystem<Field = PCS::Field>,
+    >(
+        &mut self,
+        prng: &mut R,
+        transcript: &mut T,
+        pcs: &PCS,
+        cs: &CS,
+        shifts: &CosetShifts<PCS::Field>,
+        witness: &[PCS::Field],
+    ) -> Result<PlonkPf<PCS>> {
+        let circuit = self.preprocess(cs, pcs, shifts).c(d!())?;
+        prover_online(
+            prng,
+            transcript,
+            pcs,
+            cs,
+            &circuit.params,
+            &circuit.offline,
+            witness,
+        )
+        .c(d!())
+    }
+
+    /// Returns true if the circuit `cs` is cached for `pcs` and `shifts`
+    pub fn contains<CS: ConstraintSystem<Field = PCS::Field>>(
+        &self,
+        cs: &CS,
+        pcs: &PCS,
+        shifts: &CosetShifts<PCS::Field>,
+    ) -> Result<bool> {
+        Ok(self
+            .circuits
+            .contains_key(&Self::key(cs, pcs, shifts).c(d!())?))
+    }
+
+    /// Removes the circuit with key `key` from the cache, returning its preprocessing if it
+    /// was cached
+    pub fn remove(&mut self, key: &[u8; 32]) -> Option<Arc<PreprocessedCircuit<PCS>>> {
+        self.circuits.remove(key)
+    }
+
+    /// Returns the number of cached circuits
+    pub fn len(&self) -> usize {
+        self.circuits.len()
+    }
+
+    /// Returns true if no circuit is cached
+    pub fn is_empty(&self) -> bool {
+        self.circuits.is_empty()
+    }
+
+    /// Removes every cached circuit
+    pub fn clear(&mut self) {
+        self.circuits.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::KZGCommitmentSchemeBLS;
+    use crate::plonk::plonk_setup::{
+        preprocess_verifier_with_coset_shifts, CosetShifts,
+    };
+    use crate::plonk::protocol::prover::verifier;
+    use crate::plonk::prover_context::{circuit_digest, ProverContext};
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::Scalar;
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    // x * y = z, with z public
+    fn mul_circuit(x: u32, y: u32) -> TurboPlonkConstraintSystem<F> {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let x = cs.new_variable(F::from_u32(x));
+        let y = cs.new_variable(F::from_u32(y));
+        let z = cs.mul(x, y);
+        cs.prepare_io_variable(z);
+        cs.pad();
+        cs
+    }
+
+    #[test]
+    fn test_prover_context() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let pcs = KZGCommitmentSchemeBLS::new(20, &mut prng);
+        let mut context = ProverContext::new();
+        let shifts = CosetShifts::Seeded([0u8; 32]);
+        assert!(context.is_empty());
+
+        for (x, y) in [(2, 3), (5, 7)] {
+            let mut cs = mul_circuit(x, y);
+            let witness = cs.get_and_clear_witness();
+            let mut transcript = Transcript::new(b"TestProverContext");
+            let proof = pnk!(context.prove(
+                &mut prng,
+                &mut transcript,
+                &pcs,
+                &cs,
+                &shifts,
+                &witness
+            ));
+            let verifier_params =
+                pnk!(preprocess_verifier_with_coset_shifts(&cs, &pcs, &shifts));
+            let mut transcript = Transcript::new(b"TestProverContext");
+            let z = F::from_u32(x * y);
+            pnk!(verifier(
+                &mut transcript,
+                &pcs,
+                &cs,
+                &verifier_params,
+                &[z],
+                &proof
+            ));
+        }
+        assert_eq!(context.len(), 1);
+        let cs = mul_circuit(2, 3);
+        assert!(pnk!(context.contains(&cs, &pcs, &shifts)));
+
+        // other shifts, another SRS or another circuit are other entries
+        let other_shifts = CosetShifts::GeneratorPowers;
+        assert!(!pnk!(context.contains(&cs, &pcs, &other_shifts)));
+        let other_pcs = KZGCommitmentSchemeBLS::new(20, &mut prng);
+        assert!(!pnk!(context.contains(&cs, &other_pcs, &shifts)));
+        let mut add_cs = TurboPlonkConstraintSystem::<F>::new();
+        let x = add_cs.new_variable(F::from_u32(2));
+        let y = add_cs.new_variable(F::from_u32(3));
+        let z = add_cs.add(x, y);
+        add_cs.prepare_io_variable(z);
+        add_cs.pad();
+        assert_ne!(pnk!(circuit_digest(&add_cs)), pnk!(circuit_digest(&cs)));
+        pnk!(context.preprocess(&add_cs, &pcs, &shifts));
+        pnk!(context.preprocess(&cs, &pcs, &other_shifts));
+        assert_eq!(context.len(), 3);
+
+        let key = pnk!(ProverContext::key(&cs, &pcs, &shifts));
+        assert!(context.remove(&key).is_some());
+        assert!(context.remove(&key).is_none());
+        assert_eq!(context.len(), 2);
+        context.clear();
+        assert!(context.is_empty());
+    }
+}
@@ -0,0 +1,915 @@
+// This file generates a Solidity verifier contract for the TurboPlonk proofs of a circuit,
+// with KZG commitments over BLS12-381, so that the proofs can be verified on Ethereum. The
+// contract relies on the BLS12-381 precompiles of EIP-2537 (G1MSM at 0x0c, PAIRING_CHECK at
+// 0x0f) and on the MODEXP precompile, and replays the verifier of plonk::protocol with a
+// KeccakTranscript:
+//   - the absorption of the verifier key in the transcript does not depend on the proof,
+//     hence the generator computes it and the contract starts from the resulting state,
+//   - the contract absorbs the public inputs and the proof, and derives the challenges
+//     gamma, delta, alpha, beta of PLONK and alpha, rho of the batch evaluation proof.
+//     Commitments are absorbed as their compressed encoding, which the contract computes
+//     from the affine coordinates of the calldata,
+//   - it computes PI(beta), L(beta) and the scalars of the commitment C_L to the
+//     linearization polynomial, as plonk::turbo_plonk_cs::plonk_verifier,
+//   - with the scalars z_i(rho) of the batch evaluation proof, it computes in a single
+//     multi-scalar multiplication A = D + rho * W, where D is the derived commitment of
+//     PolyComScheme::batch_eval_reduce and W the KZG evaluation proof, and checks the
+//     pairing equation e(A, g2) * e(W, -[s]g2) = 1, that is e(D, g2) = e(W, [s]g2 - rho g2).
+// Only the 5 wires and 13 selectors of TurboPlonkConstraintSystem without lookup gates are
+// supported, the proofs of protocol version 0 are not, and the proofs must be computed with
+// KeccakTranscript::new(transcript_label) for the label of the contract. Only BLS12-381 is supported: the BN254 precompiles of
+// EIP-196 and EIP-197 would need a contract of their own for KZGCommitmentSchemeBN254.
+// Calldata of verify(bytes proof, uint256[] publicInputs), see evm_calldata:
+//   - proof: the commitments to the witness polynomials, Sigma and the split quotient
+//     polynomials, the evaluations of the witness polynomials and of the permutations but
+//     the last at beta and of Sigma at g * beta, then the commitment and the evaluation
+//     proof of the batch evaluation proof, that is 13 G1 points and 10 scalars,
+//     EVM_PROOF_LEN bytes,
+//   - publicInputs: the public inputs, in the order of the verifier key.
+// G1 points are encoded as in EIP-2537, 64 big endian bytes per coordinate and 128 zero
+// bytes for the identity; scalars are uint256 lower than the order of the field.
+// The generator is experimental: its tests replay the transcript and the pairing equation of
+// the contract in Rust on the calldata, but the generated Solidity is not compiled nor
+// executed on an EVM in this crate, so the contract must be tested with solc and an EVM
+// implementing EIP-2537 before it is deployed.
+
+use crate::commitments::kzg_poly_com::KZGCommitmentSchemeBLS;
+use crate::plonk::errors::PlonkError;
+use crate::plonk::plonk_setup::VerifierParams;
+use crate::plonk::protocol::prover::PlonkPf;
+use crate::plonk::transcript::{transcript_init_plonk, KeccakTranscript};
+use algebra::bls12_381::{BLSScalar, BLSG1, BLSG2};
+use algebra::groups::{Group, GroupArithmetic, Scalar};
+use ruc::*;
+use sha3::{Digest, Keccak256};
+use utils::serialization::Validate;
+
+const N_WIRES_PER_GATE: usize = 5;
+const N_SELECTORS: usize = 13;
+
+/// Number of bytes of the proof argument of the verifier contract
+pub const EVM_PROOF_LEN: usize = 13 * EVM_G1_LEN + 10 * 32;
+
+/// Signature of the verification function of the contract
+pub const EVM_VERIFY_SIGNATURE: &str = "verify(bytes,uint256[])";
+
+// Number of bytes of a G1 point in EIP-2537
+const EVM_G1_LEN: usize = 128;
+
+// Offsets in the proof argument of the evaluations, of the commitment and of the evaluation
+// proof of the batch evaluation proof, after the 11 commitments of the PLONK proof
+const EVM_EVALS_OFFSET: usize = 11 * EVM_G1_LEN;
+const EVM_BATCH_COMMITMENT_OFFSET: usize = EVM_EVALS_OFFSET + 10 * 32;
+const EVM_EVAL_PROOF_OFFSET: usize = EVM_BATCH_COMMITMENT_OFFSET + EVM_G1_LEN;
+
+// Encodes a field element of 48 little endian bytes in the 64 big endian bytes of EIP-2537
+fn fp_to_evm(le_bytes: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0u8; 64 - le_bytes.len()];
+    bytes.extend(le_bytes.iter().rev());
+    bytes
+}
+
+fn g1_to_evm(point: &BLSG1) -> Vec<u8> {
+    match point.to_affine_le_bytes() {
+        Some((x, y)) => [fp_to_evm(&x), fp_to_evm(&y)].concat(),
+        None => vec![0u8; EVM_G1_LEN],
+    }
+}
+
+fn g2_to_evm(point: &BLSG2) -> Vec<u8> {
+    match point.to_affine_le_bytes() {
+        Some((x, y)) => [
+            fp_to_evm(&x[0]),
+            fp_to_evm(&x[1]),
+            fp_to_evm(&y[0]),
+            fp_to_evm(&y[1]),
+        ]
+        .concat(),
+        None => vec![0u8; 2 * EVM_G1_LEN],
+    }
+}
+
+// Encodes a scalar as a big endian uint256
+fn scalar_to_evm(scalar: &BLSScalar) -> Vec<u8> {
+    let mut bytes = scalar.to_bytes();
+    bytes.reverse();
+    bytes
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn scalar_to_hex(scalar: &BLSScalar) -> String {
+    format!("0x{}", to_hex(&scalar_to_evm(scalar)))
+}
+
+// Checks that the verifier key is the one of a TurboPlonk circuit without lookup gates, for
+// proofs of a protocol version other than 0, which send L(beta)
+fn check_verifier_key(vk: &VerifierParams<KZGCommitmentSchemeBLS>) -> Result<()> {
+    if vk.lookup.is_some()
+        || vk.version == 0
+        || vk.k.len() != N_WIRES_PER_GATE
+        || vk.selectors.len() != N_SELECTORS
+        || vk.extended_permutations.len() != N_WIRES_PER_GATE
+    {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+    Ok(())
+}
+
+/// Returns the source of a Solidity contract named `contract_name` that verifies the proofs of
+/// the circuit of the verifier key `vk` preprocessed with `pcs`, computed with the transcript
+/// KeccakTranscript::new(transcript_label), see solidity.rs.
+/// Experimental: the contract is not compiled nor executed by the tests of this crate.
+/// Returns SetupError if `vk` was not preprocessed with the SRS of `pcs`, and FuncParamsError
+/// if `vk` is not the key of a TurboPlonk circuit without lookup gates, of a protocol version
+/// other than 0, or if `contract_name` is not a Solidity identifier.
+pub fn solidity_verifier(
+    pcs: &KZGCommitmentSchemeBLS,
+    vk: &VerifierParams<KZGCommitmentSchemeBLS>,
+    transcript_label: &'static [u8],
+    contract_name: &str,
+) -> Result<String> {
+    vk.check_srs(pcs).c(d!())?;
+    check_verifier_key(vk).c(d!())?;
+    let is_identifier = contract_name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !contract_name.starts_with(|c: char| c.is_ascii_digit());
+    if contract_name.is_empty() || !is_identifier {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+
+    let mut transcript = KeccakTranscript::new(transcript_label);
+    transcript_init_plonk(&mut transcript, vk, &[], vk.version);
+
+    let mut vk_points = vec![];
+    for commitment in vk.selectors.iter().chain(vk.extended_permutations.iter()) {
+        vk_points.extend(g1_to_evm(&commitment.value));
+    }
+    vk_points.extend(g1_to_evm(&pcs.public_parameter_group_1[0]));
+    let g2 = &pcs.public_parameter_group_2[0];
+    let neg_g2_s = BLSG2::get_identity().sub(&pcs.public_parameter_group_2[1]);
+    let mut public_input_constants = vec![];
+    for (constraint_index, lagrange_constant) in vk
+        .public_vars_constraint_indices
+        .iter()
+        .zip(vk.lagrange_constants.iter())
+    {
+        let root_to_j = vk.root.pow_u64(*constraint_index as u64);
+        public_input_constants.extend(scalar_to_evm(&root_to_j));
+        public_input_constants.extend(scalar_to_evm(lagrange_constant));
+    }
+
+    let mut source = SOLIDITY_VERIFIER_TEMPLATE
+        .replace("{{CONTRACT_NAME}}", contract_name)
+        .replace(
+            "{{TRANSCRIPT_LABEL}}",
+            &String::from_utf8_lossy(transcript_label),
+        )
+        .replace("{{N}}", &vk.cs_size.to_string())
+        .replace(
+            "{{N_PUBLIC_INPUTS}}",
+            &vk.public_vars_constraint_indices.len().to_string(),
+        )
+        .replace("{{PROOF_LEN}}", &EVM_PROOF_LEN.to_string())
+        .replace("{{EVALS_OFFSET}}", &EVM_EVALS_OFFSET.to_string())
+        .replace(
+            "{{BATCH_COMMITMENT_OFFSET}}",
+            &EVM_BATCH_COMMITMENT_OFFSET.to_string(),
+        )
+        .replace("{{EVAL_PROOF_OFFSET}}", &EVM_EVAL_PROOF_OFFSET.to_string())
+        .replace("{{ROOT}}", &scalar_to_hex(&vk.root))
+        .replace("{{TRANSCRIPT_STATE}}", &to_hex(&transcript.state()))
+        .replace(
+            "{{FIELD_SIZE}}",
+            &to_hex(&BLSScalar::get_field_size_lsf_bytes()),
+        )
+        .replace(
+            "{{MAX_DEGREE}}",
+            &to_hex(&(vk.cs_size as u64 + 2).to_le_bytes()),
+        )
+        .replace("{{VK_POINTS}}", &to_hex(&vk_points))
+        .replace("{{G2_GENERATOR}}", &to_hex(&g2_to_evm(g2)))
+        .replace("{{NEG_G2_S}}", &to_hex(&g2_to_evm(&neg_g2_s)))
+        .replace(
+            "{{PUBLIC_INPUT_CONSTANTS}}",
+            &to_hex(&public_input_constants),
+        );
+    for (i, k) in vk.k.iter().enumerate() {
+        source = source.replace(&format!("{{{{K_{}}}}}", i), &scalar_to_hex(k));
+    }
+    Ok(source)
+}
+
+/// Encodes `proof` for the proof argument of the verifier contract of the circuit of `vk`,
+/// see solidity.rs.
+/// Returns FuncParamsError if `vk` is not the key of a TurboPlonk circuit without lookup
+/// gates, of a protocol version other than 0, and VerificationError if `proof` is not a proof of protocol version `vk.version()`
+/// for it.
+pub fn evm_proof_bytes(
+    vk: &VerifierParams<KZGCommitmentSchemeBLS>,
+    proof: &PlonkPf<KZGCommitmentSchemeBLS>,
+) -> Result<Vec<u8>> {
+    check_verifier_key(vk).c(d!())?;
+    proof.validate().c(d!())?;
+    if proof.version != vk.version
+        || proof.lookup.is_some()
+        || proof.C_witness_polys.len() != N_WIRES_PER_GATE
+        || proof.C_q_polys.len() != N_WIRES_PER_GATE
+    {
+        return Err(eg!(PlonkError::VerificationError));
+    }
+    let mut bytes = Vec::with_capacity(EVM_PROOF_LEN);
+    for commitment in proof
+        .C_witness_polys
+        .iter()
+        .chain(Some(&proof.C_Sigma))
+        .chain(proof.C_q_polys.iter())
+    {
+        bytes.extend(g1_to_evm(&commitment.value));
+    }
+    for eval in proof
+        .witness_polys_eval_beta
+        .iter()
+        .chain(proof.perms_eval_beta.iter())
+        .chain(Some(&proof.Sigma_eval_g_beta))
+    {
+        bytes.extend(scalar_to_evm(eval));
+    }
+    bytes.extend(g1_to_evm(&proof.batch_eval_proof.commitment.value));
+    bytes.extend(g1_to_evm(&proof.batch_eval_proof.eval_proof.0));
+    Ok(bytes)
+}
+
+/// Returns the calldata of the call verify(proof, publicInputs) of the verifier contract of
+/// the circuit of `vk` for the proof `proof` and the public inputs `public_inputs`, ABI
+/// encoded after the selector of EVM_VERIFY_SIGNATURE.
+/// Returns the errors of evm_proof_bytes, and FuncParamsError if the number of public inputs
+/// does not match `vk`.
+pub fn evm_calldata(
+    vk: &VerifierParams<KZGCommitmentSchemeBLS>,
+    proof: &PlonkPf<KZGCommitmentSchemeBLS>,
+    public_inputs: &[BLSScalar],
+) -> Result<Vec<u8>> {
+    if public_inputs.len() != vk.public_vars_constraint_indices.len() {
+        return Err(eg!(PlonkError::FuncParamsError));
+    }
+    let proof_bytes = evm_proof_bytes(vk, proof).c(d!())?;
+    let padded_len = (proof_bytes.len() + 31) / 32 * 32;
+    let mut calldata = Keccak256::digest(EVM_VERIFY_SIGNATURE.as_bytes())[..4].to_vec();
+    // offsets of the dynamic arguments, then the length and the padded bytes of the proof,
+    // then the number and the values of the public inputs
+    calldata.extend(u256_to_evm(0x40));
+    calldata.extend(u256_to_evm(0x60 + padded_len));
+    calldata.extend(u256_to_evm(proof_bytes.len()));
+    calldata.extend(&proof_bytes);
+    calldata.extend(vec![0u8; padded_len - proof_bytes.len()]);
+    calldata.extend(u256_to_evm(public_inputs.len()));
+    for value in public_inputs.iter() {
+        calldata.extend(scalar_to_evm(value));
+    }
+    Ok(calldata)
+}
+
+// Encodes an integer as a big endian uint256
+fn u256_to_evm(value: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; 24];
+    bytes.extend_from_slice(&(value as u64).to_be_bytes());
+    bytes
+}
+
+const SOLIDITY_VERIFIER_TEMPLATE: &str = r#"// SPDX-License-Identifier: BUSL-1.1
+// Generated by poly_iops::plonk::solidity::solidity_verifier, do not edit.
+pragma solidity ^0.8.19;
+
+/// @notice Verifier of the TurboPlonk proofs of a zei circuit, with KZG commitments over
+/// BLS12-381 and the KeccakTranscript labelled "{{TRANSCRIPT_LABEL}}". Requires the BLS12-381
+/// precompiles of EIP-2537.
+contract {{CONTRACT_NAME}} {
+    // order of the scalar field, and 2^256 modulo the order
+    uint256 internal constant R =
+        0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001;
+    uint256 internal constant R_2_256 =
+        0x1824b159acc5056f998c4fefecbc4ff55884b7fa0003480200000001fffffffe;
+    // (p - 1) / 2 for the order p of the base field, high 128 bits and low 256 bits
+    uint256 internal constant HALF_P_HI = 0x0d0088f51cbff34d258dd3db21a5d66b;
+    uint256 internal constant HALF_P_LO =
+        0xb23ba5c279c2895fb39869507b587b120f55ffff58a9ffffdcff7fffffffd555;
+
+    address internal constant MODEXP = address(0x05);
+    address internal constant G1_MSM = address(0x0c);
+    address internal constant PAIRING_CHECK = address(0x0f);
+
+    // size of the constraint system, and generator of its group of roots of unity
+    uint256 internal constant N = {{N}};
+    uint256 internal constant ROOT = {{ROOT}};
+    // constants k_i of the cosets of the wires
+    uint256 internal constant K_0 = {{K_0}};
+    uint256 internal constant K_1 = {{K_1}};
+    uint256 internal constant K_2 = {{K_2}};
+    uint256 internal constant K_3 = {{K_3}};
+    uint256 internal constant K_4 = {{K_4}};
+    uint256 internal constant N_PUBLIC_INPUTS = {{N_PUBLIC_INPUTS}};
+    uint256 internal constant PROOF_LEN = {{PROOF_LEN}};
+
+    // state of the transcript after the absorption of the verifier key
+    bytes32 internal constant TRANSCRIPT_STATE =
+        0x{{TRANSCRIPT_STATE}};
+    bytes internal constant FIELD_SIZE = hex"{{FIELD_SIZE}}";
+    bytes internal constant MAX_DEGREE = hex"{{MAX_DEGREE}}";
+    // commitments to the 13 selectors and the 5 extended permutations, then the generator
+    // of G1
+    bytes internal constant VK_POINTS = hex"{{VK_POINTS}}";
+    // generator g2 of G2 and -[s]g2
+    bytes internal constant G2_GENERATOR = hex"{{G2_GENERATOR}}";
+    bytes internal constant NEG_G2_S = hex"{{NEG_G2_S}}";
+    // for each public input j, the root g^{i_j} of its constraint i_j and its Lagrange
+    // constant
+    bytes internal constant PUBLIC_INPUT_CONSTANTS = hex"{{PUBLIC_INPUT_CONSTANTS}}";
+
+    // offsets of the proof
+    uint256 internal constant EVALS_OFFSET = {{EVALS_OFFSET}};
+    uint256 internal constant BATCH_COMMITMENT_OFFSET = {{BATCH_COMMITMENT_OFFSET}};
+    uint256 internal constant EVAL_PROOF_OFFSET = {{EVAL_PROOF_OFFSET}};
+
+    /// @notice Returns true if `proof` is a valid proof for the public inputs `publicInputs`
+    function verify(bytes calldata proof, uint256[] calldata publicInputs)
+        external
+        view
+        returns (bool)
+    {
+        if (proof.length != PROOF_LEN || publicInputs.length != N_PUBLIC_INPUTS) {
+            return false;
+        }
+        for (uint256 i = 0; i < N_PUBLIC_INPUTS; i++) {
+            if (publicInputs[i] >= R) {
+                return false;
+            }
+        }
+        uint256[10] memory evals;
+        for (uint256 i = 0; i < 10; i++) {
+            evals[i] = word(proof, EVALS_OFFSET + 32 * i);
+            if (evals[i] >= R) {
+                return false;
+            }
+        }
+        // gamma, delta, alpha, beta, then alpha and rho of the batch evaluation proof
+        uint256[6] memory challenges = transcriptChallenges(proof, publicInputs);
+        uint256[32] memory scalars = msmScalars(evals, publicInputs, challenges);
+        return pairingCheck(proof, scalars);
+    }
+
+    // Replays the transcript of the verifier
+    function transcriptChallenges(bytes calldata proof, uint256[] calldata publicInputs)
+        internal
+        view
+        returns (uint256[6] memory challenges)
+    {
+        bytes32 state = TRANSCRIPT_STATE;
+        for (uint256 i = 0; i < publicInputs.length; i++) {
+            state = appendScalar(state, publicInputs[i]);
+        }
+        for (uint256 i = 0; i < 5; i++) {
+            state = appendPoint(state, proof, 128 * i);
+        }
+        (state, challenges[0]) = plonkChallenge(state, "gamma");
+        (state, challenges[1]) = plonkChallenge(state, "delta");
+        state = appendPoint(state, proof, 640);
+        (state, challenges[2]) = plonkChallenge(state, "alpha");
+        for (uint256 i = 0; i < 5; i++) {
+            state = appendPoint(state, proof, 768 + 128 * i);
+        }
+        (state, challenges[3]) = plonkChallenge(state, "beta");
+        for (uint256 i = 0; i < 10; i++) {
+            state = appendScalar(state, word(proof, EVALS_OFFSET + 32 * i));
+        }
+        // the batch evaluation proof at beta for 10 polynomials and at g * beta for Sigma
+        state = appendMessage(state, "Domain Separator", "New PCS-Batch-Eval Protocol");
+        state = appendMessage(state, "field size", FIELD_SIZE);
+        state = appendMessage(state, "max_degree", MAX_DEGREE);
+        for (uint256 i = 0; i < 10; i++) {
+            state = appendScalar(state, challenges[3]);
+        }
+        state = appendScalar(state, mulmod(challenges[3], ROOT, R));
+        (state, challenges[4]) = challenge(state, "alpha");
+        state = appendPoint(state, proof, BATCH_COMMITMENT_OFFSET);
+        (, challenges[5]) = challenge(state, "rho");
+    }
+
+    // Returns the scalars of the multi-scalar multiplication of pairingCheck
+    function msmScalars(
+        uint256[10] memory evals,
+        uint256[] calldata publicInputs,
+        uint256[6] memory challenges
+    ) internal view returns (uint256[32] memory scalars) {
+        (uint256[20] memory lin, uint256 lEval) =
+            linearization(evals, publicInputs, challenges);
+        (uint256[11] memory s, uint256 zRho) = batchScalars(challenges);
+        uint256 valueSum = mulmod(s[9], lEval, R);
+        for (uint256 i = 0; i < 9; i++) {
+            valueSum = addmod(valueSum, mulmod(s[i], evals[i], R), R);
+        }
+        valueSum = addmod(valueSum, mulmod(s[10], evals[9], R), R);
+
+        // proof points: witness polynomials, Sigma, split quotient polynomials, batch
+        // commitment, evaluation proof
+        for (uint256 i = 0; i < 5; i++) {
+            scalars[i] = s[i];
+            scalars[6 + i] = mulmod(s[9], lin[15 + i], R);
+        }
+        scalars[5] = addmod(mulmod(s[9], lin[13], R), s[10], R);
+        scalars[11] = negate(zRho);
+        scalars[12] = challenges[5];
+        // verifier key points: selectors, extended permutations, generator
+        for (uint256 i = 0; i < 13; i++) {
+            scalars[13 + i] = mulmod(s[9], lin[i], R);
+        }
+        for (uint256 i = 0; i < 4; i++) {
+            scalars[26 + i] = s[5 + i];
+        }
+        scalars[30] = mulmod(s[9], lin[14], R);
+        scalars[31] = negate(valueSum);
+    }
+
+    // Returns the scalars s_i = alpha^i * z_i(rho) of the batch evaluation proof, for the
+    // witness polynomials, the permutations but the last and L at beta, then Sigma at
+    // g * beta, and z(rho) = (rho - beta)^10 * (rho - g * beta), where
+    // z_i(rho) = z(rho) / (rho - x_i)
+    function batchScalars(uint256[6] memory challenges)
+        internal
+        view
+        returns (uint256[11] memory s, uint256 zRho)
+    {
+        uint256 d1 = addmod(challenges[5], R - challenges[3], R);
+        uint256 d2 = addmod(challenges[5], R - mulmod(challenges[3], ROOT, R), R);
+        require(d1 != 0 && d2 != 0, "rho is an evaluation point");
+        uint256 d1Pow9 = expmod(d1, 9);
+        uint256 zBeta = mulmod(d1Pow9, d2, R);
+        zRho = mulmod(zBeta, d1, R);
+        uint256 c = 1;
+        for (uint256 i = 0; i < 10; i++) {
+            s[i] = mulmod(c, zBeta, R);
+            c = mulmod(c, challenges[4], R);
+        }
+        s[10] = mulmod(c, mulmod(d1Pow9, d1, R), R);
+    }
+
+    // Returns the scalars of the commitment C_L to the linearization polynomial for the 13
+    // selectors, Sigma, the last extended permutation and the 5 split quotient polynomials,
+    // and the evaluation L(beta), for the challenges gamma, delta, alpha, beta
+    function linearization(
+        uint256[10] memory evals,
+        uint256[] calldata publicInputs,
+        uint256[6] memory challenges
+    ) internal view returns (uint256[20] memory lin, uint256 lEval) {
+        // Z_H(beta) = beta^n - 1 and alpha^2 * L1(beta) = alpha^2 * Z_H(beta) / (beta - 1)
+        uint256 zH = addmod(expmod(challenges[3], N), R - 1, R);
+        uint256 alphaSqL1 = mulmod(
+            mulmod(challenges[2], challenges[2], R),
+            mulmod(zH, inverse(addmod(challenges[3], R - 1, R)), R),
+            R
+        );
+
+        // selector multipliers of TurboPlonkConstraintSystem
+        for (uint256 i = 0; i < 4; i++) {
+            lin[i] = evals[i];
+            lin[8 + i] = pow5(evals[i]);
+        }
+        lin[4] = mulmod(evals[0], evals[1], R);
+        lin[5] = mulmod(evals[2], evals[3], R);
+        lin[6] = 1;
+        lin[7] = mulmod(mulmod(lin[4], lin[5], R), evals[4], R);
+        lin[12] = negate(evals[4]);
+
+        // Sigma: alpha * prod_i (f_i + gamma * k_i * beta + delta) + alpha^2 * L1(beta)
+        {
+            uint256[5] memory k = [K_0, K_1, K_2, K_3, K_4];
+            uint256 gammaBeta = mulmod(challenges[0], challenges[3], R);
+            uint256 a = challenges[2];
+            for (uint256 i = 0; i < 5; i++) {
+                uint256 term = addmod(evals[i], mulmod(gammaBeta, k[i], R), R);
+                a = mulmod(a, addmod(term, challenges[1], R), R);
+            }
+            lin[13] = addmod(a, alphaSqL1, R);
+        }
+
+        // b = alpha * Sigma(g * beta) * prod_{i < 4} (f_i + gamma * perm_i + delta), and
+        // the last extended permutation has the scalar - gamma * b
+        uint256 b = mulmod(challenges[2], evals[9], R);
+        for (uint256 i = 0; i < 4; i++) {
+            uint256 term = addmod(evals[i], mulmod(challenges[0], evals[5 + i], R), R);
+            b = mulmod(b, addmod(term, challenges[1], R), R);
+        }
+        lin[14] = negate(mulmod(b, challenges[0], R));
+
+        // split quotient polynomials: - Z_H(beta) * beta^{(n + 2) i}
+        {
+            uint256 factor = expmod(challenges[3], N + 2);
+            lin[15] = negate(zH);
+            for (uint256 i = 1; i < 5; i++) {
+                lin[15 + i] = mulmod(lin[14 + i], factor, R);
+            }
+        }
+
+        // L(beta) = b * (f_4 + delta) + alpha^2 * L1(beta) - PI(beta)
+        lEval = addmod(
+            addmod(mulmod(b, addmod(evals[4], challenges[1], R), R), alphaSqL1, R),
+            negate(publicInputsEval(publicInputs, challenges[3], zH)),
+            R
+        );
+    }
+
+    // PI(beta) = sum_j c_j * Z_H(beta) / (beta - g^{i_j}) * publicInputs[j]
+    function publicInputsEval(uint256[] calldata publicInputs, uint256 beta, uint256 zH)
+        internal
+        view
+        returns (uint256 eval)
+    {
+        bytes memory constants = PUBLIC_INPUT_CONSTANTS;
+        for (uint256 j = 0; j < N_PUBLIC_INPUTS; j++) {
+            uint256 rootToJ = memoryWord(constants, 64 * j);
+            uint256 lagrangeConstant = memoryWord(constants, 64 * j + 32);
+            uint256 lagrange = mulmod(
+                lagrangeConstant,
+                mulmod(zH, inverse(addmod(beta, R - rootToJ, R)), R),
+                R
+            );
+            eval = addmod(eval, mulmod(lagrange, publicInputs[j], R), R);
+        }
+    }
+
+    // Checks e(A, g2) * e(W, -[s]g2) = 1 for A = sum_i scalars[i] * P_i, see msmScalars
+    function pairingCheck(bytes calldata proof, uint256[32] memory scalars)
+        internal
+        view
+        returns (bool)
+    {
+        bytes memory input = new bytes(32 * 160);
+        bytes memory vkPoints = VK_POINTS;
+        for (uint256 i = 0; i < 32; i++) {
+            uint256 scalar = scalars[i];
+            if (i < 13) {
+                uint256 offset =
+                    i < 11 ? 128 * i : BATCH_COMMITMENT_OFFSET + 128 * (i - 11);
+                assembly {
+                    let dst := add(add(input, 0x20), mul(i, 160))
+                    calldatacopy(dst, add(proof.offset, offset), 128)
+                    mstore(add(dst, 128), scalar)
+                }
+            } else {
+                assembly {
+                    let dst := add(add(input, 0x20), mul(i, 160))
+                    let src := add(add(vkPoints, 0x20), mul(sub(i, 13), 128))
+                    mstore(dst, mload(src))
+                    mstore(add(dst, 0x20), mload(add(src, 0x20)))
+                    mstore(add(dst, 0x40), mload(add(src, 0x40)))
+                    mstore(add(dst, 0x60), mload(add(src, 0x60)))
+                    mstore(add(dst, 128), scalar)
+                }
+            }
+        }
+        (bool ok, bytes memory a) = G1_MSM.staticcall(input);
+        if (!ok || a.length != 128) {
+            return false;
+        }
+        bytes memory pairingInput = abi.encodePacked(
+            a,
+            G2_GENERATOR,
+            proof[EVAL_PROOF_OFFSET:EVAL_PROOF_OFFSET + 128],
+            NEG_G2_S
+        );
+        bytes memory result;
+        (ok, result) = PAIRING_CHECK.staticcall(pairingInput);
+        return ok && result.length == 32 && abi.decode(result, (uint256)) == 1;
+    }
+
+    // KeccakTranscript
+
+    function appendMessage(bytes32 state, bytes memory label, bytes memory message)
+        internal
+        pure
+        returns (bytes32)
+    {
+        return keccak256(
+            abi.encodePacked(
+                state,
+                uint32(label.length),
+                label,
+                uint64(message.length),
+                message
+            )
+        );
+    }
+
+    // Absorbs a scalar as its 32 little endian bytes
+    function appendScalar(bytes32 state, uint256 scalar) internal pure returns (bytes32) {
+        bytes memory message = abi.encodePacked(reverseBytes(scalar));
+        return appendMessage(state, "append field point", message);
+    }
+
+    // Absorbs the G1 point at `offset` in `proof` as its compressed encoding: the 48 little
+    // endian bytes of x, with the flag 0x80 in the last byte if y > -y, and the flag 0x40
+    // alone for the identity
+    function appendPoint(bytes32 state, bytes calldata proof, uint256 offset)
+        internal
+        pure
+        returns (bytes32)
+    {
+        uint256 xHi = word(proof, offset);
+        uint256 xLo = word(proof, offset + 32);
+        uint256 yHi = word(proof, offset + 64);
+        uint256 yLo = word(proof, offset + 96);
+        uint256 flag;
+        if (xHi == 0 && xLo == 0 && yHi == 0 && yLo == 0) {
+            flag = 0x40;
+        } else if (yHi > HALF_P_HI || (yHi == HALF_P_HI && yLo > HALF_P_LO)) {
+            flag = 0x80;
+        }
+        xHi |= flag << 120;
+        bytes memory compressed = abi.encodePacked(
+            bytes32(reverseBytes(xLo)),
+            bytes16(bytes32(reverseBytes(xHi)))
+        );
+        return appendMessage(state, "append commitment", compressed);
+    }
+
+    // Squeezes a challenge: the integer of 64 challenge bytes modulo R
+    function challenge(bytes32 state, bytes memory label)
+        internal
+        pure
+        returns (bytes32, uint256)
+    {
+        state = appendMessage(state, label, abi.encodePacked(uint64(64)));
+        uint256 h0 = uint256(keccak256(abi.encodePacked(state, uint32(0))));
+        uint256 h1 = uint256(keccak256(abi.encodePacked(state, uint32(1))));
+        state = keccak256(abi.encodePacked(state, "ratchet"));
+        return (state, addmod(mulmod(h0, R_2_256, R), h1 % R, R));
+    }
+
+    // Squeezes a challenge that is not a root of unity of order N
+    function plonkChallenge(bytes32 state, bytes memory label)
+        internal
+        view
+        returns (bytes32, uint256)
+    {
+        uint256 c;
+        do {
+            (state, c) = challenge(state, label);
+        } while (expmod(c, N) == 1);
+        return (state, c);
+    }
+
+    // Arithmetic
+
+    function negate(uint256 x) internal pure returns (uint256) {
+        return (R - x) % R;
+    }
+
+    function pow5(uint256 x) internal pure returns (uint256) {
+        uint256 x2 = mulmod(x, x, R);
+        return mulmod(x, mulmod(x2, x2, R), R);
+    }
+
+    function inverse(uint256 x) internal view returns (uint256) {
+        require(x != 0, "inverse of zero");
+        return expmod(x, R - 2);
+    }
+
+    function expmod(uint256 base, uint256 exponent) internal view returns (uint256 result) {
+        bytes memory input =
+            abi.encodePacked(uint256(32), uint256(32), uint256(32), base, exponent, R);
+        (bool ok, bytes memory output) = MODEXP.staticcall(input);
+        require(ok && output.length == 32, "modexp failed");
+        result = abi.decode(output, (uint256));
+    }
+
+    function reverseBytes(uint256 v) internal pure returns (uint256) {
+        v = ((v & 0xFF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00) >> 8)
+            | ((v & 0x00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF) << 8);
+        v = ((v & 0xFFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000) >> 16)
+            | ((v & 0x0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF) << 16);
+        v = ((v & 0xFFFFFFFF00000000FFFFFFFF00000000FFFFFFFF00000000FFFFFFFF00000000) >> 32)
+            | ((v & 0x00000000FFFFFFFF00000000FFFFFFFF00000000FFFFFFFF00000000FFFFFFFF) << 32);
+        v = ((v & 0xFFFFFFFFFFFFFFFF0000000000000000FFFFFFFFFFFFFFFF0000000000000000) >> 64)
+            | ((v & 0x0000000000000000FFFFFFFFFFFFFFFF0000000000000000FFFFFFFFFFFFFFFF) << 64);
+        return (v >> 128) | (v << 128);
+    }
+
+    function word(bytes calldata data, uint256 offset) internal pure returns (uint256 v) {
+        assembly {
+            v := calldataload(add(data.offset, offset))
+        }
+    }
+
+    function memoryWord(bytes memory data, uint256 offset) internal pure returns (uint256 v) {
+        assembly {
+            v := mload(add(add(data, 0x20), offset))
+        }
+    }
+}
+"#;
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
+    use crate::commitments::transcript::PolyComTranscript;
+    use crate::plonk::dump::dump_verification;
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, PlonkConstraintSystem,
+    };
+    use crate::plonk::protocol::prover::prover;
+    use crate::plonk::solidity::{
+        evm_calldata, solidity_verifier, EVM_BATCH_COMMITMENT_OFFSET, EVM_EVALS_OFFSET,
+        EVM_EVAL_PROOF_OFFSET, EVM_PROOF_LEN, EVM_VERIFY_SIGNATURE,
+    };
+    use crate::plonk::transcript::{
+        transcript_init_plonk, KeccakTranscript, PlonkTranscript,
+    };
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::{BLSScalar, Bls12381, BLSG1};
+    use algebra::groups::{Group, GroupArithmetic, Pairing, Scalar, ScalarArithmetic};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use sha3::{Digest, Keccak256};
+
+    type F = BLSScalar;
+
+    // The compressed encoding of the EIP-2537 encoding of a G1 point, as computed by the
+    // verifier contract
+    fn compress_evm_g1(point: &[u8]) -> Vec<u8> {
+        // (p - 1) / 2 in big endian
+        let half_p = hex_to_bytes(concat!(
+            "0d0088f51cbff34d258dd3db21a5d66b",
+            "b23ba5c279c2895fb39869507b587b120f55ffff58a9ffffdcff7fffffffd555"
+        ));
+        let mut x = point[16..64].to_vec();
+        if point.iter().all(|byte| *byte == 0) {
+            x[0] |= 0x40;
+        } else if point[80..128] > half_p[..] {
+            x[0] |= 0x80;
+        }
+        x.reverse();
+        x
+    }
+
+    fn hex_to_bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len() / 2)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn evm_to_scalar(bytes: &[u8]) -> F {
+        let mut le_bytes = bytes.to_vec();
+        le_bytes.reverse();
+        F::from_bytes(&le_bytes).unwrap()
+    }
+
+    #[test]
+    fn test_evm_g1_compression() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let mut points = vec![BLSG1::get_identity(), BLSG1::get_base()];
+        for _ in 0..20 {
+            points.push(BLSG1::get_random_base(&mut prng));
+        }
+        for point in points.iter() {
+            let neg_point = BLSG1::get_identity().sub(point);
+            for p in [point, &neg_point] {
+                assert_eq!(
+                    compress_evm_g1(&super::g1_to_evm(p)),
+                    p.to_compressed_bytes()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_solidity_verifier() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        // x_0 * x_1 + x_0 = x_3 with public inputs x_1 and x_3
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let x0 = cs.new_variable(F::from_u32(3));
+        let x1 = cs.new_variable(F::from_u32(5));
+        let x2 = cs.mul(x0, x1);
+        let x3 = cs.add(x2, x0);
+        cs.prepare_io_variable(x1);
+        cs.prepare_io_variable(x3);
+        cs.pad();
+        let witness = cs.get_and_clear_witness();
+        let public_values = [F::from_u32(5), F::from_u32(18)];
+        let seed = [0u8; 32];
+        let prover_params = preprocess_prover(&cs, &pcs, seed).unwrap();
+        let vk = preprocess_verifier(&cs, &pcs, seed).unwrap();
+
+        let source =
+            solidity_verifier(&pcs, &vk, b"TestEvm", "ZeiPlonkVerifier").unwrap();
+        assert!(source.contains("contract ZeiPlonkVerifier {"));
+        assert!(
+            source.contains(&format!("uint256 internal constant N = {};", vk.cs_size))
+        );
+        assert!(source.contains("uint256 internal constant N_PUBLIC_INPUTS = 2;"));
+        assert!(source.contains(&format!(
+            "uint256 internal constant EVAL_PROOF_OFFSET = {};",
+            EVM_EVAL_PROOF_OFFSET
+        )));
+        assert_eq!(EVM_EVAL_PROOF_OFFSET + 128, EVM_PROOF_LEN);
+        assert!(!source.contains("{{"));
+        let mut transcript = KeccakTranscript::new(b"TestEvm");
+        transcript_init_plonk(&mut transcript, &vk, &[], vk.version);
+        assert!(source.contains(&super::to_hex(&transcript.state())));
+
+        let mut transcript = KeccakTranscript::new(b"TestEvm");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness,
+        )
+        .unwrap();
+        let mut transcript = KeccakTranscript::new(b"TestEvm");
+        let dump =
+            dump_verification(&mut transcript, &pcs, &cs, &vk, &public_values, &proof)
+                .unwrap();
+        assert!(dump.verified);
+
+        let calldata = evm_calldata(&vk, &proof, &public_values).unwrap();
+        assert_eq!(
+            calldata[..4],
+            Keccak256::digest(EVM_VERIFY_SIGNATURE.as_bytes())[..4]
+        );
+        assert_eq!(calldata.len(), 4 + 3 * 32 + EVM_PROOF_LEN + 32 + 2 * 32);
+        let proof_bytes = &calldata[4 + 3 * 32..4 + 3 * 32 + EVM_PROOF_LEN];
+        let inputs = &calldata[4 + 4 * 32 + EVM_PROOF_LEN..];
+        for (j, value) in public_values.iter().enumerate() {
+            assert_eq!(evm_to_scalar(&inputs[32 * j..32 * (j + 1)]), *value);
+        }
+
+        // replay the transcript of the contract on the calldata
+        let point = |offset: usize| compress_evm_g1(&proof_bytes[offset..offset + 128]);
+        let eval = |i: usize| {
+            let offset = EVM_EVALS_OFFSET + 32 * i;
+            evm_to_scalar(&proof_bytes[offset..offset + 32])
+        };
+        let n = vk.cs_size;
+        let mut transcript = KeccakTranscript::new(b"TestEvm");
+        transcript_init_plonk(&mut transcript, &vk, &public_values, vk.version);
+        for i in 0..5 {
+            transcript.append_message(b"append commitment", &point(128 * i));
+        }
+        let gamma: F = transcript.challenge_plonk_field_elem(b"gamma", n);
+        let delta: F = transcript.challenge_plonk_field_elem(b"delta", n);
+        assert_eq!((gamma, delta), (dump.gamma, dump.delta));
+        transcript.append_message(b"append commitment", &point(128 * 5));
+        let alpha: F = transcript.challenge_plonk_field_elem(b"alpha", n);
+        assert_eq!(alpha, dump.alpha);
+        for i in 6..11 {
+            transcript.append_message(b"append commitment", &point(128 * i));
+        }
+        let beta: F = transcript.challenge_plonk_field_elem(b"beta", n);
+        assert_eq!(beta, dump.beta);
+        for i in 0..10 {
+            transcript.append_field_elem(&eval(i));
+        }
+        transcript.append_message(b"Domain Separator", b"New PCS-Batch-Eval Protocol");
+        transcript.append_message(b"field size", &F::get_field_size_lsf_bytes());
+        transcript.append_u64(b"max_degree", n as u64 + 2);
+        for _ in 0..10 {
+            transcript.append_field_elem(&beta);
+        }
+        transcript.append_field_elem(&beta.mul(&vk.root));
+        let batch_alpha: F = transcript.challenge_field_elem(b"alpha");
+        assert_eq!(batch_alpha, dump.batch_eval_alpha);
+        transcript
+            .append_message(b"append commitment", &point(EVM_BATCH_COMMITMENT_OFFSET));
+        let rho: F = transcript.challenge_field_elem(b"rho");
+        assert_eq!(rho, dump.batch_eval_rho);
+
+        // the pairing equation of the contract
+        let eval_proof = &dump.batch_eval_proof.0;
+        let lhs = dump
+            .batch_eval_derived_commitment
+            .value
+            .add(&eval_proof.mul(&rho));
+        assert_eq!(
+            Bls12381::pairing(&lhs, &pcs.public_parameter_group_2[0]),
+            Bls12381::pairing(eval_proof, &pcs.public_parameter_group_2[1])
+        );
+        assert_eq!(
+            &proof_bytes[EVM_EVAL_PROOF_OFFSET..],
+            &super::g1_to_evm(eval_proof)[..]
+        );
+
+        // wrong number of public inputs
+        assert!(evm_calldata(&vk, &proof, &public_values[..1]).is_err());
+        // the 3 wires of a PlonkConstraintSystem
+        let mut cs = PlonkConstraintSystem::<F>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.pad();
+        let vk = preprocess_verifier(&cs, &pcs, seed).unwrap();
+        assert!(solidity_verifier(&pcs, &vk, b"TestEvm", "ZeiPlonkVerifier").is_err());
+    }
+}
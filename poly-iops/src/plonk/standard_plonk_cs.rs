@@ -0,0 +1,720 @@
+// This file implements a standard (non-Turbo) PLONK constraint system with 3 wires per gate and
+// the 5 selectors q_L, q_R, q_M, q_O, q_C. Each gate enforces
+//    wl * q_L + wr * q_R + wl * wr * q_M - wo * q_O + q_C + PI = 0.
+// It offers the variable and gate API of TurboPlonkConstraintSystem (new_variable, add, mul,
+// range_check, select, ...) for circuits that need neither the ECC nor the Rescue gates.
+// Compared to TurboPLONK, a proof commits to 3 witness and 3 quotient polynomials instead of 5 and
+// 5, the quotient polynomial has degree 3n + 5 instead of 5n + 7 and is computed on a domain of
+// size 4n instead of 6n, and the verifier key holds 5 selectors instead of 13. The commitment scheme
+// must support polynomials of degree n + 2, where n is the padded number of constraints.
+
+use crate::plonk::errors::PlonkError;
+use crate::plonk::plonk_setup::ConstraintSystem;
+use crate::plonk::turbo_plonk_cs::{
+    check_var_indices, compute_binary_le, zeroize_witness, CsIndex, VarIndex,
+};
+use algebra::groups::Scalar;
+use ruc::*;
+
+pub const N_WIRES_PER_GATE: usize = 3;
+pub const N_SELECTORS: usize = 5;
+
+#[derive(Serialize, Deserialize)]
+pub struct StandardPlonkConstraintSystem<F: Scalar> {
+    pub selectors: Vec<Vec<F>>,
+    pub wiring: [Vec<VarIndex>; N_WIRES_PER_GATE],
+    pub num_vars: usize,
+    pub size: usize,
+    pub public_vars_constraint_indices: Vec<CsIndex>,
+    pub public_vars_witness_indices: Vec<VarIndex>,
+    // A private witness for the circuit, cleared after computing a proof
+    witness: Vec<F>,
+    // A reserved variable that maps to value zero
+    zero_var: Option<VarIndex>,
+    // A reserved variable that maps to value one
+    one_var: Option<VarIndex>,
+}
+
+impl<F: Scalar> ConstraintSystem for StandardPlonkConstraintSystem<F> {
+    type Field = F;
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    fn wiring(&self) -> &[Vec<usize>] {
+        &self.wiring[..]
+    }
+
+    /// `quot_eval_dom_size` divides (q-1), and should be larger than the degree of the quotient
+    /// polynomial, i.e., `quot_eval_dom_size` > 3 * `self.size` + 5
+    fn quot_eval_dom_size(&self) -> usize {
+        if self.size > 4 {
+            self.size * 4
+        } else {
+            self.size * 8
+        }
+    }
+
+    fn n_wires_per_gate(&self) -> usize {
+        N_WIRES_PER_GATE
+    }
+
+    fn num_selectors(&self) -> usize {
+        N_SELECTORS
+    }
+
+    fn public_vars_constraint_indices(&self) -> &[CsIndex] {
+        &self.public_vars_constraint_indices
+    }
+
+    fn public_vars_witness_indices(&self) -> &[VarIndex] {
+        &self.public_vars_witness_indices
+    }
+
+    fn selector(&self, index: usize) -> Result<&[F]> {
+        if index >= self.selectors.len() {
+            return Err(eg!(PlonkError::SelectorMismatch(
+                index,
+                self.selectors.len()
+            )));
+        }
+        Ok(&self.selectors[index])
+    }
+
+    /// The equation is wl * ql + wr * qr + wl * wr * qm - wo * qo + qc + PI  = 0.
+    fn eval_gate_func(
+        &self,
+        wire_vals: &[&F],
+        sel_vals: &[&F],
+        pub_input: &F,
+    ) -> Result<F> {
+        if sel_vals.len() < N_SELECTORS {
+            return Err(eg!(PlonkError::SelectorMismatch(
+                sel_vals.len(),
+                N_SELECTORS
+            )));
+        }
+        if wire_vals.len() < N_WIRES_PER_GATE {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
+        let left = sel_vals[0].mul(wire_vals[0]);
+        let right = sel_vals[1].mul(wire_vals[1]);
+        let mul = sel_vals[2].mul(&wire_vals[0].mul(wire_vals[1]));
+        let out = sel_vals[3].mul(wire_vals[2]);
+        let constant = sel_vals[4].add(pub_input);
+        Ok(left.add(&right.add(&mul.add(&constant))).sub(&out))
+    }
+
+    /// The coefficients are (wl, wr, wl * wr, -wo, 1).
+    fn eval_selector_multipliers(&self, wire_vals: &[&F]) -> Result<Vec<F>> {
+        if wire_vals.len() < N_WIRES_PER_GATE {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
+        Ok(vec![
+            *wire_vals[0],
+            *wire_vals[1],
+            wire_vals[0].mul(wire_vals[1]),
+            wire_vals[2].neg(),
+            F::one(),
+        ])
+    }
+}
+
+impl<F: Scalar> Default for StandardPlonkConstraintSystem<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Scalar> StandardPlonkConstraintSystem<F> {
+    /// Create an empty standard PLONK constraint system.
+    pub fn new() -> StandardPlonkConstraintSystem<F> {
+        let selectors: Vec<Vec<F>> =
+            std::iter::repeat(vec![]).take(N_SELECTORS).collect();
+        StandardPlonkConstraintSystem {
+            selectors,
+            wiring: [vec![], vec![], vec![]],
+            num_vars: 0,
+            size: 0,
+            public_vars_constraint_indices: vec![],
+            public_vars_witness_indices: vec![],
+            witness: vec![],
+            zero_var: None,
+            one_var: None,
+        }
+    }
+
+    pub fn zero_var(&mut self) -> VarIndex {
+        if self.zero_var.is_none() {
+            self.zero_var = Some(self.num_vars);
+            self.witness.push(F::zero());
+            self.num_vars += 1;
+        }
+        self.zero_var.unwrap() // safe unwrap
+    }
+
+    pub fn one_var(&mut self) -> VarIndex {
+        if self.one_var.is_none() {
+            self.one_var = Some(self.num_vars);
+            self.witness.push(F::one());
+            self.num_vars += 1;
+        }
+        self.one_var.unwrap() // safe unwrap
+    }
+
+    /// Insert a gate with selectors (q_L, q_R, q_M, q_O, q_C) on wires (wl, wr, wo).
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+        q_left: F,
+        q_right: F,
+        q_mul: F,
+        q_out: F,
+        q_constant: F,
+    ) {
+        pnk!(self.try_insert_gate(
+            left_var, right_var, out_var, q_left, q_right, q_mul, q_out, q_constant,
+        ))
+    }
+
+    /// Same as insert_gate, returning WireIndexOutOfBounds instead of panicking if a wire is
+    /// not a variable of the constraint system.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_insert_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+        q_left: F,
+        q_right: F,
+        q_mul: F,
+        q_out: F,
+        q_constant: F,
+    ) -> Result<()> {
+        check_var_indices(&[left_var, right_var, out_var], self.num_vars).c(d!())?;
+        self.selectors[0].push(q_left);
+        self.selectors[1].push(q_right);
+        self.selectors[2].push(q_mul);
+        self.selectors[3].push(q_out);
+        self.selectors[4].push(q_constant);
+        self.wiring[0].push(left_var);
+        self.wiring[1].push(right_var);
+        self.wiring[2].push(out_var);
+        self.size += 1;
+        Ok(())
+    }
+
+    /// Insert a linear combination gate: wo = wl * q1 + wr * q2.
+    pub fn insert_lc_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+        q1: F,
+        q2: F,
+    ) {
+        pnk!(self.try_insert_lc_gate(left_var, right_var, out_var, q1, q2))
+    }
+
+    /// Same as insert_lc_gate, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system.
+    pub fn try_insert_lc_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+        q1: F,
+        q2: F,
+    ) -> Result<()> {
+        let zero = F::zero();
+        self.try_insert_gate(left_var, right_var, out_var, q1, q2, zero, F::one(), zero)
+    }
+
+    pub fn insert_add_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+    ) {
+        pnk!(self.try_insert_add_gate(left_var, right_var, out_var))
+    }
+
+    /// Same as insert_add_gate, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system.
+    pub fn try_insert_add_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+    ) -> Result<()> {
+        self.try_insert_lc_gate(left_var, right_var, out_var, F::one(), F::one())
+    }
+
+    pub fn insert_sub_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+    ) {
+        pnk!(self.try_insert_sub_gate(left_var, right_var, out_var))
+    }
+
+    /// Same as insert_sub_gate, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system.
+    pub fn try_insert_sub_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+    ) -> Result<()> {
+        self.try_insert_lc_gate(left_var, right_var, out_var, F::one(), F::one().neg())
+    }
+
+    pub fn insert_mul_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+    ) {
+        pnk!(self.try_insert_mul_gate(left_var, right_var, out_var))
+    }
+
+    /// Same as insert_mul_gate, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system.
+    pub fn try_insert_mul_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+    ) -> Result<()> {
+        let zero = F::zero();
+        let one = F::one();
+        self.try_insert_gate(left_var, right_var, out_var, zero, zero, one, one, zero)
+    }
+
+    /// Add a variable (with actual value `value`) into the constraint system.
+    pub fn new_variable(&mut self, value: F) -> VarIndex {
+        self.num_vars += 1;
+        self.witness.push(value);
+        self.num_vars - 1
+    }
+
+    /// Add a vector of variables into the constraint system.
+    pub fn add_variables(&mut self, values: &[F]) {
+        self.num_vars += values.len();
+        for value in values.iter() {
+            self.witness.push(*value);
+        }
+    }
+
+    /// Create an output variable and insert a linear combination gate.
+    pub fn linear_combine(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        q1: F,
+        q2: F,
+    ) -> VarIndex {
+        assert!(left_var < self.num_vars, "left_var index out of bound");
+        assert!(right_var < self.num_vars, "right_var index out of bound");
+        let lc = self.witness[left_var]
+            .mul(&q1)
+            .add(&self.witness[right_var].mul(&q2));
+        let out_var = self.new_variable(lc);
+        self.insert_lc_gate(left_var, right_var, out_var, q1, q2);
+        out_var
+    }
+
+    /// Create an output variable and insert an addition gate.
+    pub fn add(&mut self, left_var: VarIndex, right_var: VarIndex) -> VarIndex {
+        self.linear_combine(left_var, right_var, F::one(), F::one())
+    }
+
+    /// Create an output variable and insert a subraction gate.
+    pub fn sub(&mut self, left_var: VarIndex, right_var: VarIndex) -> VarIndex {
+        self.linear_combine(left_var, right_var, F::one(), F::one().neg())
+    }
+
+    // Add a constraint that `left_var` and `right_var` have the same value.
+    pub fn equal(&mut self, left_var: VarIndex, right_var: VarIndex) {
+        let zero_var = self.zero_var();
+        self.insert_sub_gate(left_var, right_var, zero_var);
+    }
+
+    /// Create an output variable and insert a multiplication gate.
+    pub fn mul(&mut self, left_var: VarIndex, right_var: VarIndex) -> VarIndex {
+        assert!(left_var < self.num_vars, "left_var index out of bound");
+        assert!(right_var < self.num_vars, "right_var index out of bound");
+        let out_var =
+            self.new_variable(self.witness[left_var].mul(&self.witness[right_var]));
+        self.insert_mul_gate(left_var, right_var, out_var);
+        out_var
+    }
+
+    /// Boolean constrain `var` by adding a multiplication gate:
+    /// `witness[var] * witness[var] = witness[var]`
+    pub fn insert_boolean_gate(&mut self, var: VarIndex) {
+        self.insert_mul_gate(var, var, var);
+    }
+
+    /// Same as insert_boolean_gate, returning WireIndexOutOfBounds instead of panicking if
+    /// `var` is not in the constraint system.
+    pub fn try_insert_boolean_gate(&mut self, var: VarIndex) -> Result<()> {
+        self.try_insert_mul_gate(var, var, var)
+    }
+
+    /// Enforce a range constraint: `0 <= witness[var] < 2^n_bits`:
+    /// 1. Transform `witness[var]` into a binary vector and boolean constrain the binary vector.
+    /// 2. Adding a chain of linear combination constraints acc' = 2 * acc + b showing that the
+    /// binary vector is a binary representation of `witness[var]`.
+    /// 3. Return witness indices of the binary vector. The binary vector is in little endian form.
+    pub fn range_check(&mut self, var: VarIndex, n_bits: usize) -> Vec<VarIndex> {
+        assert!(var < self.num_vars, "var index out of bound");
+        assert!(n_bits >= 1, "the number of bits is zero");
+        let witness_bytes = self.witness[var].to_bytes();
+        let mut binary_repr = compute_binary_le::<F>(&witness_bytes);
+        while binary_repr.len() < n_bits {
+            binary_repr.push(F::zero());
+        }
+
+        let b: Vec<VarIndex> = binary_repr
+            .into_iter()
+            .take(n_bits)
+            .map(|val| self.new_variable(val))
+            .collect();
+
+        for elem in b.iter() {
+            self.insert_boolean_gate(*elem);
+        }
+        if n_bits == 1 {
+            self.equal(b[0], var);
+            return b;
+        }
+
+        let one = F::one();
+        let two = one.add(&one);
+        let mut acc = b[n_bits - 1];
+        for bit in b[1..n_bits - 1].iter().rev() {
+            acc = self.linear_combine(acc, *bit, two, one);
+        }
+        self.insert_lc_gate(acc, b[0], var, two, one);
+        b
+    }
+
+    /// Given two variables `var0` and `var1` and a boolean variable `bit`, return var_bit.
+    /// var_bit = (1-bit) * var0 + bit * var1 = var0 + bit * (var1 - var0)
+    pub fn select(&mut self, var0: VarIndex, var1: VarIndex, bit: VarIndex) -> VarIndex {
+        assert!(var0 < self.num_vars, "var0 index out of bound");
+        assert!(var1 < self.num_vars, "var1 index out of bound");
+        assert!(bit < self.num_vars, "bit var index out of bound");
+        let diff = self.sub(var1, var0);
+        let bit_diff = self.mul(bit, diff);
+        self.add(var0, bit_diff)
+    }
+
+    // Returns a boolean variable that equals 1 if and only if `left_var` == `right_var`
+    pub fn is_equal(&mut self, left_var: VarIndex, right_var: VarIndex) -> VarIndex {
+        let (is_equal, _) = self.is_equal_or_not_equal(left_var, right_var);
+        is_equal
+    }
+
+    // Returns a boolean variable that equals 1 if and only if `left_var` != `right_var`
+    pub fn is_not_equal(&mut self, left_var: VarIndex, right_var: VarIndex) -> VarIndex {
+        let (_, is_not_equal) = self.is_equal_or_not_equal(left_var, right_var);
+        is_not_equal
+    }
+
+    // Returns two boolean variables that equals (1, 0) if and only if `left_var` == `right_var` and (0, 1) otherwise
+    pub fn is_equal_or_not_equal(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+    ) -> (VarIndex, VarIndex) {
+        let diff = self.sub(left_var, right_var);
+        // `inv_diff` = `diff`^{-1} when `diff` != 0, and an arbitrary value otherwise
+        let inv_diff_scalar = self.witness[diff].inv().unwrap_or_else(|_| F::zero());
+        let inv_diff = self.new_variable(inv_diff_scalar);
+
+        // `diff_is_zero` = 1 - `diff` * `inv_diff`
+        let mul_var = self.mul(diff, inv_diff);
+        let one_var = self.one_var();
+        let diff_is_zero = self.sub(one_var, mul_var);
+
+        // enforce `diff` * `diff_is_zero` == 0
+        let zero_var = self.zero_var();
+        self.insert_mul_gate(diff, diff_is_zero, zero_var);
+
+        (diff_is_zero, mul_var)
+    }
+
+    /// Insert a constant constraint: wo = constant
+    pub fn insert_constant_gate(&mut self, var: VarIndex, constant: F) {
+        pnk!(self.try_insert_constant_gate(var, constant))
+    }
+
+    /// Same as insert_constant_gate, returning WireIndexOutOfBounds instead of panicking if
+    /// `var` is not in the constraint system.
+    pub fn try_insert_constant_gate(
+        &mut self,
+        var: VarIndex,
+        constant: F,
+    ) -> Result<()> {
+        let zero = F::zero();
+        self.try_insert_gate(var, var, var, zero, zero, zero, F::one(), constant)
+    }
+
+    /// Insert constraint of a public IO value to be decided online.
+    pub fn prepare_io_variable(&mut self, var: VarIndex) {
+        self.public_vars_witness_indices.push(var);
+        self.public_vars_constraint_indices.push(self.size);
+        self.insert_constant_gate(var, F::zero());
+    }
+
+    /// Pad the number of constraints to a power of two.
+    pub fn pad(&mut self) {
+        let n = self.size.next_power_of_two();
+        let diff = n - self.size();
+        for selector in self.selectors.iter_mut() {
+            selector.extend(vec![F::zero(); diff]);
+        }
+        for wire in self.wiring.iter_mut() {
+            wire.extend(vec![0; diff]);
+        }
+        self.size += diff;
+    }
+
+    fn get_witness_index(&self, wire_index: usize, cs_index: CsIndex) -> VarIndex {
+        assert!(wire_index < N_WIRES_PER_GATE, "wire index out of bound");
+        assert!(cs_index < self.size, "constraint index out of bound");
+        self.wiring[wire_index][cs_index]
+    }
+
+    pub fn verify_witness(&self, witness: &[F], online_vars: &[F]) -> Result<()> {
+        if witness.len() != self.num_vars {
+            return Err(eg!(format!(
+                "witness len = {}, num_vars = {}",
+                witness.len(),
+                self.num_vars
+            )));
+        }
+        if online_vars.len() != self.public_vars_witness_indices.len()
+            || online_vars.len() != self.public_vars_constraint_indices.len()
+        {
+            return Err(eg!("wrong number of online variables"));
+        }
+        for cs_index in 0..self.size() {
+            let mut public_online = F::zero();
+            // check if the constraint constrains a public variable
+            for ((c_i, w_i), online_var) in self
+                .public_vars_constraint_indices
+                .iter()
+                .zip(self.public_vars_witness_indices.iter())
+                .zip(online_vars.iter())
+            {
+                if *c_i == cs_index {
+                    public_online = *online_var;
+                    if witness[*w_i] != *online_var {
+                        return Err(eg!(format!(
+                            "cs index {}: online var {:?} does not match witness {:?}",
+                            cs_index, *online_var, witness[*w_i]
+                        )));
+                    }
+                }
+            }
+            let wire_vals: Vec<&F> = (0..N_WIRES_PER_GATE)
+                .map(|i| &witness[self.get_witness_index(i, cs_index)])
+                .collect();
+            let sel_vals: Vec<&F> = (0..N_SELECTORS)
+                .map(|i| &self.selectors[i][cs_index])
+                .collect();
+            let eval_gate = self
+                .eval_gate_func(&wire_vals, &sel_vals, &public_online)
+                .c(d!("wrong func params for eval_gate_func()"))?;
+            if eval_gate != F::zero() {
+                return Err(eg!(format!(
+                    "cs index {}: wire_vals = ({:?}), sel_vals = ({:?})",
+                    cs_index, wire_vals, sel_vals
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Extract and clear the entire witness of the circuit. The witness consists of
+    /// secret inputs, public inputs, and the values of intermediate variables.
+    /// The constraint system keeps no copy of the witness, and the caller is responsible for
+    /// wiping the returned values: prefer `with_witness`, which does it.
+    pub fn get_and_clear_witness(&mut self) -> Vec<F> {
+        std::mem::take(&mut self.witness)
+    }
+
+    /// Extract the entire witness of the circuit, pass it to `f` (e.g. a call to the prover),
+    /// then overwrite it with zeroes and return the output of `f`.
+    pub fn with_witness<T, G: FnOnce(&[F]) -> T>(&mut self, f: G) -> T {
+        let mut witness = self.get_and_clear_witness();
+        let res = f(&witness);
+        zeroize_witness(&mut witness);
+        res
+    }
+
+    /// Overwrite the witness of the circuit with zeroes and clear it.
+    pub fn clear_witness(&mut self) {
+        zeroize_witness(&mut self.witness);
+    }
+}
+
+impl<F: Scalar> Drop for StandardPlonkConstraintSystem<F> {
+    fn drop(&mut self) {
+        zeroize_witness(&mut self.witness);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitmentScheme, KZGCommitmentSchemeBLS,
+    };
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, ConstraintSystem,
+    };
+    use crate::plonk::protocol::prover::{prover, verifier};
+    use crate::plonk::standard_plonk_cs::StandardPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::Scalar;
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_gates() {
+        let mut cs = StandardPlonkConstraintSystem::new();
+        let num: Vec<F> = (0..4).map(|x| F::from_u32(x as u32)).collect();
+        cs.add_variables(&num);
+        let add = cs.add(0, 2);
+        let sub = cs.sub(3, 1);
+        cs.equal(add, sub);
+        let prod = cs.mul(2, 3);
+        let selected0 = cs.select(2, 3, 0);
+        let selected1 = cs.select(2, 3, 1);
+        assert_eq!(cs.witness[prod], F::from_u32(6));
+        assert_eq!(cs.witness[selected0], num[2]);
+        assert_eq!(cs.witness[selected1], num[3]);
+        let two_equals_two = cs.is_equal(2, add);
+        let one_equals_two = cs.is_equal(1, 2);
+        assert_eq!(cs.witness[two_equals_two], num[1]);
+        assert_eq!(cs.witness[one_equals_two], num[0]);
+        cs.insert_constant_gate(3, num[3]);
+
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[prod] = F::from_u32(5);
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        // a gate on a variable that is not in the constraint system is rejected
+        let size = cs.size;
+        let num_vars = cs.num_vars;
+        let err = cs.try_insert_mul_gate(0, num_vars, 1).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&format!("WireIndexOutOfBounds: variable {}", num_vars)));
+        assert!(cs.try_insert_constant_gate(num_vars + 1, num[1]).is_err());
+        assert_eq!(cs.size, size);
+    }
+
+    #[test]
+    fn test_range_check() {
+        for n_bits in [1, 2, 5] {
+            let mut cs = StandardPlonkConstraintSystem::new();
+            let value = cs.new_variable(F::from_u32(1));
+            let bits = cs.range_check(value, n_bits);
+            assert_eq!(bits.len(), n_bits);
+            let mut witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness, &[]));
+            witness[value] = F::from_u32(1 << n_bits);
+            assert!(cs.verify_witness(&witness, &[]).is_err());
+        }
+
+        let mut cs = StandardPlonkConstraintSystem::new();
+        let value = cs.new_variable(F::from_u32(21));
+        cs.range_check(value, 5);
+        let witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        let mut cs = StandardPlonkConstraintSystem::new();
+        let value = cs.new_variable(F::from_u32(32));
+        cs.range_check(value, 5);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
+    #[test]
+    fn test_standard_plonk_kzg() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs: KZGCommitmentSchemeBLS = KZGCommitmentScheme::new(20, &mut prng);
+
+        // circuit (x_0 + y_0) * x_1 = y_1, with x_1 in [0, 8) and public inputs y_0, y_1
+        let four = F::from_u32(4);
+        let mut cs = StandardPlonkConstraintSystem::new();
+        let x_0 = cs.new_variable(F::from_u32(3));
+        let x_1 = cs.new_variable(F::from_u32(5));
+        let y_0 = cs.new_variable(four);
+        let sum = cs.add(x_0, y_0);
+        let y_1 = cs.mul(sum, x_1);
+        cs.range_check(x_1, 3);
+        cs.insert_constant_gate(y_0, four);
+        cs.prepare_io_variable(y_0);
+        cs.prepare_io_variable(y_1);
+        cs.pad();
+        assert_eq!(cs.n_wires_per_gate(), 3);
+        assert_eq!(cs.quot_eval_dom_size(), 4 * cs.size());
+
+        let online_vars = [four, F::from_u32(35)];
+        let witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &online_vars));
+
+        let common_seed = [0u8; 32];
+        let prover_params = pnk!(preprocess_prover(&cs, &pcs, common_seed));
+        let mut transcript = Transcript::new(b"TestStandardPlonk");
+        let proof = pnk!(prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness
+        ));
+        assert_eq!(proof.C_witness_polys.len(), 3);
+        assert_eq!(proof.C_q_polys.len(), 3);
+
+        let verifier_params = pnk!(preprocess_verifier(&cs, &pcs, common_seed));
+        let mut transcript = Transcript::new(b"TestStandardPlonk");
+        pnk!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &online_vars,
+            &proof
+        ));
+        let mut transcript = Transcript::new(b"TestStandardPlonk");
+        assert!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[four, F::from_u32(36)],
+            &proof
+        )
+        .is_err());
+    }
+}
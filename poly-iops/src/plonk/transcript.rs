@@ -1,17 +1,337 @@
+// This file implements the Fiat-Shamir transcripts of the PLONK prover and verifier.
+// A PlonkTranscript absorbs messages and squeezes challenges, each under a domain
+// separation label, so that no two messages of a protocol can be confused. The protocols of
+// this crate only use the transcript through the trait: the commitments, field elements and
+// evaluation proofs are absorbed by commitments::transcript::PolyComTranscript, and the
+// challenges are derived by challenge_field_elem and challenge_plonk_field_elem.
+// Three transcripts are provided:
+//   - merlin::Transcript, the STROBE-based transcript used so far; its challenges are the
+//     outputs of a ChaCha PRNG seeded with 32 challenge bytes, as in the previous versions,
+//     so that proofs are unchanged,
+//   - KeccakTranscript, a hash chain of the Keccak-256 sponge (the keccak256 function of
+//     the EVM, whose padding differs from the one of the standardized SHA3-256),
+//     whose challenges are wide integers reduced modulo the size of the field, so that the
+//     challenges can be recomputed by other implementations, e.g. a verifier contract,
+//   - RescueTranscript, a duplex sponge of the Rescue permutation over BLSScalar that
+//     absorbs field elements natively, so that its challenges can be recomputed in a
+//     circuit, e.g. by a recursive verifier.
+// See below.
+
 use crate::commitments::pcs::ToBytes;
 use crate::commitments::transcript::PolyComTranscript;
 use crate::plonk::plonk_setup::PlonkVerifierParams;
-use algebra::groups::Scalar;
-use merlin::Transcript;
+use algebra::bls12_381::BLSScalar;
+use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
+use crypto::basics::hash::rescue::{RescueInstance, RoundSubKey};
+use merlin::{Transcript, TranscriptRng};
 use rand_chacha::ChaChaRng;
 use rand_core::SeedableRng;
+use sha3::{Digest, Keccak256};
+
+/// A Fiat-Shamir transcript with domain separation labels, see transcript.rs
+pub trait PlonkTranscript {
+    /// Absorbs `message` under the label `label`.
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]);
+
+    /// Absorbs the integer `value` under the label `label`, as 8 little endian bytes.
+    fn append_u64(&mut self, label: &'static [u8], value: u64) {
+        self.append_message(label, &value.to_le_bytes());
+    }
+
+    /// Absorbs the field element `elem` under the label `label`, as its bytes by default.
+    fn append_scalar<F: Scalar>(&mut self, label: &'static [u8], elem: &F) {
+        self.append_message(label, &elem.to_bytes());
+    }
+
+    /// Fills `dest` with challenge bytes under the label `label`.
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]);
+
+    /// Derives a challenge field element under the label `label`.
+    fn challenge_field_elem<F: Scalar>(&mut self, label: &'static [u8]) -> F {
+        let mut buff = [0u8; 32];
+        self.challenge_bytes(label, &mut buff);
+        F::random(&mut ChaChaRng::from_seed(buff))
+    }
+
+    /// Derives a challenge field element under the label `label` that is not a root of unity
+    /// of order `group_order`, as required for the challenges of PLONK.
+    fn challenge_plonk_field_elem<F: Scalar>(
+        &mut self,
+        label: &'static [u8],
+        group_order: usize,
+    ) -> F {
+        let mut buff = [0u8; 32];
+        self.challenge_bytes(label, &mut buff);
+        let mut prng = ChaChaRng::from_seed(buff);
+        loop {
+            let elem = F::random(&mut prng);
+            // elem should not be root-of-unity
+            if elem.pow_u64(group_order as u64) != F::one() {
+                return elem;
+            }
+        }
+    }
+}
+
+impl PlonkTranscript for Transcript {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        Transcript::append_message(self, label, message);
+    }
+
+    fn append_u64(&mut self, label: &'static [u8], value: u64) {
+        Transcript::append_u64(self, label, value);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        Transcript::challenge_bytes(self, label, dest);
+    }
+}
+
+/// A transcript over the Keccak-256 hash function, for verifiers that cannot run STROBE, e.g.
+/// on the EVM. The state is a 32-byte digest:
+///   - new(label): state = H("Zei Keccak transcript" || u32(len(label)) || label),
+///   - absorbing message under label:
+///     state = H(state || u32(len(label)) || label || u64(len(message)) || message),
+///   - squeezing k bytes under label: absorb the 8 bytes u64(k) under label, output the first
+///     k bytes of H(state || u32(0)) || H(state || u32(1)) || ..., then
+///     state = H(state || "ratchet"),
+/// with H = Keccak-256 (not SHA3-256) and the integers u32, u64 in big endian. A challenge field element is
+/// the integer of 64 challenge bytes in big endian, reduced modulo the size of the field; a
+/// PLONK challenge that is a root of unity is squeezed again under the same label.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeccakTranscript {
+    state: [u8; 32],
+}
+
+impl KeccakTranscript {
+    /// Returns a transcript for the protocol labelled `label`.
+    pub fn new(label: &'static [u8]) -> KeccakTranscript {
+        let mut hasher = Keccak256::new();
+        hasher.update(b"Zei Keccak transcript");
+        hasher.update(&(label.len() as u32).to_be_bytes());
+        hasher.update(label);
+        KeccakTranscript {
+            state: hasher.finalize().into(),
+        }
+    }
+
+    /// Returns the current state of the transcript
+    pub fn state(&self) -> [u8; 32] {
+        self.state
+    }
 
-pub(crate) fn transcript_init_plonk<C: ToBytes, F: Scalar>(
-    transcript: &mut Transcript,
+    fn hash_with_state(&self, suffix: &[u8]) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(&self.state);
+        hasher.update(suffix);
+        hasher.finalize().into()
+    }
+}
+
+impl PlonkTranscript for KeccakTranscript {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        let mut hasher = Keccak256::new();
+        hasher.update(&self.state);
+        hasher.update(&(label.len() as u32).to_be_bytes());
+        hasher.update(label);
+        hasher.update(&(message.len() as u64).to_be_bytes());
+        hasher.update(message);
+        self.state = hasher.finalize().into();
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.append_message(label, &(dest.len() as u64).to_be_bytes());
+        for (i, chunk) in dest.chunks_mut(32).enumerate() {
+            let block = self.hash_with_state(&(i as u32).to_be_bytes());
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        self.state = self.hash_with_state(b"ratchet");
+    }
+
+    fn challenge_field_elem<F: Scalar>(&mut self, label: &'static [u8]) -> F {
+        let mut buff = [0u8; 64];
+        self.challenge_bytes(label, &mut buff);
+        field_elem_from_be_bytes(&buff)
+    }
+
+    fn challenge_plonk_field_elem<F: Scalar>(
+        &mut self,
+        label: &'static [u8],
+        group_order: usize,
+    ) -> F {
+        loop {
+            let elem: F = self.challenge_field_elem(label);
+            if elem.pow_u64(group_order as u64) != F::one() {
+                return elem;
+            }
+        }
+    }
+}
+
+// Returns the integer of `bytes` in big endian modulo the size of F, for a multiple of 8 bytes
+fn field_elem_from_be_bytes<F: Scalar>(bytes: &[u8]) -> F {
+    // sum_i limb_i * 2^(64 i) with the big endian limbs of 64 bits of bytes
+    let two_pow_64 = F::from_u64(u64::MAX).add(&F::one());
+    let mut elem = F::zero();
+    for limb in bytes.chunks(8) {
+        let mut limb_bytes = [0u8; 8];
+        limb_bytes.copy_from_slice(limb);
+        elem = elem
+            .mul(&two_pow_64)
+            .add(&F::from_u64(u64::from_be_bytes(limb_bytes)));
+    }
+    elem
+}
+
+/// Tags of the operations of a RescueTranscript, absorbed before their label
+pub(crate) const RESCUE_TRANSCRIPT_NEW: u32 = 0;
+pub(crate) const RESCUE_TRANSCRIPT_MESSAGE: u32 = 1;
+pub(crate) const RESCUE_TRANSCRIPT_SCALAR: u32 = 2;
+pub(crate) const RESCUE_TRANSCRIPT_CHALLENGE: u32 = 3;
+
+/// Number of bytes packed in a field element by a RescueTranscript
+pub(crate) const RESCUE_TRANSCRIPT_BYTES_PER_ELEM: usize = 31;
+
+/// Number of challenge bytes taken from a challenge field element of a RescueTranscript, so
+/// that they are uniform up to a statistical distance of 2^-127
+const RESCUE_TRANSCRIPT_CHALLENGE_BYTES: usize = 16;
+
+/// Returns the elements absorbed by a RescueTranscript for the operation `tag` under `label`:
+/// the tag, the length of the label and the label packed in field elements
+pub(crate) fn rescue_transcript_header(tag: u32, label: &[u8]) -> Vec<BLSScalar> {
+    let mut elems = vec![
+        BLSScalar::from_u32(tag),
+        BLSScalar::from_u64(label.len() as u64),
+    ];
+    elems.extend(rescue_transcript_pack_bytes(label));
+    elems
+}
+
+/// Packs `bytes` in field elements of RESCUE_TRANSCRIPT_BYTES_PER_ELEM bytes in little endian
+pub(crate) fn rescue_transcript_pack_bytes(bytes: &[u8]) -> Vec<BLSScalar> {
+    bytes
+        .chunks(RESCUE_TRANSCRIPT_BYTES_PER_ELEM)
+        // safe unwrap: a chunk is shorter than a field element
+        .map(|chunk| BLSScalar::from_bytes(chunk).unwrap())
+        .collect()
+}
+
+/// A transcript over the Rescue sponge of crypto::basics::hash::rescue, whose challenges can
+/// be derived in a TurboPlonk circuit over BLSScalar at the cost of a few rescue permutations,
+/// see TurboPlonkConstraintSystem::new_rescue_transcript. The transcript is a duplex sponge
+/// of rate 3 and capacity 1 that absorbs field elements:
+///   - new(label): the state is zero, then the sponge absorbs the header of NEW and `label`,
+///   - absorbing message under label: the header of MESSAGE and `label`, the length of
+///     `message` and `message` packed in elements of 31 bytes,
+///   - absorbing a BLSScalar elem under label: the header of SCALAR and `label`, then `elem`,
+///   - squeezing a challenge under label: absorb the header of CHALLENGE and `label`, pad the
+///     absorbed elements with a one and zeroes to a multiple of the rate, add each block to
+///     the first elements of the state and permute, then output the first element of the
+///     state,
+/// where the header of an operation is its tag, the length of the label and the label packed
+/// in elements of 31 bytes. A challenge field element is the challenge itself; challenge
+/// bytes are the first 16 bytes of challenges. Field elements of other fields of at most 32
+/// bytes are reduced modulo the size of BLSScalar, and larger ones are absorbed as messages.
+pub struct RescueTranscript {
+    hash: RescueInstance<BLSScalar>,
+    round_keys: Vec<RoundSubKey<BLSScalar>>,
+    state: Vec<BLSScalar>,
+    pending: Vec<BLSScalar>,
+}
+
+impl RescueTranscript {
+    /// Returns a transcript for the protocol labelled `label`.
+    pub fn new(label: &'static [u8]) -> RescueTranscript {
+        let hash = RescueInstance::new();
+        let round_keys = hash.hash_init();
+        let state = vec![BLSScalar::zero(); hash.state_size()];
+        RescueTranscript {
+            hash,
+            round_keys,
+            state,
+            pending: rescue_transcript_header(RESCUE_TRANSCRIPT_NEW, label),
+        }
+    }
+
+    /// Squeezes a challenge under the label `label`.
+    pub fn challenge(&mut self, label: &'static [u8]) -> BLSScalar {
+        self.pending
+            .extend(rescue_transcript_header(RESCUE_TRANSCRIPT_CHALLENGE, label));
+        let mut padded = std::mem::take(&mut self.pending);
+        padded.push(BLSScalar::one());
+        let rate = self.hash.rate;
+        let n_zeroes = (rate - padded.len() % rate) % rate;
+        padded.extend(vec![BLSScalar::zero(); n_zeroes]);
+        for block in padded.chunks(rate) {
+            for (state_i, elem) in self.state.iter_mut().zip(block) {
+                state_i.add_assign(elem);
+            }
+            self.state = self
+                .hash
+                .rescue_with_round_keys(&self.state, &self.round_keys);
+        }
+        self.state[0]
+    }
+}
+
+impl PlonkTranscript for RescueTranscript {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.pending
+            .extend(rescue_transcript_header(RESCUE_TRANSCRIPT_MESSAGE, label));
+        self.pending.push(BLSScalar::from_u64(message.len() as u64));
+        self.pending.extend(rescue_transcript_pack_bytes(message));
+    }
+
+    fn append_scalar<F: Scalar>(&mut self, label: &'static [u8], elem: &F) {
+        if F::bytes_len() > BLSScalar::bytes_len() {
+            self.append_message(label, &elem.to_bytes());
+            return;
+        }
+        self.pending
+            .extend(rescue_transcript_header(RESCUE_TRANSCRIPT_SCALAR, label));
+        // safe unwrap: the bytes are not longer than a BLSScalar
+        self.pending
+            .push(BLSScalar::from_bytes(&elem.to_bytes()).unwrap());
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(RESCUE_TRANSCRIPT_CHALLENGE_BYTES) {
+            let challenge = self.challenge(label).to_bytes();
+            chunk.copy_from_slice(&challenge[..chunk.len()]);
+        }
+    }
+
+    fn challenge_field_elem<F: Scalar>(&mut self, label: &'static [u8]) -> F {
+        let mut bytes = self.challenge(label).to_bytes();
+        bytes.reverse();
+        field_elem_from_be_bytes(&bytes)
+    }
+
+    fn challenge_plonk_field_elem<F: Scalar>(
+        &mut self,
+        label: &'static [u8],
+        group_order: usize,
+    ) -> F {
+        loop {
+            let elem: F = self.challenge_field_elem(label);
+            if elem.pow_u64(group_order as u64) != F::one() {
+                return elem;
+            }
+        }
+    }
+}
+
+pub(crate) fn transcript_init_plonk<T: PlonkTranscript, C: ToBytes, F: Scalar>(
+    transcript: &mut T,
     params: &PlonkVerifierParams<C, F>,
     io_values: &[F],
+    version: u32,
 ) {
     transcript.append_message(b"New Domain", b"PLONK");
+    match version {
+        0 => {}
+        _ => transcript.append_u64(b"protocol version", version as u64),
+    }
     // TODO hash all this in preprocessing step
     transcript.append_u64(b"CS size", params.cs_size as u64);
     transcript.append_message(b"field size", &F::get_field_size_lsf_bytes());
@@ -25,53 +345,214 @@ pub(crate) fn transcript_init_plonk<C: ToBytes, F: Scalar>(
     for generator in params.k.iter() {
         transcript.append_field_elem(generator);
     }
+    if let Some(lookup) = &params.lookup {
+        transcript.append_commitment(&lookup.q_lookup);
+        transcript.append_commitment(&lookup.q_table);
+        for column in lookup.table.iter() {
+            transcript.append_commitment(column);
+        }
+        for value in lookup.default_entry.iter() {
+            transcript.append_field_elem(value);
+        }
+    }
 
     for io_value in io_values.iter() {
         transcript.append_field_elem(io_value);
     }
 }
 
-pub(crate) fn transcript_get_challenge_field_elem<F: Scalar>(
-    transcript: &mut Transcript,
+pub(crate) fn transcript_get_challenge_field_elem<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
     group_order: usize,
     label: &'static [u8],
 ) -> F {
-    let mut buff = [0u8; 32];
-    transcript.challenge_bytes(label, &mut buff);
-    let mut prng = ChaChaRng::from_seed(buff);
-    loop {
-        let elem = F::random(&mut prng);
-        // elem should not be root-of-unity
-        if elem.pow(&[group_order as u64]) != F::one() {
-            return elem;
-        }
-    }
+    transcript.challenge_plonk_field_elem(label, group_order)
 }
 
-pub(crate) fn transcript_get_plonk_challenge_alpha<F: Scalar>(
-    transcript: &mut Transcript,
+pub(crate) fn transcript_get_plonk_challenge_alpha<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
     group_order: usize,
 ) -> F {
     transcript_get_challenge_field_elem(transcript, group_order, b"alpha")
 }
 
-pub(crate) fn transcript_get_plonk_challenge_beta<F: Scalar>(
-    transcript: &mut Transcript,
+pub(crate) fn transcript_get_plonk_challenge_beta<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
     group_order: usize,
 ) -> F {
     transcript_get_challenge_field_elem(transcript, group_order, b"beta")
 }
 
-pub(crate) fn transcript_get_plonk_challenge_gamma<F: Scalar>(
-    transcript: &mut Transcript,
+pub(crate) fn transcript_get_plonk_challenge_gamma<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
     group_order: usize,
 ) -> F {
     transcript_get_challenge_field_elem(transcript, group_order, b"gamma")
 }
 
-pub(crate) fn transcript_get_plonk_challenge_delta<F: Scalar>(
-    transcript: &mut Transcript,
+pub(crate) fn transcript_get_plonk_challenge_delta<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
     group_order: usize,
 ) -> F {
     transcript_get_challenge_field_elem(transcript, group_order, b"delta")
 }
+
+pub(crate) fn transcript_get_plonk_challenge_zeta<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
+    group_order: usize,
+) -> F {
+    transcript_get_challenge_field_elem(transcript, group_order, b"zeta")
+}
+
+pub(crate) fn transcript_get_plonk_challenge_epsilon<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
+    group_order: usize,
+) -> F {
+    transcript_get_challenge_field_elem(transcript, group_order, b"epsilon")
+}
+
+pub(crate) fn transcript_get_plonk_challenge_theta<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
+    group_order: usize,
+) -> F {
+    transcript_get_challenge_field_elem(transcript, group_order, b"theta")
+}
+
+/// Returns a PRNG for the blinding factors of a prover, seeded from the current state of
+/// `transcript`, the `witness` and a `secret_key` of the prover, without modifying
+/// `transcript`. Proving the same witness from the same transcript state and key gives the
+/// same proof bit for bit, so proofs can be reproduced in tests and audits. The blinding
+/// factors are unpredictable to anyone who does not know `secret_key`, hence the proofs stay
+/// zero-knowledge as long as `secret_key` is uniformly random and kept secret. Different
+/// witnesses or statements give independent blinding factors.
+pub fn transcript_prover_rng<F: Scalar>(
+    transcript: &Transcript,
+    secret_key: &[u8; 32],
+    witness: &[F],
+) -> TranscriptRng {
+    let mut builder = transcript
+        .build_rng()
+        .rekey_with_witness_bytes(b"prover secret key", secret_key);
+    for value in witness.iter() {
+        builder = builder.rekey_with_witness_bytes(b"witness", &value.to_bytes());
+    }
+    // no external randomness, the secret key provides the entropy
+    builder.finalize(&mut ChaChaRng::from_seed([0u8; 32]))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, PlonkConstraintSystem,
+    };
+    use crate::plonk::protocol::prover::{prover, verifier};
+    use crate::plonk::transcript::{
+        KeccakTranscript, PlonkTranscript, RescueTranscript,
+    };
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_keccak_transcript() {
+        let mut transcript = KeccakTranscript::new(b"Test");
+        transcript.append_message(b"label", b"message");
+        let mut other = transcript.clone();
+        let mut bytes = [0u8; 40];
+        transcript.challenge_bytes(b"challenge", &mut bytes);
+        let mut other_bytes = [0u8; 40];
+        other.challenge_bytes(b"challenge", &mut other_bytes);
+        assert_eq!(bytes, other_bytes);
+        assert_eq!(transcript, other);
+        // the transcript moved on
+        transcript.challenge_bytes(b"challenge", &mut other_bytes);
+        assert_ne!(bytes, other_bytes);
+
+        // the labels and the boundaries of the messages are absorbed
+        let mut a = KeccakTranscript::new(b"Test");
+        a.append_message(b"label", b"message");
+        let mut b = KeccakTranscript::new(b"Test");
+        b.append_message(b"labelm", b"essage");
+        assert_ne!(a.state(), b.state());
+        let mut c = KeccakTranscript::new(b"Test");
+        c.append_message(b"label", b"mess");
+        c.append_message(b"label", b"age");
+        assert_ne!(a.state(), c.state());
+        assert_ne!(
+            KeccakTranscript::new(b"Test").state(),
+            KeccakTranscript::new(b"Tesu").state()
+        );
+
+        let x: BLSScalar = a.challenge_field_elem(b"x");
+        let y: BLSScalar = a.challenge_field_elem(b"x");
+        assert_ne!(x, y);
+    }
+
+    #[test]
+    fn test_plonk_keccak_transcript() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let seed = [0u8; 32];
+        let prover_params = preprocess_prover(&cs, &pcs, seed).unwrap();
+        let vk = preprocess_verifier(&cs, &pcs, seed).unwrap();
+
+        let mut transcript = KeccakTranscript::new(b"TestPlonk");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three],
+        )
+        .unwrap();
+        let mut transcript = KeccakTranscript::new(b"TestPlonk");
+        assert!(verifier(&mut transcript, &pcs, &cs, &vk, &[three], &proof).is_ok());
+        // the challenges of another transcript
+        let mut transcript = KeccakTranscript::new(b"OtherPlonk");
+        assert!(verifier(&mut transcript, &pcs, &cs, &vk, &[three], &proof).is_err());
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(verifier(&mut transcript, &pcs, &cs, &vk, &[three], &proof).is_err());
+    }
+
+    #[test]
+    fn test_plonk_rescue_transcript() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+        let seed = [0u8; 32];
+        let prover_params = preprocess_prover(&cs, &pcs, seed).unwrap();
+        let vk = preprocess_verifier(&cs, &pcs, seed).unwrap();
+
+        let mut transcript = RescueTranscript::new(b"TestPlonk");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three],
+        )
+        .unwrap();
+        let mut transcript = RescueTranscript::new(b"TestPlonk");
+        assert!(verifier(&mut transcript, &pcs, &cs, &vk, &[three], &proof).is_ok());
+        let mut transcript = RescueTranscript::new(b"OtherPlonk");
+        assert!(verifier(&mut transcript, &pcs, &cs, &vk, &[three], &proof).is_err());
+    }
+}
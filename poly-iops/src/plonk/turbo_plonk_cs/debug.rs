@@ -0,0 +1,200 @@
+// This file implements the debug mode of the constraint system, enabled by the feature
+// `debug_gates`. In debug mode, the constraint system records a label for each gate: the
+// namespace of the gate, see stats.rs, and the source location of the push_namespace call that
+// opened the namespace. When a gate is not satisfied, verify_witness reports the label and the
+// symbolic equation of the gate, with the non-zero selectors only, e.g.
+//   q1*w1 + q2*w2 = qo*wo with q1 = 1, q2 = 1, qo = 1, w1 = v3, w2 = v4, w3 = v0, w4 = v0, wo = v5
+// where v3 is the variable of index 3. The gates of other layouts than the one of
+// TurboPlonkConstraintSystem are reported with the values of their selectors and wires. The
+// labels are not serialized, so a deserialized constraint system has no labels.
+
+use crate::plonk::turbo_plonk_cs::stats::SELECTOR_NAMES;
+use crate::plonk::turbo_plonk_cs::{
+    CsIndex, TurboConstraintSystem, N_SELECTORS, N_WIRES_PER_GATE,
+};
+use algebra::groups::Scalar;
+use std::fmt;
+use std::panic::Location;
+
+// The wires multiplied by each selector, except qo that multiplies wo
+const SELECTOR_MONOMIALS: [&str; N_SELECTORS - 1] = [
+    "w1",
+    "w2",
+    "w3",
+    "w4",
+    "w1*w2",
+    "w3*w4",
+    "",
+    "w1*w2*w3*w4*wo",
+    "w1^5",
+    "w2^5",
+    "w3^5",
+    "w4^5",
+];
+
+/// The label of a gate in debug mode
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GateLabel {
+    /// The namespace of the gate, see TurboPlonkConstraintSystem::namespace
+    pub namespace: String,
+    /// The location of the push_namespace call of the innermost namespace of the gate, None
+    /// for the gates outside of any namespace and the padding gates
+    pub location: Option<&'static Location<'static>>,
+}
+
+impl fmt::Display for GateLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let namespace = if self.namespace.is_empty() {
+            "<root>"
+        } else {
+            &self.namespace
+        };
+        match self.location {
+            Some(location) => write!(f, "{} ({})", namespace, location),
+            None => write!(f, "{}", namespace),
+        }
+    }
+}
+
+impl<F: Scalar, const W: usize, const S: usize> TurboConstraintSystem<F, W, S> {
+    // Records the label of the gate being added, called once per gate by push_out_selector
+    pub(super) fn record_gate_label(&mut self) {
+        let label = GateLabel {
+            namespace: self.namespace(),
+            location: self.namespace_locations.last().copied(),
+        };
+        self.gate_labels.push(label);
+    }
+
+    // Records the label of `n` padding gates
+    pub(super) fn record_padding_labels(&mut self, n: usize) {
+        let label = GateLabel {
+            namespace: String::from("<padding>"),
+            location: None,
+        };
+        self.gate_labels.extend(vec![label; n]);
+    }
+
+    /// Returns the label of the gate `cs_index`, None if the constraint system has no label
+    /// for the gate, e.g. after deserialization.
+    pub fn gate_label(&self, cs_index: CsIndex) -> Option<&GateLabel> {
+        self.gate_labels.get(cs_index)
+    }
+
+    /// Returns the symbolic equation of the gate `cs_index`, with its non-zero selectors and the
+    /// variables of its wires. The equation of a gate with another layout than the one of
+    /// TurboPlonkConstraintSystem is unknown: its selectors and its wires are listed only.
+    pub fn gate_equation(&self, cs_index: CsIndex) -> String {
+        assert!(cs_index < self.size, "constraint index out of bound");
+        if W != N_WIRES_PER_GATE || S != N_SELECTORS {
+            return self.gate_values(cs_index);
+        }
+        let mut terms = vec![];
+        let mut values = vec![];
+        for (i, monomial) in SELECTOR_MONOMIALS.iter().enumerate() {
+            let q = &self.selectors[i][cs_index];
+            if *q != F::zero() {
+                if monomial.is_empty() {
+                    terms.push(SELECTOR_NAMES[i].to_string());
+                } else {
+                    terms.push(format!("{}*{}", SELECTOR_NAMES[i], monomial));
+                }
+                values.push(format!("{} = {:?}", SELECTOR_NAMES[i], q));
+            }
+        }
+        if self.public_vars_constraint_indices.contains(&cs_index) {
+            terms.push(String::from("PI"));
+        }
+        let q_out = &self.selectors[N_SELECTORS - 1][cs_index];
+        let right = if *q_out != F::zero() {
+            values.push(format!("{} = {:?}", SELECTOR_NAMES[N_SELECTORS - 1], q_out));
+            format!("{}*wo", SELECTOR_NAMES[N_SELECTORS - 1])
+        } else {
+            String::from("0")
+        };
+        let left = if terms.is_empty() {
+            String::from("0")
+        } else {
+            terms.join(" + ")
+        };
+        for (name, wire) in ["w1", "w2", "w3", "w4", "wo"]
+            .iter()
+            .zip(self.wiring.iter())
+        {
+            values.push(format!("{} = v{}", name, wire[cs_index]));
+        }
+        format!("{} = {} with {}", left, right, values.join(", "))
+    }
+
+    // Lists the non-zero selectors s1, s2.. and the wires w1, w2.. of the gate `cs_index`
+    fn gate_values(&self, cs_index: CsIndex) -> String {
+        let mut values = vec![];
+        for (i, selector) in self.selectors.iter().enumerate() {
+            if selector[cs_index] != F::zero() {
+                values.push(format!("s{} = {:?}", i + 1, selector[cs_index]));
+            }
+        }
+        for (i, wire) in self.wiring.iter().enumerate() {
+            values.push(format!("w{} = v{}", i + 1, wire[cs_index]));
+        }
+        format!("gate with {}", values.join(", "))
+    }
+
+    // Describes the gate `cs_index` for the errors of verify_witness
+    pub(super) fn describe_gate(&self, cs_index: CsIndex) -> String {
+        let label = match self.gate_label(cs_index) {
+            Some(label) => label.to_string(),
+            None => String::from("<unlabeled>"),
+        };
+        format!("gate {}: {}", label, self.gate_equation(cs_index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar};
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_gate_labels() {
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_variable(F::from_u32(3));
+        let b = cs.new_variable(F::from_u32(5));
+        let ab = cs.mul(a, b);
+        cs.push_namespace("sum");
+        let c = cs.add(a, b);
+        cs.pop_namespace();
+        let _ = cs.sub(b, a);
+        cs.pad();
+
+        assert_eq!(cs.gate_label(0).unwrap().namespace, "");
+        assert!(cs.gate_label(0).unwrap().location.is_none());
+        let label = cs.gate_label(1).unwrap();
+        assert_eq!(label.namespace, "sum");
+        assert!(label.location.unwrap().file().ends_with("debug.rs"));
+        assert_eq!(cs.gate_label(cs.size - 1).unwrap().namespace, "<padding>");
+        assert_eq!(
+            cs.gate_equation(0),
+            format!(
+                "qm1*w1*w2 = qo*wo with qm1 = {:?}, qo = {:?}, w1 = v{}, w2 = v{}, w3 = v0, \
+                 w4 = v0, wo = v{}",
+                F::one(),
+                F::one(),
+                a,
+                b,
+                ab
+            )
+        );
+
+        let mut witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_ok());
+        witness[c] = F::one();
+        let err = cs.verify_witness(&witness, &[]).unwrap_err().to_string();
+        assert!(err.contains("cs index 1"));
+        assert!(err.contains("gate sum (") && err.contains("debug.rs:"));
+        assert!(err.contains("q1*w1 + q2*w2 = qo*wo"));
+    }
+}
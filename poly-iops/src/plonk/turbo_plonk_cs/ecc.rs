@@ -1,10 +1,19 @@
-use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+// This file implements the gates of the elliptic curve operations over Jubjub in a constraint
+// system over BLSScalar: point variables, on-curve and subgroup checks, point compression,
+// point additions, and the fixed-base, variable-base and multi-scalar multiplications.
+
+use crate::plonk::turbo_plonk_cs::{
+    TurboPlonkConstraintSystem, VarIndex, RANGE_CHUNK_BITS,
+};
 use algebra::bls12_381::BLSScalar;
 use algebra::groups::{Group, GroupArithmetic, One, Scalar, ScalarArithmetic, Zero};
-use algebra::jubjub::JubjubPoint;
+use algebra::jubjub::{JubjubPoint, JubjubScalar};
 
 type F = BLSScalar;
 
+/// Number of bits of the integer representatives of Jubjub scalars
+pub const JUBJUB_SCALAR_BIT_LEN: usize = 252;
+
 #[derive(Clone)]
 pub struct Point(F, F); // represents a curve point in Affine form
 pub struct PointVar(VarIndex, VarIndex); // The witness indices for x/y-coordinates of a point
@@ -88,6 +97,32 @@ fn compute_base_multiples(base: JubjubPoint, n: usize) -> Vec<Vec<JubjubPoint>>
     bases
 }
 
+/// Number of bits of the integers (x - parity(x)) / 2 for x in the BLS scalar field
+const HALF_BLS_SCALAR_BIT_LEN: usize = 254;
+
+// Returns the Sapling encoding of the point with the y-coordinate `y` and an x-coordinate of
+// parity `sign`: y in little endian with `sign` in the most significant bit.
+fn sapling_bytes(sign: bool, y: &BLSScalar) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&y.to_bytes());
+    bytes[31] |= (sign as u8) << 7;
+    bytes
+}
+
+/// Returns the Jubjub point of affine coordinates (x, y), None if (x, y) is not on the curve.
+pub(crate) fn point_from_coordinates(
+    x: &BLSScalar,
+    y: &BLSScalar,
+) -> Option<JubjubPoint> {
+    let bytes = sapling_bytes(x.to_bytes()[0] & 1 == 1, y);
+    let point = JubjubPoint::from_sapling_bytes(&bytes).ok()?;
+    if point.get_x() == *x {
+        Some(point)
+    } else {
+        None
+    }
+}
+
 impl TurboPlonkConstraintSystem<BLSScalar> {
     /// Create variables for a point.
     pub fn new_point_variable(&mut self, point: Point) -> PointVar {
@@ -96,6 +131,135 @@ impl TurboPlonkConstraintSystem<BLSScalar> {
         PointVar(x, y)
     }
 
+    /// Create variables for a Jubjub point, and returns them with the point.
+    pub fn new_extended_point_variable(
+        &mut self,
+        point: JubjubPoint,
+    ) -> ExtendedPointVar {
+        let point_var = self.new_point_variable(Point::from(&point));
+        ExtendedPointVar(point_var, point)
+    }
+
+    /// Insert a gate enforcing that `point_var` is on the curve: -x^2 + y^2 = 1 + d * x^2 * y^2
+    /// wirings: w1 = x, w2 = x, w3 = y, w4 = y, w_out = 1
+    /// selectors: qm1 = -1, qm2 = 1, q_ecc = -d, qo = 1
+    pub(crate) fn insert_on_curve_gate(&mut self, point_var: &PointVar) {
+        assert!(point_var.0 < self.num_vars, "x variable index out of bound");
+        assert!(point_var.1 < self.num_vars, "y variable index out of bound");
+        let edwards_d = BLSScalar::from_bytes(&EDWARDS_D[..]).unwrap();
+        let one_var = self.one_var();
+        let zero = BLSScalar::zero();
+        let one = BLSScalar::one();
+        self.push_add_selectors(zero, zero, zero, zero);
+        self.push_mul_selectors(one.neg(), one);
+        self.push_constant_selector(zero);
+        self.push_ecc_selector(edwards_d.neg());
+        self.push_rescue_selectors(zero, zero, zero, zero);
+        self.push_out_selector(one);
+
+        self.wiring[0].push(point_var.0);
+        self.wiring[1].push(point_var.0);
+        self.wiring[2].push(point_var.1);
+        self.wiring[3].push(point_var.1);
+        self.wiring[4].push(one_var);
+        self.size += 1;
+    }
+
+    /// Enforce that the point of coordinates (`x_var`, `y_var`) is on the curve, e.g. for an
+    /// untrusted point of the witness, and returns its point variable.
+    pub fn check_point_on_curve(
+        &mut self,
+        x_var: VarIndex,
+        y_var: VarIndex,
+    ) -> PointVar {
+        let point_var = PointVar(x_var, y_var);
+        self.insert_on_curve_gate(&point_var);
+        point_var
+    }
+
+    /// Enforce that `point_var` is in the subgroup of order r_J of the base point, i.e. is on
+    /// the curve without a component of small order: the image of the multiplication by the
+    /// cofactor 8 is this subgroup, so the gates check that `point_var` = [8]Q for a point Q
+    /// on the curve, with Q = [1/8 mod r_J]`point_var` in the witness. This takes 7 gates.
+    pub fn check_point_in_subgroup(&mut self, point_var: &PointVar) {
+        assert!(point_var.0 < self.num_vars, "x variable index out of bound");
+        assert!(point_var.1 < self.num_vars, "y variable index out of bound");
+        let point = point_from_coordinates(
+            &self.witness[point_var.0],
+            &self.witness[point_var.1],
+        )
+        .unwrap_or_else(JubjubPoint::get_identity);
+        let inv_cofactor = JubjubScalar::from_u32(8).inv().unwrap(); // safe unwrap
+        let q = point.mul(&inv_cofactor);
+        let q_var = self.new_point_variable(Point::from(&q));
+        self.insert_on_curve_gate(&q_var);
+        let q2 = self.ecc_add(&q_var, &q_var, &q, &q);
+        let q4 =
+            self.ecc_add(q2.get_var(), q2.get_var(), q2.get_point(), q2.get_point());
+        self.insert_ecc_add_gate(q4.get_var(), q4.get_var(), point_var);
+    }
+
+    // Enforce that the boolean `bit_var` is the parity of the canonical representative of
+    // `witness[x_var]`, in [0, p) for the BLS scalar field order p. The gates check
+    // x = 2 * k + bit with k + bit <= (p - 1) / 2 and k < 2^254: as p is odd, this is the only
+    // decomposition of x with 2 * k + bit < p.
+    fn insert_parity_gates(&mut self, x_var: VarIndex, bit_var: VarIndex) {
+        let zero = BLSScalar::zero();
+        let one = BLSScalar::one();
+        let two = BLSScalar::from_u32(2);
+        let half_max = one.neg().mul(&two.inv().unwrap()); // safe unwrap
+        self.insert_boolean_gate(bit_var);
+        let k = self.witness[x_var]
+            .sub(&self.witness[bit_var])
+            .mul(&two.inv().unwrap()); // safe unwrap
+        let k_var = self.new_variable(k);
+        self.insert_lc_gate(&[k_var, bit_var, 0, 0], x_var, two, one, zero, zero);
+        self.range_check_chunks(k_var, HALF_BLS_SCALAR_BIT_LEN);
+        // (p - 1) / 2 - k - bit >= 0: a negative difference wraps around to a field element of
+        // more than 254 bits
+        let one_var = self.one_var();
+        let diff_var = self.linear_combine(
+            &[k_var, bit_var, one_var, 0],
+            one.neg(),
+            one.neg(),
+            half_max,
+            zero,
+        );
+        self.range_check_chunks(diff_var, HALF_BLS_SCALAR_BIT_LEN);
+    }
+
+    /// Returns the compressed form (sign, y) of `point_var`, with the boolean sign the parity
+    /// of the x-coordinate: the in-circuit counterpart of `JubjubPoint::to_sapling_bytes`, that
+    /// encodes y in little endian with the sign in the most significant bit. The point is not
+    /// constrained to be on the curve.
+    pub fn compress_point(&mut self, point_var: &PointVar) -> (VarIndex, VarIndex) {
+        assert!(point_var.0 < self.num_vars, "x variable index out of bound");
+        assert!(point_var.1 < self.num_vars, "y variable index out of bound");
+        let sign = self.witness[point_var.0].to_bytes()[0] & 1;
+        let sign_var = self.new_variable(BLSScalar::from_u32(sign as u32));
+        self.insert_parity_gates(point_var.0, sign_var);
+        (sign_var, point_var.1)
+    }
+
+    /// Returns the point of y-coordinate `y_var` with an x-coordinate of parity `sign_var`,
+    /// enforcing that it is on the curve and that `sign_var` is the boolean parity of x: the
+    /// in-circuit counterpart of `JubjubPoint::from_sapling_bytes`. The constraints are
+    /// unsatisfiable if no such point exists. The point is not checked to be in the subgroup
+    /// of the base point, see check_point_in_subgroup.
+    pub fn decompress_point(&mut self, sign_var: VarIndex, y_var: VarIndex) -> PointVar {
+        assert!(sign_var < self.num_vars, "sign variable index out of bound");
+        assert!(y_var < self.num_vars, "y variable index out of bound");
+        let bytes =
+            sapling_bytes(!self.witness[sign_var].is_zero(), &self.witness[y_var]);
+        let x = JubjubPoint::from_sapling_bytes(&bytes)
+            .map(|point| point.get_x())
+            .unwrap_or_else(|_| BLSScalar::zero());
+        let x_var = self.new_variable(x);
+        let point_var = self.check_point_on_curve(x_var, y_var);
+        self.insert_parity_gates(x_var, sign_var);
+        point_var
+    }
+
     /// Insert constraint for a public IO point to be decided online.
     pub fn prepare_io_point_variable(&mut self, point_var: PointVar) {
         self.prepare_io_variable(point_var.0);
@@ -281,6 +445,41 @@ impl TurboPlonkConstraintSystem<BLSScalar> {
         ExtendedPointVar(p_out_var, p_out_ext)
     }
 
+    /// Enforce that `witness[scalar_var]` is the integer representative of a Jubjub scalar:
+    /// `0 <= witness[scalar_var] < r_J` with `r_J` the order of the Jubjub scalar field.
+    /// This is the in-circuit counterpart of `BLSScalar::to_jubjub_scalar`.
+    /// Returns the `JUBJUB_SCALAR_BIT_LEN` bits of `witness[scalar_var]` in little endian form,
+    /// as taken by `scalar_mul_with_bases`.
+    pub fn jubjub_scalar_range_check(&mut self, scalar_var: VarIndex) -> Vec<VarIndex> {
+        assert!(scalar_var < self.num_vars, "scalar_var index out of bound");
+        // witness[scalar_var] < 2^JUBJUB_SCALAR_BIT_LEN
+        let b_scalar_var = self.range_check(scalar_var, JUBJUB_SCALAR_BIT_LEN);
+
+        // (r_J - 1) - witness[scalar_var] >= 0: both values are smaller than 2^JUBJUB_SCALAR_BIT_LEN,
+        // so a negative difference wraps around to a field element of more bits.
+        // Wires: (w1, w2, w3, w4) = (scalar_var, 0, 0, 0), w_out = diff_var
+        // Selectors: q1 = -1, qc = r_J - 1, qo = 1
+        let max_scalar = BLSScalar::from(&JubjubScalar::from_u32(1).neg());
+        let diff_var = self.new_variable(max_scalar.sub(&self.witness[scalar_var]));
+        let zero = BLSScalar::zero();
+        let one = BLSScalar::one();
+        self.push_add_selectors(one.neg(), zero, zero, zero);
+        self.push_mul_selectors(zero, zero);
+        self.push_constant_selector(max_scalar);
+        self.push_ecc_selector(zero);
+        self.push_rescue_selectors(zero, zero, zero, zero);
+        self.push_out_selector(one);
+        self.wiring[0].push(scalar_var);
+        self.wiring[1].push(0);
+        self.wiring[2].push(0);
+        self.wiring[3].push(0);
+        self.wiring[4].push(diff_var);
+        self.size += 1;
+        self.range_check(diff_var, JUBJUB_SCALAR_BIT_LEN);
+
+        b_scalar_var
+    }
+
     /// Variable-base scalar multiplication:
     /// Given a base point variable `point`, and an `n_bits`-bit secret scalar s, returns s * `point`.
     pub fn var_base_scalar_mul(
@@ -305,6 +504,51 @@ impl TurboPlonkConstraintSystem<BLSScalar> {
         (res_ext.0, res_ext.1)
     }
 
+    /// Multi-scalar multiplication:
+    /// Given point variables `points` and `n_bits`-bit secret scalars `scalar_vars`, returns
+    /// the sum of the scalar multiplications. The doublings are shared by the terms: for each
+    /// bit from the most significant one, the sum is doubled once, and the points whose scalar
+    /// has the bit are added. This saves the doublings and the final additions of independent
+    /// var_base_scalar_mul, i.e. 2 (k - 1) gates per bit for k terms.
+    pub fn msm(
+        &mut self,
+        points: &[ExtendedPointVar],
+        scalar_vars: &[VarIndex],
+        n_bits: usize,
+    ) -> (PointVar, JubjubPoint) {
+        assert_eq!(
+            points.len(),
+            scalar_vars.len(),
+            "the numbers of points and scalars differ"
+        );
+        assert!(
+            !points.is_empty(),
+            "no term in the multi-scalar multiplication"
+        );
+        let b_scalar_vars: Vec<Vec<VarIndex>> = scalar_vars
+            .iter()
+            .map(|scalar_var| self.range_check(*scalar_var, n_bits))
+            .collect();
+        let identity = self.get_identity();
+        let mut res_ext: Option<ExtendedPointVar> = None;
+        for i in (0..n_bits).rev() {
+            // doubling, except for the first bit
+            if let Some(res) = res_ext {
+                res_ext = Some(self.ecc_add(&res.0, &res.0, &res.1, &res.1));
+            }
+            // conditional additions
+            for (point, b_scalar_var) in points.iter().zip(b_scalar_vars.iter()) {
+                let tmp_ext = self.select_point(&identity, point, b_scalar_var[i]);
+                res_ext = Some(match res_ext {
+                    None => tmp_ext,
+                    Some(res) => self.ecc_add(&res.0, &tmp_ext.0, &res.1, &tmp_ext.1),
+                });
+            }
+        }
+        let res_ext = res_ext.unwrap(); // safe unwrap
+        (res_ext.0, res_ext.1)
+    }
+
     ///  Fixed-base scalar multiplication:
     ///  Given a base point `[G]` and an `n_bits`-bit secret scalar `s`, returns `s * [G]`.
     /// `n_bits` should be a positive even number.
@@ -372,15 +616,104 @@ impl TurboPlonkConstraintSystem<BLSScalar> {
         }
         (p_var_ext.0, p_var_ext.1)
     }
+
+    /// Fixed-base scalar multiplication with lookup tables:
+    /// Given a base point `[G]` and an `n_bits`-bit secret scalar `s`, returns `s * [G]`.
+    /// The scalar is split in windows of RANGE_CHUNK_BITS bits, and two lookup gates map the
+    /// value k_i of the window i to the coordinates of `k_i * 16^i * [G]`, in the tables of
+    /// fixed_base_tables, which also check the range of the windows. This takes 4 gates per
+    /// window, a third of the gates of scalar_mul, but the tables have 2 * 16 entries per
+    /// window, and the circuit has at least as many constraints as entries: the tables pay off
+    /// in large circuits, and are shared by the multiplications by the same base.
+    pub fn fixed_base_scalar_mul(
+        &mut self,
+        base: &JubjubPoint,
+        scalar_var: VarIndex,
+        n_bits: usize,
+    ) -> (PointVar, JubjubPoint) {
+        assert!(scalar_var < self.num_vars, "scalar_var index out of bound");
+        assert!(n_bits > 0, "n_bits is not positive");
+        let n_windows = (n_bits - 1) / RANGE_CHUNK_BITS + 1;
+        let tables = self.fixed_base_tables(base, n_bits);
+        let values = self.chunk_values(scalar_var, n_windows);
+        let mut windows = vec![];
+        let mut res: Option<ExtendedPointVar> = None;
+        let mut window_base = base.clone();
+        for (value, (x_table, y_table)) in values.iter().zip(tables) {
+            let window = if n_windows == 1 {
+                scalar_var
+            } else {
+                self.new_variable(*value)
+            };
+            let x = self.lookup_output(window, x_table);
+            let y = self.lookup_output(window, y_table);
+            let k = JubjubScalar::from_u32(value.to_bytes()[0] as u32);
+            let window_point = ExtendedPointVar(PointVar(x, y), window_base.mul(&k));
+            res = Some(match res {
+                None => window_point,
+                Some(acc) => {
+                    self.ecc_add(&acc.0, &window_point.0, &acc.1, &window_point.1)
+                }
+            });
+            for _ in 0..RANGE_CHUNK_BITS {
+                window_base = window_base.double();
+            }
+            windows.push(window);
+        }
+        if n_windows > 1 {
+            self.insert_chunks_recomposition(scalar_var, &windows);
+        }
+        let res = res.unwrap(); // safe unwrap
+        (res.0, res.1)
+    }
+
+    // Returns the ids of the lookup tables of the x and the y coordinates of the window
+    // multiples of `base` for an `n_bits`-bit scalar: the table of the window i maps k to the
+    // coordinate of `k * 16^i * base`, for k of RANGE_CHUNK_BITS bits, or fewer bits for the
+    // last window. The tables are registered on their first use.
+    fn fixed_base_tables(
+        &mut self,
+        base: &JubjubPoint,
+        n_bits: usize,
+    ) -> Vec<(usize, usize)> {
+        let n_windows = (n_bits - 1) / RANGE_CHUNK_BITS + 1;
+        let mut window_base = base.clone();
+        let mut tables = vec![];
+        for i in 0..n_windows {
+            let window_bits = RANGE_CHUNK_BITS.min(n_bits - i * RANGE_CHUNK_BITS);
+            let mut point = JubjubPoint::get_identity();
+            let mut x_entries = vec![];
+            let mut y_entries = vec![];
+            for k in 0..1u32 << window_bits {
+                let key = BLSScalar::from_u32(k);
+                x_entries.push((key, point.get_x()));
+                y_entries.push((key, point.get_y()));
+                point = point.add(&window_base);
+            }
+            tables.push((
+                self.find_or_add_lookup_map(&x_entries),
+                self.find_or_add_lookup_map(&y_entries),
+            ));
+            for _ in 0..RANGE_CHUNK_BITS {
+                window_base = window_base.double();
+            }
+        }
+        tables
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::plonk::turbo_plonk_cs::ecc::{Point, PointVar};
+    use crate::plonk::turbo_plonk_cs::ecc::{
+        compute_base_multiples, point_from_coordinates, Point, PointVar,
+        JUBJUB_SCALAR_BIT_LEN,
+    };
     use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
     use algebra::bls12_381::BLSScalar;
-    use algebra::groups::{Group, GroupArithmetic, One, Scalar, Zero};
+    use algebra::groups::{Group, GroupArithmetic, One, Scalar, ScalarArithmetic, Zero};
     use algebra::jubjub::{JubjubPoint, JubjubScalar};
+    use rand_chacha::ChaChaRng;
+    use rand_core::{RngCore, SeedableRng};
     use ruc::*;
 
     #[test]
@@ -448,6 +781,78 @@ mod test {
         assert!(cs.verify_witness(&witness[..], &[]).is_err());
     }
 
+    #[test]
+    fn test_fixed_base_scalar_mul() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let jubjub_scalar = JubjubScalar::random(&mut prng);
+        let base_ext = JubjubPoint::get_base();
+        let p_out_ext = base_ext.mul(&jubjub_scalar);
+
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let scalar_var = cs.new_variable(BLSScalar::from(&jubjub_scalar));
+        let (p_out_var, p_out) =
+            cs.fixed_base_scalar_mul(&base_ext, scalar_var, JUBJUB_SCALAR_BIT_LEN);
+        assert_eq!(p_out, p_out_ext);
+        let n_gates = cs.size;
+        let n_tables = cs.lookup_tables.len();
+        assert_eq!(n_tables, 2 * JUBJUB_SCALAR_BIT_LEN / 4);
+        // a second multiplication by the same base shares the tables
+        let _ = cs.fixed_base_scalar_mul(&base_ext, scalar_var, JUBJUB_SCALAR_BIT_LEN);
+        assert_eq!(cs.lookup_tables.len(), n_tables);
+
+        let mut other_cs = TurboPlonkConstraintSystem::new();
+        let scalar_var = other_cs.new_variable(BLSScalar::from(&jubjub_scalar));
+        let _ = other_cs.scalar_mul(base_ext.clone(), scalar_var, JUBJUB_SCALAR_BIT_LEN);
+        assert!(3 * n_gates < other_cs.size);
+
+        let mut witness = cs.get_and_clear_witness();
+        assert_eq!(witness[p_out_var.0], p_out_ext.get_x());
+        assert_eq!(witness[p_out_var.1], p_out_ext.get_y());
+        pnk!(cs.verify_witness(&witness[..], &[]));
+        witness[p_out_var.0] = base_ext.get_x();
+        witness[p_out_var.1] = base_ext.get_y();
+        assert!(cs.verify_witness(&witness[..], &[]).is_err());
+
+        // a scalar of 10 bits, with a last window of 2 bits
+        for (scalar, is_ok) in [(1023u32, true), (1024, false)] {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let scalar_var = cs.new_variable(BLSScalar::from_u32(scalar));
+            let _ = cs.fixed_base_scalar_mul(&base_ext, scalar_var, 10);
+            let witness = cs.get_and_clear_witness();
+            assert_eq!(cs.verify_witness(&witness[..], &[]).is_ok(), is_ok);
+        }
+    }
+
+    #[test]
+    fn test_jubjub_scalar_range_check() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let jubjub_scalar = JubjubScalar::random(&mut prng);
+        let max_jubjub_scalar = JubjubScalar::from_u32(1).neg();
+        for scalar in &[jubjub_scalar, max_jubjub_scalar, JubjubScalar::zero()] {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let scalar_var = cs.new_variable(BLSScalar::from(scalar));
+            let bits = cs.jubjub_scalar_range_check(scalar_var);
+            assert_eq!(bits.len(), JUBJUB_SCALAR_BIT_LEN);
+            // the bits give the same scalar multiplication as the Jubjub scalar
+            let bases = compute_base_multiples(JubjubPoint::get_base(), bits.len() >> 1);
+            let (_, p_out) =
+                cs.scalar_mul_with_bases(&bases[0], &bases[1], &bases[2], &bits);
+            assert_eq!(p_out, JubjubPoint::get_base().mul(scalar));
+            let witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness[..], &[]));
+        }
+
+        // the order of the Jubjub scalar field, and the largest BLS scalar
+        let jubjub_order = BLSScalar::from(&max_jubjub_scalar).add(&BLSScalar::one());
+        for scalar in &[jubjub_order, BLSScalar::one().neg()] {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let scalar_var = cs.new_variable(*scalar);
+            cs.jubjub_scalar_range_check(scalar_var);
+            let witness = cs.get_and_clear_witness();
+            assert!(cs.verify_witness(&witness[..], &[]).is_err());
+        }
+    }
+
     #[test]
     fn test_scalar_mul_with_zero_scalar() {
         // use BLS12-381 field
@@ -468,6 +873,139 @@ mod test {
         assert!(cs.verify_witness(&witness[..], &[]).is_err());
     }
 
+    #[test]
+    fn test_point_checks() {
+        let base_ext = JubjubPoint::get_base();
+        let point_ext = base_ext.mul(&JubjubScalar::from_u32(12345));
+        // the point of order 2
+        let torsion_ext =
+            point_from_coordinates(&BLSScalar::zero(), &BLSScalar::one().neg()).unwrap();
+        assert_eq!(torsion_ext.double(), JubjubPoint::get_identity());
+        let point_plus_torsion = Point::from(&point_ext.add(&torsion_ext));
+
+        let cases = [
+            (Point::from(&point_ext), true, true),
+            (Point::from(&torsion_ext), true, false),
+            (point_plus_torsion, true, false),
+            (Point::new(BLSScalar::one(), BLSScalar::one()), false, false),
+        ];
+        for (point, on_curve, in_subgroup) in cases {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let x_var = cs.new_variable(*point.get_x());
+            let y_var = cs.new_variable(*point.get_y());
+            let _ = cs.check_point_on_curve(x_var, y_var);
+            let witness = cs.get_and_clear_witness();
+            assert_eq!(cs.verify_witness(&witness[..], &[]).is_ok(), on_curve);
+
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let point_var = cs.new_point_variable(point);
+            cs.check_point_in_subgroup(&point_var);
+            assert_eq!(cs.size, 7);
+            let witness = cs.get_and_clear_witness();
+            assert_eq!(cs.verify_witness(&witness[..], &[]).is_ok(), in_subgroup);
+        }
+    }
+
+    #[test]
+    fn test_compress_point() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let point = JubjubPoint::get_base().mul(&JubjubScalar::random(&mut prng));
+        // the point of order 2
+        let torsion =
+            point_from_coordinates(&BLSScalar::zero(), &BLSScalar::one().neg()).unwrap();
+        for point in [
+            point.clone(),
+            JubjubPoint::get_identity().sub(&point),
+            JubjubPoint::get_identity(),
+            torsion,
+        ] {
+            let bytes = point.to_sapling_bytes();
+            let sign = BLSScalar::from_u32((bytes[31] >> 7) as u32);
+
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let point_var = cs.new_point_variable(Point::from(&point));
+            let (sign_var, y_var) = cs.compress_point(&point_var);
+            assert_eq!(cs.witness[sign_var], sign);
+            assert_eq!(cs.witness[y_var], point.get_y());
+            let mut witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness, &[]));
+            // the other sign
+            witness[sign_var] = BLSScalar::one().sub(&sign);
+            assert!(cs.verify_witness(&witness, &[]).is_err());
+
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let sign_var = cs.new_variable(sign);
+            let y_var = cs.new_variable(point.get_y());
+            let point_var = cs.decompress_point(sign_var, y_var);
+            assert_eq!(cs.witness[point_var.get_x()], point.get_x());
+            let mut witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness, &[]));
+            // the opposite x-coordinate has the other parity, except for x = 0
+            witness[point_var.get_x()] = point.get_x().neg();
+            assert_eq!(
+                cs.verify_witness(&witness, &[]).is_ok(),
+                point.get_x().is_zero()
+            );
+        }
+
+        // a y-coordinate without point
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let sign_var = cs.new_variable(BLSScalar::zero());
+        let mut y = BLSScalar::from_u32(2);
+        while JubjubPoint::from_sapling_bytes(&y.to_bytes()).is_ok() {
+            y = y.add(&BLSScalar::one());
+        }
+        let y_var = cs.new_variable(y);
+        let _ = cs.decompress_point(sign_var, y_var);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
+    #[test]
+    fn test_msm() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let n_bits = 64;
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let mut expected_point = JubjubPoint::get_identity();
+        let mut points = vec![];
+        let mut scalar_vars = vec![];
+        for _ in 0..3 {
+            let point = JubjubPoint::get_base().mul(&JubjubScalar::random(&mut prng));
+            let scalar = JubjubScalar::from_u64(prng.next_u64());
+            expected_point = expected_point.add(&point.mul(&scalar));
+            points.push(cs.new_extended_point_variable(point));
+            scalar_vars.push(cs.new_variable(BLSScalar::from(&scalar)));
+        }
+        let (res, res_point) = cs.msm(&points, &scalar_vars, n_bits);
+        assert_eq!(res_point, expected_point);
+        assert_eq!(cs.witness[res.0], expected_point.get_x());
+        assert_eq!(cs.witness[res.1], expected_point.get_y());
+
+        // fewer gates than independent scalar multiplications
+        let mut other_cs = TurboPlonkConstraintSystem::new();
+        let mut terms = vec![];
+        for (point, scalar_var) in points.iter().zip(scalar_vars.iter()) {
+            let point_var = other_cs.new_point_variable(Point::from(point.get_point()));
+            let scalar_var = other_cs.new_variable(cs.witness[*scalar_var]);
+            terms.push(other_cs.var_base_scalar_mul(
+                point_var,
+                point.get_point().clone(),
+                scalar_var,
+                n_bits,
+            ));
+        }
+        let sum = other_cs.ecc_add(&terms[0].0, &terms[1].0, &terms[0].1, &terms[1].1);
+        let _ =
+            other_cs.ecc_add(sum.get_var(), &terms[2].0, sum.get_point(), &terms[2].1);
+        assert!(cs.size + 4 * (n_bits - 1) <= other_cs.size);
+
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness[..], &[]));
+        witness[res.0] = JubjubPoint::get_base().get_x();
+        witness[res.1] = JubjubPoint::get_base().get_y();
+        assert!(cs.verify_witness(&witness[..], &[]).is_err());
+    }
+
     #[test]
     fn test_var_base_scalar_mul() {
         let mut cs = TurboPlonkConstraintSystem::new();
@@ -499,4 +1037,17 @@ mod test {
         let witness = cs.get_and_clear_witness();
         assert!(cs.verify_witness(&witness[..], &[]).is_ok());
     }
+
+    #[test]
+    fn test_on_curve_gate() {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let point = Point::from(&JubjubPoint::get_base().double());
+        let point_var = cs.new_point_variable(point.clone());
+        cs.insert_on_curve_gate(&point_var);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness[..], &[]));
+
+        witness[point_var.1] = point.1.add(&BLSScalar::one());
+        assert!(cs.verify_witness(&witness[..], &[]).is_err());
+    }
 }
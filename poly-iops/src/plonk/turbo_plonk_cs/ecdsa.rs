@@ -0,0 +1,510 @@
+// This file implements the verification of ECDSA signatures on secp256k1 in a constraint system,
+// with the coordinates of the points in the base field Fp and the scalars in the scalar field Fn
+// emulated by the non-native gadgets of nonnative.rs.
+// A signature (r, s) on a message hash z for the public key Q is valid if R = u1 * G + u2 * Q,
+// with u1 = z / s and u2 = r / s mod n, is not the identity and R.x = r mod n.
+// The points are in affine coordinates, and the additions use the incomplete formulas, that
+// require the x-coordinates of the two points to be different: the slope is a witness whose
+// correctness is enforced by a multiplication, which is unsatisfiable if the x-coordinates are
+// equal and the y-coordinates are not, and is not unique if the points are equal. To avoid equal
+// points, the double scalar multiplication by double-and-add starts from a fixed offset point A
+// whose discrete logarithm is unknown, the point of x-coordinate 1, and subtracts [2^k] A at the
+// end. Then a point of the double-and-add equal to G, Q or [2^k] A would reveal a discrete
+// logarithm of A, for honest and malicious provers. The witness of an honest prover satisfies
+// the constraints except with negligible probability.
+
+use crate::plonk::turbo_plonk_cs::nonnative::{NonNativeField, NonNativeVar};
+use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+use algebra::groups::Scalar;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+const SECP256K1_P: &[u8] =
+    b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+const SECP256K1_N: &[u8] =
+    b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+const SECP256K1_GX: &[u8] =
+    b"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+const SECP256K1_GY: &[u8] =
+    b"483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+// y^2 = x^3 + 7
+const SECP256K1_B: u32 = 7;
+
+/// A point of secp256k1 in affine coordinates, the identity has no affine coordinates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Secp256k1Point {
+    pub x: BigUint,
+    pub y: BigUint,
+}
+
+/// The fields of secp256k1 and the offset point of the double scalar multiplication
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Secp256k1 {
+    pub base_field: NonNativeField,
+    pub scalar_field: NonNativeField,
+    generator: Secp256k1Point,
+    offset: Secp256k1Point,
+}
+
+/// A point of secp256k1 in a constraint system
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Secp256k1PointVar {
+    pub x: NonNativeVar,
+    pub y: NonNativeVar,
+}
+
+/// An ECDSA signature in a constraint system, r and s are in the scalar field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EcdsaSignatureVar {
+    pub r: NonNativeVar,
+    pub s: NonNativeVar,
+}
+
+fn parse_hex(hex: &[u8]) -> BigUint {
+    BigUint::parse_bytes(hex, 16).unwrap() // safe unwrap
+}
+
+// x^-1 mod m for a prime m, zero for x = 0
+fn mod_inv(x: &BigUint, m: &BigUint) -> BigUint {
+    x.modpow(&(m - BigUint::from(2u32)), m)
+}
+
+fn mod_sub(a: &BigUint, b: &BigUint, m: &BigUint) -> BigUint {
+    (a % m + m - b % m) % m
+}
+
+impl Secp256k1 {
+    pub fn new() -> Secp256k1 {
+        let p = parse_hex(SECP256K1_P);
+        // the least x > 0 on the curve is 1, take the even y
+        let rhs = BigUint::from(1 + SECP256K1_B);
+        let y = rhs.modpow(&((&p + BigUint::one()) >> 2), &p);
+        let y = if y.is_odd() { &p - y } else { y };
+        Secp256k1 {
+            base_field: NonNativeField::new(p).unwrap(), // safe unwrap
+            scalar_field: NonNativeField::new(parse_hex(SECP256K1_N)).unwrap(), // safe unwrap
+            generator: Secp256k1Point {
+                x: parse_hex(SECP256K1_GX),
+                y: parse_hex(SECP256K1_GY),
+            },
+            offset: Secp256k1Point {
+                x: BigUint::one(),
+                y,
+            },
+        }
+    }
+
+    pub fn generator(&self) -> &Secp256k1Point {
+        &self.generator
+    }
+
+    fn p(&self) -> &BigUint {
+        self.base_field.modulus()
+    }
+
+    pub fn is_on_curve(&self, point: &Secp256k1Point) -> bool {
+        let p = self.p();
+        point.x < *p
+            && point.y < *p
+            && (&point.y * &point.y) % p
+                == (&point.x * &point.x * &point.x + BigUint::from(SECP256K1_B)) % p
+    }
+
+    // Slope of the line through two points, of the tangent for equal points
+    fn slope(&self, a: &Secp256k1Point, b: &Secp256k1Point) -> BigUint {
+        let p = self.p();
+        if a.x == b.x {
+            let num = BigUint::from(3u32) * &a.x * &a.x % p;
+            num * mod_inv(&(BigUint::from(2u32) * &a.y % p), p) % p
+        } else {
+            mod_sub(&b.y, &a.y, p) * mod_inv(&mod_sub(&b.x, &a.x, p), p) % p
+        }
+    }
+
+    // The point with slope `lambda` through `a`, and `b`
+    fn chord_point(
+        &self,
+        lambda: &BigUint,
+        a: &Secp256k1Point,
+        b: &Secp256k1Point,
+    ) -> Secp256k1Point {
+        let p = self.p();
+        let x = mod_sub(&mod_sub(&(lambda * lambda), &a.x, p), &b.x, p);
+        let y = mod_sub(&(lambda * mod_sub(&a.x, &x, p)), &a.y, p);
+        Secp256k1Point { x, y }
+    }
+
+    /// Sum of two points, None for the identity
+    pub fn add(
+        &self,
+        a: Option<&Secp256k1Point>,
+        b: Option<&Secp256k1Point>,
+    ) -> Option<Secp256k1Point> {
+        match (a, b) {
+            (None, b) => b.cloned(),
+            (a, None) => a.cloned(),
+            (Some(a), Some(b)) => {
+                if a.x == b.x && (&a.y + &b.y) % self.p() == BigUint::zero() {
+                    return None;
+                }
+                Some(self.chord_point(&self.slope(a, b), a, b))
+            }
+        }
+    }
+
+    /// [k] point, None for the identity
+    pub fn mul(&self, k: &BigUint, point: &Secp256k1Point) -> Option<Secp256k1Point> {
+        let mut result = None;
+        for i in (0..k.bits() as usize).rev() {
+            result = self.add(result.as_ref(), result.as_ref());
+            if ((k >> i) & BigUint::one()).is_one() {
+                result = self.add(result.as_ref(), Some(point));
+            }
+        }
+        result
+    }
+
+    fn neg(&self, point: &Secp256k1Point) -> Secp256k1Point {
+        Secp256k1Point {
+            x: point.x.clone(),
+            y: mod_sub(&BigUint::zero(), &point.y, self.p()),
+        }
+    }
+
+    /// Verifies the ECDSA signature (r, s) on the message hash `z` for the public key `pk`
+    pub fn verify_ecdsa(
+        &self,
+        pk: &Secp256k1Point,
+        z: &BigUint,
+        r: &BigUint,
+        s: &BigUint,
+    ) -> bool {
+        let n = self.scalar_field.modulus();
+        if !self.is_on_curve(pk) || r.is_zero() || r >= n || s.is_zero() || s >= n {
+            return false;
+        }
+        let w = mod_inv(s, n);
+        let u1 = z * &w % n;
+        let u2 = r * &w % n;
+        let point = self.add(
+            self.mul(&u1, &self.generator).as_ref(),
+            self.mul(&u2, pk).as_ref(),
+        );
+        match point {
+            Some(point) => point.x % n == *r,
+            None => false,
+        }
+    }
+}
+
+impl Default for Secp256k1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Scalar> TurboPlonkConstraintSystem<F> {
+    /// Adds a variable with the point `point`, constrained to be on the curve.
+    pub fn new_secp256k1_point_variable(
+        &mut self,
+        curve: &Secp256k1,
+        point: &Secp256k1Point,
+    ) -> Secp256k1PointVar {
+        let point_var = Secp256k1PointVar {
+            x: self.new_nonnative_variable(&curve.base_field, &point.x),
+            y: self.new_nonnative_variable(&curve.base_field, &point.y),
+        };
+        self.secp256k1_enforce_on_curve(curve, &point_var);
+        point_var
+    }
+
+    fn secp256k1_constant_point(
+        &mut self,
+        curve: &Secp256k1,
+        point: &Secp256k1Point,
+    ) -> Secp256k1PointVar {
+        Secp256k1PointVar {
+            x: self.nonnative_constant(&curve.base_field, &point.x),
+            y: self.nonnative_constant(&curve.base_field, &point.y),
+        }
+    }
+
+    fn secp256k1_point_value(
+        &self,
+        curve: &Secp256k1,
+        point: &Secp256k1PointVar,
+    ) -> Secp256k1Point {
+        Secp256k1Point {
+            x: self.nonnative_value(&point.x) % curve.p(),
+            y: self.nonnative_value(&point.y) % curve.p(),
+        }
+    }
+
+    /// Enforces y^2 = x^3 + 7 mod p
+    pub fn secp256k1_enforce_on_curve(
+        &mut self,
+        curve: &Secp256k1,
+        point: &Secp256k1PointVar,
+    ) {
+        let field = &curve.base_field;
+        let y_square = self.nonnative_mul(field, &point.y, &point.y);
+        let x_square = self.nonnative_mul(field, &point.x, &point.x);
+        let x_cube = self.nonnative_mul(field, &x_square, &point.x);
+        let b = self.nonnative_constant(field, &BigUint::from(SECP256K1_B));
+        let rhs = self.nonnative_add(field, &x_cube, &b);
+        self.nonnative_enforce_equal(field, &y_square, &rhs);
+    }
+
+    // The point with the slope `lambda_var` through `a`, and `b`:
+    // x = lambda^2 - a.x - b.x, y = lambda * (a.x - x) - a.y
+    fn secp256k1_chord_point(
+        &mut self,
+        curve: &Secp256k1,
+        lambda_var: &NonNativeVar,
+        a: &Secp256k1PointVar,
+        b: &Secp256k1PointVar,
+    ) -> Secp256k1PointVar {
+        let field = &curve.base_field;
+        let lambda_square = self.nonnative_mul(field, lambda_var, lambda_var);
+        let x = self.nonnative_sub(field, &lambda_square, &a.x);
+        let x = self.nonnative_sub(field, &x, &b.x);
+        let dx = self.nonnative_sub(field, &a.x, &x);
+        let y = self.nonnative_mul(field, lambda_var, &dx);
+        let y = self.nonnative_sub(field, &y, &a.y);
+        Secp256k1PointVar { x, y }
+    }
+
+    /// Returns a + b for points with different x-coordinates, the constraints are
+    /// unsatisfiable for a = -b, and underdetermined for a = b (see ecdsa.rs).
+    pub fn secp256k1_add_incomplete(
+        &mut self,
+        curve: &Secp256k1,
+        a: &Secp256k1PointVar,
+        b: &Secp256k1PointVar,
+    ) -> Secp256k1PointVar {
+        let field = &curve.base_field;
+        let a_value = self.secp256k1_point_value(curve, a);
+        let b_value = self.secp256k1_point_value(curve, b);
+        // lambda * (b.x - a.x) = b.y - a.y
+        let lambda = if a_value.x == b_value.x {
+            BigUint::zero()
+        } else {
+            curve.slope(&a_value, &b_value)
+        };
+        let lambda_var = self.new_nonnative_variable(field, &lambda);
+        let dx = self.nonnative_sub(field, &b.x, &a.x);
+        let dy = self.nonnative_sub(field, &b.y, &a.y);
+        let lambda_dx = self.nonnative_mul(field, &lambda_var, &dx);
+        self.nonnative_enforce_equal(field, &lambda_dx, &dy);
+        self.secp256k1_chord_point(curve, &lambda_var, a, b)
+    }
+
+    /// Returns 2 * a, secp256k1 has no point of order 2.
+    pub fn secp256k1_double(
+        &mut self,
+        curve: &Secp256k1,
+        a: &Secp256k1PointVar,
+    ) -> Secp256k1PointVar {
+        let field = &curve.base_field;
+        let a_value = self.secp256k1_point_value(curve, a);
+        // lambda * 2 * y = 3 * x^2
+        let lambda_var =
+            self.new_nonnative_variable(field, &curve.slope(&a_value, &a_value));
+        let two_y = self.nonnative_add(field, &a.y, &a.y);
+        let x_square = self.nonnative_mul(field, &a.x, &a.x);
+        let two_x_square = self.nonnative_add(field, &x_square, &x_square);
+        let three_x_square = self.nonnative_add(field, &two_x_square, &x_square);
+        let lambda_two_y = self.nonnative_mul(field, &lambda_var, &two_y);
+        self.nonnative_enforce_equal(field, &lambda_two_y, &three_x_square);
+        self.secp256k1_chord_point(curve, &lambda_var, a, a)
+    }
+
+    fn secp256k1_select(
+        &mut self,
+        curve: &Secp256k1,
+        a0: &Secp256k1PointVar,
+        a1: &Secp256k1PointVar,
+        bit: VarIndex,
+    ) -> Secp256k1PointVar {
+        Secp256k1PointVar {
+            x: self.nonnative_select(&curve.base_field, &a0.x, &a1.x, bit),
+            y: self.nonnative_select(&curve.base_field, &a0.y, &a1.y, bit),
+        }
+    }
+
+    /// Returns [k1] a1 + [k2] a2 for scalars given by little endian boolean variables of the
+    /// same length k, by double-and-add from the offset point A, minus [2^k] A (see ecdsa.rs).
+    /// The constraints are unsatisfiable if the result is the identity.
+    pub fn secp256k1_double_scalar_mul(
+        &mut self,
+        curve: &Secp256k1,
+        a1: &Secp256k1PointVar,
+        k1_bits: &[VarIndex],
+        a2: &Secp256k1PointVar,
+        k2_bits: &[VarIndex],
+    ) -> Secp256k1PointVar {
+        assert_eq!(k1_bits.len(), k2_bits.len(), "scalars of different lengths");
+        let mut acc = self.secp256k1_constant_point(curve, &curve.offset);
+        for (bit1, bit2) in k1_bits.iter().zip(k2_bits.iter()).rev() {
+            acc = self.secp256k1_double(curve, &acc);
+            let sum = self.secp256k1_add_incomplete(curve, &acc, a1);
+            acc = self.secp256k1_select(curve, &acc, &sum, *bit1);
+            let sum = self.secp256k1_add_incomplete(curve, &acc, a2);
+            acc = self.secp256k1_select(curve, &acc, &sum, *bit2);
+        }
+        let two_pow_k = BigUint::one() << k1_bits.len();
+        // safe unwrap: A is not of small order
+        let offset = curve.mul(&two_pow_k, &curve.offset).unwrap();
+        let neg_offset = self.secp256k1_constant_point(curve, &curve.neg(&offset));
+        self.secp256k1_add_incomplete(curve, &acc, &neg_offset)
+    }
+
+    /// Verifies the ECDSA signature `signature` on the message hash `msg_hash`, an element of
+    /// the scalar field, for the public key `pk`:
+    /// 1. r and s are invertible mod n, w = s^-1 mod n.
+    /// 2. R = [z * w] G + [r * w] Q.
+    /// 3. R.x mod p = r mod n.
+    /// The public key must be a variable constrained to be on the curve, e.g. by
+    /// new_secp256k1_point_variable.
+    pub fn verify_ecdsa(
+        &mut self,
+        curve: &Secp256k1,
+        pk: &Secp256k1PointVar,
+        msg_hash: &NonNativeVar,
+        signature: &EcdsaSignatureVar,
+    ) {
+        let scalar_field = &curve.scalar_field;
+        let n = scalar_field.modulus();
+        let one = self.nonnative_constant(scalar_field, &BigUint::one());
+        let mut inverses = vec![];
+        for x in [&signature.r, &signature.s] {
+            let x_inv = mod_inv(&(self.nonnative_value(x) % n), n);
+            let x_inv_var = self.new_nonnative_variable(scalar_field, &x_inv);
+            let product = self.nonnative_mul(scalar_field, x, &x_inv_var);
+            self.nonnative_enforce_equal(scalar_field, &product, &one);
+            inverses.push(x_inv_var);
+        }
+        let w = &inverses[1];
+        let u1 = self.nonnative_mul(scalar_field, msg_hash, w);
+        let u2 = self.nonnative_mul(scalar_field, &signature.r, w);
+        let u1_bits = self.nonnative_bits(scalar_field, &u1);
+        let u2_bits = self.nonnative_bits(scalar_field, &u2);
+        let generator = self.secp256k1_constant_point(curve, &curve.generator);
+        let point =
+            self.secp256k1_double_scalar_mul(curve, &generator, &u1_bits, pk, &u2_bits);
+        // the limbs of R.x < p are an element of the scalar field, not necessarily reduced
+        let x = self.nonnative_reduce(&curve.base_field, &point.x);
+        self.nonnative_enforce_equal(scalar_field, &x, &signature.r);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::ecdsa::{Secp256k1, Secp256k1Point};
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::Scalar;
+    use num_bigint::BigUint;
+    use num_traits::One;
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    // signs `z` with the secret key `sk` and the nonce `k`
+    fn sign(
+        curve: &Secp256k1,
+        sk: &BigUint,
+        k: &BigUint,
+        z: &BigUint,
+    ) -> (BigUint, BigUint) {
+        let n = curve.scalar_field.modulus();
+        let r = curve.mul(k, curve.generator()).unwrap().x % n;
+        let k_inv = k.modpow(&(n - BigUint::from(2u32)), n);
+        let s = k_inv * (z + &r * sk) % n;
+        (r, s)
+    }
+
+    #[test]
+    fn test_secp256k1() {
+        let curve = Secp256k1::new();
+        let g = curve.generator();
+        assert!(curve.is_on_curve(g));
+        assert!(curve.is_on_curve(&curve.offset));
+        let n = curve.scalar_field.modulus();
+        assert_eq!(curve.mul(n, g), None);
+        let a = BigUint::from(123456789u64);
+        let b = BigUint::from(987654321u64);
+        let sum = curve.add(curve.mul(&a, g).as_ref(), curve.mul(&b, g).as_ref());
+        assert_eq!(sum, curve.mul(&(&a + &b), g));
+
+        let sk = BigUint::from(0x1234_5678_9abc_def0u64);
+        let pk = curve.mul(&sk, g).unwrap();
+        let z = BigUint::parse_bytes(b"aabbccddeeff00112233445566778899", 16).unwrap();
+        let (r, s) = sign(&curve, &sk, &BigUint::from(777u32), &z);
+        assert!(curve.verify_ecdsa(&pk, &z, &r, &s));
+        assert!(!curve.verify_ecdsa(&pk, &(&z + BigUint::one()), &r, &s));
+        assert!(!curve.verify_ecdsa(&pk, &z, &s, &r));
+    }
+
+    #[test]
+    fn test_secp256k1_gadgets() {
+        let curve = Secp256k1::new();
+        let g = curve.generator();
+        let a = curve.mul(&BigUint::from(5u32), g).unwrap();
+        let b = curve.mul(&BigUint::from(9u32), g).unwrap();
+
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a_var = cs.new_secp256k1_point_variable(&curve, &a);
+        let b_var = cs.new_secp256k1_point_variable(&curve, &b);
+        let sum = cs.secp256k1_add_incomplete(&curve, &a_var, &b_var);
+        let double = cs.secp256k1_double(&curve, &a_var);
+        assert_eq!(
+            cs.secp256k1_point_value(&curve, &sum),
+            curve.mul(&BigUint::from(14u32), g).unwrap()
+        );
+        assert_eq!(
+            cs.secp256k1_point_value(&curve, &double),
+            curve.mul(&BigUint::from(10u32), g).unwrap()
+        );
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[sum.y.limbs[0]] = witness[sum.x.limbs[0]];
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        // a point off the curve
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let c = Secp256k1Point {
+            x: a.x.clone(),
+            y: b.y.clone(),
+        };
+        let _ = cs.new_secp256k1_point_variable(&curve, &c);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
+    #[test]
+    fn test_secp256k1_double_scalar_mul() {
+        // [11] G + [6] Q with scalars of 4 bits, a full scalar has 256 bits
+        let curve = Secp256k1::new();
+        let g = curve.generator();
+        let q = curve.mul(&BigUint::from(1000u32), g).unwrap();
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let g_var = cs.new_secp256k1_point_variable(&curve, g);
+        let q_var = cs.new_secp256k1_point_variable(&curve, &q);
+        let k1 = cs.new_variable(F::from_u32(11));
+        let k2 = cs.new_variable(F::from_u32(6));
+        let k1_bits = cs.range_check(k1, 4);
+        let k2_bits = cs.range_check(k2, 4);
+        let point =
+            cs.secp256k1_double_scalar_mul(&curve, &g_var, &k1_bits, &q_var, &k2_bits);
+        assert_eq!(
+            cs.secp256k1_point_value(&curve, &point),
+            curve.mul(&BigUint::from(6011u32), g).unwrap()
+        );
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[k1] = F::from_u32(12);
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+// This file implements the ElGamal encryption over Jubjub of crypto::basics::elgamal in a
+// constraint system, e.g. to prove that the asset tracing ciphertexts of an anonymous transfer
+// encrypt the amounts of the transfer, instead of a separate sigma protocol. The ciphertext of
+// the message m with the randomness r under the public key PK is
+//   e1 = [r]G, e2 = [m]G + [r]PK
+// with fixed-base scalar multiplications by the base G and a variable-base scalar
+// multiplication by PK. The message has at most `m_bits` bits, e.g. 32 bits for the halves of
+// the traced amounts, and the randomness is the integer representative of a Jubjub scalar, of
+// JUBJUB_SCALAR_BIT_LEN bits. PK is constrained to be on the curve; a public key from the
+// witness must also be checked to be in the subgroup of G by the caller, e.g. by a public input
+// checked by the verifier.
+
+use crate::plonk::turbo_plonk_cs::ecc::{PointVar, JUBJUB_SCALAR_BIT_LEN};
+use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+use algebra::bls12_381::BLSScalar;
+use algebra::jubjub::JubjubPoint;
+use crypto::basics::elgamal::ElGamalCiphertext;
+
+/// The variables of the coordinates of an ElGamal ciphertext (e1, e2)
+pub struct ElGamalCiphertextVar {
+    pub e1: PointVar,
+    pub e2: PointVar,
+}
+
+/// Returns the online values of the ciphertext `ctext` for prepare_io_elgamal_ciphertext:
+/// the coordinates of e1 then of e2.
+pub fn elgamal_ciphertext_io_values(
+    ctext: &ElGamalCiphertext<JubjubPoint>,
+) -> Vec<BLSScalar> {
+    vec![
+        ctext.e1.get_x(),
+        ctext.e1.get_y(),
+        ctext.e2.get_x(),
+        ctext.e2.get_y(),
+    ]
+}
+
+impl TurboPlonkConstraintSystem<BLSScalar> {
+    /// Computes the ElGamal ciphertext of the `m_bits`-bit message `m_var` with the randomness
+    /// `rand_var` under the public key `pk_var`, whose value is `pk_point`, see elgamal.rs.
+    /// `m_bits` should be a positive even number.
+    pub fn elgamal_encrypt(
+        &mut self,
+        base: JubjubPoint,
+        pk_var: &PointVar,
+        pk_point: JubjubPoint,
+        m_var: VarIndex,
+        m_bits: usize,
+        rand_var: VarIndex,
+    ) -> ElGamalCiphertextVar {
+        self.insert_on_curve_gate(pk_var);
+        let (e1, _) = self.scalar_mul(base.clone(), rand_var, JUBJUB_SCALAR_BIT_LEN);
+        let (m_base, m_base_point) = self.scalar_mul(base, m_var, m_bits);
+        let (r_pk, r_pk_point) = self.var_base_scalar_mul(
+            PointVar::new(pk_var.get_x(), pk_var.get_y()),
+            pk_point,
+            rand_var,
+            JUBJUB_SCALAR_BIT_LEN,
+        );
+        let e2 = self.ecc_add(&m_base, &r_pk, &m_base_point, &r_pk_point);
+        let e2 = e2.into_point_var();
+        ElGamalCiphertextVar { e1, e2 }
+    }
+
+    /// Insert the constraints for the coordinates of the ciphertext `ctext_var` to be public
+    /// inputs, in the order of elgamal_ciphertext_io_values.
+    pub fn prepare_io_elgamal_ciphertext(&mut self, ctext_var: &ElGamalCiphertextVar) {
+        self.prepare_io_variable(ctext_var.e1.get_x());
+        self.prepare_io_variable(ctext_var.e1.get_y());
+        self.prepare_io_variable(ctext_var.e2.get_x());
+        self.prepare_io_variable(ctext_var.e2.get_y());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::ecc::Point;
+    use crate::plonk::turbo_plonk_cs::elgamal::elgamal_ciphertext_io_values;
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{Group, Scalar};
+    use algebra::jubjub::{JubjubPoint, JubjubScalar};
+    use crypto::basics::elgamal::{elgamal_encrypt, elgamal_key_gen};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    #[test]
+    fn test_elgamal_encrypt() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let base = JubjubPoint::get_base();
+        let (_, pub_key) = elgamal_key_gen::<_, JubjubPoint>(&mut prng, &base);
+        let pk_point = pub_key.get_point();
+        let m = JubjubScalar::from_u32(123456789);
+        let r = JubjubScalar::random(&mut prng);
+        let ctext = elgamal_encrypt(&base, &m, &r, &pub_key);
+
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let pk_var = cs.new_point_variable(Point::from(&pk_point));
+        let m_var = cs.new_variable(BLSScalar::from(&m));
+        let rand_var = cs.new_variable(BLSScalar::from(&r));
+        let ctext_var = cs.elgamal_encrypt(base, &pk_var, pk_point, m_var, 32, rand_var);
+        cs.prepare_io_elgamal_ciphertext(&ctext_var);
+
+        let mut witness = cs.get_and_clear_witness();
+        let mut online_vars = elgamal_ciphertext_io_values(&ctext);
+        pnk!(cs.verify_witness(&witness, &online_vars));
+
+        // another ciphertext
+        online_vars[2] = online_vars[0];
+        assert!(cs.verify_witness(&witness, &online_vars).is_err());
+
+        // a message of more than 32 bits
+        let online_vars = elgamal_ciphertext_io_values(&ctext);
+        witness[m_var] = BLSScalar::from_u64(1 << 32);
+        assert!(cs.verify_witness(&witness, &online_vars).is_err());
+    }
+}
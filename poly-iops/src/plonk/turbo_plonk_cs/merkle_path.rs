@@ -0,0 +1,156 @@
+// This file implements the verification of Merkle membership proofs in a constraint system, for
+// binary Merkle trees whose nodes are the rescue hash of (left child, right child, 0, 0), as in
+// crypto::merkle_tree::sparse_merkle_tree. A path is given by the siblings of the nodes from the
+// leaf to the root, and by the direction bits of the nodes: a bit is one if the node is the
+// right child of its parent. The gadget returns the root recomputed from the leaf, that is
+// constrained by the caller, e.g. to equal a public root.
+
+use crate::plonk::turbo_plonk_cs::rescue::StateVar;
+use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+use algebra::bls12_381::BLSScalar;
+use algebra::groups::{One, ScalarArithmetic, Zero};
+use crypto::basics::hash::rescue::RescueInstance;
+
+/// Returns the root of a Merkle path with leaf `leaf`, siblings `siblings` from the leaf to the
+/// root, and `directions` true for the nodes that are right children. This is the value
+/// computed by TurboPlonkConstraintSystem::merkle_path_verify.
+pub fn merkle_path_root(
+    leaf: &BLSScalar,
+    siblings: &[BLSScalar],
+    directions: &[bool],
+) -> BLSScalar {
+    assert_eq!(
+        siblings.len(),
+        directions.len(),
+        "the number of siblings is not the number of directions"
+    );
+    let hash = RescueInstance::new();
+    let zero = BLSScalar::zero();
+    let mut node = *leaf;
+    for (sibling, &is_right) in siblings.iter().zip(directions.iter()) {
+        let (left, right) = if is_right {
+            (*sibling, node)
+        } else {
+            (node, *sibling)
+        };
+        node = hash.rescue_hash(&[left, right, zero, zero])[0];
+    }
+    node
+}
+
+impl TurboPlonkConstraintSystem<BLSScalar> {
+    /// Returns the root of the Merkle path of leaf `leaf_var` with siblings `sibling_vars`, from
+    /// the leaf to the root, and direction bits `direction_bits`, one for the nodes that are
+    /// right children. The direction bits are constrained to be boolean.
+    pub fn merkle_path_verify(
+        &mut self,
+        leaf_var: VarIndex,
+        sibling_vars: &[VarIndex],
+        direction_bits: &[VarIndex],
+    ) -> VarIndex {
+        for &bit in direction_bits.iter() {
+            self.insert_boolean_gate(bit);
+        }
+        self.merkle_compute_root(leaf_var, sibling_vars, direction_bits)
+    }
+
+    /// Same as merkle_path_verify, for direction bits that are already constrained to be
+    /// boolean, e.g. by a range check.
+    pub(crate) fn merkle_compute_root(
+        &mut self,
+        leaf_var: VarIndex,
+        sibling_vars: &[VarIndex],
+        direction_bits: &[VarIndex],
+    ) -> VarIndex {
+        assert_eq!(
+            sibling_vars.len(),
+            direction_bits.len(),
+            "the number of siblings is not the number of direction bits"
+        );
+        let zero_var = self.zero_var();
+        let one = BLSScalar::one();
+        let zero = BLSScalar::zero();
+        let mut node_var = leaf_var;
+        for (&bit, &sibling) in direction_bits.iter().zip(sibling_vars.iter()) {
+            // the node is the right child if the bit is one
+            let left = self.select(node_var, sibling, bit);
+            let right = self.linear_combine(
+                &[node_var, sibling, left, zero_var],
+                one,
+                one,
+                one.neg(),
+                zero,
+            );
+            node_var =
+                self.rescue_hash(&StateVar::new([left, right, zero_var, zero_var]))[0];
+        }
+        node_var
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::merkle_path::merkle_path_root;
+    use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar, Zero};
+    use crypto::merkle_tree::sparse_merkle_tree::{smt_leaf, SparseMerkleTree};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_merkle_path_verify() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let leaf = F::random(&mut prng);
+        let siblings: Vec<F> = (0..5).map(|_| F::random(&mut prng)).collect();
+        let directions = [true, false, false, true, true];
+        let root = merkle_path_root(&leaf, &siblings, &directions);
+
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let leaf_var = cs.new_variable(leaf);
+        let sibling_vars: Vec<VarIndex> =
+            siblings.iter().map(|s| cs.new_variable(*s)).collect();
+        let direction_bits: Vec<VarIndex> = directions
+            .iter()
+            .map(|&d| cs.new_variable(if d { F::one() } else { F::zero() }))
+            .collect();
+        let root_var = cs.merkle_path_verify(leaf_var, &sibling_vars, &direction_bits);
+        cs.prepare_io_variable(root_var);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[root]));
+        assert!(cs.verify_witness(&witness, &[leaf]).is_err());
+
+        // a wrong direction changes the root
+        witness[direction_bits[1]] = F::one();
+        assert!(cs.verify_witness(&witness, &[root]).is_err());
+
+        // the direction bits are boolean
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let leaf_var = cs.new_variable(leaf);
+        let sibling_var = cs.new_variable(siblings[0]);
+        let bit_var = cs.new_variable(F::from_u32(2));
+        let _ = cs.merkle_path_verify(leaf_var, &[sibling_var], &[bit_var]);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
+    #[test]
+    fn test_merkle_path_of_sparse_merkle_tree() {
+        // the paths of sparse Merkle trees have the same hash of nodes
+        let mut tree = pnk!(SparseMerkleTree::new(4));
+        let key = 11u64;
+        let value = F::from_u32(7);
+        pnk!(tree.insert(3, F::from_u32(5)));
+        pnk!(tree.insert(key, value));
+        let proof = pnk!(tree.prove(key));
+        let directions: Vec<bool> = (0..4).map(|i| (key >> i) & 1 == 1).collect();
+        let leaf = smt_leaf(key, Some(&value));
+        assert_eq!(
+            merkle_path_root(&leaf, &proof.siblings, &directions),
+            tree.root()
+        );
+    }
+}
@@ -1,26 +1,108 @@
 /// This file implements a Turbo PLONK constraint system. It also implements a set of
-/// arithmetic/boolean/range gates that will be used in Anonymous transfer.
-/// The gates for elliptic curve operations and Rescue cipher/hash functions are implemented
-/// in ecc.rs and rescue.rs, respectively.
+/// arithmetic/boolean/range gates that will be used in Anonymous transfer, and lookup gates
+/// that check that values are entries of lookup tables, with the Plookup argument of
+/// plonk::lookup. The constraint system is generic in its number of wires and selectors per
+/// gate, see TurboConstraintSystem, with the gates above for 5 wires and 13 selectors.
+/// The gadgets and the tools are implemented in the submodules, see their headers:
+/// - ecc.rs, rescue.rs, poseidon.rs: elliptic curve operations, Rescue and Poseidon
+/// - merkle_path.rs, sparse_merkle_tree.rs: Merkle membership proofs and sparse Merkle trees
+/// - signed.rs, nonnative.rs: signed integers and foreign field arithmetic
+/// - ecdsa.rs, schnorr.rs, elgamal.rs: signatures and encryption
+/// - plonk_verifier.rs: the scalar part of a TurboPlonk verifier
+/// - serialization.rs, stats.rs, optimize.rs, template.rs: encoding, cost report,
+///   optimization pass and gadget templates
+/// - debug.rs, testing.rs: debugging and testing of gadgets
+#[cfg(feature = "debug_gates")]
+pub mod debug;
+
 pub mod ecc;
 
+pub mod ecdsa;
+
+pub mod elgamal;
+
+pub mod merkle_path;
+
+pub mod nonnative;
+
+pub mod optimize;
+
+#[allow(non_snake_case)]
+pub mod plonk_verifier;
+
+pub mod poseidon;
+
 pub mod rescue;
 
+pub mod schnorr;
+
+pub mod serialization;
+
+pub mod signed;
+
+pub mod sparse_merkle_tree;
+
+pub mod stats;
+
+pub mod template;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 use crate::plonk::errors::PlonkError;
-use crate::plonk::plonk_setup::ConstraintSystem;
+use crate::plonk::lookup::LookupColumns;
+use crate::plonk::plonk_setup::{ConstraintSystem, QuotEvalDomainStrategy};
+use crate::polynomials::evaluation_domain::fft_size_at_least;
 use algebra::groups::Scalar;
+#[cfg(feature = "debug_gates")]
+use debug::GateLabel;
+use rand_core::{CryptoRng, RngCore};
 use ruc::*;
+use std::collections::HashSet;
+#[cfg(feature = "debug_gates")]
+use std::panic::Location;
 
 pub type VarIndex = usize; // Variable index
 pub type CsIndex = usize; // Constraint index
 
 pub const N_WIRES_PER_GATE: usize = 5;
 pub const N_SELECTORS: usize = 13;
+/// The number of bits of the chunks of range_check_chunks
+pub const RANGE_CHUNK_BITS: usize = 4;
+/// The default domain of the quotient polynomial of a TurboPLONK circuit, of degree 5n + 7
+pub const TURBO_QUOT_EVAL_DOM_STRATEGY: QuotEvalDomainStrategy =
+    QuotEvalDomainStrategy::Factor {
+        factor: 6,
+        small_factor: 16,
+    };
+
+fn default_quot_eval_dom_strategy() -> QuotEvalDomainStrategy {
+    TURBO_QUOT_EVAL_DOM_STRATEGY
+}
 
+/// What `inv` and `div` do when the value to invert is zero
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DivisionByZeroPolicy {
+    /// The constraints are unsatisfiable: the circuit proves that the value is not zero.
+    Unsatisfiable,
+    /// The output is zero, and a boolean flag variable equals 1 if and only if the value is zero.
+    Flag,
+}
+
+/// The Turbo PLONK constraint system with the gates of this module, see TurboConstraintSystem
+pub type TurboPlonkConstraintSystem<F> =
+    TurboConstraintSystem<F, N_WIRES_PER_GATE, N_SELECTORS>;
+
+/// A constraint system with `W` wires and `S` selectors per gate. The gate equation of a layout
+/// is given by its implementation of ConstraintSystem, and the gadgets of this module are
+/// implemented for TurboPlonkConstraintSystem, the layout with 5 wires and 13 selectors. The
+/// storage of the gates, the variables, the witness and the padding are common to all the
+/// layouts.
 #[derive(Serialize, Deserialize)]
-pub struct TurboPlonkConstraintSystem<F> {
+pub struct TurboConstraintSystem<F: Scalar, const W: usize, const S: usize> {
     pub selectors: Vec<Vec<F>>,
-    pub wiring: [Vec<VarIndex>; N_WIRES_PER_GATE],
+    #[serde(with = "serialization::wiring_serde")]
+    pub wiring: [Vec<VarIndex>; W],
     pub num_vars: usize,
     pub size: usize,
     pub public_vars_constraint_indices: Vec<CsIndex>,
@@ -31,6 +113,29 @@ pub struct TurboPlonkConstraintSystem<F> {
     zero_var: Option<VarIndex>,
     // A reserved variable that maps to value one
     one_var: Option<VarIndex>,
+    // The (input, output) entries of the lookup tables, indexed by table id
+    #[serde(default)]
+    pub lookup_tables: Vec<Vec<(F, F)>>,
+    // The constraint index and the table id of the lookup gates
+    #[serde(default)]
+    pub lookup_gates: Vec<(CsIndex, usize)>,
+    // The choice of the size of the domain of the quotient polynomial
+    #[serde(default = "default_quot_eval_dom_strategy")]
+    quot_eval_dom_strategy: QuotEvalDomainStrategy,
+    // The labels of the open namespaces, see stats.rs
+    #[serde(skip)]
+    namespace_stack: Vec<String>,
+    // The constraint index, the variable index and the label of each change of namespace
+    #[serde(skip)]
+    namespace_marks: Vec<(CsIndex, VarIndex, String)>,
+    // The locations of the push_namespace calls of the open namespaces, see debug.rs
+    #[cfg(feature = "debug_gates")]
+    #[serde(skip)]
+    namespace_locations: Vec<&'static Location<'static>>,
+    // The label of each gate, see debug.rs
+    #[cfg(feature = "debug_gates")]
+    #[serde(skip)]
+    gate_labels: Vec<GateLabel>,
 }
 
 impl<F: Scalar> ConstraintSystem for TurboPlonkConstraintSystem<F> {
@@ -49,13 +154,11 @@ impl<F: Scalar> ConstraintSystem for TurboPlonkConstraintSystem<F> {
     }
 
     /// `quot_eval_dom_size` divides (q-1), and should be larger than the degree of the quotient
-    /// polynomial, i.e., `quot_eval_dom_size` > 5 * `self.size` + 7
+    /// polynomial, i.e., `quot_eval_dom_size` > 5 * `self.size` + 7, see
+    /// set_quot_eval_dom_strategy
     fn quot_eval_dom_size(&self) -> usize {
-        if self.size > 4 {
-            self.size * 6
-        } else {
-            self.size * 16
-        }
+        self.quot_eval_dom_strategy
+            .quot_eval_dom_size(self.size, 5 * self.size + 8)
     }
 
     fn n_wires_per_gate(&self) -> usize {
@@ -76,7 +179,10 @@ impl<F: Scalar> ConstraintSystem for TurboPlonkConstraintSystem<F> {
 
     fn selector(&self, index: usize) -> Result<&[F]> {
         if index >= self.selectors.len() {
-            return Err(eg!(PlonkError::FuncParamsError));
+            return Err(eg!(PlonkError::SelectorMismatch(
+                index,
+                self.selectors.len()
+            )));
         }
         Ok(&self.selectors[index])
     }
@@ -94,7 +200,13 @@ impl<F: Scalar> ConstraintSystem for TurboPlonkConstraintSystem<F> {
         sel_vals: &[&F],
         pub_input: &F,
     ) -> Result<F> {
-        if wire_vals.len() != N_WIRES_PER_GATE || sel_vals.len() != N_SELECTORS {
+        if sel_vals.len() != N_SELECTORS {
+            return Err(eg!(PlonkError::SelectorMismatch(
+                sel_vals.len(),
+                N_SELECTORS
+            )));
+        }
+        if wire_vals.len() != N_WIRES_PER_GATE {
             return Err(eg!(PlonkError::FuncParamsError));
         }
         let add1 = sel_vals[0].mul(&wire_vals[0]);
@@ -160,11 +272,54 @@ impl<F: Scalar> ConstraintSystem for TurboPlonkConstraintSystem<F> {
             wire_vals[4].neg(),
         ])
     }
+
+    /// The lookup gate at constraint index i asserts that (w1, w2) is an entry of the table
+    /// q_table[i]
+    fn lookup_columns(&self) -> Option<LookupColumns<F>> {
+        if self.lookup_tables.is_empty() {
+            return None;
+        }
+        let mut q_lookup = vec![F::zero(); self.size];
+        let mut q_table = vec![F::zero(); self.size];
+        for (cs_index, table_id) in self.lookup_gates.iter() {
+            q_lookup[*cs_index] = F::one();
+            q_table[*cs_index] = F::from_u64(*table_id as u64);
+        }
+        let mut table = vec![];
+        for (table_id, entries) in self.lookup_tables.iter().enumerate() {
+            let id = F::from_u64(table_id as u64);
+            table.extend(entries.iter().map(|(input, output)| [*input, *output, id]));
+        }
+        Some(LookupColumns {
+            q_lookup,
+            q_table,
+            table,
+        })
+    }
+}
+
+// Overwrites the values of `witness` with zeroes and clears it. The buffer is passed to
+// `black_box` so that the compiler does not drop the writes as dead stores. Copies of the
+// values made by reallocations while the witness was growing are out of reach.
+pub(crate) fn zeroize_witness<F: Scalar>(witness: &mut Vec<F>) {
+    for value in witness.iter_mut() {
+        *value = F::zero();
+    }
+    std::hint::black_box(&mut witness[..]);
+    witness.clear();
+}
+
+// Returns WireIndexOutOfBounds if a variable of `vars` is not below `num_vars`
+pub(crate) fn check_var_indices(vars: &[VarIndex], num_vars: usize) -> Result<()> {
+    match vars.iter().find(|var| **var >= num_vars) {
+        Some(var) => Err(eg!(PlonkError::WireIndexOutOfBounds(*var, num_vars))),
+        None => Ok(()),
+    }
 }
 
 // A helper function that computes the little-endian binary representation of a value.
 // Each bit is represented as a field element.
-fn compute_binary_le<F: Scalar>(bytes: &[u8]) -> Vec<F> {
+pub(crate) fn compute_binary_le<F: Scalar>(bytes: &[u8]) -> Vec<F> {
     let mut res = vec![];
     for byte in bytes.iter() {
         let mut tmp = *byte;
@@ -180,20 +335,21 @@ fn compute_binary_le<F: Scalar>(bytes: &[u8]) -> Vec<F> {
     res
 }
 
-impl<F: Scalar> Default for TurboPlonkConstraintSystem<F> {
+impl<F: Scalar, const W: usize, const S: usize> Default
+    for TurboConstraintSystem<F, W, S>
+{
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<F: Scalar> TurboPlonkConstraintSystem<F> {
+impl<F: Scalar, const W: usize, const S: usize> TurboConstraintSystem<F, W, S> {
     /// Create a TurboPLONK constraint system with a certain field size.
-    pub fn new() -> TurboPlonkConstraintSystem<F> {
-        let selectors: Vec<Vec<F>> =
-            std::iter::repeat(vec![]).take(N_SELECTORS).collect();
-        TurboPlonkConstraintSystem {
+    pub fn new() -> TurboConstraintSystem<F, W, S> {
+        let selectors: Vec<Vec<F>> = std::iter::repeat(vec![]).take(S).collect();
+        TurboConstraintSystem {
             selectors,
-            wiring: [vec![], vec![], vec![], vec![], vec![]],
+            wiring: std::array::from_fn(|_| vec![]),
             num_vars: 0,
             size: 0,
             public_vars_constraint_indices: vec![],
@@ -201,6 +357,15 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
             witness: vec![],
             zero_var: None,
             one_var: None,
+            lookup_tables: vec![],
+            lookup_gates: vec![],
+            quot_eval_dom_strategy: TURBO_QUOT_EVAL_DOM_STRATEGY,
+            namespace_stack: vec![],
+            namespace_marks: vec![],
+            #[cfg(feature = "debug_gates")]
+            namespace_locations: vec![],
+            #[cfg(feature = "debug_gates")]
+            gate_labels: vec![],
         }
     }
 
@@ -222,6 +387,238 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         self.one_var.unwrap() // safe unwrap
     }
 
+    /// Add a variable (with actual value `value`) into the constraint system.
+    pub fn new_variable(&mut self, value: F) -> VarIndex {
+        self.num_vars += 1;
+        self.witness.push(value);
+        self.num_vars - 1
+    }
+
+    /// Add a vector of variables into the constraint system.
+    pub fn add_variables(&mut self, values: &[F]) {
+        self.num_vars += values.len();
+        for value in values.iter() {
+            self.witness.push(*value);
+        }
+    }
+
+    /// Insert a gate with the selector values `selectors` on the variables `wires`, in the
+    /// order of the wires and the selectors of the layout.
+    pub fn insert_gate(&mut self, wires: [VarIndex; W], selectors: [F; S]) {
+        pnk!(self.try_insert_gate(wires, selectors))
+    }
+
+    /// Same as insert_gate, returning WireIndexOutOfBounds instead of panicking if a wire is
+    /// not a variable of the constraint system.
+    pub fn try_insert_gate(
+        &mut self,
+        wires: [VarIndex; W],
+        selectors: [F; S],
+    ) -> Result<()> {
+        check_var_indices(&wires, self.num_vars).c(d!())?;
+        for (selector, value) in self.selectors.iter_mut().zip(selectors) {
+            selector.push(value);
+        }
+        for (wire, var) in self.wiring.iter_mut().zip(wires) {
+            wire.push(var);
+        }
+        #[cfg(feature = "debug_gates")]
+        self.record_gate_label();
+        self.size += 1;
+        Ok(())
+    }
+
+    /// Returns the strategy choosing the size of the domain of the quotient polynomial
+    pub fn quot_eval_dom_strategy(&self) -> QuotEvalDomainStrategy {
+        self.quot_eval_dom_strategy
+    }
+
+    /// Sets the strategy choosing the size of the domain of the quotient polynomial, by
+    /// default TURBO_QUOT_EVAL_DOM_STRATEGY. It must be set before the preprocessing, by the
+    /// prover and the verifier alike, and again after decoding a constraint system with
+    /// `from_bytes`, whose encoding does not record it.
+    pub fn set_quot_eval_dom_strategy(&mut self, strategy: QuotEvalDomainStrategy) {
+        self.quot_eval_dom_strategy = strategy;
+    }
+
+    /// Pad the number of constraints to a power of two. With lookup tables, the number of
+    /// constraints is at least the number of entries of the tables, and the last constraint is
+    /// not a lookup gate, as the lookup argument does not check it.
+    pub fn pad(&mut self) {
+        let n = self.padding_min_size().next_power_of_two();
+        self.pad_to(n);
+    }
+
+    /// Same as pad, to the smallest size of the form 2^k or 3 * 2^k instead of a power of two,
+    /// e.g. 1536 instead of 2048 for 1100 constraints. Also sets the strategy of the domain of
+    /// the quotient polynomial to QuotEvalDomainStrategy::Smallest, as the default domain of
+    /// 6 * 3 * 2^k elements for 3 * 2^k constraints is not supported by the FFT.
+    pub fn pad_mixed_radix(&mut self) {
+        let n = fft_size_at_least(self.padding_min_size());
+        self.pad_to(n);
+        self.quot_eval_dom_strategy = QuotEvalDomainStrategy::Smallest;
+    }
+
+    // The number of constraints of the padded constraint system is at least this number
+    fn padding_min_size(&self) -> usize {
+        let mut min_size = self.size;
+        if !self.lookup_tables.is_empty() {
+            if self.lookup_gates.last().map(|(cs_index, _)| cs_index + 1)
+                == Some(self.size)
+            {
+                min_size += 1;
+            }
+            let n_entries = self.lookup_tables.iter().map(|table| table.len()).sum();
+            min_size = min_size.max(n_entries).max(2);
+        }
+        min_size
+    }
+
+    fn pad_to(&mut self, n: usize) {
+        let diff = n - self.size;
+        for selector in self.selectors.iter_mut() {
+            selector.extend(vec![F::zero(); diff]);
+        }
+        for wire in self.wiring.iter_mut() {
+            wire.extend(vec![0; diff]);
+        }
+        #[cfg(feature = "debug_gates")]
+        self.record_padding_labels(diff);
+        self.size += diff;
+    }
+
+    /// Same as pad, after appending `n_blinding_gates` gates with zero selectors on new
+    /// variables with random values: the evaluations of the wire polynomials on these rows are
+    /// uniformly random, so that a proof reveals nothing on the witness even without the
+    /// blinding of the wire polynomials by the prover, as long as each wire polynomial is opened
+    /// at fewer points than `n_blinding_gates`. The gates constrain nothing, and are not lookup
+    /// nor public input gates, so that the prover and the verifier need no change. The random
+    /// values are part of the witness of the circuit, and the gates are the same for any `prng`,
+    /// so that the circuit is preprocessed once.
+    pub fn pad_with_blinding<R: CryptoRng + RngCore>(
+        &mut self,
+        n_blinding_gates: usize,
+        prng: &mut R,
+    ) {
+        for _ in 0..n_blinding_gates {
+            let wires: [VarIndex; W] =
+                std::array::from_fn(|_| self.new_variable(F::random(prng)));
+            self.insert_gate(wires, [F::zero(); S]);
+        }
+        self.pad();
+    }
+
+    fn get_witness_index(&self, wire_index: usize, cs_index: CsIndex) -> VarIndex {
+        assert!(wire_index < W, "wire index out of bound");
+        assert!(cs_index < self.size, "constraint index out of bound");
+        self.wiring[wire_index][cs_index]
+    }
+
+    /// Extract and clear the entire witness of the circuit. The witness consists of
+    /// secret inputs, public inputs, and the values of intermediate variables.
+    /// The constraint system keeps no copy of the witness, and the caller is responsible for
+    /// wiping the returned values: prefer `with_witness`, which does it.
+    pub fn get_and_clear_witness(&mut self) -> Vec<F> {
+        std::mem::take(&mut self.witness)
+    }
+
+    /// Extract the entire witness of the circuit, pass it to `f` (e.g. a call to the prover),
+    /// then overwrite it with zeroes and return the output of `f`.
+    pub fn with_witness<T, G: FnOnce(&[F]) -> T>(&mut self, f: G) -> T {
+        let mut witness = self.get_and_clear_witness();
+        let res = f(&witness);
+        zeroize_witness(&mut witness);
+        res
+    }
+
+    /// Overwrite the witness of the circuit with zeroes and clear it.
+    pub fn clear_witness(&mut self) {
+        zeroize_witness(&mut self.witness);
+    }
+}
+
+impl<F: Scalar, const W: usize, const S: usize> TurboConstraintSystem<F, W, S>
+where
+    Self: ConstraintSystem<Field = F>,
+{
+    pub fn verify_witness(&self, witness: &[F], online_vars: &[F]) -> Result<()> {
+        if witness.len() != self.num_vars {
+            return Err(eg!(format!(
+                "witness len = {}, num_vars = {}",
+                witness.len(),
+                self.num_vars
+            )));
+        }
+        if online_vars.len() != self.public_vars_witness_indices.len()
+            || online_vars.len() != self.public_vars_constraint_indices.len()
+        {
+            return Err(eg!("wrong number of online variables"));
+        }
+        for cs_index in 0..self.size() {
+            let mut public_online = F::zero();
+            // check if the constraint constrains a public variable
+            // search constraint index in online vars
+            for ((c_i, w_i), online_var) in self
+                .public_vars_constraint_indices
+                .iter()
+                .zip(self.public_vars_witness_indices.iter())
+                .zip(online_vars.iter())
+            {
+                if *c_i == cs_index {
+                    // found
+                    public_online = *online_var;
+                    if witness[*w_i] != *online_var {
+                        return Err(eg!(format!(
+                            "cs index {}: online var {:?} does not match witness {:?}",
+                            cs_index, *online_var, witness[*w_i]
+                        )));
+                    }
+                }
+            }
+            let wire_vals: Vec<&F> = (0..W)
+                .map(|i| &witness[self.get_witness_index(i, cs_index)])
+                .collect();
+            let sel_vals: Vec<&F> = (0..self.num_selectors())
+                .map(|i| &self.selectors[i][cs_index])
+                .collect();
+            let eval_gate = self
+                .eval_gate_func(&wire_vals, &sel_vals, &public_online)
+                .c(d!("wrong func params for eval_gate_func()"))?;
+            if eval_gate != F::zero() {
+                let msg = format!(
+                    "cs index {}: wire_vals = ({:?}), sel_vals = ({:?})",
+                    cs_index, wire_vals, sel_vals
+                );
+                #[cfg(feature = "debug_gates")]
+                let msg = format!("{}, {}", msg, self.describe_gate(cs_index));
+                return Err(eg!(msg));
+            }
+        }
+        let tables: Vec<HashSet<(Vec<u8>, Vec<u8>)>> = self
+            .lookup_tables
+            .iter()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|(input, output)| (input.to_bytes(), output.to_bytes()))
+                    .collect()
+            })
+            .collect();
+        for (cs_index, table_id) in self.lookup_gates.iter() {
+            let input = &witness[self.get_witness_index(0, *cs_index)];
+            let output = &witness[self.get_witness_index(1, *cs_index)];
+            if !tables[*table_id].contains(&(input.to_bytes(), output.to_bytes())) {
+                return Err(eg!(format!(
+                    "cs index {}: ({:?}, {:?}) is not in lookup table {}",
+                    cs_index, input, output, table_id
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Scalar> TurboPlonkConstraintSystem<F> {
     /// Insert a linear combination gate: wo = w1 * q1 + w2 * q2 + w3 * q3 + w4 * q4.
     pub fn insert_lc_gate(
         &mut self,
@@ -232,11 +629,22 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         q3: F,
         q4: F,
     ) {
-        assert!(
-            wires_in.iter().all(|&x| x < self.num_vars),
-            "input wire index out of bound"
-        );
-        assert!(wire_out < self.num_vars, "wire_out index out of bound");
+        pnk!(self.try_insert_lc_gate(wires_in, wire_out, q1, q2, q3, q4))
+    }
+
+    /// Same as insert_lc_gate, returning WireIndexOutOfBounds instead of panicking if a wire
+    /// is not a variable of the constraint system.
+    pub fn try_insert_lc_gate(
+        &mut self,
+        wires_in: &[VarIndex; 4],
+        wire_out: VarIndex,
+        q1: F,
+        q2: F,
+        q3: F,
+        q4: F,
+    ) -> Result<()> {
+        check_var_indices(wires_in, self.num_vars).c(d!())?;
+        check_var_indices(&[wire_out], self.num_vars).c(d!())?;
         let zero = F::zero();
         self.push_add_selectors(q1, q2, q3, q4);
         self.push_mul_selectors(zero, zero);
@@ -249,6 +657,7 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         }
         self.wiring[4].push(wire_out);
         self.size += 1;
+        Ok(())
     }
 
     pub fn insert_add_gate(
@@ -257,14 +666,25 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         right_var: VarIndex,
         out_var: VarIndex,
     ) {
-        self.insert_lc_gate(
+        pnk!(self.try_insert_add_gate(left_var, right_var, out_var))
+    }
+
+    /// Same as insert_add_gate, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system.
+    pub fn try_insert_add_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+    ) -> Result<()> {
+        self.try_insert_lc_gate(
             &[left_var, right_var, 0, 0],
             out_var,
             F::one(),
             F::one(),
             F::zero(),
             F::zero(),
-        );
+        )
     }
 
     pub fn insert_sub_gate(
@@ -273,14 +693,25 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         right_var: VarIndex,
         out_var: VarIndex,
     ) {
-        self.insert_lc_gate(
+        pnk!(self.try_insert_sub_gate(left_var, right_var, out_var))
+    }
+
+    /// Same as insert_sub_gate, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system.
+    pub fn try_insert_sub_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+    ) -> Result<()> {
+        self.try_insert_lc_gate(
             &[left_var, right_var, 0, 0],
             out_var,
             F::one(),
             F::one().neg(),
             F::zero(),
             F::zero(),
-        );
+        )
     }
 
     pub fn insert_mul_gate(
@@ -289,9 +720,18 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         right_var: VarIndex,
         out_var: VarIndex,
     ) {
-        assert!(left_var < self.num_vars, "left_var index out of bound");
-        assert!(right_var < self.num_vars, "right_var index out of bound");
-        assert!(out_var < self.num_vars, "out_var index out of bound");
+        pnk!(self.try_insert_mul_gate(left_var, right_var, out_var))
+    }
+
+    /// Same as insert_mul_gate, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system.
+    pub fn try_insert_mul_gate(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        out_var: VarIndex,
+    ) -> Result<()> {
+        check_var_indices(&[left_var, right_var, out_var], self.num_vars).c(d!())?;
         let zero = F::zero();
         self.push_add_selectors(zero, zero, zero, zero);
         self.push_mul_selectors(F::one(), zero);
@@ -305,21 +745,7 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         self.wiring[3].push(0);
         self.wiring[4].push(out_var);
         self.size += 1;
-    }
-
-    /// Add a variable (with actual value `value`) into the constraint system.
-    pub fn new_variable(&mut self, value: F) -> VarIndex {
-        self.num_vars += 1;
-        self.witness.push(value);
-        self.num_vars - 1
-    }
-
-    /// Add a vector of variables into the constraint system.
-    pub fn add_variables(&mut self, values: &[F]) {
-        self.num_vars += values.len();
-        for value in values.iter() {
-            self.witness.push(*value);
-        }
+        Ok(())
     }
 
     /// Create an output variable and insert a linear combination gate.
@@ -390,6 +816,41 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         self.insert_mul_gate(var, var, var);
     }
 
+    /// Same as insert_boolean_gate, returning WireIndexOutOfBounds instead of panicking if
+    /// `var` is not in the constraint system.
+    pub fn try_insert_boolean_gate(&mut self, var: VarIndex) -> Result<()> {
+        self.try_insert_mul_gate(var, var, var)
+    }
+
+    /// Boolean constrain the variables `vars`, two variables per gate: a lookup gate checks
+    /// that the values of two variables are an entry of boolean_pair_table. The last variable
+    /// of an odd number of variables has a boolean gate. The gate equation is a single
+    /// constraint per gate, that can check one bit only; the lookup gates halve the number of
+    /// gates of insert_boolean_gate, at the cost of the lookup argument in the proof if the
+    /// circuit has no other lookup table.
+    pub fn insert_boolean_gates(&mut self, vars: &[VarIndex]) {
+        pnk!(self.try_insert_boolean_gates(vars))
+    }
+
+    /// Same as insert_boolean_gates, returning WireIndexOutOfBounds instead of panicking if a
+    /// variable is not in the constraint system. No gate is inserted on error.
+    pub fn try_insert_boolean_gates(&mut self, vars: &[VarIndex]) -> Result<()> {
+        check_var_indices(vars, self.num_vars).c(d!())?;
+        let pairs = vars.chunks_exact(2);
+        let last = pairs.remainder().first().copied();
+        if vars.len() >= 2 {
+            let table_id = self.boolean_pair_table();
+            for pair in pairs {
+                self.try_insert_lookup_gate(pair[0], pair[1], table_id)
+                    .c(d!())?;
+            }
+        }
+        if let Some(var) = last {
+            self.try_insert_boolean_gate(var).c(d!())?;
+        }
+        Ok(())
+    }
+
     /// Enforce a range constraint: `0 < witness[var] < 2^n_bits`:
     /// 1. Transform `witness[var]` into a binary vector and boolean constrain the binary vector.
     /// 2. Adding a set of linear combination constraints showing that the binary vector is a binary
@@ -413,11 +874,12 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         for elem in b.iter() {
             self.insert_boolean_gate(*elem);
         }
-        let one = F::one();
-        let two = one.add(&one);
-        let four = two.add(&two);
-        let eight = four.add(&four);
-        let bin = vec![one, two, four, eight];
+        let bin = vec![
+            F::from_u32(1),
+            F::from_u32(2),
+            F::from_u32(4),
+            F::from_u32(8),
+        ];
 
         let mut acc = b[n_bits - 1];
         let m = (n_bits - 2) / 3;
@@ -460,37 +922,342 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         b
     }
 
-    /// Given two variables `var0` and `var1` and a boolean variable `bit`, return var_bit.
-    /// var_bit = (1-bit) * var0 + bit * var1 = - bit * var0 + bit * var1 + var0
-    /// Wires: (w1, w2, w3 , w4) = (bit, var0, bit, var1)
-    /// Selectors: q2 = qm2 = qo = 1, qm1 = -1
-    pub fn select(&mut self, var0: VarIndex, var1: VarIndex, bit: VarIndex) -> VarIndex {
-        assert!(var0 < self.num_vars, "var0 index out of bound");
-        assert!(var1 < self.num_vars, "var1 index out of bound");
-        assert!(bit < self.num_vars, "bit var index out of bound");
-        let zero = F::zero();
-        let one = F::one();
-        self.push_add_selectors(zero, one, zero, zero);
-        self.push_mul_selectors(one.neg(), one);
-        self.push_constant_selector(zero);
-        self.push_ecc_selector(zero);
-        self.push_rescue_selectors(zero, zero, zero, zero);
-        self.push_out_selector(one);
-        let out = if self.witness[bit] == zero {
-            self.witness[var0]
-        } else {
-            self.witness[var1]
-        };
-        let out_var = self.new_variable(out);
-        self.wiring[0].push(bit);
-        self.wiring[1].push(var0);
-        self.wiring[2].push(bit);
-        self.wiring[3].push(var1);
-        self.wiring[4].push(out_var);
+    /// Enforce a range constraint `0 <= witness[var] < 2^n_bits` with chunks of
+    /// `RANGE_CHUNK_BITS` bits: each chunk is checked by a single lookup gate against the table
+    /// of its range (see range_table), and linear combination gates recompose `witness[var]`
+    /// from the chunks, three chunks per gate. Returns the witness indices of the chunks in
+    /// little endian form; the last chunk has `n_bits % RANGE_CHUNK_BITS` bits if not zero.
+    /// This takes about `n_bits / 3` gates, against `4 * n_bits / 3` gates for range_check,
+    /// that returns the bits.
+    pub fn range_check_chunks(&mut self, var: VarIndex, n_bits: usize) -> Vec<VarIndex> {
+        assert!(var < self.num_vars, "var index out of bound");
+        assert!(n_bits >= 1, "the number of bits is zero");
+        let n_chunks = (n_bits - 1) / RANGE_CHUNK_BITS + 1;
+        if n_chunks == 1 {
+            let table_id = self.range_table(n_bits);
+            self.lookup(var, table_id);
+            return vec![var];
+        }
+        let chunks: Vec<VarIndex> = self
+            .chunk_values(var, n_chunks)
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let chunk = self.new_variable(value);
+                let chunk_bits = RANGE_CHUNK_BITS.min(n_bits - i * RANGE_CHUNK_BITS);
+                let table_id = self.range_table(chunk_bits);
+                self.lookup(chunk, table_id);
+                chunk
+            })
+            .collect();
+        self.insert_chunks_recomposition(var, &chunks);
+        chunks
+    }
+
+    // Returns the values of the `n_chunks` chunks of RANGE_CHUNK_BITS bits of `witness[var]`,
+    // in little endian form
+    fn chunk_values(&self, var: VarIndex, n_chunks: usize) -> Vec<F> {
+        let witness_bytes = self.witness[var].to_bytes();
+        let mut binary_repr = compute_binary_le::<F>(&witness_bytes);
+        binary_repr.resize(n_chunks * RANGE_CHUNK_BITS, F::zero());
+        let two = F::from_u32(2);
+        binary_repr
+            .chunks(RANGE_CHUNK_BITS)
+            .map(|bits| {
+                bits.iter()
+                    .rev()
+                    .fold(F::zero(), |acc, bit| acc.mul(&two).add(bit))
+            })
+            .collect()
+    }
+
+    // Inserts the linear combination gates checking that `var` is the sum of the chunks of
+    // RANGE_CHUNK_BITS bits `chunks`, in little endian form, three chunks per gate. There are
+    // at least two chunks.
+    fn insert_chunks_recomposition(&mut self, var: VarIndex, chunks: &[VarIndex]) {
+        let n_chunks = chunks.len();
+        // acc = acc * 2^(RANGE_CHUNK_BITS * len) + the next `len` chunks, from the most
+        // significant chunk
+        let shift = F::from_u32(1 << RANGE_CHUNK_BITS);
+        let mut acc = chunks[n_chunks - 1];
+        let lower_chunks: Vec<VarIndex> =
+            chunks[..n_chunks - 1].iter().rev().copied().collect();
+        let n_groups = lower_chunks.chunks(3).len();
+        for (i, group) in lower_chunks.chunks(3).enumerate() {
+            let mut wires = [acc, 0, 0, 0];
+            let mut coefs = [F::zero(); 4];
+            let mut coef = F::one();
+            for (j, chunk) in group.iter().enumerate().rev() {
+                wires[j + 1] = *chunk;
+                coefs[j + 1] = coef;
+                coef = coef.mul(&shift);
+            }
+            coefs[0] = coef;
+            if i == n_groups - 1 {
+                self.insert_lc_gate(&wires, var, coefs[0], coefs[1], coefs[2], coefs[3]);
+            } else {
+                acc =
+                    self.linear_combine(&wires, coefs[0], coefs[1], coefs[2], coefs[3]);
+            }
+        }
+    }
+
+    /// Returns the id of the lookup table of the values `0..2^n_bits`, for
+    /// `n_bits <= RANGE_CHUNK_BITS`. The table is registered on its first use.
+    pub fn range_table(&mut self, n_bits: usize) -> usize {
+        assert!(
+            (1..=RANGE_CHUNK_BITS).contains(&n_bits),
+            "wrong number of bits of a range table"
+        );
+        let entries: Vec<(F, F)> = (0..1u32 << n_bits)
+            .map(|value| (F::from_u32(value), F::zero()))
+            .collect();
+        self.find_or_add_lookup_map(&entries)
+    }
+
+    /// Returns the id of the lookup table of the pairs of bits (0, 0), (0, 1), (1, 0), (1, 1).
+    /// The table is registered on its first use.
+    pub fn boolean_pair_table(&mut self) -> usize {
+        let (zero, one) = (F::zero(), F::one());
+        self.find_or_add_lookup_map(&[
+            (zero, zero),
+            (zero, one),
+            (one, zero),
+            (one, one),
+        ])
+    }
+
+    // Returns the id of the lookup table with the entries `entries`, registered if no table
+    // has these entries
+    fn find_or_add_lookup_map(&mut self, entries: &[(F, F)]) -> usize {
+        match self
+            .lookup_tables
+            .iter()
+            .position(|table| table[..] == *entries)
+        {
+            Some(table_id) => table_id,
+            None => self.add_lookup_map(entries),
+        }
+    }
+
+    /// Given two variables `var0` and `var1` and a boolean variable `bit`, return var_bit.
+    /// var_bit = (1-bit) * var0 + bit * var1 = - bit * var0 + bit * var1 + var0
+    /// Wires: (w1, w2, w3 , w4) = (bit, var0, bit, var1)
+    /// Selectors: q2 = qm2 = qo = 1, qm1 = -1
+    pub fn select(&mut self, var0: VarIndex, var1: VarIndex, bit: VarIndex) -> VarIndex {
+        assert!(var0 < self.num_vars, "var0 index out of bound");
+        assert!(var1 < self.num_vars, "var1 index out of bound");
+        assert!(bit < self.num_vars, "bit var index out of bound");
+        let zero = F::zero();
+        let one = F::one();
+        self.push_add_selectors(zero, one, zero, zero);
+        self.push_mul_selectors(one.neg(), one);
+        self.push_constant_selector(zero);
+        self.push_ecc_selector(zero);
+        self.push_rescue_selectors(zero, zero, zero, zero);
+        self.push_out_selector(one);
+        let out = if self.witness[bit] == zero {
+            self.witness[var0]
+        } else {
+            self.witness[var1]
+        };
+        let out_var = self.new_variable(out);
+        self.wiring[0].push(bit);
+        self.wiring[1].push(var0);
+        self.wiring[2].push(bit);
+        self.wiring[3].push(var1);
+        self.wiring[4].push(out_var);
+        self.size += 1;
+        out_var
+    }
+
+    /// Given 2^k variables `vars` and k boolean variables `index_bits`, the little endian binary
+    /// representation of an index i (e.g. the output of `range_check`), return vars[i].
+    /// The output is computed by a tree of `select` gates, 2^k - 1 gates in total: the level l of
+    /// the tree selects with index_bits[l] between the outputs of the previous level.
+    /// The index bits are not boolean constrained by this gadget.
+    pub fn select_from(
+        &mut self,
+        vars: &[VarIndex],
+        index_bits: &[VarIndex],
+    ) -> VarIndex {
+        assert!(
+            index_bits.len() < usize::BITS as usize
+                && vars.len() == 1 << index_bits.len(),
+            "the number of variables is not 2^(number of index bits)"
+        );
+        let mut level = vars.to_vec();
+        for bit in index_bits.iter() {
+            level = level
+                .chunks(2)
+                .map(|pair| self.select(pair[0], pair[1], *bit))
+                .collect();
+        }
+        level[0]
+    }
+
+    /// Given two boolean variables `left_var` and `right_var`, return left_var XOR right_var.
+    /// xor = left_var + right_var - 2 * left_var * right_var
+    /// Wires: (w1, w2, w3, w4) = (left_var, right_var, 0, 0)
+    /// Selectors: q1 = q2 = qo = 1, qm1 = -2
+    /// The inputs are not boolean constrained by this gate.
+    pub fn xor(&mut self, left_var: VarIndex, right_var: VarIndex) -> VarIndex {
+        assert!(left_var < self.num_vars, "left_var index out of bound");
+        assert!(right_var < self.num_vars, "right_var index out of bound");
+        let zero = F::zero();
+        let one = F::one();
+        let two = one.add(&one);
+        self.push_add_selectors(one, one, zero, zero);
+        self.push_mul_selectors(two.neg(), zero);
+        self.push_constant_selector(zero);
+        self.push_ecc_selector(zero);
+        self.push_rescue_selectors(zero, zero, zero, zero);
+        self.push_out_selector(one);
+        let left = self.witness[left_var];
+        let right = self.witness[right_var];
+        let out = left.add(&right).sub(&two.mul(&left.mul(&right)));
+        let out_var = self.new_variable(out);
+        self.wiring[0].push(left_var);
+        self.wiring[1].push(right_var);
+        self.wiring[2].push(0);
+        self.wiring[3].push(0);
+        self.wiring[4].push(out_var);
         self.size += 1;
         out_var
     }
 
+    /// Given two boolean variables `left_var` and `right_var`, return left_var AND right_var,
+    /// i.e. their product. The inputs are not boolean constrained by this gate.
+    pub fn and(&mut self, left_var: VarIndex, right_var: VarIndex) -> VarIndex {
+        self.mul(left_var, right_var)
+    }
+
+    /// Bitwise XOR of two words given as vectors of boolean variables of the same length
+    /// (e.g. the output of `range_check`), one gate per bit.
+    pub fn xor_word(
+        &mut self,
+        left_bits: &[VarIndex],
+        right_bits: &[VarIndex],
+    ) -> Vec<VarIndex> {
+        assert_eq!(
+            left_bits.len(),
+            right_bits.len(),
+            "words of different lengths"
+        );
+        left_bits
+            .iter()
+            .zip(right_bits.iter())
+            .map(|(left, right)| self.xor(*left, *right))
+            .collect()
+    }
+
+    /// Bitwise AND of two words given as vectors of boolean variables of the same length
+    /// (e.g. the output of `range_check`), one gate per bit.
+    pub fn and_word(
+        &mut self,
+        left_bits: &[VarIndex],
+        right_bits: &[VarIndex],
+    ) -> Vec<VarIndex> {
+        assert_eq!(
+            left_bits.len(),
+            right_bits.len(),
+            "words of different lengths"
+        );
+        left_bits
+            .iter()
+            .zip(right_bits.iter())
+            .map(|(left, right)| self.and(*left, *right))
+            .collect()
+    }
+
+    /// Returns a boolean variable that equals 1 if and only if `witness[left_var]` >=
+    /// `witness[right_var]`, for values in [0, 2^n_bits):
+    /// 1. diff = left_var - right_var + 2^n_bits is in (0, 2^(n_bits + 1)).
+    /// 2. Range check diff to n_bits + 1 bits, the most significant bit is 1 if and only if
+    /// left_var >= right_var.
+    /// The inputs are not range constrained by this gadget: the caller has to range check them
+    /// to `n_bits` (e.g. balances are range checked in transfers), otherwise the output is
+    /// meaningless.
+    pub fn greater_eq(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        n_bits: usize,
+    ) -> VarIndex {
+        assert!(n_bits >= 1, "the number of bits is zero");
+        let one = F::one();
+        let one_var = self.one_var();
+        let two_pow_n = F::from_u32(2).pow_u64(n_bits as u64);
+        let diff = self.linear_combine(
+            &[left_var, right_var, one_var, 0],
+            one,
+            one.neg(),
+            two_pow_n,
+            F::zero(),
+        );
+        let bits = self.range_check(diff, n_bits + 1);
+        bits[n_bits]
+    }
+
+    /// Returns a boolean variable that equals 1 if and only if `witness[left_var]` <
+    /// `witness[right_var]`, for values in [0, 2^n_bits) (see greater_eq).
+    pub fn less_than(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        n_bits: usize,
+    ) -> VarIndex {
+        let greater_eq = self.greater_eq(left_var, right_var, n_bits);
+        let one_var = self.one_var();
+        self.sub(one_var, greater_eq)
+    }
+
+    /// Returns a variable `inv_var` with the inverse of `witness[var]`.
+    /// With DivisionByZeroPolicy::Unsatisfiable, a multiplication gate enforces
+    /// var * inv_var = 1 and the flag is None.
+    /// With DivisionByZeroPolicy::Flag, the flag is Some(is_zero) with is_zero = 1 - var * inv_var,
+    /// and the gates var * is_zero = 0 and inv_var * is_zero = 0 enforce that is_zero = 1 and
+    /// inv_var = 0 if var = 0, and that var * inv_var = 1 otherwise.
+    pub fn inv(
+        &mut self,
+        var: VarIndex,
+        policy: DivisionByZeroPolicy,
+    ) -> (VarIndex, Option<VarIndex>) {
+        assert!(var < self.num_vars, "var index out of bound");
+        let inv = self.witness[var].inv().unwrap_or_else(|_| F::zero());
+        let inv_var = self.new_variable(inv);
+        let one_var = self.one_var();
+        match policy {
+            DivisionByZeroPolicy::Unsatisfiable => {
+                self.insert_mul_gate(var, inv_var, one_var);
+                (inv_var, None)
+            }
+            DivisionByZeroPolicy::Flag => {
+                let is_not_zero = self.mul(var, inv_var);
+                let is_zero = self.sub(one_var, is_not_zero);
+                let zero_var = self.zero_var();
+                self.insert_mul_gate(var, is_zero, zero_var);
+                self.insert_mul_gate(inv_var, is_zero, zero_var);
+                (inv_var, Some(is_zero))
+            }
+        }
+    }
+
+    /// Returns a variable `q_var` with witness[left_var] / witness[right_var], i.e.
+    /// q_var = left_var * inv_var with inv_var the inverse of right_var, which enforces
+    /// right_var * q_var = left_var. The case right_var = 0 is handled according to `policy`
+    /// as in `inv`: the constraints are unsatisfiable, or q_var = 0 and the returned flag is 1.
+    pub fn div(
+        &mut self,
+        left_var: VarIndex,
+        right_var: VarIndex,
+        policy: DivisionByZeroPolicy,
+    ) -> (VarIndex, Option<VarIndex>) {
+        assert!(left_var < self.num_vars, "left_var index out of bound");
+        let (inv_var, is_zero) = self.inv(right_var, policy);
+        (self.mul(left_var, inv_var), is_zero)
+    }
+
     // Returns a boolean variable that equals 1 if and only if `left_var` == `right_var`
     pub fn is_equal(&mut self, left_var: VarIndex, right_var: VarIndex) -> VarIndex {
         let (is_equal, _) = self.is_equal_or_not_equal(left_var, right_var);
@@ -530,7 +1297,17 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
 
     /// Insert a constant constraint: wo = constant
     pub fn insert_constant_gate(&mut self, var: VarIndex, constant: F) {
-        assert!(var < self.num_vars, "variable index out of bound");
+        pnk!(self.try_insert_constant_gate(var, constant))
+    }
+
+    /// Same as insert_constant_gate, returning WireIndexOutOfBounds instead of panicking if
+    /// `var` is not in the constraint system.
+    pub fn try_insert_constant_gate(
+        &mut self,
+        var: VarIndex,
+        constant: F,
+    ) -> Result<()> {
+        check_var_indices(&[var], self.num_vars).c(d!())?;
         let zero = F::zero();
         self.push_add_selectors(zero, zero, zero, zero);
         self.push_mul_selectors(zero, zero);
@@ -542,6 +1319,7 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
             self.wiring[i].push(var);
         }
         self.size += 1;
+        Ok(())
     }
 
     /// Insert constraint of a public IO value to be decided online.
@@ -551,17 +1329,163 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
         self.insert_constant_gate(var, F::zero());
     }
 
-    /// Pad the number of constraints to a power of two.
-    pub fn pad(&mut self) {
-        let n = self.size.next_power_of_two();
-        let diff = n - self.size();
-        for selector in self.selectors.iter_mut() {
-            selector.extend(vec![F::zero(); diff]);
+    /// Register a lookup table with entries `values` and return its id. Then `lookup(var, id)`
+    /// asserts that the value of `var` is in `values`, e.g. a range check against 0..2^k with
+    /// a single gate. The tables are part of the circuit: `pad` pads the number of constraints
+    /// to at least the total number of entries of the tables.
+    pub fn add_lookup_table(&mut self, values: &[F]) -> usize {
+        let entries: Vec<(F, F)> =
+            values.iter().map(|value| (*value, F::zero())).collect();
+        self.add_lookup_map(&entries)
+    }
+
+    /// Register a lookup table that maps the inputs to the outputs of `entries`, e.g. an S-box,
+    /// and return its id. Then `lookup_output(var, id)` returns the output for the value of `var`.
+    pub fn add_lookup_map(&mut self, entries: &[(F, F)]) -> usize {
+        assert!(!entries.is_empty(), "empty lookup table");
+        self.lookup_tables.push(entries.to_vec());
+        self.lookup_tables.len() - 1
+    }
+
+    /// Insert a lookup gate: (witness[in_var], witness[out_var]) is an entry of the table
+    /// `table_id`. The wires are (w1, w2) = (in_var, out_var) and all the selectors are zero.
+    pub fn insert_lookup_gate(
+        &mut self,
+        in_var: VarIndex,
+        out_var: VarIndex,
+        table_id: usize,
+    ) {
+        pnk!(self.try_insert_lookup_gate(in_var, out_var, table_id))
+    }
+
+    /// Same as insert_lookup_gate, returning WireIndexOutOfBounds or LookupTableNotFound
+    /// instead of panicking if a variable is not in the constraint system or if the table is
+    /// not registered.
+    pub fn try_insert_lookup_gate(
+        &mut self,
+        in_var: VarIndex,
+        out_var: VarIndex,
+        table_id: usize,
+    ) -> Result<()> {
+        check_var_indices(&[in_var, out_var], self.num_vars).c(d!())?;
+        if table_id >= self.lookup_tables.len() {
+            return Err(eg!(PlonkError::LookupTableNotFound(
+                table_id,
+                self.lookup_tables.len()
+            )));
         }
-        for wire in self.wiring.iter_mut() {
-            wire.extend(vec![0; diff]);
+        let zero = F::zero();
+        self.push_add_selectors(zero, zero, zero, zero);
+        self.push_mul_selectors(zero, zero);
+        self.push_constant_selector(zero);
+        self.push_ecc_selector(zero);
+        self.push_rescue_selectors(zero, zero, zero, zero);
+        self.push_out_selector(zero);
+        self.wiring[0].push(in_var);
+        self.wiring[1].push(out_var);
+        self.wiring[2].push(0);
+        self.wiring[3].push(0);
+        self.wiring[4].push(0);
+        self.lookup_gates.push((self.size, table_id));
+        self.size += 1;
+        Ok(())
+    }
+
+    /// Assert that the value of `var` is in the lookup table `table_id` (see add_lookup_table).
+    pub fn lookup(&mut self, var: VarIndex, table_id: usize) {
+        let zero_var = self.zero_var();
+        self.insert_lookup_gate(var, zero_var, table_id);
+    }
+
+    /// Create an output variable with the output of the lookup table `table_id` for the value of
+    /// `var` (see add_lookup_map). If the value is not an input of the table, the output is zero
+    /// and the witness does not satisfy the circuit.
+    pub fn lookup_output(&mut self, var: VarIndex, table_id: usize) -> VarIndex {
+        assert!(var < self.num_vars, "var index out of bound");
+        assert!(table_id < self.lookup_tables.len(), "table id out of bound");
+        let output = self.lookup_tables[table_id]
+            .iter()
+            .find(|(input, _)| *input == self.witness[var])
+            .map(|(_, output)| *output)
+            .unwrap_or_else(F::zero);
+        let out_var = self.new_variable(output);
+        self.insert_lookup_gate(var, out_var, table_id);
+        out_var
+    }
+
+    /// Append the gates, the variables and the public inputs of `other` after the ones of this
+    /// constraint system, and return the offset of the variables of `other`: the variable
+    /// `var` of `other` is the variable `var + offset` of the result. The public inputs of
+    /// `other` follow the public inputs of this constraint system, the lookup tables of `other`
+    /// are registered unless an identical table exists, and the reserved zero and one variables
+    /// of `other` are replaced by the ones of this constraint system. The witness of `other` is
+    /// appended to the witness, so the constraint systems are appended before clearing their
+    /// witnesses, and before padding.
+    pub fn append(&mut self, other: &TurboPlonkConstraintSystem<F>) -> VarIndex {
+        let var_offset = self.num_vars;
+        let cs_offset = self.size;
+        let zero_var = match (self.zero_var, other.zero_var) {
+            (Some(var), _) => Some(var),
+            (None, Some(other_var)) => Some(other_var + var_offset),
+            (None, None) => None,
+        };
+        let one_var = match (self.one_var, other.one_var) {
+            (Some(var), _) => Some(var),
+            (None, Some(other_var)) => Some(other_var + var_offset),
+            (None, None) => None,
+        };
+        let map_var = |var: VarIndex| {
+            if other.zero_var == Some(var) {
+                zero_var.unwrap() // safe unwrap
+            } else if other.one_var == Some(var) {
+                one_var.unwrap() // safe unwrap
+            } else {
+                var + var_offset
+            }
+        };
+
+        for (selector, other_selector) in
+            self.selectors.iter_mut().zip(other.selectors.iter())
+        {
+            selector.extend_from_slice(other_selector);
         }
-        self.size += diff;
+        for (wire, other_wire) in self.wiring.iter_mut().zip(other.wiring.iter()) {
+            wire.extend(other_wire.iter().map(|var| map_var(*var)));
+        }
+        self.public_vars_witness_indices.extend(
+            other
+                .public_vars_witness_indices
+                .iter()
+                .map(|var| map_var(*var)),
+        );
+        self.public_vars_constraint_indices.extend(
+            other
+                .public_vars_constraint_indices
+                .iter()
+                .map(|cs_index| cs_index + cs_offset),
+        );
+        let table_ids: Vec<usize> = other
+            .lookup_tables
+            .iter()
+            .map(|entries| {
+                match self.lookup_tables.iter().position(|table| table == entries) {
+                    Some(table_id) => table_id,
+                    None => self.add_lookup_map(entries),
+                }
+            })
+            .collect();
+        self.lookup_gates.extend(
+            other.lookup_gates.iter().map(|(cs_index, table_id)| {
+                (cs_index + cs_offset, table_ids[*table_id])
+            }),
+        );
+        self.witness.extend_from_slice(&other.witness);
+        self.append_namespaces(other);
+        self.zero_var = zero_var;
+        self.one_var = one_var;
+        self.num_vars += other.num_vars;
+        self.size += other.size;
+        var_offset
     }
 
     fn push_add_selectors(&mut self, q1: F, q2: F, q3: F, q4: F) {
@@ -599,82 +1523,22 @@ impl<F: Scalar> TurboPlonkConstraintSystem<F> {
 
     fn push_out_selector(&mut self, q_out: F) {
         self.selectors[12].push(q_out);
+        #[cfg(feature = "debug_gates")]
+        self.record_gate_label();
     }
+}
 
-    fn get_witness_index(&self, wire_index: usize, cs_index: CsIndex) -> VarIndex {
-        assert!(wire_index < N_WIRES_PER_GATE, "wire index out of bound");
-        assert!(cs_index < self.size, "constraint index out of bound");
-        self.wiring[wire_index][cs_index]
-    }
-
-    pub fn verify_witness(&self, witness: &[F], online_vars: &[F]) -> Result<()> {
-        if witness.len() != self.num_vars {
-            return Err(eg!(format!(
-                "witness len = {}, num_vars = {}",
-                witness.len(),
-                self.num_vars
-            )));
-        }
-        if online_vars.len() != self.public_vars_witness_indices.len()
-            || online_vars.len() != self.public_vars_constraint_indices.len()
-        {
-            return Err(eg!("wrong number of online variables"));
-        }
-        for cs_index in 0..self.size() {
-            let mut public_online = F::zero();
-            // check if the constraint constrains a public variable
-            // search constraint index in online vars
-            for ((c_i, w_i), online_var) in self
-                .public_vars_constraint_indices
-                .iter()
-                .zip(self.public_vars_witness_indices.iter())
-                .zip(online_vars.iter())
-            {
-                if *c_i == cs_index {
-                    // found
-                    public_online = *online_var;
-                    if witness[*w_i] != *online_var {
-                        return Err(eg!(format!(
-                            "cs index {}: online var {:?} does not match witness {:?}",
-                            cs_index, *online_var, witness[*w_i]
-                        )));
-                    }
-                }
-            }
-            let w1_value = &witness[self.get_witness_index(0, cs_index)];
-            let w2_value = &witness[self.get_witness_index(1, cs_index)];
-            let w3_value = &witness[self.get_witness_index(2, cs_index)];
-            let w4_value = &witness[self.get_witness_index(3, cs_index)];
-            let w_out_value = &witness[self.get_witness_index(4, cs_index)];
-            let wire_vals = vec![w1_value, w2_value, w3_value, w4_value, w_out_value];
-            let sel_vals: Vec<&F> = (0..self.num_selectors())
-                .map(|i| &self.selectors[i][cs_index])
-                .collect();
-            let eval_gate = self
-                .eval_gate_func(&wire_vals, &sel_vals, &public_online)
-                .c(d!("wrong func params for eval_gate_func()"))?;
-            if eval_gate != F::zero() {
-                return Err(eg!(format!(
-                    "cs index {}: wire_vals = ({:?}), sel_vals = ({:?})",
-                    cs_index, wire_vals, sel_vals
-                )));
-            }
-        }
-        Ok(())
-    }
-
-    /// Extract and clear the entire witness of the circuit. The witness consists of
-    /// secret inputs, public inputs, and the values of intermediate variables.
-    pub fn get_and_clear_witness(&mut self) -> Vec<F> {
-        let res = self.witness.clone();
-        self.witness.clear();
-        res
+impl<F: Scalar, const W: usize, const S: usize> Drop for TurboConstraintSystem<F, W, S> {
+    fn drop(&mut self) {
+        zeroize_witness(&mut self.witness);
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use crate::plonk::turbo_plonk_cs::{
+        zeroize_witness, DivisionByZeroPolicy, TurboPlonkConstraintSystem, VarIndex,
+    };
     use algebra::bls12_381::BLSScalar;
     use algebra::groups::{Scalar, ScalarArithmetic};
     use ruc::*;
@@ -710,6 +1574,70 @@ mod test {
             .is_err());
     }
 
+    #[test]
+    fn test_select_from() {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let vars: Vec<usize> = (0..8)
+            .map(|i| cs.new_variable(F::from_u32(10 + i)))
+            .collect();
+        let index_var = cs.new_variable(F::from_u32(5));
+        let index_bits = cs.range_check(index_var, 3);
+        let out = cs.select_from(&vars, &index_bits);
+        assert_eq!(cs.witness[out], F::from_u32(15));
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[out] = F::from_u32(14);
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        // a single variable
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let var = cs.new_variable(F::from_u32(1));
+        assert_eq!(cs.select_from(&[var], &[]), var);
+    }
+
+    #[test]
+    fn test_inv_and_div() {
+        let zero = F::from_u32(0);
+        let one = F::from_u32(1);
+        let six = F::from_u32(6);
+        let three = F::from_u32(3);
+
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let six_var = cs.new_variable(six);
+        let three_var = cs.new_variable(three);
+        let (q, flag) = cs.div(six_var, three_var, DivisionByZeroPolicy::Unsatisfiable);
+        assert!(flag.is_none());
+        assert_eq!(cs.witness[q], F::from_u32(2));
+        let (inv, flag) = cs.inv(three_var, DivisionByZeroPolicy::Flag);
+        let flag = flag.unwrap();
+        assert_eq!(cs.witness[inv].mul(&three), one);
+        assert_eq!(cs.witness[flag], zero);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[flag] = one;
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        // division by zero
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let six_var = cs.new_variable(six);
+        let zero_var = cs.new_variable(zero);
+        let (q, flag) = cs.div(six_var, zero_var, DivisionByZeroPolicy::Flag);
+        let flag = flag.unwrap();
+        assert_eq!(cs.witness[q], zero);
+        assert_eq!(cs.witness[flag], one);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[q] = six;
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let six_var = cs.new_variable(six);
+        let zero_var = cs.new_variable(zero);
+        let _ = cs.div(six_var, zero_var, DivisionByZeroPolicy::Unsatisfiable);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
     #[test]
     fn test_sub_and_equal() {
         let mut cs = TurboPlonkConstraintSystem::new();
@@ -733,6 +1661,61 @@ mod test {
             .is_err());
     }
 
+    #[test]
+    fn test_try_insert_gates() {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        cs.add_variables(&[F::from_u32(2), F::from_u32(3), F::from_u32(6)]);
+        pnk!(cs.try_insert_mul_gate(0, 1, 2));
+        let size = cs.size;
+        // the gates on variables that are not in the constraint system are rejected, and the
+        // errors carry the index of the variable
+        let err = cs.try_insert_mul_gate(0, 7, 2).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("WireIndexOutOfBounds: variable 7 of 3"));
+        assert!(cs.try_insert_add_gate(3, 1, 2).is_err());
+        let one = F::from_u32(1);
+        assert!(cs
+            .try_insert_lc_gate(&[0, 1, 2, 5], 2, one, one, one, one)
+            .is_err());
+        assert!(cs.try_insert_constant_gate(4, one).is_err());
+        assert!(cs.try_insert_boolean_gate(3).is_err());
+        assert!(cs.try_insert_boolean_gates(&[0, 1, 3]).is_err());
+        assert!(cs.lookup_tables.is_empty());
+        let err = cs.try_insert_lookup_gate(0, 1, 0).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("LookupTableNotFound: table 0 of 0"));
+        assert_eq!(cs.size, size);
+        assert!(cs.wiring.iter().all(|wire| wire.len() == size));
+
+        let witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+    }
+
+    #[test]
+    fn test_with_witness() {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let one = F::from_u32(1);
+        let two = cs.new_variable(one.add(&one));
+        let three = cs.new_variable(two.add(&one));
+        let six = cs.mul(two, three);
+        cs.insert_constant_gate(six, F::from_u32(6));
+        let product = cs.with_witness(|witness| {
+            assert_eq!(witness.len(), 3);
+            witness[two].mul(&witness[three])
+        });
+        assert_eq!(product, F::from_u32(6));
+        assert!(cs.witness.is_empty());
+
+        let mut witness = vec![one; 4];
+        zeroize_witness(&mut witness);
+        assert!(witness.is_empty());
+        cs.new_variable(one);
+        cs.clear_witness();
+        assert!(cs.witness.is_empty());
+    }
+
     #[test]
     fn test_is_equal() {
         let mut cs = TurboPlonkConstraintSystem::new();
@@ -754,6 +1737,204 @@ mod test {
         assert!(cs.verify_witness(&witness, &[]).is_err());
     }
 
+    #[test]
+    fn test_xor_and() {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let zero = F::from_u32(0);
+        let one = F::from_u32(1);
+        let bits: Vec<usize> = [zero, zero, one, one, zero, one, zero, one]
+            .iter()
+            .map(|bit| cs.new_variable(*bit))
+            .collect();
+        for (i, j) in [(0, 4), (1, 5), (2, 6), (3, 7)] {
+            let xor = cs.xor(bits[i], bits[j]);
+            let and = cs.and(bits[i], bits[j]);
+            let left = cs.witness[bits[i]];
+            let right = cs.witness[bits[j]];
+            assert_eq!(cs.witness[xor], if left == right { zero } else { one });
+            assert_eq!(cs.witness[and], left.mul(&right));
+        }
+
+        // 12 = 0b1100 and 10 = 0b1010
+        let left_var = cs.new_variable(F::from_u32(12));
+        let right_var = cs.new_variable(F::from_u32(10));
+        let left_bits = cs.range_check(left_var, 4);
+        let right_bits = cs.range_check(right_var, 4);
+        let xor_bits = cs.xor_word(&left_bits, &right_bits);
+        let and_bits = cs.and_word(&left_bits, &right_bits);
+        let xor_values: Vec<F> = xor_bits.iter().map(|bit| cs.witness[*bit]).collect();
+        let and_values: Vec<F> = and_bits.iter().map(|bit| cs.witness[*bit]).collect();
+        assert_eq!(xor_values, vec![zero, one, one, zero]);
+        assert_eq!(and_values, vec![zero, zero, zero, one]);
+
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[xor_bits[0]] = one;
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+        witness[xor_bits[0]] = zero;
+        witness[and_bits[3]] = zero;
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
+    #[test]
+    fn test_less_than() {
+        let zero = F::from_u32(0);
+        let one = F::from_u32(1);
+        for (left, right, expected) in
+            [(3, 5, one), (5, 3, zero), (4, 4, zero), (0, 15, one)]
+        {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let left_var = cs.new_variable(F::from_u32(left));
+            let right_var = cs.new_variable(F::from_u32(right));
+            let less_than = cs.less_than(left_var, right_var, 4);
+            let greater_eq = cs.greater_eq(left_var, right_var, 4);
+            assert_eq!(cs.witness[less_than], expected);
+            assert_eq!(cs.witness[greater_eq], one.sub(&expected));
+            let mut witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness, &[]));
+            witness[less_than] = one.sub(&expected);
+            assert!(cs.verify_witness(&witness, &[]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let values: Vec<F> = (0..4).map(F::from_u32).collect();
+        let range_id = cs.add_lookup_table(&values);
+        let square_id = cs.add_lookup_map(
+            &values
+                .iter()
+                .map(|x| (*x, x.mul(x)))
+                .collect::<Vec<(F, F)>>(),
+        );
+        let x = cs.new_variable(F::from_u32(3));
+        cs.lookup(x, range_id);
+        let x_square = cs.lookup_output(x, square_id);
+        assert_eq!(cs.witness[x_square], F::from_u32(9));
+        cs.pad();
+        assert_eq!(cs.size, 8);
+
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[x_square] = F::from_u32(3);
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+        witness[x_square] = F::from_u32(16);
+        witness[x] = F::from_u32(4);
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
+    #[test]
+    fn test_insert_boolean_gates() {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let bits: Vec<VarIndex> = [1u32, 0, 1, 1, 0]
+            .iter()
+            .map(|bit| cs.new_variable(F::from_u32(*bit)))
+            .collect();
+        cs.insert_boolean_gates(&bits);
+        cs.insert_boolean_gates(&bits[..2]);
+        // the pairs share one table
+        assert_eq!(cs.lookup_tables.len(), 1);
+        assert_eq!(cs.lookup_gates.len(), 3);
+        assert_eq!(cs.size, 4);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        for var in [bits[1], bits[4]] {
+            witness[var] = F::from_u32(2);
+            assert!(cs.verify_witness(&witness, &[]).is_err());
+            witness[var] = F::zero();
+        }
+    }
+
+    #[test]
+    fn test_range_check_chunks() {
+        for (value, n_bits) in
+            [(5u64, 3), (13, 4), (200, 8), (1 << 20, 21), (u64::MAX, 64)]
+        {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let var = cs.new_variable(F::from_u64(value));
+            let chunks = cs.range_check_chunks(var, n_bits);
+            assert_eq!(chunks.len(), (n_bits - 1) / 4 + 1);
+            let recomposed = chunks.iter().rev().fold(0u64, |acc, chunk| {
+                let bytes = cs.witness[*chunk].to_bytes();
+                (acc << 4) + bytes[0] as u64
+            });
+            assert_eq!(recomposed, value);
+            let witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness, &[]));
+        }
+
+        // 64 bits with 16 lookup gates and 5 linear combination gates
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let var = cs.new_variable(F::from_u64(12345));
+        let _ = cs.range_check_chunks(var, 64);
+        assert_eq!(cs.size, 21);
+        assert_eq!(cs.lookup_tables.len(), 1);
+
+        // the value is out of range
+        for (value, n_bits) in [(8u64, 3), (16, 4), (1 << 21, 21)] {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let var = cs.new_variable(F::from_u64(value));
+            let _ = cs.range_check_chunks(var, n_bits);
+            let witness = cs.get_and_clear_witness();
+            assert!(cs.verify_witness(&witness, &[]).is_err());
+        }
+
+        // a chunk out of range
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let var = cs.new_variable(F::from_u32(0x35));
+        let chunks = cs.range_check_chunks(var, 8);
+        let mut witness = cs.get_and_clear_witness();
+        witness[chunks[0]] = F::from_u32(0x15);
+        witness[chunks[1]] = F::from_u32(2);
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
+    #[test]
+    fn test_append() {
+        fn section(cs: &mut TurboPlonkConstraintSystem<F>, value: u32) -> VarIndex {
+            let a = cs.new_variable(F::from_u32(value));
+            let zero_var = cs.zero_var();
+            let b = cs.add(a, zero_var);
+            let _ = cs.range_check_chunks(b, 8);
+            let c = cs.mul(a, b);
+            cs.prepare_io_variable(c);
+            c
+        }
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let c1 = section(&mut cs, 3);
+        let (n_gates, n_vars) = (cs.size, cs.num_vars);
+        let mut other = TurboPlonkConstraintSystem::new();
+        let c2 = section(&mut other, 5);
+        let mut expected_cs = TurboPlonkConstraintSystem::new();
+        let _ = section(&mut expected_cs, 3);
+        let _ = section(&mut expected_cs, 5);
+
+        let offset = cs.append(&other);
+        assert_eq!(offset, n_vars);
+        assert_eq!(cs.num_vars, 2 * n_vars);
+        assert_eq!(cs.size, expected_cs.size);
+        assert_eq!(cs.selectors, expected_cs.selectors);
+        // the sections share the range table
+        assert_eq!(cs.lookup_tables, expected_cs.lookup_tables);
+        assert_eq!(cs.lookup_gates, expected_cs.lookup_gates);
+        assert_eq!(
+            cs.public_vars_constraint_indices,
+            expected_cs.public_vars_constraint_indices
+        );
+        assert_eq!(cs.public_vars_witness_indices, vec![c1, c2 + offset]);
+        // the addition of the second section uses the zero variable of the first one
+        assert_eq!(Some(cs.wiring[1][n_gates]), cs.zero_var);
+
+        let witness = cs.get_and_clear_witness();
+        assert_eq!(witness[c2 + offset], F::from_u32(25));
+        let online_vars = [F::from_u32(9), F::from_u32(25)];
+        pnk!(cs.verify_witness(&witness, &online_vars));
+        assert!(cs
+            .verify_witness(&witness, &[F::from_u32(25), F::from_u32(9)])
+            .is_err());
+    }
+
     #[test]
     fn test_turbo_plonk_circuit_1() {
         let mut cs = TurboPlonkConstraintSystem::new();
@@ -929,26 +2110,141 @@ mod test {
 
 #[cfg(test)]
 mod turbo_plonk_proofs_test {
+    use crate::commitments::fri_poly_com::FRICommitmentScheme;
+    use crate::commitments::ipa_poly_com::IPACommitmentSchemeBLS;
     use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
     use crate::commitments::pcs::PolyComScheme;
-    use crate::plonk::plonk_setup::preprocess_prover;
-    use crate::plonk::protocol::prover::{prover, verifier};
+    use crate::plonk::errors::PlonkError;
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_prover_with_coset_shifts, preprocess_verifier,
+        preprocess_verifier_with_coset_shifts, ConstraintSystem, CosetShifts,
+        QuotEvalDomainStrategy,
+    };
+    use crate::plonk::protocol::prover::{prover, verifier, PlonkPf};
     use crate::plonk::turbo_plonk_cs::rescue::State;
-    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use crate::plonk::turbo_plonk_cs::{
+        CsIndex, TurboConstraintSystem, TurboPlonkConstraintSystem, VarIndex,
+        TURBO_QUOT_EVAL_DOM_STRATEGY,
+    };
     use algebra::bls12_381::BLSScalar;
     use algebra::groups::{Group, One, Scalar, ScalarArithmetic, Zero};
     use algebra::jubjub::JubjubPoint;
     use merlin::Transcript;
     use rand_chacha::ChaChaRng;
     use rand_core::{CryptoRng, RngCore, SeedableRng};
+    use ruc::*;
     use std::str::FromStr;
 
+    // A layout with the 3 wires and the 5 selectors of standard PLONK
+    type StandardLayoutCS<F> = TurboConstraintSystem<F, 3, 5>;
+
+    impl<F: Scalar> ConstraintSystem for StandardLayoutCS<F> {
+        type Field = F;
+
+        fn size(&self) -> usize {
+            self.size
+        }
+
+        fn num_vars(&self) -> usize {
+            self.num_vars
+        }
+
+        fn wiring(&self) -> &[Vec<usize>] {
+            &self.wiring[..]
+        }
+
+        fn quot_eval_dom_size(&self) -> usize {
+            if self.size > 4 {
+                self.size * 4
+            } else {
+                self.size * 8
+            }
+        }
+
+        fn n_wires_per_gate(&self) -> usize {
+            3
+        }
+
+        fn num_selectors(&self) -> usize {
+            5
+        }
+
+        fn public_vars_constraint_indices(&self) -> &[CsIndex] {
+            &self.public_vars_constraint_indices
+        }
+
+        fn public_vars_witness_indices(&self) -> &[VarIndex] {
+            &self.public_vars_witness_indices
+        }
+
+        fn selector(&self, index: usize) -> Result<&[F]> {
+            if index >= self.selectors.len() {
+                return Err(eg!(PlonkError::FuncParamsError));
+            }
+            Ok(&self.selectors[index])
+        }
+
+        // q1*w1 + q2*w2 + qm*w1*w2 + qc + PI - qo*wo = 0
+        fn eval_gate_func(
+            &self,
+            wire_vals: &[&F],
+            sel_vals: &[&F],
+            pub_input: &F,
+        ) -> Result<F> {
+            if wire_vals.len() != 3 || sel_vals.len() != 5 {
+                return Err(eg!(PlonkError::FuncParamsError));
+            }
+            let left = sel_vals[0].mul(wire_vals[0]);
+            let right = sel_vals[1].mul(wire_vals[1]);
+            let mul = sel_vals[2].mul(&wire_vals[0].mul(wire_vals[1]));
+            let constant = sel_vals[3].add(pub_input);
+            let out = sel_vals[4].mul(wire_vals[2]);
+            Ok(left.add(&right).add(&mul).add(&constant).sub(&out))
+        }
+
+        fn eval_selector_multipliers(&self, wire_vals: &[&F]) -> Result<Vec<F>> {
+            if wire_vals.len() < 3 {
+                return Err(eg!(PlonkError::FuncParamsError));
+            }
+            Ok(vec![
+                *wire_vals[0],
+                *wire_vals[1],
+                wire_vals[0].mul(wire_vals[1]),
+                F::one(),
+                wire_vals[2].neg(),
+            ])
+        }
+    }
+
     #[test]
     fn test_turbo_plonk_kzg() {
         let mut prng = ChaChaRng::from_seed([1u8; 32]);
         let pcs = KZGCommitmentScheme::new(20, &mut prng);
         test_turbo_plonk_with_constant_and_online_values(&pcs, &mut prng);
         test_turbo_plonk_arithmetic_gates(&pcs, &mut prng);
+        test_turbo_plonk_lookup_gates(&pcs, &mut prng);
+        test_turbo_plonk_blinding_gates(&pcs, &mut prng);
+    }
+
+    #[test]
+    fn test_turbo_plonk_ipa() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = IPACommitmentSchemeBLS::new(20);
+        test_turbo_plonk_with_constant_and_online_values(&pcs, &mut prng);
+        test_turbo_plonk_arithmetic_gates(&pcs, &mut prng);
+        test_turbo_plonk_lookup_gates(&pcs, &mut prng);
+        test_turbo_plonk_blinding_gates(&pcs, &mut prng);
+    }
+
+    #[test]
+    fn test_turbo_plonk_fri() {
+        // the proofs with FRI are sound but not zero knowledge, see fri_poly_com.rs
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = FRICommitmentScheme::<BLSScalar>::new(20, 2, 16, 4).unwrap();
+        test_turbo_plonk_with_constant_and_online_values(&pcs, &mut prng);
+        test_turbo_plonk_arithmetic_gates(&pcs, &mut prng);
+        test_turbo_plonk_lookup_gates(&pcs, &mut prng);
+        test_turbo_plonk_blinding_gates(&pcs, &mut prng);
     }
 
     #[test]
@@ -957,6 +2253,128 @@ mod turbo_plonk_proofs_test {
         let pcs = KZGCommitmentScheme::new(260, &mut prng);
         test_turbo_plonk_ecc_gates(&pcs, &mut prng);
         test_turbo_plonk_rescue_gates(&pcs, &mut prng);
+        test_turbo_plonk_range_chunk_gates(&pcs, &mut prng);
+    }
+
+    #[test]
+    fn test_custom_gate_layout() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        type F = BLSScalar;
+        let zero = F::zero();
+        let one = F::one();
+
+        // (a * b + a) * 2 + 1 with a = 3, b = 5
+        let mut cs = StandardLayoutCS::<F>::new();
+        let a = cs.new_variable(F::from_u32(3));
+        let b = cs.new_variable(F::from_u32(5));
+        let ab = cs.new_variable(F::from_u32(15));
+        let c = cs.new_variable(F::from_u32(18));
+        let d = cs.new_variable(F::from_u32(37));
+        cs.insert_gate([a, b, ab], [zero, zero, one, zero, one]);
+        cs.insert_gate([ab, a, c], [one, one, zero, zero, one]);
+        cs.insert_gate([c, a, d], [one.add(&one), zero, zero, one, one]);
+        cs.pad();
+        assert_eq!(cs.size, 4);
+        assert_eq!(cs.selectors.len(), 5);
+
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        let common_seed = [0u8; 32];
+        let prover_params = pnk!(preprocess_prover(&cs, &pcs, common_seed));
+        let mut transcript = Transcript::new(b"TestTurboPlonk");
+        let proof = pnk!(prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness
+        ));
+        let mut transcript = Transcript::new(b"TestTurboPlonk");
+        pnk!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params.verifier_params,
+            &[],
+            &proof
+        ));
+
+        witness[d] = F::from_u32(38);
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        // the wiring is encoded as an array of 3 wires
+        let bytes = bincode::serialize(&cs).unwrap();
+        let decoded: StandardLayoutCS<F> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.wiring, cs.wiring);
+        assert_eq!(decoded.selectors, cs.selectors);
+    }
+
+    #[test]
+    fn test_mixed_radix_padding() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        type F = BLSScalar;
+
+        // 5 gates, padded to 6 instead of 8
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let mut sum = cs.new_variable(F::one());
+        for _ in 0..5 {
+            sum = cs.add(sum, sum);
+        }
+        cs.pad_mixed_radix();
+        assert_eq!(cs.size, 6);
+        assert_eq!(
+            cs.quot_eval_dom_strategy(),
+            QuotEvalDomainStrategy::Smallest
+        );
+        assert_eq!(cs.quot_eval_dom_size(), 48);
+        let witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+
+        let shifts = CosetShifts::GeneratorPowers;
+        let prover_params =
+            pnk!(preprocess_prover_with_coset_shifts(&cs, &pcs, &shifts));
+        let verifier_params =
+            pnk!(preprocess_verifier_with_coset_shifts(&cs, &pcs, &shifts));
+        assert_eq!(prover_params.quot_eval_domain().size(), 48);
+        let mut transcript = Transcript::new(b"TestTurboPlonk");
+        let proof = pnk!(prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &witness
+        ));
+        let mut transcript = Transcript::new(b"TestTurboPlonk");
+        pnk!(verifier(
+            &mut transcript,
+            &pcs,
+            &cs,
+            &verifier_params,
+            &[],
+            &proof
+        ));
+        // the parameters of other shifts do not verify the proof
+        let other_params = pnk!(preprocess_verifier(&cs, &pcs, [0u8; 32]));
+        let mut transcript = Transcript::new(b"TestTurboPlonk");
+        assert!(
+            verifier(&mut transcript, &pcs, &cs, &other_params, &[], &proof).is_err()
+        );
+
+        // the default domain of 36 elements is not supported by the FFT
+        cs.set_quot_eval_dom_strategy(TURBO_QUOT_EVAL_DOM_STRATEGY);
+        assert_eq!(cs.quot_eval_dom_size(), 36);
+        assert!(preprocess_prover(&cs, &pcs, [0u8; 32]).is_err());
+
+        // invalid shifts: k_2 * H = k_1 * H
+        let k_1 = F::multiplicative_generator();
+        let omega = prover_params.eval_domain().element(1);
+        let shifts = CosetShifts::Custom(vec![F::one(), k_1, k_1.mul(&omega), k_1, k_1]);
+        cs.set_quot_eval_dom_strategy(QuotEvalDomainStrategy::Smallest);
+        assert!(preprocess_prover_with_coset_shifts(&cs, &pcs, &shifts).is_err());
     }
 
     fn test_turbo_plonk_with_constant_and_online_values<
@@ -1039,6 +2457,128 @@ mod turbo_plonk_proofs_test {
         check_turbo_plonk_proof(pcs, prng, &cs, &witness, &[]);
     }
 
+    fn test_turbo_plonk_lookup_gates<PCS: PolyComScheme, R: CryptoRng + RngCore>(
+        pcs: &PCS,
+        prng: &mut R,
+    ) {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let num: Vec<PCS::Field> =
+            (0..8).map(|x| PCS::Field::from_u32(x as u32)).collect();
+
+        // The circuit description:
+        // 1. 0 <= a < 8
+        // 2. c = b^3 with 0 <= b < 4
+        // 3. d = c + a is public
+        // The secret inputs: [a, b] = [5, 2]
+        let range_id = cs.add_lookup_table(&num);
+        let cube_id = cs.add_lookup_map(
+            &num[..4]
+                .iter()
+                .map(|x| (*x, x.mul(x).mul(x)))
+                .collect::<Vec<(PCS::Field, PCS::Field)>>(),
+        );
+        let a = cs.new_variable(num[5]);
+        let b = cs.new_variable(num[2]);
+        cs.lookup(a, range_id);
+        let c = cs.lookup_output(b, cube_id);
+        let d = cs.add(c, a);
+        cs.prepare_io_variable(d);
+        cs.pad();
+
+        let online_vars = [PCS::Field::from_u32(13)];
+        let mut witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness[..], &online_vars).is_ok());
+        check_turbo_plonk_proof(pcs, prng, &cs, &witness, &online_vars);
+
+        // a proof with a tampered lookup evaluation or h1 commitment is rejected
+        let prover_params = preprocess_prover(&cs, pcs, [0u8; 32]).unwrap();
+        let mut transcript = Transcript::new(b"TestTurboPlonk");
+        let proof =
+            prover(prng, &mut transcript, pcs, &cs, &prover_params, &witness).unwrap();
+        let verify = |proof: &PlonkPf<PCS>| {
+            let mut transcript = Transcript::new(b"TestTurboPlonk");
+            verifier(
+                &mut transcript,
+                pcs,
+                &cs,
+                &prover_params.verifier_params,
+                &online_vars,
+                proof,
+            )
+        };
+        assert!(verify(&proof).is_ok());
+        let mut bad_proof = proof.clone();
+        let lookup = bad_proof.lookup.as_mut().unwrap();
+        lookup.h1_eval_beta = lookup.h1_eval_beta.add(&PCS::Field::one());
+        assert!(verify(&bad_proof).is_err());
+        let mut bad_proof = proof.clone();
+        let lookup = bad_proof.lookup.as_mut().unwrap();
+        lookup.C_h1 = lookup.C_h2.clone();
+        assert!(verify(&bad_proof).is_err());
+
+        // a is out of range
+        witness[a] = PCS::Field::from_u32(9);
+        witness[d] = PCS::Field::from_u32(17);
+        let online_vars = [PCS::Field::from_u32(17)];
+        assert!(cs.verify_witness(&witness[..], &online_vars).is_err());
+        let prover_params = preprocess_prover(&cs, pcs, [0u8; 32]).unwrap();
+        let mut transcript = Transcript::new(b"TestTurboPlonk");
+        assert!(
+            prover(prng, &mut transcript, pcs, &cs, &prover_params, &witness).is_err()
+        );
+    }
+
+    fn test_turbo_plonk_blinding_gates<PCS: PolyComScheme, R: CryptoRng + RngCore>(
+        pcs: &PCS,
+        prng: &mut R,
+    ) {
+        let build = |prng: &mut R| {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let a = cs.new_variable(PCS::Field::from_u32(3));
+            let b = cs.new_variable(PCS::Field::from_u32(5));
+            let c = cs.mul(a, b);
+            cs.prepare_io_variable(c);
+            cs.pad_with_blinding(3, prng);
+            cs
+        };
+        let mut cs = build(prng);
+        let mut other_cs = build(prng);
+        // 2 gates and 3 blinding gates, padded to 8 gates
+        assert_eq!(cs.size, 8);
+        assert!(cs
+            .selectors
+            .iter()
+            .all(|selector| selector[2..5].iter().all(|q| *q == PCS::Field::zero())));
+        // the gates do not depend on the random values
+        assert_eq!(cs.selectors, other_cs.selectors);
+        assert_eq!(cs.wiring, other_cs.wiring);
+
+        let online_vars = [PCS::Field::from_u32(15)];
+        let witness = cs.get_and_clear_witness();
+        let other_witness = other_cs.get_and_clear_witness();
+        assert_eq!(witness.len(), 3 + 3 * 5);
+        assert_eq!(witness[..3], other_witness[..3]);
+        assert_ne!(witness[3..], other_witness[3..]);
+        assert!(cs.verify_witness(&witness, &online_vars).is_ok());
+        check_turbo_plonk_proof(pcs, prng, &cs, &witness, &online_vars);
+    }
+
+    fn test_turbo_plonk_range_chunk_gates<PCS: PolyComScheme, R: CryptoRng + RngCore>(
+        pcs: &PCS,
+        prng: &mut R,
+    ) {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let a = cs.new_variable(PCS::Field::from_u64(u32::MAX as u64 + 7));
+        let _ = cs.range_check_chunks(a, 34);
+        cs.prepare_io_variable(a);
+        cs.pad();
+
+        let online_vars = [PCS::Field::from_u64(u32::MAX as u64 + 7)];
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness[..], &online_vars).is_ok());
+        check_turbo_plonk_proof(pcs, prng, &cs, &witness, &online_vars);
+    }
+
     fn test_turbo_plonk_ecc_gates<
         PCS: PolyComScheme<Field = BLSScalar>,
         R: CryptoRng + RngCore,
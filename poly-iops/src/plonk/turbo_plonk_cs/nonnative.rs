@@ -0,0 +1,560 @@
+// This file implements gadgets for the arithmetic of a foreign prime field Fp, e.g. the base
+// field of secp256k1, in a constraint system over the native field F.
+// An element x of Fp is a NonNativeVar: n limbs x_0, ..., x_{n-1} of NONNATIVE_LIMB_BITS = b bits
+// with x = sum_i x_i 2^(b i), where n is the least number of limbs for p. Every limb is range
+// checked to b bits, so x < 2^(n b), but x is not necessarily reduced modulo p: the outputs of the
+// gadgets computed by an honest prover are reduced, and nonnative_reduce enforces x < p.
+// An operation, e.g. c = a * b mod p, is proved by an identity between integers, e.g.
+// a * b - q * p - c = 0 with a quotient q given as witness with range checked limbs. The identity
+// is written as a polynomial in 2^b with coefficients z_k, linear combinations of the limbs and
+// of the products of limbs, that are small signed integers in F. Then sum_k z_k 2^(b k) = 0 if and
+// only if there are carries c_k with z_0 = c_0 2^b, z_k + c_{k-1} = c_k 2^b, and
+// z_last + c_{last-1} = 0. The carries are range checked as signed integers, hence none of these
+// native equations wraps modulo the size of F and they hold over the integers.
+
+use crate::plonk::errors::PlonkError;
+use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+use algebra::groups::Scalar;
+use num_bigint::{BigInt, BigUint, Sign};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use ruc::*;
+
+/// Number of bits of a limb of a NonNativeVar
+pub const NONNATIVE_LIMB_BITS: usize = 64;
+
+/// Maximal number of bits of a foreign modulus
+pub const NONNATIVE_MAX_MODULUS_BITS: usize = 512;
+
+/// A foreign prime field, given by its modulus
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NonNativeField {
+    modulus: BigUint,
+    n_limbs: usize,
+}
+
+/// A variable with an element of a foreign field, see nonnative.rs.
+/// The limbs are in little endian order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NonNativeVar {
+    pub limbs: Vec<VarIndex>,
+}
+
+impl NonNativeField {
+    /// Returns an error if the modulus is smaller than 2 or has more than
+    /// NONNATIVE_MAX_MODULUS_BITS bits.
+    pub fn new(modulus: BigUint) -> Result<NonNativeField> {
+        let bits = modulus.bits() as usize;
+        if !(2..=NONNATIVE_MAX_MODULUS_BITS).contains(&bits) {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
+        let n_limbs = (bits + NONNATIVE_LIMB_BITS - 1) / NONNATIVE_LIMB_BITS;
+        Ok(NonNativeField { modulus, n_limbs })
+    }
+
+    pub fn modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    pub fn n_limbs(&self) -> usize {
+        self.n_limbs
+    }
+
+    // The smallest multiple of p that is at least 2^(n b), added to a difference so that it
+    // stays nonnegative. It is smaller than 2^(n b) + p, hence it has n + 1 limbs.
+    fn sub_offset(&self) -> BigUint {
+        let bound = BigUint::one() << (self.n_limbs * NONNATIVE_LIMB_BITS);
+        bound.div_ceil(&self.modulus) * &self.modulus
+    }
+}
+
+// Little endian limbs of `x`, at least `n_limbs` of them
+fn to_limbs(x: &BigUint, n_limbs: usize) -> Vec<BigUint> {
+    let mask = (BigUint::one() << NONNATIVE_LIMB_BITS) - BigUint::one();
+    let mut limbs = vec![];
+    let mut x = x.clone();
+    while limbs.len() < n_limbs || !x.is_zero() {
+        limbs.push(&x & &mask);
+        x >>= NONNATIVE_LIMB_BITS;
+    }
+    limbs
+}
+
+// `x` must be smaller than the native modulus
+fn biguint_to_scalar<F: Scalar>(x: &BigUint) -> F {
+    F::from_bytes(&x.to_bytes_le()).unwrap() // safe unwrap
+}
+
+fn scalar_to_biguint<F: Scalar>(x: &F) -> BigUint {
+    BigUint::from_bytes_le(&x.to_bytes())
+}
+
+// The signed integer embedded in `x`, negative if `x` is larger than (r - 1) / 2
+fn scalar_to_bigint<F: Scalar>(x: &F) -> BigInt {
+    let value = scalar_to_biguint(x);
+    let half = BigUint::from_bytes_le(&F::field_size_minus_one_half());
+    if value > half {
+        let modulus = BigUint::from_bytes_le(&F::get_field_size_lsf_bytes());
+        BigInt::from_biguint(Sign::Minus, modulus - value)
+    } else {
+        BigInt::from_biguint(Sign::Plus, value)
+    }
+}
+
+fn bigint_to_scalar<F: Scalar>(x: &BigInt) -> F {
+    let abs = biguint_to_scalar::<F>(x.magnitude());
+    if x.sign() == Sign::Minus {
+        abs.neg()
+    } else {
+        abs
+    }
+}
+
+impl<F: Scalar> TurboPlonkConstraintSystem<F> {
+    // New variables with the limbs of `value`, range checked to NONNATIVE_LIMB_BITS bits
+    fn nonnative_limb_variables(
+        &mut self,
+        value: &BigUint,
+        n_limbs: usize,
+    ) -> Vec<VarIndex> {
+        to_limbs(value, n_limbs)
+            .iter()
+            .take(n_limbs)
+            .map(|limb| {
+                let var = self.new_variable(biguint_to_scalar(limb));
+                self.range_check(var, NONNATIVE_LIMB_BITS);
+                var
+            })
+            .collect()
+    }
+
+    /// Adds a variable with `value` mod p.
+    pub fn new_nonnative_variable(
+        &mut self,
+        field: &NonNativeField,
+        value: &BigUint,
+    ) -> NonNativeVar {
+        let value = value % &field.modulus;
+        NonNativeVar {
+            limbs: self.nonnative_limb_variables(&value, field.n_limbs),
+        }
+    }
+
+    /// Adds a variable constrained to the constant `value` mod p.
+    pub fn nonnative_constant(
+        &mut self,
+        field: &NonNativeField,
+        value: &BigUint,
+    ) -> NonNativeVar {
+        let value = value % &field.modulus;
+        let limbs = to_limbs(&value, field.n_limbs)
+            .iter()
+            .map(|limb| {
+                let limb = biguint_to_scalar(limb);
+                let var = self.new_variable(limb);
+                self.insert_constant_gate(var, limb);
+                var
+            })
+            .collect();
+        NonNativeVar { limbs }
+    }
+
+    /// The integer sum_i x_i 2^(b i) of the witness of `x`, not necessarily reduced modulo p
+    pub fn nonnative_value(&self, x: &NonNativeVar) -> BigUint {
+        x.limbs.iter().rev().fold(BigUint::zero(), |acc, limb| {
+            (acc << NONNATIVE_LIMB_BITS) + scalar_to_biguint(&self.witness[*limb])
+        })
+    }
+
+    // Returns a variable with sum_i coef_i * var_i, in chained linear combination gates
+    fn nonnative_sum(&mut self, terms: &[(VarIndex, F)]) -> VarIndex {
+        let zero = F::zero();
+        let mut padded = terms.to_vec();
+        while padded.len() < 4 || (padded.len() - 4) % 3 != 0 {
+            padded.push((0, zero));
+        }
+        let mut acc = self.linear_combine(
+            &[padded[0].0, padded[1].0, padded[2].0, padded[3].0],
+            padded[0].1,
+            padded[1].1,
+            padded[2].1,
+            padded[3].1,
+        );
+        for chunk in padded[4..].chunks(3) {
+            acc = self.linear_combine(
+                &[acc, chunk[0].0, chunk[1].0, chunk[2].0],
+                F::one(),
+                chunk[0].1,
+                chunk[1].1,
+                chunk[2].1,
+            );
+        }
+        acc
+    }
+
+    // Enforces sum_k z_k 2^(b k) = 0 over the integers, with the coefficient z_k the linear
+    // combination `terms[k]` of variables. |z_k| must be smaller than 2^z_bits for all the
+    // values of the limbs in range.
+    fn nonnative_enforce_identity(
+        &mut self,
+        terms: &[Vec<(VarIndex, F)>],
+        z_bits: usize,
+    ) {
+        let b = NONNATIVE_LIMB_BITS;
+        // |c_k| <= (|z_k| + |c_{k-1}|) / 2^b < 2^(z_bits - b + 1)
+        let carry_bits = z_bits - b + 2;
+        let two_pow_b = F::from_u32(2).pow_u64(b as u64);
+        let zero = F::zero();
+        let one = F::one();
+        let zero_var = self.zero_var();
+        let mut carry_var = zero_var;
+        let mut carry = BigInt::zero();
+        for (k, terms_k) in terms.iter().enumerate() {
+            let z_var = self.nonnative_sum(terms_k);
+            if k + 1 == terms.len() {
+                // z_last + c_{last-1} = 0
+                self.insert_lc_gate(
+                    &[z_var, carry_var, 0, 0],
+                    zero_var,
+                    one,
+                    one,
+                    zero,
+                    zero,
+                );
+            } else {
+                let z = scalar_to_bigint(&self.witness[z_var]);
+                carry = (z + &carry) >> b;
+                let next_carry_var = self.new_variable(bigint_to_scalar(&carry));
+                let shifted = self.signed_shift(next_carry_var, carry_bits);
+                self.range_check(shifted, carry_bits);
+                // z_k + c_{k-1} - c_k 2^b = 0
+                self.insert_lc_gate(
+                    &[z_var, carry_var, next_carry_var, 0],
+                    zero_var,
+                    one,
+                    one,
+                    two_pow_b.neg(),
+                    zero,
+                );
+                carry_var = next_carry_var;
+            }
+        }
+    }
+
+    // Bound on the number of bits of the coefficients of the identities of n limbs: a
+    // coefficient is a sum of at most n products of two limbs on each side, plus a few limbs.
+    fn nonnative_z_bits(field: &NonNativeField) -> usize {
+        let log_n = (usize::BITS - (field.n_limbs - 1).leading_zeros()) as usize;
+        2 * NONNATIVE_LIMB_BITS + log_n + 1
+    }
+
+    // Adds the terms `coef * x * 2^(b k)` for the limbs of x
+    fn nonnative_push_limbs(terms: &mut [Vec<(VarIndex, F)>], x: &[VarIndex], coef: F) {
+        for (k, limb) in x.iter().enumerate() {
+            terms[k].push((*limb, coef));
+        }
+    }
+
+    // Adds the terms `-q * p`
+    fn nonnative_push_quotient(
+        terms: &mut [Vec<(VarIndex, F)>],
+        q: &[VarIndex],
+        field: &NonNativeField,
+    ) {
+        let p_limbs = to_limbs(&field.modulus, field.n_limbs);
+        for (j, q_j) in q.iter().enumerate() {
+            for (i, p_i) in p_limbs.iter().enumerate() {
+                terms[i + j].push((*q_j, biguint_to_scalar::<F>(p_i).neg()));
+            }
+        }
+    }
+
+    // Adds the terms `coef * constant`
+    fn nonnative_push_constant(
+        &mut self,
+        terms: &mut [Vec<(VarIndex, F)>],
+        constant: &BigUint,
+        coef: F,
+    ) {
+        let one_var = self.one_var();
+        for (k, limb) in to_limbs(constant, 1).iter().enumerate() {
+            terms[k].push((one_var, coef.mul(&biguint_to_scalar(limb))));
+        }
+    }
+
+    fn check_nonnative_var(&self, field: &NonNativeField, x: &NonNativeVar) {
+        assert_eq!(
+            x.limbs.len(),
+            field.n_limbs,
+            "wrong number of limbs for the field"
+        );
+        assert!(
+            x.limbs.iter().all(|&limb| limb < self.num_vars),
+            "limb index out of bound"
+        );
+    }
+
+    /// Returns the little endian bits of the limbs of `x`, boolean constrained.
+    pub fn nonnative_bits(
+        &mut self,
+        field: &NonNativeField,
+        x: &NonNativeVar,
+    ) -> Vec<VarIndex> {
+        self.check_nonnative_var(field, x);
+        x.limbs
+            .iter()
+            .flat_map(|limb| self.range_check(*limb, NONNATIVE_LIMB_BITS))
+            .collect()
+    }
+
+    /// Returns x0 if the boolean variable `bit` is 0 and x1 otherwise, limb by limb.
+    pub fn nonnative_select(
+        &mut self,
+        field: &NonNativeField,
+        x0: &NonNativeVar,
+        x1: &NonNativeVar,
+        bit: VarIndex,
+    ) -> NonNativeVar {
+        self.check_nonnative_var(field, x0);
+        self.check_nonnative_var(field, x1);
+        let limbs = x0
+            .limbs
+            .iter()
+            .zip(x1.limbs.iter())
+            .map(|(limb0, limb1)| self.select(*limb0, *limb1, bit))
+            .collect();
+        NonNativeVar { limbs }
+    }
+
+    /// Returns a + b mod p: a + b - q * p - c = 0 with a quotient q of one limb.
+    pub fn nonnative_add(
+        &mut self,
+        field: &NonNativeField,
+        a: &NonNativeVar,
+        b: &NonNativeVar,
+    ) -> NonNativeVar {
+        self.check_nonnative_var(field, a);
+        self.check_nonnative_var(field, b);
+        let sum = self.nonnative_value(a) + self.nonnative_value(b);
+        let (q, c) = sum.div_rem(&field.modulus);
+        let q_var = self.nonnative_limb_variables(&q, 1);
+        let c_var = self.nonnative_limb_variables(&c, field.n_limbs);
+
+        let one = F::one();
+        let mut terms = vec![vec![]; field.n_limbs];
+        Self::nonnative_push_limbs(&mut terms, &a.limbs, one);
+        Self::nonnative_push_limbs(&mut terms, &b.limbs, one);
+        Self::nonnative_push_limbs(&mut terms, &c_var, one.neg());
+        Self::nonnative_push_quotient(&mut terms, &q_var, field);
+        self.nonnative_enforce_identity(&terms, Self::nonnative_z_bits(field));
+        NonNativeVar { limbs: c_var }
+    }
+
+    /// Returns a - b mod p: a + K - b - q * p - c = 0 with K the smallest multiple of p
+    /// larger than b, and a quotient q of one limb.
+    pub fn nonnative_sub(
+        &mut self,
+        field: &NonNativeField,
+        a: &NonNativeVar,
+        b: &NonNativeVar,
+    ) -> NonNativeVar {
+        self.check_nonnative_var(field, a);
+        self.check_nonnative_var(field, b);
+        let offset = field.sub_offset();
+        let diff = self.nonnative_value(a) + &offset - self.nonnative_value(b);
+        let (q, c) = diff.div_rem(&field.modulus);
+        let q_var = self.nonnative_limb_variables(&q, 1);
+        let c_var = self.nonnative_limb_variables(&c, field.n_limbs);
+
+        let one = F::one();
+        let mut terms = vec![vec![]; field.n_limbs + 1];
+        Self::nonnative_push_limbs(&mut terms, &a.limbs, one);
+        Self::nonnative_push_limbs(&mut terms, &b.limbs, one.neg());
+        Self::nonnative_push_limbs(&mut terms, &c_var, one.neg());
+        self.nonnative_push_constant(&mut terms, &offset, one);
+        Self::nonnative_push_quotient(&mut terms, &q_var, field);
+        self.nonnative_enforce_identity(&terms, Self::nonnative_z_bits(field));
+        NonNativeVar { limbs: c_var }
+    }
+
+    /// Returns a * b mod p: a * b - q * p - c = 0 with a quotient q of n limbs.
+    pub fn nonnative_mul(
+        &mut self,
+        field: &NonNativeField,
+        a: &NonNativeVar,
+        b: &NonNativeVar,
+    ) -> NonNativeVar {
+        self.check_nonnative_var(field, a);
+        self.check_nonnative_var(field, b);
+        let product = self.nonnative_value(a) * self.nonnative_value(b);
+        let (q, c) = product.div_rem(&field.modulus);
+        let n = field.n_limbs;
+        // q < p for reduced inputs
+        let q = if q.bits() as usize > n * NONNATIVE_LIMB_BITS {
+            BigUint::zero()
+        } else {
+            q
+        };
+        let q_var = self.nonnative_limb_variables(&q, n);
+        let c_var = self.nonnative_limb_variables(&c, n);
+
+        let one = F::one();
+        let mut terms = vec![vec![]; 2 * n - 1];
+        for (i, a_i) in a.limbs.iter().enumerate() {
+            for (j, b_j) in b.limbs.iter().enumerate() {
+                let product_var = self.mul(*a_i, *b_j);
+                terms[i + j].push((product_var, one));
+            }
+        }
+        Self::nonnative_push_limbs(&mut terms, &c_var, one.neg());
+        Self::nonnative_push_quotient(&mut terms, &q_var, field);
+        self.nonnative_enforce_identity(&terms, Self::nonnative_z_bits(field));
+        NonNativeVar { limbs: c_var }
+    }
+
+    /// Returns the canonical representative c < p of `a` mod p:
+    /// a - q * p - c = 0 with a quotient q of one limb, and c + d = p - 1 with range checked
+    /// limbs for d.
+    pub fn nonnative_reduce(
+        &mut self,
+        field: &NonNativeField,
+        a: &NonNativeVar,
+    ) -> NonNativeVar {
+        self.check_nonnative_var(field, a);
+        let (q, c) = self.nonnative_value(a).div_rem(&field.modulus);
+        let p_minus_one = &field.modulus - BigUint::one();
+        let d = &p_minus_one - &c;
+        let q_var = self.nonnative_limb_variables(&q, 1);
+        let c_var = self.nonnative_limb_variables(&c, field.n_limbs);
+        let d_var = self.nonnative_limb_variables(&d, field.n_limbs);
+
+        let one = F::one();
+        let z_bits = Self::nonnative_z_bits(field);
+        let mut terms = vec![vec![]; field.n_limbs];
+        Self::nonnative_push_limbs(&mut terms, &a.limbs, one);
+        Self::nonnative_push_limbs(&mut terms, &c_var, one.neg());
+        Self::nonnative_push_quotient(&mut terms, &q_var, field);
+        self.nonnative_enforce_identity(&terms, z_bits);
+
+        let mut terms = vec![vec![]; field.n_limbs];
+        Self::nonnative_push_limbs(&mut terms, &c_var, one);
+        Self::nonnative_push_limbs(&mut terms, &d_var, one);
+        self.nonnative_push_constant(&mut terms, &p_minus_one, one.neg());
+        self.nonnative_enforce_identity(&terms, z_bits);
+        NonNativeVar { limbs: c_var }
+    }
+
+    /// Enforces a = b mod p: a + K - b - q * p = 0 with K as in nonnative_sub and a quotient q
+    /// of one limb.
+    pub fn nonnative_enforce_equal(
+        &mut self,
+        field: &NonNativeField,
+        a: &NonNativeVar,
+        b: &NonNativeVar,
+    ) {
+        self.check_nonnative_var(field, a);
+        self.check_nonnative_var(field, b);
+        let offset = field.sub_offset();
+        let diff = self.nonnative_value(a) + &offset - self.nonnative_value(b);
+        let q = diff / &field.modulus;
+        let q_var = self.nonnative_limb_variables(&q, 1);
+
+        let one = F::one();
+        let mut terms = vec![vec![]; field.n_limbs + 1];
+        Self::nonnative_push_limbs(&mut terms, &a.limbs, one);
+        Self::nonnative_push_limbs(&mut terms, &b.limbs, one.neg());
+        self.nonnative_push_constant(&mut terms, &offset, one);
+        Self::nonnative_push_quotient(&mut terms, &q_var, field);
+        self.nonnative_enforce_identity(&terms, Self::nonnative_z_bits(field));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::nonnative::{NonNativeField, NonNativeVar};
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{Scalar, ScalarArithmetic};
+    use num_bigint::BigUint;
+    use num_traits::One;
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    // the base field of secp256k1
+    fn secp256k1_base_field() -> NonNativeField {
+        let p = BigUint::parse_bytes(
+            b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+            16,
+        )
+        .unwrap();
+        pnk!(NonNativeField::new(p))
+    }
+
+    #[test]
+    fn test_nonnative_field() {
+        assert!(NonNativeField::new(BigUint::one()).is_err());
+        assert!(NonNativeField::new(BigUint::one() << 512).is_err());
+        let field = secp256k1_base_field();
+        assert_eq!(field.n_limbs(), 4);
+        assert!(field.sub_offset() >= BigUint::one() << 256);
+    }
+
+    #[test]
+    fn test_nonnative_arithmetic() {
+        let field = secp256k1_base_field();
+        let p = field.modulus().clone();
+        let a = &p - BigUint::from(5u32);
+        let b = (BigUint::one() << 200) + BigUint::from(12345u32);
+
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a_var = cs.new_nonnative_variable(&field, &a);
+        let b_var = cs.new_nonnative_variable(&field, &b);
+        let seven = cs.nonnative_constant(&field, &BigUint::from(7u32));
+        let sum = cs.nonnative_add(&field, &a_var, &seven);
+        let diff = cs.nonnative_sub(&field, &b_var, &a_var);
+        let product = cs.nonnative_mul(&field, &a_var, &b_var);
+        assert_eq!(cs.nonnative_value(&sum), BigUint::from(2u32));
+        assert_eq!(cs.nonnative_value(&diff), (&b + BigUint::from(5u32)) % &p);
+        assert_eq!(cs.nonnative_value(&product), (&a * &b) % &p);
+        let reduced = cs.nonnative_reduce(&field, &product);
+        assert_eq!(cs.nonnative_value(&reduced), (&a * &b) % &p);
+        cs.nonnative_enforce_equal(&field, &reduced, &product);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+
+        // a wrong product
+        witness[product.limbs[1]] = witness[product.limbs[1]].add(&F::from_u32(1));
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
+    #[test]
+    fn test_nonnative_reduce_and_equal() {
+        let field = secp256k1_base_field();
+        let p = field.modulus().clone();
+
+        // x = p + 3 is not reduced
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let x = &p + BigUint::from(3u32);
+        let limbs: Vec<usize> = (0..4usize)
+            .map(|i| {
+                let limb = (&x >> (64 * i)) & ((BigUint::one() << 64) - BigUint::one());
+                cs.new_variable(F::from_bytes(&limb.to_bytes_le()).unwrap())
+            })
+            .collect();
+        let x_var = NonNativeVar { limbs };
+        let three = cs.nonnative_constant(&field, &BigUint::from(3u32));
+        let reduced = cs.nonnative_reduce(&field, &x_var);
+        assert_eq!(cs.nonnative_value(&reduced), BigUint::from(3u32));
+        cs.nonnative_enforce_equal(&field, &x_var, &three);
+        let witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let four = cs.nonnative_constant(&field, &BigUint::from(4u32));
+        let three = cs.nonnative_constant(&field, &BigUint::from(3u32));
+        cs.nonnative_enforce_equal(&field, &four, &three);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+// This file implements an optimization pass of the constraint system, that removes the gates
+// and the variables that do not change the statement of the circuit:
+// 1. Constant folding: a variable is constant if it is the output of a gate whose used input
+//    wires are constants, e.g. the variable of a constant gate, or the sum of two constants. The
+//    used wires of a gate are the wires with a non-zero selector, the other wires are ignored by
+//    the gate equation. Gates with the q_ecc selector, that multiplies the output wire by the
+//    inputs, do not define constants.
+// 2. A gate whose used wires are all constants is removed, after checking that the constants
+//    satisfy it: this removes the redundant constant gates, the gates of computations on
+//    constants, and the empty gates.
+// 3. The constant variables with the same value are merged into one variable, pinned by one
+//    constant gate in place of the first gate that defined the value, if a remaining gate uses
+//    the value.
+// 4. The variables that are not used by any remaining gate nor public input are removed, and
+//    the variables are renumbered in their order.
+// The public input gates and the lookup gates are never removed. The pass returns the new index
+// of each variable, as the handles of the variables of the circuit change: the merged variables
+// have the index of their representative, and the removed variables have no index. The witness
+// of the constraint system, if any, is renumbered. The pass is meant to run before `pad`, and
+// fails without changing the constraint system if the constants do not satisfy a gate.
+
+use crate::plonk::plonk_setup::ConstraintSystem;
+use crate::plonk::turbo_plonk_cs::{
+    CsIndex, TurboPlonkConstraintSystem, VarIndex, N_SELECTORS, N_WIRES_PER_GATE,
+};
+use algebra::groups::Scalar;
+use ruc::*;
+use std::collections::{HashMap, HashSet};
+
+impl<F: Scalar> TurboPlonkConstraintSystem<F> {
+    /// Removes the gates of the constant computations, merges the constants with the same
+    /// value, and removes the unused variables. Returns the new index of each variable, None for
+    /// the removed variables, see optimize.rs.
+    pub fn optimize(&mut self) -> Result<Vec<Option<VarIndex>>> {
+        let zero = F::zero();
+        let one = F::one();
+        let fixed_gates: HashSet<CsIndex> = self
+            .public_vars_constraint_indices
+            .iter()
+            .chain(self.lookup_gates.iter().map(|(cs_index, _)| cs_index))
+            .copied()
+            .collect();
+        let used_wires: Vec<[bool; N_WIRES_PER_GATE]> = (0..self.size)
+            .map(|cs_index| self.used_wires(cs_index))
+            .collect();
+
+        // constant folding
+        let mut constants: Vec<Option<F>> = vec![None; self.num_vars];
+        let mut defined_by: Vec<CsIndex> = vec![0; self.num_vars];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (cs_index, used) in used_wires.iter().enumerate() {
+                let out_var = self.wiring[4][cs_index];
+                if fixed_gates.contains(&cs_index)
+                    || self.selectors[7][cs_index] != zero
+                    || self.selectors[12][cs_index] == zero
+                    || constants[out_var].is_some()
+                {
+                    continue;
+                }
+                let constant_inputs = (0..N_WIRES_PER_GATE - 1)
+                    .all(|i| !used[i] || constants[self.wiring[i][cs_index]].is_some());
+                if constant_inputs {
+                    // the output is the gate equation with a zero output, divided by qo
+                    let value = self
+                        .eval_with_constants(cs_index, &constants)
+                        .c(d!())?
+                        .mul(&self.selectors[12][cs_index].inv().c(d!())?);
+                    constants[out_var] = Some(value);
+                    defined_by[out_var] = cs_index;
+                    changed = true;
+                }
+            }
+        }
+
+        // the gates on constants
+        let mut removed_gates = vec![false; self.size];
+        for (cs_index, removed) in removed_gates.iter_mut().enumerate() {
+            if fixed_gates.contains(&cs_index) {
+                continue;
+            }
+            let constant_wires = (0..N_WIRES_PER_GATE).all(|i| {
+                !used_wires[cs_index][i] || constants[self.wiring[i][cs_index]].is_some()
+            });
+            if constant_wires {
+                if self.eval_with_constants(cs_index, &constants).c(d!())? != zero {
+                    return Err(eg!(format!(
+                        "cs index {}: the constants do not satisfy the gate",
+                        cs_index
+                    )));
+                }
+                *removed = true;
+            }
+        }
+
+        // merge the constants with the same value, the representative of a value is its
+        // first variable, pinned in place of the first gate that defines the value
+        let mut representatives: Vec<VarIndex> = (0..self.num_vars).collect();
+        let mut values: HashMap<Vec<u8>, VarIndex> = HashMap::new();
+        for (var, constant) in constants.iter().enumerate() {
+            if let Some(value) = constant {
+                let rep = *values.entry(value.to_bytes()).or_insert(var);
+                representatives[var] = rep;
+                defined_by[rep] = defined_by[rep].min(defined_by[var]);
+            }
+        }
+        let mut used_vars = vec![false; self.num_vars];
+        for &var in self.public_vars_witness_indices.iter() {
+            used_vars[representatives[var]] = true;
+        }
+        for cs_index in (0..self.size).filter(|&cs_index| !removed_gates[cs_index]) {
+            for (wire, &used) in self.wiring.iter().zip(used_wires[cs_index].iter()) {
+                if used {
+                    used_vars[representatives[wire[cs_index]]] = true;
+                }
+            }
+        }
+        let mut pinned_constants: HashMap<CsIndex, VarIndex> = HashMap::new();
+        for &rep in values.values() {
+            if used_vars[rep] {
+                pinned_constants.insert(defined_by[rep], rep);
+            }
+        }
+
+        // the new indices
+        let mut new_var_indices: Vec<Option<VarIndex>> = vec![None; self.num_vars];
+        let mut num_vars = 0;
+        for (new_var_index, &used) in new_var_indices.iter_mut().zip(used_vars.iter()) {
+            if used {
+                *new_var_index = Some(num_vars);
+                num_vars += 1;
+            }
+        }
+        let var_map: Vec<Option<VarIndex>> = representatives
+            .iter()
+            .map(|&rep| new_var_indices[rep])
+            .collect();
+        let mut new_cs_indices: Vec<Option<CsIndex>> = vec![None; self.size];
+        let mut size = 0;
+        for (cs_index, new_cs_index) in new_cs_indices.iter_mut().enumerate() {
+            if !removed_gates[cs_index] || pinned_constants.contains_key(&cs_index) {
+                *new_cs_index = Some(size);
+                size += 1;
+            }
+        }
+
+        // rewire the gates
+        let mut selectors: Vec<Vec<F>> = vec![vec![]; N_SELECTORS];
+        let mut wiring: [Vec<VarIndex>; N_WIRES_PER_GATE] =
+            [vec![], vec![], vec![], vec![], vec![]];
+        for (cs_index, (&removed, used)) in
+            removed_gates.iter().zip(used_wires.iter()).enumerate()
+        {
+            if let Some(&rep) = pinned_constants.get(&cs_index) {
+                let value = constants[rep].unwrap(); // safe unwrap
+                for (i, selector) in selectors.iter_mut().enumerate() {
+                    selector.push(match i {
+                        6 => value,
+                        12 => one,
+                        _ => zero,
+                    });
+                }
+                for wire in wiring.iter_mut() {
+                    wire.push(var_map[rep].unwrap()); // safe unwrap
+                }
+            } else if !removed {
+                for (selector, old_selector) in
+                    selectors.iter_mut().zip(self.selectors.iter())
+                {
+                    selector.push(old_selector[cs_index]);
+                }
+                for (i, wire) in wiring.iter_mut().enumerate() {
+                    let var = self.wiring[i][cs_index];
+                    wire.push(match var_map[var] {
+                        Some(new_var) if used[i] => new_var,
+                        _ => 0,
+                    });
+                }
+            }
+        }
+
+        // the namespace marks of stats.rs count the gates and the variables before them
+        let cs_prefix = prefix_counts(new_cs_indices.iter().map(|c| c.is_some()));
+        let var_prefix = prefix_counts(used_vars.iter().copied());
+        for (cs_index, var_index, _) in self.namespace_marks.iter_mut() {
+            *cs_index = cs_prefix[*cs_index];
+            *var_index = var_prefix[*var_index];
+        }
+        #[cfg(feature = "debug_gates")]
+        {
+            let labels = std::mem::take(&mut self.gate_labels);
+            self.gate_labels = labels
+                .into_iter()
+                .enumerate()
+                .filter(|(cs_index, _)| new_cs_indices[*cs_index].is_some())
+                .map(|(_, label)| label)
+                .collect();
+        }
+
+        if self.witness.len() == self.num_vars {
+            self.witness = (0..self.num_vars)
+                .filter(|&var| used_vars[var])
+                .map(|var| self.witness[var])
+                .collect();
+        }
+        self.zero_var = self.zero_var.and_then(|var| var_map[var]);
+        self.one_var = self.one_var.and_then(|var| var_map[var]);
+        for var in self.public_vars_witness_indices.iter_mut() {
+            *var = var_map[*var].unwrap(); // safe unwrap
+        }
+        for cs_index in self.public_vars_constraint_indices.iter_mut() {
+            *cs_index = new_cs_indices[*cs_index].unwrap(); // safe unwrap
+        }
+        for (cs_index, _) in self.lookup_gates.iter_mut() {
+            *cs_index = new_cs_indices[*cs_index].unwrap(); // safe unwrap
+        }
+        self.selectors = selectors;
+        self.wiring = wiring;
+        self.size = size;
+        self.num_vars = num_vars;
+        Ok(var_map)
+    }
+
+    // Returns the wires of the gate `cs_index` whose values change the gate equation, and the
+    // input and output wires of a lookup gate
+    fn used_wires(&self, cs_index: CsIndex) -> [bool; N_WIRES_PER_GATE] {
+        if self.lookup_gates.iter().any(|(c_i, _)| *c_i == cs_index) {
+            return [true, true, false, false, false];
+        }
+        let used = |i: usize| self.selectors[i][cs_index] != F::zero();
+        let ecc = used(7);
+        [
+            used(0) || used(4) || used(8) || ecc,
+            used(1) || used(4) || used(9) || ecc,
+            used(2) || used(5) || used(10) || ecc,
+            used(3) || used(5) || used(11) || ecc,
+            used(12) || ecc,
+        ]
+    }
+
+    // Evaluates the gate `cs_index` on the constant values of the wires, and zero for the
+    // other wires
+    fn eval_with_constants(
+        &self,
+        cs_index: CsIndex,
+        constants: &[Option<F>],
+    ) -> Result<F> {
+        let wire_vals: Vec<F> = self
+            .wiring
+            .iter()
+            .map(|wire| constants[wire[cs_index]].unwrap_or_else(F::zero))
+            .collect();
+        let wire_vals: Vec<&F> = wire_vals.iter().collect();
+        let sel_vals: Vec<&F> = self
+            .selectors
+            .iter()
+            .map(|selector| &selector[cs_index])
+            .collect();
+        self.eval_gate_func(&wire_vals, &sel_vals, &F::zero())
+            .c(d!())
+    }
+}
+
+// Returns the number of true values before each position of `flags`, and the total
+fn prefix_counts<I: Iterator<Item = bool>>(flags: I) -> Vec<usize> {
+    let mut counts = vec![0];
+    for flag in flags {
+        counts.push(counts[counts.len() - 1] + flag as usize);
+    }
+    counts
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar};
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_optimize() {
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_variable(F::from_u32(7));
+        let c1 = cs.new_variable(F::from_u32(3));
+        cs.insert_constant_gate(c1, F::from_u32(3));
+        let c2 = cs.new_variable(F::from_u32(3));
+        cs.insert_constant_gate(c2, F::from_u32(3));
+        // d = 6 is a constant
+        let d = cs.add(c1, c2);
+        let e = cs.mul(a, d);
+        let f = cs.mul(e, c2);
+        let unused = cs.new_variable(F::from_u32(11));
+        cs.prepare_io_variable(f);
+        assert_eq!(cs.size, 6);
+
+        let var_map = pnk!(cs.optimize());
+        assert_eq!(var_map.len(), 7);
+        assert_eq!(var_map[a], Some(0));
+        assert_eq!(var_map[c1], Some(1));
+        assert_eq!(var_map[c2], Some(1));
+        assert_eq!(var_map[d], Some(2));
+        assert_eq!(var_map[e], Some(3));
+        assert_eq!(var_map[f], Some(4));
+        assert_eq!(var_map[unused], None);
+        // the constant gates of 3 and 6, the two multiplications and the public input
+        assert_eq!(cs.size, 5);
+        assert_eq!(cs.num_vars, 5);
+        assert_eq!(cs.public_vars_witness_indices, vec![4]);
+        assert_eq!(cs.public_vars_constraint_indices, vec![4]);
+
+        let online = F::from_u32(7 * 6 * 3);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[online]));
+        // the merged constant is still pinned
+        witness[1] = F::one();
+        assert!(cs.verify_witness(&witness, &[online]).is_err());
+    }
+
+    #[test]
+    fn test_optimize_range_check() {
+        // the optimized circuit accepts the same witnesses
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_variable(F::from_u32(200));
+        let one_var = cs.one_var();
+        cs.insert_constant_gate(one_var, F::one());
+        let b = cs.add(a, one_var);
+        let _ = cs.range_check(b, 8);
+        let table_id = cs.add_lookup_table(&[F::from_u32(200), F::from_u32(201)]);
+        cs.lookup(a, table_id);
+        cs.prepare_io_variable(b);
+        let n_gates = cs.size;
+
+        let var_map = pnk!(cs.optimize());
+        assert_eq!(cs.size, n_gates);
+        assert_eq!(var_map[one_var], cs.one_var);
+        let witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[F::from_u32(201)]));
+        assert!(cs.verify_witness(&witness, &[F::from_u32(200)]).is_err());
+    }
+
+    #[test]
+    fn test_optimize_unsatisfiable_constants() {
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_variable(F::from_u32(1));
+        cs.insert_constant_gate(a, F::from_u32(1));
+        cs.insert_constant_gate(a, F::from_u32(2));
+        assert!(cs.optimize().is_err());
+        assert_eq!(cs.size, 2);
+        assert_eq!(cs.num_vars, 1);
+    }
+}
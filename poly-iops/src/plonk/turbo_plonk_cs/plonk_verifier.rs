@@ -0,0 +1,406 @@
+// This file implements the scalar part of the verifier of a TurboPlonk proof in a TurboPlonk
+// constraint system over BLSScalar, as a step towards the recursive verification of proofs,
+// e.g. the aggregation of the proofs of many anonymous transfers.
+// From the challenges gamma, delta, alpha, beta of the transcript, the evaluations of the
+// proof at beta and the public inputs, the verifier of plonk::protocol computes:
+//   - PI(beta) = sum_j c_j * (beta^n - 1) / (beta - g^j) * pub_j, see eval_public_var_poly,
+//   - L(beta), the evaluation of the linearization polynomial, see derive_L_eval_beta,
+//   - the scalars of the commitment C_L to the linearization polynomial, the multi-scalar
+//     multiplication C_L = sum_i w_i * C_{q_i} + sigma_scalar * C_Sigma
+//     - b * C_{perm_last} - (beta^n - 1) * sum_i beta^{(n+2) i} * C_{Q_i}, see linearization,
+// then checks a batch evaluation proof for C_L and the other commitments with a pairing.
+// All of these scalars are elements of BLSScalar, the native field of the outer constraint
+// system, and plonk_verifier_scalars computes them with native gates. The commitments are
+// points of BLS12-381 G1 with coordinates in its base field, and BLS12-381 has no cycle of
+// curves: the multi-scalar multiplication and the pairing would need the nonnative gadgets,
+// at the cost of millions of gates, and the Merlin transcript has no gadget. So these checks
+// are deferred to the native verifier of the outer proof: it derives the challenges from the
+// transcript of the inner proof, computes C_L with linearization_commitment_from_scalars and
+// checks the batch evaluation proof. The gadget does not verify an inner proof on its own: the
+// caller binds the challenges, the evaluations and the output scalars to the values checked
+// by the native verifier, e.g. as public inputs of the outer circuit. Inner proofs with lookup
+// gates, and inner proofs of protocol version 0, which send L(beta), are not supported.
+// There is no aggregation of N proofs into an O(log N) proof with an inner pairing product
+// argument, as SnarkPack does for Groth16. The verification equation of a Groth16 proof is
+// fixed by the verifier key and the public inputs, but the final pairing check of a PLONK
+// proof is on a multi-scalar multiplication of its commitments with scalars derived from its
+// transcript, that is from the same commitments. A verifier of such an aggregate would replay
+// the N transcripts, hence read the N proofs, for the O(N) work of
+// plonk::protocol::prover::batch_verifier, which already checks N proofs with a single
+// multi-pairing. A succinct aggregate needs the transcripts to be checked in a circuit, which
+// is the purpose of this gadget.
+
+use crate::commitments::pcs::HomomorphicPolyComElem;
+use crate::plonk::errors::PlonkError;
+use crate::plonk::plonk_setup::PlonkVerifierParams;
+use crate::plonk::protocol::prover::PlonkProof;
+use crate::plonk::turbo_plonk_cs::{
+    DivisionByZeroPolicy, TurboPlonkConstraintSystem, VarIndex, N_SELECTORS,
+    N_WIRES_PER_GATE,
+};
+use algebra::bls12_381::BLSScalar;
+use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
+use ruc::*;
+
+/// The variables of the evaluations of an inner proof at the challenge beta
+pub struct PlonkProofEvalsVar {
+    pub witness_polys_eval_beta: Vec<VarIndex>,
+    pub perms_eval_beta: Vec<VarIndex>,
+    pub sigma_eval_g_beta: VarIndex,
+}
+
+/// The variables of the challenges of the transcript of an inner proof
+pub struct PlonkChallengesVar {
+    pub gamma: VarIndex,
+    pub delta: VarIndex,
+    pub alpha: VarIndex,
+    pub beta: VarIndex,
+}
+
+/// The variables of the scalars computed by the verifier of an inner proof, see
+/// plonk_verifier.rs. The scalars of the commitments are signed: C_L is the sum of the
+/// commitments multiplied by their scalars.
+pub struct PlonkVerifierScalarsVar {
+    pub public_vars_eval_beta: VarIndex,
+    pub linearization_eval_beta: VarIndex,
+    /// The scalars of the commitments to the selectors
+    pub selector_scalars: Vec<VarIndex>,
+    /// The scalar of the commitment C_Sigma
+    pub sigma_scalar: VarIndex,
+    /// The scalar of the commitment to the last extended permutation
+    pub last_perm_scalar: VarIndex,
+    /// The scalars of the commitments to the split quotient polynomials
+    pub quotient_scalars: Vec<VarIndex>,
+}
+
+/// Computes natively the commitment C_L to the linearization polynomial of `proof` from the
+/// values of the scalars `scalars_var` in `witness`, the multi-scalar multiplication deferred
+/// to the native verifier, see plonk_verifier.rs.
+pub fn linearization_commitment_from_scalars<
+    C: HomomorphicPolyComElem<Scalar = BLSScalar>,
+    E,
+>(
+    vk: &PlonkVerifierParams<C, BLSScalar>,
+    proof: &PlonkProof<C, E, BLSScalar>,
+    witness: &[BLSScalar],
+    scalars_var: &PlonkVerifierScalarsVar,
+) -> C {
+    let mut C_L = C::get_identity();
+    for (C_q, scalar_var) in vk.selectors.iter().zip(scalars_var.selector_scalars.iter())
+    {
+        C_L.op_assign(&C_q.exp(&witness[*scalar_var]));
+    }
+    C_L.op_assign(&proof.C_Sigma.exp(&witness[scalars_var.sigma_scalar]));
+    // safe unwrap: the verifier parameters have an extended permutation per wire
+    let C_perm_last = vk.extended_permutations.last().unwrap();
+    C_L.op_assign(&C_perm_last.exp(&witness[scalars_var.last_perm_scalar]));
+    for (C_Q, scalar_var) in proof
+        .C_q_polys
+        .iter()
+        .zip(scalars_var.quotient_scalars.iter())
+    {
+        C_L.op_assign(&C_Q.exp(&witness[*scalar_var]));
+    }
+    C_L
+}
+
+impl TurboPlonkConstraintSystem<BLSScalar> {
+    /// Allocates the variables of the evaluations of the inner proof `proof`.
+    pub fn new_plonk_proof_evals_variables<C, E>(
+        &mut self,
+        proof: &PlonkProof<C, E, BLSScalar>,
+    ) -> PlonkProofEvalsVar {
+        PlonkProofEvalsVar {
+            witness_polys_eval_beta: proof
+                .witness_polys_eval_beta
+                .iter()
+                .map(|eval| self.new_variable(*eval))
+                .collect(),
+            perms_eval_beta: proof
+                .perms_eval_beta
+                .iter()
+                .map(|eval| self.new_variable(*eval))
+                .collect(),
+            sigma_eval_g_beta: self.new_variable(proof.Sigma_eval_g_beta),
+        }
+    }
+
+    /// Allocates the variables of the challenges of an inner proof, e.g. the challenges of
+    /// plonk::dump::dump_verification.
+    pub fn new_plonk_challenges_variables(
+        &mut self,
+        gamma: BLSScalar,
+        delta: BLSScalar,
+        alpha: BLSScalar,
+        beta: BLSScalar,
+    ) -> PlonkChallengesVar {
+        PlonkChallengesVar {
+            gamma: self.new_variable(gamma),
+            delta: self.new_variable(delta),
+            alpha: self.new_variable(alpha),
+            beta: self.new_variable(beta),
+        }
+    }
+
+    /// Computes the scalars of the verifier of an inner proof for the circuit of the verifier
+    /// key `vk` with the evaluations `evals_var`, the challenges `challenges_var` and the
+    /// public inputs `public_vars`, see plonk_verifier.rs.
+    /// Returns FuncParamsError if the inner circuit has lookup gates, or if the numbers of
+    /// evaluations or public inputs do not match the verifier key.
+    pub fn plonk_verifier_scalars<C>(
+        &mut self,
+        vk: &PlonkVerifierParams<C, BLSScalar>,
+        evals_var: &PlonkProofEvalsVar,
+        challenges_var: &PlonkChallengesVar,
+        public_vars: &[VarIndex],
+    ) -> Result<PlonkVerifierScalarsVar> {
+        if vk.lookup.is_some()
+            || vk.k.len() != N_WIRES_PER_GATE
+            || vk.selectors.len() != N_SELECTORS
+            || evals_var.witness_polys_eval_beta.len() != N_WIRES_PER_GATE
+            || evals_var.perms_eval_beta.len() != N_WIRES_PER_GATE - 1
+            || public_vars.len() != vk.public_vars_constraint_indices.len()
+        {
+            return Err(eg!(PlonkError::FuncParamsError));
+        }
+        let f = &evals_var.witness_polys_eval_beta;
+        let perms = &evals_var.perms_eval_beta;
+        let PlonkChallengesVar {
+            gamma,
+            delta,
+            alpha,
+            beta,
+        } = *challenges_var;
+        let one = BLSScalar::one();
+        let zero = BLSScalar::zero();
+
+        // Z_H(beta) = beta^n - 1 and L1(beta) = (beta^n - 1) / (beta - 1)
+        let beta_n = self.pow_var(beta, vk.cs_size as u64);
+        let z_h = self.affine_var(beta_n, one, one.neg());
+        let beta_minus_one = self.affine_var(beta, one, one.neg());
+        let (l1, _) = self.div(z_h, beta_minus_one, DivisionByZeroPolicy::Unsatisfiable);
+        let alpha_sq = self.mul(alpha, alpha);
+        let alpha_sq_l1 = self.mul(alpha_sq, l1);
+
+        // PI(beta)
+        let mut public_vars_eval_beta = self.constant_var(zero);
+        for ((constraint_index, pub_var), lagrange_constant) in vk
+            .public_vars_constraint_indices
+            .iter()
+            .zip(public_vars.iter())
+            .zip(vk.lagrange_constants.iter())
+        {
+            let root_to_j = vk.root.pow_u64(*constraint_index as u64);
+            let den = self.affine_var(beta, one, root_to_j.neg());
+            let num = self.mul(z_h, *pub_var);
+            let (lagrange_i, _) =
+                self.div(num, den, DivisionByZeroPolicy::Unsatisfiable);
+            public_vars_eval_beta = self.linear_combine(
+                &[public_vars_eval_beta, lagrange_i, 0, 0],
+                one,
+                *lagrange_constant,
+                zero,
+                zero,
+            );
+        }
+
+        // the selector multipliers of TurboPlonkConstraintSystem::eval_selector_multipliers
+        let mut selector_scalars = f[..4].to_vec();
+        selector_scalars.push(self.mul(f[0], f[1]));
+        selector_scalars.push(self.mul(f[2], f[3]));
+        selector_scalars.push(self.constant_var(one));
+        let mut f_prod = f[0];
+        for eval in f.iter().skip(1) {
+            f_prod = self.mul(f_prod, *eval);
+        }
+        selector_scalars.push(f_prod);
+        for eval in f[..4].iter() {
+            selector_scalars.push(self.pow_var(*eval, 5));
+        }
+        selector_scalars.push(self.affine_var(f[4], one.neg(), zero));
+
+        // sigma_scalar = alpha * prod_i (f_i + gamma * k_i * beta + delta)
+        //              + alpha^2 * L1(beta)
+        let gamma_beta = self.mul(gamma, beta);
+        let mut a = alpha;
+        for (eval, k) in f.iter().zip(vk.k.iter()) {
+            let ai =
+                self.linear_combine(&[*eval, gamma_beta, delta, 0], one, *k, one, zero);
+            a = self.mul(a, ai);
+        }
+        let sigma_scalar = self.add(a, alpha_sq_l1);
+
+        // prod_{i < n_wires_per_gate - 1} (f_i + gamma * perm_i + delta)
+        let mut perm_prod = self.mul(alpha, evals_var.sigma_eval_g_beta);
+        for (eval, perm) in f.iter().zip(perms.iter()) {
+            let gamma_perm = self.mul(gamma, *perm);
+            let bi =
+                self.linear_combine(&[*eval, gamma_perm, delta, 0], one, one, one, zero);
+            perm_prod = self.mul(perm_prod, bi);
+        }
+        // - alpha * Sigma(g * beta) * gamma * prod_i (...)
+        let b = self.mul(perm_prod, gamma);
+        let last_perm_scalar = self.affine_var(b, one.neg(), zero);
+
+        // L(beta) = alpha * Sigma(g * beta) * prod_i (...) * (f_last + delta)
+        //         + alpha^2 * L1(beta) - PI(beta)
+        let f_last_delta = self.add(f[N_WIRES_PER_GATE - 1], delta);
+        let term1 = self.mul(perm_prod, f_last_delta);
+        let linearization_eval_beta = self.linear_combine(
+            &[term1, alpha_sq_l1, public_vars_eval_beta, 0],
+            one,
+            one,
+            one.neg(),
+            zero,
+        );
+
+        // - (beta^n - 1) * beta^{(n+2) i} for the quotient polynomial Q_i, see combine_q_polys
+        let factor = self.pow_var(beta, vk.cs_size as u64 + 2);
+        let mut quotient_scalars = vec![self.affine_var(z_h, one.neg(), zero)];
+        for i in 1..N_WIRES_PER_GATE {
+            let scalar = self.mul(quotient_scalars[i - 1], factor);
+            quotient_scalars.push(scalar);
+        }
+
+        Ok(PlonkVerifierScalarsVar {
+            public_vars_eval_beta,
+            linearization_eval_beta,
+            selector_scalars,
+            sigma_scalar,
+            last_perm_scalar,
+            quotient_scalars,
+        })
+    }
+
+    // Returns a variable with a * var + c
+    fn affine_var(&mut self, var: VarIndex, a: BLSScalar, c: BLSScalar) -> VarIndex {
+        let zero = BLSScalar::zero();
+        let out_var = self.new_variable(self.witness[var].mul(&a).add(&c));
+        let mut selectors = [zero; N_SELECTORS];
+        selectors[0] = a;
+        selectors[6] = c;
+        selectors[N_SELECTORS - 1] = BLSScalar::one();
+        self.insert_gate([var, 0, 0, 0, out_var], selectors);
+        out_var
+    }
+
+    // Returns a variable constrained to `value`
+    fn constant_var(&mut self, value: BLSScalar) -> VarIndex {
+        let var = self.new_variable(value);
+        self.insert_constant_gate(var, value);
+        var
+    }
+
+    // Returns a variable with var^exponent, for a positive exponent, by square and multiply
+    fn pow_var(&mut self, var: VarIndex, exponent: u64) -> VarIndex {
+        assert!(exponent > 0, "zero exponent");
+        let mut result = var;
+        for i in (0..63 - exponent.leading_zeros()).rev() {
+            result = self.mul(result, result);
+            if (exponent >> i) & 1 == 1 {
+                result = self.mul(result, var);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::KZGCommitmentScheme;
+    use crate::plonk::dump::dump_verification;
+    use crate::plonk::plonk_setup::{preprocess_prover, preprocess_verifier};
+    use crate::plonk::protocol::prover::prover;
+    use crate::plonk::turbo_plonk_cs::plonk_verifier::linearization_commitment_from_scalars;
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_plonk_verifier_scalars() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        // inner circuit x_0 * x_1 + x_0 = x_3 with public inputs x_1 and x_3
+        let mut inner = TurboPlonkConstraintSystem::<F>::new();
+        let x0 = inner.new_variable(F::from_u32(3));
+        let x1 = inner.new_variable(F::from_u32(5));
+        let x2 = inner.mul(x0, x1);
+        let x3 = inner.add(x2, x0);
+        inner.prepare_io_variable(x1);
+        inner.prepare_io_variable(x3);
+        inner.pad();
+        let inner_witness = inner.get_and_clear_witness();
+        let inner_public_values = [F::from_u32(5), F::from_u32(18)];
+
+        let seed = [0u8; 32];
+        let prover_params = preprocess_prover(&inner, &pcs, seed).unwrap();
+        let vk = preprocess_verifier(&inner, &pcs, seed).unwrap();
+        let mut transcript = Transcript::new(b"TestRecursion");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &inner,
+            &prover_params,
+            &inner_witness,
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"TestRecursion");
+        let dump = dump_verification(
+            &mut transcript,
+            &pcs,
+            &inner,
+            &vk,
+            &inner_public_values,
+            &proof,
+        )
+        .unwrap();
+        assert!(dump.verified);
+
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let evals_var = cs.new_plonk_proof_evals_variables(&proof);
+        let challenges_var = cs.new_plonk_challenges_variables(
+            dump.gamma, dump.delta, dump.alpha, dump.beta,
+        );
+        let public_vars: Vec<usize> = inner_public_values
+            .iter()
+            .map(|value| cs.new_variable(*value))
+            .collect();
+        let scalars_var = pnk!(cs.plonk_verifier_scalars(
+            &vk,
+            &evals_var,
+            &challenges_var,
+            &public_vars
+        ));
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        assert_eq!(
+            witness[scalars_var.public_vars_eval_beta],
+            dump.public_vars_eval_beta
+        );
+        assert_eq!(
+            witness[scalars_var.linearization_eval_beta],
+            dump.linearization_eval_beta
+        );
+        // the deferred multi-scalar multiplication
+        assert_eq!(
+            linearization_commitment_from_scalars(&vk, &proof, &witness, &scalars_var),
+            dump.linearization_commitment
+        );
+
+        // another evaluation of the linearization polynomial
+        witness[scalars_var.linearization_eval_beta].add_assign(&F::one());
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        // wrong number of public inputs
+        assert!(cs
+            .plonk_verifier_scalars(&vk, &evals_var, &challenges_var, &public_vars[..1])
+            .is_err());
+    }
+}
@@ -0,0 +1,136 @@
+// This file implements the Poseidon permutation of crypto::basics::hash::poseidon in a
+// constraint system, with the same gates as the Rescue permutation: the q_hash selectors
+// multiply the 5th powers of the input wires, so a gate computes
+//   w_out = sum_{i=1..3} q_i * w_i + sum_{i=1..3} q_hash_i * w_i^5 + q_c.
+// The round constants of round r+1 are added by the gates of round r, after the MDS matrix:
+// with u_r = state_r + round_constants[r], the gates of round r compute
+//   u_{r+1}[i] = sum_j MDS[i][j] * S-box(u_r)[j] + round_constants[r+1][i],
+// one gate per element of the state, where S-box(u_r)[j] = u_r[j]^5 for a full round and for
+// j = 0 in a partial round, and u_r[j] otherwise. The state has 3 elements, so the fourth wire
+// of the gates is unused.
+
+use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+use algebra::bls12_381::BLSScalar;
+use algebra::groups::{One, ScalarArithmetic, Zero};
+use crypto::basics::hash::poseidon::PoseidonInstance;
+
+// state size
+const WIDTH: usize = 3;
+
+impl TurboPlonkConstraintSystem<BLSScalar> {
+    /// Returns the output of the Poseidon permutation on the variables `input_vars`, with the
+    /// same value as crypto::basics::hash::poseidon::PoseidonInstance::permutation.
+    pub fn poseidon_permutation(&mut self, input_vars: &[VarIndex]) -> Vec<VarIndex> {
+        assert_eq!(input_vars.len(), WIDTH, "wrong size of the Poseidon state");
+        let poseidon = PoseidonInstance::new();
+        let zero = BLSScalar::zero();
+        let one = BLSScalar::one();
+
+        let mut state: Vec<VarIndex> = (0..WIDTH)
+            .map(|i| {
+                let mut linear = [zero; WIDTH];
+                linear[i] = one;
+                self.add_poseidon_constraint(
+                    input_vars,
+                    &linear,
+                    &[zero; WIDTH],
+                    &poseidon.round_constants[0][i],
+                )
+            })
+            .collect();
+        for r in 0..poseidon.num_rounds() {
+            let constants = match poseidon.round_constants.get(r + 1) {
+                Some(constants) => constants.clone(),
+                None => vec![zero; WIDTH],
+            };
+            let is_full_round = poseidon.is_full_round(r);
+            state = (0..WIDTH)
+                .map(|i| {
+                    let mut linear = [zero; WIDTH];
+                    let mut power = [zero; WIDTH];
+                    for (j, coef) in poseidon.MDS[i].iter().enumerate() {
+                        if is_full_round || j == 0 {
+                            power[j] = *coef;
+                        } else {
+                            linear[j] = *coef;
+                        }
+                    }
+                    self.add_poseidon_constraint(&state, &linear, &power, &constants[i])
+                })
+                .collect();
+        }
+        state
+    }
+
+    /// Add a constraint:
+    /// witness[out_var] = sum_{i=1..3} linear[i] * witness[vars[i]]
+    ///                    + sum_{i=1..3} power[i] * witness[vars[i]]^5 + constant
+    fn add_poseidon_constraint(
+        &mut self,
+        vars: &[VarIndex],
+        linear: &[BLSScalar; WIDTH],
+        power: &[BLSScalar; WIDTH],
+        constant: &BLSScalar,
+    ) -> VarIndex {
+        assert_eq!(vars.len(), WIDTH);
+        let out_val = (0..WIDTH).fold(*constant, |sum, i| {
+            let value = &self.witness[vars[i]];
+            sum.add(&linear[i].mul(value))
+                .add(&power[i].mul(&value.pow(&[5u64])))
+        });
+        let out_var = self.new_variable(out_val);
+        let zero = BLSScalar::zero();
+        let one = BLSScalar::one();
+        self.push_add_selectors(linear[0], linear[1], linear[2], zero);
+        self.push_mul_selectors(zero, zero);
+        self.push_constant_selector(*constant);
+        self.push_ecc_selector(zero);
+        self.push_rescue_selectors(power[0], power[1], power[2], zero);
+        self.push_out_selector(one);
+        for (i, var) in vars.iter().enumerate() {
+            self.wiring[i].push(*var);
+        }
+        self.wiring[3].push(0);
+        self.wiring[4].push(out_var);
+        self.size += 1;
+
+        out_var
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{Scalar, Zero};
+    use crypto::basics::hash::poseidon::PoseidonInstance;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_poseidon_permutation() {
+        let poseidon = PoseidonInstance::new();
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let random_input: Vec<F> = (0..3).map(|_| F::random(&mut prng)).collect();
+        let test_vector_input = vec![F::from_u32(0), F::from_u32(1), F::from_u32(2)];
+        for input in [random_input, test_vector_input] {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let input_vars: Vec<VarIndex> =
+                input.iter().map(|elem| cs.new_variable(*elem)).collect();
+            let output_vars = cs.poseidon_permutation(&input_vars);
+            let output: Vec<F> =
+                output_vars.iter().map(|&var| cs.witness[var]).collect();
+            assert_eq!(output, poseidon.permutation(&input));
+            // 3 gates per round and 3 gates for the first round constants
+            assert_eq!(cs.size, 3 * poseidon.num_rounds() + 3);
+
+            let mut witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness, &[]));
+            witness[output_vars[2]] = F::zero();
+            assert!(cs.verify_witness(&witness, &[]).is_err());
+        }
+    }
+}
@@ -1,7 +1,15 @@
+// This file implements the gates of the Rescue permutation in a constraint system over
+// BLSScalar, and the functions built on it: the Rescue hash and sponge, the PRF and MAC, the
+// Rescue cipher and its counter mode, and the Rescue transcript of plonk::transcript.
+
 use crate::ioputils::u8_lsf_slice_to_u64_lsf_le_vec;
+use crate::plonk::transcript::{
+    rescue_transcript_header, rescue_transcript_pack_bytes, RESCUE_TRANSCRIPT_CHALLENGE,
+    RESCUE_TRANSCRIPT_MESSAGE, RESCUE_TRANSCRIPT_NEW, RESCUE_TRANSCRIPT_SCALAR,
+};
 use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
 use algebra::bls12_381::BLSScalar;
-use algebra::groups::{One, ScalarArithmetic, Zero};
+use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
 use crypto::basics::hash::rescue::RescueInstance;
 
 // state size
@@ -55,6 +63,12 @@ impl StateVar {
     }
 }
 
+/// Returns the single online value of a circuit that exposes `values` with
+/// TurboPlonkConstraintSystem::prepare_hashed_io_variables.
+pub fn hashed_public_inputs(values: &[BLSScalar]) -> BLSScalar {
+    RescueInstance::new().rescue_sponge_digest(values)
+}
+
 impl TurboPlonkConstraintSystem<BLSScalar> {
     /// Create a rescue state variable.
     pub fn new_rescue_state_variable(&mut self, state: State) -> StateVar {
@@ -74,7 +88,8 @@ impl TurboPlonkConstraintSystem<BLSScalar> {
         input_var
     }
 
-    /// Returns the output of the rescue hash function on input variable `input_var`
+    /// Returns the output of the rescue hash function on input variable `input_var`, a single
+    /// state. Inputs of any length are hashed by `rescue_sponge_digest`.
     pub fn rescue_hash(&mut self, input_var: &StateVar) -> Vec<VarIndex> {
         let hash = RescueInstance::new();
         let zero = BLSScalar::zero();
@@ -110,6 +125,90 @@ impl TurboPlonkConstraintSystem<BLSScalar> {
         state_var.0
     }
 
+    /// Returns the output of the rescue sponge-based PRF on key `key_var` and messages
+    /// `msg_vars`, with the same value as crypto::basics::prf::PRF::eval.
+    /// The state (0 || ... || 0 || key) absorbs `WIDTH` messages per rescue permutation.
+    pub fn prf(&mut self, key_var: VarIndex, msg_vars: &[VarIndex]) -> VarIndex {
+        assert!(!msg_vars.is_empty(), "no PRF input");
+        let zero_var = self.zero_var();
+        // the first block is injected into the zero state without addition gates
+        let mut state: Vec<VarIndex> = (0..WIDTH)
+            .map(|j| msg_vars.get(j).copied().unwrap_or(zero_var))
+            .collect();
+        state[WIDTH - 1] = match msg_vars.get(WIDTH - 1) {
+            Some(&msg_var) => self.add(msg_var, key_var),
+            None => key_var,
+        };
+        state = self.rescue_hash(&StateVar(state));
+        for block in msg_vars[WIDTH.min(msg_vars.len())..].chunks(WIDTH) {
+            for (state_var, &msg_var) in state.iter_mut().zip(block.iter()) {
+                *state_var = self.add(*state_var, msg_var);
+            }
+            state = self.rescue_hash(&StateVar(state));
+        }
+        state[0]
+    }
+
+    /// Returns the MAC tag of messages `msg_vars` under key `key_var`, with the same value
+    /// as crypto::basics::mac::MAC::tag: the PRF of the messages padded with a one and zeroes.
+    pub fn mac(&mut self, key_var: VarIndex, msg_vars: &[VarIndex]) -> VarIndex {
+        let mut padded = msg_vars.to_vec();
+        padded.push(self.one_var());
+        let n_zeroes = (WIDTH - padded.len() % WIDTH) % WIDTH;
+        padded.extend(vec![self.zero_var(); n_zeroes]);
+        self.prf(key_var, &padded)
+    }
+
+    /// Returns the rescue sponge digest of `vars`, with the same value as
+    /// crypto::basics::hash::rescue::RescueInstance::rescue_sponge_digest.
+    /// The variables, padded with a one and zeroes, are absorbed `WIDTH - 1` per rescue
+    /// permutation; the last element of the state is the capacity.
+    pub fn rescue_sponge_digest(&mut self, vars: &[VarIndex]) -> VarIndex {
+        let rate = WIDTH - 1;
+        let zero_var = self.zero_var();
+        let mut padded = vars.to_vec();
+        padded.push(self.one_var());
+        let n_zeroes = (rate - padded.len() % rate) % rate;
+        padded.extend(vec![zero_var; n_zeroes]);
+
+        // the first block is injected into the zero state without addition gates
+        let mut state = padded[..rate].to_vec();
+        state.push(zero_var);
+        state = self.rescue_hash(&StateVar(state));
+        for block in padded[rate..].chunks(rate) {
+            for (state_var, &var) in state.iter_mut().zip(block.iter()) {
+                if var != zero_var {
+                    *state_var = self.add(*state_var, var);
+                }
+            }
+            state = self.rescue_hash(&StateVar(state));
+        }
+        state[0]
+    }
+
+    /// Exposes the rescue sponge digest of `vars` as a single public input and returns the
+    /// digest variable. The verifier puts hashed_public_inputs(values) instead of `values` in
+    /// the online inputs. Compared to prepare_io_variable on each of `vars`, this saves the
+    /// verifier the work and the constant gate of each public input, at the price of about
+    /// 50 gates per variable for the hash in the circuit. It pays off for long public vectors.
+    pub fn prepare_hashed_io_variables(&mut self, vars: &[VarIndex]) -> VarIndex {
+        let digest_var = self.rescue_sponge_digest(vars);
+        self.prepare_io_variable(digest_var);
+        digest_var
+    }
+
+    /// Replaces the public inputs of the circuit by the rescue sponge digest of their variables,
+    /// in the order of the public inputs, see prepare_hashed_io_variables. The verifier puts
+    /// hashed_public_inputs(online_values) instead of the online values in the online inputs.
+    /// The gates of the former public inputs become empty, and are removed by optimize.
+    pub fn hash_public_inputs(&mut self) -> VarIndex {
+        let vars = std::mem::take(&mut self.public_vars_witness_indices);
+        for cs_index in std::mem::take(&mut self.public_vars_constraint_indices) {
+            self.selectors[12][cs_index] = BLSScalar::zero();
+        }
+        self.prepare_hashed_io_variables(&vars)
+    }
+
     /// Rescue block cipher
     /// * `key_var` - the state variable representing the cipher key.
     /// * `input_var` - the state variable representing the block cipher input.
@@ -398,13 +497,130 @@ impl TurboPlonkConstraintSystem<BLSScalar> {
     }
 }
 
+// An element absorbed by a RescueTranscriptVar: a constant of the circuit, e.g. a label, or a
+// variable
+#[derive(Clone, Copy)]
+enum TranscriptElemVar {
+    Constant(BLSScalar),
+    Var(VarIndex),
+}
+
+/// The state of a plonk::transcript::RescueTranscript in a constraint system: the challenges
+/// squeezed by rescue_transcript_challenge equal the challenges of a RescueTranscript with the
+/// same labels, messages and scalars. The labels and the messages are constants of the
+/// circuit, and a PLONK challenge that is a root of unity, which a RescueTranscript squeezes
+/// again, is not excluded.
+pub struct RescueTranscriptVar {
+    state: Vec<VarIndex>,
+    pending: Vec<TranscriptElemVar>,
+}
+
+impl RescueTranscriptVar {
+    fn push_header(&mut self, tag: u32, label: &[u8]) {
+        self.pending.extend(
+            rescue_transcript_header(tag, label)
+                .into_iter()
+                .map(TranscriptElemVar::Constant),
+        );
+    }
+}
+
+impl TurboPlonkConstraintSystem<BLSScalar> {
+    /// Returns the state of a RescueTranscript for the protocol labelled `label`.
+    pub fn new_rescue_transcript(
+        &mut self,
+        label: &'static [u8],
+    ) -> RescueTranscriptVar {
+        let zero = BLSScalar::zero();
+        let state = (0..WIDTH)
+            .map(|_| {
+                let var = self.new_variable(zero);
+                self.insert_constant_gate(var, zero);
+                var
+            })
+            .collect();
+        let mut transcript = RescueTranscriptVar {
+            state,
+            pending: vec![],
+        };
+        transcript.push_header(RESCUE_TRANSCRIPT_NEW, label);
+        transcript
+    }
+
+    /// Absorbs the constant `message` under the label `label` in `transcript`.
+    pub fn rescue_transcript_append_message(
+        &mut self,
+        transcript: &mut RescueTranscriptVar,
+        label: &'static [u8],
+        message: &[u8],
+    ) {
+        transcript.push_header(RESCUE_TRANSCRIPT_MESSAGE, label);
+        transcript
+            .pending
+            .push(TranscriptElemVar::Constant(BLSScalar::from_u64(
+                message.len() as u64,
+            )));
+        transcript.pending.extend(
+            rescue_transcript_pack_bytes(message)
+                .into_iter()
+                .map(TranscriptElemVar::Constant),
+        );
+    }
+
+    /// Absorbs the variable `var` under the label `label` in `transcript`, as
+    /// RescueTranscript::append_scalar.
+    pub fn rescue_transcript_append_scalar(
+        &mut self,
+        transcript: &mut RescueTranscriptVar,
+        label: &'static [u8],
+        var: VarIndex,
+    ) {
+        transcript.push_header(RESCUE_TRANSCRIPT_SCALAR, label);
+        transcript.pending.push(TranscriptElemVar::Var(var));
+    }
+
+    /// Returns the variable of the challenge squeezed from `transcript` under the label
+    /// `label`, as RescueTranscript::challenge.
+    pub fn rescue_transcript_challenge(
+        &mut self,
+        transcript: &mut RescueTranscriptVar,
+        label: &'static [u8],
+    ) -> VarIndex {
+        let rate = WIDTH - 1;
+        transcript.push_header(RESCUE_TRANSCRIPT_CHALLENGE, label);
+        let mut padded = std::mem::take(&mut transcript.pending);
+        padded.push(TranscriptElemVar::Constant(BLSScalar::one()));
+        let n_zeroes = (rate - padded.len() % rate) % rate;
+        padded.extend(vec![
+            TranscriptElemVar::Constant(BLSScalar::zero());
+            n_zeroes
+        ]);
+        for block in padded.chunks(rate) {
+            for (state_var, elem) in transcript.state.iter_mut().zip(block.iter()) {
+                *state_var = match elem {
+                    TranscriptElemVar::Constant(c) if *c == BLSScalar::zero() => {
+                        *state_var
+                    }
+                    TranscriptElemVar::Constant(c) => self.add_constant(*state_var, c),
+                    TranscriptElemVar::Var(var) => self.add(*state_var, *var),
+                };
+            }
+            transcript.state = self.rescue_hash(&StateVar(transcript.state.clone()));
+        }
+        transcript.state[0]
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::plonk::turbo_plonk_cs::rescue::State;
-    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use crate::plonk::transcript::{PlonkTranscript, RescueTranscript};
+    use crate::plonk::turbo_plonk_cs::rescue::{hashed_public_inputs, State};
+    use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
     use algebra::bls12_381::BLSScalar;
-    use algebra::groups::{Scalar, Zero};
+    use algebra::groups::{Scalar, ScalarArithmetic, Zero};
     use crypto::basics::hash::rescue::{RescueCtr, RescueInstance};
+    use crypto::basics::mac::MAC;
+    use crypto::basics::prf::PRF;
     use rand_chacha::ChaChaRng;
     use rand_core::SeedableRng;
     use ruc::*;
@@ -444,6 +660,126 @@ mod test {
         assert!(cs.verify_witness(&witness[..], &[]).is_err());
     }
 
+    #[test]
+    fn test_prf_and_mac() {
+        let prf = PRF::new();
+        let mac = MAC::new();
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let key = BLSScalar::random(&mut prng);
+        let msgs: Vec<BLSScalar> =
+            (0..9).map(|_| BLSScalar::random(&mut prng)).collect();
+        for n in [1, 3, 4, 5, 9] {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let key_var = cs.new_variable(key);
+            let msg_vars: Vec<VarIndex> =
+                msgs[..n].iter().map(|msg| cs.new_variable(*msg)).collect();
+            let prf_var = cs.prf(key_var, &msg_vars);
+            let mac_var = cs.mac(key_var, &msg_vars);
+            assert_eq!(cs.witness[prf_var], prf.eval(&key, &msgs[..n]));
+            assert_eq!(cs.witness[mac_var], mac.tag(&key, &msgs[..n]));
+
+            let mut witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness, &[]));
+            witness[mac_var] = F::zero();
+            assert!(cs.verify_witness(&witness, &[]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_rescue_sponge_digest() {
+        let hash = RescueInstance::new();
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let values: Vec<BLSScalar> =
+            (0..7).map(|_| BLSScalar::random(&mut prng)).collect();
+        for n in 0..=values.len() {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let vars: Vec<VarIndex> =
+                values[..n].iter().map(|v| cs.new_variable(*v)).collect();
+            let digest_var = cs.rescue_sponge_digest(&vars);
+            assert_eq!(
+                cs.witness[digest_var],
+                hash.rescue_sponge_digest(&values[..n])
+            );
+            let mut witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness, &[]));
+            witness[digest_var] = F::zero();
+            assert!(cs.verify_witness(&witness, &[]).is_err());
+        }
+
+        // the padding distinguishes inputs ending with zeroes or a one
+        let one = BLSScalar::from_u32(1);
+        let zero = BLSScalar::zero();
+        let inputs = [
+            vec![one],
+            vec![one, zero],
+            vec![one, one],
+            vec![one, zero, zero],
+        ];
+        let mut digests = vec![];
+        for input in inputs.iter() {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let vars: Vec<VarIndex> =
+                input.iter().map(|v| cs.new_variable(*v)).collect();
+            let digest_var = cs.rescue_sponge_digest(&vars);
+            assert!(!digests.contains(&cs.witness[digest_var]));
+            digests.push(cs.witness[digest_var]);
+        }
+    }
+
+    #[test]
+    fn test_hashed_public_inputs() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let values: Vec<BLSScalar> =
+            (0..8).map(|_| BLSScalar::random(&mut prng)).collect();
+        for n in [0, 1, 2, 3, 8] {
+            let mut cs = TurboPlonkConstraintSystem::new();
+            let vars: Vec<VarIndex> =
+                values[..n].iter().map(|v| cs.new_variable(*v)).collect();
+            let digest_var = cs.prepare_hashed_io_variables(&vars);
+            let digest = hashed_public_inputs(&values[..n]);
+            assert_eq!(cs.witness[digest_var], digest);
+
+            let mut witness = cs.get_and_clear_witness();
+            pnk!(cs.verify_witness(&witness, &[digest]));
+            assert!(cs.verify_witness(&witness, &[F::zero()]).is_err());
+            if n > 0 {
+                witness[vars[0]] = F::zero();
+                assert!(cs.verify_witness(&witness, &[digest]).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_public_inputs() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let values: Vec<BLSScalar> =
+            (0..3).map(|_| BLSScalar::random(&mut prng)).collect();
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let vars: Vec<VarIndex> = values.iter().map(|v| cs.new_variable(*v)).collect();
+        let sum_var = cs.add(vars[0], vars[1]);
+        cs.prepare_io_variable(vars[2]);
+        cs.prepare_io_variable(sum_var);
+        cs.prepare_io_variable(vars[0]);
+        let online = vec![values[2], values[0].add(&values[1]), values[0]];
+
+        let digest_var = cs.hash_public_inputs();
+        let digest = hashed_public_inputs(&online);
+        assert_eq!(cs.witness[digest_var], digest);
+        assert_eq!(cs.public_vars_witness_indices, vec![digest_var]);
+        let n_gates = cs.size;
+        let var_map = pnk!(cs.optimize());
+        // the 3 empty gates of the former public inputs
+        assert_eq!(cs.size, n_gates - 3);
+
+        let witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[digest]));
+        assert!(cs.verify_witness(&witness, &online).is_err());
+        let digest_var = var_map[digest_var].unwrap();
+        let mut wrong_witness = witness.clone();
+        wrong_witness[digest_var] = F::zero();
+        assert!(cs.verify_witness(&wrong_witness, &[digest]).is_err());
+    }
+
     #[test]
     fn test_rescue_cipher() {
         let cipher = RescueInstance::new();
@@ -521,4 +857,44 @@ mod test {
         witness[ctxts_vars[0]] = BLSScalar::from_u32(1);
         assert!(cs.verify_witness(&witness, &[]).is_err());
     }
+
+    #[test]
+    fn test_rescue_transcript() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let values: Vec<BLSScalar> =
+            (0..3).map(|_| BLSScalar::random(&mut prng)).collect();
+        let mut transcript = RescueTranscript::new(b"Test");
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let mut transcript_var = cs.new_rescue_transcript(b"Test");
+        let vars: Vec<VarIndex> = values.iter().map(|v| cs.new_variable(*v)).collect();
+
+        transcript.append_message(b"message", b"a message of more than 31 bytes ....");
+        cs.rescue_transcript_append_message(
+            &mut transcript_var,
+            b"message",
+            b"a message of more than 31 bytes ....",
+        );
+        transcript.append_scalar(b"scalar", &values[0]);
+        cs.rescue_transcript_append_scalar(&mut transcript_var, b"scalar", vars[0]);
+        let x = transcript.challenge(b"x");
+        let x_var = cs.rescue_transcript_challenge(&mut transcript_var, b"x");
+        assert_eq!(cs.witness[x_var], x);
+        // a challenge right after another one
+        let y: BLSScalar = transcript.challenge_field_elem(b"y");
+        let y_var = cs.rescue_transcript_challenge(&mut transcript_var, b"y");
+        assert_eq!(cs.witness[y_var], y);
+        assert_ne!(x, y);
+        for (value, var) in values[1..].iter().zip(vars[1..].iter()) {
+            transcript.append_scalar(b"scalar", value);
+            cs.rescue_transcript_append_scalar(&mut transcript_var, b"scalar", *var);
+        }
+        let z = transcript.challenge(b"z");
+        let z_var = cs.rescue_transcript_challenge(&mut transcript_var, b"z");
+        assert_eq!(cs.witness[z_var], z);
+
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[z_var] = F::zero();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
 }
@@ -0,0 +1,194 @@
+// This file implements the verification of Schnorr signatures over the Jubjub curve in a
+// constraint system, e.g. to check the spend authority of a key inside an anonymous transfer.
+// The challenge is computed with the Rescue sponge instead of the SHA-512 of
+// crypto::basics::signatures::schnorr, so these signatures are a different scheme:
+// * `sign(sk, m)` => sample a random scalar k and compute R = [k]G. Compute the challenge
+//   c = H(PK, R, m) = rescue_sponge_digest(PK.x, PK.y, R.x, R.y, m) and s = k + c * sk mod r_J.
+// * `verify(PK, m, (R, s))` => check that [s]G = R + [c]PK.
+// The messages are vectors of BLS scalars. The challenge c is a BLS scalar, which is multiplied
+// with PK as an integer: this is the same as with c mod r_J since PK is in the subgroup of order
+// r_J. In the circuit, c is decomposed in 255 bits, and this decomposition is unique up to adding
+// the BLS modulus to c, so a malicious prover gets at most two challenges per R, which does not
+// help it forge signatures. R is constrained to be on the curve, and then the equation implies
+// that R is in the subgroup of G and PK.
+
+use crate::plonk::turbo_plonk_cs::ecc::{
+    ExtendedPointVar, PointVar, JUBJUB_SCALAR_BIT_LEN,
+};
+use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+use algebra::bls12_381::BLSScalar;
+use algebra::groups::{Group, GroupArithmetic, Scalar, ScalarArithmetic};
+use algebra::jubjub::{JubjubPoint, JubjubScalar};
+use crypto::basics::hash::rescue::RescueInstance;
+use rand_core::{CryptoRng, RngCore};
+
+/// Number of bits of the integer representatives of BLS scalars, e.g. of the challenges
+const CHALLENGE_BIT_LEN: usize = 255;
+
+/// A Schnorr signature (R, s) with a Rescue challenge, see schnorr.rs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchnorrSignature {
+    pub r: JubjubPoint,
+    pub s: JubjubScalar,
+}
+
+/// The variables of a Schnorr signature: the point R and the integer representative of s
+pub struct SchnorrSignatureVar {
+    pub r: ExtendedPointVar,
+    pub s: VarIndex,
+}
+
+/// Returns the challenge H(PK, R, m) of a Schnorr signature (R, s) on `msg` for `pk`.
+pub fn schnorr_challenge(
+    pk: &JubjubPoint,
+    r: &JubjubPoint,
+    msg: &[BLSScalar],
+) -> BLSScalar {
+    let mut input = vec![pk.get_x(), pk.get_y(), r.get_x(), r.get_y()];
+    input.extend_from_slice(msg);
+    RescueInstance::new().rescue_sponge_digest(&input)
+}
+
+/// Signs `msg` with the secret key `sk`, for the public key [sk]G with G the Jubjub base.
+pub fn schnorr_sign<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    sk: &JubjubScalar,
+    msg: &[BLSScalar],
+) -> SchnorrSignature {
+    let base = JubjubPoint::get_base();
+    let pk = base.mul(sk);
+    let k = JubjubScalar::random(prng);
+    let r = base.mul(&k);
+    let challenge = schnorr_challenge(&pk, &r, msg).to_jubjub_scalar_mod_order();
+    SchnorrSignature {
+        r,
+        s: k.add(&challenge.mul(sk)),
+    }
+}
+
+/// Verifies the Schnorr signature `signature` on `msg` for the public key `pk`
+pub fn schnorr_verify(
+    pk: &JubjubPoint,
+    msg: &[BLSScalar],
+    signature: &SchnorrSignature,
+) -> bool {
+    let challenge =
+        schnorr_challenge(pk, &signature.r, msg).to_jubjub_scalar_mod_order();
+    JubjubPoint::get_base().mul(&signature.s) == signature.r.add(&pk.mul(&challenge))
+}
+
+impl TurboPlonkConstraintSystem<BLSScalar> {
+    /// Create variables for a Schnorr signature.
+    pub fn new_schnorr_signature_variable(
+        &mut self,
+        signature: &SchnorrSignature,
+    ) -> SchnorrSignatureVar {
+        SchnorrSignatureVar {
+            r: self.new_extended_point_variable(signature.r.clone()),
+            s: self.new_variable(BLSScalar::from(&signature.s)),
+        }
+    }
+
+    /// Verifies the Schnorr signature `sig_vars` on the messages `msg_vars` for the public key
+    /// `pk_var`: [s]G = R + [H(PK, R, m)]PK, with R on the curve.
+    /// The public key must be in the subgroup of order r_J, e.g. [sk]G for a secret key sk.
+    pub fn verify_schnorr(
+        &mut self,
+        pk_var: &ExtendedPointVar,
+        msg_vars: &[VarIndex],
+        sig_vars: &SchnorrSignatureVar,
+    ) {
+        let pk = pk_var.get_var();
+        let r = sig_vars.r.get_var();
+        self.insert_on_curve_gate(r);
+
+        let mut input_vars = vec![pk.get_x(), pk.get_y(), r.get_x(), r.get_y()];
+        input_vars.extend_from_slice(msg_vars);
+        let challenge_var = self.rescue_sponge_digest(&input_vars);
+
+        let (s_base_var, _) =
+            self.scalar_mul(JubjubPoint::get_base(), sig_vars.s, JUBJUB_SCALAR_BIT_LEN);
+        let (c_pk_var, c_pk) = self.var_base_scalar_mul(
+            PointVar::new(pk.get_x(), pk.get_y()),
+            pk_var.get_point().clone(),
+            challenge_var,
+            CHALLENGE_BIT_LEN,
+        );
+        let sum = self.ecc_add(r, &c_pk_var, sig_vars.r.get_point(), &c_pk);
+        self.equal(sum.get_var().get_x(), s_base_var.get_x());
+        self.equal(sum.get_var().get_y(), s_base_var.get_y());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::schnorr::{
+        schnorr_challenge, schnorr_sign, schnorr_verify, SchnorrSignature,
+    };
+    use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{Group, GroupArithmetic, One, Scalar, ScalarArithmetic, Zero};
+    use algebra::jubjub::{JubjubPoint, JubjubScalar};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_native_schnorr() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let sk = JubjubScalar::random(&mut prng);
+        let pk = JubjubPoint::get_base().mul(&sk);
+        let msg: Vec<F> = (0..3).map(|_| F::random(&mut prng)).collect();
+        let signature = schnorr_sign(&mut prng, &sk, &msg);
+        assert!(schnorr_verify(&pk, &msg, &signature));
+        assert!(!schnorr_verify(&pk, &msg[..2], &signature));
+        let other_pk = pk.double();
+        assert!(!schnorr_verify(&other_pk, &msg, &signature));
+        let wrong_signature = SchnorrSignature {
+            r: signature.r.clone(),
+            s: signature.s.add(&JubjubScalar::one()),
+        };
+        assert!(!schnorr_verify(&pk, &msg, &wrong_signature));
+        // the challenge depends on the public key
+        assert_ne!(
+            schnorr_challenge(&pk, &signature.r, &msg),
+            schnorr_challenge(&other_pk, &signature.r, &msg)
+        );
+    }
+
+    #[test]
+    fn test_verify_schnorr() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let sk = JubjubScalar::random(&mut prng);
+        let pk = JubjubPoint::get_base().mul(&sk);
+        let msg: Vec<F> = (0..5).map(|_| F::random(&mut prng)).collect();
+        let signature = schnorr_sign(&mut prng, &sk, &msg);
+
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let pk_var = cs.new_extended_point_variable(pk.clone());
+        let msg_vars: Vec<VarIndex> = msg.iter().map(|m| cs.new_variable(*m)).collect();
+        let sig_vars = cs.new_schnorr_signature_variable(&signature);
+        cs.verify_schnorr(&pk_var, &msg_vars, &sig_vars);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+
+        // wrong message
+        witness[msg_vars[0]] = F::zero();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        // wrong signature
+        let wrong_signature = SchnorrSignature {
+            r: signature.r.clone(),
+            s: signature.s.add(&JubjubScalar::one()),
+        };
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let pk_var = cs.new_extended_point_variable(pk);
+        let msg_vars: Vec<VarIndex> = msg.iter().map(|m| cs.new_variable(*m)).collect();
+        let sig_vars = cs.new_schnorr_signature_variable(&wrong_signature);
+        cs.verify_schnorr(&pk_var, &msg_vars, &sig_vars);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+}
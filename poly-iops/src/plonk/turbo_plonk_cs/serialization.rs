@@ -0,0 +1,361 @@
+// This file implements a versioned binary encoding of TurboPlonkConstraintSystem, so that a
+// circuit can be built once, stored, and loaded by provers and verifiers without rebuilding it
+// in code. The layout is canonical: two constraint systems with the same gates have the same
+// encoding, and the witness is never encoded.
+// Layout, integers in little endian:
+// - the magic bytes CS_MAGIC and the format version CS_FORMAT_VERSION (u32),
+// - the length in bytes of a field element (u32),
+// - size, num_vars, the number of selectors and the number of wires per gate (u64),
+// - the selectors, column after column, `size` field elements each,
+// - the wiring, column after column, `size` variable indices (u64) each,
+// - the public variables: their number (u64), then the constraint and the witness index (u64)
+//   of each of them,
+// - the reserved zero and one variables: a tag (u8), 0 for None and 1 for Some, followed by the
+//   variable index (u64) for Some,
+// - the lookup tables: their number (u64), then for each table its number of entries (u64)
+//   followed by the entries as (input, output) field elements,
+// - the lookup gates: their number (u64), then the constraint index and the table id (u64) of
+//   each of them.
+// Decoding checks that the counts are consistent with the remaining bytes before allocating,
+// and that every index is in bounds, so that a decoded constraint system can be used safely.
+// The serde encoding of TurboConstraintSystem, derived, encodes the wiring with wiring_serde, as
+// an array of wires for any number of wires per gate.
+
+use crate::plonk::errors::PlonkError;
+use crate::plonk::turbo_plonk_cs::{
+    CsIndex, TurboPlonkConstraintSystem, VarIndex, N_SELECTORS, N_WIRES_PER_GATE,
+};
+use algebra::groups::Scalar;
+use ruc::*;
+
+/// Magic bytes at the beginning of an encoded constraint system
+pub const CS_MAGIC: &[u8; 4] = b"ZTPC";
+
+/// Version of the encoding of constraint systems written by `to_bytes`
+pub const CS_FORMAT_VERSION: u32 = 1;
+
+// Reads the fields of an encoded constraint system from a byte slice
+struct BytesReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> BytesReader<'a> {
+    fn read(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read(1).c(d!())?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let mut array = [0u8; 4];
+        array.copy_from_slice(self.read(4).c(d!())?);
+        Ok(u32::from_le_bytes(array))
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        let mut array = [0u8; 8];
+        array.copy_from_slice(self.read(8).c(d!())?);
+        Ok(u64::from_le_bytes(array))
+    }
+
+    // Reads an index smaller than `bound`
+    fn read_index(&mut self, bound: usize) -> Result<usize> {
+        let index = self.read_u64().c(d!())?;
+        if index >= bound as u64 {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        Ok(index as usize)
+    }
+
+    // Reads a count of items of at least `item_len` bytes each, checked against the remaining
+    // bytes
+    fn read_count(&mut self, item_len: usize) -> Result<usize> {
+        let count = self.read_u64().c(d!())?;
+        self.check_count(count, item_len).c(d!())?;
+        Ok(count as usize)
+    }
+
+    fn check_count(&self, count: u64, item_len: usize) -> Result<()> {
+        match count.checked_mul(item_len as u64) {
+            Some(len) if len <= self.bytes.len() as u64 => Ok(()),
+            _ => Err(eg!(PlonkError::DeserializationError)),
+        }
+    }
+
+    fn read_scalar<F: Scalar>(&mut self, scalar_len: usize) -> Result<F> {
+        let bytes = self.read(scalar_len).c(d!())?;
+        let scalar = F::from_bytes(bytes).c(d!(PlonkError::DeserializationError))?;
+        // canonical encoding
+        if scalar.to_bytes() != bytes {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        Ok(scalar)
+    }
+
+    fn read_option_index(&mut self, bound: usize) -> Result<Option<usize>> {
+        match self.read_u8().c(d!())? {
+            0 => Ok(None),
+            1 => Ok(Some(self.read_index(bound).c(d!())?)),
+            _ => Err(eg!(PlonkError::DeserializationError)),
+        }
+    }
+}
+
+fn write_u64(bytes: &mut Vec<u8>, value: usize) {
+    bytes.extend_from_slice(&(value as u64).to_le_bytes());
+}
+
+fn write_option_index(bytes: &mut Vec<u8>, index: Option<usize>) {
+    match index {
+        None => bytes.push(0),
+        Some(index) => {
+            bytes.push(1);
+            write_u64(bytes, index);
+        }
+    }
+}
+
+impl<F: Scalar> TurboPlonkConstraintSystem<F> {
+    /// Encodes the constraint system, without its witness, in the binary format of version
+    /// CS_FORMAT_VERSION.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(CS_MAGIC);
+        bytes.extend_from_slice(&CS_FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(F::zero().to_bytes().len() as u32).to_le_bytes());
+        write_u64(&mut bytes, self.size);
+        write_u64(&mut bytes, self.num_vars);
+        write_u64(&mut bytes, self.selectors.len());
+        write_u64(&mut bytes, self.wiring.len());
+        for selector in self.selectors.iter() {
+            for value in selector.iter() {
+                bytes.extend_from_slice(&value.to_bytes());
+            }
+        }
+        for wire in self.wiring.iter() {
+            for var in wire.iter() {
+                write_u64(&mut bytes, *var);
+            }
+        }
+        write_u64(&mut bytes, self.public_vars_constraint_indices.len());
+        for (cs_index, var) in self
+            .public_vars_constraint_indices
+            .iter()
+            .zip(self.public_vars_witness_indices.iter())
+        {
+            write_u64(&mut bytes, *cs_index);
+            write_u64(&mut bytes, *var);
+        }
+        write_option_index(&mut bytes, self.zero_var);
+        write_option_index(&mut bytes, self.one_var);
+        write_u64(&mut bytes, self.lookup_tables.len());
+        for table in self.lookup_tables.iter() {
+            write_u64(&mut bytes, table.len());
+            for (input, output) in table.iter() {
+                bytes.extend_from_slice(&input.to_bytes());
+                bytes.extend_from_slice(&output.to_bytes());
+            }
+        }
+        write_u64(&mut bytes, self.lookup_gates.len());
+        for (cs_index, table_id) in self.lookup_gates.iter() {
+            write_u64(&mut bytes, *cs_index);
+            write_u64(&mut bytes, *table_id);
+        }
+        bytes
+    }
+
+    /// Decodes a constraint system encoded by `to_bytes`, with an empty witness.
+    /// Returns UnsupportedVersion for an encoding of another format version, and
+    /// DeserializationError for a malformed encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<TurboPlonkConstraintSystem<F>> {
+        let mut reader = BytesReader { bytes };
+        if reader.read(CS_MAGIC.len()).c(d!())? != CS_MAGIC {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        if reader.read_u32().c(d!())? != CS_FORMAT_VERSION {
+            return Err(eg!(PlonkError::UnsupportedVersion));
+        }
+        let scalar_len = reader.read_u32().c(d!())? as usize;
+        if scalar_len != F::zero().to_bytes().len() {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        let size = reader.read_u64().c(d!())?;
+        let num_vars = reader.read_u64().c(d!())?;
+        let n_selectors = reader.read_u64().c(d!())?;
+        let n_wires = reader.read_u64().c(d!())?;
+        if n_selectors != N_SELECTORS as u64 || n_wires != N_WIRES_PER_GATE as u64 {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        // the selectors and the wiring are the bulk of the encoding
+        let gate_len = N_SELECTORS * scalar_len + N_WIRES_PER_GATE * 8;
+        reader.check_count(size, gate_len).c(d!())?;
+        let size = size as usize;
+        let num_vars =
+            usize::try_from(num_vars).c(d!(PlonkError::DeserializationError))?;
+
+        let mut cs = TurboPlonkConstraintSystem::new();
+        cs.size = size;
+        cs.num_vars = num_vars;
+        for selector in cs.selectors.iter_mut() {
+            for _ in 0..size {
+                selector.push(reader.read_scalar(scalar_len).c(d!())?);
+            }
+        }
+        for wire in cs.wiring.iter_mut() {
+            for _ in 0..size {
+                wire.push(reader.read_index(num_vars).c(d!())?);
+            }
+        }
+        let n_public_vars = reader.read_count(16).c(d!())?;
+        for _ in 0..n_public_vars {
+            let cs_index: CsIndex = reader.read_index(size).c(d!())?;
+            let var: VarIndex = reader.read_index(num_vars).c(d!())?;
+            cs.public_vars_constraint_indices.push(cs_index);
+            cs.public_vars_witness_indices.push(var);
+        }
+        cs.zero_var = reader.read_option_index(num_vars).c(d!())?;
+        cs.one_var = reader.read_option_index(num_vars).c(d!())?;
+        let n_tables = reader.read_count(8).c(d!())?;
+        for _ in 0..n_tables {
+            let n_entries = reader.read_count(2 * scalar_len).c(d!())?;
+            if n_entries == 0 {
+                return Err(eg!(PlonkError::DeserializationError));
+            }
+            let mut table = Vec::with_capacity(n_entries);
+            for _ in 0..n_entries {
+                let input = reader.read_scalar(scalar_len).c(d!())?;
+                let output = reader.read_scalar(scalar_len).c(d!())?;
+                table.push((input, output));
+            }
+            cs.lookup_tables.push(table);
+        }
+        let n_lookup_gates = reader.read_count(16).c(d!())?;
+        for _ in 0..n_lookup_gates {
+            let cs_index = reader.read_index(size).c(d!())?;
+            let table_id = reader.read_index(n_tables).c(d!())?;
+            cs.lookup_gates.push((cs_index, table_id));
+        }
+        if !reader.bytes.is_empty() {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        Ok(cs)
+    }
+}
+
+// The serde encoding of the wiring of a constraint system with W wires per gate, the encoding
+// of an array of W wires that serde derives for the arrays of a fixed length
+pub(super) mod wiring_serde {
+    use crate::plonk::turbo_plonk_cs::VarIndex;
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::ser::SerializeTuple;
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer, const W: usize>(
+        wiring: &[Vec<VarIndex>; W],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(W)?;
+        for wire in wiring.iter() {
+            tuple.serialize_element(wire)?;
+        }
+        tuple.end()
+    }
+
+    struct WiringVisitor<const W: usize>;
+
+    impl<'de, const W: usize> Visitor<'de> for WiringVisitor<W> {
+        type Value = [Vec<VarIndex>; W];
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "an array of {} wires", W)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(
+            self,
+            mut seq: A,
+        ) -> Result<Self::Value, A::Error> {
+            let mut wiring: [Vec<VarIndex>; W] = std::array::from_fn(|_| vec![]);
+            for (i, wire) in wiring.iter_mut().enumerate() {
+                *wire = seq
+                    .next_element()?
+                    .ok_or_else(|| A::Error::invalid_length(i, &self))?;
+            }
+            Ok(wiring)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const W: usize>(
+        deserializer: D,
+    ) -> Result<[Vec<VarIndex>; W], D::Error> {
+        deserializer.deserialize_tuple(W, WiringVisitor::<W>)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::serialization::CS_FORMAT_VERSION;
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar};
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_cs_to_from_bytes() {
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let a = cs.new_variable(F::from_u32(3));
+        let b = cs.new_variable(F::from_u32(5));
+        let c = cs.mul(a, b);
+        let d = cs.linear_combine(&[a, b, c, c], F::one(), F::one(), F::one(), F::one());
+        let is_equal = cs.is_equal(c, d);
+        let table_id = cs.add_lookup_table(&[F::from_u32(0), F::from_u32(1)]);
+        cs.lookup(is_equal, table_id);
+        cs.prepare_io_variable(d);
+        cs.pad();
+        let witness = cs.get_and_clear_witness();
+        let online_vars = [F::from_u32(38)];
+        pnk!(cs.verify_witness(&witness, &online_vars));
+
+        let bytes = cs.to_bytes();
+        let decoded = pnk!(TurboPlonkConstraintSystem::<F>::from_bytes(&bytes));
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_eq!(decoded.size, cs.size);
+        assert_eq!(decoded.num_vars, cs.num_vars);
+        assert_eq!(decoded.selectors, cs.selectors);
+        assert_eq!(decoded.wiring, cs.wiring);
+        assert_eq!(decoded.lookup_tables, cs.lookup_tables);
+        assert_eq!(decoded.lookup_gates, cs.lookup_gates);
+        pnk!(decoded.verify_witness(&witness, &online_vars));
+
+        // malformed encodings
+        assert!(
+            TurboPlonkConstraintSystem::<F>::from_bytes(&bytes[..bytes.len() - 1])
+                .is_err()
+        );
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(TurboPlonkConstraintSystem::<F>::from_bytes(&longer).is_err());
+        let mut other_version = bytes.clone();
+        other_version[4..8].copy_from_slice(&(CS_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(TurboPlonkConstraintSystem::<F>::from_bytes(&other_version).is_err());
+        // a huge size is rejected before allocating
+        let mut huge_size = bytes.clone();
+        huge_size[12..20].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(TurboPlonkConstraintSystem::<F>::from_bytes(&huge_size).is_err());
+        // a wire out of bounds
+        let mut bad_wire = bytes;
+        let wiring_start = 44 + 13 * 32 * cs.size;
+        bad_wire[wiring_start..wiring_start + 8]
+            .copy_from_slice(&(cs.num_vars as u64).to_le_bytes());
+        assert!(TurboPlonkConstraintSystem::<F>::from_bytes(&bad_wire).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+// This file implements gadgets for signed integers of a fixed bit width in circuits.
+// A SignedVar of width n is a variable whose value is a signed integer x in [-2^(n-1), 2^(n-1)),
+// embedded in the field as x for x >= 0 and as the additive inverse of |x| for x < 0, i.e. the
+// field element with the two's-complement representation of x. The invariant x + 2^(n-1) in
+// [0, 2^n) is enforced by a range check when a SignedVar is created, and the output of every
+// operation is range checked again, so an overflow makes the constraints unsatisfiable instead of
+// wrapping modulo the field size.
+
+use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+use algebra::groups::Scalar;
+
+/// Maximal bit width of a SignedVar
+pub const SIGNED_MAX_BITS: usize = 64;
+
+/// A variable with a signed integer of `n_bits` bits, see signed.rs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignedVar {
+    pub var: VarIndex,
+    pub n_bits: usize,
+}
+
+impl<F: Scalar> TurboPlonkConstraintSystem<F> {
+    // Returns a variable with var + 2^(n_bits - 1), in [0, 2^n_bits) for a signed integer
+    pub(crate) fn signed_shift(&mut self, var: VarIndex, n_bits: usize) -> VarIndex {
+        let one_var = self.one_var();
+        let zero = F::zero();
+        let offset = F::from_u32(2).pow_u64(n_bits as u64 - 1);
+        self.linear_combine(&[var, one_var, 0, 0], F::one(), offset, zero, zero)
+    }
+
+    /// Range checks the value of `var` as a signed integer of `n_bits` bits, i.e. in
+    /// [-2^(n_bits-1), 2^(n_bits-1)), and returns it as a SignedVar.
+    pub fn signed_range_check(&mut self, var: VarIndex, n_bits: usize) -> SignedVar {
+        assert!(
+            (2..=SIGNED_MAX_BITS).contains(&n_bits),
+            "the number of bits of a signed integer is not between 2 and 64"
+        );
+        let shifted = self.signed_shift(var, n_bits);
+        self.range_check(shifted, n_bits);
+        SignedVar { var, n_bits }
+    }
+
+    /// Adds a variable with the signed integer `value` on `n_bits` bits.
+    pub fn new_signed_variable(&mut self, value: i64, n_bits: usize) -> SignedVar {
+        let var = self.new_variable(F::from_i64(value));
+        self.signed_range_check(var, n_bits)
+    }
+
+    fn check_same_width(left: &SignedVar, right: &SignedVar) -> usize {
+        assert_eq!(
+            left.n_bits, right.n_bits,
+            "signed integers of different widths"
+        );
+        left.n_bits
+    }
+
+    /// Returns left + right, the constraints are unsatisfiable if the sum overflows.
+    pub fn signed_add(&mut self, left: &SignedVar, right: &SignedVar) -> SignedVar {
+        let n_bits = Self::check_same_width(left, right);
+        let var = self.add(left.var, right.var);
+        self.signed_range_check(var, n_bits)
+    }
+
+    /// Returns left - right, the constraints are unsatisfiable if the difference overflows.
+    pub fn signed_sub(&mut self, left: &SignedVar, right: &SignedVar) -> SignedVar {
+        let n_bits = Self::check_same_width(left, right);
+        let var = self.sub(left.var, right.var);
+        self.signed_range_check(var, n_bits)
+    }
+
+    /// Returns left * right, the constraints are unsatisfiable if the product overflows.
+    /// The product of two integers of 64 bits does not wrap modulo the field size.
+    pub fn signed_mul(&mut self, left: &SignedVar, right: &SignedVar) -> SignedVar {
+        let n_bits = Self::check_same_width(left, right);
+        let var = self.mul(left.var, right.var);
+        self.signed_range_check(var, n_bits)
+    }
+
+    /// Returns -x, the constraints are unsatisfiable for x = -2^(n_bits-1).
+    pub fn signed_neg(&mut self, x: &SignedVar) -> SignedVar {
+        let zero_var = self.zero_var();
+        let var = self.sub(zero_var, x.var);
+        self.signed_range_check(var, x.n_bits)
+    }
+
+    /// Returns a boolean variable that equals 1 if and only if left >= right.
+    pub fn signed_greater_eq(
+        &mut self,
+        left: &SignedVar,
+        right: &SignedVar,
+    ) -> VarIndex {
+        let n_bits = Self::check_same_width(left, right);
+        // the shifted values are in [0, 2^n_bits) by the invariant of SignedVar
+        let left_shifted = self.signed_shift(left.var, n_bits);
+        let right_shifted = self.signed_shift(right.var, n_bits);
+        self.greater_eq(left_shifted, right_shifted, n_bits)
+    }
+
+    /// Returns a boolean variable that equals 1 if and only if left < right.
+    pub fn signed_less_than(&mut self, left: &SignedVar, right: &SignedVar) -> VarIndex {
+        let greater_eq = self.signed_greater_eq(left, right);
+        let one_var = self.one_var();
+        self.sub(one_var, greater_eq)
+    }
+
+    /// Returns a boolean variable that equals 1 if and only if x < 0, i.e. the sign bit of x,
+    /// the complement of the most significant bit of x + 2^(n_bits-1).
+    pub fn signed_is_negative(&mut self, x: &SignedVar) -> VarIndex {
+        let shifted = self.signed_shift(x.var, x.n_bits);
+        let bits = self.range_check(shifted, x.n_bits);
+        let one_var = self.one_var();
+        self.sub(one_var, bits[x.n_bits - 1])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar, Zero};
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_signed_arithmetic() {
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_signed_variable(-7, 8);
+        let b = cs.new_signed_variable(5, 8);
+        let sum = cs.signed_add(&a, &b);
+        let diff = cs.signed_sub(&b, &a);
+        let product = cs.signed_mul(&a, &b);
+        let neg = cs.signed_neg(&a);
+        assert_eq!(cs.witness[sum.var], F::from_i64(-2));
+        assert_eq!(cs.witness[diff.var], F::from_i64(12));
+        assert_eq!(cs.witness[product.var], F::from_i64(-35));
+        assert_eq!(cs.witness[neg.var], F::from_i64(7));
+
+        let a_less_than_b = cs.signed_less_than(&a, &b);
+        let a_greater_eq_b = cs.signed_greater_eq(&a, &b);
+        let b_less_than_a = cs.signed_less_than(&b, &a);
+        let a_is_negative = cs.signed_is_negative(&a);
+        let b_is_negative = cs.signed_is_negative(&b);
+        assert_eq!(cs.witness[a_less_than_b], F::one());
+        assert_eq!(cs.witness[a_greater_eq_b], F::zero());
+        assert_eq!(cs.witness[b_less_than_a], F::zero());
+        assert_eq!(cs.witness[a_is_negative], F::one());
+        assert_eq!(cs.witness[b_is_negative], F::zero());
+
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+        witness[a_less_than_b] = F::zero();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+
+    #[test]
+    fn test_signed_overflow() {
+        // -128 is the smallest integer of 8 bits
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_signed_variable(-128, 8);
+        let b = cs.new_signed_variable(127, 8);
+        let _ = cs.signed_add(&a, &b);
+        let witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_signed_variable(100, 8);
+        let b = cs.new_signed_variable(28, 8);
+        let _ = cs.signed_add(&a, &b);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_signed_variable(-128, 8);
+        let _ = cs.signed_neg(&a);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let _ = cs.new_signed_variable(128, 8);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+// This file implements the gadgets of the sparse Merkle trees of
+// crypto::merkle_tree::sparse_merkle_tree in a constraint system: the leaf of a key and a value,
+// the root of a tree from a leaf and its siblings, and the read and write of a key, so that a
+// circuit can prove that a map contains or does not contain a key, or that a map is updated
+// at a single key.
+
+use crate::plonk::turbo_plonk_cs::rescue::StateVar;
+use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+use algebra::bls12_381::BLSScalar;
+use crypto::merkle_tree::sparse_merkle_tree::SMTProof;
+
+// cs variables for the siblings of a sparse Merkle tree proof, from the leaf to the root
+pub struct SMTProofVars {
+    pub siblings: Vec<VarIndex>,
+}
+
+impl TurboPlonkConstraintSystem<BLSScalar> {
+    /// Adds the siblings of `proof` as variables
+    pub fn add_smt_proof_variables(&mut self, proof: &SMTProof) -> SMTProofVars {
+        SMTProofVars {
+            siblings: proof
+                .siblings
+                .iter()
+                .map(|sibling| self.new_variable(*sibling))
+                .collect(),
+        }
+    }
+
+    /// Returns the leaf of key `key_var` with value `value_var`, or the empty leaf if `value_var`
+    /// is None, as crypto::merkle_tree::sparse_merkle_tree::smt_leaf
+    pub fn smt_leaf(
+        &mut self,
+        key_var: VarIndex,
+        value_var: Option<VarIndex>,
+    ) -> VarIndex {
+        let zero_var = self.zero_var();
+        match value_var {
+            Some(value_var) => self
+                .rescue_hash(&StateVar::new([key_var, value_var, zero_var, zero_var]))
+                [0],
+            None => zero_var,
+        }
+    }
+
+    /// Returns the root of a sparse Merkle tree whose leaf at the position given by the little
+    /// endian bits `key_bits` is `leaf_var`, with the siblings `proof_vars`.
+    fn smt_compute_root(
+        &mut self,
+        key_bits: &[VarIndex],
+        leaf_var: VarIndex,
+        proof_vars: &SMTProofVars,
+    ) -> VarIndex {
+        self.merkle_compute_root(leaf_var, &proof_vars.siblings, key_bits)
+    }
+
+    /// Returns the root of a sparse Merkle tree in which key `key_var` has value `value_var`, or
+    /// is absent if `value_var` is None. The key is range-checked against the depth of the tree,
+    /// i.e. the number of siblings in `proof_vars`. Equality constraints between the returned
+    /// root and a public root prove that the map contains, or does not contain, the key.
+    pub fn smt_read(
+        &mut self,
+        key_var: VarIndex,
+        value_var: Option<VarIndex>,
+        proof_vars: &SMTProofVars,
+    ) -> VarIndex {
+        let key_bits = self.range_check(key_var, proof_vars.siblings.len());
+        let leaf_var = self.smt_leaf(key_var, value_var);
+        self.smt_compute_root(&key_bits, leaf_var, proof_vars)
+    }
+
+    /// Returns the roots of a sparse Merkle tree before and after setting the value of key
+    /// `key_var` from `old_value_var` to `new_value_var`, None standing for an absent key.
+    /// The two roots share the siblings `proof_vars`, so the other keys are left untouched.
+    pub fn smt_write(
+        &mut self,
+        key_var: VarIndex,
+        old_value_var: Option<VarIndex>,
+        new_value_var: Option<VarIndex>,
+        proof_vars: &SMTProofVars,
+    ) -> (VarIndex, VarIndex) {
+        let key_bits = self.range_check(key_var, proof_vars.siblings.len());
+        let old_leaf_var = self.smt_leaf(key_var, old_value_var);
+        let new_leaf_var = self.smt_leaf(key_var, new_value_var);
+        let old_root_var = self.smt_compute_root(&key_bits, old_leaf_var, proof_vars);
+        let new_root_var = self.smt_compute_root(&key_bits, new_leaf_var, proof_vars);
+        (old_root_var, new_root_var)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{Scalar, ScalarArithmetic, Zero};
+    use crypto::merkle_tree::sparse_merkle_tree::SparseMerkleTree;
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_smt_read_and_write() {
+        let mut tree = pnk!(SparseMerkleTree::new(4));
+        for key in [1u64, 6, 9] {
+            pnk!(tree.insert(key, F::from_u64(key + 100)));
+        }
+        let key = 6u64;
+        let old_value = F::from_u64(106);
+        let new_value = old_value.add(&F::from_u32(1));
+        let proof = pnk!(tree.prove(key));
+        let old_root = tree.root();
+        pnk!(tree.insert(key, new_value));
+        let new_root = tree.root();
+
+        // membership
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let key_var = cs.new_variable(F::from_u64(key));
+        let value_var = cs.new_variable(old_value);
+        let proof_vars = cs.add_smt_proof_variables(&proof);
+        let root_var = cs.smt_read(key_var, Some(value_var), &proof_vars);
+        cs.prepare_io_variable(root_var);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[old_root]));
+        assert!(cs.verify_witness(&witness, &[new_root]).is_err());
+        witness[value_var] = new_value;
+        assert!(cs.verify_witness(&witness, &[old_root]).is_err());
+
+        // state transition
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let key_var = cs.new_variable(F::from_u64(key));
+        let old_value_var = cs.new_variable(old_value);
+        let new_value_var = cs.new_variable(new_value);
+        let proof_vars = cs.add_smt_proof_variables(&proof);
+        let (old_root_var, new_root_var) = cs.smt_write(
+            key_var,
+            Some(old_value_var),
+            Some(new_value_var),
+            &proof_vars,
+        );
+        assert_eq!(cs.witness[old_root_var], old_root);
+        assert_eq!(cs.witness[new_root_var], new_root);
+        cs.prepare_io_variable(old_root_var);
+        cs.prepare_io_variable(new_root_var);
+        let mut witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[old_root, new_root]));
+        witness[proof_vars.siblings[2]] = F::zero();
+        assert!(cs.verify_witness(&witness, &[old_root, new_root]).is_err());
+
+        // insertion of an absent key
+        let key = 12u64;
+        let proof = pnk!(tree.prove(key));
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let key_var = cs.new_variable(F::from_u64(key));
+        let value_var = cs.new_variable(new_value);
+        let proof_vars = cs.add_smt_proof_variables(&proof);
+        let absent_root_var = cs.smt_read(key_var, None, &proof_vars);
+        let (old_root_var, new_root_var) =
+            cs.smt_write(key_var, None, Some(value_var), &proof_vars);
+        assert_eq!(cs.witness[absent_root_var], new_root);
+        assert_eq!(cs.witness[old_root_var], new_root);
+        pnk!(tree.insert(key, new_value));
+        assert_eq!(cs.witness[new_root_var], tree.root());
+        let witness = cs.get_and_clear_witness();
+        pnk!(cs.verify_witness(&witness, &[]));
+
+        // the key is range-checked against the depth
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let key_var = cs.new_variable(F::from_u64(key + 16));
+        let value_var = cs.new_variable(new_value);
+        let proof_vars = cs.add_smt_proof_variables(&proof);
+        let _ = cs.smt_read(key_var, Some(value_var), &proof_vars);
+        let witness = cs.get_and_clear_witness();
+        assert!(cs.verify_witness(&witness, &[]).is_err());
+    }
+}
@@ -0,0 +1,317 @@
+// This file implements a report of the cost of a constraint system, to attribute the gates and
+// the variables of a circuit to the gadgets that created them.
+// Circuit developers scope gadgets with push_namespace("label") and pop_namespace(). Namespaces
+// nest: the label of a gate is the path of the open namespaces, joined by '/', and the empty
+// string for the gates outside of any namespace. The constraint system records the gate and the
+// variable counts at each change of namespace, so the gates and the variables created between
+// two changes belong to the same label. The namespaces are not part of the circuit: they are not
+// serialized, and have no effect on the proofs.
+
+use crate::plonk::turbo_plonk_cs::{
+    CsIndex, TurboConstraintSystem, TurboPlonkConstraintSystem, VarIndex, N_SELECTORS,
+};
+use algebra::groups::Scalar;
+use std::fmt;
+
+/// The names of the selectors, in the order of TurboPlonkConstraintSystem::selectors
+pub const SELECTOR_NAMES: [&str; N_SELECTORS] = [
+    "q1", "q2", "q3", "q4", "qm1", "qm2", "qc", "q_ecc", "q_hash1", "q_hash2",
+    "q_hash3", "q_hash4", "qo",
+];
+
+/// The cost of the gates and the variables of a label, see stats.rs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GadgetStats {
+    pub label: String,
+    pub n_gates: usize,
+    pub n_vars: usize,
+    /// The number of gates of the label with a non-zero value for each selector
+    pub selector_usage: [usize; N_SELECTORS],
+}
+
+/// The cost of a constraint system, returned by TurboPlonkConstraintSystem::stats
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub n_gates: usize,
+    pub n_vars: usize,
+    pub n_public_inputs: usize,
+    pub n_lookup_gates: usize,
+    /// The number of gates with a non-zero value for each selector
+    pub selector_usage: [usize; N_SELECTORS],
+    /// The cost of each label, in the order of their first gate or variable
+    pub gadgets: Vec<GadgetStats>,
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} gates, {} variables, {} public inputs, {} lookup gates",
+            self.n_gates, self.n_vars, self.n_public_inputs, self.n_lookup_gates
+        )?;
+        write_selector_usage(f, &self.selector_usage)?;
+        for gadget in self.gadgets.iter() {
+            let label = if gadget.label.is_empty() {
+                "<root>"
+            } else {
+                &gadget.label
+            };
+            writeln!(
+                f,
+                "{}: {} gates, {} variables",
+                label, gadget.n_gates, gadget.n_vars
+            )?;
+            write_selector_usage(f, &gadget.selector_usage)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_selector_usage(
+    f: &mut fmt::Formatter<'_>,
+    usage: &[usize; N_SELECTORS],
+) -> fmt::Result {
+    let used: Vec<String> = SELECTOR_NAMES
+        .iter()
+        .zip(usage.iter())
+        .filter(|(_, &count)| count > 0)
+        .map(|(name, count)| format!("{}: {}", name, count))
+        .collect();
+    writeln!(f, "    {}", used.join(", "))
+}
+
+impl<F: Scalar, const W: usize, const S: usize> TurboConstraintSystem<F, W, S> {
+    /// Opens a namespace `label` nested in the current namespace: the next gates and
+    /// variables are attributed to it by `stats`, until the matching `pop_namespace`.
+    #[track_caller]
+    pub fn push_namespace(&mut self, label: &str) {
+        assert!(!label.is_empty(), "empty namespace label");
+        self.namespace_stack.push(label.to_string());
+        #[cfg(feature = "debug_gates")]
+        self.namespace_locations
+            .push(std::panic::Location::caller());
+        self.mark_namespace();
+    }
+
+    /// Closes the namespace opened by the last `push_namespace`.
+    pub fn pop_namespace(&mut self) {
+        assert!(
+            self.namespace_stack.pop().is_some(),
+            "no namespace to close"
+        );
+        #[cfg(feature = "debug_gates")]
+        self.namespace_locations.pop();
+        self.mark_namespace();
+    }
+
+    /// Returns the label of the current namespace, the path of the open namespaces.
+    pub fn namespace(&self) -> String {
+        self.namespace_stack.join("/")
+    }
+
+    // Records that the gates and the variables from now on belong to the current namespace
+    fn mark_namespace(&mut self) {
+        let label = self.namespace();
+        // a namespace without gates nor variables is dropped
+        if let Some((cs_index, var_index, _)) = self.namespace_marks.last() {
+            if *cs_index == self.size && *var_index == self.num_vars {
+                self.namespace_marks.pop();
+            }
+        }
+        self.namespace_marks.push((self.size, self.num_vars, label));
+    }
+}
+
+impl<F: Scalar> TurboPlonkConstraintSystem<F> {
+    // Records the namespaces of the gates and the variables of `other`, appended by
+    // TurboPlonkConstraintSystem::append: they are nested in the current namespace
+    pub(super) fn append_namespaces(&mut self, other: &TurboPlonkConstraintSystem<F>) {
+        let prefix = self.namespace();
+        let nest = |label: &str| match (prefix.is_empty(), label.is_empty()) {
+            (_, true) => prefix.clone(),
+            (true, false) => label.to_string(),
+            (false, false) => format!("{}/{}", prefix, label),
+        };
+        for (cs_index, var_index, label) in other.namespace_marks.iter() {
+            self.namespace_marks.push((
+                cs_index + self.size,
+                var_index + self.num_vars,
+                nest(label),
+            ));
+        }
+        #[cfg(feature = "debug_gates")]
+        for label in other.gate_labels.iter() {
+            let mut label = label.clone();
+            label.namespace = nest(&label.namespace);
+            self.gate_labels.push(label);
+        }
+        if !other.namespace_marks.is_empty() {
+            // the next gates and variables are in the current namespace
+            self.namespace_marks.push((
+                self.size + other.size,
+                self.num_vars + other.num_vars,
+                prefix,
+            ));
+        }
+    }
+
+    // Returns the label of each group of consecutive gates and variables, with the first
+    // constraint index and the first variable of the group
+    fn namespace_groups(&self) -> Vec<(CsIndex, VarIndex, String)> {
+        let mut groups = vec![(0, 0, String::new())];
+        groups.extend(self.namespace_marks.iter().cloned());
+        groups
+    }
+
+    /// Returns the number of gates, variables, and selectors used by the circuit, and by
+    /// each namespace label.
+    pub fn stats(&self) -> CircuitStats {
+        let groups = self.namespace_groups();
+        let mut gadgets: Vec<GadgetStats> = vec![];
+        for (i, (cs_start, var_start, label)) in groups.iter().enumerate() {
+            let (cs_end, var_end) = match groups.get(i + 1) {
+                Some((cs_index, var_index, _)) => (*cs_index, *var_index),
+                None => (self.size, self.num_vars),
+            };
+            if cs_end == *cs_start && var_end == *var_start {
+                continue;
+            }
+            let position = match gadgets.iter().position(|g| g.label == *label) {
+                Some(position) => position,
+                None => {
+                    gadgets.push(GadgetStats {
+                        label: label.clone(),
+                        n_gates: 0,
+                        n_vars: 0,
+                        selector_usage: [0; N_SELECTORS],
+                    });
+                    gadgets.len() - 1
+                }
+            };
+            let gadget = &mut gadgets[position];
+            gadget.n_gates += cs_end - cs_start;
+            gadget.n_vars += var_end - var_start;
+            for (usage, selector) in
+                gadget.selector_usage.iter_mut().zip(self.selectors.iter())
+            {
+                *usage += selector[*cs_start..cs_end]
+                    .iter()
+                    .filter(|q| **q != F::zero())
+                    .count();
+            }
+        }
+
+        let mut selector_usage = [0; N_SELECTORS];
+        for gadget in gadgets.iter() {
+            for (usage, count) in selector_usage.iter_mut().zip(gadget.selector_usage) {
+                *usage += count;
+            }
+        }
+        CircuitStats {
+            n_gates: self.size,
+            n_vars: self.num_vars,
+            n_public_inputs: self.public_vars_witness_indices.len(),
+            n_lookup_gates: self.lookup_gates.len(),
+            selector_usage,
+            gadgets,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::stats::SELECTOR_NAMES;
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::Scalar;
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_stats() {
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_variable(F::from_u32(3));
+        let b = cs.new_variable(F::from_u32(5));
+        cs.push_namespace("arith");
+        let c = cs.add(a, b);
+        cs.push_namespace("mul");
+        let _ = cs.mul(c, a);
+        assert_eq!(cs.namespace(), "arith/mul");
+        cs.pop_namespace();
+        let _ = cs.sub(c, b);
+        cs.pop_namespace();
+        cs.push_namespace("range");
+        let _ = cs.range_check(a, 8);
+        cs.pop_namespace();
+        // an empty namespace
+        cs.push_namespace("empty");
+        cs.pop_namespace();
+        cs.push_namespace("arith");
+        let _ = cs.add(a, a);
+        cs.pop_namespace();
+        cs.prepare_io_variable(c);
+
+        let stats = cs.stats();
+        assert_eq!(stats.n_gates, cs.size);
+        assert_eq!(stats.n_vars, cs.num_vars);
+        assert_eq!(stats.n_public_inputs, 1);
+        assert_eq!(stats.n_lookup_gates, 0);
+        let labels: Vec<&str> = stats.gadgets.iter().map(|g| g.label.as_str()).collect();
+        assert_eq!(labels, vec!["", "arith", "arith/mul", "range"]);
+        assert_eq!(
+            stats.gadgets.iter().map(|g| g.n_gates).sum::<usize>(),
+            stats.n_gates
+        );
+        assert_eq!(
+            stats.gadgets.iter().map(|g| g.n_vars).sum::<usize>(),
+            stats.n_vars
+        );
+
+        // the variables a, b and the public input gate are in the root namespace
+        assert_eq!(stats.gadgets[0].n_vars, 2);
+        assert_eq!(stats.gadgets[0].n_gates, 1);
+        // two additions and a subtraction
+        assert_eq!(stats.gadgets[1].n_gates, 3);
+        assert_eq!(stats.gadgets[1].n_vars, 3);
+        assert_eq!(stats.gadgets[2].n_gates, 1);
+        let qm1 = SELECTOR_NAMES
+            .iter()
+            .position(|name| *name == "qm1")
+            .unwrap();
+        assert_eq!(stats.gadgets[2].selector_usage[qm1], 1);
+        assert_eq!(
+            stats.selector_usage[qm1],
+            stats.gadgets[2].selector_usage[qm1] + 8
+        );
+        // 8 boolean gates and 3 linear combination gates
+        assert_eq!(stats.gadgets[3].n_gates, 11);
+        assert_eq!(stats.gadgets[3].n_vars, 10);
+        assert!(stats
+            .to_string()
+            .contains("arith/mul: 1 gates, 1 variables"));
+    }
+
+    #[test]
+    fn test_stats_of_appended_circuit() {
+        let mut section = TurboPlonkConstraintSystem::<F>::new();
+        let a = section.new_variable(F::from_u32(3));
+        let _ = section.add(a, a);
+        section.push_namespace("mul");
+        let _ = section.mul(a, a);
+        section.pop_namespace();
+
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let b = cs.new_variable(F::from_u32(5));
+        cs.push_namespace("section");
+        let _ = cs.append(&section);
+        cs.pop_namespace();
+        let _ = cs.sub(b, b);
+        let stats = cs.stats();
+        let gadgets: Vec<(&str, usize)> = stats
+            .gadgets
+            .iter()
+            .map(|g| (g.label.as_str(), g.n_gates))
+            .collect();
+        assert_eq!(gadgets, vec![("", 1), ("section", 1), ("section/mul", 1)]);
+    }
+}
@@ -0,0 +1,275 @@
+// This file implements gadget templates, to build many instances of the same gadget, e.g. the
+// windows of a scalar multiplication. A template records the gates of a gadget once, built by a
+// function `build(cs, input_vars) -> output_vars` on a constraint system with sample inputs. The
+// structure of the gates of the gadget must not depend on the values of its inputs, as for any
+// circuit.
+// An instance of the template copies its selectors, and its wiring with an offset: the input
+// variables of the template are replaced by the input variables of the instance, the reserved
+// zero and one variables by the ones of the constraint system, and the other variables of the
+// template by new variables, allocated after the last variable of the constraint system in the
+// same order. The witness of the new variables is computed by `build` on a scratch constraint
+// system with the inputs of the instance; with a constraint system without witness, e.g. for the
+// preprocessing of the circuit, see instantiate_without_witness, the gates are copied only.
+// The templates have neither public inputs nor lookup gates.
+
+use crate::plonk::turbo_plonk_cs::{
+    TurboPlonkConstraintSystem, VarIndex, N_WIRES_PER_GATE,
+};
+use algebra::groups::Scalar;
+
+// A variable of a template
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TemplateVar {
+    Input(usize),
+    Zero,
+    One,
+    // the index of a new variable of the instance
+    Local(usize),
+}
+
+/// The gates of a gadget, to instantiate with TurboPlonkConstraintSystem::instantiate
+pub struct GadgetTemplate<F: Scalar> {
+    build: fn(&mut TurboPlonkConstraintSystem<F>, &[VarIndex]) -> Vec<VarIndex>,
+    n_inputs: usize,
+    n_local_vars: usize,
+    vars: Vec<TemplateVar>,
+    selectors: Vec<Vec<F>>,
+    wiring: [Vec<VarIndex>; N_WIRES_PER_GATE],
+    output_vars: Vec<VarIndex>,
+}
+
+impl<F: Scalar> GadgetTemplate<F> {
+    /// Records the gates built by `build` on a constraint system with the input values
+    /// `sample_inputs`.
+    pub fn new(
+        sample_inputs: &[F],
+        build: fn(&mut TurboPlonkConstraintSystem<F>, &[VarIndex]) -> Vec<VarIndex>,
+    ) -> GadgetTemplate<F> {
+        let (mut cs, output_vars) = run_build(build, sample_inputs);
+        assert!(
+            cs.public_vars_witness_indices.is_empty(),
+            "public inputs in a gadget template"
+        );
+        assert!(
+            cs.lookup_gates.is_empty(),
+            "lookup gates in a gadget template"
+        );
+        let n_inputs = sample_inputs.len();
+        let mut n_local_vars = 0;
+        let vars = (0..cs.num_vars)
+            .map(|var| {
+                if var < n_inputs {
+                    TemplateVar::Input(var)
+                } else if cs.zero_var == Some(var) {
+                    TemplateVar::Zero
+                } else if cs.one_var == Some(var) {
+                    TemplateVar::One
+                } else {
+                    n_local_vars += 1;
+                    TemplateVar::Local(n_local_vars - 1)
+                }
+            })
+            .collect();
+        GadgetTemplate {
+            build,
+            n_inputs,
+            n_local_vars,
+            vars,
+            selectors: std::mem::take(&mut cs.selectors),
+            wiring: std::mem::take(&mut cs.wiring),
+            output_vars,
+        }
+    }
+
+    /// The number of gates of an instance
+    pub fn n_gates(&self) -> usize {
+        self.wiring[0].len()
+    }
+
+    /// The number of new variables of an instance
+    pub fn n_local_vars(&self) -> usize {
+        self.n_local_vars
+    }
+
+    // Returns the values of the new variables of an instance with inputs `inputs`
+    fn local_witness(&self, inputs: &[F]) -> Vec<F> {
+        let (cs, _) = run_build(self.build, inputs);
+        assert_eq!(
+            cs.size,
+            self.n_gates(),
+            "the gates of the gadget depend on its inputs"
+        );
+        self.vars
+            .iter()
+            .zip(cs.witness.iter())
+            .filter(|(var, _)| matches!(var, TemplateVar::Local(_)))
+            .map(|(_, value)| *value)
+            .collect()
+    }
+}
+
+// Builds the gadget `build` on a new constraint system, with input variables 0..inputs.len()
+fn run_build<F: Scalar>(
+    build: fn(&mut TurboPlonkConstraintSystem<F>, &[VarIndex]) -> Vec<VarIndex>,
+    inputs: &[F],
+) -> (TurboPlonkConstraintSystem<F>, Vec<VarIndex>) {
+    let mut cs = TurboPlonkConstraintSystem::new();
+    let input_vars: Vec<VarIndex> =
+        inputs.iter().map(|value| cs.new_variable(*value)).collect();
+    let output_vars = build(&mut cs, &input_vars);
+    (cs, output_vars)
+}
+
+impl<F: Scalar> TurboPlonkConstraintSystem<F> {
+    /// Adds an instance of the gadget `template` on the variables `input_vars`, and returns its
+    /// output variables, see template.rs.
+    pub fn instantiate(
+        &mut self,
+        template: &GadgetTemplate<F>,
+        input_vars: &[VarIndex],
+    ) -> Vec<VarIndex> {
+        let inputs: Vec<F> = input_vars.iter().map(|var| self.witness[*var]).collect();
+        let local_values = template.local_witness(&inputs);
+        self.instantiate_gates(template, input_vars, &local_values)
+    }
+
+    /// Same as instantiate for a constraint system without witness: the new variables have the
+    /// value zero. The gates are the gates of instantiate.
+    pub fn instantiate_without_witness(
+        &mut self,
+        template: &GadgetTemplate<F>,
+        input_vars: &[VarIndex],
+    ) -> Vec<VarIndex> {
+        self.instantiate_gates(
+            template,
+            input_vars,
+            &vec![F::zero(); template.n_local_vars],
+        )
+    }
+
+    fn instantiate_gates(
+        &mut self,
+        template: &GadgetTemplate<F>,
+        input_vars: &[VarIndex],
+        local_values: &[F],
+    ) -> Vec<VarIndex> {
+        assert_eq!(
+            input_vars.len(),
+            template.n_inputs,
+            "wrong number of inputs of the gadget template"
+        );
+        assert!(
+            input_vars.iter().all(|&var| var < self.num_vars),
+            "input variable index out of bound"
+        );
+        let uses_zero = template.vars.contains(&TemplateVar::Zero);
+        let uses_one = template.vars.contains(&TemplateVar::One);
+        let zero_var = if uses_zero { self.zero_var() } else { 0 };
+        let one_var = if uses_one { self.one_var() } else { 0 };
+        let offset = self.num_vars;
+        self.add_variables(local_values);
+        let var_map: Vec<VarIndex> = template
+            .vars
+            .iter()
+            .map(|var| match var {
+                TemplateVar::Input(i) => input_vars[*i],
+                TemplateVar::Zero => zero_var,
+                TemplateVar::One => one_var,
+                TemplateVar::Local(i) => offset + i,
+            })
+            .collect();
+
+        for (selector, template_selector) in
+            self.selectors.iter_mut().zip(template.selectors.iter())
+        {
+            selector.extend_from_slice(template_selector);
+        }
+        for (wire, template_wire) in self.wiring.iter_mut().zip(template.wiring.iter()) {
+            wire.extend(template_wire.iter().map(|var| var_map[*var]));
+        }
+        #[cfg(feature = "debug_gates")]
+        for _ in 0..template.n_gates() {
+            self.record_gate_label();
+        }
+        self.size += template.n_gates();
+
+        template
+            .output_vars
+            .iter()
+            .map(|var| var_map[*var])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::template::GadgetTemplate;
+    use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar, Zero};
+    use ruc::*;
+
+    type F = BLSScalar;
+
+    // (x^3 + y, x + 1), with a range check of y
+    fn gadget(
+        cs: &mut TurboPlonkConstraintSystem<F>,
+        vars: &[VarIndex],
+    ) -> Vec<VarIndex> {
+        let x2 = cs.mul(vars[0], vars[0]);
+        let x3 = cs.mul(x2, vars[0]);
+        let out = cs.add(x3, vars[1]);
+        let _ = cs.range_check(vars[1], 8);
+        let one_var = cs.one_var();
+        let x_plus_one = cs.add(vars[0], one_var);
+        vec![out, x_plus_one]
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let template = GadgetTemplate::new(&[F::zero(), F::zero()], gadget);
+        let mut cs = TurboPlonkConstraintSystem::new();
+        let one_var = cs.one_var();
+        cs.insert_constant_gate(one_var, F::one());
+        let mut expected_cs = TurboPlonkConstraintSystem::new();
+        let expected_one_var = expected_cs.one_var();
+        expected_cs.insert_constant_gate(expected_one_var, F::one());
+
+        let mut outputs = vec![];
+        let mut expected_outputs = vec![];
+        for (x, y) in [(2u32, 5u32), (3, 200)] {
+            let x_var = cs.new_variable(F::from_u32(x));
+            let y_var = cs.new_variable(F::from_u32(y));
+            outputs.extend(cs.instantiate(&template, &[x_var, y_var]));
+            let x_var = expected_cs.new_variable(F::from_u32(x));
+            let y_var = expected_cs.new_variable(F::from_u32(y));
+            expected_outputs.extend(gadget(&mut expected_cs, &[x_var, y_var]));
+        }
+        assert_eq!(outputs, expected_outputs);
+        assert_eq!(cs.size, 1 + 2 * template.n_gates());
+        // the wires of the instances are the wires of the gadget, except the wires unused by
+        // the selectors, that point to the first input instead of the variable 0
+        assert_eq!(cs.selectors, expected_cs.selectors);
+        assert_eq!(cs.wiring[4], expected_cs.wiring[4]);
+        assert_eq!(cs.num_vars, expected_cs.num_vars);
+
+        let witness = cs.get_and_clear_witness();
+        assert_eq!(witness, expected_cs.get_and_clear_witness());
+        assert_eq!(witness[outputs[0]], F::from_u32(13));
+        assert_eq!(witness[outputs[3]], F::from_u32(4));
+        pnk!(cs.verify_witness(&witness, &[]));
+
+        // the gates of an instance without witness are the same
+        let mut cs_without_witness = TurboPlonkConstraintSystem::new();
+        let one_var = cs_without_witness.one_var();
+        cs_without_witness.insert_constant_gate(one_var, F::one());
+        for _ in 0..2 {
+            let x_var = cs_without_witness.new_variable(F::zero());
+            let y_var = cs_without_witness.new_variable(F::zero());
+            let _ = cs_without_witness
+                .instantiate_without_witness(&template, &[x_var, y_var]);
+        }
+        assert_eq!(cs_without_witness.selectors, cs.selectors);
+        assert_eq!(cs_without_witness.wiring, cs.wiring);
+    }
+}
@@ -0,0 +1,111 @@
+// This file implements a harness to test that the gates of a gadget constrain all its
+// variables, compiled for the tests and with the feature `testing` for the gadgets of other
+// crates. Given a constraint system and a valid witness, the harness mutates each entry of the
+// witness in turn, to its value plus one and minus one, and checks that verify_witness rejects
+// the mutated witness, with the public inputs of the valid witness. A variable whose mutation is
+// accepted is not determined by the other variables and the public inputs: the circuit is
+// under-constrained, e.g. a result of a gadget that is computed in the witness but not checked by
+// any gate, or a bit that is only boolean constrained.
+// The reserved zero and one variables are constrained only if a gate of the circuit uses them
+// with a non-zero selector, or if they are pinned by insert_constant_gate. A variable that is
+// not used by any gate is reported as well.
+// Each mutation verifies the whole witness, so the harness is quadratic in the size of the
+// circuit, and is meant for the tests of gadgets, not of full circuits.
+
+use crate::plonk::turbo_plonk_cs::{TurboPlonkConstraintSystem, VarIndex};
+use algebra::groups::Scalar;
+
+/// Returns the variables of `cs` whose value can be changed in the valid witness `witness`
+/// without violating the constraints. Panics if `witness` is not a valid witness of `cs`.
+pub fn unconstrained_variables<F: Scalar>(
+    cs: &TurboPlonkConstraintSystem<F>,
+    witness: &[F],
+) -> Vec<VarIndex> {
+    let online_vars: Vec<F> = cs
+        .public_vars_witness_indices
+        .iter()
+        .map(|&var| witness[var])
+        .collect();
+    if let Err(e) = cs.verify_witness(witness, &online_vars) {
+        panic!("invalid witness: {}", e);
+    }
+    let mut mutated = witness.to_vec();
+    let mut unconstrained = vec![];
+    for (var, &value) in witness.iter().enumerate() {
+        for mutation in [value.add(&F::one()), value.sub(&F::one())] {
+            mutated[var] = mutation;
+            if cs.verify_witness(&mutated, &online_vars).is_ok() {
+                unconstrained.push(var);
+                break;
+            }
+        }
+        mutated[var] = value;
+    }
+    unconstrained
+}
+
+/// Asserts that the gates of `cs` constrain every variable of the valid witness `witness`,
+/// see unconstrained_variables.
+pub fn assert_fully_constrained<F: Scalar>(
+    cs: &TurboPlonkConstraintSystem<F>,
+    witness: &[F],
+) {
+    let unconstrained = unconstrained_variables(cs, witness);
+    assert!(
+        unconstrained.is_empty(),
+        "the constraints do not determine the variables {:?}",
+        unconstrained
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plonk::turbo_plonk_cs::testing::{
+        assert_fully_constrained, unconstrained_variables,
+    };
+    use crate::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{Scalar, Zero};
+
+    type F = BLSScalar;
+
+    #[test]
+    fn test_assert_fully_constrained() {
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_variable(F::from_u32(3));
+        let b = cs.new_variable(F::from_u32(5));
+        let c = cs.mul(a, b);
+        let d = cs.add(c, a);
+        let _ = cs.range_check(d, 8);
+        cs.prepare_io_variable(d);
+        let witness = cs.get_and_clear_witness();
+        assert_fully_constrained(&cs, &witness);
+    }
+
+    #[test]
+    fn test_unconstrained_variables() {
+        // a * b = 0 does not determine a when b = 0
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        let a = cs.new_variable(F::from_u32(3));
+        let b = cs.new_variable(F::zero());
+        let zero_var = cs.zero_var();
+        cs.insert_mul_gate(a, b, zero_var);
+        cs.insert_constant_gate(zero_var, F::zero());
+        cs.insert_constant_gate(b, F::zero());
+        // a variable without gates
+        let unused = cs.new_variable(F::from_u32(7));
+        let witness = cs.get_and_clear_witness();
+        assert_eq!(unconstrained_variables(&cs, &witness), vec![a, unused]);
+    }
+
+    #[test]
+    #[should_panic(expected = "the constraints do not determine the variables")]
+    fn test_assert_fully_constrained_fails() {
+        let mut cs = TurboPlonkConstraintSystem::<F>::new();
+        // a boolean gate alone accepts both values of the bit
+        let bit = cs.new_variable(F::from_u32(1));
+        cs.insert_boolean_gate(bit);
+        let witness = cs.get_and_clear_witness();
+        assert_fully_constrained(&cs, &witness);
+    }
+}
@@ -0,0 +1,170 @@
+// This file implements the binary encoding of the verifier key of a circuit, the verifier
+// parameters computed by plonk_setup::preprocess_verifier: the commitments to the selectors and
+// the extended permutations, the constants k_i of the wire cosets, the root of unity of the
+// group H, the indices and Lagrange constants of the public inputs, the digest of the SRS, the
+// protocol version and the commitments of the lookup gates. The key holds no polynomial nor
+// evaluation, so that light clients can store the key of a circuit and verify its proofs
+// without ever preprocessing or loading the prover parameters.
+// Layout: the magic bytes VK_MAGIC, the format version VK_FORMAT_VERSION (u32, little endian),
+// then the bincode encoding of the fields of PlonkVerifierParams in order, with fixed size
+// integers, in which the commitments are compressed group elements that are validated when
+// decoded. As the format version records the layout, the fields are encoded without the
+// version tag of the serde encoding of PlonkVerifierParams. Decoding rejects trailing bytes.
+
+use crate::commitments::pcs::PolyComScheme;
+use crate::plonk::errors::PlonkError;
+use crate::plonk::plonk_setup::{PlonkVerifierParams, VerifierParams};
+use bincode::Options;
+use ruc::*;
+use serde::de::{DeserializeOwned, DeserializeSeed};
+use serde::{Deserializer, Serialize};
+use std::marker::PhantomData;
+
+/// The verifier key of a circuit, see verifier_key.rs
+pub type PlonkVerifierKey<PCS> = VerifierParams<PCS>;
+
+/// Magic bytes at the beginning of an encoded verifier key
+pub const VK_MAGIC: &[u8; 4] = b"ZPVK";
+
+/// Version of the encoding of verifier keys written by `to_bytes`
+pub const VK_FORMAT_VERSION: u32 = 1;
+
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+}
+
+// Decoding of the fields of the parameters in order, see verifier_key.rs
+struct FieldsDecoding<C, F>(PhantomData<(C, F)>);
+
+impl<'de, C: DeserializeOwned, F: DeserializeOwned> DeserializeSeed<'de>
+    for FieldsDecoding<C, F>
+{
+    type Value = PlonkVerifierParams<C, F>;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> core::result::Result<Self::Value, D::Error> {
+        PlonkVerifierParams::deserialize_fields(deserializer)
+    }
+}
+
+impl<C: Serialize + DeserializeOwned, F: Serialize + DeserializeOwned>
+    PlonkVerifierParams<C, F>
+{
+    /// Encodes the verifier key in the binary format of version VK_FORMAT_VERSION.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(VK_MAGIC);
+        bytes.extend_from_slice(&VK_FORMAT_VERSION.to_le_bytes());
+        // safe unwrap: the encoding of the parameters into a vector does not fail
+        bytes.extend(bincode_options().serialize(&self.fields_ref()).unwrap());
+        bytes
+    }
+
+    /// Decodes a verifier key encoded by `to_bytes`.
+    /// Returns UnsupportedVersion for an encoding of another format version, and
+    /// DeserializationError for a malformed encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PlonkVerifierParams<C, F>> {
+        let header_len = VK_MAGIC.len() + 4;
+        if bytes.len() < header_len || &bytes[..VK_MAGIC.len()] != VK_MAGIC {
+            return Err(eg!(PlonkError::DeserializationError));
+        }
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&bytes[VK_MAGIC.len()..header_len]);
+        if u32::from_le_bytes(version) != VK_FORMAT_VERSION {
+            return Err(eg!(PlonkError::UnsupportedVersion));
+        }
+        bincode_options()
+            .deserialize_seed(FieldsDecoding(PhantomData), &bytes[header_len..])
+            .c(d!(PlonkError::DeserializationError))
+    }
+}
+
+/// Decodes the verifier key of a circuit for the commitment scheme `PCS`, see
+/// PlonkVerifierParams::from_bytes.
+pub fn verifier_key_from_bytes<PCS: PolyComScheme>(
+    bytes: &[u8],
+) -> Result<PlonkVerifierKey<PCS>> {
+    PlonkVerifierParams::from_bytes(bytes).c(d!())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitments::kzg_poly_com::{
+        KZGCommitmentScheme, KZGCommitmentSchemeBLS,
+    };
+    use crate::plonk::plonk_setup::{
+        preprocess_prover, preprocess_verifier, PlonkConstraintSystem,
+    };
+    use crate::plonk::protocol::prover::{prover, verifier};
+    use crate::plonk::verifier_key::{
+        verifier_key_from_bytes, VK_FORMAT_VERSION, VK_MAGIC,
+    };
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, ScalarArithmetic};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_verifier_key_bytes() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let pcs = KZGCommitmentScheme::new(20, &mut prng);
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+        let three = two.add(&one);
+        let mut cs = PlonkConstraintSystem::<BLSScalar>::new(3);
+        cs.insert_add_gate(0, 1, 2);
+        cs.prepare_io_variable(2);
+        cs.pad();
+
+        let seed = [0u8; 32];
+        let prover_params = preprocess_prover(&cs, &pcs, seed).unwrap();
+        let vk = preprocess_verifier(&cs, &pcs, seed).unwrap();
+        let bytes = vk.to_bytes();
+        assert_eq!(prover_params.get_verifier_params_ref().to_bytes(), bytes);
+        assert_eq!(&bytes[..4], VK_MAGIC);
+
+        let decoded = verifier_key_from_bytes::<KZGCommitmentSchemeBLS>(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        let mut transcript = Transcript::new(b"TestPlonk");
+        let proof = prover(
+            &mut prng,
+            &mut transcript,
+            &pcs,
+            &cs,
+            &prover_params,
+            &[one, two, three],
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"TestPlonk");
+        assert!(
+            verifier(&mut transcript, &pcs, &cs, &decoded, &[three], &proof).is_ok()
+        );
+
+        // trailing bytes
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(verifier_key_from_bytes::<KZGCommitmentSchemeBLS>(&longer).is_err());
+        // truncated key
+        assert!(verifier_key_from_bytes::<KZGCommitmentSchemeBLS>(
+            &bytes[..bytes.len() - 1]
+        )
+        .is_err());
+        // another format version
+        let mut other_version = bytes.clone();
+        other_version[4..8].copy_from_slice(&(VK_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(
+            verifier_key_from_bytes::<KZGCommitmentSchemeBLS>(&other_version).is_err()
+        );
+        // wrong magic bytes
+        let mut other_magic = bytes;
+        other_magic[0] ^= 1;
+        assert!(
+            verifier_key_from_bytes::<KZGCommitmentSchemeBLS>(&other_magic).is_err()
+        );
+    }
+}
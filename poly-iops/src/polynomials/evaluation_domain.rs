@@ -0,0 +1,240 @@
+// Evaluation domains of the FFT: a multiplicative subgroup H = <generator> of the scalar field
+// of order n, or one of its cosets offset * H.
+// The PLONK prover interpolates the witness over H and computes the quotient polynomial over a
+// coset k * H' of a larger subgroup H' (see PlonkProverParams::quot_eval_domain). Custom gates
+// and external protocols can evaluate their polynomials over the same domains with this API.
+// The vanishing polynomial of offset * H is X^n - offset^n.
+// The FFT supports the sizes of the form 2^k, and 3 * 2^k with a radix-3 step, so that the
+// smallest domain of size at least s has less than 1.5 * s points instead of nearly 2 * s
+// (see fft_size_at_least).
+
+use crate::polynomials::field_polynomial::{primitive_nth_root_of_unity, FpPolynomial};
+use algebra::groups::Scalar;
+
+/// Returns true if the FFT supports domains of `size` elements, of the form 2^k or 3 * 2^k
+pub fn is_fft_size(size: usize) -> bool {
+    size.is_power_of_two() || (size % 3 == 0 && (size / 3).is_power_of_two())
+}
+
+/// Returns the smallest size supported by the FFT that is at least `min_size`
+/// # Example
+/// ```
+/// use poly_iops::polynomials::evaluation_domain::fft_size_at_least;
+/// assert_eq!(fft_size_at_least(5), 6);
+/// assert_eq!(fft_size_at_least(7), 8);
+/// assert_eq!(fft_size_at_least(1025), 1536);
+/// ```
+pub fn fft_size_at_least(min_size: usize) -> usize {
+    let power_of_two = min_size.max(1).next_power_of_two();
+    if power_of_two >= 4 && power_of_two / 4 * 3 >= min_size {
+        power_of_two / 4 * 3
+    } else {
+        power_of_two
+    }
+}
+
+/// Multiplicative subgroup of order `size` of the field, or a coset of it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvaluationDomain<F> {
+    size: usize,
+    generator: F,
+    offset: F,
+    offset_inv: F,
+}
+
+impl<F: Scalar> EvaluationDomain<F> {
+    /// Returns the subgroup of the `size`-th roots of unity, or None if the field has no
+    /// such subgroup. `size` is with the form 2^k or 3 * 2^k
+    /// # Example
+    /// ```
+    /// use poly_iops::polynomials::evaluation_domain::EvaluationDomain;
+    /// use algebra::bls12_381::BLSScalar;
+    /// use algebra::groups::One;
+    /// let domain = EvaluationDomain::<BLSScalar>::new(4).unwrap();
+    /// assert_eq!(domain.size(), 4);
+    /// assert_eq!(domain.offset(), &BLSScalar::one());
+    /// assert_eq!(domain.elements().count(), 4);
+    /// ```
+    pub fn new(size: usize) -> Option<Self> {
+        if !is_fft_size(size) {
+            return None;
+        }
+        let generator = primitive_nth_root_of_unity(size)?;
+        Some(Self::from_generator(size, generator, F::one()))
+    }
+
+    // `generator` must be a primitive `size`-th root of unity, and `offset` non zero
+    pub(crate) fn from_generator(size: usize, generator: F, offset: F) -> Self {
+        EvaluationDomain {
+            size,
+            generator,
+            offset,
+            offset_inv: offset.inv().unwrap(),
+        }
+    }
+
+    /// Returns the coset offset * H of the subgroup H of the domain, or None if `offset` is zero
+    /// # Example
+    /// ```
+    /// use poly_iops::polynomials::evaluation_domain::EvaluationDomain;
+    /// use algebra::bls12_381::BLSScalar;
+    /// use algebra::groups::{One, Zero, ScalarArithmetic};
+    /// let domain = EvaluationDomain::<BLSScalar>::new(4).unwrap();
+    /// let two = BLSScalar::one().add(&BLSScalar::one());
+    /// let coset = domain.coset(two).unwrap();
+    /// assert_eq!(coset.element(0), two);
+    /// assert_eq!(coset.element(1), two.mul(domain.generator()));
+    /// assert!(domain.coset(BLSScalar::zero()).is_none());
+    /// ```
+    pub fn coset(&self, offset: F) -> Option<Self> {
+        if offset.is_zero() {
+            return None;
+        }
+        Some(Self::from_generator(self.size, self.generator, offset))
+    }
+
+    /// Returns the number of elements of the domain
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the generator of the subgroup, a primitive root of unity of order the size of
+    /// the domain
+    pub fn generator(&self) -> &F {
+        &self.generator
+    }
+
+    /// Returns the offset of the coset, which is one for the subgroup itself
+    pub fn offset(&self) -> &F {
+        &self.offset
+    }
+
+    /// Returns the i-th element of the domain, offset * generator^i
+    pub fn element(&self, i: usize) -> F {
+        self.offset.mul(&self.generator.pow_u64(i as u64))
+    }
+
+    /// Iterates over the elements of the domain, in the order of the FFT evaluations
+    pub fn elements(&self) -> impl Iterator<Item = F> + '_ {
+        let mut current = self.offset;
+        (0..self.size).map(move |_| {
+            let element = current;
+            current.mul_assign(&self.generator);
+            element
+        })
+    }
+
+    /// Returns the vanishing polynomial X^n - offset^n of the domain
+    pub fn vanishing_polynomial(&self) -> FpPolynomial<F> {
+        let mut coefs = vec![F::zero(); self.size + 1];
+        coefs[0] = self.offset.pow_u64(self.size as u64).neg();
+        coefs[self.size] = F::one();
+        FpPolynomial::from_coefs(coefs)
+    }
+
+    /// Evaluates the vanishing polynomial of the domain at `point`
+    /// # Example
+    /// ```
+    /// use poly_iops::polynomials::evaluation_domain::EvaluationDomain;
+    /// use algebra::bls12_381::BLSScalar;
+    /// use algebra::groups::{One, Zero, ScalarArithmetic};
+    /// let domain = EvaluationDomain::<BLSScalar>::new(8).unwrap();
+    /// let two = BLSScalar::one().add(&BLSScalar::one());
+    /// let coset = domain.coset(two).unwrap();
+    /// for element in coset.elements() {
+    ///     assert_eq!(coset.evaluate_vanishing_polynomial(&element), BLSScalar::zero());
+    ///     assert_ne!(domain.evaluate_vanishing_polynomial(&element), BLSScalar::zero());
+    /// }
+    /// ```
+    pub fn evaluate_vanishing_polynomial(&self, point: &F) -> F {
+        point
+            .pow_u64(self.size as u64)
+            .sub(&self.offset.pow_u64(self.size as u64))
+    }
+
+    /// Evaluates `polynomial` on the elements of the domain, in the order of `elements()`.
+    /// The degree of the polynomial must be lower than the size of the domain
+    pub fn fft(&self, polynomial: &FpPolynomial<F>) -> Vec<F> {
+        assert!(polynomial.degree() < self.size);
+        polynomial.coset_fft_with_unity_root(&self.generator, self.size, &self.offset)
+    }
+
+    /// Interpolates the polynomial of degree lower than the size of the domain with
+    /// evaluations `values` on the elements of the domain
+    /// # Example
+    /// ```
+    /// use poly_iops::polynomials::evaluation_domain::EvaluationDomain;
+    /// use poly_iops::polynomials::field_polynomial::FpPolynomial;
+    /// use algebra::bls12_381::BLSScalar;
+    /// use algebra::groups::{One, ScalarArithmetic};
+    /// let one = BLSScalar::one();
+    /// let two = one.add(&one);
+    /// let coset = EvaluationDomain::<BLSScalar>::new(4).unwrap().coset(two).unwrap();
+    /// let poly = FpPolynomial::from_coefs(vec![one, two, one]);
+    /// let evals = coset.fft(&poly);
+    /// assert_eq!(evals[1], poly.eval(&coset.element(1)));
+    /// assert_eq!(coset.ifft(&evals), poly);
+    /// ```
+    pub fn ifft(&self, values: &[F]) -> FpPolynomial<F> {
+        assert_eq!(values.len(), self.size);
+        FpPolynomial::coset_ffti(&self.generator, values, &self.offset_inv)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::polynomials::evaluation_domain::{
+        fft_size_at_least, is_fft_size, EvaluationDomain,
+    };
+    use crate::polynomials::field_polynomial::FpPolynomial;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar, Zero};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn test_fft_sizes() {
+        for min_size in 0..200 {
+            let size = fft_size_at_least(min_size);
+            assert!(is_fft_size(size));
+            assert!(size >= min_size);
+            assert!(((min_size.max(1))..size).all(|smaller| !is_fft_size(smaller)));
+        }
+        assert!(!is_fft_size(0));
+        assert!(!is_fft_size(9));
+    }
+
+    #[test]
+    fn test_evaluation_domain() {
+        assert!(EvaluationDomain::<BLSScalar>::new(5).is_none());
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        for size in [1, 4, 12, 16] {
+            let subgroup = EvaluationDomain::<BLSScalar>::new(size).unwrap();
+            let coset = subgroup.coset(BLSScalar::random(&mut prng)).unwrap();
+            assert_eq!(subgroup.generator().pow_u64(size as u64), BLSScalar::one());
+            let coefs = (0..size).map(|_| BLSScalar::random(&mut prng)).collect();
+            let polynomial = FpPolynomial::from_coefs(coefs);
+            for domain in [subgroup, coset] {
+                let elements: Vec<BLSScalar> = domain.elements().collect();
+                assert_eq!(elements.len(), size);
+                let vanishing = domain.vanishing_polynomial();
+                let evals = domain.fft(&polynomial);
+                for (i, element) in elements.iter().enumerate() {
+                    assert_eq!(&domain.element(i), element);
+                    assert_eq!(vanishing.eval(element), BLSScalar::zero());
+                    assert_eq!(
+                        domain.evaluate_vanishing_polynomial(element),
+                        BLSScalar::zero()
+                    );
+                    assert_eq!(evals[i], polynomial.eval(element));
+                }
+                assert_eq!(domain.ifft(&evals), polynomial);
+                let point = BLSScalar::random(&mut prng);
+                assert_eq!(
+                    domain.evaluate_vanishing_polynomial(&point),
+                    vanishing.eval(&point)
+                );
+            }
+        }
+    }
+}
@@ -1,8 +1,24 @@
+// Dense univariate polynomials over a scalar field, in coefficient form.
+// This is the polynomial type used by the PLONK prover and the polynomial commitment schemes,
+// and it is public so that protocols built on top of poly-iops can reuse it:
+// - arithmetic: add, sub, neg, mul_scalar, naive_mul and fast_mul (FFT based),
+// - division: div_rem by any polynomial, and div_by_vanishing by the vanishing polynomial
+//   X^n - 1 of the roots of unity of order n, in linear time,
+// - evaluation: eval at a point, and fft/coset_fft_with_unity_root on a whole domain.
+// Polynomials are always trimmed: the leading coefficient is not zero, unless the polynomial
+// is the zero polynomial, whose coefficient vector is [0].
+// With the `parallel` feature, the FFTs split their two halves on multiple threads above
+// PARALLEL_FFT_MIN_SIZE points, and fast_mul multiplies the evaluations on multiple threads.
+// The field operations are the same in both cases, so the results do not depend on the
+// feature.
+
 use algebra::groups::Scalar;
 use num_bigint::{BigUint, ToBigUint};
 use num_integer::Integer;
 use num_traits::Zero;
 use rand::{CryptoRng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::ops::Sub;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -14,6 +30,11 @@ impl<F> FpPolynomial<F> {
     pub fn get_coefs_ref(&self) -> &[F] {
         self.coefs.as_slice()
     }
+
+    /// Returns the coefficient vector, low-order coefficient first
+    pub fn into_coefs(self) -> Vec<F> {
+        self.coefs
+    }
 }
 
 impl<F: Scalar> FpPolynomial<F> {
@@ -475,9 +496,11 @@ impl<F: Scalar> FpPolynomial<F> {
         let n = (self.degree() + other.degree() + 1).next_power_of_two();
         let (mut self_evals, root) = self.fft(n).unwrap();
         let other_evals = other.fft_with_unity_root(&root, n);
-        for (self_eval, other_eval) in self_evals.iter_mut().zip(other_evals.iter()) {
-            self_eval.mul_assign(other_eval);
-        }
+        #[cfg(feature = "parallel")]
+        let iter = self_evals.par_iter_mut().zip(other_evals.par_iter());
+        #[cfg(not(feature = "parallel"))]
+        let iter = self_evals.iter_mut().zip(other_evals.iter());
+        iter.for_each(|(self_eval, other_eval)| self_eval.mul_assign(other_eval));
         Self::ffti(&root, &self_evals)
     }
 
@@ -733,6 +756,63 @@ impl<F: Scalar> FpPolynomial<F> {
         (q, r)
     }
 
+    /// Returns the vanishing polynomial X^n - 1 of the group of n-th roots of unity
+    /// # Example:
+    /// ```
+    /// use poly_iops::polynomials::field_polynomial::{primitive_nth_root_of_unity, FpPolynomial};
+    /// use algebra::bls12_381::BLSScalar;
+    /// use algebra::groups::{Zero, One, ScalarArithmetic};
+    /// let vanishing = FpPolynomial::<BLSScalar>::vanishing(4);
+    /// assert_eq!(vanishing.degree(), 4);
+    /// let root = primitive_nth_root_of_unity::<BLSScalar>(4).unwrap();
+    /// assert_eq!(vanishing.eval(&root), BLSScalar::zero());
+    /// assert_ne!(vanishing.eval(&root.add(&BLSScalar::one())), BLSScalar::zero());
+    /// ```
+    pub fn vanishing(n: usize) -> Self {
+        let mut coefs = vec![F::zero(); n + 1];
+        coefs[0] = F::one().neg();
+        coefs[n] = F::one();
+        Self::from_coefs(coefs)
+    }
+
+    /// Divide polynomial by the vanishing polynomial X^n - 1, producing quotient and remainder
+    /// polynomial. Same as self.div_rem(&FpPolynomial::vanishing(n)) in O(degree) operations.
+    /// The remainder is zero iff the polynomial vanishes on the n-th roots of unity.
+    /// # Example:
+    /// ```
+    /// use poly_iops::polynomials::field_polynomial::FpPolynomial;
+    /// use algebra::bls12_381::BLSScalar;
+    /// use algebra::groups::{Zero, One, ScalarArithmetic};
+    /// let zero = BLSScalar::zero();
+    /// let one = BLSScalar::one();
+    /// let two = one.add(&one);
+    /// // (X^2 - 1)(X + 2) + X + 1
+    /// let poly = FpPolynomial::from_coefs(vec![one.neg(), zero, two, one]);
+    /// let (q, r) = poly.div_by_vanishing(2);
+    /// assert_eq!(q, FpPolynomial::from_coefs(vec![two, one]));
+    /// assert_eq!(r, FpPolynomial::from_coefs(vec![one, one]));
+    /// let (q, r) = q.fast_mul(&FpPolynomial::vanishing(2)).div_by_vanishing(2);
+    /// assert_eq!(q, FpPolynomial::from_coefs(vec![two, one]));
+    /// assert!(r.is_zero());
+    /// ```
+    pub fn div_by_vanishing(&self, n: usize) -> (Self, Self) {
+        assert!(n > 0, "X^0 - 1 is the zero polynomial");
+        let k = self.coefs.len();
+        if k <= n {
+            return (Self::zero(), self.clone());
+        }
+        // X^i = X^{i-n} * (X^n - 1) + X^{i-n}: the coefficient of order i >= n moves to the
+        // quotient, and is added to the coefficient of order i - n
+        let mut rem = self.coefs.clone();
+        let mut quo = vec![F::zero(); k - n];
+        for i in (n..k).rev() {
+            let coef = rem.pop().unwrap(); // rem has length i + 1
+            quo[i - n] = coef;
+            rem[i - n].add_assign(&coef);
+        }
+        (FpPolynomial::from_coefs(quo), FpPolynomial::from_coefs(rem))
+    }
+
     /// Compute polynomial with another polynomials f.compose(inner)(x) = f(inner(x))
     /// # Example:
     /// ```
@@ -839,6 +919,10 @@ impl<F: Scalar> FpPolynomial<F> {
     }
 }
 
+/// The number of points from which the two halves of an FFT are computed on two threads with
+/// the `parallel` feature, so that tasks are not smaller than the cost of spawning them
+pub const PARALLEL_FFT_MIN_SIZE: usize = 1 << 10;
+
 /// given the coefs of a polynomial and a primitive n-th root of unity for field, compute its DFT
 /// n is with the form 2^k or 3 * 2^k
 fn recursive_fft<F: Scalar>(coefs: &[&F], root: &F) -> Vec<F> {
@@ -879,8 +963,23 @@ fn recursive_fft<F: Scalar>(coefs: &[&F], root: &F) -> Vec<F> {
         .map(|(_, c)| *c)
         .collect();
 
-    let y_even = recursive_fft(&even, &root_sq);
-    let y_odd = recursive_fft(&odd, &root_sq);
+    #[cfg(feature = "parallel")]
+    let (y_even, y_odd) = if n >= PARALLEL_FFT_MIN_SIZE {
+        rayon::join(
+            || recursive_fft(&even, &root_sq),
+            || recursive_fft(&odd, &root_sq),
+        )
+    } else {
+        (
+            recursive_fft(&even, &root_sq),
+            recursive_fft(&odd, &root_sq),
+        )
+    };
+    #[cfg(not(feature = "parallel"))]
+    let (y_even, y_odd) = (
+        recursive_fft(&even, &root_sq),
+        recursive_fft(&odd, &root_sq),
+    );
 
     let mut omega = F::one();
     let mut dft = vec![F::zero(); n];
@@ -930,7 +1029,7 @@ fn u32_limbs_to_u64_limbs(s: &[u32]) -> Vec<u64> {
 pub fn recursive_ifft<F: Scalar>(values: &[&F], root: &F) -> Vec<F> {
     let n = values.len();
     assert!(n.is_power_of_two() || ((n % 3 == 0) && (n / 3).is_power_of_two()));
-    let root_inv = root.pow(&[(n - 1) as u64]);
+    let root_inv = root.pow_u64((n - 1) as u64);
     let n = F::from_u64(n as u64);
     let n_inv = n.inv().unwrap();
     recursive_fft(values, &root_inv)
@@ -1079,6 +1178,25 @@ mod test {
         assert_eq!(ffti_polynomial, polynomial);
     }
 
+    #[test]
+    fn test_large_fft() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        // 3 * 2^k points, with two levels of the recursion above PARALLEL_FFT_MIN_SIZE
+        let n = 3 * 4 * super::PARALLEL_FFT_MIN_SIZE;
+        let coefs = (0..n).map(|_| BLSScalar::random(&mut prng)).collect();
+        let polynomial = FpPolynomial::from_coefs(coefs);
+        let (dft, root) = polynomial.fft(n).unwrap();
+        let mut point = BLSScalar::one();
+        for i in (0..n).step_by(n / 16) {
+            assert_eq!(dft[i], polynomial.eval(&point));
+            point = point.mul(&root.pow_u64((n / 16) as u64));
+        }
+        assert_eq!(FpPolynomial::ffti(&root, &dft), polynomial);
+        let product = polynomial.fast_mul(&polynomial);
+        let x = BLSScalar::random(&mut prng);
+        assert_eq!(product.eval(&x), polynomial.eval(&x).square());
+    }
+
     #[test]
     fn test_fast_mul() {
         let mut prng = ChaChaRng::from_seed([0u8; 32]);
@@ -1109,4 +1227,18 @@ mod test {
             assert_eq!(a, b);
         }
     }
+
+    #[test]
+    fn test_div_by_vanishing() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let coefs: Vec<BLSScalar> =
+            (0..20).map(|_| BLSScalar::random(&mut prng)).collect();
+        let polynomial = FpPolynomial::from_coefs(coefs);
+        for n in [1, 3, 8, 19, 20, 25] {
+            let vanishing = FpPolynomial::vanishing(n);
+            let (q, r) = polynomial.div_by_vanishing(n);
+            assert_eq!((q.clone(), r.clone()), polynomial.div_rem(&vanishing));
+            assert_eq!(q.fast_mul(&vanishing).add(&r), polynomial);
+        }
+    }
 }
@@ -1 +1,3 @@
+pub mod evaluation_domain;
 pub mod field_polynomial;
+pub mod multilinear;
@@ -0,0 +1,219 @@
+// Multilinear polynomials, of degree at most one in each of their n variables, in evaluation
+// form: the vector of the 2^n evaluations on the boolean hypercube {0, 1}^n. The i-th
+// evaluation is the evaluation on the point whose j-th coordinate is the j-th bit of i, least
+// significant bit first, so that the first variable x_0 selects between consecutive
+// evaluations.
+// A multilinear polynomial is the unique multilinear extension of its evaluations:
+// f(x) = \sum_b f(b) * eq(x, b), with eq(x, b) = \prod_j (x_j * b_j + (1 - x_j) * (1 - b_j)).
+// This is the representation used by the sumcheck protocol (see sumcheck) and the
+// multilinear KZG commitment scheme (see commitments::multilinear_kzg).
+
+use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
+use ruc::*;
+use utils::errors::ZeiError;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultilinearPolynomial<F> {
+    num_vars: usize,
+    evals: Vec<F>,
+}
+
+impl<F: Scalar> MultilinearPolynomial<F> {
+    /// Builds the multilinear polynomial with evaluations `evals` on the hypercube. The number
+    /// of evaluations must be a power of two.
+    /// # Example
+    /// ```
+    /// use poly_iops::polynomials::multilinear::MultilinearPolynomial;
+    /// use algebra::bls12_381::BLSScalar;
+    /// use algebra::groups::Scalar;
+    /// let evals: Vec<BLSScalar> = (0..4).map(BLSScalar::from_u32).collect();
+    /// let poly = MultilinearPolynomial::from_evals(evals).unwrap();
+    /// assert_eq!(poly.num_vars(), 2);
+    /// // f(x_0, x_1) = x_0 + 2 * x_1
+    /// let point = [BLSScalar::from_u32(5), BLSScalar::from_u32(7)];
+    /// assert_eq!(poly.evaluate(&point).unwrap(), BLSScalar::from_u32(19));
+    /// assert!(MultilinearPolynomial::from_evals(vec![BLSScalar::from_u32(1); 3]).is_err());
+    /// ```
+    pub fn from_evals(evals: Vec<F>) -> Result<Self> {
+        if !evals.len().is_power_of_two() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(MultilinearPolynomial {
+            num_vars: evals.len().trailing_zeros() as usize,
+            evals,
+        })
+    }
+
+    /// Returns the polynomial eq(point, x), whose evaluation on a point b of the hypercube is
+    /// one if b = point and zero otherwise when point is itself on the hypercube
+    pub fn eq(point: &[F]) -> Self {
+        let mut evals = vec![F::one()];
+        // the first coordinate selects between consecutive evaluations, it is processed last
+        for coordinate in point.iter().rev() {
+            let one_minus = F::one().sub(coordinate);
+            evals = evals
+                .iter()
+                .flat_map(|eval| vec![eval.mul(&one_minus), eval.mul(coordinate)])
+                .collect();
+        }
+        MultilinearPolynomial {
+            num_vars: point.len(),
+            evals,
+        }
+    }
+
+    /// Returns the number of variables
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    /// Returns the evaluations on the hypercube
+    pub fn evals(&self) -> &[F] {
+        &self.evals
+    }
+
+    /// Evaluates the polynomial at `point`, which must have as many coordinates as the
+    /// polynomial has variables
+    pub fn evaluate(&self, point: &[F]) -> Result<F> {
+        if point.len() != self.num_vars {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let mut poly = self.clone();
+        for coordinate in point {
+            poly = poly.fix_first_variable(coordinate);
+        }
+        Ok(poly.evals[0])
+    }
+
+    /// Returns the polynomial in the remaining variables x_1, ... obtained by setting the
+    /// first variable x_0 to `value`. The polynomial must have at least one variable.
+    pub fn fix_first_variable(&self, value: &F) -> Self {
+        assert!(self.num_vars > 0);
+        let evals = self
+            .evals
+            .chunks(2)
+            .map(|pair| pair[0].add(&pair[1].sub(&pair[0]).mul(value)))
+            .collect();
+        MultilinearPolynomial {
+            num_vars: self.num_vars - 1,
+            evals,
+        }
+    }
+
+    /// Returns the sum of the evaluations on the hypercube
+    pub fn sum_over_hypercube(&self) -> F {
+        let mut sum = F::zero();
+        for eval in self.evals.iter() {
+            sum.add_assign(eval);
+        }
+        sum
+    }
+
+    /// Adds `other`, with the same number of variables
+    pub fn add(&self, other: &Self) -> Result<Self> {
+        if self.num_vars != other.num_vars {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(MultilinearPolynomial {
+            num_vars: self.num_vars,
+            evals: self
+                .evals
+                .iter()
+                .zip(other.evals.iter())
+                .map(|(a, b)| a.add(b))
+                .collect(),
+        })
+    }
+
+    /// Multiplies the polynomial by `scalar`
+    pub fn mul_scalar(&self, scalar: &F) -> Self {
+        MultilinearPolynomial {
+            num_vars: self.num_vars,
+            evals: self.evals.iter().map(|eval| eval.mul(scalar)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::polynomials::multilinear::MultilinearPolynomial;
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use ruc::*;
+
+    #[test]
+    fn test_multilinear_evaluate() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let evals: Vec<BLSScalar> =
+            (0..16).map(|_| BLSScalar::random(&mut prng)).collect();
+        let poly = pnk!(MultilinearPolynomial::from_evals(evals.clone()));
+        assert_eq!(poly.num_vars(), 4);
+
+        // evaluations on the hypercube
+        for (i, eval) in evals.iter().enumerate() {
+            let point: Vec<BLSScalar> = (0..4)
+                .map(|j| BLSScalar::from_u32(((i >> j) & 1) as u32))
+                .collect();
+            assert_eq!(&pnk!(poly.evaluate(&point)), eval);
+            assert_eq!(
+                MultilinearPolynomial::eq(&point).evals()[i],
+                BLSScalar::one()
+            );
+            assert_eq!(
+                MultilinearPolynomial::eq(&point).sum_over_hypercube(),
+                BLSScalar::one()
+            );
+        }
+
+        // f(r) = \sum_b f(b) * eq(r, b)
+        let point: Vec<BLSScalar> =
+            (0..4).map(|_| BLSScalar::random(&mut prng)).collect();
+        let eq = MultilinearPolynomial::eq(&point);
+        let mut expected = BLSScalar::zero();
+        for (eval, eq_eval) in evals.iter().zip(eq.evals()) {
+            expected.add_assign(&eval.mul(eq_eval));
+        }
+        assert_eq!(pnk!(poly.evaluate(&point)), expected);
+        assert!(poly.evaluate(&point[1..]).is_err());
+
+        // fixing the first variable
+        let fixed = poly.fix_first_variable(&point[0]);
+        assert_eq!(fixed.num_vars(), 3);
+        assert_eq!(pnk!(fixed.evaluate(&point[1..])), expected);
+    }
+
+    #[test]
+    fn test_multilinear_arithmetic() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let random_poly = |prng: &mut ChaChaRng, num_vars: usize| {
+            let evals = (0..1 << num_vars)
+                .map(|_| BLSScalar::random(prng))
+                .collect();
+            pnk!(MultilinearPolynomial::from_evals(evals))
+        };
+        let poly1 = random_poly(&mut prng, 3);
+        let poly2 = random_poly(&mut prng, 3);
+        let three = BLSScalar::from_u32(3);
+        let sum = pnk!(poly1.add(&poly2.mul_scalar(&three)));
+        let point: Vec<BLSScalar> =
+            (0..3).map(|_| BLSScalar::random(&mut prng)).collect();
+        assert_eq!(
+            pnk!(sum.evaluate(&point)),
+            pnk!(poly1.evaluate(&point)).add(&pnk!(poly2.evaluate(&point)).mul(&three))
+        );
+        assert_eq!(
+            sum.sum_over_hypercube(),
+            poly1
+                .sum_over_hypercube()
+                .add(&poly2.sum_over_hypercube().mul(&three))
+        );
+        assert!(poly1.add(&random_poly(&mut prng, 2)).is_err());
+
+        // a polynomial with no variables is a constant
+        let constant = pnk!(MultilinearPolynomial::from_evals(vec![three]));
+        assert_eq!(constant.num_vars(), 0);
+        assert_eq!(pnk!(constant.evaluate(&[])), three);
+    }
+}
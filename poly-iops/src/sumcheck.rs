@@ -0,0 +1,205 @@
+// This file implements the sumcheck protocol (https://dl.acm.org/doi/10.1145/146585.146605)
+// for the sum over the boolean hypercube of a product of multilinear polynomials:
+//    claim = \sum_{b in {0, 1}^n} f_1(b) * ... * f_d(b)
+// In the i-th round, the prover sends the evaluations on 0, 1, ..., d of the univariate
+// polynomial
+//    g_i(X) = \sum_{b in {0, 1}^{n - i - 1}} \prod_k f_k(r_0, ..., r_{i - 1}, X, b)
+// of degree at most d. The verifier checks that g_i(0) + g_i(1) is the current claim, and
+// replaces the claim by g_i(r_i) for a challenge r_i. After the n rounds, the verifier is
+// left with the claim that \prod_k f_k(r) is the last claim at the random point
+// r = (r_0, ..., r_{n - 1}), which it checks with evaluation proofs of the f_k, e.g. with the
+// multilinear KZG commitment scheme (see commitments::multilinear_kzg), or directly.
+// The challenges are derived from a PlonkTranscript.
+
+use crate::commitments::transcript::PolyComTranscript;
+use crate::plonk::transcript::PlonkTranscript;
+use crate::polynomials::field_polynomial::FpPolynomial;
+use crate::polynomials::multilinear::MultilinearPolynomial;
+use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
+use ruc::*;
+use utils::errors::ZeiError;
+
+/// Proof of the sumcheck protocol: the evaluations of the univariate polynomial of each
+/// round on 0, 1, ..., d
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SumcheckProof<F> {
+    pub(crate) round_evals: Vec<Vec<F>>,
+}
+
+fn init_sumcheck_transcript<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
+    num_vars: usize,
+    degree: usize,
+    claim: &F,
+) {
+    transcript.append_message(b"Domain Separator", b"New Sumcheck Protocol");
+    transcript.append_u64(b"num_vars", num_vars as u64);
+    transcript.append_u64(b"degree", degree as u64);
+    transcript.append_field_elem(claim);
+}
+
+/// Proves that the sum over the hypercube of the product of `polys`, with the same number
+/// of variables, is `claim`. Returns the proof, the random point r and the evaluations of
+/// the polynomials at r.
+pub fn prove_sumcheck<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
+    polys: &[MultilinearPolynomial<F>],
+    claim: &F,
+) -> Result<(SumcheckProof<F>, Vec<F>, Vec<F>)> {
+    if polys.is_empty() || polys.iter().any(|p| p.num_vars() != polys[0].num_vars()) {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let num_vars = polys[0].num_vars();
+    let degree = polys.len();
+    init_sumcheck_transcript(transcript, num_vars, degree, claim);
+
+    let points: Vec<F> = (0..=degree).map(|t| F::from_u64(t as u64)).collect();
+    let mut polys = polys.to_vec();
+    let mut round_evals = Vec::with_capacity(num_vars);
+    let mut point = Vec::with_capacity(num_vars);
+    for _ in 0..num_vars {
+        // g(t) = \sum_j \prod_k (f_k[2j] + t * (f_k[2j + 1] - f_k[2j]))
+        let half = polys[0].evals().len() / 2;
+        let mut evals = vec![F::zero(); degree + 1];
+        for j in 0..half {
+            for (eval, t) in evals.iter_mut().zip(points.iter()) {
+                let mut product = F::one();
+                for poly in polys.iter() {
+                    let (lo, hi) = (&poly.evals()[2 * j], &poly.evals()[2 * j + 1]);
+                    product.mul_assign(&lo.add(&hi.sub(lo).mul(t)));
+                }
+                eval.add_assign(&product);
+            }
+        }
+        for eval in evals.iter() {
+            transcript.append_field_elem(eval);
+        }
+        let r = transcript.get_challenge_field_elem::<F>(b"sumcheck challenge");
+        polys = polys
+            .iter()
+            .map(|poly| poly.fix_first_variable(&r))
+            .collect();
+        round_evals.push(evals);
+        point.push(r);
+    }
+    let final_evals = polys.iter().map(|poly| poly.evals()[0]).collect();
+    Ok((SumcheckProof { round_evals }, point, final_evals))
+}
+
+/// Verifies a sumcheck proof that the sum over the hypercube of a product of `degree`
+/// multilinear polynomials in `num_vars` variables is `claim`. Returns the random point r
+/// and the value that the product of the polynomials must have at r, which the caller must
+/// check.
+pub fn verify_sumcheck<T: PlonkTranscript, F: Scalar>(
+    transcript: &mut T,
+    num_vars: usize,
+    degree: usize,
+    claim: &F,
+    proof: &SumcheckProof<F>,
+) -> Result<(Vec<F>, F)> {
+    if proof.round_evals.len() != num_vars
+        || proof
+            .round_evals
+            .iter()
+            .any(|evals| evals.len() != degree + 1)
+    {
+        return Err(eg!(ZeiError::ArgumentVerificationError));
+    }
+    init_sumcheck_transcript(transcript, num_vars, degree, claim);
+
+    let points: Vec<F> = (0..=degree).map(|t| F::from_u64(t as u64)).collect();
+    let mut claim = *claim;
+    let mut point = Vec::with_capacity(num_vars);
+    for evals in proof.round_evals.iter() {
+        if evals[0].add(&evals[1]) != claim {
+            return Err(eg!(ZeiError::ArgumentVerificationError));
+        }
+        for eval in evals.iter() {
+            transcript.append_field_elem(eval);
+        }
+        let r = transcript.get_challenge_field_elem::<F>(b"sumcheck challenge");
+        claim = FpPolynomial::from_interpolation(&points, evals)
+            .c(d!(ZeiError::ArgumentVerificationError))?
+            .eval(&r);
+        point.push(r);
+    }
+    Ok((point, claim))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::polynomials::multilinear::MultilinearPolynomial;
+    use crate::sumcheck::{prove_sumcheck, verify_sumcheck};
+    use algebra::bls12_381::BLSScalar;
+    use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn random_poly(
+        prng: &mut ChaChaRng,
+        num_vars: usize,
+    ) -> MultilinearPolynomial<BLSScalar> {
+        let evals = (0..1 << num_vars)
+            .map(|_| BLSScalar::random(prng))
+            .collect();
+        pnk!(MultilinearPolynomial::from_evals(evals))
+    }
+
+    #[test]
+    fn test_sumcheck() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        for degree in 1..4 {
+            let polys: Vec<_> = (0..degree).map(|_| random_poly(&mut prng, 5)).collect();
+            let mut claim = BLSScalar::zero();
+            for i in 0..32 {
+                let mut product = BLSScalar::one();
+                for poly in polys.iter() {
+                    product.mul_assign(&poly.evals()[i]);
+                }
+                claim.add_assign(&product);
+            }
+
+            let (proof, point, evals) = {
+                let mut transcript = Transcript::new(b"TestSumcheck");
+                pnk!(prove_sumcheck(&mut transcript, &polys, &claim))
+            };
+            for (poly, eval) in polys.iter().zip(evals.iter()) {
+                assert_eq!(&pnk!(poly.evaluate(&point)), eval);
+            }
+            let mut transcript = Transcript::new(b"TestSumcheck");
+            let (verifier_point, expected) =
+                pnk!(verify_sumcheck(&mut transcript, 5, degree, &claim, &proof));
+            assert_eq!(verifier_point, point);
+            let mut product = BLSScalar::one();
+            for eval in evals.iter() {
+                product.mul_assign(eval);
+            }
+            assert_eq!(product, expected);
+
+            // wrong claim
+            let wrong_claim = claim.add(&BLSScalar::one());
+            let mut transcript = Transcript::new(b"TestSumcheck");
+            assert!(
+                verify_sumcheck(&mut transcript, 5, degree, &wrong_claim, &proof)
+                    .is_err()
+            );
+            // a cheating prover proving a wrong claim fails
+            let (proof, _, _) = {
+                let mut transcript = Transcript::new(b"TestSumcheck");
+                pnk!(prove_sumcheck(&mut transcript, &polys, &wrong_claim))
+            };
+            let mut transcript = Transcript::new(b"TestSumcheck");
+            assert!(
+                verify_sumcheck(&mut transcript, 5, degree, &wrong_claim, &proof)
+                    .is_err()
+            );
+            let mut transcript = Transcript::new(b"TestSumcheck");
+            assert!(verify_sumcheck(&mut transcript, 4, degree, &claim, &proof).is_err());
+        }
+        let mut transcript = Transcript::new(b"TestSumcheck");
+        let polys = [random_poly(&mut prng, 2), random_poly(&mut prng, 3)];
+        assert!(prove_sumcheck(&mut transcript, &polys, &BLSScalar::zero()).is_err());
+    }
+}
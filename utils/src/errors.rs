@@ -47,6 +47,9 @@ pub enum ZeiError {
     R1CSProofError,
     NoMemoInAssetTracerMemo,
     BogusAssetTracerMemo,
+    MACVerificationError,
+    UnsupportedVersionError,
+    CredentialRevokedError,
 }
 
 impl fmt::Display for ZeiError {
@@ -126,6 +129,9 @@ impl fmt::Display for ZeiError {
                   ZeiError::R1CSProofError => { "Could not create R1CSProof" }
                   ZeiError::NoMemoInAssetTracerMemo => { "Cannot decrypt asset tracer memo, try brute force decoding" }
                   ZeiError::BogusAssetTracerMemo => { "AssetTracerMemo decryption yields inconsistent data, try brute force decoding" }
+                  ZeiError::MACVerificationError => "MAC tag not valid for message",
+                  ZeiError::UnsupportedVersionError => "Unsupported protocol version",
+                  ZeiError::CredentialRevokedError => "The credential has been revoked",
                 })
     }
 }
@@ -10,6 +10,14 @@ pub trait ZeiFromToBytes: Sized {
     fn zei_from_bytes(bytes: &[u8]) -> Result<Self>;
 }
 
+/// Structural checks of a deserialized object before it is used: lengths of the vectors that
+/// are later zipped or indexed together, bounds and encodings of group elements. An object that
+/// passes validate() can be fed to the verification functions without making them panic.
+/// Decoding an arbitrary byte string followed by validate() must return an error, not panic.
+pub trait Validate {
+    fn validate(&self) -> Result<()>;
+}
+
 impl ZeiFromToBytes for RangeProof {
     fn zei_to_bytes(&self) -> Vec<u8> {
         let mut v = vec![];
@@ -150,3 +158,70 @@ pub mod zei_obj_serde {
         }
     }
 }
+
+/// Helpers for the binary encoding of the structures that record a protocol version, such as
+/// PLONK proofs and transfer notes, so that the encodings from before the version was recorded
+/// still decode.
+/// The binary encoding of a versioned structure starts with VERSIONED_ENCODING_TAG and the
+/// version, followed by the other fields of the structure. The encoding of a structure from
+/// before the version was recorded starts instead with the length of a vector, its first field,
+/// which is never VERSIONED_ENCODING_TAG, hence the decoder reads the head of the encoding
+/// with next_versioned_head and then the fields of the format of the version it found.
+/// Self-describing encodings such as JSON keep the encoding of the fields by name, in which a
+/// missing version decodes as 0.
+/// The decoding of the legacy encodings relies on the bincode encoding of a structure, the
+/// concatenation of its fields with no length nor type marker: the structure is deserialized
+/// as a tuple of VERSIONED_ENCODING_MAX_LEN elements, of which the visitor reads the ones of
+/// the format it found, and the length prefix of the first vector is read as a u64. Other
+/// binary formats such as MessagePack encode a structure as an array of its fields, whose
+/// first vector is an array and not a u64: the legacy encodings only decode in bincode.
+pub mod versioned_serde {
+    use serde::de::{Error, SeqAccess};
+    use serde::Deserialize;
+
+    /// First element of the binary encoding of a versioned structure
+    pub const VERSIONED_ENCODING_TAG: u64 = u64::MAX;
+
+    /// Number of elements requested from the deserializer for a versioned structure: the
+    /// encoding of a legacy structure has one element per entry of its first vector
+    pub const VERSIONED_ENCODING_MAX_LEN: usize = usize::MAX;
+
+    /// Head of the binary encoding of a versioned structure
+    pub enum VersionedHead<T> {
+        /// encoding with a version, followed by all the fields but the version
+        Versioned(u32),
+        /// encoding from before the version was recorded, whose first field is this vector
+        Legacy(Vec<T>),
+    }
+
+    /// Returns the next element of a versioned structure, or an error if there is none
+    pub fn next_element<'de, A, T>(seq: &mut A) -> core::result::Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+        T: Deserialize<'de>,
+    {
+        seq.next_element()?
+            .ok_or_else(|| A::Error::custom("truncated versioned structure"))
+    }
+
+    /// Reads the head of a versioned structure, the version or the first vector of a legacy
+    /// structure
+    pub fn next_versioned_head<'de, A, T>(
+        seq: &mut A,
+    ) -> core::result::Result<VersionedHead<T>, A::Error>
+    where
+        A: SeqAccess<'de>,
+        T: Deserialize<'de>,
+    {
+        let head: u64 = next_element(seq)?;
+        if head == VERSIONED_ENCODING_TAG {
+            return Ok(VersionedHead::Versioned(next_element(seq)?));
+        }
+        // the length is untrusted: nothing is allocated before the elements are read
+        let mut first_vector = vec![];
+        for _ in 0..head {
+            first_vector.push(next_element(seq)?);
+        }
+        Ok(VersionedHead::Legacy(first_vector))
+    }
+}
@@ -1,5 +1,6 @@
 use crate::anon_xfr::keys::AXfrPubKey;
 use crate::anon_xfr::structs::{BlindFactor, Commitment, MTNode, MTPath, Nullifier};
+use crate::setup::MAX_SAFE_XFR_INPUTS;
 use algebra::bls12_381::BLSScalar;
 use algebra::groups::{Group, GroupArithmetic, One, Scalar, ScalarArithmetic, Zero};
 use algebra::jubjub::{JubjubPoint, JubjubScalar};
@@ -83,6 +84,7 @@ pub(crate) struct AMultiXfrPubInputs {
     pub payers_inputs: Vec<(Nullifier, AXfrPubKey)>,
     pub payees_commitments: Vec<Commitment>,
     pub merkle_root: BLSScalar,
+    pub fee: u64, // public fee of the circuit of build_multi_xfr_cs_with_fees, 0 if no fee is paid
 }
 
 impl AMultiXfrPubInputs {
@@ -100,6 +102,10 @@ impl AMultiXfrPubInputs {
         for comm in &self.payees_commitments {
             result.push(*comm);
         }
+        // fee
+        if self.fee != 0 {
+            result.push(BLSScalar::from_u64(self.fee));
+        }
         result
     }
 
@@ -181,18 +187,22 @@ impl AMultiXfrPubInputs {
             payers_inputs,
             payees_commitments,
             merkle_root: node,
+            fee: 0,
         }
     }
 }
 
 /// Returns the constraint system (and associated number of constraints) for a multi-inputs/outputs transaction.
-/// A prover can provide honest `secret_inputs` and obtain the cs witness by calling `cs.get_and_clear_witness()`.
+/// A prover can provide honest `secret_inputs` and obtain the cs witness by calling `cs.get_and_clear_witness()`,
+/// or `cs.with_witness()` to wipe the witness after use.
 /// One provide an empty secret_inputs to get the constraint system `cs` for verification only.
 pub(crate) fn build_multi_xfr_cs(
     secret_inputs: AMultiXfrWitness,
 ) -> (TurboPlonkCS, usize) {
     assert_ne!(secret_inputs.payers_secrets.len(), 0);
     assert_ne!(secret_inputs.payees_secrets.len(), 0);
+    assert!(secret_inputs.payers_secrets.len() <= MAX_SAFE_XFR_INPUTS);
+    assert!(secret_inputs.payees_secrets.len() <= MAX_SAFE_XFR_INPUTS);
 
     let mut cs = TurboPlonkConstraintSystem::new();
     let payers_secrets = add_payers_secrets(&mut cs, &secret_inputs.payers_secrets);
@@ -206,6 +216,12 @@ pub(crate) fn build_multi_xfr_cs(
     let mut root_var: Option<VarIndex> = None;
     for payer in &payers_secrets {
         // prove knowledge of payer's secret key: pk = base^{sk}
+        // Range check `amount`
+        // The payers' amounts are bound to their accumulated abars, which were range-checked
+        // when created, but checking them here bounds the sum of the input amounts by the
+        // circuit alone, so that the asset-mixing sums cannot wrap around the field.
+        cs.range_check_chunks(payer.amount, AMOUNT_LEN);
+
         let (pk_var, pk_point) = cs.scalar_mul(base.clone(), payer.sec_key, SK_LEN);
         let pk_x = pk_var.get_x();
         let pk_y = pk_var.get_y();
@@ -263,10 +279,7 @@ pub(crate) fn build_multi_xfr_cs(
             commit(&mut cs, payee.blind, payee.amount, payee.asset_type);
 
         // Range check `amount`
-        // Note we don't need to range-check payers' `amount`, because those amounts are bound
-        // to payers' accumulated abars, whose underlying amounts have already been range-checked
-        // in the transactions that created the payers' abars.
-        cs.range_check(payee.amount, AMOUNT_LEN);
+        cs.range_check_chunks(payee.amount, AMOUNT_LEN);
 
         // prepare the public input for the output commitment
         cs.prepare_io_variable(com_abar_out_var);
@@ -291,15 +304,18 @@ pub(crate) fn build_multi_xfr_cs(
 }
 
 /// Returns the constraint system (and associated number of constraints) for a multi-inputs/outputs transaction.
-/// This one also takes fee parameters as input.
-#[allow(dead_code)]
+/// This one also takes fee parameters as input: the inputs of type `fee_type` pay the amount
+/// `fee` on top of the outputs. The fee is a public input, following the output commitments,
+/// so the circuit does not depend on the fee and the amounts remain hidden.
 pub(crate) fn build_multi_xfr_cs_with_fees(
     secret_inputs: AMultiXfrWitness,
     fee_type: BLSScalar,
-    fee_calculating_func: &dyn Fn(u32, u32) -> u32,
+    fee: u64,
 ) -> (TurboPlonkCS, usize) {
     assert_ne!(secret_inputs.payers_secrets.len(), 0);
     assert_ne!(secret_inputs.payees_secrets.len(), 0);
+    assert!(secret_inputs.payers_secrets.len() <= MAX_SAFE_XFR_INPUTS);
+    assert!(secret_inputs.payees_secrets.len() <= MAX_SAFE_XFR_INPUTS);
 
     let mut cs = TurboPlonkConstraintSystem::new();
     let payers_secrets = add_payers_secrets(&mut cs, &secret_inputs.payers_secrets);
@@ -313,6 +329,12 @@ pub(crate) fn build_multi_xfr_cs_with_fees(
     let mut root_var: Option<VarIndex> = None;
     for payer in &payers_secrets {
         // prove knowledge of payer's secret key: pk = base^{sk}
+        // Range check `amount`
+        // The payers' amounts are bound to their accumulated abars, which were range-checked
+        // when created, but checking them here bounds the sum of the input amounts by the
+        // circuit alone, so that the asset-mixing sums cannot wrap around the field.
+        cs.range_check_chunks(payer.amount, AMOUNT_LEN);
+
         let (pk_var, pk_point) = cs.scalar_mul(base.clone(), payer.sec_key, SK_LEN);
         let pk_x = pk_var.get_x();
         let pk_y = pk_var.get_y();
@@ -370,10 +392,7 @@ pub(crate) fn build_multi_xfr_cs_with_fees(
             commit(&mut cs, payee.blind, payee.amount, payee.asset_type);
 
         // Range check `amount`
-        // Note we don't need to range-check payers' `amount`, because those amounts are bound
-        // to payers' accumulated abars, whose underlying amounts have already been range-checked
-        // in the transactions that created the payers' abars.
-        cs.range_check(payee.amount, AMOUNT_LEN);
+        cs.range_check_chunks(payee.amount, AMOUNT_LEN);
 
         // prepare the public input for the output commitment
         cs.prepare_io_variable(com_abar_out_var);
@@ -388,7 +407,11 @@ pub(crate) fn build_multi_xfr_cs_with_fees(
         .into_iter()
         .map(|payee| (payee.asset_type, payee.amount))
         .collect();
-    asset_mixing_with_fees(&mut cs, &inputs, &outputs, fee_type, fee_calculating_func);
+    let fee_var = cs.new_variable(BLSScalar::from_u64(fee));
+    asset_mixing_with_public_fee(&mut cs, &inputs, &outputs, fee_type, fee_var);
+
+    // prepare the public input for the fee
+    cs.prepare_io_variable(fee_var);
 
     // pad the number of constraints to power of two
     cs.pad();
@@ -663,9 +686,7 @@ fn nullify(
         nullifier_input_vars.pub_key_x,
         nullifier_input_vars.pub_key_y,
     );
-    let input_var =
-        StateVar::new([uid_amount, asset_type, pub_key_x, cs.add(pub_key_y, sk_var)]);
-    cs.rescue_hash(&input_var)[0]
+    cs.prf(sk_var, &[uid_amount, asset_type, pub_key_x, pub_key_y])
 }
 
 /// Enforce asset_mixing constraints:
@@ -779,6 +800,28 @@ fn asset_mixing_with_fees(
     outputs: &[(VarIndex, VarIndex)],
     fee_type: BLSScalar,
     fee_calculating_func: &dyn Fn(u32, u32) -> u32,
+) {
+    // Calculate the fee
+    let fee = BLSScalar::from_u32(fee_calculating_func(
+        inputs.len() as u32,
+        outputs.len() as u32,
+    ));
+    let fee_var = cs.new_variable(fee);
+    cs.insert_constant_gate(fee_var, fee);
+
+    asset_mixing_with_public_fee(cs, inputs, outputs, fee_type, fee_var);
+}
+
+/// Enforce the constraints of asset_mixing_with_fees, for a fee amount given by the variable
+/// `fee_var` rather than by a constant of the circuit. The caller binds `fee_var`, e.g. as a
+/// public input, so that the fee of a transaction is revealed while the input and output
+/// amounts remain hidden.
+fn asset_mixing_with_public_fee(
+    cs: &mut TurboPlonkCS,
+    inputs: &[(VarIndex, VarIndex)],
+    outputs: &[(VarIndex, VarIndex)],
+    fee_type: BLSScalar,
+    fee_var: VarIndex,
 ) {
     // Compute the `sum_in_i`
     let inputs_type_sum_amounts: Vec<(VarIndex, VarIndex)> = inputs
@@ -820,14 +863,6 @@ fn asset_mixing_with_fees(
     let fee_type_val = cs.new_variable(fee_type);
     cs.insert_constant_gate(fee_type_val, fee_type);
 
-    // Calculate the fee
-    let fee = BLSScalar::from_u32(fee_calculating_func(
-        inputs.len() as u32,
-        outputs.len() as u32,
-    ));
-    let fee_var = cs.new_variable(fee);
-    cs.insert_constant_gate(fee_var, fee);
-
     // At least one input type is `fee_type` by checking `flag_no_fee_type = 0`
     // and also check that the amount is matching
     // and also check that every input type appears in the set of output types (except if the fee has used up)
@@ -2268,16 +2303,23 @@ pub(crate) mod tests {
         fee_calculating_func: &dyn Fn(u32, u32) -> u32,
     ) {
         let secret_inputs = new_multi_xfr_witness_for_test(inputs, outputs, [0u8; 32]);
-        let pub_inputs = AMultiXfrPubInputs::from_witness(&secret_inputs);
-
-        // check the constraints
-        let (mut cs, _) =
-            build_multi_xfr_cs_with_fees(secret_inputs, fee_type, fee_calculating_func);
+        let mut pub_inputs = AMultiXfrPubInputs::from_witness(&secret_inputs);
+
+        // check the constraints, the fee is the last public input
+        let fee = fee_calculating_func(
+            secret_inputs.payers_secrets.len() as u32,
+            secret_inputs.payees_secrets.len() as u32,
+        ) as u64;
+        pub_inputs.fee = fee;
+        let (mut cs, _) = build_multi_xfr_cs_with_fees(secret_inputs, fee_type, fee);
         let witness = cs.get_and_clear_witness();
-        let online_inputs = pub_inputs.to_vec();
+        let mut online_inputs = pub_inputs.to_vec();
         let verify = cs.verify_witness(&witness, &online_inputs);
         if witness_is_valid {
             pnk!(verify);
+            // the witness does not verify against another public fee
+            *online_inputs.last_mut().unwrap() = BLSScalar::from_u64(fee + 1);
+            assert!(cs.verify_witness(&witness, &online_inputs).is_err());
         } else {
             assert!(verify.is_err());
         }
@@ -52,6 +52,11 @@ impl AXfrKeyPair {
         AXfrPubKey(self.0.pub_key.clone())
     }
 
+    /// Build the key pair of secret key scalar `sec_key`
+    pub(crate) fn from_secret_scalar(sec_key: JubjubScalar) -> AXfrKeyPair {
+        AXfrKeyPair(schnorr::KeyPair::from_secret_scalar(sec_key))
+    }
+
     /// Return secret key scalar value
     pub(crate) fn get_secret_scalar(&self) -> JubjubScalar {
         self.0.get_secret_scalar()
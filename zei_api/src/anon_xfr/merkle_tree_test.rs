@@ -3,6 +3,7 @@ mod tests {
     use crate::anon_xfr::circuits::{
         add_merkle_path_variables, compute_merkle_root, AccElemVars,
     };
+    use crate::anon_xfr::hash_abars;
     use crate::anon_xfr::keys::AXfrKeyPair;
     use crate::anon_xfr::structs::{
         AnonBlindAssetRecord, MTNode, MTPath, OpenAnonBlindAssetRecord,
@@ -564,6 +565,67 @@ mod tests {
         pmt.commit().unwrap();
     }
 
+    #[test]
+    fn test_persistent_merkle_tree_batch() {
+        let path = thread::current().name().unwrap().to_owned();
+        let fdb = TempRocksDB::open(path.clone()).expect("failed to open db");
+        let cs = Arc::new(RwLock::new(ChainState::new(fdb, "test_db".to_string(), 0)));
+        let mut state = State::new(cs, false);
+        let store = PrefixedStore::new("mystore", &mut state);
+        let mut mt = PersistentMerkleTree::new(store).unwrap();
+
+        let batch_fdb =
+            TempRocksDB::open(format!("{}_batch", path)).expect("failed to open db");
+        let batch_cs = Arc::new(RwLock::new(ChainState::new(
+            batch_fdb,
+            "test_db".to_string(),
+            0,
+        )));
+        let mut batch_state = State::new(batch_cs, false);
+        let batch_store = PrefixedStore::new("mystore", &mut batch_state);
+        let mut batch_mt = PersistentMerkleTree::new(batch_store).unwrap();
+
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let key_pair: AXfrKeyPair = AXfrKeyPair::generate(&mut prng);
+        let abars: Vec<AnonBlindAssetRecord> = (0..12)
+            .map(|_| AnonBlindAssetRecord {
+                amount_type_commitment: BLSScalar::random(&mut prng),
+                public_key: key_pair.pub_key(),
+            })
+            .collect();
+        let uid_abars: Vec<(u64, &AnonBlindAssetRecord)> =
+            (0..).zip(abars.iter()).collect();
+        let hashes = hash_abars(&uid_abars);
+        for ((uid, abar), hash) in uid_abars.iter().zip(hashes.iter()) {
+            assert_eq!(*hash, hash_abar(*uid, abar));
+            assert_eq!(mt.add_commitment_hash(*hash).unwrap(), *uid);
+        }
+
+        // a leaf, then a batch spanning several subtrees
+        assert_eq!(batch_mt.add_commitment_hash(hashes[0]).unwrap(), 0);
+        assert_eq!(
+            batch_mt.add_commitment_hashes(&hashes[1..]).unwrap(),
+            (1..12).collect::<Vec<u64>>()
+        );
+        assert!(batch_mt.add_commitment_hashes(&[]).unwrap().is_empty());
+        assert_eq!(batch_mt.entry_count(), mt.entry_count());
+        assert_eq!(
+            batch_mt.get_current_root_hash().unwrap(),
+            mt.get_current_root_hash().unwrap()
+        );
+        for uid in 0..12 {
+            let proof = mt.generate_proof(uid).unwrap();
+            let batch_proof = batch_mt.generate_proof(uid).unwrap();
+            assert_eq!(proof.nodes.len(), batch_proof.nodes.len());
+            for (node, batch_node) in proof.nodes.iter().zip(batch_proof.nodes.iter()) {
+                assert_eq!(node.siblings1, batch_node.siblings1);
+                assert_eq!(node.siblings2, batch_node.siblings2);
+                assert_eq!(node.is_left_child, batch_node.is_left_child);
+                assert_eq!(node.is_right_child, batch_node.is_right_child);
+            }
+        }
+    }
+
     fn hash_abar(uid: u64, abar: &AnonBlindAssetRecord) -> BLSScalar {
         let hash = RescueInstance::new();
 
@@ -6,7 +6,7 @@ use crate::anon_xfr::proofs::{prove_xfr, verify_xfr};
 use crate::anon_xfr::structs::{
     AXfrBody, AXfrProof, AnonBlindAssetRecord, OpenAnonBlindAssetRecord,
 };
-use crate::setup::{NodeParams, UserParams};
+use crate::setup::{NodeParams, UserParams, MAX_SAFE_XFR_INPUTS};
 use crate::xfr::structs::{AssetType, OwnerMemo, ASSET_TYPE_LENGTH};
 use algebra::bls12_381::{BLSScalar, BLS_SCALAR_LEN};
 use algebra::groups::{Scalar, ScalarArithmetic, Zero};
@@ -23,12 +23,14 @@ use rand_core::{CryptoRng, RngCore};
 use ruc::*;
 use std::collections::HashMap;
 use utils::errors::ZeiError;
+use utils::serialization::Validate;
 
 pub mod bar_to_from_abar;
 pub(crate) mod circuits;
 pub mod keys;
 mod merkle_tree_test;
 pub(crate) mod proofs;
+pub mod sapling;
 pub mod structs;
 
 /// Build an anonymous transfer structure AXfrBody. It also returns randomized signature keys to sign the transfer,
@@ -42,13 +44,50 @@ pub fn gen_anon_xfr_body<R: CryptoRng + RngCore>(
     inputs: &[OpenAnonBlindAssetRecord],
     outputs: &[OpenAnonBlindAssetRecord],
     input_keypairs: &[AXfrKeyPair],
+) -> Result<(AXfrBody, Vec<AXfrKeyPair>)> {
+    gen_anon_xfr_body_with_fee(
+        prng,
+        params,
+        inputs,
+        outputs,
+        input_keypairs,
+        &AssetType::default(),
+        0,
+    )
+    .c(d!())
+}
+
+/// Build an anonymous transfer structure AXfrBody that pays the public amount `fee` of type
+/// `fee_type` out of the inputs, on top of the outputs, so that no change output is needed to pay
+/// the fee. The fee is a public input of the proof, and the input and output amounts remain
+/// hidden. If `fee` is not 0, the params must be generated by UserParams::new_with_fee for
+/// `fee_type`, otherwise as for gen_anon_xfr_body. It also returns randomized signature keys to
+/// sign the transfer.
+/// * `rng` - pseudo-random generator.
+/// * `params` - User parameters
+/// * `inputs` - Open source asset records
+/// * `outputs` - Description of output asset records.
+/// * `fee_type` - asset type of the fee
+/// * `fee` - public amount paid by the inputs of type `fee_type`
+pub fn gen_anon_xfr_body_with_fee<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    params: &UserParams,
+    inputs: &[OpenAnonBlindAssetRecord],
+    outputs: &[OpenAnonBlindAssetRecord],
+    input_keypairs: &[AXfrKeyPair],
+    fee_type: &AssetType,
+    fee: u64,
 ) -> Result<(AXfrBody, Vec<AXfrKeyPair>)> {
     // 1. check input correctness
-    if inputs.is_empty() || outputs.is_empty() {
+    if inputs.is_empty()
+        || outputs.is_empty()
+        || inputs.len() > MAX_SAFE_XFR_INPUTS
+        || outputs.len() > MAX_SAFE_XFR_INPUTS
+    {
         return Err(eg!(ZeiError::AXfrProverParamsError));
     }
     check_inputs(inputs, input_keypairs).c(d!())?;
-    check_asset_amount(inputs, outputs).c(d!())?;
+    check_asset_amount(inputs, outputs, fee_type, fee).c(d!())?;
     check_roots(inputs).c(d!())?;
 
     // 2. randomize input key pair with open_abar rand key
@@ -105,7 +144,8 @@ pub fn gen_anon_xfr_body<R: CryptoRng + RngCore>(
         payers_secrets,
         payees_secrets,
     };
-    let proof = prove_xfr(prng, params, secret_inputs).c(d!())?;
+    let proof =
+        prove_xfr(prng, params, secret_inputs, fee_type.as_scalar(), fee).c(d!())?;
 
     let diversified_key_pairs = rand_input_keypairs
         .iter()
@@ -133,13 +173,15 @@ pub fn gen_anon_xfr_body<R: CryptoRng + RngCore>(
                 merkle_root_version: mt_info_temp.root_version,
             },
             owner_memos: out_memos.c(d!())?,
+            fee,
         },
         diversified_key_pairs,
     ))
 }
 
 /// Verifies an anonymous transfer structure AXfrBody.
-/// * `params` - Verifier parameters
+/// * `params` - Verifier parameters, derived from the params of UserParams::new_with_fee if the
+/// transfer pays a fee
 /// * `body` - Transfer structure to verify
 /// * `accumulator` - candidate state of the accumulator. It must match body.proof.merkle_root, otherwise it returns ZeiError::AXfrVerification Error.
 pub fn verify_anon_xfr_body(
@@ -147,6 +189,7 @@ pub fn verify_anon_xfr_body(
     body: &AXfrBody,
     merkle_root: &BLSScalar,
 ) -> Result<()> {
+    body.validate().c(d!())?;
     if *merkle_root != body.proof.merkle_root {
         return Err(eg!(ZeiError::AXfrVerificationError));
     }
@@ -159,6 +202,7 @@ pub fn verify_anon_xfr_body(
         payers_inputs: body.inputs.clone(),
         payees_commitments,
         merkle_root: *merkle_root,
+        fee: body.fee,
     };
     verify_xfr(params, &pub_inputs, &body.proof.snark_proof)
         .c(d!(ZeiError::AXfrVerificationError))
@@ -179,12 +223,16 @@ fn check_inputs(
     }
     Ok(())
 }
-/// Check that for each asset type total input amount == total output amount
+/// Check that for each asset type total input amount == total output amount, plus `fee` for
+/// the type `fee_type`
 fn check_asset_amount(
     inputs: &[OpenAnonBlindAssetRecord],
     outputs: &[OpenAnonBlindAssetRecord],
+    fee_type: &AssetType,
+    fee: u64,
 ) -> Result<()> {
     let mut balances = HashMap::new();
+    balances.insert(*fee_type, -(fee as i128));
 
     for record in inputs.iter() {
         if let Some(x) = balances.get_mut(&record.asset_type) {
@@ -315,19 +363,58 @@ pub fn hash_abar(uid: u64, abar: &AnonBlindAssetRecord) -> BLSScalar {
     ])[0]
 }
 
+/// Computes hash_abar for each (uid, abar) pair of `abars`, e.g. to insert a batch of records in
+/// the Merkle tree. The hashes are computed on multiple threads with the `parallel` feature.
+pub fn hash_abars(abars: &[(u64, &AnonBlindAssetRecord)]) -> Vec<BLSScalar> {
+    let hash = RescueInstance::new();
+
+    let pk_inputs: Vec<Vec<BLSScalar>> = abars
+        .iter()
+        .map(|(_, abar)| {
+            vec![
+                abar.public_key.0.point_ref().get_x(),
+                abar.public_key.0.point_ref().get_y(),
+                BLSScalar::zero(),
+                BLSScalar::zero(),
+            ]
+        })
+        .collect();
+    let pk_hashes = hash.rescue_hash_batch(&pk_inputs);
+
+    let inputs: Vec<Vec<BLSScalar>> = abars
+        .iter()
+        .zip(pk_hashes.iter())
+        .map(|((uid, abar), pk_hash)| {
+            vec![
+                BLSScalar::from_u64(*uid),
+                abar.amount_type_commitment,
+                pk_hash[0],
+                BLSScalar::zero(),
+            ]
+        })
+        .collect();
+    hash.rescue_hash_batch(&inputs)
+        .into_iter()
+        .map(|state| state[0])
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::anon_xfr::{gen_anon_xfr_body, verify_anon_xfr_body};
+    use crate::anon_xfr::{
+        gen_anon_xfr_body, gen_anon_xfr_body_with_fee, verify_anon_xfr_body,
+    };
     use crate::anon_xfr::{
         hash_abar,
-        keys::AXfrKeyPair,
+        keys::{AXfrKeyPair, AXfrPubKey},
         structs::{
-            AXfrNote, AnonBlindAssetRecord, MTLeafInfo, MTNode, MTPath,
-            OpenAnonBlindAssetRecord, OpenAnonBlindAssetRecordBuilder,
+            AXfrBody, AXfrNote, AXfrProof, AnonBlindAssetRecord, MTLeafInfo, MTNode,
+            MTPath, Nullifier, OpenAnonBlindAssetRecord,
+            OpenAnonBlindAssetRecordBuilder,
         },
     };
     use crate::setup::{NodeParams, UserParams, DEFAULT_BP_NUM_GENS};
-    use crate::xfr::structs::AssetType;
+    use crate::xfr::structs::{AssetType, OwnerMemo};
     use accumulators::merkle_tree::{PersistentMerkleTree, Proof};
     use algebra::bls12_381::BLSScalar;
     use algebra::groups::{One, Scalar, ScalarArithmetic, Zero};
@@ -345,6 +432,7 @@ mod tests {
     use storage::state::{ChainState, State};
     use storage::store::PrefixedStore;
     use utils::errors::ZeiError;
+    use utils::serialization::versioned_serde::VERSIONED_ENCODING_TAG;
 
     pub fn create_mt_leaf_info(proof: Proof) -> MTLeafInfo {
         MTLeafInfo {
@@ -493,6 +581,152 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_anon_xfr_with_fee() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let fee_type = AssetType::from_identical_byte(3);
+        let user_params =
+            UserParams::new_with_fee(1, 2, Some(1), DEFAULT_BP_NUM_GENS, &fee_type);
+
+        let zero = BLSScalar::zero();
+        let one = BLSScalar::one();
+        let two = one.add(&one);
+
+        // the input pays the outputs and a fee of 3
+        let (oabar, keypair_in, dec_key_in, _) =
+            gen_oabar_and_keys(&mut prng, 23, fee_type);
+        let abar = AnonBlindAssetRecord::from_oabar(&oabar);
+        let owner_memo = oabar.get_owner_memo().unwrap();
+
+        // simulate merklee tree state
+        let hash = RescueInstance::new();
+        let rand_pk_in_jj = abar.public_key.as_jubjub_point();
+        let pk_in_hash = hash.rescue_hash(&[
+            rand_pk_in_jj.get_x(),
+            rand_pk_in_jj.get_y(),
+            zero,
+            zero,
+        ])[0];
+        let leaf = hash.rescue_hash(&[
+            /*uid=*/ two,
+            abar.amount_type_commitment,
+            pk_in_hash,
+            zero,
+        ])[0];
+        let merkle_root = hash
+            .rescue_hash(&[/*sib1[0]=*/ one, /*sib2[0]=*/ two, leaf, zero])[0];
+        let mt_leaf_info = MTLeafInfo {
+            path: MTPath {
+                nodes: vec![MTNode {
+                    siblings1: one,
+                    siblings2: two,
+                    is_left_child: 0u8,
+                    is_right_child: 1u8,
+                }],
+            },
+            root: merkle_root,
+            uid: 2,
+            root_version: 0,
+        };
+        let oabar_in = OpenAnonBlindAssetRecordBuilder::from_abar(
+            &abar,
+            owner_memo,
+            &keypair_in,
+            &dec_key_in,
+        )
+        .unwrap()
+        .mt_leaf_info(mt_leaf_info)
+        .build()
+        .unwrap();
+
+        let (keypairs_out, _, enc_keys_out) = gen_keys(&mut prng, 2);
+        let oabars_out = [10u64, 10u64]
+            .iter()
+            .zip(keypairs_out.iter().zip(enc_keys_out.iter()))
+            .map(|(&amount, (keypair, enc_key))| {
+                OpenAnonBlindAssetRecordBuilder::new()
+                    .amount(amount)
+                    .asset_type(fee_type)
+                    .pub_key(keypair.pub_key())
+                    .finalize(&mut prng, enc_key)
+                    .unwrap()
+                    .build()
+                    .unwrap()
+            })
+            .collect_vec();
+
+        // the fee must make the amounts balance
+        for fee in [2, 4] {
+            msg_eq!(
+                ZeiError::XfrCreationAssetAmountError,
+                gen_anon_xfr_body_with_fee(
+                    &mut prng,
+                    &user_params,
+                    &[oabar_in.clone()],
+                    &oabars_out,
+                    &[keypair_in.clone()],
+                    &fee_type,
+                    fee
+                )
+                .unwrap_err(),
+            );
+        }
+        let (body, key_pairs) = gen_anon_xfr_body_with_fee(
+            &mut prng,
+            &user_params,
+            &[oabar_in],
+            &oabars_out,
+            &[keypair_in],
+            &fee_type,
+            3,
+        )
+        .unwrap();
+        assert_eq!(body.fee, 3);
+
+        let verifier_params = NodeParams::from(user_params);
+        pnk!(verify_anon_xfr_body(&verifier_params, &body, &merkle_root));
+        // the proof is bound to the fee
+        let mut bad_body = body.clone();
+        bad_body.fee = 2;
+        assert!(
+            verify_anon_xfr_body(&verifier_params, &bad_body, &merkle_root).is_err()
+        );
+
+        // the binary encoding of a body with a fee is versioned and ends with the fee
+        let bytes = bincode::serialize(&body).unwrap();
+        assert_eq!(bytes[..8], VERSIONED_ENCODING_TAG.to_le_bytes());
+        assert_eq!(bytes[bytes.len() - 8..], 3u64.to_le_bytes());
+        assert_eq!(body, bincode::deserialize(&bytes).unwrap());
+        let json = serde_json::to_string(&body).unwrap();
+        assert_eq!(body, serde_json::from_str(&json).unwrap());
+        // a versioned body has a fee
+        let mut bad_bytes = bytes.clone();
+        let n = bad_bytes.len();
+        bad_bytes[n - 8..].copy_from_slice(&0u64.to_le_bytes());
+        assert!(bincode::deserialize::<AXfrBody>(&bad_bytes).is_err());
+
+        // the encoding of a body with no fee is the one from before the fee
+        #[derive(Serialize, Deserialize)]
+        struct LegacyAXfrBody {
+            inputs: Vec<(Nullifier, AXfrPubKey)>,
+            outputs: Vec<AnonBlindAssetRecord>,
+            proof: AXfrProof,
+            owner_memos: Vec<OwnerMemo>,
+        }
+        let mut no_fee_body = body.clone();
+        no_fee_body.fee = 0;
+        let no_fee_bytes = bincode::serialize(&no_fee_body).unwrap();
+        let legacy_body: LegacyAXfrBody = bincode::deserialize(&no_fee_bytes).unwrap();
+        assert_eq!(no_fee_bytes, bincode::serialize(&legacy_body).unwrap());
+        assert_eq!(no_fee_body, bincode::deserialize(&no_fee_bytes).unwrap());
+
+        // the signatures sign the fee
+        let mut note = AXfrNote::generate_note_from_body(body, key_pairs).unwrap();
+        pnk!(note.verify());
+        note.body.fee = 2;
+        assert!(note.verify().is_err());
+    }
+
     // outputs &mut merkle tree (wrap it in an option merkle tree, not req)
     fn build_new_merkle_tree(
         n: i32,
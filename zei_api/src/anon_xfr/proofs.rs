@@ -1,5 +1,6 @@
 use crate::anon_xfr::circuits::{
-    build_eq_committed_vals_cs, build_multi_xfr_cs, AMultiXfrPubInputs, AMultiXfrWitness,
+    build_eq_committed_vals_cs, build_multi_xfr_cs, build_multi_xfr_cs_with_fees,
+    AMultiXfrPubInputs, AMultiXfrWitness,
 };
 use crate::setup::{NodeParams, UserParams};
 use algebra::bls12_381::BLSScalar;
@@ -23,10 +24,15 @@ pub(crate) type AXfrPlonkPf = PlonkPf<KZGCommitmentSchemeBLS>;
 /// * `rng` - pseudo-random generator.
 /// * `params` - System params
 /// * `secret_inputs` - input to generate witness of the constraint system
+/// * `fee_type` - asset type of the fee, ignored if `fee` is 0
+/// * `fee` - public fee paid by the inputs of type `fee_type`. If it is not 0, the constraint
+/// system of the params must be the one of build_multi_xfr_cs_with_fees for `fee_type`
 pub(crate) fn prove_xfr<R: CryptoRng + RngCore>(
     rng: &mut R,
     params: &UserParams,
     secret_inputs: AMultiXfrWitness,
+    fee_type: BLSScalar,
+    fee: u64,
 ) -> Result<AXfrPlonkPf> {
     let mut transcript = Transcript::new(ANON_XFR_TRANSCRIPT);
     transcript.append_u64(
@@ -38,17 +44,22 @@ pub(crate) fn prove_xfr<R: CryptoRng + RngCore>(
         secret_inputs.payees_secrets.len() as u64,
     );
 
-    let (mut cs, _) = build_multi_xfr_cs(secret_inputs);
-    let witness = cs.get_and_clear_witness();
-
-    prover(
-        rng,
-        &mut transcript,
-        &params.pcs,
-        &params.cs,
-        &params.prover_params,
-        &witness,
-    )
+    let (mut cs, _) = if fee == 0 {
+        build_multi_xfr_cs(secret_inputs)
+    } else {
+        build_multi_xfr_cs_with_fees(secret_inputs, fee_type, fee)
+    };
+    // the witness is wiped once the proof is computed
+    cs.with_witness(|witness| {
+        prover(
+            rng,
+            &mut transcript,
+            &params.pcs,
+            &params.cs,
+            &params.prover_params,
+            witness,
+        )
+    })
     .c(d!(ZeiError::AXfrProofError))
 }
 
@@ -100,16 +111,17 @@ pub(crate) fn prove_eq_committed_vals<R: CryptoRng + RngCore>(
     let mut transcript = Transcript::new(EQ_COMM_TRANSCRIPT);
     let (mut cs, _) =
         build_eq_committed_vals_cs(amount, asset_type, blind_pc, blind_hash, pc_gens);
-    let witness = cs.get_and_clear_witness();
-
-    prover(
-        rng,
-        &mut transcript,
-        &params.pcs,
-        &params.cs,
-        &params.prover_params,
-        &witness,
-    )
+    // the witness is wiped once the proof is computed
+    cs.with_witness(|witness| {
+        prover(
+            rng,
+            &mut transcript,
+            &params.pcs,
+            &params.cs,
+            &params.prover_params,
+            witness,
+        )
+    })
     .c(d!(ZeiError::AXfrProofError))
 }
 
@@ -141,16 +153,23 @@ pub(crate) fn verify_eq_committed_vals(
 #[cfg(test)]
 mod tests {
     use crate::anon_xfr::circuits::tests::new_multi_xfr_witness_for_test;
-    use crate::anon_xfr::circuits::AMultiXfrPubInputs;
+    use crate::anon_xfr::circuits::{build_multi_xfr_cs, AMultiXfrPubInputs};
     use crate::anon_xfr::proofs::{
         prove_eq_committed_vals, prove_xfr, verify_eq_committed_vals, verify_xfr,
+        ANON_XFR_TRANSCRIPT, N_INPUTS_TRANSCRIPT, N_OUTPUTS_TRANSCRIPT,
     };
-    use crate::setup::{NodeParams, UserParams, DEFAULT_BP_NUM_GENS};
+    use crate::setup::{NodeParams, UserParams, COMMON_SEED, DEFAULT_BP_NUM_GENS};
     use algebra::bls12_381::BLSScalar;
     use algebra::groups::{Group, GroupArithmetic, One, Scalar, Zero};
     use algebra::jubjub::{JubjubPoint, JubjubScalar};
     use crypto::basics::commitments::pedersen::PedersenGens;
     use crypto::basics::commitments::rescue::HashCommitment;
+    use merlin::Transcript;
+    use poly_iops::plonk::low_memory::{
+        low_memory_chunk_bytes, preprocess_prover_low_memory, prover_low_memory,
+        MemoryCosetEvalStorage,
+    };
+    use poly_iops::plonk::plonk_setup::ConstraintSystem;
     use rand::RngCore;
     use rand_chacha::ChaChaRng;
     use rand_core::SeedableRng;
@@ -322,6 +341,7 @@ mod tests {
     ) {
         let n_payers = inputs.len();
         let n_payees = outputs.len();
+        let zero = BLSScalar::zero();
 
         // build cs
         let secret_inputs =
@@ -336,12 +356,13 @@ mod tests {
         )
         .unwrap();
         let mut prng = ChaChaRng::from_seed([0u8; 32]);
-        let proof = prove_xfr(&mut prng, &params, secret_inputs).unwrap();
+        let proof = prove_xfr(&mut prng, &params, secret_inputs, zero, 0).unwrap();
 
         // A bad proof should fail the verification
         let bad_secret_inputs =
             new_multi_xfr_witness_for_test(inputs, outputs, [1u8; 32]);
-        let bad_proof = prove_xfr(&mut prng, &params, bad_secret_inputs).unwrap();
+        let bad_proof =
+            prove_xfr(&mut prng, &params, bad_secret_inputs, zero, 0).unwrap();
 
         // verify good witness
         let node_params = NodeParams::from(params);
@@ -351,6 +372,61 @@ mod tests {
         assert!(verify_xfr(&node_params, &pub_inputs, &bad_proof).is_err());
     }
 
+    #[test]
+    fn test_anon_xfr_proof_low_memory() {
+        // the range checks of the circuit are lookup gates
+        let zero = BLSScalar::zero();
+        let inputs = vec![(60, zero)];
+        let outputs = vec![(60, zero)];
+        let params =
+            UserParams::from_file_if_exists(1, 1, Some(1), DEFAULT_BP_NUM_GENS, None)
+                .unwrap();
+        assert!(params.cs.lookup_columns().is_some());
+        let mut storage = MemoryCosetEvalStorage::new();
+        let low_memory_params = preprocess_prover_low_memory(
+            &params.cs,
+            &params.pcs,
+            COMMON_SEED,
+            &mut storage,
+        )
+        .unwrap();
+
+        let secret_inputs =
+            new_multi_xfr_witness_for_test(inputs.to_vec(), outputs.to_vec(), [0u8; 32]);
+        let pub_inputs = AMultiXfrPubInputs::from_witness(&secret_inputs);
+        let (mut cs, _) = build_multi_xfr_cs(secret_inputs);
+        let witness = cs.get_and_clear_witness();
+        let mut transcript = Transcript::new(ANON_XFR_TRANSCRIPT);
+        transcript.append_u64(N_INPUTS_TRANSCRIPT, 1);
+        transcript.append_u64(N_OUTPUTS_TRANSCRIPT, 1);
+        // one chunk of the quotient polynomial at a time
+        let proof = prover_low_memory(
+            &mut ChaChaRng::from_seed([1u8; 32]),
+            &mut transcript,
+            &params.pcs,
+            &params.cs,
+            &low_memory_params,
+            &storage,
+            low_memory_chunk_bytes(&params.cs),
+            &witness,
+        )
+        .unwrap();
+
+        // the proof of prove_xfr, with the same randomness
+        let secret_inputs = new_multi_xfr_witness_for_test(inputs, outputs, [0u8; 32]);
+        let expected_proof = prove_xfr(
+            &mut ChaChaRng::from_seed([1u8; 32]),
+            &params,
+            secret_inputs,
+            zero,
+            0,
+        )
+        .unwrap();
+        assert_eq!(proof, expected_proof);
+        let node_params = NodeParams::from(params);
+        assert!(verify_xfr(&node_params, &pub_inputs, &proof).is_ok());
+    }
+
     #[test]
     fn test_eq_committed_vals_proof() {
         let mut prng = ChaChaRng::from_seed([0u8; 32]);
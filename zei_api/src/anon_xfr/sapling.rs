@@ -0,0 +1,562 @@
+//! # Zcash Sapling interoperability
+//!
+//! Sapling (<https://zips.z.cash/protocol/protocol.pdf>, section 4 and 5.4) and the anonymous
+//! transfers of zei are both built on the Jubjub curve, hence Sapling keys and notes can be
+//! handled with zei's Jubjub types. This file implements:
+//! * the key components: expanded spending key (ask, nsk, ovk), full viewing key (ak, nk, ovk),
+//!   incoming viewing key ivk and payment addresses (d, pk_d),
+//! * the note commitment NoteCommit^Sapling (windowed Pedersen commitment) and the nullifier
+//!   PRF^nfSapling, so that notes can be verified against the commitments and nullifiers
+//!   published on the Zcash chain,
+//! * the import of spending keys and notes into zei's anonymous transfer structures.
+//!
+//! Sapling proofs and signatures (Groth16, RedJubjub) are not compatible with zei's, and zei's
+//! record commitments are Rescue based: an imported note is re-issued as a new zei record of the
+//! same amount, and an imported spending key controls zei records with the same Jubjub secret
+//! scalar but different public key, since zei uses another generator than Sapling.
+
+use crate::anon_xfr::keys::{AXfrKeyPair, AXfrPubKey};
+use crate::anon_xfr::structs::OpenAnonBlindAssetRecordBuilder;
+use crate::xfr::structs::AssetType;
+use algebra::bls12_381::BLSScalar;
+use algebra::groups::{Group, GroupArithmetic, Scalar, ScalarArithmetic};
+use algebra::jubjub::{JubjubPoint, JubjubScalar};
+use ruc::*;
+use utils::errors::ZeiError;
+
+pub const SAPLING_DIVERSIFIER_LENGTH: usize = 11;
+pub const SAPLING_PAYMENT_ADDRESS_LENGTH: usize = SAPLING_DIVERSIFIER_LENGTH + 32;
+pub const SAPLING_KEY_LENGTH: usize = 96;
+
+// Uniform random string of the Sapling group hash
+const SAPLING_URS: &[u8] =
+    b"096b36a5804bfacef1691e173c366a47ff5ba84a44f26ddd7e8d9f79d5b42df0";
+const SPENDING_KEY_GENERATOR_PERSONAL: &[u8] = b"Zcash_G_";
+const PROOF_GENERATION_KEY_GENERATOR_PERSONAL: &[u8] = b"Zcash_H_";
+const PEDERSEN_HASH_GENERATORS_PERSONAL: &[u8] = b"Zcash_PH";
+const NULLIFIER_POSITION_GENERATOR_PERSONAL: &[u8] = b"Zcash_J_";
+const DIVERSIFIER_PERSONAL: &[u8] = b"Zcash_gd";
+const IVK_PERSONAL: &[u8] = b"Zcashivk";
+const NULLIFIER_PERSONAL: &[u8] = b"Zcash_nf";
+const EXPAND_SEED_PERSONAL: &[u8] = b"Zcash_ExpandSeed";
+const PEDERSEN_HASH_CHUNKS_PER_SEGMENT: usize = 63;
+
+// GroupHash^J*: BLAKE2s-256 of the message decoded as a point, multiplied by the cofactor
+fn group_hash(personal: &[u8], msg: &[u8]) -> Option<JubjubPoint> {
+    let hash = blake2s_simd::Params::new()
+        .hash_length(32)
+        .personal(personal)
+        .to_state()
+        .update(SAPLING_URS)
+        .update(msg)
+        .finalize();
+    let point = JubjubPoint::from_sapling_bytes(hash.as_bytes())
+        .ok()?
+        .mul_by_cofactor();
+    if point == JubjubPoint::get_identity() {
+        None
+    } else {
+        Some(point)
+    }
+}
+
+// FindGroupHash^J*: first valid group hash of msg || i
+fn find_group_hash(personal: &[u8], msg: &[u8]) -> JubjubPoint {
+    let mut input = msg.to_vec();
+    input.push(0);
+    loop {
+        if let Some(point) = group_hash(personal, &input) {
+            return point;
+        }
+        // the probability to fail 256 times is negligible
+        input[msg.len()] += 1;
+    }
+}
+
+// PRF^expand: BLAKE2b-512 of sk || t
+fn prf_expand(sk: &[u8; 32], t: u8) -> [u8; 64] {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(64)
+        .personal(EXPAND_SEED_PERSONAL)
+        .to_state()
+        .update(sk)
+        .update(&[t])
+        .finalize();
+    let mut bytes = [0u8; 64];
+    bytes.copy_from_slice(hash.as_bytes());
+    bytes
+}
+
+// ToScalar: 512 bits little-endian integer reduced modulo the Jubjub order
+fn scalar_from_wide_bytes(bytes: &[u8; 64]) -> JubjubScalar {
+    let two_256 = JubjubScalar::from_u32(2).pow(&[256]);
+    let low = JubjubScalar::from_bytes(&bytes[..32]).unwrap(); // safe unwrap
+    let high = JubjubScalar::from_bytes(&bytes[32..]).unwrap(); // safe unwrap
+    low.add(&high.mul(&two_256))
+}
+
+fn canonical_scalar_from_bytes(bytes: &[u8]) -> Result<JubjubScalar> {
+    let scalar =
+        JubjubScalar::from_bytes(bytes).c(d!(ZeiError::DeserializationError))?;
+    if scalar.to_bytes() != bytes {
+        return Err(eg!(ZeiError::DeserializationError));
+    }
+    Ok(scalar)
+}
+
+// little-endian bits of each byte
+fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|b| (0..8).map(move |i| (b >> i) & 1 == 1))
+        .collect()
+}
+
+// PedersenHashToPoint: the message is split in 3 bits chunks, the i-th segment of 63 chunks
+// encodes a scalar for the i-th generator
+fn pedersen_hash_to_point(personal: &[u8], bits: &[bool]) -> JubjubPoint {
+    let mut result = JubjubPoint::get_identity();
+    let chunks_per_segment = PEDERSEN_HASH_CHUNKS_PER_SEGMENT * 3;
+    for (i, segment) in bits.chunks(chunks_per_segment).enumerate() {
+        let mut scalar = JubjubScalar::from_u32(0);
+        let mut power = JubjubScalar::from_u32(1);
+        let sixteen = JubjubScalar::from_u32(16);
+        for chunk in segment.chunks(3) {
+            // enc(s0, s1, s2) = (1 - 2*s2) * (1 + s0 + 2*s1), missing bits are zero
+            let s0 = chunk[0];
+            let s1 = chunk.get(1).copied().unwrap_or(false);
+            let s2 = chunk.get(2).copied().unwrap_or(false);
+            let enc = JubjubScalar::from_u32(1 + s0 as u32 + 2 * (s1 as u32));
+            if s2 {
+                scalar = scalar.sub(&enc.mul(&power));
+            } else {
+                scalar = scalar.add(&enc.mul(&power));
+            }
+            power = power.mul(&sixteen);
+        }
+        let generator = find_group_hash(personal, &(i as u32).to_le_bytes());
+        result = result.add(&generator.mul(&scalar));
+    }
+    result
+}
+
+/// Sapling expanded spending key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaplingExpandedSpendingKey {
+    ask: JubjubScalar,
+    nsk: JubjubScalar,
+    ovk: [u8; 32],
+}
+
+impl SaplingExpandedSpendingKey {
+    /// Expand the Sapling spending key `sk`
+    pub fn from_spending_key(sk: &[u8; 32]) -> SaplingExpandedSpendingKey {
+        let mut ovk = [0u8; 32];
+        ovk.copy_from_slice(&prf_expand(sk, 2)[..32]);
+        SaplingExpandedSpendingKey {
+            ask: scalar_from_wide_bytes(&prf_expand(sk, 0)),
+            nsk: scalar_from_wide_bytes(&prf_expand(sk, 1)),
+            ovk,
+        }
+    }
+
+    /// Decode `ask || nsk || ovk`
+    pub fn from_bytes(bytes: &[u8]) -> Result<SaplingExpandedSpendingKey> {
+        if bytes.len() != SAPLING_KEY_LENGTH {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
+        let mut ovk = [0u8; 32];
+        ovk.copy_from_slice(&bytes[64..]);
+        Ok(SaplingExpandedSpendingKey {
+            ask: canonical_scalar_from_bytes(&bytes[..32]).c(d!())?,
+            nsk: canonical_scalar_from_bytes(&bytes[32..64]).c(d!())?,
+            ovk,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.ask.to_bytes();
+        bytes.extend_from_slice(&self.nsk.to_bytes());
+        bytes.extend_from_slice(&self.ovk);
+        bytes
+    }
+
+    pub fn full_viewing_key(&self) -> SaplingFullViewingKey {
+        SaplingFullViewingKey {
+            ak: find_group_hash(SPENDING_KEY_GENERATOR_PERSONAL, &[]).mul(&self.ask),
+            nk: find_group_hash(PROOF_GENERATION_KEY_GENERATOR_PERSONAL, &[])
+                .mul(&self.nsk),
+            ovk: self.ovk,
+        }
+    }
+
+    /// Anonymous transfer key pair with the spend authorizing key `ask` as secret key.
+    /// Its public key is not `ak`, zei and Sapling use different generators.
+    pub fn to_axfr_key_pair(&self) -> AXfrKeyPair {
+        AXfrKeyPair::from_secret_scalar(self.ask)
+    }
+}
+
+/// Sapling full viewing key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaplingFullViewingKey {
+    ak: JubjubPoint,
+    nk: JubjubPoint,
+    ovk: [u8; 32],
+}
+
+impl SaplingFullViewingKey {
+    /// Decode `ak || nk || ovk`
+    pub fn from_bytes(bytes: &[u8]) -> Result<SaplingFullViewingKey> {
+        if bytes.len() != SAPLING_KEY_LENGTH {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
+        let ak = JubjubPoint::from_sapling_bytes(&bytes[..32])
+            .c(d!(ZeiError::DecompressElementError))?;
+        let nk = JubjubPoint::from_sapling_bytes(&bytes[32..64])
+            .c(d!(ZeiError::DecompressElementError))?;
+        if ak.mul_by_cofactor() == JubjubPoint::get_identity() {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
+        let mut ovk = [0u8; 32];
+        ovk.copy_from_slice(&bytes[64..]);
+        Ok(SaplingFullViewingKey { ak, nk, ovk })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.ak.to_sapling_bytes().to_vec();
+        bytes.extend_from_slice(&self.nk.to_sapling_bytes());
+        bytes.extend_from_slice(&self.ovk);
+        bytes
+    }
+
+    /// CRH^ivk: BLAKE2s-256 of `ak || nk` truncated to 251 bits
+    pub fn incoming_viewing_key(&self) -> SaplingIncomingViewingKey {
+        let hash = blake2s_simd::Params::new()
+            .hash_length(32)
+            .personal(IVK_PERSONAL)
+            .to_state()
+            .update(&self.ak.to_sapling_bytes())
+            .update(&self.nk.to_sapling_bytes())
+            .finalize();
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(hash.as_bytes());
+        bytes[31] &= 0x07;
+        SaplingIncomingViewingKey(JubjubScalar::from_bytes(&bytes).unwrap()) // safe unwrap
+    }
+
+    /// Nullifier of `note` at position `position` of the note commitment tree
+    pub fn nullifier(&self, note: &SaplingNote, position: u64) -> Result<[u8; 32]> {
+        let rho = note.commitment_point().c(d!())?.add(
+            &find_group_hash(NULLIFIER_POSITION_GENERATOR_PERSONAL, &[])
+                .mul(&JubjubScalar::from_u64(position)),
+        );
+        let hash = blake2s_simd::Params::new()
+            .hash_length(32)
+            .personal(NULLIFIER_PERSONAL)
+            .to_state()
+            .update(&self.nk.to_sapling_bytes())
+            .update(&rho.to_sapling_bytes())
+            .finalize();
+        let mut nullifier = [0u8; 32];
+        nullifier.copy_from_slice(hash.as_bytes());
+        Ok(nullifier)
+    }
+}
+
+/// Sapling incoming viewing key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SaplingIncomingViewingKey(JubjubScalar);
+
+impl SaplingIncomingViewingKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SaplingIncomingViewingKey> {
+        if bytes.len() != 32 || bytes[31] & 0xf8 != 0 {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
+        Ok(SaplingIncomingViewingKey(
+            JubjubScalar::from_bytes(bytes).c(d!(ZeiError::DeserializationError))?,
+        ))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Payment address of diversifier `diversifier`, fails if the diversifier is invalid
+    pub fn payment_address(
+        &self,
+        diversifier: &[u8; SAPLING_DIVERSIFIER_LENGTH],
+    ) -> Result<SaplingPaymentAddress> {
+        let address = SaplingPaymentAddress {
+            diversifier: *diversifier,
+            pk_d: JubjubPoint::get_identity(),
+        };
+        let g_d = address.diversified_base().c(d!())?;
+        Ok(SaplingPaymentAddress {
+            pk_d: g_d.mul(&self.0),
+            ..address
+        })
+    }
+}
+
+/// Sapling payment address
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaplingPaymentAddress {
+    diversifier: [u8; SAPLING_DIVERSIFIER_LENGTH],
+    pk_d: JubjubPoint,
+}
+
+impl SaplingPaymentAddress {
+    /// Decode `d || pk_d`
+    pub fn from_bytes(bytes: &[u8]) -> Result<SaplingPaymentAddress> {
+        if bytes.len() != SAPLING_PAYMENT_ADDRESS_LENGTH {
+            return Err(eg!(ZeiError::DeserializationError));
+        }
+        let mut diversifier = [0u8; SAPLING_DIVERSIFIER_LENGTH];
+        diversifier.copy_from_slice(&bytes[..SAPLING_DIVERSIFIER_LENGTH]);
+        let address = SaplingPaymentAddress {
+            diversifier,
+            pk_d: JubjubPoint::from_sapling_bytes(&bytes[SAPLING_DIVERSIFIER_LENGTH..])
+                .c(d!(ZeiError::DecompressElementError))?,
+        };
+        address.diversified_base().c(d!())?;
+        Ok(address)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.diversifier.to_vec();
+        bytes.extend_from_slice(&self.pk_d.to_sapling_bytes());
+        bytes
+    }
+
+    pub fn diversifier(&self) -> &[u8; SAPLING_DIVERSIFIER_LENGTH] {
+        &self.diversifier
+    }
+
+    pub fn transmission_key(&self) -> &JubjubPoint {
+        &self.pk_d
+    }
+
+    /// DiversifyHash: the base g_d of the address
+    pub fn diversified_base(&self) -> Result<JubjubPoint> {
+        group_hash(DIVERSIFIER_PERSONAL, &self.diversifier)
+            .c(d!(ZeiError::ParameterError))
+    }
+}
+
+/// Sapling note: a value sent to a payment address, with commitment randomness `rcm`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaplingNote {
+    address: SaplingPaymentAddress,
+    value: u64,
+    rcm: JubjubScalar,
+}
+
+impl SaplingNote {
+    pub fn new(address: SaplingPaymentAddress, value: u64, rcm: JubjubScalar) -> Self {
+        SaplingNote {
+            address,
+            value,
+            rcm,
+        }
+    }
+
+    pub fn get_address(&self) -> &SaplingPaymentAddress {
+        &self.address
+    }
+
+    pub fn get_value(&self) -> u64 {
+        self.value
+    }
+
+    /// NoteCommit^Sapling: windowed Pedersen commitment to
+    /// `1^6 || value || repr(g_d) || repr(pk_d)` with randomness `rcm`
+    pub fn commitment_point(&self) -> Result<JubjubPoint> {
+        let g_d = self.address.diversified_base().c(d!())?;
+        let mut bits = vec![true; 6];
+        bits.extend(bytes_to_bits(&self.value.to_le_bytes()));
+        bits.extend(bytes_to_bits(&g_d.to_sapling_bytes()));
+        bits.extend(bytes_to_bits(&self.address.pk_d.to_sapling_bytes()));
+        let randomness_base = find_group_hash(PEDERSEN_HASH_GENERATORS_PERSONAL, b"r");
+        Ok(
+            pedersen_hash_to_point(PEDERSEN_HASH_GENERATORS_PERSONAL, &bits)
+                .add(&randomness_base.mul(&self.rcm)),
+        )
+    }
+
+    /// Note commitment as published on chain (cm_u): the x-coordinate of the commitment point
+    pub fn commitment(&self) -> Result<BLSScalar> {
+        Ok(self.commitment_point().c(d!())?.get_x())
+    }
+
+    /// Check the note against the note commitment `cm`
+    pub fn verify_commitment(&self, cm: &BLSScalar) -> Result<()> {
+        if &self.commitment().c(d!())? != cm {
+            return Err(eg!(ZeiError::CommitmentVerificationError));
+        }
+        Ok(())
+    }
+
+    /// Builder of the anonymous record that re-issues the note value as `asset_type` to
+    /// `pub_key`
+    pub fn to_oabar_builder(
+        &self,
+        asset_type: AssetType,
+        pub_key: AXfrPubKey,
+    ) -> OpenAnonBlindAssetRecordBuilder {
+        OpenAnonBlindAssetRecordBuilder::new()
+            .amount(self.value)
+            .asset_type(asset_type)
+            .pub_key(pub_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        SaplingExpandedSpendingKey, SaplingFullViewingKey, SaplingIncomingViewingKey,
+        SaplingNote, SaplingPaymentAddress,
+    };
+    use crate::xfr::structs::AssetType;
+    use algebra::groups::{Group, GroupArithmetic, Scalar};
+    use algebra::jubjub::{JubjubPoint, JubjubScalar};
+    use crypto::basics::hybrid_encryption::{XPublicKey, XSecretKey};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn sapling_generators() {
+        // spending key generator of the Sapling specification
+        let generator =
+            super::find_group_hash(super::SPENDING_KEY_GENERATOR_PERSONAL, &[]);
+        let mut x =
+            from_hex("0926d4f32059c712d418a7ff26753b6ad5b9a7d3ef8e282747bf46920a95a753");
+        let mut y =
+            from_hex("57a1019e6de9b67553bb37d0c21cfd056d65674dcedbddbc305632adaaf2b530");
+        x.reverse();
+        y.reverse();
+        assert_eq!(generator.get_x().to_bytes(), x);
+        assert_eq!(generator.get_y().to_bytes(), y);
+    }
+
+    #[test]
+    fn sapling_key_components() {
+        // test vector of the Zcash specification
+        let expsk = SaplingExpandedSpendingKey::from_spending_key(&[0u8; 32]);
+        let bytes = expsk.to_bytes();
+        assert_eq!(
+            bytes[..32].to_vec(),
+            from_hex("8548a14a473ea547aa2378402044f818cf1911cf5dd2054f678345f00d0e8806")
+        );
+        assert_eq!(
+            bytes[32..64].to_vec(),
+            from_hex("30114ea0dd0bb61cf0eaeab6ec3331f581b0425e27338501262d7eac745e6e05")
+        );
+        assert_eq!(
+            bytes[64..].to_vec(),
+            from_hex("98d16913d99b04177caba44f6e4d224e03b5ac031d7ce45e865138e1b996d63b")
+        );
+        assert_eq!(pnk!(SaplingExpandedSpendingKey::from_bytes(&bytes)), expsk);
+
+        let fvk = expsk.full_viewing_key();
+        let fvk_bytes = fvk.to_bytes();
+        assert_eq!(
+            fvk_bytes[..32].to_vec(),
+            from_hex("f344ec380fe1273e3098c2588c5d3a791fd7ba958032760777fd0efa8ef11620")
+        );
+        assert_eq!(
+            fvk_bytes[32..64].to_vec(),
+            from_hex("f7cf9e77f2e58683383c1519ac7b062d30040e27a725fb88fb19a978bd3fd6ba")
+        );
+        assert_eq!(pnk!(SaplingFullViewingKey::from_bytes(&fvk_bytes)), fvk);
+
+        let ivk = fvk.incoming_viewing_key();
+        assert_eq!(
+            ivk.to_bytes(),
+            from_hex("b70b7cd0ed03cbdfd7ada9502ee245b13e569d54a5719d2daa0f5f1451479204")
+        );
+        assert_eq!(
+            pnk!(SaplingIncomingViewingKey::from_bytes(&ivk.to_bytes())),
+            ivk
+        );
+
+        let mut diversifier = [0u8; 11];
+        diversifier.copy_from_slice(&from_hex("f19d9b797e39f337445839"));
+        let address = pnk!(ivk.payment_address(&diversifier));
+        assert_eq!(
+            address.transmission_key().to_sapling_bytes().to_vec(),
+            from_hex("db4cd2b0aac4f7eb8ca131f16567c445a9555126d3c29f14e3d776e841ae7415")
+        );
+        assert_eq!(
+            pnk!(SaplingPaymentAddress::from_bytes(&address.to_bytes())),
+            address
+        );
+
+        // invalid diversifier
+        let mut diversifier = [0u8; 11];
+        diversifier[0] = 1;
+        assert!(ivk.payment_address(&diversifier).is_err());
+
+        // non canonical or truncated encodings
+        let mut bad_bytes = bytes.clone();
+        bad_bytes[31] = 0xff;
+        assert!(SaplingExpandedSpendingKey::from_bytes(&bad_bytes).is_err());
+        assert!(SaplingExpandedSpendingKey::from_bytes(&bytes[..95]).is_err());
+        assert!(SaplingFullViewingKey::from_bytes(&fvk_bytes[..95]).is_err());
+        let mut bad_ivk = ivk.to_bytes();
+        bad_ivk[31] |= 0x08;
+        assert!(SaplingIncomingViewingKey::from_bytes(&bad_ivk).is_err());
+    }
+
+    #[test]
+    fn sapling_note() {
+        let expsk = SaplingExpandedSpendingKey::from_spending_key(&[0u8; 32]);
+        let fvk = expsk.full_viewing_key();
+        let mut diversifier = [0u8; 11];
+        diversifier.copy_from_slice(&from_hex("f19d9b797e39f337445839"));
+        let address = pnk!(fvk.incoming_viewing_key().payment_address(&diversifier));
+        let rcm = pnk!(JubjubScalar::from_bytes(&from_hex(
+            "39176dac39ace4980ecc8d778e89860255ec36150600ba44a28e2f82652b5905"
+        )));
+        let note = SaplingNote::new(address.clone(), 100000, rcm);
+
+        let cm = pnk!(note.commitment());
+        assert_eq!(
+            cm.to_bytes(),
+            from_hex("0f3a44e8ae2623857ef4595866b381cd029bacccc5848029d778bb6bcdab270a")
+        );
+        pnk!(note.verify_commitment(&cm));
+        assert_eq!(
+            pnk!(fvk.nullifier(&note, 7)).to_vec(),
+            from_hex("59cd8846f8379aa4a797d6f75ad47719b587f0f69a6cf2bbb06baeb693f11421")
+        );
+        assert_ne!(pnk!(fvk.nullifier(&note, 7)), pnk!(fvk.nullifier(&note, 8)));
+
+        let other_note = SaplingNote::new(address, 100001, rcm);
+        assert!(other_note.verify_commitment(&cm).is_err());
+
+        // import in zei
+        let key_pair = expsk.to_axfr_key_pair();
+        assert_eq!(
+            key_pair.pub_key().as_jubjub_point(),
+            &JubjubPoint::get_base().mul(&key_pair.get_secret_scalar())
+        );
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let enc_key = XPublicKey::from(&XSecretKey::new(&mut prng));
+        let oabar = pnk!(pnk!(note
+            .to_oabar_builder(AssetType([1u8; 32]), key_pair.pub_key())
+            .finalize(&mut prng, &enc_key))
+        .build());
+        assert_eq!(oabar.get_amount(), 100000);
+        assert_eq!(oabar.get_asset_type(), AssetType([1u8; 32]));
+        assert_eq!(oabar.pub_key_ref(), &key_pair.pub_key());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::anon_xfr::decrypt_memo;
 use crate::anon_xfr::keys::{AXfrKeyPair, AXfrPubKey, AXfrSignature};
+use crate::setup::MAX_SAFE_XFR_INPUTS;
 use crate::xfr::structs::{AssetType, OwnerMemo};
 use algebra::bls12_381::{BLSScalar, Bls12381};
 use algebra::groups::{Scalar, Zero};
@@ -12,8 +13,15 @@ use poly_iops::commitments::kzg_poly_com::KZGCommitmentScheme;
 use poly_iops::plonk::protocol::prover::PlonkPf;
 use rand_core::{CryptoRng, RngCore};
 use ruc::*;
+use serde::de::Error as _;
+use serde::ser::SerializeTuple;
 use serde::Serialize;
 use utils::errors::ZeiError;
+use utils::serialization::versioned_serde::{
+    next_element, next_versioned_head, VersionedHead, VERSIONED_ENCODING_MAX_LEN,
+    VERSIONED_ENCODING_TAG,
+};
+use utils::serialization::Validate;
 use wasm_bindgen::prelude::*;
 
 pub type Nullifier = BLSScalar;
@@ -61,6 +69,7 @@ impl AXfrNote {
     }
 
     pub fn verify(&self) -> Result<()> {
+        self.validate().c(d!())?;
         let msg: Vec<u8> = bincode::serialize(&self.body)
             .map_err(|_| ZeiError::SerializationError)
             .c(d!())?;
@@ -77,13 +86,171 @@ impl AXfrNote {
     }
 }
 
+impl Validate for AXfrNote {
+    /// Checks the body and that there is one signature for each input
+    fn validate(&self) -> Result<()> {
+        self.body.validate().c(d!())?;
+        if self.signatures.len() != self.body.inputs.len() {
+            return Err(eg!(ZeiError::InconsistentStructureError));
+        }
+        Ok(())
+    }
+}
+
+/// Version of the binary encoding of the anonymous transfer bodies that pay a fee. The bodies
+/// with no fee keep the encoding from before the fee, with no version, so that the signatures of
+/// the notes created before still verify.
+const AXFR_BODY_FEE_VERSION: u32 = 1;
+
 /// Anonymous transfers structure
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Eq)]
+/// The binary encoding of a body that pays a fee starts with a version, see
+/// utils::serialization::versioned_serde, and ends with the fee
+#[derive(Debug, PartialEq, Clone, Eq)]
 pub struct AXfrBody {
     pub inputs: Vec<(Nullifier, AXfrPubKey)>,
     pub outputs: Vec<AnonBlindAssetRecord>,
     pub proof: AXfrProof,
     pub owner_memos: Vec<OwnerMemo>,
+    pub fee: u64, // public fee paid by the inputs of the fee type of the params, 0 if no fee is paid
+}
+
+// Encoding of the fields by name, for self-describing encodings
+#[derive(Serialize)]
+struct AXfrBodyFieldsRef<'a> {
+    inputs: &'a Vec<(Nullifier, AXfrPubKey)>,
+    outputs: &'a Vec<AnonBlindAssetRecord>,
+    proof: &'a AXfrProof,
+    owner_memos: &'a Vec<OwnerMemo>,
+    fee: u64,
+}
+
+#[derive(Deserialize)]
+struct AXfrBodyFields {
+    inputs: Vec<(Nullifier, AXfrPubKey)>,
+    outputs: Vec<AnonBlindAssetRecord>,
+    proof: AXfrProof,
+    owner_memos: Vec<OwnerMemo>,
+    #[serde(default)]
+    fee: u64,
+}
+
+impl serde::Serialize for AXfrBody {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if serializer.is_human_readable() {
+            return serde::Serialize::serialize(
+                &AXfrBodyFieldsRef {
+                    inputs: &self.inputs,
+                    outputs: &self.outputs,
+                    proof: &self.proof,
+                    owner_memos: &self.owner_memos,
+                    fee: self.fee,
+                },
+                serializer,
+            );
+        }
+        if self.fee == 0 {
+            let mut tuple = serializer.serialize_tuple(4)?;
+            tuple.serialize_element(&self.inputs)?;
+            tuple.serialize_element(&self.outputs)?;
+            tuple.serialize_element(&self.proof)?;
+            tuple.serialize_element(&self.owner_memos)?;
+            return tuple.end();
+        }
+        let mut tuple = serializer.serialize_tuple(7)?;
+        tuple.serialize_element(&VERSIONED_ENCODING_TAG)?;
+        tuple.serialize_element(&AXFR_BODY_FEE_VERSION)?;
+        tuple.serialize_element(&self.inputs)?;
+        tuple.serialize_element(&self.outputs)?;
+        tuple.serialize_element(&self.proof)?;
+        tuple.serialize_element(&self.owner_memos)?;
+        tuple.serialize_element(&self.fee)?;
+        tuple.end()
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for AXfrBody {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let fields: AXfrBodyFields = serde::Deserialize::deserialize(deserializer)?;
+            return Ok(AXfrBody {
+                inputs: fields.inputs,
+                outputs: fields.outputs,
+                proof: fields.proof,
+                owner_memos: fields.owner_memos,
+                fee: fields.fee,
+            });
+        }
+        deserializer.deserialize_tuple(VERSIONED_ENCODING_MAX_LEN, AXfrBodyVisitor)
+    }
+}
+
+struct AXfrBodyVisitor;
+
+impl<'de> serde::de::Visitor<'de> for AXfrBodyVisitor {
+    type Value = AXfrBody;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an anonymous transfer body")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<AXfrBody, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let (inputs, legacy) = match next_versioned_head(&mut seq)? {
+            VersionedHead::Versioned(AXFR_BODY_FEE_VERSION) => {
+                (next_element(&mut seq)?, false)
+            }
+            VersionedHead::Versioned(_) => {
+                return Err(A::Error::custom(
+                    "unsupported version of anonymous transfer body",
+                ));
+            }
+            VersionedHead::Legacy(inputs) => (inputs, true),
+        };
+        let outputs = next_element(&mut seq)?;
+        let proof = next_element(&mut seq)?;
+        let owner_memos = next_element(&mut seq)?;
+        let fee = if legacy { 0 } else { next_element(&mut seq)? };
+        // a body with no fee has a single encoding, the legacy one, which its signatures sign
+        if !legacy && fee == 0 {
+            return Err(A::Error::custom(
+                "versioned anonymous transfer body with no fee",
+            ));
+        }
+        Ok(AXfrBody {
+            inputs,
+            outputs,
+            proof,
+            owner_memos,
+            fee,
+        })
+    }
+}
+
+impl Validate for AXfrBody {
+    /// Checks the number of inputs and outputs, that each output has an owner memo and the
+    /// structure of the proof
+    fn validate(&self) -> Result<()> {
+        if self.inputs.is_empty()
+            || self.outputs.is_empty()
+            || self.inputs.len() > MAX_SAFE_XFR_INPUTS
+            || self.outputs.len() > MAX_SAFE_XFR_INPUTS
+            || self.owner_memos.len() != self.outputs.len()
+        {
+            return Err(eg!(ZeiError::InconsistentStructureError));
+        }
+        self.proof
+            .snark_proof
+            .validate()
+            .c(d!(ZeiError::InconsistentStructureError))
+    }
 }
 
 /// Asset record to be published
@@ -30,8 +30,27 @@ pub type Credential = crypto::anon_creds::Credential<G1, G2, Attr>;
 
 pub type ACRevealProof = crypto::anon_creds::ACRevealProof<G2, S>;
 
+pub type ACBlindIssuanceKey = crypto::anon_creds::ACBlindIssuanceKey<G1>;
+
+pub type ACBlindIssueRequest = crypto::anon_creds::ACBlindIssueRequest<G1, S>;
+
+pub type ACBlindingKey = crypto::anon_creds::ACBlindingKey<S>;
+
+pub type ACBlindSignature = crypto::anon_creds::ACBlindSignature<G1>;
+
 pub type ACConfidentialRevealProof = crypto::conf_cred_reveal::CACPoK<G1, G2, S>;
 
+pub type ACRevocationPublicKey = crypto::cred_revocation::ACRevocationPublicKey<G2>;
+
+pub type ACRevocationSecretKey = crypto::cred_revocation::ACRevocationSecretKey<S>;
+
+pub type ACAccumulator = crypto::cred_revocation::ACAccumulator<G1>;
+
+pub type ACRevocationWitness = crypto::cred_revocation::ACRevocationWitness<G1, S>;
+
+pub type ACNonRevokedRevealSig =
+    crypto::cred_revocation::ACNonRevokedRevealSig<G1, G2, S>;
+
 pub type Attr = u32;
 
 /// Generates e key pair for a credential issuer
@@ -100,6 +119,105 @@ pub fn ac_sign<R: CryptoRng + RngCore>(
     .c(d!())
 }
 
+/// Computes the elements that a credential issuer publishes for blind issuance
+pub fn ac_blind_issuance_key(issuer_sk: &ACIssuerSecretKey) -> ACBlindIssuanceKey {
+    crypto::anon_creds::ac_blind_issuance_key::<Bls12381>(issuer_sk)
+}
+
+/// Computes a request for the blind signature of a set of attributes. The attributes at the
+/// positions where reveal_bitmap is true are known to the issuer, the other ones are hidden.
+/// # Example
+/// ```
+/// use rand_core::SeedableRng;
+/// use rand_chacha::ChaChaRng;
+/// use zei::api::anon_creds::{ac_keygen_issuer, ac_keygen_user, ac_blind_issuance_key};
+/// use zei::api::anon_creds::{ac_blind_issue_request, ac_issuer_blind_sign, ac_unblind_credential};
+/// let mut prng = ChaChaRng::from_seed([0u8;32]);
+/// let num_attrs = 2;
+/// let (issuer_pk, issuer_sk) = ac_keygen_issuer::<ChaChaRng>(&mut prng, num_attrs);
+/// let blind_key = ac_blind_issuance_key(&issuer_sk);
+/// let (user_pk, user_sk) = ac_keygen_user::<ChaChaRng>(&mut prng, &issuer_pk);
+/// let attributes = vec![10u32, 20];
+/// let bitmap = [true, false]; // The issuer knows the first attribute only
+/// let (request, blinding_key) = ac_blind_issue_request::<ChaChaRng>(&mut prng, &issuer_pk, &blind_key, &user_sk, &attributes, &bitmap).unwrap();
+/// let blind_sig = ac_issuer_blind_sign::<ChaChaRng>(&mut prng, &issuer_sk, &issuer_pk, &user_pk, &[Some(10), None], &request).unwrap();
+/// let credential = ac_unblind_credential(&issuer_pk, &user_sk, &blind_sig, &blinding_key, attributes).unwrap();
+/// ```
+pub fn ac_blind_issue_request<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    issuer_pk: &ACIssuerPublicKey,
+    blind_key: &ACBlindIssuanceKey,
+    user_sk: &ACUserSecretKey,
+    attrs: &[Attr],
+    reveal_bitmap: &[bool],
+) -> Result<(ACBlindIssueRequest, ACBlindingKey)> {
+    let attrs_scalar: Vec<BLSScalar> =
+        attrs.iter().map(|x| BLSScalar::from_u32(*x)).collect();
+    crypto::anon_creds::ac_blind_issue_request::<_, Bls12381>(
+        prng,
+        issuer_pk,
+        blind_key,
+        user_sk,
+        attrs_scalar.as_slice(),
+        reveal_bitmap,
+    )
+    .c(d!())
+}
+
+/// Verifies a blind issuance request and signs its attributes, where Some(attr) is an
+/// attribute known to the issuer and None an attribute hidden in the request.
+pub fn ac_issuer_blind_sign<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    issuer_sk: &ACIssuerSecretKey,
+    issuer_pk: &ACIssuerPublicKey,
+    user_pk: &ACUserPublicKey,
+    attrs: &[Option<Attr>],
+    request: &ACBlindIssueRequest,
+) -> Result<ACBlindSignature> {
+    let attrs_scalar: Vec<Attribute<S>> = attrs
+        .iter()
+        .map(|attr| match attr {
+            Some(x) => Attribute::Revealed(BLSScalar::from_u32(*x)),
+            None => Attribute::Hidden(None),
+        })
+        .collect();
+    crypto::anon_creds::ac_issuer_blind_sign::<_, Bls12381>(
+        prng,
+        issuer_sk,
+        issuer_pk,
+        user_pk,
+        attrs_scalar.as_slice(),
+        request,
+    )
+    .c(d!())
+}
+
+/// Unblinds the signature of a blind issuance request and returns the credential for the
+/// attributes. Fails if the signature is not valid for the attributes.
+pub fn ac_unblind_credential(
+    issuer_pk: &ACIssuerPublicKey,
+    user_sk: &ACUserSecretKey,
+    blind_sig: &ACBlindSignature,
+    blinding_key: &ACBlindingKey,
+    attrs: Vec<Attr>,
+) -> Result<Credential> {
+    let attrs_scalar: Vec<BLSScalar> =
+        attrs.iter().map(|x| BLSScalar::from_u32(*x)).collect();
+    let credential = crypto::anon_creds::ac_unblind_credential::<Bls12381>(
+        issuer_pk,
+        user_sk,
+        blind_sig,
+        blinding_key,
+        attrs_scalar,
+    )
+    .c(d!())?;
+    Ok(Credential {
+        signature: credential.signature,
+        attributes: attrs,
+        issuer_pub_key: credential.issuer_pub_key,
+    })
+}
+
 /// Produces opening key for credential commitment creation and attribute opening
 /// # Example
 /// ```
@@ -330,6 +448,162 @@ pub fn ac_verify(
     .c(d!())
 }
 
+/// Generates the keys and the initial accumulator of a credential revocation authority
+pub fn ac_revocation_keygen<R: CryptoRng + RngCore>(
+    prng: &mut R,
+) -> (ACRevocationPublicKey, ACRevocationSecretKey, ACAccumulator) {
+    crypto::cred_revocation::ac_revocation_keygen::<_, Bls12381>(prng)
+}
+
+/// Computes the witness that the revocation handle `handle` is accumulated in `accumulator`
+pub fn ac_revocation_witness(
+    revocation_sk: &ACRevocationSecretKey,
+    accumulator: &ACAccumulator,
+    handle: Attr,
+) -> Result<ACRevocationWitness> {
+    crypto::cred_revocation::ac_revocation_witness::<Bls12381>(
+        revocation_sk,
+        accumulator,
+        &BLSScalar::from_u32(handle),
+    )
+    .c(d!())
+}
+
+/// Revokes the credentials with revocation handle `handle` and returns the new accumulator
+pub fn ac_revoke(
+    revocation_sk: &ACRevocationSecretKey,
+    accumulator: &ACAccumulator,
+    handle: Attr,
+) -> Result<ACAccumulator> {
+    crypto::cred_revocation::ac_revoke::<Bls12381>(
+        revocation_sk,
+        accumulator,
+        &BLSScalar::from_u32(handle),
+    )
+    .c(d!())
+}
+
+/// Updates a witness after the revocation of `revoked_handle`. Fails with
+/// ZeiError::CredentialRevokedError if the witness handle is the revoked one.
+pub fn ac_update_revocation_witness(
+    witness: &mut ACRevocationWitness,
+    revoked_handle: Attr,
+    new_accumulator: &ACAccumulator,
+) -> Result<()> {
+    crypto::cred_revocation::ac_update_revocation_witness::<Bls12381>(
+        witness,
+        &BLSScalar::from_u32(revoked_handle),
+        new_accumulator,
+    )
+    .c(d!())
+}
+
+/// Verifies that a witness is valid for the accumulator
+pub fn ac_verify_revocation_witness(
+    revocation_pk: &ACRevocationPublicKey,
+    accumulator: &ACAccumulator,
+    witness: &ACRevocationWitness,
+) -> Result<()> {
+    crypto::cred_revocation::ac_verify_revocation_witness::<Bls12381>(
+        revocation_pk,
+        accumulator,
+        witness,
+    )
+    .c(d!())
+}
+
+/// Produces a ACRevealSig for a credential along with the proof that the credential is not
+/// revoked. The handle_index-th attribute of the credential is its revocation handle, it must
+/// be hidden in `reveal_bitmap`.
+/// # Example
+/// ```
+/// use rand_core::SeedableRng;
+/// use rand_chacha::ChaChaRng;
+/// use zei::api::anon_creds::{ac_keygen_issuer, ac_keygen_user, ac_sign, Credential};
+/// use zei::api::anon_creds::{ac_revocation_keygen, ac_revocation_witness, ac_revoke};
+/// use zei::api::anon_creds::{ac_reveal_non_revoked, ac_verify_non_revoked};
+/// let mut prng = ChaChaRng::from_seed([0u8;32]);
+/// let (issuer_pk, issuer_sk) = ac_keygen_issuer::<ChaChaRng>(&mut prng, 2);
+/// let (user_pk, user_sk) = ac_keygen_user::<ChaChaRng>(&mut prng, &issuer_pk);
+/// let (revocation_pk, revocation_sk, accumulator) = ac_revocation_keygen::<ChaChaRng>(&mut prng);
+/// let attributes = vec![10u32, 1234]; // the second attribute is the revocation handle
+/// let signature = ac_sign::<ChaChaRng>(&mut prng, &issuer_sk, &user_pk, &attributes[..]).unwrap();
+/// let credential = Credential{
+///   signature,
+///   attributes,
+///   issuer_pub_key: issuer_pk.clone(),
+/// };
+/// let witness = ac_revocation_witness(&revocation_sk, &accumulator, 1234).unwrap();
+/// let reveal_sig = ac_reveal_non_revoked::<ChaChaRng>(&mut prng, &user_sk, &credential, &[true, false], 1, &revocation_pk, &accumulator, &witness).unwrap();
+/// let attr_map = [Some(10u32), None];
+/// assert!(ac_verify_non_revoked(&issuer_pk, &attr_map, 1, &revocation_pk, &accumulator, &reveal_sig).is_ok());
+/// let accumulator = ac_revoke(&revocation_sk, &accumulator, 1234).unwrap();
+/// assert!(ac_verify_non_revoked(&issuer_pk, &attr_map, 1, &revocation_pk, &accumulator, &reveal_sig).is_err());
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn ac_reveal_non_revoked<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    user_sk: &ACUserSecretKey,
+    credential: &Credential,
+    reveal_bitmap: &[bool],
+    handle_index: usize,
+    revocation_pk: &ACRevocationPublicKey,
+    accumulator: &ACAccumulator,
+    witness: &ACRevocationWitness,
+) -> Result<ACNonRevokedRevealSig> {
+    let c = crypto::anon_creds::Credential {
+        signature: credential.signature.clone(),
+        attributes: credential
+            .attributes
+            .iter()
+            .map(|a| BLSScalar::from_u32(*a))
+            .collect_vec(),
+        issuer_pub_key: credential.issuer_pub_key.clone(),
+    };
+    crypto::cred_revocation::ac_reveal_non_revoked::<_, Bls12381>(
+        prng,
+        user_sk,
+        &c,
+        reveal_bitmap,
+        handle_index,
+        revocation_pk,
+        accumulator,
+        witness,
+    )
+    .c(d!())
+}
+
+/// Verifies an anonymous credential reveal proof and its proof that the credential is not
+/// revoked, the handle_index-th attribute being the hidden revocation handle. See ac_verify for
+/// `attrs`.
+/// # Example
+/// see zei::api::anon_creds::ac_reveal_non_revoked
+pub fn ac_verify_non_revoked(
+    issuer_pub_key: &ACIssuerPublicKey,
+    attrs: &[Option<Attr>],
+    handle_index: usize,
+    revocation_pk: &ACRevocationPublicKey,
+    accumulator: &ACAccumulator,
+    reveal_sig: &ACNonRevokedRevealSig,
+) -> Result<()> {
+    let attrs_scalar: Vec<Attribute<S>> = attrs
+        .iter()
+        .map(|attr| match attr {
+            Some(x) => Attribute::Revealed(BLSScalar::from_u32(*x)),
+            None => Attribute::Hidden(None),
+        })
+        .collect();
+    crypto::cred_revocation::ac_verify_non_revoked::<Bls12381>(
+        issuer_pub_key,
+        attrs_scalar.as_slice(),
+        handle_index,
+        revocation_pk,
+        accumulator,
+        reveal_sig,
+    )
+    .c(d!())
+}
+
 pub type AttributeEncKey = crypto::basics::elgamal::ElGamalEncKey<G1>;
 pub type AttributeDecKey = crypto::basics::elgamal::ElGamalDecKey<S>;
 pub type AttributeCiphertext = crypto::basics::elgamal::ElGamalCiphertext<G1>;
@@ -14,6 +14,7 @@ use ruc::*;
 use std::collections::HashSet;
 use std::fmt;
 use utils::errors::ZeiError;
+use utils::serialization::{Validate, ZeiFromToBytes};
 
 /// record for solvency proof, indicates asset or liability
 pub enum SolvencyRecordType {
@@ -96,6 +97,8 @@ impl SolvencyAudit {
         if not_matches!(self.stage, SolvencyAuditStage::RecordCollection) {
             return Err(eg!(ZeiError::SolvencyInputError));
         }
+        // the commitments of the records are decompressed when building the verifier
+        record.validate().c(d!(ZeiError::SolvencyInputError))?;
         match record_type {
             SolvencyRecordType::Asset => self.assets.push(record.clone()),
             SolvencyRecordType::Liability => self.liabilities.push(record.clone()),
@@ -300,6 +303,7 @@ impl SolvencyAudit {
 
     fn get_hidden_record_commitments(record: &BlindAssetRecord) -> CloakCommitment {
         let pc_gens = RistrettoPedersenGens::default();
+        // safe unwraps, the commitments are checked in add_record
         let amount_com = if record.amount.is_confidential() {
             let (amount_com_lo, amount_com_hi) =
                 record.amount.get_commitments().unwrap();
@@ -396,6 +400,7 @@ impl SolvencyVerifier {
         if self.conv_rates.is_empty() {
             return Err(eg!(ZeiError::SolvencyVerificationError));
         }
+        let proof = R1CSProof::zei_from_bytes(proof).c(d!())?;
 
         solvency::verify_solvency(
             &bp_gens,
@@ -405,7 +410,7 @@ impl SolvencyVerifier {
             &self.hidden_liabilities_commitments,
             &self.public_liabilities,
             &self.conv_rates,
-            &R1CSProof::from_bytes(proof).unwrap(),
+            &proof,
         )
     }
 }
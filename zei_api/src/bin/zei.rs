@@ -0,0 +1,606 @@
+#![allow(clippy::upper_case_acronyms)]
+
+// Command-line tool to prototype and debug with Zei without writing Rust.
+// Keys, specs, notes, witnesses and public inputs are JSON files; SRS, constraint systems,
+// preprocessed parameters and proofs are bincode files, as produced by `gen-params`.
+// Constraint systems are bincode serializations of a `TurboPlonkCS` circuit.
+
+use algebra::bls12_381::{BLSScalar, BLSG1, BLSG2};
+use algebra::groups::{Group, Scalar};
+use merlin::Transcript;
+use poly_iops::commitments::kzg_poly_com::KZGCommitmentSchemeBLS;
+use poly_iops::plonk::plonk_setup::{preprocess_prover, ProverParams, VerifierParams};
+use poly_iops::plonk::protocol::prover::{prover, verifier, PlonkPf};
+use poly_iops::plonk::turbo_plonk_cs::TurboPlonkConstraintSystem;
+use rand_chacha::ChaChaRng;
+use rand_core::SeedableRng;
+use ruc::*;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_derive::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use utils::errors::ZeiError;
+use zei::anon_xfr::keys::AXfrKeyPair;
+use zei::setup::PublicParams;
+use zei::xfr::asset_record::{open_blind_asset_record, AssetRecordType};
+use zei::xfr::lib::{gen_xfr_note, verify_xfr_note, XfrNotePolicies};
+use zei::xfr::sig::{XfrKeyPair, XfrPublicKey};
+use zei::xfr::structs::{
+    AssetRecord, AssetRecordTemplate, AssetType, BlindAssetRecord, OwnerMemo, XfrNote,
+};
+
+const PLONK_TRANSCRIPT_LABEL: &[u8] = b"Zei CLI PLONK proof";
+const COMMON_SEED: [u8; 32] = [0u8; 32];
+
+type TurboPlonkCS = TurboPlonkConstraintSystem<BLSScalar>;
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    about = "Zei tool to prototype and debug keys, circuits, proofs and transfers.",
+    rename_all = "kebab-case"
+)]
+enum Actions {
+    /// Generate a transfer key pair, or an anonymous transfer key pair with --anon
+    KeyGen {
+        #[structopt(long)]
+        anon: bool,
+        /// Derive the key pair deterministically from this seed (for tests only)
+        #[structopt(long)]
+        seed: Option<String>,
+        out_filename: Option<PathBuf>,
+    },
+
+    /// Import a KZG SRS over BLS12-381 from a raw file: `num_g1_powers` compressed G1
+    /// points (48 bytes each) followed by compressed G2 points (96 bytes each)
+    SrsImport {
+        in_filename: PathBuf,
+        num_g1_powers: usize,
+        out_filename: PathBuf,
+    },
+
+    /// Trim a KZG SRS to polynomials of degree at most `max_degree`
+    SrsTrim {
+        in_filename: PathBuf,
+        max_degree: usize,
+        out_filename: PathBuf,
+    },
+
+    /// Preprocess a constraint system into prover and verifier parameters
+    Preprocess {
+        cs_filename: PathBuf,
+        srs_filename: PathBuf,
+        prover_params_out_filename: PathBuf,
+        verifier_params_out_filename: PathBuf,
+    },
+
+    /// Prove that a witness (JSON array) satisfies a constraint system
+    Prove {
+        cs_filename: PathBuf,
+        srs_filename: PathBuf,
+        prover_params_filename: PathBuf,
+        witness_filename: PathBuf,
+        out_filename: PathBuf,
+    },
+
+    /// Verify a proof against public inputs (JSON array)
+    Verify {
+        cs_filename: PathBuf,
+        srs_filename: PathBuf,
+        verifier_params_filename: PathBuf,
+        public_inputs_filename: PathBuf,
+        proof_filename: PathBuf,
+    },
+
+    /// Build and verify a transfer note from a JSON spec:
+    /// {"inputs": [input], "outputs": [record]}, where an input is either
+    /// {"keypair", "record", "owner_memo"} for an existing record or {"keypair", "amount",
+    /// "asset_type", "record_type"} for a fresh one, and a record is {"amount", "asset_type",
+    /// "record_type", "public_key"}
+    XfrNote {
+        spec_filename: PathBuf,
+        out_filename: Option<PathBuf>,
+    },
+
+    /// Decrypt the owner memo of the `output_index`-th output of a transfer note
+    DecryptMemo {
+        keypair_filename: PathBuf,
+        note_filename: PathBuf,
+        output_index: usize,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum XfrInputSpec {
+    Record {
+        keypair: XfrKeyPair,
+        record: BlindAssetRecord,
+        owner_memo: Option<OwnerMemo>,
+    },
+    Fresh {
+        keypair: XfrKeyPair,
+        amount: u64,
+        asset_type: AssetType,
+        record_type: AssetRecordType,
+    },
+}
+
+#[derive(Deserialize)]
+struct XfrOutputSpec {
+    amount: u64,
+    asset_type: AssetType,
+    record_type: AssetRecordType,
+    public_key: XfrPublicKey,
+}
+
+#[derive(Deserialize)]
+struct XfrNoteSpec {
+    inputs: Vec<XfrInputSpec>,
+    outputs: Vec<XfrOutputSpec>,
+}
+
+fn main() {
+    let action = Actions::from_args();
+    if let Err(e) = run(action) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(action: Actions) -> Result<()> {
+    use Actions::*;
+    match action {
+        KeyGen {
+            anon,
+            seed,
+            out_filename,
+        } => key_gen(anon, seed, out_filename),
+        SrsImport {
+            in_filename,
+            num_g1_powers,
+            out_filename,
+        } => srs_import(in_filename, num_g1_powers, out_filename),
+        SrsTrim {
+            in_filename,
+            max_degree,
+            out_filename,
+        } => srs_trim(in_filename, max_degree, out_filename),
+        Preprocess {
+            cs_filename,
+            srs_filename,
+            prover_params_out_filename,
+            verifier_params_out_filename,
+        } => preprocess(
+            cs_filename,
+            srs_filename,
+            prover_params_out_filename,
+            verifier_params_out_filename,
+        ),
+        Prove {
+            cs_filename,
+            srs_filename,
+            prover_params_filename,
+            witness_filename,
+            out_filename,
+        } => prove(
+            cs_filename,
+            srs_filename,
+            prover_params_filename,
+            witness_filename,
+            out_filename,
+        ),
+        Verify {
+            cs_filename,
+            srs_filename,
+            verifier_params_filename,
+            public_inputs_filename,
+            proof_filename,
+        } => verify(
+            cs_filename,
+            srs_filename,
+            verifier_params_filename,
+            public_inputs_filename,
+            proof_filename,
+        ),
+        XfrNote {
+            spec_filename,
+            out_filename,
+        } => xfr_note(spec_filename, out_filename),
+        DecryptMemo {
+            keypair_filename,
+            note_filename,
+            output_index,
+        } => decrypt_memo(keypair_filename, note_filename, output_index),
+    }
+}
+
+fn read_bincode<T: DeserializeOwned>(filename: &Path) -> Result<T> {
+    let contents = fs::read(filename).c(d!(ZeiError::ParameterError))?;
+    bincode::deserialize(&contents).c(d!(ZeiError::DeserializationError))
+}
+
+fn write_bincode<T: Serialize>(value: &T, filename: &Path) -> Result<()> {
+    let contents = bincode::serialize(value).c(d!(ZeiError::SerializationError))?;
+    fs::write(filename, contents).c(d!())?;
+    println!("Written in file {}.", filename.display());
+    Ok(())
+}
+
+fn read_json<T: DeserializeOwned>(filename: &Path) -> Result<T> {
+    let contents = fs::read(filename).c(d!(ZeiError::ParameterError))?;
+    serde_json::from_slice(&contents).c(d!(ZeiError::DeserializationError))
+}
+
+// Writes `value` in `filename`, or on the standard output if no file is given
+fn write_json<T: Serialize>(value: &T, filename: Option<PathBuf>) -> Result<()> {
+    let contents =
+        serde_json::to_string_pretty(value).c(d!(ZeiError::SerializationError))?;
+    match filename {
+        Some(filename) => {
+            fs::write(&filename, contents).c(d!())?;
+            println!("Written in file {}.", filename.display());
+        }
+        None => println!("{}", contents),
+    }
+    Ok(())
+}
+
+fn new_prng(seed: Option<String>) -> ChaChaRng {
+    match seed {
+        Some(seed) => {
+            let mut prng_seed = [0u8; 32];
+            prng_seed.copy_from_slice(&Sha256::digest(seed.as_bytes()));
+            ChaChaRng::from_seed(prng_seed)
+        }
+        None => ChaChaRng::from_entropy(),
+    }
+}
+
+// Parses a JSON array of scalars: numbers, or scalars in their serialized (base64) form
+fn scalars_from_json(value: Value) -> Result<Vec<BLSScalar>> {
+    let values = match value {
+        Value::Array(values) => values,
+        _ => return Err(eg!(ZeiError::DeserializationError)),
+    };
+    values
+        .into_iter()
+        .map(|v| match v.as_u64() {
+            Some(n) => Ok(BLSScalar::from_u64(n)),
+            None => serde_json::from_value(v).c(d!(ZeiError::DeserializationError)),
+        })
+        .collect()
+}
+
+fn key_gen(
+    anon: bool,
+    seed: Option<String>,
+    out_filename: Option<PathBuf>,
+) -> Result<()> {
+    let mut prng = new_prng(seed);
+    if anon {
+        write_json(&AXfrKeyPair::generate(&mut prng), out_filename)
+    } else {
+        write_json(&XfrKeyPair::generate(&mut prng), out_filename)
+    }
+}
+
+fn srs_import(
+    in_filename: PathBuf,
+    num_g1_powers: usize,
+    out_filename: PathBuf,
+) -> Result<()> {
+    let contents = fs::read(&in_filename).c(d!(ZeiError::ParameterError))?;
+    let g1_len = num_g1_powers
+        .checked_mul(BLSG1::COMPRESSED_LEN)
+        .c(d!(ZeiError::ParameterError))?;
+    if contents.len() < g1_len || (contents.len() - g1_len) % BLSG2::COMPRESSED_LEN != 0
+    {
+        return Err(eg!(ZeiError::DeserializationError));
+    }
+    let (g1_bytes, g2_bytes) = contents.split_at(g1_len);
+    let g1_powers = g1_bytes
+        .chunks(BLSG1::COMPRESSED_LEN)
+        .map(BLSG1::from_compressed_bytes)
+        .collect::<Result<Vec<_>>>()
+        .c(d!(ZeiError::DeserializationError))?;
+    let g2_powers = g2_bytes
+        .chunks(BLSG2::COMPRESSED_LEN)
+        .map(BLSG2::from_compressed_bytes)
+        .collect::<Result<Vec<_>>>()
+        .c(d!(ZeiError::DeserializationError))?;
+    let srs = KZGCommitmentSchemeBLS::from_powers(g1_powers, g2_powers).c(d!())?;
+    println!("Imported a KZG SRS of max degree {}.", srs.max_degree());
+    write_bincode(&srs, &out_filename)
+}
+
+fn srs_trim(
+    in_filename: PathBuf,
+    max_degree: usize,
+    out_filename: PathBuf,
+) -> Result<()> {
+    let srs: KZGCommitmentSchemeBLS = read_bincode(&in_filename).c(d!())?;
+    let trimmed = srs.truncate(max_degree).c(d!())?;
+    write_bincode(&trimmed, &out_filename)
+}
+
+fn preprocess(
+    cs_filename: PathBuf,
+    srs_filename: PathBuf,
+    prover_params_out_filename: PathBuf,
+    verifier_params_out_filename: PathBuf,
+) -> Result<()> {
+    let cs: TurboPlonkCS = read_bincode(&cs_filename).c(d!())?;
+    let srs: KZGCommitmentSchemeBLS = read_bincode(&srs_filename).c(d!())?;
+    let prover_params = preprocess_prover(&cs, &srs, COMMON_SEED).c(d!())?;
+    write_bincode(&prover_params, &prover_params_out_filename).c(d!())?;
+    write_bincode(
+        prover_params.get_verifier_params_ref(),
+        &verifier_params_out_filename,
+    )
+}
+
+fn prove(
+    cs_filename: PathBuf,
+    srs_filename: PathBuf,
+    prover_params_filename: PathBuf,
+    witness_filename: PathBuf,
+    out_filename: PathBuf,
+) -> Result<()> {
+    let cs: TurboPlonkCS = read_bincode(&cs_filename).c(d!())?;
+    let srs: KZGCommitmentSchemeBLS = read_bincode(&srs_filename).c(d!())?;
+    let prover_params: ProverParams<KZGCommitmentSchemeBLS> =
+        read_bincode(&prover_params_filename).c(d!())?;
+    let witness = scalars_from_json(read_json(&witness_filename).c(d!())?).c(d!())?;
+    let mut prng = ChaChaRng::from_entropy();
+    let mut transcript = Transcript::new(PLONK_TRANSCRIPT_LABEL);
+    let proof = prover(
+        &mut prng,
+        &mut transcript,
+        &srs,
+        &cs,
+        &prover_params,
+        &witness,
+    )
+    .c(d!())?;
+    write_bincode(&proof, &out_filename)
+}
+
+fn verify(
+    cs_filename: PathBuf,
+    srs_filename: PathBuf,
+    verifier_params_filename: PathBuf,
+    public_inputs_filename: PathBuf,
+    proof_filename: PathBuf,
+) -> Result<()> {
+    let cs: TurboPlonkCS = read_bincode(&cs_filename).c(d!())?;
+    let srs: KZGCommitmentSchemeBLS = read_bincode(&srs_filename).c(d!())?;
+    let verifier_params: VerifierParams<KZGCommitmentSchemeBLS> =
+        read_bincode(&verifier_params_filename).c(d!())?;
+    let public_inputs =
+        scalars_from_json(read_json(&public_inputs_filename).c(d!())?).c(d!())?;
+    let proof: PlonkPf<KZGCommitmentSchemeBLS> =
+        read_bincode(&proof_filename).c(d!())?;
+    let mut transcript = Transcript::new(PLONK_TRANSCRIPT_LABEL);
+    verifier(
+        &mut transcript,
+        &srs,
+        &cs,
+        &verifier_params,
+        &public_inputs,
+        &proof,
+    )
+    .c(d!())?;
+    println!("Proof verified.");
+    Ok(())
+}
+
+fn xfr_note(spec_filename: PathBuf, out_filename: Option<PathBuf>) -> Result<()> {
+    let spec: XfrNoteSpec = read_json(&spec_filename).c(d!())?;
+    let mut prng = ChaChaRng::from_entropy();
+    let mut inputs = vec![];
+    let mut keypairs = vec![];
+    for input in spec.inputs {
+        match input {
+            XfrInputSpec::Record {
+                keypair,
+                record,
+                owner_memo,
+            } => {
+                let oar =
+                    open_blind_asset_record(&record, &owner_memo, &keypair).c(d!())?;
+                inputs.push(AssetRecord::from_open_asset_record_no_asset_tracing(oar));
+                keypairs.push(keypair);
+            }
+            XfrInputSpec::Fresh {
+                keypair,
+                amount,
+                asset_type,
+                record_type,
+            } => {
+                let template = AssetRecordTemplate::with_no_asset_tracing(
+                    amount,
+                    asset_type,
+                    record_type,
+                    keypair.pub_key,
+                );
+                inputs.push(
+                    AssetRecord::from_template_no_identity_tracing(&mut prng, &template)
+                        .c(d!())?,
+                );
+                keypairs.push(keypair);
+            }
+        }
+    }
+    let outputs = spec
+        .outputs
+        .iter()
+        .map(|output| {
+            let template = AssetRecordTemplate::with_no_asset_tracing(
+                output.amount,
+                output.asset_type,
+                output.record_type,
+                output.public_key,
+            );
+            AssetRecord::from_template_no_identity_tracing(&mut prng, &template)
+        })
+        .collect::<Result<Vec<_>>>()
+        .c(d!())?;
+    let keypairs_ref: Vec<&XfrKeyPair> = keypairs.iter().collect();
+    let note: XfrNote =
+        gen_xfr_note(&mut prng, &inputs, &outputs, &keypairs_ref).c(d!())?;
+
+    let policies = XfrNotePolicies::empty_policies(inputs.len(), outputs.len());
+    let mut params = PublicParams::default();
+    verify_xfr_note(&mut prng, &mut params, &note, &policies.to_ref()).c(d!())?;
+    write_json(&note, out_filename)
+}
+
+fn decrypt_memo(
+    keypair_filename: PathBuf,
+    note_filename: PathBuf,
+    output_index: usize,
+) -> Result<()> {
+    let keypair: XfrKeyPair = read_json(&keypair_filename).c(d!())?;
+    let note: XfrNote = read_json(&note_filename).c(d!())?;
+    let record = note
+        .body
+        .outputs
+        .get(output_index)
+        .c(d!(ZeiError::IndexError))?;
+    let owner_memo = note
+        .body
+        .owners_memos
+        .get(output_index)
+        .c(d!(ZeiError::IndexError))?;
+    let oar = open_blind_asset_record(record, owner_memo, &keypair).c(d!())?;
+    write_json(&oar, None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::RngCore;
+
+    // a directory of the temporary directory, removed on drop even if the test fails
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> TempDir {
+            let path = std::env::temp_dir().join(format!(
+                "zei_cli_test_{}_{:016x}",
+                std::process::id(),
+                ChaChaRng::from_entropy().next_u64()
+            ));
+            pnk!(fs::create_dir(&path));
+            TempDir(path)
+        }
+
+        fn file(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_key_gen() {
+        let dir = TempDir::new();
+        let seed = Some("zei cli test".to_string());
+        pnk!(key_gen(false, seed.clone(), Some(dir.file("key.json"))));
+        pnk!(key_gen(
+            false,
+            seed.clone(),
+            Some(dir.file("same_key.json"))
+        ));
+        pnk!(key_gen(true, seed, Some(dir.file("anon_key.json"))));
+        let keypair: XfrKeyPair = pnk!(read_json(&dir.file("key.json")));
+        let same_keypair: XfrKeyPair = pnk!(read_json(&dir.file("same_key.json")));
+        assert_eq!(keypair.pub_key, same_keypair.pub_key);
+        let message = b"message";
+        pnk!(keypair.pub_key.verify(message, &same_keypair.sign(message)));
+        let anon_keypair: AXfrKeyPair = pnk!(read_json(&dir.file("anon_key.json")));
+        pnk!(key_gen(true, None, Some(dir.file("other_anon_key.json"))));
+        let other_anon_keypair: AXfrKeyPair =
+            pnk!(read_json(&dir.file("other_anon_key.json")));
+        assert_ne!(anon_keypair, other_anon_keypair);
+        // a keypair file is not an anonymous keypair file
+        assert!(read_json::<AXfrKeyPair>(&dir.file("key.json")).is_err());
+    }
+
+    #[test]
+    fn test_srs_trim() {
+        let dir = TempDir::new();
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let srs = KZGCommitmentSchemeBLS::new(20, &mut prng);
+        pnk!(write_bincode(&srs, &dir.file("srs.bin")));
+        pnk!(srs_trim(
+            dir.file("srs.bin"),
+            10,
+            dir.file("trimmed_srs.bin")
+        ));
+        let trimmed: KZGCommitmentSchemeBLS =
+            pnk!(read_bincode(&dir.file("trimmed_srs.bin")));
+        assert_eq!(trimmed.max_degree(), 10);
+        assert_eq!(trimmed.powers().0, &srs.powers().0[..11]);
+        // the SRS cannot be extended
+        assert!(srs_trim(dir.file("srs.bin"), 21, dir.file("longer_srs.bin")).is_err());
+        assert!(!dir.file("longer_srs.bin").exists());
+    }
+
+    #[test]
+    fn test_prove_verify() {
+        let dir = TempDir::new();
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let srs = KZGCommitmentSchemeBLS::new(100, &mut prng);
+        pnk!(write_bincode(&srs, &dir.file("srs.bin")));
+        // a * b = c, with c public
+        let mut cs = TurboPlonkCS::new();
+        let a = cs.new_variable(BLSScalar::from_u32(3));
+        let b = cs.new_variable(BLSScalar::from_u32(5));
+        let c = cs.mul(a, b);
+        cs.prepare_io_variable(c);
+        cs.pad();
+        let witness = cs.get_and_clear_witness();
+        pnk!(write_bincode(&cs, &dir.file("cs.bin")));
+        let witness_json = pnk!(serde_json::to_vec(&witness));
+        pnk!(fs::write(dir.file("witness.json"), witness_json));
+        pnk!(fs::write(dir.file("public_inputs.json"), b"[15]"));
+        pnk!(fs::write(dir.file("wrong_public_inputs.json"), b"[16]"));
+
+        pnk!(preprocess(
+            dir.file("cs.bin"),
+            dir.file("srs.bin"),
+            dir.file("prover_params.bin"),
+            dir.file("verifier_params.bin"),
+        ));
+        pnk!(prove(
+            dir.file("cs.bin"),
+            dir.file("srs.bin"),
+            dir.file("prover_params.bin"),
+            dir.file("witness.json"),
+            dir.file("proof.bin"),
+        ));
+        pnk!(verify(
+            dir.file("cs.bin"),
+            dir.file("srs.bin"),
+            dir.file("verifier_params.bin"),
+            dir.file("public_inputs.json"),
+            dir.file("proof.bin"),
+        ));
+        assert!(verify(
+            dir.file("cs.bin"),
+            dir.file("srs.bin"),
+            dir.file("verifier_params.bin"),
+            dir.file("wrong_public_inputs.json"),
+            dir.file("proof.bin"),
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,528 @@
+//! # Hierarchical deterministic key derivation
+//!
+//! This file implements BIP32-style derivation of transfer keys from a single seed, so that a
+//! custodian can derive a fresh deposit key per user from one cold master key.
+//! * `XfrKeyPair` (ed25519) keys are derived with SLIP-0010
+//!   (<https://github.com/satoshilabs/slips/blob/master/slip-0010.md>). Ed25519 only supports
+//!   hardened derivation, every index of the path must be hardened.
+//! * `AXfrKeyPair` (Jubjub) keys are derived additively as in BIP32: a hardened child is derived
+//!   from the parent secret key, a non-hardened child from the parent public key only. Hence the
+//!   extended public key of an account derives the deposit public keys of that account without
+//!   access to any secret key.
+//!
+//! ## Path convention
+//! Paths follow `m/account'/change/index`, where `'` marks a hardened index:
+//! * `account`: one account per custodian client (always hardened),
+//! * `change`: `0` for deposit (external) keys, `1` for change (internal) keys,
+//! * `index`: sequential number of the key within the chain.
+//!
+//! For ed25519 keys the path is `m/account'/change'/index'`.
+
+use crate::anon_xfr::keys::{AXfrKeyPair, AXfrPubKey};
+use crate::xfr::sig::{XfrKeyPair, XfrSecretKey};
+use algebra::groups::{Group, GroupArithmetic, Scalar, ScalarArithmetic, Zero};
+use algebra::jubjub::{JubjubPoint, JubjubScalar};
+use hmac::{Hmac, Mac, NewMac};
+use ruc::*;
+use sha2::{Digest, Sha512};
+use std::fmt;
+use utils::errors::ZeiError;
+use utils::serialization::ZeiFromToBytes;
+
+/// Indices from `HARDENED_OFFSET` on denote hardened derivation
+pub const HARDENED_OFFSET: u32 = 0x8000_0000;
+/// Change value of deposit (external) keys
+pub const EXTERNAL_CHAIN: u32 = 0;
+/// Change value of change (internal) keys
+pub const INTERNAL_CHAIN: u32 = 1;
+
+const ED25519_SEED_KEY: &[u8] = b"ed25519 seed";
+const JUBJUB_SEED_KEY: &[u8] = b"Zei Jubjub seed";
+const JUBJUB_TWEAK_DOMAIN: &[u8] = b"Zei Jubjub HD tweak";
+const MIN_SEED_LENGTH: usize = 16;
+const MAX_SEED_LENGTH: usize = 64;
+
+/// Returns the hardened version of `index`
+pub fn hardened(index: u32) -> Result<u32> {
+    if index >= HARDENED_OFFSET {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    Ok(index | HARDENED_OFFSET)
+}
+
+/// Returns true if `index` denotes a hardened derivation
+pub fn is_hardened(index: u32) -> bool {
+    index >= HARDENED_OFFSET
+}
+
+/// Derivation path: the list of child indices from the master key
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Path `m/account'/change/index` of the Jubjub keys
+    pub fn new(account: u32, change: u32, index: u32) -> Result<DerivationPath> {
+        if change >= HARDENED_OFFSET || index >= HARDENED_OFFSET {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(DerivationPath(vec![
+            hardened(account).c(d!())?,
+            change,
+            index,
+        ]))
+    }
+
+    /// Path `m/account'/change'/index'` of the ed25519 keys
+    pub fn new_hardened(
+        account: u32,
+        change: u32,
+        index: u32,
+    ) -> Result<DerivationPath> {
+        Ok(DerivationPath(vec![
+            hardened(account).c(d!())?,
+            hardened(change).c(d!())?,
+            hardened(index).c(d!())?,
+        ]))
+    }
+
+    /// Path from a list of indices, hardened indices include `HARDENED_OFFSET`
+    pub fn from_indices(indices: Vec<u32>) -> DerivationPath {
+        DerivationPath(indices)
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// Returns true if every index of the path is hardened
+    pub fn is_hardened(&self) -> bool {
+        self.0.iter().all(|i| is_hardened(*i))
+    }
+
+    /// Parse a path such as `m/0'/1/5`, `h` and `H` are accepted instead of `'`
+    pub fn parse(path: &str) -> Result<DerivationPath> {
+        let mut components = path.split('/');
+        if components.next() != Some("m") {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let mut indices = vec![];
+        for component in components {
+            let (number, hardened_index) = match component
+                .strip_suffix('\'')
+                .or_else(|| component.strip_suffix('h'))
+                .or_else(|| component.strip_suffix('H'))
+            {
+                Some(number) => (number, true),
+                None => (component, false),
+            };
+            if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(eg!(ZeiError::ParameterError));
+            }
+            let index = number.parse::<u32>().c(d!(ZeiError::ParameterError))?;
+            if index >= HARDENED_OFFSET {
+                return Err(eg!(ZeiError::ParameterError));
+            }
+            indices.push(if hardened_index {
+                index | HARDENED_OFFSET
+            } else {
+                index
+            });
+        }
+        Ok(DerivationPath(indices))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for index in self.0.iter() {
+            if is_hardened(*index) {
+                write!(f, "/{}'", index - HARDENED_OFFSET)?;
+            } else {
+                write!(f, "/{}", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// HMAC-SHA512(key, data) split in the left and right 32 bytes halves
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    // safe unwrap, HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha512>::new_varkey(key).unwrap();
+    for d in data {
+        mac.update(d);
+    }
+    let output = mac.finalize().into_bytes();
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    (left, right)
+}
+
+fn check_seed(seed: &[u8]) -> Result<()> {
+    if seed.len() < MIN_SEED_LENGTH || seed.len() > MAX_SEED_LENGTH {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    Ok(())
+}
+
+fn jubjub_tweak(bytes: &[u8; 32]) -> Result<JubjubScalar> {
+    let mut hasher = Sha512::new();
+    hasher.update(JUBJUB_TWEAK_DOMAIN);
+    hasher.update(bytes);
+    let tweak = JubjubScalar::from_hash(hasher);
+    if tweak.is_zero() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    Ok(tweak)
+}
+
+/// Ed25519 key pair together with its chain code
+#[derive(Clone, Debug)]
+pub struct XfrExtendedKeyPair {
+    key_pair: XfrKeyPair,
+    chain_code: [u8; 32],
+}
+
+impl XfrExtendedKeyPair {
+    /// Master key of `seed`, the seed has between 16 and 64 bytes
+    pub fn from_seed(seed: &[u8]) -> Result<XfrExtendedKeyPair> {
+        check_seed(seed).c(d!())?;
+        let (key, chain_code) = hmac_sha512(ED25519_SEED_KEY, &[seed]);
+        XfrExtendedKeyPair::new(&key, chain_code).c(d!())
+    }
+
+    fn new(key: &[u8; 32], chain_code: [u8; 32]) -> Result<XfrExtendedKeyPair> {
+        let sec_key = XfrSecretKey::zei_from_bytes(key).c(d!())?;
+        Ok(XfrExtendedKeyPair {
+            key_pair: sec_key.into_keypair(),
+            chain_code,
+        })
+    }
+
+    /// Derive the child key of `index`, which must be hardened
+    pub fn derive_child(&self, index: u32) -> Result<XfrExtendedKeyPair> {
+        if !is_hardened(index) {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        let (key, chain_code) = hmac_sha512(
+            &self.chain_code,
+            &[
+                &[0u8],
+                self.key_pair.get_sk_ref().zei_to_bytes().as_slice(),
+                &index.to_be_bytes(),
+            ],
+        );
+        XfrExtendedKeyPair::new(&key, chain_code).c(d!())
+    }
+
+    /// Derive the descendant key of `path`
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<XfrExtendedKeyPair> {
+        let mut key = self.clone();
+        for index in path.indices() {
+            key = key.derive_child(*index).c(d!())?;
+        }
+        Ok(key)
+    }
+
+    pub fn get_key_pair(&self) -> &XfrKeyPair {
+        &self.key_pair
+    }
+
+    pub fn get_chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+}
+
+/// Jubjub key pair together with its chain code
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AXfrExtendedKeyPair {
+    key_pair: AXfrKeyPair,
+    chain_code: [u8; 32],
+}
+
+/// Jubjub public key together with its chain code, it derives non-hardened children only
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AXfrExtendedPubKey {
+    pub_key: AXfrPubKey,
+    chain_code: [u8; 32],
+}
+
+impl AXfrExtendedKeyPair {
+    /// Master key of `seed`, the seed has between 16 and 64 bytes
+    pub fn from_seed(seed: &[u8]) -> Result<AXfrExtendedKeyPair> {
+        check_seed(seed).c(d!())?;
+        let (key, chain_code) = hmac_sha512(JUBJUB_SEED_KEY, &[seed]);
+        let sec_key = jubjub_tweak(&key).c(d!())?;
+        Ok(AXfrExtendedKeyPair {
+            key_pair: AXfrKeyPair::from_secret_scalar(sec_key),
+            chain_code,
+        })
+    }
+
+    /// Derive the child key of `index`.
+    /// A hardened child depends on the secret key, a non-hardened child on the public key only
+    /// and matches the child derived by `AXfrExtendedPubKey::derive_child`.
+    pub fn derive_child(&self, index: u32) -> Result<AXfrExtendedKeyPair> {
+        if !is_hardened(index) {
+            return self.derive_non_hardened_child(index).c(d!());
+        }
+        let sec_key = self.key_pair.get_secret_scalar();
+        let (tweak, chain_code) = hmac_sha512(
+            &self.chain_code,
+            &[&[0u8], sec_key.to_bytes().as_slice(), &index.to_be_bytes()],
+        );
+        let tweak = jubjub_tweak(&tweak).c(d!())?;
+        let child_key = sec_key.add(&tweak);
+        if child_key.is_zero() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(AXfrExtendedKeyPair {
+            key_pair: AXfrKeyPair::from_secret_scalar(child_key),
+            chain_code,
+        })
+    }
+
+    fn derive_non_hardened_child(&self, index: u32) -> Result<AXfrExtendedKeyPair> {
+        let (tweak, chain_code) =
+            non_hardened_tweak(&self.key_pair.pub_key(), &self.chain_code, index)
+                .c(d!())?;
+        let child_key = self.key_pair.get_secret_scalar().add(&tweak);
+        if child_key.is_zero() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(AXfrExtendedKeyPair {
+            key_pair: AXfrKeyPair::from_secret_scalar(child_key),
+            chain_code,
+        })
+    }
+
+    /// Derive the descendant key of `path`
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<AXfrExtendedKeyPair> {
+        let mut key = self.clone();
+        for index in path.indices() {
+            key = key.derive_child(*index).c(d!())?;
+        }
+        Ok(key)
+    }
+
+    /// Extended public key, to be handed out to derive public keys without the secret key
+    pub fn extended_pub_key(&self) -> AXfrExtendedPubKey {
+        AXfrExtendedPubKey {
+            pub_key: self.key_pair.pub_key(),
+            chain_code: self.chain_code,
+        }
+    }
+
+    pub fn get_key_pair(&self) -> &AXfrKeyPair {
+        &self.key_pair
+    }
+
+    pub fn get_chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+}
+
+// Returns the tweak added to the parent key and the child chain code of the non-hardened
+// child `index`
+fn non_hardened_tweak(
+    pub_key: &AXfrPubKey,
+    chain_code: &[u8; 32],
+    index: u32,
+) -> Result<(JubjubScalar, [u8; 32])> {
+    if is_hardened(index) {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    let (tweak, child_chain_code) = hmac_sha512(
+        chain_code,
+        &[
+            pub_key.as_jubjub_point().to_compressed_bytes().as_slice(),
+            &index.to_be_bytes(),
+        ],
+    );
+    Ok((jubjub_tweak(&tweak).c(d!())?, child_chain_code))
+}
+
+impl AXfrExtendedPubKey {
+    /// Derive the child public key of `index`, which must not be hardened
+    pub fn derive_child(&self, index: u32) -> Result<AXfrExtendedPubKey> {
+        let (tweak, chain_code) =
+            non_hardened_tweak(&self.pub_key, &self.chain_code, index).c(d!())?;
+        let point = self
+            .pub_key
+            .as_jubjub_point()
+            .add(&JubjubPoint::get_base().mul(&tweak));
+        if point == JubjubPoint::get_identity() {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+        Ok(AXfrExtendedPubKey {
+            pub_key: AXfrPubKey::from_jubjub_point(point),
+            chain_code,
+        })
+    }
+
+    /// Derive the descendant public key of `path`, which has no hardened index
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<AXfrExtendedPubKey> {
+        let mut key = self.clone();
+        for index in path.indices() {
+            key = key.derive_child(*index).c(d!())?;
+        }
+        Ok(key)
+    }
+
+    pub fn get_pub_key(&self) -> &AXfrPubKey {
+        &self.pub_key
+    }
+
+    pub fn get_chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        hardened, AXfrExtendedKeyPair, DerivationPath, XfrExtendedKeyPair,
+        EXTERNAL_CHAIN, HARDENED_OFFSET, INTERNAL_CHAIN,
+    };
+    use ruc::*;
+    use utils::serialization::ZeiFromToBytes;
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn derivation_path() {
+        let path = pnk!(DerivationPath::parse("m/0'/1/5"));
+        assert_eq!(path.indices(), &[HARDENED_OFFSET, 1, 5]);
+        assert_eq!(path.to_string(), "m/0'/1/5");
+        assert_eq!(path, pnk!(DerivationPath::new(0, INTERNAL_CHAIN, 5)));
+        assert_eq!(
+            pnk!(DerivationPath::parse("m/0h/1H/5")).to_string(),
+            "m/0'/1'/5"
+        );
+        assert_eq!(
+            pnk!(DerivationPath::parse("m/3'/0'/7'")),
+            pnk!(DerivationPath::new_hardened(3, EXTERNAL_CHAIN, 7))
+        );
+        assert!(pnk!(DerivationPath::new_hardened(3, 0, 7)).is_hardened());
+        assert!(!path.is_hardened());
+        assert_eq!(pnk!(DerivationPath::parse("m")).indices().len(), 0);
+
+        for bad in &[
+            "",
+            "0/1",
+            "m/",
+            "m//1",
+            "m/a",
+            "m/-1",
+            "m/+1",
+            "m/1''",
+            "m/2147483648",
+        ] {
+            assert!(DerivationPath::parse(bad).is_err());
+        }
+        assert!(DerivationPath::new(HARDENED_OFFSET, 0, 0).is_err());
+        assert!(DerivationPath::new(0, 0, HARDENED_OFFSET).is_err());
+        assert!(hardened(HARDENED_OFFSET).is_err());
+    }
+
+    #[test]
+    fn ed25519_slip10_test_vector() {
+        let seed = from_hex("000102030405060708090a0b0c0d0e0f");
+        let master = pnk!(XfrExtendedKeyPair::from_seed(&seed));
+        assert_eq!(
+            master.get_key_pair().get_sk_ref().zei_to_bytes(),
+            from_hex("2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7")
+        );
+        assert_eq!(
+            master.get_chain_code().to_vec(),
+            from_hex("90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb")
+        );
+        assert_eq!(
+            master.get_key_pair().get_pk_ref().zei_to_bytes(),
+            from_hex("a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed")
+        );
+
+        let key = pnk!(master.derive_path(&pnk!(DerivationPath::parse("m/0'/1'"))));
+        assert_eq!(
+            key.get_key_pair().get_sk_ref().zei_to_bytes(),
+            from_hex("b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2")
+        );
+        assert_eq!(
+            key.get_chain_code().to_vec(),
+            from_hex("a320425f77d1b5c2505a6b1b27382b37368ee640e3557c315416801243552f14")
+        );
+        assert_eq!(
+            key.get_key_pair().get_pk_ref().zei_to_bytes(),
+            from_hex("1932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187")
+        );
+
+        let path = pnk!(DerivationPath::parse("m/0'/1'/2'/2'/1000000000'"));
+        let key = pnk!(master.derive_path(&path));
+        assert_eq!(
+            key.get_key_pair().get_sk_ref().zei_to_bytes(),
+            from_hex("8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793")
+        );
+        assert_eq!(
+            key.get_key_pair().get_pk_ref().zei_to_bytes(),
+            from_hex("3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a")
+        );
+
+        // ed25519 has no non-hardened derivation
+        assert!(master.derive_child(0).is_err());
+        assert!(master
+            .derive_path(&pnk!(DerivationPath::new(0, EXTERNAL_CHAIN, 0)))
+            .is_err());
+        assert!(XfrExtendedKeyPair::from_seed(&[0u8; 15]).is_err());
+        assert!(XfrExtendedKeyPair::from_seed(&[0u8; 65]).is_err());
+    }
+
+    #[test]
+    fn jubjub_derivation() {
+        let master = pnk!(AXfrExtendedKeyPair::from_seed(&[7u8; 32]));
+        assert_eq!(master, pnk!(AXfrExtendedKeyPair::from_seed(&[7u8; 32])));
+        assert_ne!(master, pnk!(AXfrExtendedKeyPair::from_seed(&[8u8; 32])));
+
+        // the custodian hands out the account extended public key and derives the deposit
+        // public keys from it, the deposit secret keys are derived from the cold master key
+        let account = pnk!(master.derive_child(pnk!(hardened(0))));
+        let account_xpub = account.extended_pub_key();
+        let mut deposit_keys = vec![];
+        for index in 0..3 {
+            let path = pnk!(DerivationPath::new(0, EXTERNAL_CHAIN, index));
+            let key = pnk!(master.derive_path(&path));
+            let pub_key =
+                pnk!(account_xpub.derive_path(&DerivationPath::from_indices(vec![
+                    EXTERNAL_CHAIN,
+                    index
+                ])));
+            assert_eq!(&key.get_key_pair().pub_key(), pub_key.get_pub_key());
+            assert_eq!(key.get_chain_code(), pub_key.get_chain_code());
+
+            let sig = key.get_key_pair().sign(b"deposit");
+            pnk!(pub_key.get_pub_key().verify(b"deposit", &sig));
+            deposit_keys.push(key);
+        }
+        assert_ne!(deposit_keys[0], deposit_keys[1]);
+        assert_ne!(deposit_keys[1], deposit_keys[2]);
+
+        // change keys are on another chain
+        let change_key =
+            pnk!(master.derive_path(&pnk!(DerivationPath::new(0, INTERNAL_CHAIN, 0))));
+        assert_ne!(change_key, deposit_keys[0]);
+
+        // hardened children cannot be derived from the public key
+        assert!(account_xpub.derive_child(pnk!(hardened(1))).is_err());
+        let hardened_child = pnk!(account.derive_child(pnk!(hardened(1))));
+        let normal_child = pnk!(account.derive_child(1));
+        assert_ne!(hardened_child, normal_child);
+        assert!(AXfrExtendedKeyPair::from_seed(&[0u8; 15]).is_err());
+    }
+}
@@ -1,8 +1,9 @@
 //The Public Setup needed for Proofs
 use crate::anon_xfr::circuits::{
-    build_eq_committed_vals_cs, build_multi_xfr_cs, AMultiXfrWitness, TurboPlonkCS,
-    TREE_DEPTH,
+    build_eq_committed_vals_cs, build_multi_xfr_cs, build_multi_xfr_cs_with_fees,
+    AMultiXfrWitness, TurboPlonkCS, TREE_DEPTH,
 };
+use crate::xfr::structs::AssetType;
 use algebra::bls12_381::BLSScalar;
 use algebra::groups::Zero;
 use algebra::jubjub::JubjubPoint;
@@ -52,7 +53,12 @@ pub const DEFAULT_BP_NUM_GENS: usize = 256;
 
 pub const MAX_PARTY_NUMBER: usize = 128;
 
-const COMMON_SEED: [u8; 32] = [0u8; 32];
+/// Maximum number of inputs, and of outputs, of a transfer. The total of this many amounts of
+/// 64 bits is lower than 2^80, so that summing the amounts of a transfer neither overflows
+/// 128-bit integers nor wraps around the scalar fields of the amount proofs.
+pub const MAX_SAFE_XFR_INPUTS: usize = 1 << 16;
+
+pub(crate) const COMMON_SEED: [u8; 32] = [0u8; 32];
 
 fn from_file<T: for<'de> Deserialize<'de>>(filename: &str) -> Result<T> {
     let contents = fs::read(filename).c(d!(ZeiError::ParameterError))?;
@@ -148,6 +154,36 @@ impl UserParams {
         }
     }
 
+    /// Generates the parameters of the anonymous transfers that pay a nonzero public fee of type
+    /// `fee_type`, see gen_anon_xfr_body_with_fee. The constraint system depends on `fee_type`
+    /// but not on the fee.
+    pub fn new_with_fee(
+        n_payers: usize,
+        n_payees: usize,
+        tree_depth: Option<usize>,
+        bp_num_gens: usize,
+        fee_type: &AssetType,
+    ) -> UserParams {
+        let (cs, n_constraints) = build_multi_xfr_cs_with_fees(
+            AMultiXfrWitness::fake(n_payers, n_payees, tree_depth.unwrap_or(TREE_DEPTH)),
+            fee_type.as_scalar(),
+            0,
+        );
+
+        let pcs = KZGCommitmentScheme::new(
+            n_constraints + 2,
+            &mut ChaChaRng::from_seed([0u8; 32]),
+        );
+
+        let prover_params = preprocess_prover(&cs, &pcs, COMMON_SEED).unwrap();
+        UserParams {
+            bp_params: PublicParams::new(bp_num_gens),
+            pcs,
+            cs,
+            prover_params,
+        }
+    }
+
     //This function is the same that new, but max_degree_poly_com allows to set the size of the CRS
     //the parameter max_degree_poly_com is padded to the minimum power of two grater than it.
     pub fn new_max_degree_poly_com(
@@ -0,0 +1,179 @@
+// Confidential asset type codes.
+// An issuer derives the code of a new asset type from its public key and a nonce with
+// AssetType::derive and registers the code. Transfers with a confidential asset type only
+// publish a commitment to the code, and an AssetCodeProof shows that this commitment opens to
+// one of a list of registered codes, e.g. all the codes of the ledger or of a set of issuers,
+// without revealing which one. Hence the asset type, and so the issuer of the asset, is hidden
+// among the codes of the list, while the record is still known to carry a registered asset type.
+
+use crate::xfr::structs::{AssetType, BlindAssetRecord, OpenAssetRecord, XfrAssetType};
+use algebra::ristretto::{RistrettoPoint, RistrettoScalar as Scalar};
+use crypto::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
+use crypto::pc_membership::{
+    pc_membership_prove, pc_membership_verify, PCMembershipProof,
+};
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+const ASSET_CODE_TRANSCRIPT: &[u8] = b"Zei asset type code proof";
+
+/// Proof that the asset type commitment of a record opens to one of a list of registered codes
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetCodeProof(pub(crate) PCMembershipProof<Scalar>);
+
+fn codes_as_scalars(codes: &[AssetType]) -> Vec<Scalar> {
+    codes.iter().map(|code| code.as_scalar()).collect()
+}
+
+/// I compute a proof that the confidential asset type of `record` is one of the `codes`.
+/// Returns an error if the asset type of the record is not confidential or is not in `codes`.
+/// * `prng` - pseudo-random number generator
+/// * `codes` - registered asset type codes
+/// * `record` - opened record whose asset type commitment is proven
+pub fn prove_registered_asset_type<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    codes: &[AssetType],
+    record: &OpenAssetRecord,
+) -> Result<AssetCodeProof> {
+    let commitment = match record.blind_asset_record.asset_type {
+        XfrAssetType::Confidential(commitment) => commitment
+            .decompress()
+            .c(d!(ZeiError::DecompressElementError))?,
+        XfrAssetType::NonConfidential(_) => {
+            return Err(eg!(ZeiError::ParameterError));
+        }
+    };
+    let index = codes
+        .iter()
+        .position(|code| code == &record.asset_type)
+        .c(d!(ZeiError::ParameterError))?;
+    let pc_gens = RistrettoPedersenGens::default();
+    let mut transcript = Transcript::new(ASSET_CODE_TRANSCRIPT);
+    let proof = pc_membership_prove::<_, RistrettoPoint>(
+        &mut transcript,
+        prng,
+        &pc_gens.B,
+        &pc_gens.B_blinding,
+        &commitment,
+        &codes_as_scalars(codes),
+        index,
+        &record.type_blind,
+    )
+    .c(d!())?;
+    Ok(AssetCodeProof(proof))
+}
+
+/// I verify that the asset type of `record` is one of the `codes`. A record with a
+/// non-confidential asset type is checked in the clear.
+/// * `codes` - registered asset type codes
+/// * `record` - record whose asset type is checked
+/// * `proof` - proof for a confidential asset type
+pub fn verify_registered_asset_type(
+    codes: &[AssetType],
+    record: &BlindAssetRecord,
+    proof: &AssetCodeProof,
+) -> Result<()> {
+    let commitment = match record.asset_type {
+        XfrAssetType::Confidential(commitment) => commitment
+            .decompress()
+            .c(d!(ZeiError::DecompressElementError))?,
+        XfrAssetType::NonConfidential(asset_type) => {
+            if codes.contains(&asset_type) {
+                return Ok(());
+            }
+            return Err(eg!(ZeiError::ZKProofVerificationError));
+        }
+    };
+    let pc_gens = RistrettoPedersenGens::default();
+    let mut transcript = Transcript::new(ASSET_CODE_TRANSCRIPT);
+    pc_membership_verify::<RistrettoPoint>(
+        &mut transcript,
+        &pc_gens.B,
+        &pc_gens.B_blinding,
+        &commitment,
+        &codes_as_scalars(codes),
+        &proof.0,
+    )
+    .c(d!(ZeiError::ZKProofVerificationError))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{prove_registered_asset_type, verify_registered_asset_type};
+    use crate::xfr::asset_record::AssetRecordType;
+    use crate::xfr::sig::XfrKeyPair;
+    use crate::xfr::structs::{AssetRecord, AssetRecordTemplate, AssetType};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+
+    #[test]
+    fn test_registered_asset_type() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let issuers: Vec<XfrKeyPair> =
+            (0..3).map(|_| XfrKeyPair::generate(&mut prng)).collect();
+        let codes: Vec<AssetType> = issuers
+            .iter()
+            .enumerate()
+            .map(|(i, issuer)| AssetType::derive(&issuer.pub_key, &[i as u8]))
+            .collect();
+        let owner = XfrKeyPair::generate(&mut prng);
+        let record = |prng: &mut ChaChaRng, asset_type, record_type| {
+            let template = AssetRecordTemplate::with_no_asset_tracing(
+                10,
+                asset_type,
+                record_type,
+                owner.pub_key,
+            );
+            AssetRecord::from_template_no_identity_tracing(prng, &template)
+                .unwrap()
+                .open_asset_record
+        };
+
+        let confidential = AssetRecordType::ConfidentialAmount_ConfidentialAssetType;
+        let oar = record(&mut prng, codes[1], confidential);
+        let proof = pnk!(prove_registered_asset_type(&mut prng, &codes, &oar));
+        pnk!(verify_registered_asset_type(
+            &codes,
+            &oar.blind_asset_record,
+            &proof
+        ));
+        // the proof is bound to the list of codes
+        assert!(verify_registered_asset_type(
+            &codes[1..],
+            &oar.blind_asset_record,
+            &proof
+        )
+        .is_err());
+        // another record of the same asset type has another commitment
+        let other_oar = record(&mut prng, codes[1], confidential);
+        assert!(verify_registered_asset_type(
+            &codes,
+            &other_oar.blind_asset_record,
+            &proof
+        )
+        .is_err());
+
+        // unregistered asset type
+        let unregistered = AssetType::derive(&issuers[0].pub_key, b"unregistered");
+        let oar = record(&mut prng, unregistered, confidential);
+        assert!(prove_registered_asset_type(&mut prng, &codes, &oar).is_err());
+
+        // non-confidential asset types are checked in the clear
+        let public = AssetRecordType::ConfidentialAmount_NonConfidentialAssetType;
+        let oar = record(&mut prng, codes[2], public);
+        assert!(prove_registered_asset_type(&mut prng, &codes, &oar).is_err());
+        pnk!(verify_registered_asset_type(
+            &codes,
+            &oar.blind_asset_record,
+            &proof
+        ));
+        let oar = record(&mut prng, unregistered, public);
+        assert!(
+            verify_registered_asset_type(&codes, &oar.blind_asset_record, &proof)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,367 @@
+// Aggregate flows of an asset type for asset tracers.
+// The confidential amount of a record is committed as a (low, high) pair of Pedersen commitments
+// to its 32-bit halves, and the tracers of the record get ElGamal encryptions of the same halves,
+// with the commitment blindings as encryption randomness (see TracerMemo::new). Both schemes are
+// additively homomorphic: the sum of the commitments (resp. ciphertexts) of several records
+// commits to (resp. encrypts) the sums of their low and high halves.
+// aggregate_asset_flow sums the commitments and the ciphertexts of all the outputs of an asset
+// type in a block, and anyone can recompute the aggregate from the block. The tracer opens the
+// aggregate with a single decryption of each half and publishes the sums with a proof of correct
+// decryption. Hence a regulator monitors the aggregate flow of the asset without any
+// per-transaction decryption, and without learning the amounts of the single transfers.
+// The transfer verifier checks that the tracer ciphertexts of each record encrypt its committed
+// amount, so the opened total is also the total committed on the ledger.
+// Only the outputs with a non-confidential asset type are aggregated, as the asset type of the
+// other outputs is hidden from the aggregator.
+
+use crate::xfr::asset_tracer::{
+    RecordDataCiphertext, RecordDataDecKey, RecordDataEncKey,
+};
+use crate::xfr::structs::{
+    AssetType, BlindAssetRecord, TracerMemo, XfrAmount, XfrAssetType, XfrBody,
+};
+use algebra::groups::{Group, GroupArithmetic, Scalar as _};
+use algebra::ristretto::{
+    CompressedRistretto, RistrettoPoint, RistrettoScalar as Scalar,
+};
+use crypto::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
+use crypto::basics::elgamal::{
+    elgamal_decrypt_bsgs, elgamal_prove_decryption, elgamal_verify_decryption,
+};
+use crypto::sigma::SigmaProof;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use ruc::*;
+use utils::errors::ZeiError;
+
+const ASSET_FLOW_TRANSCRIPT: &[u8] = b"Zei asset flow opening";
+
+/// Aggregate of the outputs of an asset type, for the asset tracer of `tracer_enc_key`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetFlow {
+    pub asset_type: AssetType,
+    pub tracer_enc_key: RecordDataEncKey,
+    /// number of aggregated outputs
+    pub n_outputs: u64,
+    /// sum of the non-confidential amounts
+    #[serde(with = "serde_str")]
+    pub public_amount: u128,
+    /// sums of the commitments to the low and high halves of the confidential amounts
+    pub amount_commitments: (CompressedRistretto, CompressedRistretto),
+    /// sums of the tracer encryptions of the low and high halves of the confidential amounts
+    pub lock_amount: (RecordDataCiphertext, RecordDataCiphertext),
+}
+
+impl AssetFlow {
+    /// Returns the total amount of the flow given the sums of the low and high halves of its
+    /// confidential amounts
+    fn total(&self, amount_sums: (u64, u64)) -> u128 {
+        self.public_amount + amount_sums.0 as u128 + ((amount_sums.1 as u128) << 32)
+    }
+}
+
+/// Sums of the halves of the confidential amounts of an asset flow, decrypted by its tracer,
+/// with a proof of correct decryption
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetFlowOpening {
+    /// sums of the low and high halves of the confidential amounts
+    pub amount_sums: (u64, u64),
+    pub(crate) proof: SigmaProof<Scalar, RistrettoPoint>,
+}
+
+/// I aggregate the outputs of `asset_type` among `outputs`, given as pairs of a record and its
+/// tracer memos, for the tracer of `tracer_enc_key`.
+/// Returns ZeiError::AssetTracingExtractionError if an output of `asset_type` with a
+/// confidential amount has no amount memo for the tracer.
+/// * `asset_type` - the asset type of the flow
+/// * `tracer_enc_key` - the record data encryption key of the tracer
+/// * `outputs` - records with their tracer memos, e.g. all the outputs of a block
+pub fn aggregate_asset_flow(
+    asset_type: &AssetType,
+    tracer_enc_key: &RecordDataEncKey,
+    outputs: &[(&BlindAssetRecord, &[TracerMemo])],
+) -> Result<AssetFlow> {
+    let mut n_outputs = 0u64;
+    let mut public_amount = 0u128;
+    let mut amount_commitments = (
+        RistrettoPoint::get_identity(),
+        RistrettoPoint::get_identity(),
+    );
+    let mut lock_amount = (
+        RecordDataCiphertext::identity(),
+        RecordDataCiphertext::identity(),
+    );
+    for (record, memos) in outputs.iter() {
+        if record.asset_type != XfrAssetType::NonConfidential(*asset_type) {
+            continue;
+        }
+        match record.amount {
+            XfrAmount::NonConfidential(amount) => public_amount += amount as u128,
+            XfrAmount::Confidential((com_low, com_high)) => {
+                let (lock_low, lock_high) = memos
+                    .iter()
+                    .find(|memo| memo.enc_key.record_data_enc_key == *tracer_enc_key)
+                    .and_then(|memo| memo.lock_amount.as_ref())
+                    .c(d!(ZeiError::AssetTracingExtractionError))?;
+                let com_low = com_low
+                    .decompress()
+                    .c(d!(ZeiError::DecompressElementError))?;
+                let com_high = com_high
+                    .decompress()
+                    .c(d!(ZeiError::DecompressElementError))?;
+                amount_commitments = (
+                    amount_commitments.0.add(&com_low),
+                    amount_commitments.1.add(&com_high),
+                );
+                lock_amount =
+                    (lock_amount.0.add(lock_low), lock_amount.1.add(lock_high));
+            }
+        }
+        n_outputs += 1;
+    }
+    Ok(AssetFlow {
+        asset_type: *asset_type,
+        tracer_enc_key: tracer_enc_key.clone(),
+        n_outputs,
+        public_amount,
+        amount_commitments: (
+            amount_commitments.0.compress(),
+            amount_commitments.1.compress(),
+        ),
+        lock_amount,
+    })
+}
+
+/// I aggregate the outputs of `asset_type` of the transfers `bodies`, e.g. of a block, for the
+/// tracer of `tracer_enc_key`. See aggregate_asset_flow.
+pub fn aggregate_block_asset_flow(
+    asset_type: &AssetType,
+    tracer_enc_key: &RecordDataEncKey,
+    bodies: &[&XfrBody],
+) -> Result<AssetFlow> {
+    let mut outputs = vec![];
+    for body in bodies.iter() {
+        // the tracer memos of the inputs come first
+        let n_inputs = body.inputs.len();
+        for (j, output) in body.outputs.iter().enumerate() {
+            let memos = body
+                .asset_tracing_memos
+                .get(n_inputs + j)
+                .map(|memos| memos.as_slice())
+                .unwrap_or(&[]);
+            outputs.push((output, memos));
+        }
+    }
+    aggregate_asset_flow(asset_type, tracer_enc_key, &outputs).c(d!())
+}
+
+fn init_asset_flow_transcript(flow: &AssetFlow) -> Transcript {
+    let mut transcript = Transcript::new(ASSET_FLOW_TRANSCRIPT);
+    transcript.append_message(b"asset type", &flow.asset_type.0);
+    transcript.append_u64(b"n outputs", flow.n_outputs);
+    transcript
+}
+
+fn amount_sums_as_scalars(amount_sums: (u64, u64)) -> [Scalar; 2] {
+    [
+        Scalar::from_u64(amount_sums.0),
+        Scalar::from_u64(amount_sums.1),
+    ]
+}
+
+/// I open `flow` with the tracer decryption key: I decrypt the sums of the low and high halves
+/// of its confidential amounts and prove the decryption.
+/// Returns ZeiError::AssetTracingExtractionError if the sums cannot be decrypted within the
+/// bound, or with the key.
+/// * `prng` - pseudo-random number generator
+/// * `dec_key` - the record data decryption key of the tracer
+/// * `flow` - the aggregated flow
+/// * `max_amount` - upper bound on the sum of the confidential amounts of the flow, e.g. the
+///   supply of the asset. The decryption takes O(sqrt(max_amount)) group operations.
+pub fn open_asset_flow<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    dec_key: &RecordDataDecKey,
+    flow: &AssetFlow,
+    max_amount: u64,
+) -> Result<AssetFlowOpening> {
+    // each half of an amount is at most the amount, and each output adds less than 2^32 to the
+    // sum of the low halves
+    let low_bound = max_amount.min(flow.n_outputs.saturating_mul(u32::MAX as u64));
+    let high_bound = max_amount >> 32;
+    let pc_gens = RistrettoPedersenGens::default();
+    let amount_sums = (
+        elgamal_decrypt_bsgs(&pc_gens.B, &flow.lock_amount.0, dec_key, low_bound)
+            .c(d!(ZeiError::AssetTracingExtractionError))?,
+        elgamal_decrypt_bsgs(&pc_gens.B, &flow.lock_amount.1, dec_key, high_bound)
+            .c(d!(ZeiError::AssetTracingExtractionError))?,
+    );
+    let proof = elgamal_prove_decryption(
+        &mut init_asset_flow_transcript(flow),
+        prng,
+        &pc_gens.B,
+        &flow.tracer_enc_key,
+        &[flow.lock_amount.0.clone(), flow.lock_amount.1.clone()],
+        &amount_sums_as_scalars(amount_sums),
+        dec_key,
+    )
+    .c(d!(ZeiError::AssetTracingExtractionError))?;
+    Ok(AssetFlowOpening { amount_sums, proof })
+}
+
+/// I verify the opening of `flow` by its tracer, and return the total amount of the flow: the
+/// sum of the amounts of its outputs, public and confidential.
+/// Returns ZeiError::ZKProofVerificationError if the opening is not valid.
+/// * `prng` - pseudo-random number generator
+/// * `flow` - the aggregated flow
+/// * `opening` - the opening of the tracer
+pub fn verify_asset_flow_opening<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    flow: &AssetFlow,
+    opening: &AssetFlowOpening,
+) -> Result<u128> {
+    let pc_gens = RistrettoPedersenGens::default();
+    elgamal_verify_decryption(
+        &mut init_asset_flow_transcript(flow),
+        prng,
+        &pc_gens.B,
+        &flow.tracer_enc_key,
+        &[flow.lock_amount.0.clone(), flow.lock_amount.1.clone()],
+        &amount_sums_as_scalars(opening.amount_sums),
+        &opening.proof,
+    )
+    .c(d!(ZeiError::ZKProofVerificationError))?;
+    Ok(flow.total(opening.amount_sums))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        aggregate_asset_flow, aggregate_block_asset_flow, open_asset_flow,
+        verify_asset_flow_opening,
+    };
+    use crate::xfr::asset_record::AssetRecordType;
+    use crate::xfr::lib::gen_xfr_note;
+    use crate::xfr::sig::XfrKeyPair;
+    use crate::xfr::structs::{
+        AssetRecord, AssetRecordTemplate, AssetTracerKeyPair, AssetType, TracerMemo,
+        TracingPolicies, TracingPolicy, XfrNote,
+    };
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use ruc::*;
+    use utils::errors::ZeiError;
+
+    fn transfer(
+        prng: &mut ChaChaRng,
+        asset_type: AssetType,
+        record_type: AssetRecordType,
+        amounts: &[u64],
+        policies: &TracingPolicies,
+    ) -> XfrNote {
+        let keypair = XfrKeyPair::generate(prng);
+        let input_template = AssetRecordTemplate::with_no_asset_tracing(
+            amounts.iter().sum(),
+            asset_type,
+            record_type,
+            keypair.pub_key,
+        );
+        let input =
+            AssetRecord::from_template_no_identity_tracing(prng, &input_template)
+                .unwrap();
+        let outputs: Vec<AssetRecord> = amounts
+            .iter()
+            .map(|amount| {
+                let template = AssetRecordTemplate::with_asset_tracing(
+                    *amount,
+                    asset_type,
+                    record_type,
+                    keypair.pub_key,
+                    policies.clone(),
+                );
+                AssetRecord::from_template_no_identity_tracing(prng, &template).unwrap()
+            })
+            .collect();
+        pnk!(gen_xfr_note(prng, &[input], &outputs, &[&keypair]))
+    }
+
+    const MAX_AMOUNT: u64 = (1 << 32) + 1000;
+
+    #[test]
+    fn test_asset_flow() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let tracer_keys = AssetTracerKeyPair::generate(&mut prng);
+        let enc_key = &tracer_keys.enc_key.record_data_enc_key;
+        let dec_key = &tracer_keys.dec_key.record_data_dec_key;
+        let policies = TracingPolicies::from_policy(TracingPolicy {
+            enc_keys: tracer_keys.enc_key.clone(),
+            asset_tracing: true,
+            identity_tracing: None,
+        });
+        let asset_type = AssetType::from_identical_byte(1u8);
+        let other_asset_type = AssetType::from_identical_byte(2u8);
+        let confidential = AssetRecordType::ConfidentialAmount_NonConfidentialAssetType;
+        let public = AssetRecordType::NonConfidentialAmount_NonConfidentialAssetType;
+
+        let block = [
+            transfer(&mut prng, asset_type, confidential, &[60, 40], &policies),
+            transfer(&mut prng, asset_type, public, &[5], &policies),
+            transfer(&mut prng, other_asset_type, confidential, &[30], &policies),
+            transfer(
+                &mut prng,
+                asset_type,
+                confidential,
+                &[(1 << 32) + 7],
+                &policies,
+            ),
+        ];
+        let bodies: Vec<_> = block.iter().map(|note| &note.body).collect();
+        let flow = pnk!(aggregate_block_asset_flow(&asset_type, enc_key, &bodies));
+        assert_eq!(flow.n_outputs, 4);
+        assert_eq!(flow.public_amount, 5);
+
+        let opening = pnk!(open_asset_flow(&mut prng, dec_key, &flow, MAX_AMOUNT));
+        assert_eq!(opening.amount_sums, (107, 1));
+        let total = pnk!(verify_asset_flow_opening(&mut prng, &flow, &opening));
+        assert_eq!(total, (1 << 32) + 112);
+
+        // wrong sums
+        let mut wrong_opening = opening.clone();
+        wrong_opening.amount_sums.0 += 1;
+        assert!(verify_asset_flow_opening(&mut prng, &flow, &wrong_opening).is_err());
+        // the opening is bound to the flow
+        let other_flow = pnk!(aggregate_block_asset_flow(
+            &asset_type,
+            enc_key,
+            &bodies[..1]
+        ));
+        assert!(verify_asset_flow_opening(&mut prng, &other_flow, &opening).is_err());
+        // the bound on the amounts is too small
+        assert!(open_asset_flow(&mut prng, dec_key, &flow, 100).is_err());
+        // another tracer cannot open the flow
+        let other_tracer_keys = AssetTracerKeyPair::generate(&mut prng);
+        assert!(open_asset_flow(
+            &mut prng,
+            &other_tracer_keys.dec_key.record_data_dec_key,
+            &flow,
+            MAX_AMOUNT
+        )
+        .is_err());
+
+        // the outputs of the asset type must be traced by the tracer
+        let untraced = transfer(
+            &mut prng,
+            asset_type,
+            confidential,
+            &[10],
+            &TracingPolicies::new(),
+        );
+        let output = &untraced.body.outputs[0];
+        let no_memos: &[TracerMemo] = &[];
+        msg_eq!(
+            ZeiError::AssetTracingExtractionError,
+            aggregate_asset_flow(&asset_type, enc_key, &[(output, no_memos)])
+                .err()
+                .unwrap()
+        );
+    }
+}
@@ -2,10 +2,11 @@ use crate::api::anon_creds::{
     ac_confidential_open_commitment, ACCommitmentKey, ACUserSecretKey, Attr,
     AttributeCiphertext, ConfidentialAC, Credential,
 };
+use crate::xfr::asset_tracer::gen_tracer_memos;
 use crate::xfr::sig::{XfrKeyPair, XfrPublicKey};
 use crate::xfr::structs::{
     AssetRecord, AssetRecordTemplate, AssetType, BlindAssetRecord, OpenAssetRecord,
-    OwnerMemo, TracerMemo, TracingPolicies, XfrAmount, XfrAssetType,
+    OwnerMemo, SharedTracerMemo, TracerMemo, TracingPolicies, XfrAmount, XfrAssetType,
 };
 use algebra::groups::Zero;
 use algebra::ristretto::RistrettoScalar as Scalar;
@@ -93,6 +94,7 @@ impl AssetRecord {
             identity_proofs: Vec::new(),
             asset_tracers_memos: Vec::new(),
             owner_memo: None,
+            shared_tracer_memo: None,
         }
     }
 
@@ -106,49 +108,31 @@ impl AssetRecord {
         oar: OpenAssetRecord,
         asset_tracing_policies: TracingPolicies,
     ) -> Result<AssetRecord> {
-        let mut memos = vec![];
+        let no_attrs: &[(Attr, AttributeCiphertext)] = &[];
+        let mut policies_info = vec![];
         let mut identity_proofs = vec![];
         for asset_tracing_policy in asset_tracing_policies.get_policies().iter() {
             // 1. check for inconsistency errors
             if asset_tracing_policy.identity_tracing.is_some() {
                 return Err(eg!(ZeiError::ParameterError)); // should use from_open_asset_record_with_identity_tracing method
             }
-
-            let (amount_info, asset_type_info) =
-                if asset_tracing_policy.asset_tracing {
-                    let amount_info = match oar.get_record_type() {
-          AssetRecordType::NonConfidentialAmount_NonConfidentialAssetType
-          | AssetRecordType::NonConfidentialAmount_ConfidentialAssetType => None,
-          _ => {
-            let amount = u64_to_u32_pair(oar.amount);
-            Some((amount.0, amount.1, &oar.amount_blinds.0, &oar.amount_blinds.1))
-          }
-        };
-                    let asset_type_info = match oar.get_record_type() {
-          AssetRecordType::NonConfidentialAmount_NonConfidentialAssetType
-          | AssetRecordType::ConfidentialAmount_NonConfidentialAssetType => None,
-          _ => Some((&oar.asset_type, &oar.type_blind)),
-        };
-                    (amount_info, asset_type_info)
-                } else {
-                    (None, None)
-                };
-            let asset_tracer_memo = TracerMemo::new(
-                prng,
+            policies_info.push((
                 &asset_tracing_policy.enc_keys,
-                amount_info,
-                asset_type_info,
-                &[],
-            );
-            memos.push(asset_tracer_memo);
+                asset_tracing_policy.asset_tracing,
+                no_attrs,
+            ));
             identity_proofs.push(None);
         }
+        let (amount_info, asset_type_info) = record_data_info(&oar);
+        let (memos, shared_tracer_memo) =
+            gen_tracer_memos(prng, amount_info, asset_type_info, &policies_info, true);
         Ok(AssetRecord {
             open_asset_record: oar,
             tracing_policies: asset_tracing_policies,
             identity_proofs,
             asset_tracers_memos: memos,
             owner_memo: None,
+            shared_tracer_memo,
         })
     }
 
@@ -164,30 +148,10 @@ impl AssetRecord {
         credential: &Credential,
         credential_commitment_key: &ACCommitmentKey,
     ) -> Result<AssetRecord> {
-        let mut memos = vec![];
+        let mut attrs_and_ctexts_list = vec![];
         let mut identity_proofs = vec![];
         for asset_tracing_policy in asset_tracing_policies.get_policies().iter() {
-            // 1. compute tracer_memo
-            let (amount_info, asset_type_info) =
-                if asset_tracing_policy.asset_tracing {
-                    let amount_info = match oar.get_record_type() {
-          AssetRecordType::NonConfidentialAmount_NonConfidentialAssetType
-          | AssetRecordType::NonConfidentialAmount_ConfidentialAssetType => None,
-          _ => {
-            let amount = u64_to_u32_pair(oar.amount);
-            Some((amount.0, amount.1, &oar.amount_blinds.0, &oar.amount_blinds.1))
-          }
-        };
-                    let asset_type_info = match oar.get_record_type() {
-          AssetRecordType::NonConfidentialAmount_NonConfidentialAssetType
-          | AssetRecordType::ConfidentialAmount_NonConfidentialAssetType => None,
-          _ => Some((&oar.asset_type, &oar.type_blind)),
-        };
-                    (amount_info, asset_type_info)
-                } else {
-                    (None, None)
-                };
-
+            // 1. compute the identity attributes of tracer_memo
             let (attrs_and_ctexts, proof) =
                 match asset_tracing_policy.identity_tracing.as_ref() {
                     Some(id_policy) => {
@@ -216,22 +180,32 @@ impl AssetRecord {
                     }
                     None => (vec![], None),
                 };
-            let asset_tracer_memo = TracerMemo::new(
-                prng,
-                &asset_tracing_policy.enc_keys,
-                amount_info,
-                asset_type_info,
-                &attrs_and_ctexts,
-            );
             identity_proofs.push(proof);
-            memos.push(asset_tracer_memo);
+            attrs_and_ctexts_list.push(attrs_and_ctexts);
         }
+        // 2. compute tracer_memos
+        let policies_info = asset_tracing_policies
+            .get_policies()
+            .iter()
+            .zip(attrs_and_ctexts_list.iter())
+            .map(|(policy, attrs_and_ctexts)| {
+                (
+                    &policy.enc_keys,
+                    policy.asset_tracing,
+                    attrs_and_ctexts.as_slice(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let (amount_info, asset_type_info) = record_data_info(&oar);
+        let (memos, shared_tracer_memo) =
+            gen_tracer_memos(prng, amount_info, asset_type_info, &policies_info, true);
         Ok(AssetRecord {
             open_asset_record: oar,
             tracing_policies: asset_tracing_policies,
             identity_proofs,
             asset_tracers_memos: memos,
             owner_memo: None,
+            shared_tracer_memo,
         })
     }
 
@@ -326,16 +300,53 @@ impl AssetRecordTemplate {
         template
     }
 }
+
+// Returns the amount and asset type information of the tracer memos of a record: the amount
+// (low, high, blinds) if it is confidential, and the asset type and blind if it is confidential
+#[allow(clippy::type_complexity)]
+fn record_data_info(
+    oar: &OpenAssetRecord,
+) -> (
+    Option<(u32, u32, &Scalar, &Scalar)>,
+    Option<(&AssetType, &Scalar)>,
+) {
+    let amount_info = match oar.get_record_type() {
+        AssetRecordType::NonConfidentialAmount_NonConfidentialAssetType
+        | AssetRecordType::NonConfidentialAmount_ConfidentialAssetType => None,
+        _ => {
+            let amount = u64_to_u32_pair(oar.amount);
+            Some((
+                amount.0,
+                amount.1,
+                &oar.amount_blinds.0,
+                &oar.amount_blinds.1,
+            ))
+        }
+    };
+    let asset_type_info = match oar.get_record_type() {
+        AssetRecordType::NonConfidentialAmount_NonConfidentialAssetType
+        | AssetRecordType::ConfidentialAmount_NonConfidentialAssetType => None,
+        _ => Some((&oar.asset_type, &oar.type_blind)),
+    };
+    (amount_info, asset_type_info)
+}
+
+// Samples the record of `asset_record` and its memos. The amount and asset type of the tracer
+// memos are in a shared tracer memo if `share_record_data` and at least two policies trace
+// them, see gen_tracer_memos
+#[allow(clippy::type_complexity)]
 fn sample_blind_asset_record<R: CryptoRng + RngCore>(
     prng: &mut R,
     pc_gens: &RistrettoPedersenGens,
     asset_record: &AssetRecordTemplate,
     attrs_and_ctexts: Vec<Vec<(Attr, AttributeCiphertext)>>,
+    share_record_data: bool,
 ) -> (
     BlindAssetRecord,
     (Scalar, Scalar),
     Scalar,
     Vec<TracerMemo>,
+    Option<SharedTracerMemo>,
     Option<OwnerMemo>,
 ) {
     // use enum matching instead of nested if else clause for readability and clarity
@@ -426,35 +437,85 @@ fn sample_blind_asset_record<R: CryptoRng + RngCore>(
     };
 
     // TODO: (alex) API for asset tracer to be improved
-    let mut tracer_memos = vec![];
-    let tracing_policies = &asset_record.asset_tracing_policies.0;
-    for (policy, attr_ctexts) in tracing_policies.iter().zip(attrs_and_ctexts) {
-        let mut amount_info = None;
-        let mut asset_type_info = None;
-        if policy.asset_tracing {
-            if asset_record.asset_record_type.is_confidential_amount() {
-                let (amount_lo, amount_hi) = utils::u64_to_u32_pair(asset_record.amount);
-                amount_info =
-                    Some((amount_lo, amount_hi, &amount_blinds.0, &amount_blinds.1));
-            }
-            if asset_record.asset_record_type.is_confidential_asset_type() {
-                asset_type_info = Some((&asset_record.asset_type, &asset_type_blind));
-            }
-        }
-        let memo = TracerMemo::new(
-            prng,
-            &policy.enc_keys,
-            amount_info,
-            asset_type_info,
-            &attr_ctexts,
-        );
-        tracer_memos.push(memo);
+    let mut amount_info = None;
+    let mut asset_type_info = None;
+    if asset_record.asset_record_type.is_confidential_amount() {
+        let (amount_lo, amount_hi) = utils::u64_to_u32_pair(asset_record.amount);
+        amount_info = Some((amount_lo, amount_hi, &amount_blinds.0, &amount_blinds.1));
     }
+    if asset_record.asset_record_type.is_confidential_asset_type() {
+        asset_type_info = Some((&asset_record.asset_type, &asset_type_blind));
+    }
+    let tracing_policies = &asset_record.asset_tracing_policies.0;
+    let policies_info = tracing_policies
+        .iter()
+        .zip(attrs_and_ctexts.iter())
+        .map(|(policy, attr_ctexts)| {
+            (
+                &policy.enc_keys,
+                policy.asset_tracing,
+                attr_ctexts.as_slice(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let (tracer_memos, shared_tracer_memo) = gen_tracer_memos(
+        prng,
+        amount_info,
+        asset_type_info,
+        &policies_info,
+        share_record_data,
+    );
     (
         blind_asset_record,
         amount_blinds,
         asset_type_blind,
         tracer_memos,
+        shared_tracer_memo,
+        owner_memo,
+    )
+}
+
+// build_open_asset_record, with the amount and asset type of the tracer memos in a shared
+// tracer memo if `share_record_data`, see sample_blind_asset_record
+fn sample_open_asset_record<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    pc_gens: &RistrettoPedersenGens,
+    asset_record: &AssetRecordTemplate,
+    attrs_and_ctexts: Vec<Vec<(Attr, AttributeCiphertext)>>,
+    share_record_data: bool,
+) -> (
+    OpenAssetRecord,
+    Vec<TracerMemo>,
+    Option<SharedTracerMemo>,
+    Option<OwnerMemo>,
+) {
+    let (
+        blind_asset_record,
+        amount_blinds,
+        type_blind,
+        asset_tracing_memos,
+        shared_tracer_memo,
+        owner_memo,
+    ) = sample_blind_asset_record(
+        prng,
+        pc_gens,
+        asset_record,
+        attrs_and_ctexts,
+        share_record_data,
+    );
+
+    let open_asset_record = OpenAssetRecord {
+        blind_asset_record,
+        amount: asset_record.amount,
+        amount_blinds,
+        asset_type: asset_record.asset_type,
+        type_blind,
+    };
+
+    (
+        open_asset_record,
+        asset_tracing_memos,
+        shared_tracer_memo,
         owner_memo,
     )
 }
@@ -472,16 +533,8 @@ pub fn build_open_asset_record<R: CryptoRng + RngCore>(
     asset_record: &AssetRecordTemplate,
     attrs_and_ctexts: Vec<Vec<(Attr, AttributeCiphertext)>>,
 ) -> (OpenAssetRecord, Vec<TracerMemo>, Option<OwnerMemo>) {
-    let (blind_asset_record, amount_blinds, type_blind, asset_tracing_memos, owner_memo) =
-        sample_blind_asset_record(prng, pc_gens, asset_record, attrs_and_ctexts);
-
-    let open_asset_record = OpenAssetRecord {
-        blind_asset_record,
-        amount: asset_record.amount,
-        amount_blinds,
-        asset_type: asset_record.asset_type,
-        type_blind,
-    };
+    let (open_asset_record, asset_tracing_memos, _, owner_memo) =
+        sample_open_asset_record(prng, pc_gens, asset_record, attrs_and_ctexts, false);
 
     (open_asset_record, asset_tracing_memos, owner_memo)
 }
@@ -499,8 +552,8 @@ pub fn build_blind_asset_record<R: CryptoRng + RngCore>(
     asset_record: &AssetRecordTemplate,
     attrs_and_ctexts: Vec<Vec<(Attr, AttributeCiphertext)>>,
 ) -> (BlindAssetRecord, Vec<TracerMemo>, Option<OwnerMemo>) {
-    let (blind_asset_record, _, _, asset_tracing_memos, owner_memo) =
-        sample_blind_asset_record(prng, pc_gens, asset_record, attrs_and_ctexts);
+    let (blind_asset_record, _, _, asset_tracing_memos, _, owner_memo) =
+        sample_blind_asset_record(prng, pc_gens, asset_record, attrs_and_ctexts, false);
 
     (blind_asset_record, asset_tracing_memos, owner_memo)
 }
@@ -607,8 +660,8 @@ fn build_record_input_from_template<R: CryptoRng + RngCore>(
         attrs_ctexts.push(attrs_and_ctexts);
         reveal_proofs.push(reveal_proof);
     }
-    let (open_asset_record, asset_tracing_memos, owner_memo) =
-        build_open_asset_record(prng, &pc_gens, asset_record, attrs_ctexts);
+    let (open_asset_record, asset_tracing_memos, shared_tracer_memo, owner_memo) =
+        sample_open_asset_record(prng, &pc_gens, asset_record, attrs_ctexts, true);
 
     Ok(AssetRecord {
         open_asset_record,
@@ -616,6 +669,7 @@ fn build_record_input_from_template<R: CryptoRng + RngCore>(
         identity_proofs: reveal_proofs,
         asset_tracers_memos: asset_tracing_memos,
         owner_memo,
+        shared_tracer_memo,
     })
 }
 
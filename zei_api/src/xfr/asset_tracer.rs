@@ -1,5 +1,7 @@
 use crate::api::anon_creds::{Attr, AttributeCiphertext};
-use crate::xfr::structs::{AssetTracerDecKeys, AssetTracerEncKeys, TracerMemo};
+use crate::xfr::structs::{
+    AssetTracerDecKeys, AssetTracerEncKeys, SharedTracerMemo, TracerMemo,
+};
 use crate::xfr::structs::{AssetType, ASSET_TYPE_LENGTH};
 use algebra::bls12_381::{BLSScalar, BLSG1};
 use algebra::groups::{Group, GroupArithmetic, Scalar as ZeiScalar};
@@ -12,6 +14,10 @@ use crypto::basics::elgamal::{
 use crypto::basics::hybrid_encryption::{
     hybrid_decrypt_with_x25519_secret_key, hybrid_encrypt_with_x25519_key,
 };
+use crypto::basics::multi_recipient_encryption::{
+    multi_recipient_decrypt, multi_recipient_encrypt, multi_recipient_verify,
+};
+use itertools::Itertools;
 use rand_core::{CryptoRng, RngCore};
 use ruc::*;
 use utils::errors::ZeiError;
@@ -35,12 +41,34 @@ impl TracerMemo {
         asset_type_info: Option<(&AssetType, &Scalar)>,
         attrs_info: &[(Attr, AttributeCiphertext)],
     ) -> Self {
-        let mut plaintext = vec![];
+        Self::sample(
+            prng,
+            tracer_enc_key,
+            amount_info,
+            asset_type_info,
+            attrs_info,
+            true,
+        )
+    }
+
+    // Samples a TracerMemo whose lock_info encrypts the amount and asset type if
+    // `lock_record_data`, and the identity attributes
+    fn sample<R: CryptoRng + RngCore>(
+        prng: &mut R,
+        tracer_enc_key: &AssetTracerEncKeys,
+        amount_info: Option<(u32, u32, &Scalar, &Scalar)>,
+        asset_type_info: Option<(&AssetType, &Scalar)>,
+        attrs_info: &[(Attr, AttributeCiphertext)],
+        lock_record_data: bool,
+    ) -> Self {
+        let mut plaintext = if lock_record_data {
+            record_data_plaintext(amount_info, asset_type_info)
+        } else {
+            vec![]
+        };
         let pc_gens = RistrettoPedersenGens::default();
         let lock_amount =
             amount_info.map(|(amount_low, amount_high, blind_low, blind_high)| {
-                plaintext.extend_from_slice(&amount_low.to_be_bytes());
-                plaintext.extend_from_slice(&amount_high.to_be_bytes());
                 let ctext_amount_low = elgamal_encrypt(
                     &pc_gens.B,
                     &Scalar::from_u32(amount_low),
@@ -57,7 +85,6 @@ impl TracerMemo {
             });
 
         let lock_asset_type = asset_type_info.map(|(asset_type, blind)| {
-            plaintext.extend_from_slice(&asset_type.0);
             elgamal_encrypt(
                 &pc_gens.B,
                 &asset_type.as_scalar(),
@@ -87,10 +114,32 @@ impl TracerMemo {
     /// Decrypts the asset tracer memo:
     /// Returns ZeiError:BogusAssetTracerMemo in case decrypted values are inconsistents
     pub fn decrypt(&self, dec_key: &AssetTracerDecKeys) -> Result<DecryptedAssetMemo> {
+        self.decrypt_with_shared_memo(dec_key, None).c(d!())
+    }
+
+    /// Decrypts the asset tracer memo of the `index`-th recipient of `shared_memo`, which
+    /// encrypts the amount and asset type instead of self.lock_info, see gen_tracer_memos.
+    /// With no shared memo, it is the same as decrypt.
+    /// Returns ZeiError:BogusAssetTracerMemo in case decrypted values are inconsistents
+    pub fn decrypt_with_shared_memo(
+        &self,
+        dec_key: &AssetTracerDecKeys,
+        shared_memo: Option<(&SharedTracerMemo, usize)>,
+    ) -> Result<DecryptedAssetMemo> {
         let mut plaintext = hybrid_decrypt_with_x25519_secret_key(
             &self.lock_info,
             &dec_key.lock_info_dec_key,
         );
+        if let Some((shared_memo, index)) = shared_memo {
+            let mut record_data = multi_recipient_decrypt(
+                shared_memo,
+                index,
+                &dec_key.record_data_dec_key,
+            )
+            .c(d!(ZeiError::BogusAssetTracerMemo))?;
+            record_data.append(&mut plaintext);
+            plaintext = record_data;
+        }
 
         // decrypt and sanitize amount
         let amount = if self.lock_amount.is_some() {
@@ -268,6 +317,111 @@ impl TracerMemo {
     }
 }
 
+// Returns the encoding of the amount and asset type in the lock_info of a TracerMemo
+fn record_data_plaintext(
+    amount_info: Option<(u32, u32, &Scalar, &Scalar)>,
+    asset_type_info: Option<(&AssetType, &Scalar)>,
+) -> Vec<u8> {
+    let mut plaintext = vec![];
+    if let Some((amount_low, amount_high, _, _)) = amount_info {
+        plaintext.extend_from_slice(&amount_low.to_be_bytes());
+        plaintext.extend_from_slice(&amount_high.to_be_bytes());
+    }
+    if let Some((asset_type, _)) = asset_type_info {
+        plaintext.extend_from_slice(&asset_type.0);
+    }
+    plaintext
+}
+
+/// Samples the tracer memos of a record, one for each policy of `policies_info`, given by its
+/// tracer keys, whether it traces the amount and asset type, and its identity attributes.
+/// If `share_record_data` and at least two memos encrypt the amount or the asset type, they are
+/// encrypted once to the record data keys of these memos in the returned SharedTracerMemo,
+/// instead of in the lock_info of every memo, which then only encrypts the attributes.
+/// The ElGamal ciphertexts of every memo are unchanged, as the tracing proofs are about them.
+/// amount_info is (amount_low, amount_high, amount_blind_low, amount_blind_high) tuple
+/// asset_type_info is (asset_type, asset_type_blind) tuple
+pub fn gen_tracer_memos<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    amount_info: Option<(u32, u32, &Scalar, &Scalar)>,
+    asset_type_info: Option<(&AssetType, &Scalar)>,
+    policies_info: &[(&AssetTracerEncKeys, bool, &[(Attr, AttributeCiphertext)])],
+    share_record_data: bool,
+) -> (Vec<TracerMemo>, Option<SharedTracerMemo>) {
+    let has_record_data = amount_info.is_some() || asset_type_info.is_some();
+    let recipients = policies_info
+        .iter()
+        .filter(|(_, asset_tracing, _)| *asset_tracing && has_record_data)
+        .map(|(enc_key, _, _)| enc_key.record_data_enc_key.clone())
+        .collect_vec();
+    let shared = share_record_data && recipients.len() >= 2;
+    let memos = policies_info
+        .iter()
+        .map(|(enc_key, asset_tracing, attrs_info)| {
+            let (amount_info, asset_type_info) = if *asset_tracing {
+                (amount_info, asset_type_info)
+            } else {
+                (None, None)
+            };
+            TracerMemo::sample(
+                prng,
+                enc_key,
+                amount_info,
+                asset_type_info,
+                attrs_info,
+                !shared,
+            )
+        })
+        .collect_vec();
+    if !shared {
+        return (memos, None);
+    }
+    let shared_memo = multi_recipient_encrypt(
+        prng,
+        &RistrettoPoint::get_base(),
+        &recipients,
+        &record_data_plaintext(amount_info, asset_type_info),
+    )
+    .unwrap(); // safe unwrap: there are at least two recipients
+    (memos, Some(shared_memo))
+}
+
+/// Returns the index of the `position`-th memo of `memos` among the recipients of the shared
+/// memo of their record, that is, the memos that encrypt the amount or the asset type.
+/// Returns None if this memo encrypts neither.
+pub fn shared_tracer_memo_index(memos: &[TracerMemo], position: usize) -> Option<usize> {
+    let memo = memos.get(position)?;
+    if memo.lock_amount.is_none() && memo.lock_asset_type.is_none() {
+        return None;
+    }
+    Some(
+        memos[..position]
+            .iter()
+            .filter(|m| m.lock_amount.is_some() || m.lock_asset_type.is_some())
+            .count(),
+    )
+}
+
+/// Verifies that `shared_memo` wraps the same key to the record data keys of the memos of
+/// `memos` that encrypt the amount or the asset type, in order, and that there are at least two
+/// of them. Returns ZeiError::BogusAssetTracerMemo otherwise.
+pub(crate) fn verify_shared_tracer_memo<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    memos: &[TracerMemo],
+    shared_memo: &SharedTracerMemo,
+) -> Result<()> {
+    let recipients = memos
+        .iter()
+        .filter(|m| m.lock_amount.is_some() || m.lock_asset_type.is_some())
+        .map(|m| m.enc_key.record_data_enc_key.clone())
+        .collect_vec();
+    if recipients.len() < 2 {
+        return Err(eg!(ZeiError::BogusAssetTracerMemo));
+    }
+    multi_recipient_verify(prng, &RistrettoPoint::get_base(), &recipients, shared_memo)
+        .c(d!(ZeiError::BogusAssetTracerMemo))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::xfr::structs::{AssetTracerKeyPair, AssetType, TracerMemo};
@@ -1,8 +1,9 @@
 use crate::api::anon_creds::{ACCommitment, Attr};
-use crate::setup::PublicParams;
+use crate::setup::{PublicParams, MAX_SAFE_XFR_INPUTS};
 use crate::xfr::asset_mixer::{
     batch_verify_asset_mixing, prove_asset_mixing, AssetMixProof, AssetMixingInstance,
 };
+use crate::xfr::asset_tracer::{shared_tracer_memo_index, verify_shared_tracer_memo};
 use crate::xfr::proofs::{
     asset_amount_tracing_proofs, asset_proof, batch_verify_confidential_amount,
     batch_verify_confidential_asset, batch_verify_tracer_tracing_proof, range_proof,
@@ -11,13 +12,16 @@ use crate::xfr::sig::{XfrKeyPair, XfrMultiSig, XfrPublicKey};
 use crate::xfr::structs::*;
 use algebra::groups::{GroupArithmetic, Scalar as _, ScalarArithmetic};
 use algebra::ristretto::{CompressedRistretto, RistrettoScalar as Scalar};
+use bincode::Options;
 use crypto::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
 use itertools::Itertools;
 use rand_core::{CryptoRng, RngCore};
 use ruc::*;
 use serde::ser::Serialize;
 use std::collections::HashMap;
+use std::io::Read;
 use utils::errors::ZeiError;
+use utils::serialization::Validate;
 use utils::u64_to_u32_pair;
 
 const POW_2_32: u64 = 0xFFFF_FFFFu64 + 1;
@@ -180,7 +184,36 @@ pub fn gen_xfr_note<R: CryptoRng + RngCore>(
 
     let multisig = compute_transfer_multisig(&body, input_key_pairs).c(d!())?;
 
-    Ok(XfrNote { body, multisig })
+    Ok(XfrNote {
+        body,
+        multisig,
+        version: XFR_NOTE_VERSION,
+    })
+}
+
+/// I create a XfrNote that pays the public amount `fee` out of the inputs, see gen_xfr_body_with_fee
+pub fn gen_xfr_note_with_fee<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    inputs: &[AssetRecord],
+    outputs: &[AssetRecord],
+    input_key_pairs: &[&XfrKeyPair],
+    fee: u64,
+) -> Result<XfrNote> {
+    if inputs.is_empty() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+
+    check_keys(inputs, input_key_pairs).c(d!())?;
+
+    let body = gen_xfr_body_with_fee(prng, inputs, outputs, fee).c(d!())?;
+
+    let multisig = compute_transfer_multisig(&body, input_key_pairs).c(d!())?;
+
+    Ok(XfrNote {
+        body,
+        multisig,
+        version: XFR_NOTE_VERSION,
+    })
 }
 
 /// I create the body of a xfr note. This body contains the data to be signed.
@@ -240,11 +273,42 @@ pub fn gen_xfr_body<R: CryptoRng + RngCore>(
     inputs: &[AssetRecord],
     outputs: &[AssetRecord],
 ) -> Result<XfrBody> {
-    if inputs.is_empty() {
+    gen_xfr_body_with_fee(prng, inputs, outputs, 0).c(d!())
+}
+
+/// I create the body of a xfr note that pays the public amount `fee` out of the inputs.
+/// The fee is deducted from the total input amount in the range proof of the transfer, so
+/// that inputs and outputs keep their amounts confidential and no transparent change output is
+/// needed to pay the fee. The transfer must involve a single asset type and some confidential
+/// amount, the fee being of this asset type.
+/// * `prng` - pseudo-random number generator
+/// * `inputs` - asset records containing amounts, assets, policies and memos
+/// * `outputs` - asset records containing amounts, assets, policies and memos
+/// * `fee` - public amount paid by the transfer: the total input amount equals the total output amount plus the fee
+/// * `returns` - an XfrBody struct or an error
+pub fn gen_xfr_body_with_fee<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    inputs: &[AssetRecord],
+    outputs: &[AssetRecord],
+    fee: u64,
+) -> Result<XfrBody> {
+    if inputs.is_empty()
+        || inputs.len() > MAX_SAFE_XFR_INPUTS
+        || outputs.len() > MAX_SAFE_XFR_INPUTS
+    {
         return Err(eg!(ZeiError::ParameterError));
     }
     let xfr_type = XfrType::from_inputs_outputs(inputs, outputs);
-    check_asset_amount(inputs, outputs).c(d!())?;
+    if fee != 0
+        && !matches!(
+            xfr_type,
+            XfrType::ConfidentialAmount_NonConfidentialAssetType_SingleAsset
+                | XfrType::Confidential_SingleAsset
+        )
+    {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    check_asset_amount(inputs, outputs, fee).c(d!())?;
 
     let single_asset = !matches!(
         xfr_type,
@@ -265,6 +329,7 @@ pub fn gen_xfr_body<R: CryptoRng + RngCore>(
             open_inputs.as_slice(),
             open_outputs.as_slice(),
             xfr_type,
+            fee,
         )
         .c(d!())?
     } else {
@@ -320,12 +385,27 @@ pub fn gen_xfr_body<R: CryptoRng + RngCore>(
             record_input.owner_memo.clone() // Can I avoid this clone?
         })
         .collect_vec();
+    let shared_tracer_memos = if inputs
+        .iter()
+        .chain(outputs)
+        .all(|record_input| record_input.shared_tracer_memo.is_none())
+    {
+        vec![]
+    } else {
+        inputs
+            .iter()
+            .chain(outputs)
+            .map(|record_input| record_input.shared_tracer_memo.clone())
+            .collect_vec()
+    };
     Ok(XfrBody {
         inputs: xfr_inputs,
         outputs: xfr_outputs,
         proofs,
         asset_tracing_memos: tracer_memos,
         owners_memos: owner_memos,
+        fee,
+        shared_tracer_memos,
     })
 }
 
@@ -386,14 +466,17 @@ fn gen_xfr_proofs_single_asset<R: CryptoRng + RngCore>(
     inputs: &[&OpenAssetRecord],
     outputs: &[&OpenAssetRecord],
     xfr_type: XfrType,
+    fee: u64,
 ) -> Result<AssetTypeAndAmountProof> {
     let pc_gens = RistrettoPedersenGens::default();
 
     match xfr_type {
         XfrType::NonConfidential_SingleAsset => Ok(AssetTypeAndAmountProof::NoProof),
-        XfrType::ConfidentialAmount_NonConfidentialAssetType_SingleAsset => Ok(
-            AssetTypeAndAmountProof::ConfAmount(range_proof(inputs, outputs).c(d!())?),
-        ),
+        XfrType::ConfidentialAmount_NonConfidentialAssetType_SingleAsset => {
+            Ok(AssetTypeAndAmountProof::ConfAmount(
+                range_proof(inputs, outputs, fee).c(d!())?,
+            ))
+        }
         XfrType::NonConfidentialAmount_ConfidentialAssetType_SingleAsset => {
             Ok(AssetTypeAndAmountProof::ConfAsset(Box::new(
                 asset_proof(prng, &pc_gens, inputs, outputs).c(d!())?,
@@ -401,7 +484,7 @@ fn gen_xfr_proofs_single_asset<R: CryptoRng + RngCore>(
         }
         XfrType::Confidential_SingleAsset => {
             Ok(AssetTypeAndAmountProof::ConfAll(Box::new((
-                range_proof(inputs, outputs).c(d!())?,
+                range_proof(inputs, outputs, fee).c(d!())?,
                 asset_proof(prng, &pc_gens, inputs, outputs).c(d!())?,
             ))))
         }
@@ -411,10 +494,19 @@ fn gen_xfr_proofs_single_asset<R: CryptoRng + RngCore>(
 
 /// Check that for each asset type total input amount >= total output amount,
 /// returns Err(ZeiError::XfrCreationAssetAmountError) otherwise.
+/// The `fee` is paid in the asset type of the first input, on top of the outputs.
 /// Return Ok(true) if all inputs and outputs involve a single asset type. If multiple assets
 /// are detected, then return Ok(false)
-fn check_asset_amount(inputs: &[AssetRecord], outputs: &[AssetRecord]) -> Result<()> {
+fn check_asset_amount(
+    inputs: &[AssetRecord],
+    outputs: &[AssetRecord],
+    fee: u64,
+) -> Result<()> {
     let mut amounts = HashMap::new();
+    amounts.insert(
+        inputs[0].open_asset_record.asset_type,
+        vec![-i128::from(fee)],
+    );
 
     for record in inputs.iter() {
         match amounts.get_mut(&(record.open_asset_record.asset_type)) {
@@ -454,31 +546,71 @@ fn check_asset_amount(inputs: &[AssetRecord], outputs: &[AssetRecord]) -> Result
     Ok(())
 }
 
-/// I compute a multisignature over the transfer's body
-pub(crate) fn compute_transfer_multisig(
+/// I return the message signed by the multisignature of a transfer note of version `version`
+pub(crate) fn transfer_multisig_message(
     body: &XfrBody,
-    keys: &[&XfrKeyPair],
-) -> Result<XfrMultiSig> {
+    version: u32,
+) -> Result<Vec<u8>> {
     let mut bytes = vec![];
+    match version {
+        0 => {}
+        1 | 2 => {
+            bytes.extend_from_slice(b"Zei XfrNote");
+            bytes.extend_from_slice(&version.to_le_bytes());
+        }
+        _ => return Err(eg!(ZeiError::UnsupportedVersionError)),
+    }
     body.serialize(&mut rmp_serde::Serializer::new(&mut bytes))
         .c(d!(ZeiError::SerializationError))?;
+    // the fee is not part of the encoding of the body, and the notes of version 0 have none
+    if version > 0 {
+        bytes.extend_from_slice(&body.fee.to_le_bytes());
+    }
+    // nor are the shared tracer memos, which only the notes of version 2 have
+    if version >= 2 {
+        body.shared_tracer_memos
+            .serialize(&mut rmp_serde::Serializer::new(&mut bytes))
+            .c(d!(ZeiError::SerializationError))?;
+    }
+    Ok(bytes)
+}
+
+/// I compute a multisignature over the transfer's body for a note of version XFR_NOTE_VERSION
+pub(crate) fn compute_transfer_multisig(
+    body: &XfrBody,
+    keys: &[&XfrKeyPair],
+) -> Result<XfrMultiSig> {
+    let bytes = transfer_multisig_message(body, XFR_NOTE_VERSION).c(d!())?;
     Ok(XfrMultiSig::sign(&keys, &bytes))
 }
 
-/// I verify the transfer multisignature over the its body
-pub(crate) fn verify_transfer_multisig(xfr_note: &XfrNote) -> Result<()> {
-    let mut bytes = vec![];
-    xfr_note
-        .body
-        .serialize(&mut rmp_serde::Serializer::new(&mut bytes))
-        .c(d!(ZeiError::SerializationError))?;
-    let pubkeys = xfr_note
-        .body
-        .inputs
+/// I verify the transfer multisignatures of a list of notes with a single batch verification
+/// of their ed25519 signatures. The signatures of the notes of version 0 are verified with the
+/// legacy rule of XfrPublicKey::verify, and the ones of later versions with the strict rule of
+/// XfrPublicKey::verify_strict
+pub(crate) fn batch_verify_transfer_multisigs(xfr_notes: &[&XfrNote]) -> Result<()> {
+    let mut bodies_bytes = vec![];
+    let mut pubkeys = vec![];
+    for xfr_note in xfr_notes {
+        bodies_bytes
+            .push(transfer_multisig_message(&xfr_note.body, xfr_note.version).c(d!())?);
+        pubkeys.push(
+            xfr_note
+                .body
+                .inputs
+                .iter()
+                .map(|input| &input.public_key)
+                .collect_vec(),
+        );
+    }
+    let multisigs = xfr_notes.iter().map(|note| &note.multisig).collect_vec();
+    let pubkeys_ref = pubkeys.iter().map(|keys| keys.as_slice()).collect_vec();
+    let messages = bodies_bytes
         .iter()
-        .map(|input| &input.public_key)
+        .map(|bytes| bytes.as_slice())
         .collect_vec();
-    xfr_note.multisig.verify(&pubkeys, &bytes)
+    let strict = xfr_notes.iter().map(|note| note.version > 0).collect_vec();
+    XfrMultiSig::batch_verify(&multisigs, &pubkeys_ref, &messages, &strict).c(d!())
 }
 
 /// XfrNote verification
@@ -495,6 +627,31 @@ pub fn verify_xfr_note<R: CryptoRng + RngCore>(
     batch_verify_xfr_notes(prng, params, &[&xfr_note], &[&policies]).c(d!())
 }
 
+/// XfrNote verification from an untrusted reader providing the bincode encoding of the note,
+/// reading at most `max_note_size` bytes. This bounds the input size only: the note is decoded,
+/// then verified as in verify_xfr_note, with the same allocations
+/// * `prng` - pseudo-random number generator
+/// * `reader` - reader of the encoding of the XfrNote to be verified
+/// * `max_note_size` - max number of bytes read from `reader`, it bounds the memory allocated to decode the note
+/// * `policies` - list of set of policies and associated information corresponding to the xfr_note
+/// * `returns` - the verified XfrNote or an ZeiError in case of decoding or verification error
+pub fn verify_xfr_note_from_bounded_reader<R: CryptoRng + RngCore, Rd: Read>(
+    prng: &mut R,
+    params: &mut PublicParams,
+    reader: &mut Rd,
+    max_note_size: u64,
+    policies: &XfrNotePoliciesRef,
+) -> Result<XfrNote> {
+    let xfr_note: XfrNote = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(max_note_size)
+        .deserialize_from(reader)
+        .c(d!(ZeiError::DeserializationError))?;
+    verify_xfr_note(prng, params, &xfr_note, policies).c(d!())?;
+    Ok(xfr_note)
+}
+
 /// XfrNote Batch verification
 /// * `prng` - pseudo-random number generator
 /// * `xfr_notes` - XfrNote structs to be verified
@@ -506,13 +663,16 @@ pub fn batch_verify_xfr_notes<R: CryptoRng + RngCore>(
     notes: &[&XfrNote],
     policies: &[&XfrNotePoliciesRef],
 ) -> Result<()> {
-    // 1. verify signature
-    for xfr_note in notes {
-        verify_transfer_multisig(xfr_note).c(d!())?;
+    // 0. check the structure of the notes
+    for note in notes {
+        note.validate().c(d!())?;
     }
 
+    // 1. verify signatures
+    batch_verify_transfer_multisigs(notes).c(d!())?;
+
     let bodies = notes.iter().map(|note| &note.body).collect_vec();
-    batch_verify_xfr_bodies(prng, params, &bodies, policies).c(d!())
+    batch_verify_valid_xfr_bodies(prng, params, &bodies, policies).c(d!())
 }
 
 pub(crate) fn batch_verify_xfr_body_asset_records<R: CryptoRng + RngCore>(
@@ -525,16 +685,31 @@ pub(crate) fn batch_verify_xfr_body_asset_records<R: CryptoRng + RngCore>(
     let mut conf_asset_mix_bodies = vec![];
 
     for body in bodies {
+        if body.inputs.len() > MAX_SAFE_XFR_INPUTS
+            || body.outputs.len() > MAX_SAFE_XFR_INPUTS
+        {
+            return Err(eg!(ZeiError::XfrVerifyAssetAmountError));
+        }
         match &body.proofs.asset_type_and_amount_proof {
             AssetTypeAndAmountProof::ConfAll(x) => {
                 let range_proof = &(*x).0;
                 let asset_proof = &(*x).1;
-                conf_amount_records.push((&body.inputs, &body.outputs, range_proof));
+                conf_amount_records.push((
+                    &body.inputs,
+                    &body.outputs,
+                    range_proof,
+                    body.fee,
+                ));
                 conf_asset_type_records.push((&body.inputs, &body.outputs, asset_proof));
                 // save for batching
             }
             AssetTypeAndAmountProof::ConfAmount(range_proof) => {
-                conf_amount_records.push((&body.inputs, &body.outputs, range_proof)); // save for batching
+                conf_amount_records.push((
+                    &body.inputs,
+                    &body.outputs,
+                    range_proof,
+                    body.fee,
+                )); // save for batching
                 verify_plain_asset(body.inputs.as_slice(), body.outputs.as_slice())
                     .c(d!())?; // no batching
             }
@@ -684,12 +859,40 @@ pub fn batch_verify_xfr_bodies<R: CryptoRng + RngCore>(
     params: &mut PublicParams,
     bodies: &[&XfrBody],
     policies: &[&XfrNotePoliciesRef],
+) -> Result<()> {
+    for body in bodies {
+        body.validate().c(d!())?;
+    }
+    batch_verify_valid_xfr_bodies(prng, params, bodies, policies).c(d!())
+}
+
+// verification of bodies that passed XfrBody::validate
+fn batch_verify_valid_xfr_bodies<R: CryptoRng + RngCore>(
+    prng: &mut R,
+    params: &mut PublicParams,
+    bodies: &[&XfrBody],
+    policies: &[&XfrNotePoliciesRef],
 ) -> Result<()> {
     // 1. verify amounts and asset types
     batch_verify_xfr_body_asset_records(prng, params, bodies).c(d!())?;
 
     // 2. verify tracing proofs
-    batch_verify_tracer_tracing_proof(prng, &params.pc_gens, bodies, policies).c(d!())
+    batch_verify_tracer_tracing_proof(prng, &params.pc_gens, bodies, policies)
+        .c(d!())?;
+
+    // 3. verify that the shared tracer memos are encrypted to all their tracers
+    for body in bodies {
+        for (memos, shared_memo) in body
+            .asset_tracing_memos
+            .iter()
+            .zip(body.shared_tracer_memos.iter())
+        {
+            if let Some(shared_memo) = shared_memo {
+                verify_shared_tracer_memo(prng, memos, shared_memo).c(d!())?;
+            }
+        }
+    }
+    Ok(())
 }
 
 /// Takes a vector of u64, converts each element to u128 and compute the sum of the new elements.
@@ -883,21 +1086,53 @@ pub fn find_tracing_memos<'a>(
     xfr_body: &'a XfrBody,
     pub_key: &AssetTracerEncKeys,
 ) -> Result<Vec<(&'a BlindAssetRecord, &'a TracerMemo)>> {
+    Ok(find_tracing_memos_with_shared_memos(xfr_body, pub_key)
+        .c(d!())?
+        .into_iter()
+        .map(|(blind_asset_record, memo, _)| (blind_asset_record, memo))
+        .collect())
+}
+
+/// (BlindAssetRecord, TracerMemo, shared tracer memo of the record and index of the tracer
+/// among its recipients)
+type TracingMemoRef<'a> = (
+    &'a BlindAssetRecord,
+    &'a TracerMemo,
+    Option<(&'a SharedTracerMemo, usize)>,
+);
+
+// find_tracing_memos, also returning the shared tracer memo that the memo is decrypted with
+fn find_tracing_memos_with_shared_memos<'a>(
+    xfr_body: &'a XfrBody,
+    pub_key: &AssetTracerEncKeys,
+) -> Result<Vec<TracingMemoRef<'a>>> {
     let mut result = vec![];
-    if xfr_body.inputs.len() + xfr_body.outputs.len()
-        != xfr_body.asset_tracing_memos.len()
+    let n_records = xfr_body.inputs.len() + xfr_body.outputs.len();
+    if n_records != xfr_body.asset_tracing_memos.len()
+        || (!xfr_body.shared_tracer_memos.is_empty()
+            && n_records != xfr_body.shared_tracer_memos.len())
     {
         return Err(eg!(ZeiError::InconsistentStructureError));
     }
-    for (blind_asset_record, bar_memos) in xfr_body
+    for (i, (blind_asset_record, bar_memos)) in xfr_body
         .inputs
         .iter()
         .chain(&xfr_body.outputs)
         .zip(&xfr_body.asset_tracing_memos)
+        .enumerate()
     {
-        for memo in bar_memos {
+        let shared_memo = xfr_body
+            .shared_tracer_memos
+            .get(i)
+            .and_then(|shared_memo| shared_memo.as_ref());
+        for (position, memo) in bar_memos.iter().enumerate() {
             if memo.enc_key == *pub_key {
-                result.push((blind_asset_record, memo));
+                let shared = match shared_memo {
+                    Some(shared_memo) => shared_tracer_memo_index(bar_memos, position)
+                        .map(|index| (shared_memo, index)),
+                    None => None,
+                };
+                result.push((blind_asset_record, memo, shared));
             }
         }
     }
@@ -915,7 +1150,9 @@ pub fn trace_assets(
     xfr_body: &XfrBody,
     tracer_keypair: &AssetTracerKeyPair,
 ) -> Result<Vec<RecordData>> {
-    let bars_memos = find_tracing_memos(xfr_body, &tracer_keypair.enc_key).c(d!())?;
+    let bars_memos =
+        find_tracing_memos_with_shared_memos(xfr_body, &tracer_keypair.enc_key)
+            .c(d!())?;
     extract_tracing_info(bars_memos.as_slice(), &tracer_keypair.dec_key).c(d!())
 }
 
@@ -939,21 +1176,23 @@ pub fn trace_assets_brute_force(
     .c(d!())
 }
 
-/// Scan list of (BlindAssetRecord, AssetTracerMemo) retrieved by find_tracing_memos
-/// (e.i. intended for the same asset tracer). It takes each AssetTracer memo,
-/// decrypts its lock_info field to retrieve amount, asset type and identity attributed.
+/// Scan list of (BlindAssetRecord, AssetTracerMemo, shared tracer memo) retrieved by
+/// find_tracing_memos_with_shared_memos (e.i. intended for the same asset tracer). It takes each
+/// AssetTracer memo, decrypts its lock_info field, and the shared tracer memo of the record if it
+/// has one, to retrieve amount, asset type and identity attributed.
 /// ElGamal ciphertext are decrypted and verified agains the retrieved data from `memo.lock_info`
 /// Returning ZeiError::BogusAssetTracerMemo in case a TracerMemo decrypts inconsistent information, and
 /// ZeiError::InconsistentStructureError if amount or asset_type cannot be found.
 /// Return Vector of RecordData = (amount, asset_type, identity attributes, public key)
 pub(crate) fn extract_tracing_info(
-    memos: &[(&BlindAssetRecord, &TracerMemo)],
+    memos: &[TracingMemoRef],
     dec_key: &AssetTracerDecKeys,
 ) -> Result<Vec<RecordData>> {
     let mut result = vec![];
-    for (blind_asset_record, memo) in memos {
-        let (amount_option, asset_type_option, attributes) =
-            memo.decrypt(dec_key).c(d!())?; // return BogusAssetTracerMemo in case of error.
+    for (blind_asset_record, memo, shared_memo) in memos {
+        let (amount_option, asset_type_option, attributes) = memo
+            .decrypt_with_shared_memo(dec_key, *shared_memo)
+            .c(d!())?; // return BogusAssetTracerMemo in case of error.
         let amount = match memo.lock_amount {
             None => blind_asset_record
                 .amount
@@ -1,3 +1,5 @@
+pub mod asset_code;
+pub mod asset_flow;
 pub mod asset_mixer;
 pub mod asset_record;
 pub mod asset_tracer;
@@ -265,17 +265,17 @@ pub(crate) fn batch_verify_tracer_tracing_proof<R: CryptoRng + RngCore>(
     // Identity proofs can be batched(?)
     for (xfr_body, policies) in xfr_bodies.iter().zip(instances_policies.iter()) {
         // 2. do identity tracing proof
-        let inputs_len = xfr_body.inputs.len();
+        let (inputs_memos, outputs_memos) = xfr_body.split_tracing_memos().c(d!())?;
         verify_identity_proofs(
             &policies.inputs_tracing_policies,
-            &xfr_body.asset_tracing_memos[..inputs_len],
+            inputs_memos,
             &xfr_body.proofs.asset_tracing_proof.inputs_identity_proofs,
             &policies.inputs_sig_commitments,
         )
         .c(d!())?;
         verify_identity_proofs(
             &policies.outputs_tracing_policies,
-            &xfr_body.asset_tracing_memos[inputs_len..],
+            outputs_memos,
             &xfr_body.proofs.asset_tracing_proof.outputs_identity_proofs,
             &policies.outputs_sig_commitments,
         )
@@ -370,10 +370,10 @@ fn collect_records_memos_by_key<'a>(
     output_reveal_policies: &'a [&TracingPolicies],
 ) -> Result<LinearMap<RecordDataEncKey, BarMemoVec<'a>>> {
     let mut map: LinearMap<RecordDataEncKey, BarMemoVec<'a>> = LinearMap::new();
-    let inputs_len = xfr_body.inputs.len();
+    let (inputs_memos, outputs_memos) = xfr_body.split_tracing_memos().c(d!())?;
     let bars_memo_policies_input = BarMemosPoliciesCollection::new(
         &xfr_body.inputs,
-        &xfr_body.asset_tracing_memos[..inputs_len], // only inputs
+        inputs_memos,
         input_reveal_policies,
     );
     collect_bars_and_memos_by_keys(&mut map, &bars_memo_policies_input)
@@ -381,7 +381,7 @@ fn collect_records_memos_by_key<'a>(
 
     let bars_memo_policies_output = BarMemosPoliciesCollection::new(
         &xfr_body.outputs,
-        &xfr_body.asset_tracing_memos[inputs_len..], // only outputs
+        outputs_memos,
         output_reveal_policies,
     );
     collect_bars_and_memos_by_keys(&mut map, &bars_memo_policies_output)
@@ -507,10 +507,11 @@ fn extract_ciphertext_and_commitments(
 
 /// I compute a range proof for confidential amount transfers.
 /// The proof guarantees that output amounts and difference between total input
-/// and total output are in the range [0,2^{64} - 1]
+/// and total output plus the public `fee` are in the range [0,2^{64} - 1]
 pub(crate) fn range_proof(
     inputs: &[&OpenAssetRecord],
     outputs: &[&OpenAssetRecord],
+    fee: u64,
 ) -> Result<XfrRangeProof> {
     let num_output = outputs.len();
     let upper_power2 =
@@ -522,14 +523,15 @@ pub(crate) fn range_proof(
     let params = PublicParams::default();
 
     //build values vector (out amounts + amount difference)
-    let in_total = inputs.iter().fold(0u64, |accum, x| accum + x.amount);
+    // the totals are summed in 128 bits, they cannot overflow for up to MAX_SAFE_XFR_INPUTS amounts
+    let in_total = inputs.iter().map(|x| u128::from(x.amount)).sum::<u128>();
     let out_amounts: Vec<u64> = outputs.iter().map(|x| x.amount).collect();
-    let out_total = out_amounts.iter().sum::<u64>();
-    let xfr_diff = if in_total >= out_total {
-        in_total - out_total
-    } else {
-        return Err(eg!(ZeiError::RangeProofProveError));
-    };
+    let out_total = out_amounts.iter().map(|x| u128::from(*x)).sum::<u128>();
+    let xfr_diff = in_total
+        .checked_sub(out_total)
+        .and_then(|diff| diff.checked_sub(u128::from(fee)))
+        .and_then(|diff| u64::try_from(diff).ok())
+        .c(d!(ZeiError::RangeProofProveError))?;
     let mut values = Vec::with_capacity(out_amounts.len() + 1);
     for x in out_amounts {
         let (lower, higher) = u64_to_u32_pair(x);
@@ -585,6 +587,8 @@ fn add_blindings(oar: &[&OpenAssetRecord]) -> (Scalar, Scalar) {
     )
 }
 
+/// I verify the range proofs of confidential amount transfers, each instance being the inputs,
+/// the outputs, the range proof and the public fee of a transfer
 pub(crate) fn batch_verify_confidential_amount<R: CryptoRng + RngCore>(
     prng: &mut R,
     params: &PublicParams,
@@ -592,15 +596,18 @@ pub(crate) fn batch_verify_confidential_amount<R: CryptoRng + RngCore>(
         &Vec<BlindAssetRecord>,
         &Vec<BlindAssetRecord>,
         &XfrRangeProof,
+        u64,
     )],
 ) -> Result<()> {
     let mut transcripts = vec![Transcript::new(b"Zei Range Proof"); instances.len()];
-    let proofs: Vec<&RangeProof> =
-        instances.iter().map(|(_, _, pf)| &pf.range_proof).collect();
+    let proofs: Vec<&RangeProof> = instances
+        .iter()
+        .map(|(_, _, pf, _)| &pf.range_proof)
+        .collect();
     let mut commitments = vec![];
-    for (input, output, proof) in instances {
+    for (input, output, proof, fee) in instances {
         commitments.push(
-            extract_value_commitments(input.as_slice(), output.as_slice(), proof)
+            extract_value_commitments(input.as_slice(), output.as_slice(), proof, *fee)
                 .c(d!())?,
         );
     }
@@ -621,6 +628,7 @@ fn extract_value_commitments(
     inputs: &[BlindAssetRecord],
     outputs: &[BlindAssetRecord],
     proof: &XfrRangeProof,
+    fee: u64,
 ) -> Result<Vec<CompressedRistretto>> {
     let num_output = outputs.len();
     let upper_power2 =
@@ -678,12 +686,17 @@ fn extract_value_commitments(
         //output_com.push(com_low + com_high * Scalar::from(0xFFFFFFFF as u64 + 1));
     }
 
-    // 3. derive input - output commitment, compare with proof struct low anc high commitments
-    let derived_xfr_diff_com = total_input_com_low.sub(&total_output_com_low).add(
-        &total_input_com_high
-            .sub(&total_output_com_high)
-            .mul(&pow2_32),
-    );
+    // 3. derive input - output - fee commitment, compare with proof struct low anc high commitments
+    let fee_com = RistrettoPedersenGens::default()
+        .commit(Scalar::from_u64(fee), Scalar::from_u32(0));
+    let derived_xfr_diff_com = total_input_com_low
+        .sub(&total_output_com_low)
+        .add(
+            &total_input_com_high
+                .sub(&total_output_com_high)
+                .mul(&pow2_32),
+        )
+        .sub(&fee_com);
     let proof_xfr_com_low = proof
         .xfr_diff_commitment_low
         .decompress()
@@ -2,6 +2,7 @@ use algebra::{
     groups::Scalar as _,
     ristretto::{CompressedEdwardsY, RistrettoScalar as Scalar},
 };
+use curve25519_dalek::edwards::CompressedEdwardsY as CEY;
 use ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey, Signature, Verifier};
 use itertools::Itertools;
 use rand_core::{CryptoRng, RngCore};
@@ -68,6 +69,21 @@ impl XfrPublicKey {
             .c(d!(ZeiError::SignatureError))
     }
 
+    /// Verify `signature` on `message` with the rule of the notes of version 1 and of
+    /// batch_verify_signatures: on top of the checks of `verify`, the public key and the R value
+    /// of the signature must be canonical encodings of prime order points, that is with no
+    /// torsion component
+    pub fn verify_strict(&self, message: &[u8], signature: &XfrSignature) -> Result<()> {
+        if !is_torsion_free(self.as_bytes())
+            || !is_torsion_free(&signature.0.to_bytes()[..32])
+        {
+            return Err(eg!(ZeiError::SignatureError));
+        }
+        self.0
+            .verify_strict(message, &signature.0)
+            .c(d!(ZeiError::SignatureError))
+    }
+
     pub fn as_bytes(&self) -> &[u8] {
         self.0.as_bytes()
     }
@@ -206,26 +222,159 @@ impl XfrMultiSig {
 
     /// Verify a multisig
     pub fn verify(&self, pubkeys: &[&XfrPublicKey], message: &[u8]) -> Result<()> {
+        for (pk, sig) in self.signed_pairs(pubkeys).c(d!())? {
+            pk.verify(&message, &sig).c(d!())?;
+        }
+        Ok(())
+    }
+
+    /// Verify a multisig, each signature with XfrPublicKey::verify_strict
+    pub fn verify_strict(
+        &self,
+        pubkeys: &[&XfrPublicKey],
+        message: &[u8],
+    ) -> Result<()> {
+        for (pk, sig) in self.signed_pairs(pubkeys).c(d!())? {
+            pk.verify_strict(&message, &sig).c(d!())?;
+        }
+        Ok(())
+    }
+
+    /// Return the public keys sorted as in the multisig, each with its signature
+    pub(crate) fn signed_pairs<'a>(
+        &'a self,
+        pubkeys: &[&'a XfrPublicKey],
+    ) -> Result<Vec<(&'a XfrPublicKey, &'a XfrSignature)>> {
         if pubkeys.len() != self.signatures.len() {
             return Err(eg!(ZeiError::SignatureError));
         }
         // sort the key pairs based on alphabetical order of their public keys
         let mut sorted = pubkeys.to_owned();
         sorted.sort_unstable_by_key(|k| k.zei_to_bytes());
-        for (pk, sig) in sorted.iter().zip(self.signatures.iter()) {
-            pk.verify(&message, &sig).c(d!())?;
+        Ok(sorted.into_iter().zip(self.signatures.iter()).collect_vec())
+    }
+
+    /// Batch verify a list of multisigs, `multisigs[i]` being a multisig under `pubkeys[i]`
+    /// on `messages[i]`, verified with `verify_strict` if `strict[i]` and with `verify`
+    /// otherwise. The signatures whose public key and R value are torsion free are checked
+    /// with a single ed25519 batch verification; the other ones are rejected under the strict
+    /// rule and verified alone under the legacy rule, as the batch equation would not match
+    /// `verify` on them (see batch_verify_signatures). If the batch fails, each multisig is
+    /// verified alone and the error of the first invalid one is returned, so that the outcome
+    /// is always the one of verifying each multisig with its rule.
+    pub fn batch_verify(
+        multisigs: &[&XfrMultiSig],
+        pubkeys: &[&[&XfrPublicKey]],
+        messages: &[&[u8]],
+        strict: &[bool],
+    ) -> Result<()> {
+        if multisigs.len() != pubkeys.len()
+            || multisigs.len() != messages.len()
+            || multisigs.len() != strict.len()
+        {
+            return Err(eg!(ZeiError::ParameterError));
         }
-        Ok(())
+        let mut batch_pubkeys = vec![];
+        let mut batch_messages = vec![];
+        let mut batch_signatures = vec![];
+        for (((multisig, keys), message), strict) in multisigs
+            .iter()
+            .zip(pubkeys.iter())
+            .zip(messages.iter())
+            .zip(strict.iter())
+        {
+            for (pk, sig) in multisig.signed_pairs(keys).c(d!())? {
+                if is_torsion_free(pk.as_bytes())
+                    && is_torsion_free(&sig.0.to_bytes()[..32])
+                {
+                    batch_pubkeys.push(pk);
+                    batch_messages.push(*message);
+                    batch_signatures.push(sig);
+                } else if *strict {
+                    return Err(eg!(ZeiError::SignatureError));
+                } else {
+                    pk.verify(message, sig).c(d!())?;
+                }
+            }
+        }
+        if batch_verify_signatures(&batch_pubkeys, &batch_messages, &batch_signatures)
+            .is_ok()
+        {
+            return Ok(());
+        }
+        // failure isolation
+        for (((multisig, keys), message), strict) in multisigs
+            .iter()
+            .zip(pubkeys.iter())
+            .zip(messages.iter())
+            .zip(strict.iter())
+        {
+            if *strict {
+                multisig.verify_strict(keys, message).c(d!())?;
+            } else {
+                multisig.verify(keys, message).c(d!())?;
+            }
+        }
+        Err(eg!(ZeiError::SignatureError))
+    }
+}
+
+/// Batch verify a list of signatures, `signatures[i]` being a signature under `pubkeys[i]` on
+/// `messages[i]`, with the outcome of XfrPublicKey::verify_strict on each signature. The batch
+/// equation does not multiply by the cofactor, so a torsion component in a public key or in a
+/// R value can vanish in the random linear combination while the single verification of the
+/// signature fails. Every public key and R value are hence required to be torsion free before
+/// batching.
+pub fn batch_verify_signatures(
+    pubkeys: &[&XfrPublicKey],
+    messages: &[&[u8]],
+    signatures: &[&XfrSignature],
+) -> Result<()> {
+    if pubkeys.len() != messages.len() || pubkeys.len() != signatures.len() {
+        return Err(eg!(ZeiError::ParameterError));
+    }
+    if pubkeys.is_empty() {
+        return Ok(());
+    }
+    for (pk, sig) in pubkeys.iter().zip(signatures.iter()) {
+        let sig_bytes = sig.0.to_bytes();
+        if !is_torsion_free(pk.as_bytes()) || !is_torsion_free(&sig_bytes[..32]) {
+            return Err(eg!(ZeiError::SignatureError));
+        }
+    }
+    let keys = pubkeys.iter().map(|pk| pk.0).collect_vec();
+    let sigs = signatures.iter().map(|sig| sig.0).collect_vec();
+    ed25519_dalek::verify_batch(messages, &sigs, &keys).c(d!(ZeiError::SignatureError))
+}
+
+/// Return true if `bytes` is the canonical encoding of a prime order edwards point, that is a
+/// point that is not of small order and has no torsion component
+fn is_torsion_free(bytes: &[u8]) -> bool {
+    match CEY::from_slice(bytes).decompress() {
+        Some(point) => {
+            !point.is_small_order()
+                && point.is_torsion_free()
+                && point.compress().as_bytes() == bytes
+        }
+        None => false,
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::xfr::sig::{XfrKeyPair, XfrMultiSig};
+    use crate::xfr::sig::{
+        batch_verify_signatures, XfrKeyPair, XfrMultiSig, XfrPublicKey, XfrSignature,
+    };
+    use curve25519_dalek::{
+        constants::{ED25519_BASEPOINT_TABLE, EIGHT_TORSION},
+        scalar::Scalar,
+    };
+    use ed25519_dalek::{PublicKey, Signature, Verifier};
     use itertools::Itertools;
     use rand_chacha::ChaChaRng;
     use rand_core::SeedableRng;
     use ruc::err::*;
+    use sha2::{Digest, Sha512};
     use utils::errors::ZeiError::SignatureError;
 
     #[test]
@@ -320,4 +469,208 @@ mod test {
             "Multisignature should have verify correctly even when keylist is unordered"
         );
     }
+
+    #[test]
+    fn batch_multisig() {
+        let mut prng = rand_chacha::ChaChaRng::from_seed([2u8; 32]);
+        let msgs = [b"first note".to_vec(), b"second note".to_vec()];
+        let keypairs = [
+            generate_keypairs(&mut prng, 3),
+            generate_keypairs(&mut prng, 1),
+        ];
+        let pubkeys = keypairs
+            .iter()
+            .map(|kps| kps.iter().map(|kp| &kp.pub_key).collect_vec())
+            .collect_vec();
+        let pubkeys_ref = pubkeys.iter().map(|pks| pks.as_slice()).collect_vec();
+        let msgs_ref = msgs.iter().map(|m| m.as_slice()).collect_vec();
+        let multisigs = keypairs
+            .iter()
+            .zip(msgs.iter())
+            .map(|(kps, m)| XfrMultiSig::sign(&kps.iter().collect_vec(), m))
+            .collect_vec();
+        let multisigs_ref = multisigs.iter().collect_vec();
+        for strict in [[false, false], [true, true], [true, false]] {
+            pnk!(XfrMultiSig::batch_verify(
+                &multisigs_ref,
+                &pubkeys_ref,
+                &msgs_ref,
+                &strict
+            ));
+        }
+
+        // a single invalid signature makes the batch fail
+        let mut bad_multisig = multisigs[0].clone();
+        bad_multisig.signatures[1] = keypairs[0][0].sign(b"another message");
+        let bad_multisigs_ref = vec![&bad_multisig, &multisigs[1]];
+        msg_eq!(
+            SignatureError,
+            XfrMultiSig::batch_verify(
+                &bad_multisigs_ref,
+                &pubkeys_ref,
+                &msgs_ref,
+                &[false, false]
+            )
+            .unwrap_err()
+        );
+        assert!(XfrMultiSig::batch_verify(
+            &multisigs_ref,
+            &pubkeys_ref,
+            &[msgs_ref[1], msgs_ref[0]],
+            &[false, false]
+        )
+        .is_err());
+        assert!(XfrMultiSig::batch_verify(
+            &multisigs_ref[..1],
+            &pubkeys_ref,
+            &msgs_ref,
+            &[false, false]
+        )
+        .is_err());
+        assert!(XfrMultiSig::batch_verify(
+            &multisigs_ref,
+            &pubkeys_ref,
+            &msgs_ref,
+            &[false]
+        )
+        .is_err());
+        pnk!(XfrMultiSig::batch_verify(&[], &[], &[], &[]));
+    }
+
+    #[test]
+    fn batch_verify_rejects_torsion_components() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let message = b"torsion";
+        // the public key A = [a]B + T has a torsion component T of order 2, the signature
+        // (R, s) with R = [r]B and s = r + k * a satisfies [s]B = R + [k](A - T)
+        let a = Scalar::random(&mut prng);
+        let torsion = EIGHT_TORSION[4];
+        let point_a = &a * &ED25519_BASEPOINT_TABLE + torsion;
+        let pub_key =
+            XfrPublicKey(pnk!(PublicKey::from_bytes(point_a.compress().as_bytes())));
+        let mut found = None;
+        for _ in 0..64 {
+            let r = Scalar::random(&mut prng);
+            let point_r = (&r * &ED25519_BASEPOINT_TABLE).compress();
+            let mut hasher = Sha512::new();
+            hasher.update(point_r.as_bytes());
+            hasher.update(pub_key.as_bytes());
+            hasher.update(message);
+            let k = Scalar::from_hash(hasher);
+            // [k]T vanishes when k is even, only odd challenges give an invalid signature
+            if k.to_bytes()[0] & 1 == 0 {
+                continue;
+            }
+            let s = r + k * a;
+            let mut sig_bytes = [0u8; 64];
+            sig_bytes[..32].copy_from_slice(point_r.as_bytes());
+            sig_bytes[32..].copy_from_slice(s.as_bytes());
+            let sig = XfrSignature(pnk!(Signature::from_bytes(&sig_bytes)));
+            // keep a signature accepted by the raw ed25519 batch equation
+            if ed25519_dalek::verify_batch(&[&message[..]], &[sig.0], &[pub_key.0])
+                .is_ok()
+            {
+                found = Some(sig);
+                break;
+            }
+        }
+        let sig = found.unwrap();
+
+        // the single verifications reject the signature, and so do the batches
+        assert!(pub_key.verify(message, &sig).is_err());
+        assert!(pub_key.verify_strict(message, &sig).is_err());
+        msg_eq!(
+            SignatureError,
+            batch_verify_signatures(&[&pub_key], &[&message[..]], &[&sig]).unwrap_err()
+        );
+        let multisig = XfrMultiSig {
+            signatures: vec![sig],
+        };
+        let keys = [&pub_key];
+        assert!(multisig.verify(&keys, message).is_err());
+        for strict in [false, true] {
+            assert!(XfrMultiSig::batch_verify(
+                &[&multisig],
+                &[&keys[..]],
+                &[&message[..]],
+                &[strict]
+            )
+            .is_err());
+        }
+
+        // a small order R is rejected before batching as well
+        let keypair = XfrKeyPair::generate(&mut prng);
+        let mut sig_bytes = keypair.sign(message).0.to_bytes();
+        sig_bytes[..32].copy_from_slice(EIGHT_TORSION[1].compress().as_bytes());
+        let small_order_sig = XfrSignature(pnk!(Signature::from_bytes(&sig_bytes)));
+        assert!(batch_verify_signatures(
+            &[&keypair.pub_key],
+            &[&message[..]],
+            &[&small_order_sig]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn batch_verify_keeps_legacy_acceptance() {
+        let mut prng = ChaChaRng::from_seed([3u8; 32]);
+        let message = b"legacy";
+        // with the public key A = [a]B + T for a torsion point T of order 2, the signature
+        // (R, s) with R = [r]B and s = r + k * a satisfies [s]B = R + [k]A when the challenge
+        // k is even: the legacy rule accepts it and the strict rule rejects it
+        let a = Scalar::random(&mut prng);
+        let point_a = &a * &ED25519_BASEPOINT_TABLE + EIGHT_TORSION[4];
+        let pub_key =
+            XfrPublicKey(pnk!(PublicKey::from_bytes(point_a.compress().as_bytes())));
+        let mut found = None;
+        for _ in 0..64 {
+            let r = Scalar::random(&mut prng);
+            let point_r = (&r * &ED25519_BASEPOINT_TABLE).compress();
+            let mut hasher = Sha512::new();
+            hasher.update(point_r.as_bytes());
+            hasher.update(pub_key.as_bytes());
+            hasher.update(message);
+            let k = Scalar::from_hash(hasher);
+            if k.to_bytes()[0] & 1 == 1 {
+                continue;
+            }
+            let s = r + k * a;
+            let mut sig_bytes = [0u8; 64];
+            sig_bytes[..32].copy_from_slice(point_r.as_bytes());
+            sig_bytes[32..].copy_from_slice(s.as_bytes());
+            found = Some(XfrSignature(pnk!(Signature::from_bytes(&sig_bytes))));
+            break;
+        }
+        let sig = found.unwrap();
+        pnk!(pub_key.verify(message, &sig));
+        assert!(pub_key.0.verify(message, &sig.0).is_ok());
+        assert!(pub_key.verify_strict(message, &sig).is_err());
+        assert!(batch_verify_signatures(&[&pub_key], &[&message[..]], &[&sig]).is_err());
+
+        // the batch verification of the multisigs keeps the legacy acceptance, together with
+        // the signatures that are batched
+        let multisig = XfrMultiSig {
+            signatures: vec![sig],
+        };
+        let keys = [&pub_key];
+        pnk!(multisig.verify(&keys, message));
+        let keypairs = generate_keypairs(&mut prng, 2);
+        let other_keys = keypairs.iter().map(|kp| &kp.pub_key).collect_vec();
+        let other_multisig =
+            XfrMultiSig::sign(&keypairs.iter().collect_vec(), b"other message");
+        let multisigs = [&multisig, &other_multisig];
+        let pubkeys = [&keys[..], &other_keys[..]];
+        let messages = [&message[..], &b"other message"[..]];
+        pnk!(XfrMultiSig::batch_verify(
+            &multisigs,
+            &pubkeys,
+            &messages,
+            &[false, true]
+        ));
+        msg_eq!(
+            SignatureError,
+            XfrMultiSig::batch_verify(&multisigs, &pubkeys, &messages, &[true, true])
+                .unwrap_err()
+        );
+    }
 }
@@ -4,6 +4,7 @@ use crate::api::anon_creds::{
     ACConfidentialRevealProof, ACIssuerPublicKey, AttributeCiphertext, AttributeDecKey,
     AttributeEncKey,
 };
+use crate::setup::MAX_SAFE_XFR_INPUTS;
 use crate::xfr::asset_mixer::AssetMixProof;
 use crate::xfr::asset_record::AssetRecordType;
 use crate::xfr::asset_tracer::{
@@ -19,14 +20,20 @@ use bulletproofs::RangeProof;
 use crypto::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
 use crypto::basics::elgamal::elgamal_key_gen;
 use crypto::basics::hybrid_encryption::{self, XPublicKey, XSecretKey, ZeiHybridCipher};
+use crypto::basics::multi_recipient_encryption::MultiRecipientCiphertext;
 use crypto::chaum_pedersen::ChaumPedersenProofX;
 use crypto::pedersen_elgamal::PedersenElGamalEqProof;
 use digest::Digest;
 use rand_core::{CryptoRng, RngCore};
 use ruc::*;
+use serde::ser::SerializeTuple;
 use sha2::Sha512;
 use utils::errors::ZeiError;
-use utils::serialization;
+use utils::serialization::versioned_serde::{
+    next_element, next_versioned_head, VersionedHead, VERSIONED_ENCODING_MAX_LEN,
+    VERSIONED_ENCODING_TAG,
+};
+use utils::serialization::{self, Validate};
 
 /// Asset Type identifier
 pub const ASSET_TYPE_LENGTH: usize = 32;
@@ -52,6 +59,30 @@ impl AssetType {
         Self([byte; ASSET_TYPE_LENGTH])
     }
 
+    /// Derives the code of an asset type defined by `issuer` from a `nonce` chosen by the
+    /// issuer. Distinct nonces give unrelated codes, from which the issuer cannot be recovered.
+    /// # Example
+    /// ```
+    /// use rand_chacha::ChaChaRng;
+    /// use rand_core::SeedableRng;
+    /// use zei::xfr::sig::XfrKeyPair;
+    /// use zei::xfr::structs::AssetType;
+    /// let mut prng = ChaChaRng::from_seed([0u8; 32]);
+    /// let issuer = XfrKeyPair::generate(&mut prng).pub_key;
+    /// let code = AssetType::derive(&issuer, b"nonce");
+    /// assert_eq!(code, AssetType::derive(&issuer, b"nonce"));
+    /// assert_ne!(code, AssetType::derive(&issuer, b"other nonce"));
+    /// ```
+    pub fn derive(issuer: &XfrPublicKey, nonce: &[u8]) -> Self {
+        let mut hash = sha2::Sha256::default();
+        hash.update(b"Zei asset type code");
+        hash.update(issuer.as_bytes());
+        hash.update(nonce);
+        let mut code = [0u8; ASSET_TYPE_LENGTH];
+        code.copy_from_slice(&hash.finalize());
+        Self(code)
+    }
+
     /// converts AssetType into a Scalar
     pub fn as_scalar<S: ZeiScalar>(&self) -> S {
         let repr = AssetTypeZeiRepr::from(self);
@@ -98,11 +129,147 @@ impl AssetTypeZeiRepr {
         S::from_le_bytes(&v).unwrap()
     }
 }
+/// Version of the transfer notes created by gen_xfr_note. Version 0 stands for the notes
+/// serialized before the version was recorded, which decode with version 0, see
+/// utils::serialization::versioned_serde, and whose multisignature only signs the body and is
+/// verified with XfrPublicKey::verify. The multisignature of a note of version 1 signs the
+/// version, the body and its fee, so that the version cannot be changed, and is verified with
+/// XfrPublicKey::verify_strict. The notes of version 0 have no fee. The notes of version 2 may
+/// also have shared tracer memos, which their multisignature signs too.
+pub const XFR_NOTE_VERSION: u32 = 2;
+
+/// Versions of the transfer notes that the verifier accepts
+pub const XFR_NOTE_SUPPORTED_VERSIONS: &[u32] = &[0, 1, 2];
+
 /// A Transfer note: contains a transfer body and a (multi)signature
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+/// The binary encoding starts with the version, see utils::serialization::versioned_serde, and
+/// ends with the fee of the body, after the fields of the legacy encoding, then with the shared
+/// tracer memos of the body for the notes of version 2
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct XfrNote {
     pub body: XfrBody,
     pub multisig: XfrMultiSig,
+    pub version: u32,
+}
+
+// Encoding of the fields by name, for self-describing encodings
+#[derive(Serialize)]
+struct XfrNoteFieldsRef<'a> {
+    body: &'a XfrBody,
+    multisig: &'a XfrMultiSig,
+    version: u32,
+    fee: u64,
+    shared_tracer_memos: &'a Vec<Option<SharedTracerMemo>>,
+}
+
+#[derive(Deserialize)]
+struct XfrNoteFields {
+    body: XfrBody,
+    multisig: XfrMultiSig,
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    fee: u64,
+    #[serde(default)]
+    shared_tracer_memos: Vec<Option<SharedTracerMemo>>,
+}
+
+impl serde::Serialize for XfrNote {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if serializer.is_human_readable() {
+            return serde::Serialize::serialize(
+                &XfrNoteFieldsRef {
+                    body: &self.body,
+                    multisig: &self.multisig,
+                    version: self.version,
+                    fee: self.body.fee,
+                    shared_tracer_memos: &self.body.shared_tracer_memos,
+                },
+                serializer,
+            );
+        }
+        let with_shared_memos = self.version >= 2;
+        let mut tuple = serializer.serialize_tuple(5 + with_shared_memos as usize)?;
+        tuple.serialize_element(&VERSIONED_ENCODING_TAG)?;
+        tuple.serialize_element(&self.version)?;
+        tuple.serialize_element(&self.body)?;
+        tuple.serialize_element(&self.multisig)?;
+        tuple.serialize_element(&self.body.fee)?;
+        if with_shared_memos {
+            tuple.serialize_element(&self.body.shared_tracer_memos)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for XfrNote {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let fields: XfrNoteFields = serde::Deserialize::deserialize(deserializer)?;
+            let mut body = fields.body;
+            body.fee = fields.fee;
+            body.shared_tracer_memos = fields.shared_tracer_memos;
+            return Ok(XfrNote {
+                body,
+                multisig: fields.multisig,
+                version: fields.version,
+            });
+        }
+        deserializer.deserialize_tuple(VERSIONED_ENCODING_MAX_LEN, XfrNoteVisitor)
+    }
+}
+
+struct XfrNoteVisitor;
+
+impl<'de> serde::de::Visitor<'de> for XfrNoteVisitor {
+    type Value = XfrNote;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a transfer note")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<XfrNote, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        // the encoding of a legacy note starts with the inputs of its body, and ends with its
+        // multisignature, with no fee
+        let (version, mut body, legacy) = match next_versioned_head(&mut seq)? {
+            VersionedHead::Versioned(version) => {
+                (version, next_element(&mut seq)?, false)
+            }
+            VersionedHead::Legacy(inputs) => {
+                let body = XfrBody {
+                    inputs,
+                    outputs: next_element(&mut seq)?,
+                    proofs: next_element(&mut seq)?,
+                    asset_tracing_memos: next_element(&mut seq)?,
+                    owners_memos: next_element(&mut seq)?,
+                    fee: 0,
+                    shared_tracer_memos: vec![],
+                };
+                (0, body, true)
+            }
+        };
+        let multisig = next_element(&mut seq)?;
+        if !legacy {
+            body.fee = next_element(&mut seq)?;
+        }
+        if version >= 2 {
+            body.shared_tracer_memos = next_element(&mut seq)?;
+        }
+        Ok(XfrNote {
+            body,
+            multisig,
+            version,
+        })
+    }
 }
 
 impl XfrNote {
@@ -111,6 +278,27 @@ impl XfrNote {
     }
 }
 
+impl Validate for XfrNote {
+    /// Checks the version, the body, that a note of version 0 has no fee, that only a note of
+    /// version 2 has shared tracer memos and that the multisignature has one signature for each
+    /// input
+    fn validate(&self) -> Result<()> {
+        if !XFR_NOTE_SUPPORTED_VERSIONS.contains(&self.version) {
+            return Err(eg!(ZeiError::UnsupportedVersionError));
+        }
+        self.body.validate().c(d!())?;
+        if (self.version == 0 && self.body.fee != 0)
+            || (self.version < 2 && !self.body.shared_tracer_memos.is_empty())
+        {
+            return Err(eg!(ZeiError::InconsistentStructureError));
+        }
+        if self.multisig.signatures.len() != self.body.inputs.len() {
+            return Err(eg!(ZeiError::InconsistentStructureError));
+        }
+        Ok(())
+    }
+}
+
 /// A Transfer's body: contains a inputs, outputs, proofs and messages to participants (asset tracer and output owners)
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct XfrBody {
@@ -119,6 +307,86 @@ pub struct XfrBody {
     pub proofs: XfrProofs,
     pub asset_tracing_memos: Vec<Vec<TracerMemo>>, // each input or output can have a set of tracing memos
     pub owners_memos: Vec<Option<OwnerMemo>>, // If confidential amount or asset type, lock the amount and/or asset type to the public key in asset_record
+    // Public fee deducted from the confidential input amounts, 0 if the transfer pays no fee this
+    // way. It is not part of the encoding of the body, which is the legacy one: XfrNote encodes
+    // it after its legacy fields, and the multisignature of the notes of version 1 signs it.
+    #[serde(skip)]
+    pub fee: u64,
+    // Shared tracer memos of the inputs and outputs, in the order of asset_tracing_memos, or
+    // empty if no record has one. As the fee, they are not part of the encoding of the body:
+    // the notes of version 2 encode them after the fee, and their multisignature signs them.
+    #[serde(skip)]
+    pub shared_tracer_memos: Vec<Option<SharedTracerMemo>>,
+}
+
+impl XfrBody {
+    /// Returns the tracer memos of the inputs and the tracer memos of the outputs
+    pub fn split_tracing_memos(
+        &self,
+    ) -> Result<(&[Vec<TracerMemo>], &[Vec<TracerMemo>])> {
+        if self.asset_tracing_memos.len() != self.inputs.len() + self.outputs.len() {
+            return Err(eg!(ZeiError::InconsistentStructureError));
+        }
+        Ok(self.asset_tracing_memos.split_at(self.inputs.len()))
+    }
+}
+
+impl Validate for XfrBody {
+    /// Checks the number of records, that each record has its tracer memos and identity
+    /// proofs, that each output has an owner memo, that a fee comes with a range proof, that a
+    /// shared tracer memo has a recipient for each tracer memo of its record that encrypts the
+    /// amount or the asset type, and that the commitments of the records are valid points
+    fn validate(&self) -> Result<()> {
+        if self.inputs.is_empty()
+            || self.inputs.len() > MAX_SAFE_XFR_INPUTS
+            || self.outputs.len() > MAX_SAFE_XFR_INPUTS
+        {
+            return Err(eg!(ZeiError::InconsistentStructureError));
+        }
+        if self.fee != 0
+            && !matches!(
+                self.proofs.asset_type_and_amount_proof,
+                AssetTypeAndAmountProof::ConfAmount(_)
+                    | AssetTypeAndAmountProof::ConfAll(_)
+            )
+        {
+            return Err(eg!(ZeiError::InconsistentStructureError));
+        }
+        let tracing_proofs = &self.proofs.asset_tracing_proof;
+        if self.asset_tracing_memos.len() != self.inputs.len() + self.outputs.len()
+            || self.owners_memos.len() != self.outputs.len()
+            || tracing_proofs.inputs_identity_proofs.len() != self.inputs.len()
+            || tracing_proofs.outputs_identity_proofs.len() != self.outputs.len()
+        {
+            return Err(eg!(ZeiError::InconsistentStructureError));
+        }
+        if !self.shared_tracer_memos.is_empty() {
+            if self.shared_tracer_memos.len() != self.asset_tracing_memos.len() {
+                return Err(eg!(ZeiError::InconsistentStructureError));
+            }
+            for (memos, shared_memo) in self
+                .asset_tracing_memos
+                .iter()
+                .zip(self.shared_tracer_memos.iter())
+            {
+                if let Some(shared_memo) = shared_memo {
+                    let n_recipients = memos
+                        .iter()
+                        .filter(|m| {
+                            m.lock_amount.is_some() || m.lock_asset_type.is_some()
+                        })
+                        .count();
+                    if n_recipients < 2 || n_recipients != shared_memo.num_recipients() {
+                        return Err(eg!(ZeiError::InconsistentStructureError));
+                    }
+                }
+            }
+        }
+        for record in self.inputs.iter().chain(self.outputs.iter()) {
+            record.validate().c(d!())?;
+        }
+        Ok(())
+    }
 }
 
 /// A transfer input or output record as seen in the ledger
@@ -148,6 +416,22 @@ impl BlindAssetRecord {
     }
 }
 
+impl Validate for BlindAssetRecord {
+    /// Checks that the amount and asset type commitments are valid Ristretto points
+    fn validate(&self) -> Result<()> {
+        if let XfrAmount::Confidential((low, high)) = &self.amount {
+            low.decompress().c(d!(ZeiError::DecompressElementError))?;
+            high.decompress().c(d!(ZeiError::DecompressElementError))?;
+        }
+        if let XfrAssetType::Confidential(commitment) = &self.asset_type {
+            commitment
+                .decompress()
+                .c(d!(ZeiError::DecompressElementError))?;
+        }
+        Ok(())
+    }
+}
+
 /// Amount in blind asset record: if confidential, provide commitments for lower and hight 32 bits
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum XfrAmount {
@@ -398,9 +682,14 @@ pub struct TracerMemo {
     pub lock_asset_type: Option<RecordDataCiphertext>,
     pub lock_attributes: Vec<AttributeCiphertext>,
     /// A hybrid encryption of amount, asset type and attributes encrypted above for faster access
+    /// The amount and asset type are in the SharedTracerMemo of the record instead if it has one
     pub lock_info: ZeiHybridCipher,
 }
 
+/// Encryption of the amount and asset type of a record to all the tracers of its TracerMemos that
+/// encrypt them, with a proof that they all decrypt the same data, see gen_tracer_memos
+pub type SharedTracerMemo = MultiRecipientCiphertext<RistrettoPoint>;
+
 /// Information directed to secret key holder of a BlindAssetRecord
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct OwnerMemo {
@@ -645,6 +934,8 @@ pub struct AssetRecord {
     pub identity_proofs: Vec<Option<ACConfidentialRevealProof>>,
     pub asset_tracers_memos: Vec<TracerMemo>,
     pub owner_memo: Option<OwnerMemo>,
+    #[serde(default)]
+    pub shared_tracer_memo: Option<SharedTracerMemo>, // see gen_tracer_memos
 }
 
 /// An asset record template: amount, asset type, owner public key, type and tracing
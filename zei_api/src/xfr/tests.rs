@@ -4,8 +4,8 @@ use crate::setup::PublicParams;
 use crate::xfr::asset_record::AssetRecordType;
 use crate::xfr::lib::{
     batch_verify_xfr_body_asset_records, batch_verify_xfr_notes,
-    compute_transfer_multisig, gen_xfr_note, verify_xfr_body, verify_xfr_note,
-    XfrNotePolicies,
+    compute_transfer_multisig, gen_xfr_note, gen_xfr_note_with_fee, verify_xfr_body,
+    verify_xfr_note, XfrNotePolicies,
 };
 use crate::xfr::sig::XfrKeyPair;
 use crate::xfr::structs::{
@@ -347,6 +347,39 @@ mod single_asset_no_tracing {
         do_transfer_tests_single_asset(&mut params, &inputs_template, &outputs_template);
     }
 
+    #[test]
+    fn test_transfer_large_amounts() {
+        /*! Test confidential amounts whose totals do not fit in 64 bits*/
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let mut params = PublicParams::from_file_if_exists(DEFAULT_BP_NUM_GENS, None);
+        let asset_type = AssetType::from_identical_byte(0u8);
+        let record_type = AssetRecordType::ConfidentialAmount_NonConfidentialAssetType;
+        let keys = gen_key_pair_vec(5, &mut prng);
+        let template = |amount: u64, key: &XfrKeyPair| {
+            AssetRecordTemplate::with_no_asset_tracing(
+                amount,
+                asset_type,
+                record_type,
+                key.pub_key,
+            )
+        };
+        let inputs = [template(u64::MAX, &keys[0]), template(u64::MAX, &keys[1])];
+        let outputs = [
+            template(u64::MAX, &keys[2]),
+            template(u64::MAX - 1, &keys[3]),
+            template(1, &keys[4]),
+        ];
+        let inkeys = keys[..2].iter().collect_vec();
+        let (xfr_note, _, _) = create_xfr(&mut prng, &inputs, &outputs, &inkeys);
+        let policies = XfrNotePolicies::empty_policies(2, 3);
+        pnk!(verify_xfr_note(
+            &mut prng,
+            &mut params,
+            &xfr_note,
+            &policies.to_ref()
+        ));
+    }
+
     #[test]
     fn test_transfer_confidential_asset_plain_amount() {
         /*! Test confidential asset types in all inputs and all outputs transfers*/
@@ -502,6 +535,113 @@ mod single_asset_no_tracing {
     }
 }
 
+mod fee {
+
+    use super::*;
+    use crate::setup::DEFAULT_BP_NUM_GENS;
+
+    #[test]
+    fn test_transfer_confidential_fee() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let mut params = PublicParams::from_file_if_exists(DEFAULT_BP_NUM_GENS, None);
+        let asset_type = AssetType::from_identical_byte(0u8);
+        let keys = gen_key_pair_vec(4, &mut prng);
+        let inkeys = keys[..2].iter().collect_vec();
+        let record = |prng: &mut ChaChaRng,
+                      amount: u64,
+                      record_type: AssetRecordType,
+                      key: &XfrKeyPair| {
+            let template = AssetRecordTemplate::with_no_asset_tracing(
+                amount,
+                asset_type,
+                record_type,
+                key.pub_key,
+            );
+            AssetRecord::from_template_no_identity_tracing(prng, &template).unwrap()
+        };
+
+        for record_type in [
+            AssetRecordType::ConfidentialAmount_NonConfidentialAssetType,
+            AssetRecordType::ConfidentialAmount_ConfidentialAssetType,
+        ] {
+            let inputs = [
+                record(&mut prng, 10, record_type, &keys[0]),
+                record(&mut prng, 20, record_type, &keys[1]),
+            ];
+            let outputs = [
+                record(&mut prng, 12, record_type, &keys[2]),
+                record(&mut prng, 15, record_type, &keys[3]),
+            ];
+            let policies = XfrNotePolicies::empty_policies(2, 2);
+
+            let mut xfr_note = pnk!(gen_xfr_note_with_fee(
+                &mut prng, &inputs, &outputs, &inkeys, 3
+            ));
+            assert_eq!(xfr_note.body.fee, 3);
+            pnk!(verify_xfr_note(
+                &mut prng,
+                &mut params,
+                &xfr_note,
+                &policies.to_ref()
+            ));
+
+            // the fee is encoded after the fields of the legacy encoding of the note
+            let bytes = bincode::serialize(&xfr_note).unwrap();
+            assert_eq!(bytes[bytes.len() - 8..], 3u64.to_le_bytes());
+            let decoded: XfrNote = bincode::deserialize(&bytes).unwrap();
+            assert_eq!(decoded, xfr_note);
+            let json = serde_json::to_string(&xfr_note).unwrap();
+            let decoded: XfrNote = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, xfr_note);
+            // the notes of version 0 have no fee
+            let mut note = xfr_note.clone();
+            note.version = 0;
+            msg_eq!(
+                ZeiError::InconsistentStructureError,
+                verify_xfr_note(&mut prng, &mut params, &note, &policies.to_ref())
+                    .unwrap_err()
+            );
+
+            // the multisignature signs the fee
+            let mut note = xfr_note.clone();
+            note.body.fee = 2;
+            assert!(
+                verify_xfr_note(&mut prng, &mut params, &note, &policies.to_ref())
+                    .is_err()
+            );
+
+            // the fee must make the amounts balance
+            for fee in [0, 2, 4] {
+                assert!(gen_xfr_note_with_fee(
+                    &mut prng, &inputs, &outputs, &inkeys, fee
+                )
+                .is_err());
+            }
+
+            // the range proof is bound to the fee
+            xfr_note.body.fee = 2;
+            xfr_note.multisig = pnk!(compute_transfer_multisig(&xfr_note.body, &inkeys));
+            assert!(verify_xfr_note(
+                &mut prng,
+                &mut params,
+                &xfr_note,
+                &policies.to_ref()
+            )
+            .is_err());
+        }
+
+        // a public fee is paid with a transparent output instead
+        let record_type =
+            AssetRecordType::NonConfidentialAmount_NonConfidentialAssetType;
+        let inputs = [record(&mut prng, 10, record_type, &keys[0])];
+        let outputs = [record(&mut prng, 7, record_type, &keys[2])];
+        assert!(
+            gen_xfr_note_with_fee(&mut prng, &inputs, &outputs, &inkeys[..1], 3)
+                .is_err()
+        );
+    }
+}
+
 mod multi_asset_no_tracing {
 
     use super::*;
@@ -880,13 +1020,15 @@ mod asset_tracing {
         trace_assets, trace_assets_brute_force, XfrNotePolicies, XfrNotePoliciesRef,
     };
     use crate::xfr::structs::XfrAmount::NonConfidential;
-    use crate::xfr::structs::{AssetTracerKeyPair, TracingPolicies};
+    use crate::xfr::structs::{AssetTracerKeyPair, TracingPolicies, XFR_NOTE_VERSION};
     use algebra::bls12_381::BLSScalar;
-    use algebra::groups::GroupArithmetic;
+    use algebra::groups::{Group, GroupArithmetic};
     use algebra::jubjub::JubjubScalar;
     use algebra::ristretto::{RistrettoPoint, RistrettoScalar};
     use crypto::basics::commitments::ristretto_pedersen::RistrettoPedersenGens;
     use crypto::basics::elgamal::ElGamalCiphertext;
+    use crypto::basics::multi_recipient_encryption::multi_recipient_encrypt;
+    use utils::serialization::Validate;
 
     const GOLD_ASSET: AssetType = AssetType([0; ASSET_TYPE_LENGTH]);
     const BITCOIN_ASSET: AssetType = AssetType([1; ASSET_TYPE_LENGTH]);
@@ -1763,6 +1905,155 @@ mod asset_tracing {
         assert_eq!(records_data[2].3, out_keys[1].pub_key); // third output no id tracing
     }
 
+    #[test]
+    fn test_multi_authority_asset_tracing() {
+        // Three asset tracers trace the first input and the output, the amount and asset type of
+        // each of these records are encrypted once to all of them in a shared tracer memo
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let mut params = PublicParams::default();
+        let tracer_keypairs = (0..3)
+            .map(|_| AssetTracerKeyPair::generate(&mut prng))
+            .collect_vec();
+        let mut policies = TracingPolicies::new();
+        for keypair in tracer_keypairs.iter() {
+            policies.add(gen_asset_tracing_policy(&keypair.enc_key));
+        }
+        let no_policies = TracingPolicies::new();
+        let record_type = AssetRecordType::ConfidentialAmount_ConfidentialAssetType;
+
+        let in_keys = gen_key_pair_vec(2, &mut prng);
+        let out_keys = gen_key_pair_vec(1, &mut prng);
+        let input_templates = [
+            AssetRecordTemplate::with_asset_tracing(
+                10,
+                GOLD_ASSET,
+                record_type,
+                in_keys[0].pub_key,
+                policies.clone(),
+            ),
+            AssetRecordTemplate::with_no_asset_tracing(
+                5,
+                GOLD_ASSET,
+                record_type,
+                in_keys[1].pub_key,
+            ),
+        ];
+        let output_templates = [AssetRecordTemplate::with_asset_tracing(
+            15,
+            GOLD_ASSET,
+            record_type,
+            out_keys[0].pub_key,
+            policies.clone(),
+        )];
+        let (xfr_note, _, _) = create_xfr(
+            &mut prng,
+            &input_templates,
+            &output_templates,
+            in_keys.iter().collect_vec().as_slice(),
+        );
+        let policies_ref = XfrNotePoliciesRef::new(
+            vec![&policies, &no_policies],
+            vec![None; 2],
+            vec![&policies],
+            vec![None],
+        );
+
+        let body = &xfr_note.body;
+        assert_eq!(xfr_note.version, XFR_NOTE_VERSION);
+        assert_eq!(body.shared_tracer_memos.len(), 3);
+        assert!(body.shared_tracer_memos[0].is_some());
+        assert!(body.shared_tracer_memos[1].is_none());
+        assert!(body.shared_tracer_memos[2].is_some());
+        pnk!(verify_xfr_note(
+            &mut prng,
+            &mut params,
+            &xfr_note,
+            &policies_ref
+        ));
+
+        // every tracer recovers the amount and asset type, with the shared tracer memos or from
+        // the ElGamal ciphertexts
+        let ids: Vec<u32> = vec![];
+        for keypair in tracer_keypairs.iter() {
+            let records_data = pnk!(trace_assets(body, keypair));
+            assert_eq!(
+                records_data,
+                vec![
+                    (10, GOLD_ASSET, ids.clone(), in_keys[0].pub_key),
+                    (15, GOLD_ASSET, ids.clone(), out_keys[0].pub_key)
+                ]
+            );
+            let records_data_brute_force =
+                pnk!(trace_assets_brute_force(body, keypair, &[GOLD_ASSET]));
+            assert_eq!(records_data, records_data_brute_force);
+        }
+        // the lock_info of a tracer memo no longer encrypts them
+        msg_eq!(
+            ZeiError::BogusAssetTracerMemo,
+            body.asset_tracing_memos[0][0]
+                .decrypt(&tracer_keypairs[0].dec_key)
+                .unwrap_err()
+        );
+
+        // the encodings keep the shared tracer memos
+        let bytes = bincode::serialize(&xfr_note).unwrap();
+        assert_eq!(bincode::deserialize::<XfrNote>(&bytes).unwrap(), xfr_note);
+        let json = serde_json::to_string(&xfr_note).unwrap();
+        assert_eq!(serde_json::from_str::<XfrNote>(&json).unwrap(), xfr_note);
+
+        // a shared tracer memo that is not encrypted to all the tracers is rejected
+        let other_keypair = AssetTracerKeyPair::generate(&mut prng);
+        let mut recipients = tracer_keypairs[..2]
+            .iter()
+            .map(|keypair| keypair.enc_key.record_data_enc_key.clone())
+            .collect_vec();
+        recipients.push(other_keypair.enc_key.record_data_enc_key);
+        let mut new_xfr_body = body.clone();
+        new_xfr_body.shared_tracer_memos[0] = Some(pnk!(multi_recipient_encrypt(
+            &mut prng,
+            &RistrettoPoint::get_base(),
+            &recipients,
+            b"amount and asset type"
+        )));
+        msg_eq!(
+            ZeiError::BogusAssetTracerMemo,
+            verify_xfr_body(&mut prng, &mut params, &new_xfr_body, &policies_ref)
+                .unwrap_err()
+        );
+        // or not to as many tracers
+        recipients.pop();
+        new_xfr_body.shared_tracer_memos[0] = Some(pnk!(multi_recipient_encrypt(
+            &mut prng,
+            &RistrettoPoint::get_base(),
+            &recipients,
+            b"amount and asset type"
+        )));
+        msg_eq!(
+            ZeiError::InconsistentStructureError,
+            verify_xfr_body(&mut prng, &mut params, &new_xfr_body, &policies_ref)
+                .unwrap_err()
+        );
+
+        // the shared tracer memos are signed
+        let mut note = xfr_note.clone();
+        note.body.shared_tracer_memos.swap(0, 2);
+        pnk!(verify_xfr_body(
+            &mut prng,
+            &mut params,
+            &note.body,
+            &policies_ref
+        ));
+        assert!(verify_xfr_note(&mut prng, &mut params, &note, &policies_ref).is_err());
+
+        // and only the notes of version 2 have them
+        let mut note = xfr_note;
+        note.version = 1;
+        msg_eq!(
+            ZeiError::InconsistentStructureError,
+            note.validate().unwrap_err()
+        );
+    }
+
     fn do_integer_overflow(asset_record_type: AssetRecordType) {
         let mut prng: ChaChaRng;
         prng = ChaChaRng::from_seed([0u8; 32]);
@@ -1873,3 +2164,299 @@ mod asset_tracing {
         assert_eq!(v2, v3);
     }
 }
+
+mod bounded_reader {
+    use super::*;
+    use crate::xfr::lib::verify_xfr_note_from_bounded_reader;
+    use crate::xfr::sig::XfrMultiSig;
+
+    #[test]
+    fn test_verify_xfr_note_from_bounded_reader() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let mut params = PublicParams::default();
+        let asset_type = AssetType::from_identical_byte(0u8);
+        let record_type = AssetRecordType::ConfidentialAmount_ConfidentialAssetType;
+        let keys = gen_key_pair_vec(2, &mut prng);
+        let input_templates = [AssetRecordTemplate::with_no_asset_tracing(
+            10,
+            asset_type,
+            record_type,
+            keys[0].get_pk(),
+        )];
+        let output_templates = [AssetRecordTemplate::with_no_asset_tracing(
+            10,
+            asset_type,
+            record_type,
+            keys[1].get_pk(),
+        )];
+        let (xfr_note, _, _) =
+            create_xfr(&mut prng, &input_templates, &output_templates, &[&keys[0]]);
+        let policies = XfrNotePolicies::empty_policies(1, 1);
+        let note_bytes = bincode::serialize(&xfr_note).unwrap();
+
+        let decoded = pnk!(verify_xfr_note_from_bounded_reader(
+            &mut prng,
+            &mut params,
+            &mut &note_bytes[..],
+            note_bytes.len() as u64,
+            &policies.to_ref()
+        ));
+        assert_eq!(decoded, xfr_note);
+
+        // the size bound is enforced
+        assert!(verify_xfr_note_from_bounded_reader(
+            &mut prng,
+            &mut params,
+            &mut &note_bytes[..],
+            note_bytes.len() as u64 - 1,
+            &policies.to_ref()
+        )
+        .is_err());
+
+        // a valid encoding of an invalid note
+        let mut bad_note = xfr_note;
+        bad_note.multisig = XfrMultiSig::default();
+        let bad_bytes = bincode::serialize(&bad_note).unwrap();
+        assert!(verify_xfr_note_from_bounded_reader(
+            &mut prng,
+            &mut params,
+            &mut &bad_bytes[..],
+            bad_bytes.len() as u64,
+            &policies.to_ref()
+        )
+        .is_err());
+    }
+}
+
+mod validation {
+    use super::*;
+    use crate::xfr::asset_mixer::AssetMixProof;
+    use crate::xfr::lib::transfer_multisig_message;
+    use crate::xfr::sig::XfrMultiSig;
+    use crate::xfr::structs::{
+        AssetTracingProofs, BlindAssetRecord, OwnerMemo, XFR_NOTE_VERSION,
+    };
+    use algebra::ristretto::CompressedRistretto;
+    use bulletproofs::RangeProof;
+    use crypto::chaum_pedersen::ChaumPedersenProofX;
+    use curve25519_dalek::ristretto::CompressedRistretto as CR;
+    use utils::serialization::Validate;
+
+    #[test]
+    fn test_malformed_notes() {
+        let mut prng = ChaChaRng::from_seed([0u8; 32]);
+        let mut params = PublicParams::default();
+        let asset_type = AssetType::from_identical_byte(0u8);
+        let record_type = AssetRecordType::ConfidentialAmount_ConfidentialAssetType;
+        let keys = gen_key_pair_vec(2, &mut prng);
+        let template = |key: &XfrKeyPair| {
+            AssetRecordTemplate::with_no_asset_tracing(
+                10,
+                asset_type,
+                record_type,
+                key.get_pk(),
+            )
+        };
+        let (xfr_note, _, _) = create_xfr(
+            &mut prng,
+            &[template(&keys[0])],
+            &[template(&keys[1])],
+            &[&keys[0]],
+        );
+        let policies = XfrNotePolicies::empty_policies(1, 1);
+        pnk!(xfr_note.validate());
+        pnk!(verify_xfr_note(
+            &mut prng,
+            &mut params,
+            &xfr_note,
+            &policies.to_ref()
+        ));
+
+        // malformed notes are rejected with an error before any proof is verified
+        let mut check_malformed = |malform: fn(&mut XfrNote), error: ZeiError| {
+            let mut note = xfr_note.clone();
+            malform(&mut note);
+            msg_eq!(error.clone(), note.validate().unwrap_err());
+            msg_eq!(
+                error.clone(),
+                verify_xfr_note(&mut prng, &mut params, &note, &policies.to_ref())
+                    .unwrap_err()
+            );
+            msg_eq!(
+                error,
+                verify_xfr_body(&mut prng, &mut params, &note.body, &policies.to_ref())
+                    .unwrap_err()
+            );
+        };
+        let inconsistent = ZeiError::InconsistentStructureError;
+        check_malformed(
+            |note: &mut XfrNote| note.body.inputs.clear(),
+            inconsistent.clone(),
+        );
+        check_malformed(
+            |note: &mut XfrNote| note.body.asset_tracing_memos.clear(),
+            inconsistent.clone(),
+        );
+        check_malformed(
+            |note: &mut XfrNote| note.body.owners_memos.push(None),
+            inconsistent.clone(),
+        );
+        check_malformed(
+            |note: &mut XfrNote| {
+                note.body
+                    .proofs
+                    .asset_tracing_proof
+                    .inputs_identity_proofs
+                    .push(vec![])
+            },
+            inconsistent,
+        );
+        // not a canonical encoding of a ristretto point
+        check_malformed(
+            |note: &mut XfrNote| {
+                let invalid_point = CompressedRistretto(CR([0xFF; 32]));
+                note.body.outputs[0].asset_type =
+                    XfrAssetType::Confidential(invalid_point)
+            },
+            ZeiError::DecompressElementError,
+        );
+
+        // one signature for each input
+        let mut note = xfr_note;
+        let signature = note.multisig.signatures[0].clone();
+        note.multisig.signatures.push(signature);
+        pnk!(note.body.validate());
+        msg_eq!(
+            ZeiError::InconsistentStructureError,
+            note.validate().unwrap_err()
+        );
+    }
+
+    // the transfer notes as defined before the version and the fee were recorded
+    #[derive(Serialize, Deserialize)]
+    struct LegacyXfrRangeProof {
+        #[serde(with = "utils::serialization::zei_obj_serde")]
+        range_proof: RangeProof,
+        xfr_diff_commitment_low: CompressedRistretto,
+        xfr_diff_commitment_high: CompressedRistretto,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum LegacyAssetTypeAndAmountProof {
+        AssetMix(AssetMixProof),
+        ConfAmount(LegacyXfrRangeProof),
+        ConfAsset(Box<ChaumPedersenProofX>),
+        ConfAll(Box<(LegacyXfrRangeProof, ChaumPedersenProofX)>),
+        NoProof,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct LegacyXfrProofs {
+        asset_type_and_amount_proof: LegacyAssetTypeAndAmountProof,
+        asset_tracing_proof: AssetTracingProofs,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct LegacyXfrBody {
+        inputs: Vec<BlindAssetRecord>,
+        outputs: Vec<BlindAssetRecord>,
+        proofs: LegacyXfrProofs,
+        asset_tracing_memos: Vec<Vec<TracerMemo>>,
+        owners_memos: Vec<Option<OwnerMemo>>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct LegacyXfrNote {
+        body: LegacyXfrBody,
+        multisig: XfrMultiSig,
+    }
+
+    #[test]
+    fn test_note_versions() {
+        let mut prng = ChaChaRng::from_seed([1u8; 32]);
+        let mut params = PublicParams::default();
+        let asset_type = AssetType::from_identical_byte(0u8);
+        let record_type = AssetRecordType::ConfidentialAmount_ConfidentialAssetType;
+        let keys = gen_key_pair_vec(2, &mut prng);
+        let template = |key: &XfrKeyPair| {
+            AssetRecordTemplate::with_no_asset_tracing(
+                10,
+                asset_type,
+                record_type,
+                key.get_pk(),
+            )
+        };
+        let (xfr_note, _, _) = create_xfr(
+            &mut prng,
+            &[template(&keys[0])],
+            &[template(&keys[1])],
+            &[&keys[0]],
+        );
+        let policies = XfrNotePolicies::empty_policies(1, 1);
+        assert_eq!(xfr_note.version, XFR_NOTE_VERSION);
+
+        // the version is signed
+        let mut note = xfr_note.clone();
+        note.version = 0;
+        assert!(
+            verify_xfr_note(&mut prng, &mut params, &note, &policies.to_ref()).is_err()
+        );
+        note.version = XFR_NOTE_VERSION + 1;
+        msg_eq!(
+            ZeiError::UnsupportedVersionError,
+            verify_xfr_note(&mut prng, &mut params, &note, &policies.to_ref())
+                .unwrap_err()
+        );
+
+        // a confidential note of the legacy structures, signed before the version was
+        // recorded, that is with the multisignature over the MessagePack encoding of its body
+        let body_bytes = bincode::serialize(&xfr_note.body).unwrap();
+        let legacy_body: LegacyXfrBody = bincode::deserialize(&body_bytes).unwrap();
+        assert_eq!(bincode::serialize(&legacy_body).unwrap(), body_bytes);
+        let mut message = vec![];
+        legacy_body
+            .serialize(&mut rmp_serde::Serializer::new(&mut message))
+            .unwrap();
+        assert_eq!(pnk!(transfer_multisig_message(&xfr_note.body, 0)), message);
+        let legacy_note = LegacyXfrNote {
+            body: legacy_body,
+            multisig: XfrMultiSig::sign(&[&keys[0]], &message),
+        };
+
+        // its encodings decode with version 0, and it is verified with the rules of version 0
+        let legacy_bytes = bincode::serialize(&legacy_note).unwrap();
+        let legacy_json = serde_json::to_string(&legacy_note).unwrap();
+        let decoded: XfrNote = bincode::deserialize(&legacy_bytes).unwrap();
+        assert_eq!(decoded.version, 0);
+        assert_eq!(decoded.body, xfr_note.body);
+        assert_eq!(decoded.multisig, legacy_note.multisig);
+        assert_eq!(
+            serde_json::from_str::<XfrNote>(&legacy_json).unwrap(),
+            decoded
+        );
+        pnk!(verify_xfr_note(
+            &mut prng,
+            &mut params,
+            &decoded,
+            &policies.to_ref()
+        ));
+        pnk!(batch_verify_xfr_notes(
+            &mut prng,
+            &mut params,
+            &[&decoded, &xfr_note],
+            &[&policies.to_ref(), &policies.to_ref()]
+        ));
+        let mut upgraded = decoded;
+        upgraded.version = XFR_NOTE_VERSION;
+        assert!(
+            verify_xfr_note(&mut prng, &mut params, &upgraded, &policies.to_ref())
+                .is_err()
+        );
+
+        // the current encoding starts with the version
+        let bytes = bincode::serialize(&xfr_note).unwrap();
+        assert_ne!(bytes, legacy_bytes);
+        let decoded: XfrNote = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, xfr_note);
+    }
+}